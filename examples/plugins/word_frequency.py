@@ -0,0 +1,44 @@
+#!/usr/bin/env python3
+"""Example phantom plugin.
+
+Actions:
+  word_frequency  show the five most common words of the buffer
+  sort_lines      sort the buffer lines (one undo step)
+
+It also reports the word count whenever a file is saved. See
+docs/plugin-protocol.md for the message format.
+"""
+
+import json
+import re
+import sys
+from collections import Counter
+
+
+def send(message):
+    print(json.dumps(message), flush=True)
+
+
+def words(lines):
+    return re.findall(r"[\w']+", "\n".join(lines).lower())
+
+
+for line in sys.stdin:
+    message = json.loads(line)
+    kind = message["type"]
+    if kind == "action":
+        if message["name"] == "word_frequency":
+            common = Counter(words(message["lines"])).most_common(5)
+            text = ", ".join(f"{word}: {count}" for word, count in common) or "no words"
+            send({"type": "status", "text": text})
+        elif message["name"] == "sort_lines":
+            lines = message["lines"]
+            send({"type": "replace_lines", "start": 0, "end": len(lines), "lines": sorted(lines)})
+        send({"type": "done"})
+    elif kind == "buffer_saved":
+        try:
+            with open(message["path"], encoding="utf-8") as file:
+                count = len(words(file.read().splitlines()))
+            send({"type": "status", "text": f"{count} words saved"})
+        except OSError as error:
+            send({"type": "status", "level": "warn", "text": str(error)})
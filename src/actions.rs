@@ -0,0 +1,172 @@
+use crossterm::event::KeyCode;
+
+use crate::editor::{Editor, Mode};
+use crate::ui::{Picker, PickerKind};
+
+pub(crate) const ACTIONS: &[&str] = &[
+    "append",
+    "block_append",
+    "block_insert",
+    "buffer_stats",
+    "change_selection",
+    "char_info",
+    "clear_search_highlight",
+    "close_all_folds",
+    "close_tab",
+    "close_terminal",
+    "complete_path",
+    "copy_selection",
+    "cut_selection",
+    "delete_line",
+    "delete_selection",
+    "enter_command_mode",
+    "enter_directory_nav_mode",
+    "enter_insert_mode",
+    "enter_search_mode",
+    "enter_visual_block_mode",
+    "enter_visual_mode",
+    "execute_command",
+    "execute_search",
+    "exit_command_mode",
+    "exit_file_select_mode",
+    "exit_insert_mode",
+    "exit_search_mode",
+    "exit_terminal_mode",
+    "exit_visual_mode",
+    "expand_selection",
+    "focus_debug",
+    "focus_minimap",
+    "goto_line_end",
+    "goto_line_start",
+    "insert_digraph",
+    "insert_expression",
+    "insert_unicode",
+    "last_change",
+    "move_down",
+    "move_left",
+    "move_right",
+    "move_up",
+    "new_tab",
+    "newer_change",
+    "next_bookmark",
+    "next_search_result",
+    "next_tab",
+    "older_change",
+    "open_all_folds",
+    "open_line_above",
+    "open_line_below",
+    "page_down",
+    "page_up",
+    "paste_after",
+    "paste_clipboard",
+    "paste_over_selection",
+    "prev_bookmark",
+    "previous_search_result",
+    "previous_tab",
+    "rebase_cycle",
+    "redo",
+    "refresh_sidebar",
+    "rename_word",
+    "reopen_closed_tab",
+    "reveal_in_sidebar",
+    "save_file",
+    "scroll_cursor_bottom",
+    "scroll_cursor_center",
+    "scroll_cursor_top",
+    "scroll_debug_down",
+    "scroll_debug_up",
+    "scroll_half_page_down",
+    "scroll_half_page_up",
+    "scroll_line_down",
+    "scroll_line_up",
+    "search_all_tabs",
+    "select_all",
+    "select_file",
+    "show_commit",
+    "shrink_selection",
+    "surround_change",
+    "surround_delete",
+    "surround_selection",
+    "switch_to_tab_1",
+    "switch_to_tab_2",
+    "switch_to_tab_3",
+    "switch_to_tab_4",
+    "switch_to_tab_5",
+    "switch_to_tab_6",
+    "switch_to_tab_7",
+    "switch_to_tab_8",
+    "switch_to_tab_9",
+    "toggle_alternate_tab",
+    "toggle_blame",
+    "toggle_bookmark",
+    "toggle_debug_menu",
+    "toggle_fold",
+    "toggle_minimap",
+    "toggle_outline",
+    "toggle_sidebar",
+    "toggle_terminal",
+    "undo",
+    "yank_line",
+    "yank_selection",
+    "yank_to_new_tab",
+];
+
+impl Editor {
+    pub(crate) fn fallback_action(code: KeyCode) -> Option<&'static str> {
+        match code {
+            KeyCode::Left => Some("move_left"),
+            KeyCode::Down => Some("move_down"),
+            KeyCode::Up => Some("move_up"),
+            KeyCode::Right => Some("move_right"),
+            KeyCode::Home => Some("goto_line_start"),
+            KeyCode::End => Some("goto_line_end"),
+            KeyCode::PageUp => Some("page_up"),
+            KeyCode::PageDown => Some("page_down"),
+            KeyCode::Tab => Some("next_tab"),
+            KeyCode::BackTab => Some("previous_tab"),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn is_action(&self, name: &str) -> bool {
+        ACTIONS.contains(&name) || self.settings.plugins.iter().any(|plugin| plugin.actions.iter().any(|action| action == name))
+    }
+
+    pub fn unknown_keybindings(&self) -> Vec<String> {
+        let mut unknown = Vec::new();
+        for (mode, bindings) in self.keybindings.modes() {
+            let mut keys: Vec<&String> = bindings.keys().collect();
+            keys.sort();
+            for key in keys {
+                if !self.is_action(&bindings[key]) {
+                    unknown.push(format!("config.toml: {} \"{}\" is bound to unknown action \"{}\"", mode, key, bindings[key]));
+                }
+            }
+        }
+        unknown
+    }
+
+    pub(crate) fn show_actions(&mut self) {
+        let mut names: Vec<String> = ACTIONS.iter().map(|name| name.to_string()).collect();
+        for plugin in &self.settings.plugins {
+            names.extend(plugin.actions.iter().filter(|action| !ACTIONS.contains(&action.as_str())).cloned());
+        }
+        names.sort();
+        names.dedup();
+        let width = names.iter().map(String::len).max().unwrap_or(0);
+        let items = names.iter()
+            .map(|name| {
+                let mut bindings = Vec::new();
+                for (mode, map) in self.keybindings.modes() {
+                    let mut keys: Vec<&String> = map.iter().filter(|(_, action)| *action == name).map(|(key, _)| key).collect();
+                    keys.sort();
+                    bindings.extend(keys.into_iter().map(|key| format!("{} {}", mode.trim_end_matches("_mode"), key)));
+                }
+                let bindings = if bindings.is_empty() { "unbound".to_string() } else { bindings.join(", ") };
+                format!("{:width$}  {}", name, bindings, width = width)
+            })
+            .collect();
+        self.picker = Some(Picker::new(PickerKind::Actions, "Actions", items));
+        self.mode = Mode::Picker;
+    }
+}
@@ -0,0 +1,87 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::diff::unified_diff;
+use crate::editor::{Editor, MessageLevel, Mode};
+use crate::ui::{Prompt, PromptAction};
+
+pub(crate) type DryRunWrites = Vec<(PathBuf, Vec<String>)>;
+
+pub struct Batch {
+    pub commands: Vec<String>,
+    pub dry_run: bool,
+}
+
+impl Batch {
+    pub fn load_script(path: &Path) -> io::Result<Vec<String>> {
+        Ok(fs::read_to_string(path)?
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('"'))
+            .map(String::from)
+            .collect())
+    }
+
+    pub fn run_file(&self, path: &Path) -> Result<String, String> {
+        let mut editor = Editor::headless();
+        if self.dry_run {
+            editor.dry_run_writes = Some(Vec::new());
+        }
+        editor.open_file(path).map_err(|e| e.to_string())?;
+        for command in &self.commands {
+            if editor.batch_command(command).map_err(|e| format!("{}: {}", command, e))? {
+                break;
+            }
+        }
+
+        let mut output = String::new();
+        for (written, lines) in editor.dry_run_writes.take().unwrap_or_default() {
+            let original: Vec<String> = fs::read_to_string(&written)
+                .map(|content| content.strip_prefix('\u{feff}').unwrap_or(&content).lines().map(String::from).collect())
+                .unwrap_or_default();
+            let name = written.to_string_lossy();
+            output.push_str(&unified_diff(&name, &name, &original, &lines));
+        }
+        Ok(output)
+    }
+}
+
+impl Editor {
+    pub(crate) const INTERACTIVE_COMMANDS: [&'static str; 7] = ["messages", "mes", "oldfiles", "ol", "bookmarks", "todos", "todos!"];
+
+    pub fn batch_command(&mut self, command: &str) -> Result<bool, String> {
+        let command = command.trim();
+        let command = command.strip_prefix(':').unwrap_or(command);
+        if command.split_whitespace().next().is_some_and(|name| Self::INTERACTIVE_COMMANDS.contains(&name)) {
+            return Err("Interactive commands are not available in batch mode".to_string());
+        }
+
+        self.status_message = None;
+        let quit = self.execute_command_line(command).map_err(|e| e.to_string())?;
+        self.wait_for_saves().map_err(|e| format!("Save failed: {}", e))?;
+        if let Some(message) = self.status_message.take().filter(|message| message.level == MessageLevel::Error) {
+            return Err(message.text);
+        }
+        if let Some(prompt) = self.prompt.take() {
+            return Err(match prompt {
+                Prompt::Confirm(prompt) if matches!(prompt.action, PromptAction::Quit | PromptAction::CloseOtherTabs) => {
+                    "No write since last change (add ! to override)".to_string()
+                }
+                Prompt::Confirm(prompt) if matches!(prompt.action, PromptAction::CreateDirectory { .. }) => {
+                    format!("{} (use :w! to create it)", prompt.message.trim_end_matches(" Create it?"))
+                }
+                _ => "Command needs interactive input".to_string(),
+            });
+        }
+        if self.substitute_confirm.take().is_some() {
+            return Err("Command needs interactive input".to_string());
+        }
+        if self.picker.is_some() || self.mode != Mode::Normal {
+            self.picker = None;
+            self.mode = Mode::Normal;
+            return Err("Interactive commands are not available in batch mode".to_string());
+        }
+        Ok(quit)
+    }
+}
@@ -0,0 +1,279 @@
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::buffer::Tab;
+use crate::diff::line_mapping;
+use crate::editor::Editor;
+use crate::tasks::Progress;
+
+pub(crate) struct BlameCommit {
+    pub(crate) hash: String,
+    pub(crate) author: String,
+    pub(crate) time: u64,
+    pub(crate) summary: String,
+}
+
+pub(crate) struct BlameResult {
+    pub(crate) commits: Vec<BlameCommit>,
+    pub(crate) lines: Vec<usize>,
+}
+
+pub(crate) struct Blame {
+    pub(crate) file: Option<String>,
+    pub(crate) source: Vec<String>,
+    pub(crate) result: Option<BlameResult>,
+    pub(crate) content: Vec<String>,
+    pub(crate) mapping: Vec<Option<usize>>,
+    pub(crate) receiver: Option<Receiver<Result<BlameResult, String>>>,
+}
+
+pub(crate) fn parse_porcelain(output: &str) -> Result<BlameResult, String> {
+    let mut commits: Vec<BlameCommit> = Vec::new();
+    let mut known: HashMap<&str, usize> = HashMap::new();
+    let mut lines = Vec::new();
+    let mut current = None;
+    for line in output.lines() {
+        if line.starts_with('\t') {
+            lines.push(current.ok_or("line content before a commit header")?);
+            continue;
+        }
+        let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+        if matches!(key.len(), 40 | 64) && key.bytes().all(|b| b.is_ascii_hexdigit()) {
+            let next = commits.len();
+            let index = *known.entry(key).or_insert(next);
+            if index == next {
+                commits.push(BlameCommit { hash: key.to_string(), author: String::new(), time: 0, summary: String::new() });
+            }
+            current = Some(index);
+            continue;
+        }
+        let Some(commit) = current.map(|index| &mut commits[index]) else {
+            continue;
+        };
+        match key {
+            "author" => commit.author = value.to_string(),
+            "author-time" => commit.time = value.parse().unwrap_or(0),
+            "summary" => commit.summary = value.to_string(),
+            _ => {}
+        }
+    }
+    Ok(BlameResult { commits, lines })
+}
+
+pub(crate) fn relative_time(time: u64, now: u64) -> String {
+    let elapsed = now.saturating_sub(time);
+    let (count, unit) = match elapsed {
+        0..=59 => return "just now".to_string(),
+        60..=3599 => (elapsed / 60, "minute"),
+        3600..=86_399 => (elapsed / 3600, "hour"),
+        86_400..=2_591_999 => (elapsed / 86_400, "day"),
+        2_592_000..=31_535_999 => (elapsed / 2_592_000, "month"),
+        _ => (elapsed / 31_536_000, "year"),
+    };
+    format!("{} {}{} ago", count, unit, if count == 1 { "" } else { "s" })
+}
+
+fn run_blame(path: &Path, content: &str, line: Option<usize>) -> Result<BlameResult, String> {
+    let mut command = Command::new("git");
+    command.current_dir(path.parent().unwrap_or(Path::new(".")))
+        .args(["blame", "--porcelain", "--contents", "-"]);
+    if let Some(line) = line {
+        command.arg(format!("-L{},{}", line + 1, line + 1));
+    }
+    let mut child = command.arg("--").arg(path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(content.as_bytes()).map_err(|e| e.to_string())?;
+    }
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    parse_porcelain(&String::from_utf8_lossy(&output.stdout))
+}
+
+fn buffer_text(tab: &Tab) -> String {
+    tab.content.iter().map(|line| format!("{}\n", line)).collect()
+}
+
+impl BlameCommit {
+    pub(crate) fn committed(&self) -> bool {
+        !self.hash.bytes().all(|b| b == b'0')
+    }
+
+    pub(crate) fn short_hash(&self) -> &str {
+        &self.hash[..7]
+    }
+}
+
+impl Blame {
+    pub(crate) fn request(tab: &Tab, progress: Option<Progress>) -> Self {
+        let receiver = tab.current_file.as_ref().zip(progress).map(|(file, progress)| {
+            let (sender, receiver) = mpsc::channel();
+            let path = Editor::absolute_path(Path::new(file));
+            let content = buffer_text(tab);
+            thread::spawn(move || {
+                let result = run_blame(&path, &content, None);
+                drop(progress);
+                let _ = sender.send(result);
+            });
+            receiver
+        });
+        Blame {
+            file: tab.current_file.clone(),
+            source: tab.content.clone(),
+            result: None,
+            content: tab.content.clone(),
+            mapping: (0..tab.content.len()).map(Some).collect(),
+            receiver,
+        }
+    }
+
+    pub(crate) fn loaded(&self) -> bool {
+        self.result.is_some()
+    }
+
+    pub(crate) fn commit_at(&self, line: usize) -> Option<&BlameCommit> {
+        let result = self.result.as_ref()?;
+        let source = self.mapping.get(line).copied().flatten()?;
+        Some(&result.commits[result.lines[source]]).filter(|commit| commit.committed())
+    }
+
+    pub(crate) fn label(&self, line: usize, now: u64) -> String {
+        let text = match self.commit_at(line) {
+            Some(commit) => {
+                let author: String = commit.author.chars().take(12).collect();
+                format!("{} {:<12} {}", commit.short_hash(), author, relative_time(commit.time, now))
+            }
+            None => "not committed".to_string(),
+        };
+        format!("{:<width$}", text, width = Editor::BLAME_WIDTH as usize)
+    }
+}
+
+impl Editor {
+    pub(crate) const BLAME_WIDTH: u16 = 35;
+
+    pub(crate) fn unix_now() -> u64 {
+        SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs())
+    }
+
+    pub(crate) fn toggle_blame(&mut self) {
+        if self.blame.take().is_some() {
+            return;
+        }
+        if self.tabs[self.active_tab].current_file.is_none() {
+            self.info("Blame needs a file on disk");
+        }
+        self.blame = Some(self.request_blame());
+    }
+
+    fn request_blame(&mut self) -> Blame {
+        let label = self.tabs[self.active_tab].current_file.as_ref().map(|file| format!("Blaming {}", self.display_path(file)));
+        let progress = label.map(|label| self.start_task(label, false));
+        Blame::request(&self.tabs[self.active_tab], progress)
+    }
+
+    pub fn refresh_blame(&mut self) -> bool {
+        let Some(blame) = &self.blame else {
+            return false;
+        };
+        if blame.file != self.tabs[self.active_tab].current_file {
+            self.blame = Some(self.request_blame());
+            return true;
+        }
+        let Some(blame) = &mut self.blame else {
+            return false;
+        };
+        let tab = &self.tabs[self.active_tab];
+        let mut changed = false;
+        if let Some(receiver) = &blame.receiver {
+            match receiver.try_recv() {
+                Ok(result) => {
+                    blame.result = result.ok().filter(|result| result.lines.len() == blame.source.len());
+                    blame.receiver = None;
+                    changed = true;
+                }
+                Err(TryRecvError::Disconnected) => blame.receiver = None,
+                Err(TryRecvError::Empty) => {}
+            }
+        }
+        if blame.content != tab.content {
+            blame.mapping = line_mapping(&blame.source, &tab.content);
+            blame.content = tab.content.clone();
+            changed = true;
+        }
+        changed
+    }
+
+    pub fn blame_loading(&self) -> bool {
+        self.blame.as_ref().is_some_and(|blame| blame.receiver.is_some())
+    }
+
+    pub(crate) fn blame_status(&self) -> Option<String> {
+        let blame = self.blame.as_ref().filter(|blame| blame.loaded())?;
+        let line = self.tabs[self.active_tab].cursor_position.1;
+        Some(match blame.commit_at(line) {
+            Some(commit) => format!(
+                "{} {}, {} - {}",
+                commit.short_hash(), commit.author, relative_time(commit.time, Self::unix_now()), commit.summary
+            ),
+            None => "not committed".to_string(),
+        })
+    }
+
+    pub(crate) fn show_commit(&mut self) {
+        let tab = &self.tabs[self.active_tab];
+        let Some(file) = &tab.current_file else {
+            self.info("No blame information for this buffer");
+            return;
+        };
+        let path = Self::absolute_path(Path::new(file));
+        let line = tab.cursor_position.1;
+        let hash = match self.blame.as_ref().filter(|blame| blame.loaded()) {
+            Some(blame) => blame.commit_at(line).map(|commit| commit.hash.clone()),
+            None => match run_blame(&path, &buffer_text(tab), Some(line)) {
+                Ok(result) => result.commits.into_iter().find(BlameCommit::committed).map(|commit| commit.hash),
+                Err(_) => {
+                    self.info("No blame information for this buffer");
+                    return;
+                }
+            },
+        };
+        let Some(hash) = hash else {
+            self.info("Line not committed");
+            return;
+        };
+        let output = Command::new("git")
+            .current_dir(path.parent().unwrap_or(Path::new(".")))
+            .args(["show", &hash])
+            .output();
+        let output = match output {
+            Ok(output) if output.status.success() => output,
+            Ok(output) => {
+                self.error(format!("git show failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+                return;
+            }
+            Err(e) => {
+                self.error(format!("Failed to run git: {}", e));
+                return;
+            }
+        };
+        let mut tab = Tab::from_lines(String::from_utf8_lossy(&output.stdout).lines().map(String::from).collect());
+        tab.syntax = "Diff".to_string();
+        tab.title = Some(format!("git show {}", &hash[..7]));
+        tab.read_only = true;
+        self.tabs.push(tab);
+        self.set_active_tab(self.tabs.len() - 1);
+        self.update_current_tab_info();
+    }
+}
@@ -0,0 +1,745 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use syntect::parsing::SyntaxSet;
+use unicode_width::UnicodeWidthChar;
+
+use crate::config::{FoldMethod, PositionStore};
+use crate::editor::Editor;
+use crate::filetype::detect_syntax;
+use crate::indent::IndentStyle;
+use crate::lock::FileLock;
+use crate::options::OptionValue;
+use crate::save::PendingSave;
+use crate::search::SearchPattern;
+use crate::undo::snapshot_size;
+
+pub(crate) const CHANGE_LIST_LIMIT: usize = 100;
+
+pub struct TextStats {
+    pub lines: usize,
+    pub words: usize,
+    pub chars: usize,
+    pub bytes: usize,
+}
+
+impl TextStats {
+    pub fn from_text(text: &str) -> Self {
+        TextStats {
+            lines: text.lines().count(),
+            words: text.split_whitespace().count(),
+            chars: text.chars().count(),
+            bytes: text.len(),
+        }
+    }
+}
+
+impl fmt::Display for TextStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} chars, {} words, {} lines, {} bytes", self.chars, self.words, self.lines, self.bytes)
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct EditOperation {
+    pub(crate) content: Vec<String>,
+    pub(crate) cursor_position: (usize, usize),
+    pub(crate) scroll_offset: usize,
+    pub(crate) horizontal_scroll: usize,
+    pub(crate) bookmarks: Vec<usize>,
+    pub(crate) folds: Vec<(usize, usize)>,
+    pub(crate) size: usize,
+}
+
+pub struct Tab {
+    pub(crate) id: usize,
+    pub(crate) content: Vec<String>,
+    pub(crate) cursor_position: (usize, usize),
+    pub(crate) scroll_offset: usize,
+    pub(crate) horizontal_scroll: usize,
+    pub(crate) current_file: Option<String>,
+    pub(crate) syntax: String,
+    pub(crate) undo_stack: VecDeque<EditOperation>,
+    pub(crate) redo_stack: VecDeque<EditOperation>,
+    pub(crate) modified: bool,
+    pub(crate) modified_checked: bool,
+    pub(crate) saved_hash: u64,
+    pub(crate) bookmarks: Vec<usize>,
+    pub(crate) folds: Vec<(usize, usize)>,
+    pub(crate) changes: Vec<(usize, usize)>,
+    pub(crate) change_index: usize,
+    pub(crate) title: Option<String>,
+    pub(crate) read_only: bool,
+    pub(crate) options: BTreeMap<&'static str, OptionValue>,
+    pub(crate) indent_style: Option<IndentStyle>,
+    pub(crate) crlf: bool,
+    pub(crate) bom: bool,
+    pub(crate) eol: bool,
+    pub(crate) save: Option<PendingSave>,
+    pub(crate) lock: Option<Arc<FileLock>>,
+    pub(crate) flashed: Option<Instant>,
+    pub(crate) undo_trimmed: bool,
+    pub(crate) private: bool,
+}
+
+static NEXT_TAB_ID: AtomicUsize = AtomicUsize::new(1);
+
+fn next_tab_id() -> usize {
+    NEXT_TAB_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+impl Tab {
+    pub fn new() -> Self {
+        let mut tab = Tab {
+            id: next_tab_id(),
+            content: vec![String::new()],
+            cursor_position: (0, 0),
+            scroll_offset: 0,
+            horizontal_scroll: 0,
+            current_file: None,
+            syntax: "Plain Text".to_string(),
+            undo_stack: VecDeque::new(),
+            redo_stack: VecDeque::new(),
+            modified: false,
+            modified_checked: true,
+            saved_hash: 0,
+            bookmarks: Vec::new(),
+            folds: Vec::new(),
+            changes: Vec::new(),
+            change_index: 0,
+            title: None,
+            read_only: false,
+            options: BTreeMap::new(),
+            indent_style: None,
+            crlf: false,
+            bom: false,
+            eol: true,
+            save: None,
+            lock: None,
+            flashed: None,
+            undo_trimmed: false,
+            private: false,
+        };
+        tab.saved_hash = tab.content_hash(&tab.content);
+        tab
+    }
+
+    pub(crate) fn from_file(path: &Path, ps: &SyntaxSet, positions: Option<&PositionStore>) -> io::Result<Self> {
+        let content = fs::read_to_string(path)?;
+        let (bom, content) = match content.strip_prefix('\u{feff}') {
+            Some(rest) => (true, rest),
+            None => (false, content.as_str()),
+        };
+        let lines = if content.is_empty() {
+            vec![String::new()]
+        } else {
+            content.lines().map(String::from).collect()
+        };
+
+        let syntax = detect_syntax(ps, path, &lines[0]).map_or_else(|| "Plain Text".to_string(), |syntax| syntax.name.clone());
+
+        let mut tab = Tab {
+            id: next_tab_id(),
+            content: lines,
+            cursor_position: (0, 0),
+            scroll_offset: 0,
+            horizontal_scroll: 0,
+            current_file: Some(path.to_string_lossy().into_owned()),
+            syntax,
+            undo_stack: VecDeque::new(),
+            redo_stack: VecDeque::new(),
+            modified: false,
+            modified_checked: true,
+            saved_hash: 0,
+            bookmarks: Vec::new(),
+            folds: Vec::new(),
+            changes: Vec::new(),
+            change_index: 0,
+            title: None,
+            read_only: false,
+            options: BTreeMap::new(),
+            indent_style: None,
+            crlf: content.contains("\r\n"),
+            bom,
+            eol: content.ends_with('\n'),
+            save: None,
+            lock: None,
+            flashed: None,
+            undo_trimmed: false,
+            private: false,
+        };
+        tab.saved_hash = tab.content_hash(&tab.content);
+
+        if let Some(stored) = positions.and_then(|store| store.get(&Editor::canonical_path(path))) {
+            let line = stored.cursor_position.1.min(tab.content.len() - 1);
+            let line_text = &tab.content[line];
+            let mut column = stored.cursor_position.0.min(line_text.len());
+            while !line_text.is_char_boundary(column) {
+                column -= 1;
+            }
+            tab.cursor_position = (column, line);
+            tab.scroll_offset = stored.scroll_offset.min(line);
+            tab.changes = stored.changes.iter()
+                .copied()
+                .filter(|&(_, y)| y < tab.content.len())
+                .collect();
+            tab.change_index = tab.changes.len();
+        }
+        Ok(tab)
+    }
+
+    pub(crate) fn adjust_horizontal_scroll(&mut self, editor_width: usize, sidescrolloff: usize, tab_width: usize) {
+        let margin = sidescrolloff.min(editor_width.saturating_sub(1) / 2);
+        let (x, y) = self.cursor_position;
+        let column = self.visual_column(y, x, tab_width);
+        let cursor_width = self.content.get(y)
+            .and_then(|line| line.get(x..))
+            .and_then(|rest| rest.chars().next())
+            .map_or(1, |c| char_width_at(c, column, tab_width));
+        if column < self.horizontal_scroll + margin {
+            self.horizontal_scroll = column.saturating_sub(margin);
+        } else if column + cursor_width + margin > self.horizontal_scroll + editor_width {
+            self.horizontal_scroll = (column + cursor_width + margin).saturating_sub(editor_width).min(column);
+        }
+    }
+
+    pub(crate) fn wrapped_rows(&self, line: usize, width: usize, tab_width: usize) -> usize {
+        match self.content.get(line) {
+            Some(text) if self.fold_at(line).is_none() => line_width(text, tab_width).div_ceil(width.max(1)).max(1),
+            _ => 1,
+        }
+    }
+
+    pub(crate) fn visual_column(&self, y: usize, x: usize, tab_width: usize) -> usize {
+        self.content.get(y).map_or(0, |line| line_width(line.get(..x).unwrap_or(line), tab_width))
+    }
+
+    pub fn from_lines(lines: Vec<String>) -> Self {
+        let mut tab = Tab::new();
+        if !lines.is_empty() {
+            tab.content = lines;
+        }
+        tab
+    }
+
+    pub fn lines(&self) -> &[String] {
+        &self.content
+    }
+
+    pub fn text(&self) -> String {
+        self.content.join("\n")
+    }
+
+    pub fn cursor(&self) -> (usize, usize) {
+        self.cursor_position
+    }
+
+    pub fn set_cursor(&mut self, x: usize, y: usize) {
+        self.cursor_position = self.clamp_position((x, y));
+    }
+
+    pub(crate) fn clamp_position(&self, (x, y): (usize, usize)) -> (usize, usize) {
+        let y = y.min(self.content.len() - 1);
+        let line = &self.content[y];
+        let mut x = x.min(line.len());
+        while !line.is_char_boundary(x) {
+            x -= 1;
+        }
+        (x, y)
+    }
+
+    pub(crate) fn selection_bounds(&self, a: (usize, usize), b: (usize, usize)) -> ((usize, usize), (usize, usize)) {
+        let (a, b) = (self.clamp_position(a), self.clamp_position(b));
+        let (start, end) = if (a.1, a.0) <= (b.1, b.0) { (a, b) } else { (b, a) };
+        let under_end = self.content[end.1][end.0..].chars().next().map_or(0, char::len_utf8);
+        (start, (end.0 + under_end, end.1))
+    }
+
+    pub fn selection_text(&self, a: (usize, usize), b: (usize, usize)) -> String {
+        let (start, end) = self.selection_bounds(a, b);
+        if start.1 == end.1 {
+            return self.content[start.1][start.0..end.0].to_string();
+        }
+        let mut text = self.content[start.1][start.0..].to_string();
+        for line in &self.content[start.1 + 1..end.1] {
+            text.push('\n');
+            text.push_str(line);
+        }
+        text.push('\n');
+        text.push_str(&self.content[end.1][..end.0]);
+        text
+    }
+
+    pub fn selection_remove(&mut self, a: (usize, usize), b: (usize, usize)) {
+        let (start, end) = self.selection_bounds(a, b);
+        let rest = self.content[end.1][end.0..].to_string();
+        self.content[start.1].replace_range(start.0.., &rest);
+        if end.1 > start.1 {
+            self.content.drain(start.1 + 1..=end.1);
+            self.shift_lines(start.1, end.1 - start.1 + 1, 1);
+        }
+        self.cursor_position = start;
+    }
+
+    pub fn insert_text(&mut self, text: &str) {
+        let (x, y) = self.clamp_position(self.cursor_position);
+        let rest = self.content[y].split_off(x);
+        let mut lines: Vec<String> = text.split('\n').map(String::from).collect();
+        self.content[y].push_str(&lines.remove(0));
+        let inserted = lines.len();
+        let last = if inserted == 0 { y } else { y + inserted };
+        let column = if inserted == 0 { self.content[y].len() } else { lines[inserted - 1].len() };
+        self.content.splice(y + 1..y + 1, lines);
+        self.content[last].push_str(&rest);
+        self.shift_lines(y + 1, 0, inserted);
+        self.cursor_position = (column, last);
+    }
+
+    pub fn file(&self) -> Option<&str> {
+        self.current_file.as_deref()
+    }
+
+    pub fn is_modified(&self) -> bool {
+        self.modified && (self.modified_checked || self.content_hash(&self.content) != self.saved_hash)
+    }
+
+    pub(crate) fn content_hash(&self, content: &[String]) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        content.hash(&mut hasher);
+        (self.crlf, self.bom, self.eol).hash(&mut hasher);
+        hasher.finish()
+    }
+
+    pub(crate) fn mark_modified(&mut self) {
+        self.modified = true;
+        self.modified_checked = false;
+    }
+
+    pub(crate) fn mark_saved(&mut self, content: &[String]) {
+        self.saved_hash = self.content_hash(content);
+        self.mark_modified();
+        self.refresh_modified();
+    }
+
+    pub(crate) fn refresh_modified(&mut self) {
+        if !self.modified_checked {
+            self.modified = self.content_hash(&self.content) != self.saved_hash;
+            self.modified_checked = true;
+        }
+    }
+
+    pub(crate) fn snapshot(&self) -> EditOperation {
+        EditOperation {
+            content: self.content.clone(),
+            cursor_position: self.cursor_position,
+            scroll_offset: self.scroll_offset,
+            horizontal_scroll: self.horizontal_scroll,
+            bookmarks: self.bookmarks.clone(),
+            folds: self.folds.clone(),
+            size: snapshot_size(&self.content, &self.bookmarks, &self.folds),
+        }
+    }
+
+    pub(crate) fn restore(&mut self, operation: EditOperation) {
+        self.content = operation.content;
+        self.cursor_position = operation.cursor_position;
+        self.scroll_offset = operation.scroll_offset;
+        self.horizontal_scroll = operation.horizontal_scroll;
+        self.bookmarks = operation.bookmarks;
+        self.folds = operation.folds;
+    }
+
+    pub(crate) fn shift_lines(&mut self, at: usize, removed: usize, inserted: usize) {
+        self.bookmarks.retain(|&line| line < at || line - at < inserted.min(removed) || line >= at + removed);
+        for line in &mut self.bookmarks {
+            if *line >= at + removed {
+                *line = *line - removed + inserted;
+            }
+        }
+        self.folds.retain_mut(|(start, end)| {
+            if at > *end {
+                return true;
+            }
+            if at + removed <= *start {
+                *start = *start + inserted - removed;
+            } else if at <= *start || at + removed > *end + 1 {
+                return false;
+            }
+            *end = *end + inserted - removed;
+            *start < *end
+        });
+        for (x, y) in &mut self.changes {
+            if *y >= at + removed {
+                *y = *y - removed + inserted;
+            } else if *y >= at + inserted.min(removed) {
+                *x = 0;
+                *y = at;
+            }
+        }
+        self.changes.dedup_by_key(|&mut (_, y)| y);
+        self.change_index = self.change_index.min(self.changes.len());
+    }
+
+    pub(crate) fn fold_at(&self, line: usize) -> Option<(usize, usize)> {
+        self.folds.iter()
+            .filter(|&&(start, end)| start <= line && line <= end)
+            .max_by_key(|&&(start, end)| end - start)
+            .copied()
+    }
+
+    fn hidden_ranges(&self) -> Vec<(usize, usize)> {
+        let mut ranges: Vec<(usize, usize)> = Vec::new();
+        for &(start, end) in &self.folds {
+            match ranges.last_mut() {
+                Some(last) if start <= last.1 => last.1 = last.1.max(end),
+                _ => ranges.push((start + 1, end)),
+            }
+        }
+        ranges
+    }
+
+    pub(crate) fn line_to_row(&self, line: usize) -> usize {
+        let mut row = line;
+        for (first, last) in self.hidden_ranges() {
+            if first > line {
+                break;
+            }
+            if line <= last {
+                return row - (line - first) - 1;
+            }
+            row -= last - first + 1;
+        }
+        row
+    }
+
+    pub(crate) fn row_to_line(&self, row: usize) -> usize {
+        let mut line = row;
+        for (first, last) in self.hidden_ranges() {
+            if first > line {
+                break;
+            }
+            line += last - first + 1;
+        }
+        line.min(self.content.len().saturating_sub(1))
+    }
+
+    pub(crate) fn row_count(&self) -> usize {
+        self.line_to_row(self.content.len() - 1) + 1
+    }
+
+    pub(crate) fn open_folds_at(&mut self, line: usize) -> bool {
+        let count = self.folds.len();
+        self.folds.retain(|&(start, end)| line < start || end < line);
+        self.folds.len() != count
+    }
+
+    pub(crate) fn fold_regions(&self, method: FoldMethod) -> Vec<(usize, usize)> {
+        let mut regions = match method {
+            FoldMethod::Indent => self.indent_regions(),
+            FoldMethod::Brace => self.brace_regions(),
+        };
+        regions.sort_by_key(|&(start, end)| (start, usize::MAX - end));
+        regions.dedup_by_key(|region| region.0);
+        regions
+    }
+
+    fn indent_regions(&self) -> Vec<(usize, usize)> {
+        let indent = |line: &str| (!line.trim().is_empty()).then(|| display_width(&line[..line.len() - line.trim_start().len()]));
+        let mut regions = Vec::new();
+        let mut open: Vec<(usize, usize)> = Vec::new();
+        let mut last_text = 0;
+        for (y, line) in self.content.iter().enumerate() {
+            let Some(level) = indent(line) else {
+                continue;
+            };
+            while let Some(&(start, start_level)) = open.last() {
+                if level > start_level {
+                    break;
+                }
+                open.pop();
+                if last_text > start {
+                    regions.push((start, last_text));
+                }
+            }
+            open.push((y, level));
+            last_text = y;
+        }
+        for (start, _) in open {
+            if last_text > start {
+                regions.push((start, last_text));
+            }
+        }
+        regions
+    }
+
+    fn brace_regions(&self) -> Vec<(usize, usize)> {
+        let mut regions = Vec::new();
+        let mut open = Vec::new();
+        for (y, line) in self.content.iter().enumerate() {
+            let mut chars = line.chars().peekable();
+            let mut quote = None;
+            while let Some(c) = chars.next() {
+                match (quote, c) {
+                    (Some(_), '\\') => {
+                        chars.next();
+                    }
+                    (Some(q), c) if c == q => quote = None,
+                    (Some(_), _) => {}
+                    (None, '"') => quote = Some('"'),
+                    (None, '/') if chars.peek() == Some(&'/') => break,
+                    (None, '\'') => {
+                        let mut lookahead = chars.clone();
+                        let literal = match lookahead.next() {
+                            Some('\\') => lookahead.nth(1) == Some('\''),
+                            Some(_) => lookahead.next() == Some('\''),
+                            None => false,
+                        };
+                        if literal {
+                            quote = Some('\'');
+                        }
+                    }
+                    (None, '{') => open.push(y),
+                    (None, '}') => {
+                        if let Some(start) = open.pop() {
+                            if y > start {
+                                regions.push((start, y));
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        regions
+    }
+
+    pub(crate) fn toggle_bookmark(&mut self, line: usize) -> bool {
+        match self.bookmarks.binary_search(&line) {
+            Ok(index) => {
+                self.bookmarks.remove(index);
+                false
+            }
+            Err(index) => {
+                self.bookmarks.insert(index, line);
+                true
+            }
+        }
+    }
+
+    pub(crate) fn next_bookmark(&self, line: usize, forward: bool) -> Option<usize> {
+        if forward {
+            self.bookmarks.iter().find(|&&bookmark| bookmark > line).or(self.bookmarks.first()).copied()
+        } else {
+            self.bookmarks.iter().rev().find(|&&bookmark| bookmark < line).or(self.bookmarks.last()).copied()
+        }
+    }
+
+    pub(crate) fn record_change(&mut self, position: (usize, usize)) {
+        match self.changes.last_mut() {
+            Some(last) if last.1.abs_diff(position.1) <= 1 => *last = position,
+            _ => self.changes.push(position),
+        }
+        if self.changes.len() > CHANGE_LIST_LIMIT {
+            self.changes.remove(0);
+        }
+        self.change_index = self.changes.len();
+    }
+
+    pub fn push_undo(&mut self) {
+        let operation = self.snapshot();
+        self.undo_stack.push_front(operation);
+        self.redo_stack.clear();
+        self.mark_modified();
+    }
+
+    pub fn undo(&mut self) -> bool {
+        let Some(operation) = self.undo_stack.pop_front() else {
+            return false;
+        };
+        let current_state = self.snapshot();
+        self.redo_stack.push_front(current_state);
+        self.restore(operation);
+        self.mark_modified();
+        self.refresh_modified();
+        true
+    }
+
+    pub fn redo(&mut self) -> bool {
+        let Some(operation) = self.redo_stack.pop_front() else {
+            return false;
+        };
+        let current_state = self.snapshot();
+        self.undo_stack.push_front(current_state);
+        self.restore(operation);
+        self.mark_modified();
+        self.refresh_modified();
+        true
+    }
+
+    pub fn insert_char(&mut self, c: char) {
+        let (x, y) = self.cursor_position;
+        self.content[y].insert(x, c);
+        self.cursor_position.0 += c.len_utf8();
+    }
+
+    pub fn insert_newline(&mut self) {
+        let (x, y) = self.cursor_position;
+        let rest_of_line = self.content[y].split_off(x);
+        self.content.insert(y + 1, rest_of_line);
+        self.shift_lines(if x == 0 { y } else { y + 1 }, 0, 1);
+        self.cursor_position = (0, y + 1);
+    }
+
+    pub fn backspace(&mut self) {
+        let (x, y) = self.cursor_position;
+        if x > 0 {
+            let line = &mut self.content[y];
+            let previous = line[..x].char_indices().next_back().map_or(0, |(index, _)| index);
+            line.remove(previous);
+            self.cursor_position.0 = previous;
+        } else if y > 0 {
+            let current_line = self.content.remove(y);
+            self.shift_lines(y, 1, 0);
+            self.cursor_position = (self.content[y - 1].len(), y - 1);
+            self.content[y - 1].push_str(&current_line);
+        }
+    }
+
+    pub fn delete_char(&mut self) {
+        let (x, y) = self.cursor_position;
+        if x < self.content[y].len() {
+            self.content[y].remove(x);
+        } else if y < self.content.len() - 1 {
+            let next_line = self.content.remove(y + 1);
+            self.shift_lines(y + 1, 1, 0);
+            self.content[y].push_str(&next_line);
+        }
+    }
+
+    pub fn find(&self, query: &str) -> Vec<(usize, usize)> {
+        self.find_limited(query, usize::MAX)
+    }
+
+    pub(crate) fn find_limited(&self, query: &str, limit: usize) -> Vec<(usize, usize)> {
+        SearchPattern::literal(query, true).find_in(&self.content, limit)
+    }
+
+    pub fn substitute(&mut self, start: usize, end: usize, pattern: &SearchPattern, replacement: &str, global: bool) -> (usize, usize) {
+        let replaced = pattern.replace(&self.content, start..end + 1, replacement, global);
+        let (substitutions, changed_lines) = (replaced.total(), replaced.changed_lines());
+        for ((line, text), count) in self.content[start..=end].iter_mut().zip(replaced.lines).zip(replaced.counts) {
+            if count > 0 {
+                *line = text;
+            }
+        }
+        let (x, y) = self.cursor_position;
+        self.set_cursor(x, y);
+        (substitutions, changed_lines)
+    }
+}
+
+pub(crate) fn char_width(c: char) -> usize {
+    c.width().unwrap_or(1)
+}
+
+pub(crate) fn display_width(text: &str) -> usize {
+    text.chars().map(char_width).sum()
+}
+
+pub(crate) fn char_width_at(c: char, column: usize, tab_width: usize) -> usize {
+    if c == '\t' {
+        tab_width - column % tab_width
+    } else {
+        char_width(c)
+    }
+}
+
+fn single_width(text: &str) -> bool {
+    text.is_ascii() && !text.contains('\t')
+}
+
+pub(crate) fn line_width(text: &str, tab_width: usize) -> usize {
+    if single_width(text) {
+        return text.len();
+    }
+    text.chars().fold(0, |column, c| column + char_width_at(c, column, tab_width))
+}
+
+pub(crate) fn column_to_byte(line: &str, column: usize, tab_width: usize) -> usize {
+    if single_width(line) {
+        return column.min(line.len());
+    }
+    let mut width = 0;
+    for (index, c) in line.char_indices() {
+        width += char_width_at(c, width, tab_width);
+        if width > column {
+            return index;
+        }
+    }
+    line.len()
+}
+
+pub(crate) struct BlockSpan {
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+    pub(crate) text: String,
+    pub(crate) before: usize,
+    pub(crate) after: usize,
+}
+
+pub(crate) fn block_span(line: &str, left: usize, right: usize, tab_width: usize) -> BlockSpan {
+    let mut span = BlockSpan { start: line.len(), end: line.len(), text: String::new(), before: 0, after: 0 };
+    let mut column = 0;
+    for (index, c) in line.char_indices() {
+        if column > right {
+            break;
+        }
+        let next = column + char_width_at(c, column, tab_width);
+        let inside = if next == column { column > left && column <= right + 1 } else { next > left };
+        if inside {
+            if span.start == line.len() {
+                span.start = index;
+                span.before = left.saturating_sub(column);
+            }
+            span.end = index + c.len_utf8();
+            span.after = next.saturating_sub(right + 1);
+            if column >= left && next <= right + 1 {
+                span.text.push(c);
+            } else {
+                span.text.push_str(&" ".repeat(next.min(right + 1) - column.max(left)));
+            }
+        }
+        column = next;
+    }
+    if span.start == line.len() {
+        span.before = left.saturating_sub(column);
+    }
+    span
+}
+
+pub(crate) fn insert_at_column(line: &mut String, column: usize, text: &str, tab_width: usize) {
+    let span = block_span(line, column, column, tab_width);
+    if span.start == line.len() {
+        line.push_str(&" ".repeat(span.before));
+        line.push_str(text);
+    } else if span.before > 0 && line[span.start..].starts_with('\t') {
+        let width = span.before + span.after + 1;
+        line.replace_range(span.start..span.end, &format!("{}{}{}", " ".repeat(span.before), text, " ".repeat(width - span.before)));
+    } else {
+        line.insert_str(span.start, text);
+    }
+}
+
+impl Default for Tab {
+    fn default() -> Self {
+        Self::new()
+    }
+}
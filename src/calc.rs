@@ -0,0 +1,265 @@
+use crate::editor::Editor;
+
+const MAX_DEPTH: usize = 200;
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(f64),
+    Name(String),
+    Operator(char),
+    Open,
+    Close,
+    Comma,
+}
+
+struct Parser<'a> {
+    tokens: Vec<(Token, usize)>,
+    position: usize,
+    depth: usize,
+    variables: &'a [(&'a str, f64)],
+}
+
+fn tokenize(text: &str) -> Result<Vec<(Token, usize)>, String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        if c.is_ascii_digit() || c == '.' {
+            let hex = c == '0' && matches!(chars.get(i + 1), Some('x' | 'X'));
+            let token = if hex {
+                i += 2;
+                while i < chars.len() && chars[i].is_ascii_hexdigit() {
+                    i += 1;
+                }
+                let digits: String = chars[start + 2..i].iter().collect();
+                i64::from_str_radix(&digits, 16).map(|n| n as f64).map_err(|_| format!("Invalid hex literal at column {}", start + 1))?
+            } else {
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                if i < chars.len() && matches!(chars[i], 'e' | 'E') {
+                    let sign = usize::from(matches!(chars.get(i + 1), Some('+' | '-')));
+                    if chars.get(i + 1 + sign).is_some_and(char::is_ascii_digit) {
+                        i += 1 + sign;
+                        while i < chars.len() && chars[i].is_ascii_digit() {
+                            i += 1;
+                        }
+                    }
+                }
+                let literal: String = chars[start..i].iter().collect();
+                literal.parse::<f64>().map_err(|_| format!("Invalid number {} at column {}", literal, start + 1))?
+            };
+            if chars.get(i).is_some_and(|c| c.is_alphanumeric() || *c == '_') {
+                return Err(format!("Invalid number at column {}", start + 1));
+            }
+            tokens.push((Token::Number(token), start));
+            continue;
+        }
+        if c.is_alphabetic() || c == '_' {
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push((Token::Name(chars[start..i].iter().collect()), start));
+            continue;
+        }
+        let token = match c {
+            '+' | '-' | '*' | '/' | '%' => Token::Operator(c),
+            '(' => Token::Open,
+            ')' => Token::Close,
+            ',' => Token::Comma,
+            _ => return Err(format!("Unexpected '{}' at column {}", c, start + 1)),
+        };
+        tokens.push((token, start));
+        i += 1;
+    }
+    Ok(tokens)
+}
+
+fn binding_power(operator: char) -> u8 {
+    match operator {
+        '+' | '-' => 1,
+        _ => 2,
+    }
+}
+
+fn apply(operator: char, left: f64, right: f64) -> Result<f64, String> {
+    match operator {
+        '+' => Ok(left + right),
+        '-' => Ok(left - right),
+        '*' => Ok(left * right),
+        '/' | '%' if right == 0.0 => Err("Division by zero".to_string()),
+        '/' => Ok(left / right),
+        _ => Ok(left % right),
+    }
+}
+
+fn call(name: &str, arguments: &[f64]) -> Result<f64, String> {
+    let unary = |f: fn(f64) -> f64| match arguments {
+        [x] => Ok(f(*x)),
+        _ => Err(format!("{}() takes 1 argument", name)),
+    };
+    match name {
+        "abs" => unary(f64::abs),
+        "floor" => unary(f64::floor),
+        "ceil" => unary(f64::ceil),
+        "round" => unary(f64::round),
+        "sqrt" => unary(f64::sqrt),
+        "min" | "max" if arguments.is_empty() => Err(format!("{}() takes at least 1 argument", name)),
+        "min" => Ok(arguments.iter().copied().fold(f64::INFINITY, f64::min)),
+        "max" => Ok(arguments.iter().copied().fold(f64::NEG_INFINITY, f64::max)),
+        _ => Err(format!("Unknown function {}", name)),
+    }
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(token, _)| token)
+    }
+
+    fn next(&mut self) -> Result<Token, String> {
+        let (token, _) = self.tokens.get(self.position).cloned().ok_or("Unexpected end of expression")?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn unexpected(&self) -> String {
+        match self.tokens.get(self.position) {
+            Some((token, column)) => {
+                let text = match token {
+                    Token::Number(n) => format_number(*n),
+                    Token::Name(name) => name.clone(),
+                    Token::Operator(c) => c.to_string(),
+                    Token::Open => "(".to_string(),
+                    Token::Close => ")".to_string(),
+                    Token::Comma => ",".to_string(),
+                };
+                format!("Unexpected '{}' at column {}", text, column + 1)
+            }
+            None => "Unexpected end of expression".to_string(),
+        }
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), String> {
+        if self.peek() != Some(&expected) {
+            return Err(self.unexpected());
+        }
+        self.position += 1;
+        Ok(())
+    }
+
+    fn expression(&mut self, min_power: u8) -> Result<f64, String> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err("Expression is nested too deeply".to_string());
+        }
+        let mut left = self.operand()?;
+        while let Some(&Token::Operator(operator)) = self.peek() {
+            let power = binding_power(operator);
+            if power < min_power {
+                break;
+            }
+            self.position += 1;
+            let right = self.expression(power + 1)?;
+            left = apply(operator, left, right)?;
+        }
+        self.depth -= 1;
+        Ok(left)
+    }
+
+    fn operand(&mut self) -> Result<f64, String> {
+        if matches!(self.peek(), None | Some(Token::Close | Token::Comma) | Some(Token::Operator('*' | '/' | '%'))) {
+            return Err(self.unexpected());
+        }
+        match self.next()? {
+            Token::Number(n) => Ok(n),
+            Token::Operator('-') => Ok(-self.expression(3)?),
+            Token::Operator(_) => self.expression(3),
+            Token::Open => {
+                let value = self.expression(0)?;
+                self.expect(Token::Close)?;
+                Ok(value)
+            }
+            Token::Name(name) if self.peek() == Some(&Token::Open) => {
+                self.position += 1;
+                let mut arguments = Vec::new();
+                if self.peek() != Some(&Token::Close) {
+                    arguments.push(self.expression(0)?);
+                    while self.peek() == Some(&Token::Comma) {
+                        self.position += 1;
+                        arguments.push(self.expression(0)?);
+                    }
+                }
+                self.expect(Token::Close)?;
+                call(&name, &arguments)
+            }
+            Token::Name(name) => self.variables.iter()
+                .find(|(variable, _)| *variable == name)
+                .map(|(_, value)| *value)
+                .ok_or_else(|| format!("Unknown variable {}", name)),
+            _ => unreachable!(),
+        }
+    }
+}
+
+pub(crate) fn evaluate(text: &str, variables: &[(&str, f64)]) -> Result<f64, String> {
+    let tokens = tokenize(text)?;
+    if tokens.is_empty() {
+        return Err("Empty expression".to_string());
+    }
+    let mut parser = Parser { tokens, position: 0, depth: 0, variables };
+    let value = parser.expression(0)?;
+    if parser.position < parser.tokens.len() {
+        return Err(parser.unexpected());
+    }
+    if !value.is_finite() {
+        return Err("Result is not a finite number".to_string());
+    }
+    Ok(value)
+}
+
+pub(crate) fn format_number(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        format!("{}", value as i64)
+    } else {
+        format!("{}", value)
+    }
+}
+
+impl Editor {
+    pub(crate) fn evaluate_expression(&self, text: &str) -> Result<String, String> {
+        let tab = &self.tabs[self.active_tab];
+        let (x, y) = tab.cursor_position;
+        let column = tab.content.get(y).map_or(0, |line| Self::byte_to_char(line, x.min(line.len())));
+        let variables = [
+            ("line", (y + 1) as f64),
+            ("col", (column + 1) as f64),
+            ("column", (column + 1) as f64),
+            ("lines", tab.content.len() as f64),
+        ];
+        evaluate(text, &variables).map(format_number)
+    }
+
+    pub(crate) fn insert_expression_result(&mut self, text: &str) {
+        match self.evaluate_expression(text) {
+            Ok(result) => {
+                self.save_state();
+                self.tabs[self.active_tab].insert_text(&result);
+                self.adjust_horizontal_scroll();
+            }
+            Err(e) => self.error(format!("Expression: {}", e)),
+        }
+    }
+
+    pub(crate) fn calc_command(&mut self, expression: &str) {
+        match self.evaluate_expression(expression) {
+            Ok(result) => self.info(format!("{} = {}", expression, result)),
+            Err(e) => self.error(format!("calc: {}", e)),
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::buffer::line_width;
+use crate::editor::{Editor, Mode};
+use crate::ui::{Picker, PickerKind};
+
+const NAMES: &[(u32, &str)] = &[
+    (0x0000, "NULL"),
+    (0x0009, "CHARACTER TABULATION"),
+    (0x000A, "LINE FEED"),
+    (0x000D, "CARRIAGE RETURN"),
+    (0x001B, "ESCAPE"),
+    (0x0020, "SPACE"),
+    (0x007F, "DELETE"),
+    (0x00A0, "NO-BREAK SPACE"),
+    (0x00AD, "SOFT HYPHEN"),
+    (0x0300, "COMBINING GRAVE ACCENT"),
+    (0x0301, "COMBINING ACUTE ACCENT"),
+    (0x0308, "COMBINING DIAERESIS"),
+    (0x034F, "COMBINING GRAPHEME JOINER"),
+    (0x061C, "ARABIC LETTER MARK"),
+    (0x115F, "HANGUL CHOSEONG FILLER"),
+    (0x180E, "MONGOLIAN VOWEL SEPARATOR"),
+    (0x2002, "EN SPACE"),
+    (0x2003, "EM SPACE"),
+    (0x2009, "THIN SPACE"),
+    (0x200A, "HAIR SPACE"),
+    (0x200B, "ZERO WIDTH SPACE"),
+    (0x200C, "ZERO WIDTH NON-JOINER"),
+    (0x200D, "ZERO WIDTH JOINER"),
+    (0x200E, "LEFT-TO-RIGHT MARK"),
+    (0x200F, "RIGHT-TO-LEFT MARK"),
+    (0x2028, "LINE SEPARATOR"),
+    (0x2029, "PARAGRAPH SEPARATOR"),
+    (0x202A, "LEFT-TO-RIGHT EMBEDDING"),
+    (0x202B, "RIGHT-TO-LEFT EMBEDDING"),
+    (0x202C, "POP DIRECTIONAL FORMATTING"),
+    (0x202D, "LEFT-TO-RIGHT OVERRIDE"),
+    (0x202E, "RIGHT-TO-LEFT OVERRIDE"),
+    (0x202F, "NARROW NO-BREAK SPACE"),
+    (0x2060, "WORD JOINER"),
+    (0x2061, "FUNCTION APPLICATION"),
+    (0x2062, "INVISIBLE TIMES"),
+    (0x2063, "INVISIBLE SEPARATOR"),
+    (0x2064, "INVISIBLE PLUS"),
+    (0x2066, "LEFT-TO-RIGHT ISOLATE"),
+    (0x2067, "RIGHT-TO-LEFT ISOLATE"),
+    (0x2068, "FIRST STRONG ISOLATE"),
+    (0x2069, "POP DIRECTIONAL ISOLATE"),
+    (0x3000, "IDEOGRAPHIC SPACE"),
+    (0x3164, "HANGUL FILLER"),
+    (0xFE0E, "VARIATION SELECTOR-15"),
+    (0xFE0F, "VARIATION SELECTOR-16"),
+    (0xFEFF, "ZERO WIDTH NO-BREAK SPACE"),
+    (0xFFFC, "OBJECT REPLACEMENT CHARACTER"),
+    (0xFFFD, "REPLACEMENT CHARACTER"),
+];
+
+const DIGITS: [&str; 10] = ["ZERO", "ONE", "TWO", "THREE", "FOUR", "FIVE", "SIX", "SEVEN", "EIGHT", "NINE"];
+
+fn char_name(c: char) -> Option<String> {
+    match c {
+        'A'..='Z' => Some(format!("LATIN CAPITAL LETTER {}", c)),
+        'a'..='z' => Some(format!("LATIN SMALL LETTER {}", c.to_ascii_uppercase())),
+        '0'..='9' => Some(format!("DIGIT {}", DIGITS[c as usize - '0' as usize])),
+        _ => NAMES.iter().find(|(code, _)| *code == c as u32).map(|(_, name)| name.to_string()),
+    }
+}
+
+fn char_flag(c: char) -> Option<&'static str> {
+    match c as u32 {
+        0x061C | 0x200E | 0x200F | 0x202A..=0x202E | 0x2066..=0x2069 => Some("bidi control"),
+        0x034F | 0x180E | 0x200B..=0x200D | 0x2060..=0x2064 | 0xFEFF => Some("zero-width"),
+        _ if c.is_control() && c != '\t' => Some("control"),
+        _ => None,
+    }
+}
+
+fn describe_char(c: char) -> String {
+    let mut bytes = [0; 4];
+    let utf8: Vec<String> = c.encode_utf8(&mut bytes).bytes().map(|byte| format!("{:02X}", byte)).collect();
+    let mut text = format!("U+{:04X} ({}) {}", c as u32, c as u32, utf8.join(" "));
+    if let Some(name) = char_name(c) {
+        text.push(' ');
+        text.push_str(&name);
+    }
+    text
+}
+
+fn grapheme_flags(grapheme: &str) -> Vec<&'static str> {
+    let mut flags: Vec<&str> = grapheme.chars().filter_map(char_flag).collect();
+    flags.dedup();
+    flags
+}
+
+impl Editor {
+    fn describe_grapheme(&self, line: &str, start: usize, grapheme: &str) -> String {
+        let tab_width = self.option("tabstop").number();
+        let width = line_width(&line[..start + grapheme.len()], tab_width) - line_width(&line[..start], tab_width);
+        let chars: Vec<String> = grapheme.chars().map(describe_char).collect();
+        let mut text = format!("\"{}\" width {}: {}", grapheme.escape_debug(), width, chars.join(", "));
+        let flags = grapheme_flags(grapheme);
+        if !flags.is_empty() {
+            text.push_str(&format!(" [{}]", flags.join(", ")));
+        }
+        text
+    }
+
+    pub(crate) fn show_char_info(&mut self) {
+        let tab = &self.tabs[self.active_tab];
+        let (x, y) = tab.cursor_position;
+        let line = &tab.content[y];
+        if line.is_empty() {
+            self.info("Empty line");
+            return;
+        }
+        let found = line.grapheme_indices(true).find(|(start, grapheme)| x < start + grapheme.len());
+        let Some((start, grapheme)) = found else {
+            self.info("End of line");
+            return;
+        };
+        let message = self.describe_grapheme(line, start, grapheme);
+        self.info(message);
+    }
+
+    pub(crate) fn show_line_characters(&mut self) {
+        let tab = &self.tabs[self.active_tab];
+        let y = tab.cursor_position.1;
+        let line = &tab.content[y];
+        if line.is_empty() {
+            self.info("Empty line");
+            return;
+        }
+        let mut flagged = 0;
+        let items: Vec<String> = line.grapheme_indices(true)
+            .map(|(start, grapheme)| {
+                flagged += usize::from(!grapheme_flags(grapheme).is_empty());
+                format!("{:>4}  {}", line[..start].chars().count() + 1, self.describe_grapheme(line, start, grapheme))
+            })
+            .collect();
+        let title = match flagged {
+            0 => format!("Line {}: {} characters", y + 1, items.len()),
+            _ => format!("Line {}: {} characters, {} flagged", y + 1, items.len(), flagged),
+        };
+        self.picker = Some(Picker::new(PickerKind::Characters, &title, items));
+        self.mode = Mode::Picker;
+    }
+
+    pub(crate) fn goto_picked_character(&mut self, selected: &str) {
+        let column = selected.split_whitespace().next().and_then(|column| column.parse::<usize>().ok());
+        let line = self.tabs[self.active_tab].cursor_position.1;
+        self.goto_location(line + 1, column);
+    }
+}
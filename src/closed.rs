@@ -0,0 +1,117 @@
+use std::path::Path;
+
+use crate::editor::{Editor, Mode};
+use crate::ui::{Picker, PickerKind};
+
+const CLOSED_TAB_LIMIT: usize = 20;
+
+pub(crate) struct ClosedTab {
+    file: Option<String>,
+    title: Option<String>,
+    cursor_position: (usize, usize),
+    scroll_offset: usize,
+    horizontal_scroll: usize,
+    content: Option<Vec<String>>,
+    modified: bool,
+    read_only: bool,
+}
+
+impl Editor {
+    pub(crate) fn remember_closed_tab(&mut self, tab_index: usize) {
+        let tab = &self.tabs[tab_index];
+        let untitled = tab.current_file.is_none();
+        if tab.private || (untitled && !tab.is_modified() && tab.content == vec![String::new()]) {
+            return;
+        }
+        self.closed_tabs.push(ClosedTab {
+            file: tab.current_file.clone(),
+            title: tab.title.clone(),
+            cursor_position: tab.cursor_position,
+            scroll_offset: tab.scroll_offset,
+            horizontal_scroll: tab.horizontal_scroll,
+            content: (untitled || tab.is_modified()).then(|| tab.content.clone()),
+            modified: tab.is_modified(),
+            read_only: tab.read_only,
+        });
+        if self.closed_tabs.len() > CLOSED_TAB_LIMIT {
+            self.closed_tabs.remove(0);
+        }
+    }
+
+    pub(crate) fn reopen_closed_tab(&mut self) {
+        match self.closed_tabs.len().checked_sub(1) {
+            Some(index) => self.reopen_closed_tab_at(index),
+            None => self.info("No closed tabs to reopen"),
+        }
+    }
+
+    fn reopen_closed_tab_at(&mut self, index: usize) {
+        let closed = self.closed_tabs.remove(index);
+        if let Some(file) = &closed.file {
+            if let Some(open) = self.tabs.iter().position(|tab| tab.current_file.as_deref() == Some(file.as_str())) {
+                self.set_active_tab(open);
+                self.update_current_tab_info();
+                self.info(format!("{} is already open", self.display_path(file)));
+                return;
+            }
+        }
+
+        match &closed.file {
+            Some(file) => {
+                if let Err(e) = self.open_file(Path::new(file)) {
+                    self.error(format!("Failed to reopen {}: {}", self.display_path(file), e));
+                    return;
+                }
+            }
+            None => self.new_tab(),
+        }
+        let tab = &mut self.tabs[self.active_tab];
+        if let Some(content) = closed.content {
+            tab.content = content;
+            if closed.modified {
+                tab.mark_modified();
+            }
+        }
+        tab.title = closed.title.or(tab.title.take());
+        tab.read_only |= closed.read_only;
+        tab.cursor_position = closed.cursor_position;
+        tab.scroll_offset = closed.scroll_offset;
+        tab.horizontal_scroll = closed.horizontal_scroll;
+        self.ensure_cursor_in_bounds();
+        let tab = &mut self.tabs[self.active_tab];
+        tab.scroll_offset = tab.scroll_offset.min(tab.cursor_position.1);
+        self.update_current_tab_info();
+        if closed.modified {
+            self.info("Reopened closed tab with its unsaved changes");
+        }
+    }
+
+    pub(crate) fn open_closed_tabs_picker(&mut self) {
+        if self.closed_tabs.is_empty() {
+            self.info("No closed tabs to reopen");
+            return;
+        }
+        let items = self.closed_tabs.iter()
+            .enumerate()
+            .rev()
+            .map(|(index, closed)| {
+                let name = match (&closed.file, &closed.title) {
+                    (Some(file), _) => self.display_path(file),
+                    (None, Some(title)) => title.clone(),
+                    (None, None) => "[untitled]".to_string(),
+                };
+                let marker = if closed.modified { " [+]" } else { "" };
+                format!("{}  {}{}  line {}", index + 1, name, marker, closed.cursor_position.1 + 1)
+            })
+            .collect();
+        self.picker = Some(Picker::new(PickerKind::ClosedTabs, "Closed Tabs", items));
+        self.mode = Mode::Picker;
+    }
+
+    pub(crate) fn reopen_picked_closed_tab(&mut self, selected: &str) {
+        let index = selected.split_whitespace().next().and_then(|n| n.parse::<usize>().ok());
+        if let Some(index) = index.filter(|&n| n >= 1 && n <= self.closed_tabs.len()) {
+            self.reopen_closed_tab_at(index - 1);
+        }
+    }
+}
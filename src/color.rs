@@ -0,0 +1,242 @@
+use serde::{Deserialize, Serialize};
+use tui::style::{Color, Modifier, Style};
+
+const ANSI_COLORS: [(&str, Color, (u8, u8, u8)); 16] = [
+    ("black", Color::Black, (0, 0, 0)),
+    ("red", Color::Red, (205, 0, 0)),
+    ("green", Color::Green, (0, 205, 0)),
+    ("yellow", Color::Yellow, (205, 205, 0)),
+    ("blue", Color::Blue, (0, 0, 238)),
+    ("magenta", Color::Magenta, (205, 0, 205)),
+    ("cyan", Color::Cyan, (0, 205, 205)),
+    ("white", Color::Gray, (229, 229, 229)),
+    ("bright_black", Color::DarkGray, (127, 127, 127)),
+    ("bright_red", Color::LightRed, (255, 0, 0)),
+    ("bright_green", Color::LightGreen, (0, 255, 0)),
+    ("bright_yellow", Color::LightYellow, (255, 255, 0)),
+    ("bright_blue", Color::LightBlue, (92, 92, 255)),
+    ("bright_magenta", Color::LightMagenta, (255, 0, 255)),
+    ("bright_cyan", Color::LightCyan, (0, 255, 255)),
+    ("bright_white", Color::White, (255, 255, 255)),
+];
+
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+pub fn parse_color(value: &str) -> Result<Color, String> {
+    let invalid = || format!("invalid color {:?}: expected #RGB, #RRGGBB, a color name like red or bright_blue, or a 256-color index", value);
+    let value = value.trim();
+    if let Some(hex) = value.strip_prefix('#') {
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(invalid());
+        }
+        let channel = |digits: &str| u8::from_str_radix(digits, 16).map_err(|_| invalid());
+        return match hex.len() {
+            3 => {
+                let (r, g, b) = (channel(&hex[0..1])?, channel(&hex[1..2])?, channel(&hex[2..3])?);
+                Ok(Color::Rgb(r * 17, g * 17, b * 17))
+            }
+            6 => Ok(Color::Rgb(channel(&hex[0..2])?, channel(&hex[2..4])?, channel(&hex[4..6])?)),
+            _ => Err(invalid()),
+        };
+    }
+    if !value.is_empty() && value.chars().all(|c| c.is_ascii_digit()) {
+        return value.parse::<u8>().map(Color::Indexed).map_err(|_| invalid());
+    }
+    let name = value.to_ascii_lowercase().replace(['-', ' '], "_");
+    if name == "reset" || name == "default" {
+        return Ok(Color::Reset);
+    }
+    ANSI_COLORS.iter()
+        .find(|(ansi_name, _, _)| *ansi_name == name)
+        .map(|(_, color, _)| *color)
+        .ok_or_else(invalid)
+}
+
+const MODIFIERS: [(&str, Modifier); 11] = [
+    ("bold", Modifier::BOLD),
+    ("dim", Modifier::DIM),
+    ("italic", Modifier::ITALIC),
+    ("underline", Modifier::UNDERLINED),
+    ("underlined", Modifier::UNDERLINED),
+    ("blink", Modifier::SLOW_BLINK),
+    ("reverse", Modifier::REVERSED),
+    ("reversed", Modifier::REVERSED),
+    ("hidden", Modifier::HIDDEN),
+    ("strikethrough", Modifier::CROSSED_OUT),
+    ("crossed_out", Modifier::CROSSED_OUT),
+];
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct StyleSpec {
+    pub color: Option<Color>,
+    pub fg: Option<Color>,
+    pub bg: Option<Color>,
+    pub modifiers: Modifier,
+}
+
+impl Default for StyleSpec {
+    fn default() -> Self {
+        StyleSpec { color: None, fg: None, bg: None, modifiers: Modifier::empty() }
+    }
+}
+
+impl StyleSpec {
+    pub fn primary(self) -> Option<Color> {
+        self.color.or(self.fg).or(self.bg)
+    }
+
+    pub fn fg(self) -> Style {
+        self.style(self.color.or(self.fg), self.bg)
+    }
+
+    pub fn bg(self) -> Style {
+        self.style(self.fg, self.color.or(self.bg))
+    }
+
+    fn style(self, fg: Option<Color>, bg: Option<Color>) -> Style {
+        let mut style = Style::default().add_modifier(self.modifiers);
+        if let Some(fg) = fg {
+            style = style.fg(fg);
+        }
+        if let Some(bg) = bg {
+            style = style.bg(bg);
+        }
+        style
+    }
+
+    pub fn convert(self, mode: ColorMode) -> Self {
+        let convert = |color: Option<Color>| color.map(|color| mode.convert(color));
+        StyleSpec { color: convert(self.color), fg: convert(self.fg), bg: convert(self.bg), modifiers: self.modifiers }
+    }
+}
+
+pub(crate) fn is_default_color(value: &str) -> bool {
+    let value = value.trim();
+    value.eq_ignore_ascii_case("default") || value.eq_ignore_ascii_case("none")
+}
+
+pub fn parse_style(value: &str) -> Result<StyleSpec, String> {
+    if is_default_color(value) {
+        return Ok(StyleSpec::default());
+    }
+    if let Ok(color) = parse_color(value) {
+        return Ok(StyleSpec { color: Some(color), ..StyleSpec::default() });
+    }
+    let mut spec = StyleSpec::default();
+    for token in value.split_whitespace() {
+        if let Some(color) = token.strip_prefix("fg=") {
+            spec.fg = (!is_default_color(color)).then(|| parse_color(color)).transpose()?;
+        } else if let Some(color) = token.strip_prefix("bg=") {
+            spec.bg = (!is_default_color(color)).then(|| parse_color(color)).transpose()?;
+        } else if let Some((_, modifier)) = MODIFIERS.iter().find(|(name, _)| token.eq_ignore_ascii_case(name)) {
+            spec.modifiers |= *modifier;
+        } else if is_default_color(token) {
+            continue;
+        } else if token.starts_with('#') || token.starts_with(|c: char| c.is_ascii_digit()) || parse_color(token).is_ok() {
+            if spec.color.is_some() {
+                return Err(format!("more than one color in {:?}: use fg= and bg=", value));
+            }
+            spec.color = Some(parse_color(token)?);
+        } else {
+            return Err(format!(
+                "invalid style {:?}: expected a color, fg=/bg= colors or bold, dim, italic, underline, blink, reverse, hidden, strikethrough",
+                token
+            ));
+        }
+    }
+    if value.trim().is_empty() {
+        return parse_color(value).map(|_| spec);
+    }
+    Ok(spec)
+}
+
+#[derive(Deserialize)]
+pub(crate) struct StyleFields {
+    fg: Option<String>,
+    bg: Option<String>,
+    #[serde(default)]
+    modifiers: Vec<String>,
+}
+
+impl StyleFields {
+    pub(crate) fn to_spec(&self) -> String {
+        let color = |prefix: &str, color: &Option<String>| color.as_ref().map(|color| format!("{}={}", prefix, color.trim().replace(' ', "_")));
+        color("fg", &self.fg).into_iter()
+            .chain(color("bg", &self.bg))
+            .chain(self.modifiers.iter().cloned())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Default, Debug)]
+pub enum ColorMode {
+    #[default]
+    #[serde(rename = "truecolor")]
+    TrueColor,
+    #[serde(rename = "256")]
+    Palette256,
+    #[serde(rename = "16")]
+    Palette16,
+    #[serde(rename = "none")]
+    NoColor,
+}
+
+impl ColorMode {
+    pub fn convert(self, color: Color) -> Color {
+        match (self, color) {
+            (ColorMode::Palette256, Color::Rgb(r, g, b)) => Color::Indexed(nearest_256((r, g, b))),
+            (ColorMode::Palette16, Color::Rgb(r, g, b)) => nearest_16((r, g, b)),
+            (ColorMode::Palette16, Color::Indexed(index)) => nearest_16(index_to_rgb(index)),
+            (ColorMode::NoColor, _) => Color::Reset,
+            _ => color,
+        }
+    }
+}
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let d = |x: u8, y: u8| (x as i32 - y as i32).pow(2) as u32;
+    d(a.0, b.0) + d(a.1, b.1) + d(a.2, b.2)
+}
+
+fn index_to_rgb(index: u8) -> (u8, u8, u8) {
+    match index {
+        0..=15 => ANSI_COLORS[index as usize].2,
+        16..=231 => {
+            let i = index - 16;
+            (CUBE_LEVELS[(i / 36) as usize], CUBE_LEVELS[(i / 6 % 6) as usize], CUBE_LEVELS[(i % 6) as usize])
+        }
+        _ => {
+            let level = 8 + 10 * (index - 232);
+            (level, level, level)
+        }
+    }
+}
+
+fn nearest_256(rgb: (u8, u8, u8)) -> u8 {
+    let cube = |channel: u8| {
+        (0..CUBE_LEVELS.len())
+            .min_by_key(|&i| (CUBE_LEVELS[i] as i32 - channel as i32).abs())
+            .unwrap_or(0) as u8
+    };
+    let cube_index = 16 + 36 * cube(rgb.0) + 6 * cube(rgb.1) + cube(rgb.2);
+    let average = (rgb.0 as u32 + rgb.1 as u32 + rgb.2 as u32) / 3;
+    let gray_index = 232 + (average.saturating_sub(3) / 10).min(23) as u8;
+    if distance(index_to_rgb(gray_index), rgb) < distance(index_to_rgb(cube_index), rgb) {
+        gray_index
+    } else {
+        cube_index
+    }
+}
+
+fn nearest_16(rgb: (u8, u8, u8)) -> Color {
+    ANSI_COLORS.iter()
+        .min_by_key(|(_, _, ansi)| distance(*ansi, rgb))
+        .map_or(Color::Reset, |(_, color, _)| *color)
+}
+
+pub(crate) fn light_background(colorfgbg: &str) -> bool {
+    colorfgbg.rsplit(';').next()
+        .and_then(|bg| bg.trim().parse::<u8>().ok())
+        .is_some_and(|bg| matches!(bg, 7 | 9..=15))
+}
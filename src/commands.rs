@@ -0,0 +1,930 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crossterm::event::{KeyCode, KeyEvent};
+
+use crate::config::ColorConfig;
+use crate::editor::{Editor, Mode};
+use crate::error::PhantomError;
+use crate::search::SearchPattern;
+use crate::ui::{Picker, PickerKind, PickerLocation, PromptAction};
+
+pub(crate) type LineRange = (usize, usize);
+
+pub(crate) struct SubstituteConfirm {
+    pub(crate) pattern: SearchPattern,
+    pub(crate) replacement: String,
+    pub(crate) global: bool,
+    pub(crate) end: usize,
+    pub(crate) current: (usize, usize, usize),
+    pub(crate) substitutions: usize,
+    pub(crate) changed_lines: usize,
+    pub(crate) last_changed: Option<usize>,
+    pub(crate) saved: bool,
+}
+
+struct Substitution {
+    pattern: String,
+    replacement: String,
+    global: bool,
+    ignore_case: bool,
+    confirm: bool,
+}
+
+fn substitution_summary(substitutions: usize, lines: usize) -> String {
+    format!(
+        "{} substitution{} on {} line{}",
+        substitutions,
+        if substitutions == 1 { "" } else { "s" },
+        lines,
+        if lines == 1 { "" } else { "s" },
+    )
+}
+
+impl Editor {
+    pub(crate) fn alternate_file(&self) -> Option<String> {
+        if let Some(file) = self.alternate_tab().and_then(|index| self.tabs.get(index)).and_then(|tab| tab.current_file.clone()) {
+            return Some(file);
+        }
+        let current = self.tabs[self.active_tab].current_file.as_deref();
+        self.recent_files.entries.iter()
+            .find(|entry| Some(entry.as_str()) != current)
+            .cloned()
+    }
+
+    pub(crate) fn expand_filename(&self, arg: &str) -> Result<String, String> {
+        let mut result = String::new();
+        let mut chars = arg.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' if matches!(chars.peek(), Some('%') | Some('#')) => result.extend(chars.next()),
+                '%' | '#' => {
+                    let file = if c == '%' {
+                        self.tabs[self.active_tab].current_file.clone().ok_or("No file name for %")?
+                    } else {
+                        self.alternate_file().ok_or("No alternate file name for #")?
+                    };
+                    let mut path = PathBuf::from(file);
+                    while chars.peek() == Some(&':') {
+                        let mut lookahead = chars.clone();
+                        lookahead.next();
+                        let Some(modifier) = lookahead.next().filter(|m| "phtre".contains(*m)) else {
+                            break;
+                        };
+                        chars = lookahead;
+                        path = match modifier {
+                            'p' => Self::absolute_path(&path),
+                            'h' => path.parent().map(Path::to_path_buf).unwrap_or_else(|| PathBuf::from(".")),
+                            't' => PathBuf::from(path.file_name().unwrap_or_default()),
+                            'r' => path.with_extension(""),
+                            _ => PathBuf::from(path.extension().unwrap_or_default()),
+                        };
+                    }
+                    result.push_str(&path.to_string_lossy());
+                }
+                _ => result.push(c),
+            }
+        }
+        Ok(result)
+    }
+
+    pub(crate) fn resolve_command_path(&self, arg: &str) -> Result<PathBuf, String> {
+        let expanded = self.expand_filename(arg)?;
+        let path = match expanded.strip_prefix("~/") {
+            Some(rest) => dirs::home_dir().map(|home| home.join(rest)).unwrap_or_else(|| PathBuf::from(&expanded)),
+            None => PathBuf::from(&expanded),
+        };
+        if path.is_absolute() {
+            return Ok(path);
+        }
+        let base = self.tabs[self.active_tab].current_file.as_ref()
+            .and_then(|file| Path::new(file).parent().map(Path::to_path_buf));
+        Ok(match base {
+            Some(base) => base.join(path),
+            None => path,
+        })
+    }
+
+    pub(crate) fn remember_visual_marks(&mut self) {
+        let cursor_line = self.tabs[self.active_tab].cursor_position.1;
+        let start_line = self.visual_start.1;
+        self.visual_marks = Some((start_line.min(cursor_line), start_line.max(cursor_line)));
+    }
+
+    pub(crate) fn parse_address<'a>(&self, text: &'a str) -> Result<(Option<usize>, &'a str), String> {
+        let tab = &self.tabs[self.active_tab];
+        let current = tab.cursor_position.1 + 1;
+        let last = tab.content.len();
+        let digits = |text: &'a str| {
+            let end = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+            (&text[..end], &text[end..])
+        };
+
+        let (mut line, mut rest) = if let Some(rest) = text.strip_prefix('.') {
+            (Some(current), rest)
+        } else if let Some(rest) = text.strip_prefix('$') {
+            (Some(last), rest)
+        } else if let Some(rest) = text.strip_prefix("'<") {
+            let (top, _) = self.visual_marks.ok_or("No visual selection")?;
+            (Some(top + 1), rest)
+        } else if let Some(rest) = text.strip_prefix("'>") {
+            let (_, bottom) = self.visual_marks.ok_or("No visual selection")?;
+            (Some(bottom + 1), rest)
+        } else {
+            let (number, rest) = digits(text);
+            match number.parse::<usize>() {
+                Ok(n) => (Some(n), rest),
+                Err(_) => (None, text),
+            }
+        };
+
+        while let Some(sign) = rest.chars().next().filter(|c| *c == '+' || *c == '-') {
+            let (number, remaining) = digits(&rest[1..]);
+            let offset = if number.is_empty() { 1 } else { number.parse::<usize>().map_err(|_| format!("Invalid offset: {}", number))? };
+            let base = line.unwrap_or(current);
+            line = Some(if sign == '+' {
+                base + offset
+            } else {
+                base.checked_sub(offset).ok_or("Invalid range")?
+            });
+            rest = remaining;
+        }
+
+        if let Some(line) = line {
+            if line > last {
+                return Err(format!("Line {} is past the end of the buffer", line));
+            }
+        }
+        Ok((line, rest))
+    }
+
+    pub(crate) fn parse_range<'a>(&self, text: &'a str) -> Result<(Option<LineRange>, &'a str), String> {
+        if let Some(rest) = text.strip_prefix('%') {
+            return Ok((Some((1, self.tabs[self.active_tab].content.len())), rest));
+        }
+        let (start, rest) = self.parse_address(text)?;
+        let Some(start) = start else {
+            return Ok((None, text));
+        };
+        let Some(rest) = rest.strip_prefix(',') else {
+            return Ok((Some((start, start)), rest));
+        };
+        let (end, rest) = self.parse_address(rest)?;
+        let end = end.ok_or("Missing end of range")?;
+        if start > end {
+            return Err("Backwards range".to_string());
+        }
+        Ok((Some((start, end)), rest))
+    }
+
+    pub(crate) fn line_command(&mut self, command: &str) -> Option<Result<(), String>> {
+        let (range, rest) = match self.parse_range(command) {
+            Ok(parsed) => parsed,
+            Err(e) => return Some(Err(e)),
+        };
+        let name_end = rest.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(rest.len());
+        if matches!(&rest[..name_end], "s" | "substitute") {
+            return Some(self.substitute(range, &rest[name_end..]));
+        }
+        if matches!(&rest[..name_end], "r" | "read") {
+            return Some(self.read_into_buffer(range, rest[name_end..].trim()));
+        }
+        if matches!(&rest[..name_end], "ret" | "retab") {
+            return Some(self.retab(range, &rest[name_end..]));
+        }
+        let copy = match &rest[..name_end] {
+            "t" | "co" | "copy" => true,
+            "m" | "mo" | "move" => false,
+            _ => return None,
+        };
+        let current = self.tabs[self.active_tab].cursor_position.1 + 1;
+        let (start, end) = range.unwrap_or((current, current));
+        if start == 0 {
+            return Some(Err("Invalid range".to_string()));
+        }
+        let destination = match self.parse_address(rest[name_end..].trim_start()) {
+            Ok((Some(line), "")) => line,
+            Ok((None, "")) => return Some(Err("Missing destination address".to_string())),
+            Ok((_, trailing)) => return Some(Err(format!("Trailing characters: {}", trailing))),
+            Err(e) => return Some(Err(e)),
+        };
+        if !copy && destination >= start && destination < end {
+            return Some(Err("Cannot move a range of lines into itself".to_string()));
+        }
+        let count = end + 1 - start;
+        if !copy && (destination + 1 == start || destination == end) {
+            self.tabs[self.active_tab].cursor_position = (0, start - 1);
+            self.visual_marks = Some((start - 1, end - 1));
+            self.ensure_cursor_visible();
+            return Some(Ok(()));
+        }
+
+        self.save_state();
+        let tab = &mut self.tabs[self.active_tab];
+        let lines: Vec<String> = tab.content[start - 1..end].to_vec();
+        let moved: Vec<usize> = tab.bookmarks.iter()
+            .filter(|&&line| line >= start - 1 && line < end)
+            .map(|&line| line + 1 - start)
+            .collect();
+        let insert_at = if copy {
+            destination
+        } else {
+            tab.content.drain(start - 1..end);
+            tab.shift_lines(start - 1, count, 0);
+            if destination >= end { destination - count } else { destination }
+        };
+        tab.content.splice(insert_at..insert_at, lines);
+        tab.shift_lines(insert_at, 0, count);
+        if !copy {
+            tab.bookmarks.extend(moved.iter().map(|offset| insert_at + offset));
+            tab.bookmarks.sort_unstable();
+        }
+        tab.cursor_position = (0, insert_at);
+        self.visual_marks = Some((insert_at, insert_at + count - 1));
+        self.ensure_cursor_visible();
+        Some(Ok(()))
+    }
+
+    pub(crate) fn run_shell(&self, command: &str) -> Result<String, String> {
+        let mut shell = if cfg!(windows) { Command::new("cmd") } else { Command::new("sh") };
+        shell.arg(if cfg!(windows) { "/C" } else { "-c" }).arg(command);
+        if let Some(dir) = self.tabs[self.active_tab].current_file.as_ref().and_then(|file| Path::new(file).parent()) {
+            shell.current_dir(dir);
+        }
+        let output = shell.output().map_err(|e| format!("Failed to run {}: {}", command, e))?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let reason = stderr.lines().next().map_or_else(|| output.status.to_string(), str::to_string);
+            return Err(format!("{}: {}", command, reason));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    pub(crate) fn read_into_buffer(&mut self, range: Option<LineRange>, argument: &str) -> Result<(), String> {
+        let text = if let Some(command) = argument.strip_prefix('!') {
+            self.run_shell(command.trim())?
+        } else if argument.is_empty() {
+            return Err("Missing file name".to_string());
+        } else {
+            let path = self.resolve_command_path(argument)?;
+            fs::read_to_string(&path).map_err(|e| format!("Can't read {}: {}", path.display(), e))?
+        };
+        let text = text.strip_prefix('\u{feff}').unwrap_or(&text);
+        let lines: Vec<String> = text.lines().map(String::from).collect();
+        if lines.is_empty() {
+            self.info("Nothing to insert");
+            return Ok(());
+        }
+        let current = self.tabs[self.active_tab].cursor_position.1 + 1;
+        let after = range.map_or(current, |(_, end)| end);
+        self.save_state();
+        let tab = &mut self.tabs[self.active_tab];
+        let at = after.min(tab.content.len());
+        let count = lines.len();
+        tab.content.splice(at..at, lines);
+        tab.shift_lines(at, 0, count);
+        tab.cursor_position = (0, at);
+        self.ensure_cursor_visible();
+        self.adjust_horizontal_scroll();
+        Ok(())
+    }
+
+    fn parse_substitute(&self, args: &str) -> Result<Substitution, String> {
+        let mut chars = args.chars();
+        let delimiter = chars.next()
+            .filter(|c| !c.is_alphanumeric() && !c.is_whitespace() && *c != '\\' && *c != '"')
+            .ok_or("Expected a delimiter after :s")?;
+        let mut parts = Vec::new();
+        let mut part = String::new();
+        let mut escaped = false;
+        for c in chars {
+            if c == delimiter && !escaped && parts.len() < 2 {
+                parts.push(std::mem::take(&mut part));
+                continue;
+            }
+            if escaped {
+                if c != delimiter {
+                    part.push('\\');
+                }
+                part.push(c);
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else {
+                part.push(c);
+            }
+        }
+        if escaped {
+            part.push('\\');
+        }
+        parts.push(part);
+
+        let mut substitution = Substitution {
+            pattern: parts[0].clone(),
+            replacement: parts.get(1).cloned().unwrap_or_default(),
+            global: false,
+            ignore_case: false,
+            confirm: false,
+        };
+        for flag in parts.get(2).map(String::as_str).unwrap_or("").chars() {
+            match flag {
+                'g' => substitution.global = true,
+                'c' => substitution.confirm = true,
+                'i' => substitution.ignore_case = true,
+                'I' => substitution.ignore_case = false,
+                _ => return Err(format!("Unknown flag: {}", flag)),
+            }
+        }
+        if substitution.pattern.is_empty() {
+            if self.search_query.text.is_empty() {
+                return Err("No previous search pattern".to_string());
+            }
+            substitution.pattern = self.search_query.text.clone();
+        }
+        Ok(substitution)
+    }
+
+    pub(crate) fn substitute(&mut self, range: Option<LineRange>, args: &str) -> Result<(), String> {
+        let Substitution { pattern, replacement, global, ignore_case, confirm } = self.parse_substitute(args)?;
+
+        let current = self.tabs[self.active_tab].cursor_position.1 + 1;
+        let (start, end) = range.unwrap_or((current, current));
+        if start == 0 {
+            return Err("Invalid range".to_string());
+        }
+        let search = SearchPattern::literal(&pattern, ignore_case);
+        let found = self.tabs[self.active_tab].content[start - 1..end].iter()
+            .any(|line| search.find_at(line, 0).is_some());
+        if !found {
+            return Err(format!("Pattern not found: {}", pattern));
+        }
+
+        if confirm {
+            let mut state = SubstituteConfirm {
+                pattern: search,
+                replacement,
+                global,
+                end: end - 1,
+                current: (0, 0, 0),
+                substitutions: 0,
+                changed_lines: 0,
+                last_changed: None,
+                saved: false,
+            };
+            if self.find_substitute_match(&mut state, start - 1, 0) {
+                self.substitute_confirm = Some(state);
+            }
+            return Ok(());
+        }
+
+        self.save_state();
+        let (substitutions, lines) = self.tabs[self.active_tab].substitute(start - 1, end - 1, &search, &replacement, global);
+        self.ensure_cursor_visible();
+        self.info(substitution_summary(substitutions, lines));
+        Ok(())
+    }
+
+    pub(crate) fn search_all_tabs(&mut self, pattern: &str) -> Result<(), String> {
+        let pattern = if pattern.is_empty() { self.search_query.text.clone() } else { pattern.to_string() };
+        if pattern.is_empty() {
+            return Err("No previous search pattern".to_string());
+        }
+        let limit = self.settings.tab_search_limit;
+        let mut items = Vec::new();
+        let mut locations = Vec::new();
+        let mut truncated = Vec::new();
+        let mut tabs = 0;
+        let mut total = 0;
+        for (tab_index, tab) in self.tabs.iter().enumerate() {
+            let mut found = tab.find_limited(&pattern, limit.saturating_add(1));
+            if found.len() > limit {
+                found.truncate(limit);
+                truncated.push(Self::tab_title(tab, tab_index));
+            }
+            if found.is_empty() {
+                continue;
+            }
+            let title = Self::tab_title(tab, tab_index);
+            tabs += 1;
+            total += found.len();
+            found.dedup_by_key(|&mut (line, _)| line);
+            for (line, _) in found {
+                items.push(format!("{}:{}: {}", title, line + 1, tab.content[line].trim()));
+                locations.push(PickerLocation::Tab(tab_index, line));
+            }
+        }
+        if items.is_empty() {
+            return Err(format!("Pattern not found: {}", pattern));
+        }
+        let mut title = format!("Tab search: {} match{} in {} tab{}", total, if total == 1 { "" } else { "es" }, tabs, if tabs == 1 { "" } else { "s" });
+        if !truncated.is_empty() {
+            title.push_str(" (truncated)");
+            self.warn(format!("Stopped after {} matches in {}", limit, truncated.join(", ")));
+        }
+        self.search_query.clear();
+        self.search_query.insert_str(&pattern);
+        self.search_highlight = true;
+        let mut picker = Picker::new(PickerKind::TabSearch, &title, items);
+        picker.locations = locations;
+        self.picker = Some(picker);
+        self.mode = Mode::Picker;
+        Ok(())
+    }
+
+    pub(crate) fn goto_tab_search_match(&mut self, location: PickerLocation) -> io::Result<()> {
+        let PickerLocation::Tab(_, line) = location else {
+            return self.goto_picker_location(location);
+        };
+        self.goto_picker_location(location)?;
+        self.search_results = self.tabs[self.active_tab].find(&self.search_query.text);
+        if let Some(index) = self.search_results.iter().position(|&(result_line, _)| result_line == line) {
+            self.current_search_index = index;
+            let (line, col) = self.search_results[index];
+            self.tabs[self.active_tab].set_cursor(col, line);
+            self.ensure_cursor_visible();
+            self.adjust_horizontal_scroll();
+        }
+        Ok(())
+    }
+
+    pub(crate) fn replace_in_all_tabs(&mut self, args: &str, preview: bool) -> Result<(), String> {
+        let Substitution { pattern, replacement, global, ignore_case, confirm } = self.parse_substitute(args)?;
+        if confirm {
+            return Err("The c flag is not supported across tabs".to_string());
+        }
+        let search = SearchPattern::literal(&pattern, ignore_case);
+        let matches = |line: &str| if global { search.matches(line).count() } else { usize::from(search.find_at(line, 0).is_some()) };
+
+        if preview {
+            let mut items = Vec::new();
+            let mut locations = Vec::new();
+            let mut tabs = 0;
+            let mut total = 0;
+            for (tab_index, tab) in self.tabs.iter().enumerate() {
+                let title = Self::tab_title(tab, tab_index);
+                let before = items.len();
+                for (line, text) in tab.content.iter().enumerate() {
+                    let count = matches(text);
+                    if count > 0 {
+                        total += count;
+                        let note = if tab.read_only { " (read-only)" } else { "" };
+                        items.push(format!("{}:{}{}: {}", title, line + 1, note, text.trim()));
+                        locations.push(PickerLocation::Tab(tab_index, line));
+                    }
+                }
+                tabs += usize::from(items.len() > before);
+            }
+            if items.is_empty() {
+                return Err(format!("Pattern not found: {}", pattern));
+            }
+            let title = format!("Replace preview: {} match{} in {} tab{}", total, if total == 1 { "" } else { "es" }, tabs, if tabs == 1 { "" } else { "s" });
+            let mut picker = Picker::new(PickerKind::Matches, &title, items);
+            picker.locations = locations;
+            self.picker = Some(picker);
+            self.mode = Mode::Picker;
+            return Ok(());
+        }
+
+        let mut counts = Vec::new();
+        let mut total = 0;
+        let mut skipped = 0;
+        for (tab_index, tab) in self.tabs.iter_mut().enumerate() {
+            if !tab.content.iter().any(|line| matches(line) > 0) {
+                continue;
+            }
+            if tab.read_only {
+                skipped += 1;
+                continue;
+            }
+            tab.push_undo();
+            let end = tab.content.len() - 1;
+            let (substitutions, _) = tab.substitute(0, end, &search, &replacement, global);
+            counts.push(format!("{}: {}", Self::tab_title(tab, tab_index), substitutions));
+            total += substitutions;
+        }
+        self.trim_all_undo_history();
+        if counts.is_empty() && skipped == 0 {
+            return Err(format!("Pattern not found: {}", pattern));
+        }
+        self.update_current_tab_info();
+        self.ensure_cursor_visible();
+        let mut message = format!(
+            "{} substitution{} in {} tab{}",
+            total,
+            if total == 1 { "" } else { "s" },
+            counts.len(),
+            if counts.len() == 1 { "" } else { "s" },
+        );
+        if !counts.is_empty() {
+            message.push_str(&format!(" ({})", counts.join(", ")));
+        }
+        if skipped > 0 {
+            message.push_str(&format!("; skipped {} read-only tab{}", skipped, if skipped == 1 { "" } else { "s" }));
+        }
+        self.info(message);
+        Ok(())
+    }
+
+    fn find_substitute_match(&mut self, state: &mut SubstituteConfirm, line: usize, from: usize) -> bool {
+        let tab = &self.tabs[self.active_tab];
+        let (mut line, mut from) = (line, from);
+        while line <= state.end.min(tab.content.len() - 1) {
+            if let Some(found) = state.pattern.find_at(&tab.content[line], from) {
+                let (start, end) = (found.start, found.end);
+                state.current = (line, start, end);
+                self.tabs[self.active_tab].cursor_position = (start, line);
+                self.ensure_cursor_visible();
+                self.adjust_horizontal_scroll();
+                return true;
+            }
+            line += 1;
+            from = 0;
+        }
+        false
+    }
+
+    fn replace_substitute_match(&mut self, state: &mut SubstituteConfirm) -> usize {
+        if !state.saved {
+            self.save_state();
+            state.saved = true;
+        }
+        let (line, start, end) = state.current;
+        self.tabs[self.active_tab].content[line].replace_range(start..end, &state.replacement);
+        state.substitutions += 1;
+        if state.last_changed != Some(line) {
+            state.changed_lines += 1;
+            state.last_changed = Some(line);
+        }
+        start + state.replacement.len()
+    }
+
+    fn advance_substitute(&mut self, state: &mut SubstituteConfirm, position: usize) -> bool {
+        let line = state.current.0;
+        if state.global {
+            self.find_substitute_match(state, line, position)
+        } else {
+            self.find_substitute_match(state, line + 1, 0)
+        }
+    }
+
+    pub(crate) fn handle_substitute_confirm_key(&mut self, key: KeyEvent) {
+        let Some(mut state) = self.substitute_confirm.take() else {
+            return;
+        };
+        let more = match key.code {
+            KeyCode::Char('y') => {
+                let position = self.replace_substitute_match(&mut state);
+                self.advance_substitute(&mut state, position)
+            }
+            KeyCode::Char('n') => {
+                let position = state.current.2;
+                self.advance_substitute(&mut state, position)
+            }
+            KeyCode::Char('a') => {
+                loop {
+                    let position = self.replace_substitute_match(&mut state);
+                    if !self.advance_substitute(&mut state, position) {
+                        break;
+                    }
+                }
+                false
+            }
+            KeyCode::Char('l') => {
+                self.replace_substitute_match(&mut state);
+                false
+            }
+            KeyCode::Char('q') | KeyCode::Esc => false,
+            _ => true,
+        };
+        if more {
+            self.substitute_confirm = Some(state);
+            return;
+        }
+        let tab = &mut self.tabs[self.active_tab];
+        let (x, y) = tab.cursor_position;
+        tab.set_cursor(x, y);
+        self.info(substitution_summary(state.substitutions, state.changed_lines));
+    }
+
+    pub(crate) fn execute_command(&mut self) -> io::Result<bool> {
+        let command = self.command_buffer.text.clone();
+        self.mode = Mode::Normal;
+        self.command_buffer.clear();
+
+        match command.as_str() {
+            "q" => {
+                if self.tabs[self.active_tab].is_modified() {
+                    let name = self.tabs[self.active_tab].current_file.as_ref()
+                        .map(|file| self.display_path(file))
+                        .unwrap_or_else(|| "Untitled".to_string());
+                    self.confirm(PromptAction::Quit, &format!("Save changes to {}?", name), "Save", "Discard");
+                    Ok(false)
+                } else {
+                    Ok(self.quit_tab())
+                }
+            }
+            "q!" => Ok(self.quit_tab()),
+            "w" if self.tabs[self.active_tab].current_file.is_none() => {
+                self.save_as(false);
+                Ok(false)
+            }
+            "w" => {
+                let file = self.tabs[self.active_tab].current_file.clone().unwrap_or_default();
+                self.save_as_path(Path::new(&file), false);
+                Ok(false)
+            }
+            "w!" if self.tabs[self.active_tab].current_file.is_none() => {
+                self.save_as(false);
+                Ok(false)
+            }
+            cmd if cmd == "w!" || cmd.starts_with("w! ") => {
+                let path = match cmd.split_whitespace().nth(1) {
+                    Some(filename) => self.resolve_command_path(filename),
+                    None => Ok(PathBuf::from(self.tabs[self.active_tab].current_file.clone().unwrap_or_default())),
+                };
+                let result = path.and_then(|path| {
+                    self.create_parent_dirs(&path)?;
+                    self.save_file(Some(&path)).map_err(|e| format!("Save failed: {}", e))
+                });
+                if let Err(e) = result {
+                    self.error(e);
+                }
+                Ok(false)
+            }
+            cmd if cmd.starts_with("w ") => {
+                let filename = cmd.split_whitespace().nth(1).unwrap_or_default();
+                match self.resolve_command_path(filename) {
+                    Ok(path) => {
+                        self.save_as_path(&path, false);
+                    }
+                    Err(e) => self.error(format!("Save failed: {}", e)),
+                }
+                Ok(false)
+            }
+            "wq" if self.tabs[self.active_tab].current_file.is_none() => {
+                self.save_as(true);
+                Ok(false)
+            }
+            "wq" => {
+                let file = self.tabs[self.active_tab].current_file.clone().unwrap_or_default();
+                Ok(self.save_as_path(Path::new(&file), true))
+            }
+
+            "actions" => {
+                self.show_actions();
+                Ok(false)
+            }
+            "messages" | "mes" => {
+                self.show_messages();
+                Ok(false)
+            }
+            cmd if cmd.starts_with('!') => {
+                if let Err(e) = self.run_shell_output(&cmd[1..]) {
+                    self.error(e);
+                }
+                Ok(false)
+            }
+            "oldfiles" | "ol" => {
+                self.open_oldfiles_picker();
+                Ok(false)
+            }
+            cmd if matches!(cmd.split_whitespace().next(), Some("bufdo" | "replaceall" | "replaceall?")) => {
+                let (name, args) = cmd.split_once(char::is_whitespace).unwrap_or((cmd, ""));
+                let args = args.trim_start();
+                let result = if name == "bufdo" {
+                    let command = args.strip_prefix('%').unwrap_or(args);
+                    let name_end = command.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(command.len());
+                    if matches!(&command[..name_end], "s" | "substitute") {
+                        self.replace_in_all_tabs(&command[name_end..], false)
+                    } else {
+                        Err("Only :bufdo s/pattern/replacement/[flags] is supported".to_string())
+                    }
+                } else {
+                    self.replace_in_all_tabs(args, name == "replaceall?")
+                };
+                if let Err(e) = result {
+                    self.error(e);
+                }
+                Ok(false)
+            }
+            cmd if matches!(cmd.split_whitespace().next(), Some("bsearch" | "bs")) => {
+                let pattern = cmd.split_once(char::is_whitespace).map_or("", |(_, pattern)| pattern.trim_start());
+                if let Err(e) = self.search_all_tabs(pattern) {
+                    self.error(e);
+                }
+                Ok(false)
+            }
+            "bookmarks" => {
+                self.open_bookmarks_picker();
+                Ok(false)
+            }
+            "copy_path" | "copy_relative_path" | "copy_location" => {
+                self.copy_path(&command);
+                Ok(false)
+            }
+            cmd if matches!(cmd.split_whitespace().next(), Some("calc")) => {
+                self.calc_command(cmd["calc".len()..].trim());
+                Ok(false)
+            }
+            "tablast" | "tabl" => {
+                self.switch_to_tab(self.tabs.len() - 1);
+                Ok(false)
+            }
+            cmd if matches!(cmd.split_whitespace().next(), Some("tab")) => {
+                self.tab_command(cmd["tab".len()..].trim());
+                Ok(false)
+            }
+            cmd if matches!(cmd.split_whitespace().next(), Some("tabnew" | "tabedit" | "tabe" | "badd" | "bad")) => {
+                let (name, argument) = cmd.split_once(' ').map_or((cmd, ""), |(name, argument)| (name, argument.trim()));
+                self.tab_new_command(argument, name.starts_with("bad"))?;
+                Ok(false)
+            }
+            cmd if cmd.split_whitespace().next() == Some("private") => {
+                let argument = cmd.split_once(' ').map_or("", |(_, argument)| argument.trim());
+                self.private_command(argument);
+                Ok(false)
+            }
+            cmd if matches!(cmd.split_whitespace().next(), Some("tabclose" | "tabc")) => {
+                let argument = cmd.split_once(' ').map_or("", |(_, argument)| argument.trim());
+                self.close_tab_command(argument);
+                Ok(false)
+            }
+            "tabonly" | "tabo" | "tabonly!" | "tabo!" => {
+                self.close_other_tabs(command.ends_with('!'));
+                Ok(false)
+            }
+            "nohlsearch" | "nohl" | "noh" => {
+                self.search_highlight = false;
+                Ok(false)
+            }
+            "undolist" | "undol" => {
+                self.show_undo_list();
+                Ok(false)
+            }
+            "clearundo" | "clearundo!" => {
+                self.clear_undo_command(command.ends_with('!'));
+                Ok(false)
+            }
+            "closedtabs" => {
+                self.open_closed_tabs_picker();
+                Ok(false)
+            }
+            "ascii" | "as" => {
+                self.show_line_characters();
+                Ok(false)
+            }
+            "tasks" => {
+                self.open_tasks();
+                Ok(false)
+            }
+            "digraphs" | "dig" => {
+                self.show_digraphs();
+                Ok(false)
+            }
+            "outline" => {
+                self.toggle_outline();
+                Ok(false)
+            }
+            "terminal" | "term" => {
+                self.open_terminal();
+                Ok(false)
+            }
+            "terminal!" | "term!" => {
+                self.close_terminal();
+                Ok(false)
+            }
+            "blame" => {
+                self.toggle_blame();
+                Ok(false)
+            }
+            cmd if matches!(cmd.split_whitespace().next(), Some("todos" | "todos!")) => {
+                let (name, argument) = cmd.split_once(' ').unwrap_or((cmd, ""));
+                self.todos_command(name.ends_with('!'), argument.trim());
+                Ok(false)
+            }
+            "config reload" => {
+                if let Err(e) = self.reload_config() {
+                    self.error(format!("Failed to reload configuration: {}", e));
+                }
+                Ok(false)
+            }
+            "mkconfig" => {
+                match self.write_config() {
+                    Ok(dir) => self.info(format!("Wrote settings.toml, config.toml and colors.json to {}", dir.display())),
+                    Err(e) => self.error(format!("Failed to write configuration: {}", e)),
+                }
+                Ok(false)
+            }
+            "colorscheme" | "colo" => {
+                let names = ColorConfig::preset_names().join(", ");
+                self.info(format!("Color schemes: {} (current: {})", names, self.color_config.preset));
+                Ok(false)
+            }
+            cmd if matches!(cmd.split_once(' '), Some(("colorscheme" | "colo" | "colorscheme!" | "colo!", _))) => {
+                let (name, preset) = cmd.split_once(' ').unwrap_or((cmd, ""));
+                let persist = name.ends_with('!');
+                match self.set_colorscheme(preset.trim(), persist) {
+                    Ok(()) if persist => self.info(format!("Saved color scheme {} to colors.json", preset.trim())),
+                    Ok(()) => self.info(format!("Color scheme: {}", preset.trim())),
+                    Err(e) => self.error(e),
+                }
+                Ok(false)
+            }
+            "syntax" | "syn" => {
+                self.info(format!("Syntax: {}", self.tabs[self.active_tab].syntax));
+                Ok(false)
+            }
+            cmd if matches!(cmd.split_once(' '), Some(("syntax" | "syn", _))) => {
+                let name = cmd.split_once(' ').map_or("", |(_, name)| name.trim());
+                self.set_command(&format!("syntax={}", name.replace(' ', "\\ ")), false);
+                Ok(false)
+            }
+            cmd if matches!(cmd.split_whitespace().next(), Some("set" | "se" | "setlocal" | "setl")) => {
+                let (name, arguments) = cmd.split_once(' ').unwrap_or((cmd, ""));
+                self.set_command(arguments.trim(), name.starts_with("setl"));
+                Ok(false)
+            }
+            cmd if cmd.starts_with("e ") => {
+                let mut line = None;
+                for arg in cmd.split_whitespace().skip(1) {
+                    if let Some(n) = Self::parse_line_argument(arg) {
+                        line = Some(n);
+                    } else {
+                        let path = match self.resolve_command_path(arg) {
+                            Ok(path) => path,
+                            Err(e) => {
+                                self.error(e);
+                                break;
+                            }
+                        };
+                        self.open_file(&path)?;
+                        if let Some(line) = line.take() {
+                            self.goto_location(line, None);
+                        }
+                    }
+                }
+                Ok(false)
+            }
+            cmd => {
+                match self.line_command(cmd) {
+                    Some(Ok(())) => {}
+                    Some(Err(e)) => self.error(e),
+                    None => self.report(PhantomError::Command { input: command, message: "Unknown command".to_string() }),
+                }
+                Ok(false)
+            }                
+        }
+    }
+
+    pub(crate) fn copy_path(&mut self, command: &str) {
+        let tab = &self.tabs[self.active_tab];
+        let Some(file) = &tab.current_file else {
+            self.error("No file name");
+            return;
+        };
+        let path = Self::absolute_path(Path::new(file));
+        let relative = self.project_root.as_ref()
+            .and_then(|root| path.strip_prefix(root).ok())
+            .filter(|relative| !relative.as_os_str().is_empty())
+            .unwrap_or(&path);
+        let text = match command {
+            "copy_path" => path.to_string_lossy().into_owned(),
+            "copy_relative_path" => relative.to_string_lossy().into_owned(),
+            _ => {
+                let (x, y) = tab.cursor_position;
+                let column = tab.content.get(y).map_or(0, |line| Self::byte_to_char(line, x));
+                format!("{}:{}:{}", relative.display(), y + 1, column + 1)
+            }
+        };
+        if self.set_clipboard(text.clone()) {
+            self.info(format!("Copied {}", text));
+        }
+    }
+
+    pub(crate) fn split_file_location(arg: &str) -> Option<(PathBuf, usize, Option<usize>)> {
+        let (rest, last) = arg.rsplit_once(':')?;
+        let last = last.parse::<usize>().ok()?;
+        if let Some((file, line)) = rest.rsplit_once(':') {
+            if let Ok(line) = line.parse::<usize>() {
+                if !file.is_empty() && Path::new(file).is_file() {
+                    return Some((PathBuf::from(file), line, Some(last)));
+                }
+            }
+        }
+        if !rest.is_empty() && Path::new(rest).is_file() {
+            return Some((PathBuf::from(rest), last, None));
+        }
+        None
+    }
+
+    pub fn parse_line_argument(arg: &str) -> Option<usize> {
+        let line = arg.strip_prefix('+')?;
+        if line.is_empty() {
+            Some(usize::MAX)
+        } else {
+            line.parse().ok()
+        }
+    }
+}
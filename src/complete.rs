@@ -0,0 +1,228 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::editor::Editor;
+
+type CommandCompleter = fn(&Editor, &str) -> (usize, Vec<String>);
+
+const COMMAND_COMPLETERS: &[(&[&str], CommandCompleter)] = &[
+    (&["colorscheme", "colo", "colorscheme!", "colo!"], Editor::colorscheme_completions),
+    (&["set", "se", "setlocal", "setl"], Editor::option_completions),
+    (&["syntax", "syn"], Editor::syntax_completions),
+];
+
+pub(crate) struct Completion {
+    pub(crate) items: Vec<String>,
+    pub(crate) selected: usize,
+    pub(crate) line: usize,
+    pub(crate) start: usize,
+}
+
+pub(crate) fn common_prefix(words: &[String]) -> String {
+    let Some(first) = words.first() else {
+        return String::new();
+    };
+    words.iter().fold(first.clone(), |common, word| {
+        common.chars().zip(word.chars()).take_while(|(a, b)| a == b).map(|(a, _)| a).collect()
+    })
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+    rows[0] = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        rows[i][0] = i;
+        for j in 1..=b.len() {
+            let substitution = rows[i - 1][j - 1] + usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = substitution.min(rows[i - 1][j] + 1).min(rows[i][j - 1] + 1);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = distance;
+        }
+    }
+    rows[a.len()][b.len()]
+}
+
+pub(crate) fn nearest<'a>(word: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let word = word.to_lowercase();
+    let limit = (word.chars().count() / 3).max(1);
+    candidates.into_iter()
+        .map(|candidate| (edit_distance(&word, &candidate.to_lowercase()), candidate))
+        .filter(|&(distance, _)| distance <= limit)
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, candidate)| candidate)
+}
+
+pub(crate) fn prefix_completions<'a>(word: &str, candidates: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let lowered = word.to_lowercase();
+    let mut completions: Vec<String> = candidates.into_iter()
+        .filter(|candidate| candidate.to_lowercase().starts_with(&lowered))
+        .map(String::from)
+        .collect();
+    completions.sort();
+    completions.dedup();
+    completions
+}
+
+pub(crate) fn path_completions(text: &str, base: &Path) -> Vec<String> {
+    let (dir, prefix) = match text.rfind('/') {
+        Some(index) => (&text[..=index], &text[index + 1..]),
+        None => ("", text),
+    };
+    let Ok(entries) = fs::read_dir(base.join(dir)) else {
+        return Vec::new();
+    };
+    let mut completions: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if !name.starts_with(prefix) || (name.starts_with('.') && !prefix.starts_with('.')) {
+                return None;
+            }
+            let suffix = if entry.path().is_dir() { "/" } else { "" };
+            Some(format!("{}{}{}", dir, name, suffix))
+        })
+        .collect();
+    completions.sort();
+    completions
+}
+
+fn is_path_char(c: char) -> bool {
+    !c.is_whitespace() && !matches!(c, '"' | '\'' | '`' | '(' | ')' | '[' | ']' | '{' | '}' | '<' | '>' | ',' | ';' | '=')
+}
+
+impl Editor {
+    fn path_token(&self) -> (usize, &str) {
+        let tab = &self.tabs[self.active_tab];
+        let line = &tab.content[tab.cursor_position.1];
+        let cursor = tab.cursor_position.0.min(line.len());
+        let start = line[..cursor].rfind(|c: char| !is_path_char(c))
+            .map_or(0, |index| index + line[index..].chars().next().map_or(1, char::len_utf8));
+        (start, &line[start..cursor])
+    }
+
+    fn completion_base(&self) -> PathBuf {
+        self.tabs[self.active_tab].current_file.as_deref()
+            .and_then(|file| Path::new(file).parent())
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .map_or_else(|| PathBuf::from("."), Path::to_path_buf)
+    }
+
+    fn query_path_completions(&mut self) -> bool {
+        let (start, token) = self.path_token();
+        let items = path_completions(token, &self.completion_base());
+        if items.is_empty() {
+            self.completion = None;
+            return false;
+        }
+        let line = self.tabs[self.active_tab].cursor_position.1;
+        self.completion = Some(Completion { items, selected: 0, line, start });
+        true
+    }
+
+    pub(crate) fn complete_path(&mut self) {
+        if !self.query_path_completions() {
+            let (_, token) = self.path_token();
+            let message = format!("No files match {:?}", token);
+            self.info(message);
+        }
+    }
+
+    fn accept_completion(&mut self) {
+        let Some(completion) = self.completion.take() else {
+            return;
+        };
+        let item = &completion.items[completion.selected];
+        self.save_state();
+        let tab = &mut self.tabs[self.active_tab];
+        let cursor = tab.cursor_position.0.min(tab.content[completion.line].len());
+        tab.content[completion.line].replace_range(completion.start..cursor, item);
+        tab.cursor_position.0 = completion.start + item.len();
+        if item.ends_with('/') {
+            self.query_path_completions();
+        }
+    }
+
+    pub(crate) fn handle_completion_key(&mut self, key: KeyEvent) -> bool {
+        let Some(completion) = &mut self.completion else {
+            return false;
+        };
+        let count = completion.items.len();
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Down | KeyCode::Tab => completion.selected = (completion.selected + 1) % count,
+            KeyCode::Char('n') if ctrl => completion.selected = (completion.selected + 1) % count,
+            KeyCode::Up | KeyCode::BackTab => completion.selected = (completion.selected + count - 1) % count,
+            KeyCode::Char('p') if ctrl => completion.selected = (completion.selected + count - 1) % count,
+            KeyCode::Enter => self.accept_completion(),
+            KeyCode::Esc => self.completion = None,
+            KeyCode::Char('e') if ctrl => self.completion = None,
+            KeyCode::Char(c) if !ctrl && !key.modifiers.contains(KeyModifiers::ALT) => {
+                self.insert_char(c);
+                if is_path_char(c) {
+                    self.query_path_completions();
+                } else {
+                    self.completion = None;
+                }
+            }
+            KeyCode::Backspace => {
+                let (line, start) = (completion.line, completion.start);
+                self.backspace();
+                let (x, y) = self.tabs[self.active_tab].cursor_position;
+                if y == line && x > start {
+                    self.query_path_completions();
+                } else {
+                    self.completion = None;
+                }
+            }
+            _ => {
+                self.completion = None;
+                return false;
+            }
+        }
+        true
+    }
+
+    fn command_argument_completions(&self) -> Option<(usize, Vec<String>)> {
+        let text = &self.command_buffer.text;
+        if self.command_buffer.cursor != self.command_buffer.len() {
+            return None;
+        }
+        let (name, arguments) = text.split_once(' ')?;
+        let (_, completer) = COMMAND_COMPLETERS.iter().find(|(names, _)| names.contains(&name))?;
+        let (offset, completions) = completer(self, arguments);
+        Some((name.len() + 1 + offset, completions))
+    }
+
+    pub(crate) fn complete_command(&mut self) {
+        if !self.command_completions.is_empty() {
+            self.command_completion_index = (self.command_completion_index + 1) % self.command_completions.len();
+            let (start, completion) = (self.command_completion_start, self.command_completions[self.command_completion_index].clone());
+            self.replace_command_word(start, &completion);
+            return;
+        }
+        let Some((start, completions)) = self.command_argument_completions() else {
+            return;
+        };
+        let common = common_prefix(&completions);
+        let typed = self.command_buffer.text.len() - start;
+        if completions.len() > 1 && common.len() <= typed {
+            self.replace_command_word(start, &completions[0]);
+            self.command_completions = completions;
+            self.command_completion_index = 0;
+            self.command_completion_start = start;
+        } else if !common.is_empty() {
+            self.replace_command_word(start, &common);
+        }
+    }
+
+    fn replace_command_word(&mut self, start: usize, word: &str) {
+        self.command_buffer.text.truncate(start);
+        self.command_buffer.cursor = self.command_buffer.len();
+        self.command_buffer.insert_str(word);
+    }
+}
@@ -0,0 +1,1252 @@
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use syntect::parsing::{SyntaxDefinition, SyntaxSet};
+use toml_edit::{DocumentMut, Item, TableLike, Value};
+use tui::style::{Color, Modifier, Style};
+
+use crate::color::{is_default_color, light_background, parse_style, ColorMode, StyleFields, StyleSpec};
+use crate::complete::{nearest, prefix_completions};
+use crate::editor::Editor;
+use crate::error::PhantomError;
+use crate::log::LogLevel;
+use crate::options::clamp_settings;
+use crate::outline::OutlineRule;
+use crate::plugin::PluginConfig;
+
+#[derive(Deserialize, Serialize, Clone)]
+pub(crate) struct ColorConfig {
+    pub(crate) background: String,
+    pub(crate) foreground: String,
+    pub(crate) cursor: String,
+    pub(crate) selection: String,
+    pub(crate) comment: String,
+    pub(crate) keyword: String,
+    pub(crate) string: String,
+    pub(crate) function: String,
+    pub(crate) number: String,
+    pub(crate) minimap_highlight: String,
+    pub(crate) minimap_background: String,
+    pub(crate) minimap_content: String,
+    pub(crate) minimap_border: String,
+    pub(crate) tab_active: String,
+    pub(crate) tab_inactive: String,
+    pub(crate) tab_background: String,
+    pub(crate) file_selector_background: String,
+    pub(crate) file_selector_foreground: String,
+    pub(crate) file_selector_highlight: String,
+    pub(crate) file_selector_border: String,
+    pub(crate) message_info: String,
+    pub(crate) message_warn: String,
+    pub(crate) message_error: String,
+    pub(crate) bookmark: String,
+    pub(crate) fold: String,
+    pub(crate) cursor_line: String,
+    pub(crate) whitespace: String,
+    pub(crate) start_screen_title: String,
+    pub(crate) start_screen_selection: String,
+    pub(crate) pager_background: String,
+    pub(crate) pager_border: String,
+    pub(crate) indent_guide: String,
+    pub(crate) indent_guide_active: String,
+    pub(crate) file_selector_new: String,
+    pub(crate) search_match: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) color_mode: Option<ColorMode>,
+    pub(crate) preset: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) syntax_theme: Option<String>,
+    #[serde(skip)]
+    pub(crate) detected_mode: ColorMode,
+}
+
+#[derive(Serialize, Clone)]
+pub(crate) struct Keybindings {
+    pub(crate) normal_mode: HashMap<String, String>,
+    pub(crate) insert_mode: HashMap<String, String>,
+    pub(crate) visual_mode: HashMap<String, String>,
+    pub(crate) command_mode: HashMap<String, String>,
+    pub(crate) file_select_mode: HashMap<String, String>,
+    pub(crate) search_mode: HashMap<String, String>,
+    pub(crate) tab_mode: HashMap<String, String>,
+    pub(crate) visual_block_mode: HashMap<String, String>,
+    pub(crate) terminal_mode: HashMap<String, String>,
+    pub(crate) git_rebase_todo: HashMap<String, String>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum FoldMethod {
+    Indent,
+    Brace,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum BlameDisplay {
+    Column,
+    Status,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum MouseAction {
+    Select,
+    Copy,
+    Paste,
+    None,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum DragAction {
+    Select,
+    Scroll,
+    None,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub(crate) struct MouseSettings {
+    pub(crate) enabled: bool,
+    pub(crate) left_click: MouseAction,
+    pub(crate) right_click: MouseAction,
+    pub(crate) middle_click: MouseAction,
+    pub(crate) drag: DragAction,
+    pub(crate) wheel_lines: usize,
+}
+
+impl Default for MouseSettings {
+    fn default() -> Self {
+        MouseSettings {
+            enabled: true,
+            left_click: MouseAction::Select,
+            right_click: MouseAction::Copy,
+            middle_click: MouseAction::None,
+            drag: DragAction::Select,
+            wheel_lines: 3,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub(crate) struct GitSettings {
+    pub(crate) insert_mode: bool,
+    pub(crate) color_column: usize,
+    pub(crate) wrap: bool,
+}
+
+impl Default for GitSettings {
+    fn default() -> Self {
+        GitSettings { insert_mode: true, color_column: 72, wrap: true }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub(crate) struct Settings {
+    pub(crate) live_word_count: Vec<String>,
+    pub(crate) cua_bindings: bool,
+    pub(crate) auto_theme: bool,
+    pub(crate) force_color: bool,
+    pub(crate) scrolloff: usize,
+    pub(crate) sidescrolloff: usize,
+    pub(crate) number: bool,
+    pub(crate) cursor_line: bool,
+    pub(crate) color_column: usize,
+    pub(crate) list: bool,
+    pub(crate) wrap: bool,
+    pub(crate) hlsearch: bool,
+    pub(crate) indent_guides: bool,
+    pub(crate) minimap_scale: usize,
+    pub(crate) max_fps: usize,
+    pub(crate) long_line_threshold: usize,
+    pub(crate) tab_width: usize,
+    pub(crate) tab_search_limit: usize,
+    pub(crate) expand_tab: bool,
+    pub(crate) detect_indent: bool,
+    pub(crate) modeline: bool,
+    pub(crate) restore_position: bool,
+    pub(crate) file_locks: bool,
+    pub(crate) privileged_write_command: String,
+    pub(crate) private_decrypt_command: String,
+    pub(crate) private_encrypt_command: String,
+    pub(crate) restore_position_exclude: Vec<String>,
+    pub(crate) show_ignored: bool,
+    pub(crate) recent_files: bool,
+    pub(crate) recent_files_exclude: Vec<String>,
+    pub(crate) insert_arrow_breaks_undo: bool,
+    pub(crate) undo_levels: usize,
+    pub(crate) undo_memory_limit: usize,
+    pub(crate) key_timeout_ms: u64,
+    pub(crate) copy_on_select: bool,
+    pub(crate) open_dropped_files: bool,
+    pub(crate) persist_ui_state: bool,
+    pub(crate) show_start_screen: bool,
+    pub(crate) tab_title_format: String,
+    pub(crate) active_tab_title_format: String,
+    pub(crate) debug_height: u16,
+    pub(crate) debug_level: LogLevel,
+    pub(crate) terminal_height: u16,
+    pub(crate) todo_markers: Vec<String>,
+    pub(crate) fold_methods: BTreeMap<String, FoldMethod>,
+    pub(crate) blame_display: BlameDisplay,
+    pub(crate) mouse: MouseSettings,
+    pub(crate) git: GitSettings,
+    pub(crate) plugins: Vec<PluginConfig>,
+    pub(crate) outline_rules: BTreeMap<String, Vec<OutlineRule>>,
+    pub(crate) filetype_detect: BTreeMap<String, String>,
+    pub(crate) digraphs: BTreeMap<String, String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            live_word_count: Vec::new(),
+            cua_bindings: false,
+            auto_theme: true,
+            force_color: false,
+            scrolloff: 3,
+            sidescrolloff: 5,
+            number: false,
+            cursor_line: false,
+            color_column: 0,
+            list: false,
+            wrap: false,
+            hlsearch: true,
+            indent_guides: false,
+            minimap_scale: 2,
+            max_fps: 60,
+            long_line_threshold: 10_000,
+            tab_width: 4,
+            tab_search_limit: 1000,
+            expand_tab: false,
+            detect_indent: true,
+            modeline: false,
+            restore_position: true,
+            file_locks: true,
+            privileged_write_command: String::new(),
+            private_decrypt_command: "gpg --quiet --decrypt %".to_string(),
+            private_encrypt_command: "gpg --quiet --yes --default-recipient-self --encrypt --output %".to_string(),
+            restore_position_exclude: vec![
+                "COMMIT_EDITMSG".to_string(),
+                "MERGE_MSG".to_string(),
+                "TAG_EDITMSG".to_string(),
+                "git-rebase-todo".to_string(),
+            ],
+            show_ignored: false,
+            recent_files: true,
+            recent_files_exclude: vec!["/tmp/*".to_string()],
+            insert_arrow_breaks_undo: true,
+            undo_levels: 100,
+            undo_memory_limit: 16,
+            key_timeout_ms: 800,
+            copy_on_select: cfg!(all(unix, not(target_os = "macos"))),
+            open_dropped_files: true,
+            persist_ui_state: false,
+            show_start_screen: true,
+            tab_title_format: "{index} {name}{modified}{readonly}".to_string(),
+            active_tab_title_format: String::new(),
+            debug_height: 6,
+            debug_level: LogLevel::Debug,
+            terminal_height: 12,
+            todo_markers: vec!["TODO".to_string(), "FIXME".to_string(), "HACK".to_string(), "XXX".to_string()],
+            fold_methods: ["Rust", "C", "C++", "C#", "Java", "JavaScript", "Go", "JSON", "CSS"].iter()
+                .map(|syntax| (syntax.to_string(), FoldMethod::Brace))
+                .collect(),
+            blame_display: BlameDisplay::Column,
+            mouse: MouseSettings::default(),
+            git: GitSettings::default(),
+            plugins: Vec::new(),
+            outline_rules: BTreeMap::new(),
+            filetype_detect: BTreeMap::new(),
+            digraphs: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub(crate) struct StoredPosition {
+    pub(crate) path: String,
+    pub(crate) cursor_position: (usize, usize),
+    pub(crate) scroll_offset: usize,
+    #[serde(default)]
+    pub(crate) changes: Vec<(usize, usize)>,
+}
+
+#[derive(Deserialize, Serialize, Default)]
+pub(crate) struct PositionStore {
+    pub(crate) entries: Vec<StoredPosition>,
+}
+
+impl PositionStore {
+    pub(crate) const MAX_ENTRIES: usize = 2000;
+
+    pub(crate) fn load(path: &Path) -> Self {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn save(&self, path: &Path) -> Result<(), PhantomError> {
+        save_json(path, serde_json::to_string(self))
+    }
+
+    pub(crate) fn get(&self, path: &str) -> Option<&StoredPosition> {
+        self.entries.iter().find(|entry| entry.path == path)
+    }
+
+    pub(crate) fn record(&mut self, position: StoredPosition) {
+        self.entries.retain(|entry| entry.path != position.path);
+        self.entries.insert(0, position);
+        self.entries.truncate(Self::MAX_ENTRIES);
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct LineStore<T> {
+    pub(crate) entries: BTreeMap<String, Vec<T>>,
+}
+
+impl<T> Default for LineStore<T> {
+    fn default() -> Self {
+        LineStore { entries: BTreeMap::new() }
+    }
+}
+
+impl<T: Serialize + DeserializeOwned + Clone + PartialEq> LineStore<T> {
+    pub(crate) fn load(path: &Path) -> Self {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn save(&self, path: &Path) -> Result<(), PhantomError> {
+        save_json(path, serde_json::to_string(self))
+    }
+
+    pub(crate) fn get(&self, path: &str) -> &[T] {
+        self.entries.get(path).map_or(&[], Vec::as_slice)
+    }
+
+    pub(crate) fn set(&mut self, path: String, lines: &[T]) -> bool {
+        if self.get(&path) == lines {
+            return false;
+        }
+        if lines.is_empty() {
+            self.entries.remove(&path);
+        } else {
+            self.entries.insert(path, lines.to_vec());
+        }
+        true
+    }
+}
+
+#[derive(Deserialize, Serialize, Default)]
+pub(crate) struct UiState {
+    pub(crate) sidebar_width: Option<u16>,
+    pub(crate) minimap_width: Option<u16>,
+    pub(crate) show_minimap: Option<bool>,
+    pub(crate) show_debug: Option<bool>,
+    pub(crate) debug_height: Option<u16>,
+    pub(crate) terminal_height: Option<u16>,
+}
+
+impl UiState {
+    pub(crate) fn load(path: &Path) -> Self {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn save(&self, path: &Path) -> Result<(), PhantomError> {
+        save_json(path, serde_json::to_string_pretty(self))
+    }
+}
+
+fn write_creating_dirs(path: &Path, contents: String) -> Result<(), PhantomError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| PhantomError::io(parent, e))?;
+    }
+    fs::write(path, contents).map_err(|e| PhantomError::io(path, e))
+}
+
+fn save_json(path: &Path, json: serde_json::Result<String>) -> Result<(), PhantomError> {
+    let file = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    write_creating_dirs(path, json.map_err(|e| PhantomError::config(&file, e))?)
+}
+
+fn read_if_exists(path: &Path) -> Result<String, PhantomError> {
+    match fs::read_to_string(path) {
+        Ok(text) => Ok(text),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(String::new()),
+        Err(e) => Err(PhantomError::io(path, e)),
+    }
+}
+
+pub(crate) fn merge_toml<T: Serialize>(text: &str, owned: &T) -> Result<String, String> {
+    let mut document: DocumentMut = text.parse().map_err(|e: toml_edit::TomlError| e.to_string())?;
+    let current: toml::Table = toml::from_str(text).map_err(|e| e.to_string())?;
+    let owned = toml::Table::try_from(owned).map_err(|e| e.to_string())?;
+    merge_toml_table(document.as_table_mut(), &current, &owned, false);
+    Ok(document.to_string())
+}
+
+fn merge_toml_table(table: &mut dyn TableLike, current: &toml::Table, owned: &toml::Table, inline: bool) {
+    for (key, value) in owned {
+        let existing = current.get(key);
+        if existing == Some(value) {
+            continue;
+        }
+        if let (Some(item), toml::Value::Table(owned), Some(toml::Value::Table(current))) = (table.get_mut(key), value, existing) {
+            let inline = item.is_inline_table();
+            if let Some(nested) = item.as_table_like_mut() {
+                merge_toml_table(nested, current, owned, inline);
+                continue;
+            }
+        }
+        let Some(mut item) = toml_item(key, value) else {
+            continue;
+        };
+        if inline {
+            item = match item.into_value() {
+                Ok(value) => Item::Value(value),
+                Err(item) => item,
+            };
+        }
+        match (table.get_mut(key), item) {
+            (Some(Item::Value(old)), Item::Value(mut new)) => {
+                *new.decor_mut() = old.decor().clone();
+                *old = new;
+            }
+            (_, mut item) => {
+                if let Some(nested) = item.as_table_mut() {
+                    nested.decor_mut().set_prefix("\n");
+                }
+                table.insert(key, item);
+            }
+        }
+    }
+}
+
+fn toml_item(key: &str, value: &toml::Value) -> Option<Item> {
+    let single = toml::Table::from_iter([(key.to_string(), value.clone())]);
+    let mut document: DocumentMut = toml::to_string(&single).ok()?.parse().ok()?;
+    document.remove(key)
+}
+
+pub(crate) fn update_keybindings_toml(text: &str, keybindings: &Keybindings) -> Result<String, toml_edit::TomlError> {
+    let mut document: DocumentMut = text.parse()?;
+    for (mode, bindings) in keybindings.modes() {
+        if !document.get(mode).is_some_and(Item::is_table_like) {
+            document.insert(mode, toml_edit::table());
+        }
+        let Some(table) = document[mode].as_table_like_mut() else {
+            continue;
+        };
+        let stale: Vec<String> = table.iter()
+            .filter(|(key, item)| !bindings.contains_key(*key) && item.as_str() != Some(""))
+            .map(|(key, _)| key.to_string())
+            .collect();
+        for key in stale {
+            table.remove(&key);
+        }
+        let mut keys: Vec<&String> = bindings.keys().collect();
+        keys.sort();
+        for key in keys {
+            let action = &bindings[key];
+            match table.get_mut(key).and_then(Item::as_value_mut) {
+                Some(current) if current.as_str() == Some(action.as_str()) => {}
+                Some(current) => {
+                    let decor = current.decor().clone();
+                    *current = Value::from(action.as_str());
+                    *current.decor_mut() = decor;
+                }
+                None => {
+                    table.insert(key, toml_edit::value(action.as_str()));
+                }
+            }
+        }
+    }
+    Ok(document.to_string())
+}
+
+#[derive(Deserialize, Serialize, Default)]
+pub(crate) struct RecentFiles {
+    pub(crate) entries: Vec<String>,
+}
+
+impl RecentFiles {
+    pub(crate) const MAX_ENTRIES: usize = 200;
+
+    pub(crate) fn load(path: &Path) -> Self {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn save(&self, path: &Path) -> Result<(), PhantomError> {
+        save_json(path, serde_json::to_string(self))
+    }
+
+    pub(crate) fn record(&mut self, path: String) {
+        self.entries.retain(|entry| *entry != path);
+        self.entries.insert(0, path);
+        self.entries.truncate(Self::MAX_ENTRIES);
+    }
+
+    pub(crate) fn prune(&mut self) -> bool {
+        let before = self.entries.len();
+        self.entries.retain(|entry| Path::new(entry).exists());
+        self.entries.len() != before
+    }
+}
+
+pub(crate) struct ConfigPaths {
+    pub(crate) config_dir: Option<PathBuf>,
+    pub(crate) config_writable: bool,
+    pub(crate) data_dir: Option<PathBuf>,
+}
+
+impl ConfigPaths {
+    pub(crate) fn resolve(config_override: Option<PathBuf>) -> Self {
+        let env_dir = |name: &str| env::var_os(name).filter(|value| !value.is_empty()).map(PathBuf::from);
+        let home = dirs::home_dir();
+
+        let config_candidates = [
+            config_override,
+            env_dir("PHANTOM_CONFIG_DIR"),
+            env_dir("XDG_CONFIG_HOME").map(|dir| dir.join("phantom")),
+            dirs::config_dir().map(|dir| dir.join("phantom")),
+            home.as_ref().map(|dir| dir.join(".config").join("phantom")),
+        ];
+        let mut config_dir = None;
+        let mut config_writable = false;
+        for candidate in config_candidates.into_iter().flatten() {
+            if Self::is_writable(&candidate) {
+                config_dir = Some(candidate);
+                config_writable = true;
+                break;
+            }
+            if candidate.is_dir() {
+                config_dir = Some(candidate);
+                break;
+            }
+        }
+
+        let data_candidates = [
+            env_dir("PHANTOM_DATA_DIR"),
+            env_dir("XDG_DATA_HOME").map(|dir| dir.join("phantom")),
+            dirs::data_dir().map(|dir| dir.join("phantom")),
+            home.as_ref().map(|dir| dir.join(".local").join("share").join("phantom")),
+        ];
+        let data_dir = data_candidates.into_iter().flatten().find(|candidate| Self::is_writable(candidate));
+
+        ConfigPaths {
+            config_dir,
+            config_writable,
+            data_dir,
+        }
+    }
+
+    pub(crate) fn is_writable(dir: &Path) -> bool {
+        if fs::create_dir_all(dir).is_err() {
+            return false;
+        }
+        let probe = dir.join(".phantom-write-test");
+        let writable = fs::write(&probe, b"").is_ok();
+        let _ = fs::remove_file(&probe);
+        writable
+    }
+
+    pub(crate) fn startup_warning(&self) -> Option<String> {
+        match (&self.config_dir, self.config_writable) {
+            (None, _) => Some("No usable config directory; running with built-in defaults".to_string()),
+            (Some(dir), false) => Some(format!("Config directory {} is read-only; defaults will not be written", dir.display())),
+            _ if self.data_dir.is_none() => Some("No writable data directory; cursor positions will not be saved".to_string()),
+            _ => None,
+        }
+    }
+}
+
+const COLOR_PRESETS: [(&str, &str, [&str; 35]); 6] = [
+    ("dark", "base16-ocean.dark", [
+        "#1E1E1E", "#CCCCCC", "#FFFFFF", "#264F78", "#7F848E", "#61AFEF", "#C678DD", "#E5C07B",
+        "#D19A66", "#264F78", "#1E1E1E", "#404040", "#404040", "#61AFEF", "#7F848E", "#252526",
+        "#2C2C2C", "#CCCCCC", "#3A3D41", "#4A4A4A", "#CCCCCC", "#E5C07B", "#E06C75", "#E5C07B",
+        "#2F343F", "#2A2D2E", "#404040", "#61AFEF", "#3A3D41", "#2C2C2C", "#4A4A4A", "#404040", "#7F848E", "#98C379", "#515C6A",
+    ]),
+    ("light", "InspiredGitHub", [
+        "#FAFAFA", "#383A42", "#526FFF", "#D7E3F4", "#A0A1A7", "#A626A4", "#50A14F", "#4078F2",
+        "#986801", "#D0D0D0", "#FAFAFA", "#C0C0C0", "#C0C0C0", "#4078F2", "#A0A1A7", "#EAEAEB",
+        "#F0F0F0", "#383A42", "#D4D4D4", "#C0C0C0", "#383A42", "#986801", "#E45649", "#C18401",
+        "#E5E5E6", "#F0F0F0", "#D0D0D0", "#4078F2", "#D4D4D4", "#F0F0F0", "#C0C0C0", "#D0D0D0", "#A0A1A7", "#50A14F", "#F5E7A3",
+    ]),
+    ("solarized-dark", "Solarized (dark)", [
+        "#002B36", "#839496", "#93A1A1", "#073642", "#586E75", "#859900", "#2AA198", "#268BD2",
+        "#D33682", "#073642", "#002B36", "#586E75", "#586E75", "#268BD2", "#586E75", "#073642",
+        "#073642", "#839496", "#0A4B5C", "#586E75", "#839496", "#B58900", "#DC322F", "#B58900",
+        "#0E3F4D", "#073642", "#35535C", "#268BD2", "#0A4B5C", "#073642", "#586E75", "#35535C", "#586E75", "#859900", "#2B4F3A",
+    ]),
+    ("solarized-light", "Solarized (light)", [
+        "#FDF6E3", "#657B83", "#586E75", "#EEE8D5", "#93A1A1", "#859900", "#2AA198", "#268BD2",
+        "#D33682", "#EEE8D5", "#FDF6E3", "#93A1A1", "#93A1A1", "#268BD2", "#93A1A1", "#EEE8D5",
+        "#EEE8D5", "#657B83", "#DDD6C1", "#93A1A1", "#657B83", "#B58900", "#DC322F", "#B58900",
+        "#E6DFCA", "#EEE8D5", "#C9C5B5", "#268BD2", "#DDD6C1", "#EEE8D5", "#93A1A1", "#C9C5B5", "#93A1A1", "#859900", "#F3DFA2",
+    ]),
+    ("gruvbox", "base16-mocha.dark", [
+        "#282828", "#EBDBB2", "#FBF1C7", "#504945", "#928374", "#FB4934", "#B8BB26", "#FABD2F",
+        "#D3869B", "#504945", "#282828", "#665C54", "#665C54", "#FABD2F", "#928374", "#3C3836",
+        "#32302F", "#EBDBB2", "#504945", "#665C54", "#EBDBB2", "#FABD2F", "#FB4934", "#FE8019",
+        "#3C3836", "#32302F", "#665C54", "#FABD2F", "#504945", "#32302F", "#665C54", "#665C54", "#928374", "#B8BB26", "#7C6F64",
+    ]),
+    ("transparent", "base16-ocean.dark", [
+        "default", "#CCCCCC", "#FFFFFF", "#264F78", "#7F848E", "#61AFEF", "#C678DD", "#E5C07B",
+        "#D19A66", "#264F78", "default", "#404040", "#404040", "#61AFEF", "#7F848E", "default",
+        "default", "#CCCCCC", "#3A3D41", "#4A4A4A", "#CCCCCC", "#E5C07B", "#E06C75", "#E5C07B",
+        "#2F343F", "#2A2D2E", "#404040", "#61AFEF", "#3A3D41", "default", "#4A4A4A", "#404040", "#7F848E", "#98C379", "#515C6A",
+    ]),
+];
+
+impl ColorConfig {
+    pub(crate) const SETTINGS_FIELDS: [&'static str; 3] = ["preset", "color_mode", "syntax_theme"];
+    const HIGHLIGHT_FIELDS: [&'static str; 5] = ["selection", "cursor_line", "file_selector_highlight", "start_screen_selection", "search_match"];
+
+    pub(crate) fn default() -> Self {
+        Self::preset("dark").expect("the dark preset is built in")
+    }
+
+    pub(crate) fn preset_names() -> Vec<&'static str> {
+        COLOR_PRESETS.iter().map(|(name, _, _)| *name).collect()
+    }
+
+    pub(crate) fn preset(name: &str) -> Option<Self> {
+        let (name, _, colors) = COLOR_PRESETS.iter().find(|(preset, _, _)| *preset == name)?;
+        let [background, foreground, cursor, selection, comment, keyword, string, function, number,
+            minimap_highlight, minimap_background, minimap_content, minimap_border, tab_active, tab_inactive,
+            tab_background, file_selector_background, file_selector_foreground, file_selector_highlight,
+            file_selector_border, message_info, message_warn, message_error, bookmark, fold, cursor_line, whitespace,
+            start_screen_title, start_screen_selection, pager_background, pager_border, indent_guide,
+            indent_guide_active, file_selector_new, search_match] = colors.map(String::from);
+        Some(ColorConfig {
+            background,
+            foreground,
+            cursor,
+            selection,
+            comment,
+            keyword,
+            string,
+            function,
+            number,
+            minimap_highlight,
+            minimap_background,
+            minimap_content,
+            minimap_border,
+            tab_active,
+            tab_inactive,
+            tab_background,
+            file_selector_background,
+            file_selector_foreground,
+            file_selector_highlight,
+            file_selector_border,
+            message_info,
+            message_warn,
+            message_error,
+            bookmark,
+            fold,
+            cursor_line,
+            whitespace,
+            start_screen_title,
+            start_screen_selection,
+            pager_background,
+            pager_border,
+            indent_guide,
+            indent_guide_active,
+            file_selector_new,
+            search_match,
+            color_mode: None,
+            preset: name.to_string(),
+            syntax_theme: None,
+            detected_mode: ColorMode::default(),
+        })
+    }
+
+    pub(crate) fn preset_theme(&self) -> &'static str {
+        COLOR_PRESETS.iter()
+            .find(|(name, _, _)| *name == self.preset)
+            .map_or("base16-ocean.dark", |(_, theme, _)| *theme)
+    }
+
+    pub(crate) fn from_json(json: &str, default_preset: Option<&str>) -> Result<Self, String> {
+        let user: serde_json::Value = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        Self::resolve(&user, default_preset.filter(|_| user.get("preset").is_none()))
+    }
+
+    pub(crate) fn resolve(user: &serde_json::Value, preset: Option<&str>) -> Result<Self, String> {
+        let file_preset = user.get("preset").and_then(|value| value.as_str()).unwrap_or("dark");
+        let preset = preset.unwrap_or(file_preset);
+        let unknown = |name: &str| match nearest(name, Self::preset_names()) {
+            Some(preset) => format!("Unknown color preset: {} (did you mean {}?)", name, preset),
+            None => format!("Unknown color preset: {} (available: {})", name, Self::preset_names().join(", ")),
+        };
+        let base = serde_json::to_value(Self::preset(file_preset).ok_or_else(|| unknown(file_preset))?).map_err(|e| e.to_string())?;
+        let mut resolved = serde_json::to_value(Self::preset(preset).ok_or_else(|| unknown(preset))?).map_err(|e| e.to_string())?;
+        if let Some(fields) = user.as_object() {
+            for (name, value) in fields.iter().filter(|(name, _)| *name != "preset") {
+                let value = match value {
+                    serde_json::Value::Object(_) => serde_json::from_value::<StyleFields>(value.clone())
+                        .map(|fields| serde_json::Value::String(fields.to_spec()))
+                        .map_err(|e| format!("{}: {}", name, e))?,
+                    _ => value.clone(),
+                };
+                if base.get(name) != Some(&value) {
+                    resolved[name] = value;
+                }
+            }
+        }
+        serde_json::from_value(resolved).map_err(|e| e.to_string())
+    }
+
+    pub(crate) fn color_mode(&self) -> ColorMode {
+        self.color_mode.unwrap_or(self.detected_mode)
+    }
+
+    pub(crate) fn style(&self, value: &str) -> StyleSpec {
+        parse_style(value).map(|spec| spec.convert(self.color_mode())).unwrap_or_default()
+    }
+
+    pub(crate) fn fg(&self, value: &str) -> Style {
+        self.style(value).fg()
+    }
+
+    pub(crate) fn bg(&self, value: &str) -> Style {
+        self.style(value).bg()
+    }
+
+    pub(crate) fn highlight(&self, value: &str) -> Style {
+        match self.color_mode() {
+            ColorMode::NoColor => Style::default().add_modifier(Modifier::REVERSED),
+            _ => self.bg(value),
+        }
+    }
+
+    pub(crate) fn color(&self, value: &str) -> Color {
+        self.style(value).primary().unwrap_or(Color::Reset)
+    }
+
+    pub(crate) fn validate(&mut self) -> Vec<String> {
+        let defaults = serde_json::to_value(Self::preset(&self.preset).unwrap_or_else(Self::default));
+        let (Ok(defaults), Ok(mut fields)) = (defaults, serde_json::to_value(&*self)) else {
+            return Vec::new();
+        };
+        let mut errors = Vec::new();
+        if let Some(fields) = fields.as_object_mut() {
+            for (name, value) in fields.iter_mut().filter(|(name, _)| !Self::SETTINGS_FIELDS.contains(&name.as_str())) {
+                let text = value.as_str().unwrap_or_default();
+                if let Err(e) = parse_style(text) {
+                    errors.push(format!("colors.json: {}: {}", name, e));
+                    *value = defaults[name.as_str()].clone();
+                } else if Self::HIGHLIGHT_FIELDS.contains(&name.as_str()) && !Self::has_background(text) {
+                    errors.push(format!("colors.json: {}: {:?} would hide the highlight; using the preset color", name, text));
+                    *value = defaults[name.as_str()].clone();
+                }
+            }
+        }
+        match serde_json::from_value(fields) {
+            Ok(validated) => *self = validated,
+            Err(e) => errors.push(format!("colors.json: {}", e)),
+        }
+        errors
+    }
+
+    fn has_background(value: &str) -> bool {
+        !value.split_whitespace().any(|token| is_default_color(token) || token.strip_prefix("bg=").is_some_and(is_default_color))
+    }
+
+    pub(crate) fn merge_json(&self, text: &str) -> Result<String, String> {
+        let mut merged = match text.trim() {
+            "" => serde_json::Value::Object(serde_json::Map::new()),
+            text => serde_json::from_str(text).map_err(|e| e.to_string())?,
+        };
+        let Some(fields) = merged.as_object_mut() else {
+            return Err("expected a JSON object".to_string());
+        };
+        let owned = match serde_json::to_value(self).map_err(|e| e.to_string())? {
+            serde_json::Value::Object(owned) => owned,
+            _ => return Err("expected a JSON object".to_string()),
+        };
+        for optional in ["color_mode", "syntax_theme"] {
+            if !owned.contains_key(optional) {
+                fields.remove(optional);
+            }
+        }
+        fields.extend(owned);
+        serde_json::to_string_pretty(&merged).map_err(|e| e.to_string())
+    }
+}
+
+impl Keybindings {
+    pub(crate) fn default() -> Self {
+        Keybindings {
+            normal_mode: [
+                ("i".to_string(), "enter_insert_mode".to_string()),
+                ("Insert".to_string(), "enter_insert_mode".to_string()),
+                ("a".to_string(), "append".to_string()),
+                ("o".to_string(), "open_line_below".to_string()),
+                ("O".to_string(), "open_line_above".to_string()),
+                ("dd".to_string(), "delete_line".to_string()),
+                ("yy".to_string(), "yank_line".to_string()),
+                ("ds".to_string(), "surround_delete".to_string()),
+                ("cs".to_string(), "surround_change".to_string()),
+                ("p".to_string(), "paste_after".to_string()),
+                ("v".to_string(), "enter_visual_mode".to_string()),
+                ("Ctrl+v".to_string(), "enter_visual_block_mode".to_string()),
+                (":".to_string(), "enter_command_mode".to_string()),
+                ("Ctrl+b".to_string(), "toggle_debug_menu".to_string()),
+                ("F12".to_string(), "focus_debug".to_string()),
+                ("F11".to_string(), "focus_minimap".to_string()),
+                ("Ctrl+Up".to_string(), "scroll_debug_up".to_string()),
+                ("Ctrl+Down".to_string(), "scroll_debug_down".to_string()),
+                ("Ctrl+n".to_string(), "toggle_sidebar".to_string()),
+                ("/".to_string(), "enter_search_mode".to_string()),
+                ("n".to_string(), "next_search_result".to_string()),
+                ("N".to_string(), "previous_search_result".to_string()),
+                ("Ctrl+p".to_string(), "paste_clipboard".to_string()),
+                ("u".to_string(), "undo".to_string()),
+                ("Ctrl+d".to_string(), "scroll_half_page_down".to_string()),
+                ("Ctrl+u".to_string(), "scroll_half_page_up".to_string()),
+                ("Ctrl+e".to_string(), "scroll_line_down".to_string()),
+                ("Ctrl+y".to_string(), "scroll_line_up".to_string()),
+                ("Ctrl+r".to_string(), "redo".to_string()),
+                ("Left".to_string(), "move_left".to_string()),
+                ("Down".to_string(), "move_down".to_string()),
+                ("Up".to_string(), "move_up".to_string()),
+                ("Right".to_string(), "move_right".to_string()),
+                ("Home".to_string(), "goto_line_start".to_string()),
+                ("End".to_string(), "goto_line_end".to_string()),
+                ("PageUp".to_string(), "page_up".to_string()),
+                ("PageDown".to_string(), "page_down".to_string()),
+                ("Tab".to_string(), "next_tab".to_string()),
+                ("Shift+BackTab".to_string(), "previous_tab".to_string()),
+                ("F1".to_string(), "switch_to_tab_1".to_string()),
+                ("F2".to_string(), "switch_to_tab_2".to_string()),
+                ("F3".to_string(), "switch_to_tab_3".to_string()),
+                ("F4".to_string(), "switch_to_tab_4".to_string()),
+                ("F5".to_string(), "switch_to_tab_5".to_string()),
+                ("F6".to_string(), "switch_to_tab_6".to_string()),
+                ("F7".to_string(), "switch_to_tab_7".to_string()),
+                ("F8".to_string(), "switch_to_tab_8".to_string()),
+                ("F9".to_string(), "switch_to_tab_9".to_string()),
+                ("Ctrl+t".to_string(), "new_tab".to_string()),
+                ("Ctrl+w".to_string(), "close_tab".to_string()),
+                ("Ctrl+Shift+t".to_string(), "reopen_closed_tab".to_string()),
+                ("Ctrl+Shift+Tab".to_string(), "previous_tab".to_string()),
+                ("Ctrl+6".to_string(), "toggle_alternate_tab".to_string()),
+                ("Ctrl+^".to_string(), "toggle_alternate_tab".to_string()),
+                ("Ctrl+m".to_string(), "toggle_minimap".to_string()),
+                ("gCtrl+g".to_string(), "buffer_stats".to_string()),
+                ("ga".to_string(), "char_info".to_string()),
+                ("grn".to_string(), "rename_word".to_string()),
+                ("mm".to_string(), "toggle_bookmark".to_string()),
+                ("]b".to_string(), "next_bookmark".to_string()),
+                ("[b".to_string(), "prev_bookmark".to_string()),
+                ("g;".to_string(), "older_change".to_string()),
+                ("g,".to_string(), "newer_change".to_string()),
+                ("`.".to_string(), "last_change".to_string()),
+                ("zz".to_string(), "scroll_cursor_center".to_string()),
+                ("zt".to_string(), "scroll_cursor_top".to_string()),
+                ("zb".to_string(), "scroll_cursor_bottom".to_string()),
+                ("za".to_string(), "toggle_fold".to_string()),
+                ("zR".to_string(), "open_all_folds".to_string()),
+                ("zM".to_string(), "close_all_folds".to_string()),
+                ("Alt+Up".to_string(), "expand_selection".to_string()),
+            ].iter().cloned().collect(),
+            insert_mode: [
+                ("Esc".to_string(), "exit_insert_mode".to_string()),
+                ("Ctrl+xCtrl+f".to_string(), "complete_path".to_string()),
+                ("Ctrl+r=".to_string(), "insert_expression".to_string()),
+                ("Ctrl+k".to_string(), "insert_digraph".to_string()),
+                ("Ctrl+v".to_string(), "insert_unicode".to_string()),
+            ].iter().cloned().collect(),
+            visual_mode: [
+                ("Esc".to_string(), "exit_visual_mode".to_string()),
+                ("y".to_string(), "yank_selection".to_string()),
+                ("d".to_string(), "delete_selection".to_string()),
+                ("p".to_string(), "paste_over_selection".to_string()),
+                ("c".to_string(), "change_selection".to_string()),
+                ("S".to_string(), "surround_selection".to_string()),
+                ("gCtrl+g".to_string(), "buffer_stats".to_string()),
+                (":".to_string(), "enter_command_mode".to_string()),
+                ("Left".to_string(), "move_left".to_string()),
+                ("Down".to_string(), "move_down".to_string()),
+                ("Up".to_string(), "move_up".to_string()),
+                ("Right".to_string(), "move_right".to_string()),
+                ("Alt+Up".to_string(), "expand_selection".to_string()),
+                ("Alt+Down".to_string(), "shrink_selection".to_string()),
+            ].iter().cloned().collect(),
+            command_mode: [
+                ("Enter".to_string(), "execute_command".to_string()),
+                ("Esc".to_string(), "exit_command_mode".to_string()),
+            ].iter().cloned().collect(),
+            file_select_mode: [
+                ("Enter".to_string(), "select_file".to_string()),
+                ("Esc".to_string(), "exit_file_select_mode".to_string()),
+            ].iter().cloned().collect(),
+            search_mode: [
+                ("Enter".to_string(), "execute_search".to_string()),
+                ("Esc".to_string(), "exit_search_mode".to_string()),
+            ].iter().cloned().collect(),
+            tab_mode: [
+            ].iter().cloned().collect(),
+            visual_block_mode: Self::default_visual_block_mode(),
+            terminal_mode: Self::default_terminal_mode(),
+            git_rebase_todo: [
+                ("Ctrl+a".to_string(), "rebase_cycle".to_string()),
+            ].iter().cloned().collect(),
+        }
+    }
+
+    pub(crate) fn apply_cua_bindings(&mut self) {
+        let shared = [
+            ("Ctrl+s", "save_file"),
+            ("Ctrl+a", "select_all"),
+            ("Ctrl+z", "undo"),
+            ("Ctrl+Shift+z", "redo"),
+        ];
+        let line_clipboard = [
+            ("Ctrl+c", "yank_line"),
+            ("Ctrl+x", "delete_line"),
+            ("Ctrl+v", "paste_clipboard"),
+        ];
+        let selection_clipboard = [
+            ("Ctrl+c", "yank_selection"),
+            ("Ctrl+x", "cut_selection"),
+            ("Ctrl+v", "paste_clipboard"),
+        ];
+
+        for map in [&mut self.normal_mode, &mut self.insert_mode] {
+            for (key, action) in shared.iter().chain(line_clipboard.iter()) {
+                map.insert(key.to_string(), action.to_string());
+            }
+        }
+        for map in [&mut self.visual_mode, &mut self.visual_block_mode] {
+            for (key, action) in shared.iter().chain(selection_clipboard.iter()) {
+                map.insert(key.to_string(), action.to_string());
+            }
+        }
+    }
+
+    pub(crate) fn from_toml(text: &str) -> Result<Self, toml::de::Error> {
+        let user: BTreeMap<String, HashMap<String, String>> = toml::from_str(text)?;
+        let mut keybindings = Keybindings::default();
+        for (mode, bindings) in user {
+            let Some(defaults) = keybindings.mode_mut(&mode) else {
+                continue;
+            };
+            for (key, action) in bindings {
+                if action.is_empty() {
+                    defaults.remove(&key);
+                } else {
+                    defaults.insert(key, action);
+                }
+            }
+        }
+        Ok(keybindings)
+    }
+
+    fn mode_mut(&mut self, mode: &str) -> Option<&mut HashMap<String, String>> {
+        Some(match mode {
+            "normal_mode" => &mut self.normal_mode,
+            "insert_mode" => &mut self.insert_mode,
+            "visual_mode" => &mut self.visual_mode,
+            "visual_block_mode" => &mut self.visual_block_mode,
+            "command_mode" => &mut self.command_mode,
+            "file_select_mode" => &mut self.file_select_mode,
+            "search_mode" => &mut self.search_mode,
+            "tab_mode" => &mut self.tab_mode,
+            "terminal_mode" => &mut self.terminal_mode,
+            "git_rebase_todo" => &mut self.git_rebase_todo,
+            _ => return None,
+        })
+    }
+
+    pub(crate) fn modes(&self) -> [(&'static str, &HashMap<String, String>); 10] {
+        [
+            ("normal_mode", &self.normal_mode),
+            ("insert_mode", &self.insert_mode),
+            ("visual_mode", &self.visual_mode),
+            ("visual_block_mode", &self.visual_block_mode),
+            ("command_mode", &self.command_mode),
+            ("file_select_mode", &self.file_select_mode),
+            ("search_mode", &self.search_mode),
+            ("tab_mode", &self.tab_mode),
+            ("terminal_mode", &self.terminal_mode),
+            ("git_rebase_todo", &self.git_rebase_todo),
+        ]
+    }
+
+    pub(crate) fn default_visual_block_mode() -> HashMap<String, String> {
+        [
+            ("Esc".to_string(), "exit_visual_mode".to_string()),
+            ("y".to_string(), "yank_selection".to_string()),
+            ("d".to_string(), "delete_selection".to_string()),
+            ("I".to_string(), "block_insert".to_string()),
+            ("A".to_string(), "block_append".to_string()),
+            ("gCtrl+g".to_string(), "buffer_stats".to_string()),
+            ("Left".to_string(), "move_left".to_string()),
+            ("Down".to_string(), "move_down".to_string()),
+            ("Up".to_string(), "move_up".to_string()),
+            ("Right".to_string(), "move_right".to_string()),
+        ].iter().cloned().collect()
+    }
+
+    pub(crate) fn default_terminal_mode() -> HashMap<String, String> {
+        [
+            ("Ctrl+\\Ctrl+n".to_string(), "exit_terminal_mode".to_string()),
+        ].iter().cloned().collect()
+    }
+}
+
+impl Editor {
+    pub(crate) fn color_environment(settings: &Settings) -> (Option<&'static str>, ColorMode) {
+        let no_color = !settings.force_color && env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+        let light = settings.auto_theme && env::var("COLORFGBG").is_ok_and(|value| light_background(&value));
+        (light.then_some("light"), if no_color { ColorMode::NoColor } else { ColorMode::default() })
+    }
+
+    pub(crate) fn load_color_config(config_dir: Option<&Path>, create: bool, default_preset: Option<&str>) -> Result<ColorConfig, PhantomError> {
+        let config_dir = config_dir.ok_or(PhantomError::NoConfigDir)?;
+        let config_path = config_dir.join("colors.json");
+    
+        if !config_path.exists() && create {
+            Self::create_default_color_config(&config_path)?;
+        }
+    
+        let config_str = fs::read_to_string(&config_path).map_err(|e| PhantomError::io(&config_path, e))?;
+        ColorConfig::from_json(&config_str, default_preset).map_err(|e| PhantomError::config("colors.json", e))
+    }
+
+    pub(crate) fn create_default_color_config(config_path: &Path) -> Result<(), PhantomError> {
+        let mut default_config = serde_json::to_value(ColorConfig::default()).map_err(|e| PhantomError::config("colors.json", e))?;
+        if let Some(fields) = default_config.as_object_mut() {
+            fields.remove("preset");
+        }
+        save_json(config_path, serde_json::to_string_pretty(&default_config))
+    }
+
+    pub(crate) fn load_config(config_dir: Option<&Path>, create: bool) -> Result<Keybindings, PhantomError> {
+        let config_dir = config_dir.ok_or(PhantomError::NoConfigDir)?;
+        let config_path = config_dir.join("config.toml");
+    
+        if !config_path.exists() && create {
+            Self::create_default_config(&config_path)?;
+        }
+    
+        let config_str = fs::read_to_string(&config_path).map_err(|e| PhantomError::io(&config_path, e))?;
+        Keybindings::from_toml(&config_str).map_err(|e| PhantomError::toml("config.toml", &config_str, &e))
+    }
+
+    pub(crate) fn create_default_config(config_path: &Path) -> Result<(), PhantomError> {
+        let default_config = toml::to_string_pretty(&Keybindings::default()).map_err(|e| PhantomError::config("config.toml", e))?;
+        write_creating_dirs(config_path, default_config)
+    }
+
+    pub(crate) fn load_settings(config_dir: Option<&Path>, create: bool) -> Result<Settings, PhantomError> {
+        let config_dir = config_dir.ok_or(PhantomError::NoConfigDir)?;
+        let settings_path = config_dir.join("settings.toml");
+
+        if !settings_path.exists() && create {
+            Self::create_default_settings(&settings_path)?;
+        }
+
+        let settings_str = fs::read_to_string(&settings_path).map_err(|e| PhantomError::io(&settings_path, e))?;
+        toml::from_str(&settings_str).map_err(|e| PhantomError::toml("settings.toml", &settings_str, &e))
+    }
+
+    pub(crate) fn save_settings(&self) -> Result<(), PhantomError> {
+        let config_dir = self.paths.config_dir.as_ref().ok_or(PhantomError::NoConfigDir)?;
+        if !self.paths.config_writable {
+            return Err(PhantomError::ReadOnly(config_dir.clone()));
+        }
+        let path = config_dir.join("settings.toml");
+        let settings = merge_toml(&read_if_exists(&path)?, &self.settings).map_err(|e| PhantomError::config("settings.toml", e))?;
+        fs::write(&path, settings).map_err(|e| PhantomError::io(&path, e))
+    }
+
+    pub(crate) fn write_config(&mut self) -> Result<PathBuf, PhantomError> {
+        let config_dir = self.paths.config_dir.clone().ok_or(PhantomError::NoConfigDir)?;
+        if !self.paths.config_writable {
+            return Err(PhantomError::ReadOnly(config_dir));
+        }
+        self.save_settings()?;
+        let config_path = config_dir.join("config.toml");
+        let config = update_keybindings_toml(&read_if_exists(&config_path)?, &self.keybindings).map_err(|e| PhantomError::config("config.toml", e))?;
+        fs::write(&config_path, config).map_err(|e| PhantomError::io(&config_path, e))?;
+        let colors_path = config_dir.join("colors.json");
+        let colors = self.color_config.merge_json(&read_if_exists(&colors_path)?).map_err(|e| PhantomError::config("colors.json", e))?;
+        fs::write(&colors_path, colors).map_err(|e| PhantomError::io(&colors_path, e))?;
+        Ok(config_dir)
+    }
+
+    pub(crate) fn ui_state(&self) -> UiState {
+        UiState {
+            sidebar_width: Some(self.sidebar_width),
+            minimap_width: Some(self.minimap_width),
+            show_minimap: Some(self.show_minimap),
+            show_debug: Some(self.show_debug),
+            debug_height: Some(self.settings.debug_height),
+            terminal_height: Some(self.settings.terminal_height),
+        }
+    }
+
+    pub(crate) fn restore_ui_state(&mut self) {
+        let Some(data_dir) = &self.paths.data_dir else {
+            return;
+        };
+        let state = UiState::load(&data_dir.join("state.json"));
+        let explicit: toml::Table = self.paths.config_dir.as_ref()
+            .and_then(|dir| fs::read_to_string(dir.join("settings.toml")).ok())
+            .and_then(|text| toml::from_str(&text).ok())
+            .unwrap_or_default();
+        self.sidebar_width = state.sidebar_width.unwrap_or(self.sidebar_width);
+        self.minimap_width = state.minimap_width.unwrap_or(self.minimap_width);
+        self.show_minimap = state.show_minimap.unwrap_or(self.show_minimap);
+        self.show_debug = state.show_debug.unwrap_or(self.show_debug);
+        if !explicit.contains_key("debug_height") {
+            self.settings.debug_height = state.debug_height.unwrap_or(self.settings.debug_height);
+        }
+        if !explicit.contains_key("terminal_height") {
+            self.settings.terminal_height = state.terminal_height.unwrap_or(self.settings.terminal_height);
+        }
+    }
+
+    pub(crate) fn save_ui_state(&mut self) {
+        if let Some(data_dir) = &self.paths.data_dir {
+            if let Err(e) = self.ui_state().save(&data_dir.join("state.json")) {
+                self.error(format!("Failed to save UI state: {}", e));
+            }
+        }
+    }
+
+    pub(crate) fn colorscheme_completions(&self, arguments: &str) -> (usize, Vec<String>) {
+        (0, prefix_completions(arguments, ColorConfig::preset_names()))
+    }
+
+    pub(crate) fn set_colorscheme(&mut self, name: &str, persist: bool) -> Result<(), String> {
+        let path = self.paths.config_dir.as_ref().map(|dir| dir.join("colors.json"));
+        let user = match path.as_ref().filter(|path| path.exists()) {
+            Some(path) => {
+                let json = fs::read_to_string(path).map_err(|e| e.to_string())?;
+                serde_json::from_str(&json).map_err(|e| format!("colors.json: {}", e))?
+            }
+            None => serde_json::Value::Null,
+        };
+        let mut color_config = ColorConfig::resolve(&user, Some(name))?;
+        for error in color_config.validate() {
+            self.warn(error);
+        }
+        color_config.detected_mode = self.color_config.detected_mode;
+        self.color_config = color_config;
+        if persist {
+            let path = path.ok_or("Could not find config directory")?;
+            if !self.paths.config_writable {
+                return Err(format!("{} is read-only", path.display()));
+            }
+            let current = read_if_exists(&path).map_err(|e| e.to_string())?;
+            let json = self.color_config.merge_json(&current).map_err(|e| format!("colors.json: {}", e))?;
+            fs::write(&path, json).map_err(|e| format!("Failed to save colors.json: {}", e))?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn reload_config(&mut self) -> Result<(), PhantomError> {
+        let config_dir = self.paths.config_dir.clone().ok_or(PhantomError::NoConfigDir)?;
+        let dir = Some(config_dir.as_path());
+        let exists = |name: &str| config_dir.join(name).exists();
+        let mut settings = match exists("settings.toml") {
+            true => Self::load_settings(dir, false)?,
+            false => Settings::default(),
+        };
+        let clamped = clamp_settings(&mut settings);
+        let mut keybindings = match exists("config.toml") {
+            true => Self::load_config(dir, false)?,
+            false => Keybindings::default(),
+        };
+        let (default_preset, detected_mode) = Self::color_environment(&settings);
+        let mut color_config = match exists("colors.json") {
+            true => Self::load_color_config(dir, false, default_preset)?,
+            false => ColorConfig::preset(default_preset.unwrap_or("dark")).unwrap_or_else(ColorConfig::default),
+        };
+        if settings.cua_bindings {
+            keybindings.apply_cua_bindings();
+        }
+        let (ps, mut warnings) = Self::load_syntax_set(dir);
+        warnings.extend(clamped);
+        warnings.extend(color_config.validate());
+        color_config.detected_mode = detected_mode;
+        self.settings = settings;
+        self.keybindings = keybindings;
+        self.color_config = color_config;
+        self.ps = ps;
+        for tab_index in 0..self.tabs.len() {
+            if self.tabs[tab_index].syntax != "Plain Text" {
+                continue;
+            }
+            if let Some(syntax) = self.detect_file_syntax(tab_index) {
+                self.tabs[tab_index].syntax = syntax;
+            }
+        }
+        self.syntax = self.tabs[self.active_tab].syntax.clone();
+        warnings.extend(self.unknown_keybindings());
+        self.info("Reloaded configuration");
+        for warning in warnings {
+            self.warn(warning);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn create_default_settings(settings_path: &Path) -> Result<(), PhantomError> {
+        let default_settings = toml::to_string_pretty(&Settings::default()).map_err(|e| PhantomError::config("settings.toml", e))?;
+        write_creating_dirs(settings_path, default_settings)
+    }
+
+    pub(crate) fn load_syntax_set(config_dir: Option<&Path>) -> (SyntaxSet, Vec<String>) {
+        let defaults = SyntaxSet::load_defaults_newlines();
+        let Some(entries) = config_dir.and_then(|dir| fs::read_dir(dir.join("syntaxes")).ok()) else {
+            return (defaults, Vec::new());
+        };
+        let mut paths: Vec<PathBuf> = entries.flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|extension| extension == "sublime-syntax"))
+            .collect();
+        if paths.is_empty() {
+            return (defaults, Vec::new());
+        }
+        paths.sort();
+        let mut builder = defaults.into_builder();
+        let mut warnings = Vec::new();
+        for path in paths {
+            let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+            let fallback = path.file_stem().and_then(|stem| stem.to_str());
+            let syntax_error = |message: String| PhantomError::Syntax { file: format!("syntaxes/{}", name), message };
+            let definition = fs::read_to_string(&path)
+                .map_err(|e| syntax_error(e.to_string()))
+                .and_then(|text| SyntaxDefinition::load_from_str(&text, true, fallback).map_err(|e| syntax_error(e.to_string())));
+            match definition {
+                Ok(definition) => builder.add(definition),
+                Err(e) => warnings.push(format!("Skipped {}", e)),
+            }
+        }
+        (builder.build(), warnings)
+    }
+}
@@ -0,0 +1,99 @@
+#[derive(Clone, Copy, PartialEq)]
+enum DiffOp {
+    Equal,
+    Delete,
+    Insert,
+}
+
+const CONTEXT: usize = 3;
+
+fn diff_ops(old: &[String], new: &[String]) -> Vec<(DiffOp, usize)> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..].iter().rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut ops: Vec<(DiffOp, usize)> = (0..prefix).map(|i| (DiffOp::Equal, i)).collect();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            ops.push((DiffOp::Equal, prefix + i));
+            i += 1;
+            j += 1;
+        } else if i < a.len() && (j == b.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            ops.push((DiffOp::Delete, prefix + i));
+            i += 1;
+        } else {
+            ops.push((DiffOp::Insert, prefix + j));
+            j += 1;
+        }
+    }
+    ops.extend((old.len() - suffix..old.len()).map(|i| (DiffOp::Equal, i)));
+    ops
+}
+
+pub(crate) fn line_mapping(old: &[String], new: &[String]) -> Vec<Option<usize>> {
+    diff_ops(old, new).into_iter()
+        .filter(|(op, _)| *op != DiffOp::Delete)
+        .map(|(op, index)| (op == DiffOp::Equal).then_some(index))
+        .collect()
+}
+
+pub fn unified_diff(old_name: &str, new_name: &str, old: &[String], new: &[String]) -> String {
+    let ops = diff_ops(old, new);
+    let changes: Vec<usize> = ops.iter().enumerate()
+        .filter(|(_, (op, _))| *op != DiffOp::Equal)
+        .map(|(index, _)| index)
+        .collect();
+    if changes.is_empty() {
+        return String::new();
+    }
+
+    let mut output = format!("--- {}\n+++ {}\n", old_name, new_name);
+    let mut k = 0;
+    while k < changes.len() {
+        let start = changes[k].saturating_sub(CONTEXT);
+        let mut end = changes[k] + 1;
+        while k + 1 < changes.len() && changes[k + 1] <= end + 2 * CONTEXT {
+            k += 1;
+            end = changes[k] + 1;
+        }
+        k += 1;
+        let end = (end + CONTEXT).min(ops.len());
+
+        let count = |ops: &[(DiffOp, usize)], skip: DiffOp| ops.iter().filter(|(op, _)| *op != skip).count();
+        let (old_before, new_before) = (count(&ops[..start], DiffOp::Insert), count(&ops[..start], DiffOp::Delete));
+        let hunk = &ops[start..end];
+        let (old_count, new_count) = (count(hunk, DiffOp::Insert), count(hunk, DiffOp::Delete));
+        let first = |before: usize, count: usize| if count == 0 { before } else { before + 1 };
+        output.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            first(old_before, old_count), old_count, first(new_before, new_count), new_count
+        ));
+        for (op, index) in hunk {
+            let (marker, line) = match op {
+                DiffOp::Equal => (' ', &old[*index]),
+                DiffOp::Delete => ('-', &old[*index]),
+                DiffOp::Insert => ('+', &new[*index]),
+            };
+            output.push(marker);
+            output.push_str(line);
+            output.push('\n');
+        }
+    }
+    output
+}
@@ -0,0 +1,118 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::editor::{Editor, Mode};
+use crate::ui::{Picker, PickerKind};
+
+pub(crate) const DIGRAPHS: &[(&str, char)] = &[
+    ("a:", 'ä'), ("e:", 'ë'), ("i:", 'ï'), ("o:", 'ö'), ("u:", 'ü'), ("y:", 'ÿ'),
+    ("A:", 'Ä'), ("E:", 'Ë'), ("I:", 'Ï'), ("O:", 'Ö'), ("U:", 'Ü'),
+    ("a'", 'á'), ("e'", 'é'), ("i'", 'í'), ("o'", 'ó'), ("u'", 'ú'), ("y'", 'ý'),
+    ("A'", 'Á'), ("E'", 'É'), ("I'", 'Í'), ("O'", 'Ó'), ("U'", 'Ú'), ("Y'", 'Ý'),
+    ("a!", 'à'), ("e!", 'è'), ("i!", 'ì'), ("o!", 'ò'), ("u!", 'ù'),
+    ("A!", 'À'), ("E!", 'È'), ("I!", 'Ì'), ("O!", 'Ò'), ("U!", 'Ù'),
+    ("a>", 'â'), ("e>", 'ê'), ("i>", 'î'), ("o>", 'ô'), ("u>", 'û'),
+    ("A>", 'Â'), ("E>", 'Ê'), ("I>", 'Î'), ("O>", 'Ô'), ("U>", 'Û'),
+    ("a?", 'ã'), ("o?", 'õ'), ("n?", 'ñ'), ("A?", 'Ã'), ("O?", 'Õ'), ("N?", 'Ñ'),
+    ("c,", 'ç'), ("C,", 'Ç'), ("aa", 'å'), ("AA", 'Å'), ("ae", 'æ'), ("AE", 'Æ'),
+    ("o/", 'ø'), ("O/", 'Ø'), ("oe", 'œ'), ("OE", 'Œ'), ("ss", 'ß'),
+    ("a*", 'α'), ("b*", 'β'), ("g*", 'γ'), ("d*", 'δ'), ("e*", 'ε'), ("z*", 'ζ'),
+    ("y*", 'η'), ("h*", 'θ'), ("i*", 'ι'), ("k*", 'κ'), ("l*", 'λ'), ("m*", 'μ'),
+    ("n*", 'ν'), ("c*", 'ξ'), ("o*", 'ο'), ("p*", 'π'), ("r*", 'ρ'), ("s*", 'σ'),
+    ("t*", 'τ'), ("u*", 'υ'), ("f*", 'φ'), ("x*", 'χ'), ("q*", 'ψ'), ("w*", 'ω'),
+    ("D*", 'Δ'), ("G*", 'Γ'), ("L*", 'Λ'), ("P*", 'Π'), ("S*", 'Σ'), ("W*", 'Ω'),
+    ("->", '→'), ("<-", '←'), ("-!", '↑'), ("-v", '↓'), ("<>", '↔'), ("=>", '⇒'), ("==", '⇔'),
+    ("!=", '≠'), ("=<", '≤'), (">=", '≥'), ("?2", '≈'), ("=3", '≡'), ("+-", '±'),
+    ("*X", '×'), ("-:", '÷'), ("RT", '√'), ("00", '∞'), ("FA", '∀'), ("TE", '∃'),
+    ("(-", '∈'), ("dP", '∂'), ("+Z", '∑'), ("*P", '∏'), ("In", '∫'), ("NB", '∇'),
+    ("Eu", '€'), ("Pd", '£'), ("Ye", '¥'), ("Ct", '¢'), ("Cu", '¤'),
+    ("Co", '©'), ("Rg", '®'), ("TM", '™'), ("SE", '§'), ("PI", '¶'), ("DG", '°'),
+    ("<<", '«'), (">>", '»'), ("!I", '¡'), ("?I", '¿'), (".M", '·'), ("Sb", '∙'),
+    ("-N", '–'), ("-M", '—'), ("'6", '‘'), ("'9", '’'), ("\"6", '“'), ("\"9", '”'),
+    (",.", '…'), ("NS", '\u{a0}'), ("1S", '¹'), ("2S", '²'), ("3S", '³'),
+    ("12", '½'), ("14", '¼'), ("34", '¾'), ("OK", '✓'), ("XX", '✗'),
+];
+
+pub(crate) enum LiteralInput {
+    Digraph(String),
+    Verbatim,
+    CodePoint { long: bool, digits: String },
+}
+
+impl Editor {
+    pub(crate) fn digraph(&self, sequence: &str) -> Option<String> {
+        let lookup = |sequence: &str| self.settings.digraphs.get(sequence).cloned()
+            .or_else(|| DIGRAPHS.iter().find(|(digraph, _)| *digraph == sequence).map(|(_, c)| c.to_string()));
+        lookup(sequence).or_else(|| lookup(&sequence.chars().rev().collect::<String>()))
+    }
+
+    pub(crate) fn start_literal_input(&mut self, input: LiteralInput) {
+        if self.mode == Mode::Insert {
+            self.literal_input = Some(input);
+        }
+    }
+
+    pub(crate) fn handle_literal_input(&mut self, key: KeyEvent) {
+        let Some(input) = self.literal_input.take() else {
+            return;
+        };
+        if key.code == KeyCode::Esc {
+            return;
+        }
+        let typed = match key.code {
+            KeyCode::Char(c) if !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => Some(c),
+            _ => None,
+        };
+        match (input, typed) {
+            (LiteralInput::Digraph(mut sequence), Some(c)) => {
+                sequence.push(c);
+                if sequence.chars().count() < 2 {
+                    self.literal_input = Some(LiteralInput::Digraph(sequence));
+                    return;
+                }
+                match self.digraph(&sequence) {
+                    Some(text) => text.chars().for_each(|c| self.insert_char(c)),
+                    None => self.warn(format!("Unknown digraph: Ctrl+K {}", sequence)),
+                }
+            }
+            (LiteralInput::Verbatim, Some(prefix @ ('u' | 'U'))) => {
+                self.literal_input = Some(LiteralInput::CodePoint { long: prefix == 'U', digits: String::new() });
+            }
+            (LiteralInput::Verbatim, Some(c)) => self.insert_char(c),
+            (LiteralInput::Verbatim, None) if key.code == KeyCode::Tab => self.insert_char('\t'),
+            (LiteralInput::CodePoint { long, mut digits }, Some(c)) if c.is_ascii_hexdigit() => {
+                digits.push(c);
+                if digits.len() < if long { 6 } else { 4 } {
+                    self.literal_input = Some(LiteralInput::CodePoint { long, digits });
+                    return;
+                }
+                match u32::from_str_radix(&digits, 16).ok().and_then(char::from_u32) {
+                    Some(c) => self.insert_char(c),
+                    None => self.warn(format!("Invalid code point: Ctrl+V {}{}", if long { 'U' } else { 'u' }, digits)),
+                }
+            }
+            (input, _) => {
+                let attempted = match input {
+                    LiteralInput::Digraph(sequence) => format!("Ctrl+K {}", sequence),
+                    LiteralInput::Verbatim => "Ctrl+V ".to_string(),
+                    LiteralInput::CodePoint { long, digits } => format!("Ctrl+V {}{}", if long { 'U' } else { 'u' }, digits),
+                };
+                self.warn(format!("Invalid sequence: {}{}", attempted, Self::key_event_to_string(key)));
+            }
+        }
+    }
+
+    pub(crate) fn show_digraphs(&mut self) {
+        let describe = |sequence: &str, text: &str| {
+            let codes: Vec<String> = text.chars().map(|c| format!("U+{:04X}", c as u32)).collect();
+            format!("{}  {}  {}", sequence, text, codes.join(" "))
+        };
+        let mut items: Vec<String> = self.settings.digraphs.iter()
+            .map(|(sequence, text)| describe(sequence, text))
+            .collect();
+        items.extend(DIGRAPHS.iter()
+            .filter(|(sequence, _)| !self.settings.digraphs.contains_key(*sequence))
+            .map(|(sequence, c)| describe(sequence, &c.to_string())));
+        self.picker = Some(Picker::new(PickerKind::Digraphs, "Digraphs (Ctrl+K in insert mode)", items));
+        self.mode = Mode::Picker;
+    }
+}
@@ -0,0 +1,3317 @@
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use copypasta::{ClipboardContext, ClipboardProvider};
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use syntect::highlighting::ThemeSet;
+use syntect::parsing::SyntaxSet;
+use tui::style::{Color, Style};
+
+use crate::batch::DryRunWrites;
+use crate::blame::Blame;
+use crate::buffer::{block_span, char_width_at, column_to_byte, insert_at_column, line_width, BlockSpan, Tab, TextStats};
+use crate::closed::ClosedTab;
+use crate::commands::SubstituteConfirm;
+use crate::complete::Completion;
+use crate::config::{ColorConfig, ConfigPaths, Keybindings, LineStore, PositionStore, RecentFiles, Settings, StoredPosition};
+use crate::digraph::LiteralInput;
+use crate::error::PhantomError;
+use crate::input::InputLine;
+use crate::log::{FileLogger, LogEntry, LogLevel};
+use crate::minimap::{MinimapFocus, TokenCache};
+use crate::options::clamp_settings;
+use crate::output::OutputPager;
+use crate::outline::Outline;
+use crate::pager::PagerInput;
+use crate::pending::KeyMatch;
+use crate::plugin::{EditorMessage, Plugin};
+use crate::preview::SidebarPreview;
+use crate::private::{wipe, PrivateFilter};
+use crate::rename::RenameReview;
+use crate::save::PrivilegedWrite;
+use crate::start::StartScreen;
+use crate::surround::SurroundInput;
+use crate::tasks::Tasks;
+use crate::terminal::TerminalPanel;
+use crate::textobject::SelectionExpansion;
+use crate::todos::TodoScope;
+use crate::ui::{ConfirmPrompt, FileSelector, InputAction, InputPrompt, Picker, PickerKind, PickerLocation, Prompt, PromptAction, PromptAnswer, PromptResult, ScreenLayout};
+
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum MessageLevel {
+    Info,
+    Warn,
+    Error,
+}
+
+impl fmt::Display for MessageLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MessageLevel::Info => write!(f, "info"),
+            MessageLevel::Warn => write!(f, "warn"),
+            MessageLevel::Error => write!(f, "error"),
+        }
+    }
+}
+
+pub(crate) struct StatusMessage {
+    pub(crate) text: String,
+    pub(crate) level: MessageLevel,
+    pub(crate) shown_at: Instant,
+}
+
+pub(crate) struct BlockInsert {
+    pub(crate) top: usize,
+    pub(crate) bottom: usize,
+    pub(crate) column: usize,
+    pub(crate) line_length: usize,
+    pub(crate) pad: bool,
+}
+
+pub(crate) enum ClipboardWrapper {
+    Real(Box<ClipboardContext>, Option<Box<dyn ClipboardProvider>>),
+    Internal(String),
+    Private(String, Box<ClipboardWrapper>),
+}
+
+#[cfg(all(unix, not(any(target_os = "macos", target_os = "android", target_os = "ios", target_os = "emscripten"))))]
+fn primary_selection() -> Option<Box<dyn ClipboardProvider>> {
+    use copypasta::x11_clipboard::{Primary, X11ClipboardContext};
+    X11ClipboardContext::<Primary>::new().ok().map(|clipboard| Box::new(clipboard) as Box<dyn ClipboardProvider>)
+}
+
+#[cfg(not(all(unix, not(any(target_os = "macos", target_os = "android", target_os = "ios", target_os = "emscripten")))))]
+fn primary_selection() -> Option<Box<dyn ClipboardProvider>> {
+    None
+}
+
+pub fn frame_wait(elapsed: Duration, frame: Duration) -> Option<Duration> {
+    frame.checked_sub(elapsed).filter(|wait| !wait.is_zero())
+}
+
+impl ClipboardWrapper {
+    pub(crate) fn new() -> Self {
+        match ClipboardContext::new() {
+            Ok(clipboard) => ClipboardWrapper::Real(Box::new(clipboard), primary_selection()),
+            Err(_) => ClipboardWrapper::Internal(String::new()),
+        }
+    }
+
+    pub(crate) fn set_primary(&mut self, contents: String) -> Result<(), Box<dyn Error + Send + Sync>> {
+        match self {
+            ClipboardWrapper::Real(_, Some(primary)) => primary.set_contents(contents),
+            _ => self.set_contents(contents),
+        }
+    }
+
+    pub(crate) fn set_private(&mut self, contents: String) {
+        match self {
+            ClipboardWrapper::Private(register, _) => {
+                wipe(register);
+                *register = contents;
+            }
+            _ => {
+                let clipboard = std::mem::replace(self, ClipboardWrapper::Internal(String::new()));
+                *self = ClipboardWrapper::Private(contents, Box::new(clipboard));
+            }
+        }
+    }
+
+    pub(crate) fn leave_private(&mut self) {
+        if let ClipboardWrapper::Private(register, clipboard) = self {
+            wipe(register);
+            let clipboard = std::mem::replace(&mut **clipboard, ClipboardWrapper::Internal(String::new()));
+            *self = clipboard;
+        }
+    }
+}
+
+impl ClipboardProvider for ClipboardWrapper {
+    fn get_contents(&mut self) -> Result<String, Box<dyn Error + Send + Sync>> {
+        match self {
+            ClipboardWrapper::Real(clipboard, _) => clipboard.get_contents(),
+            ClipboardWrapper::Internal(register) | ClipboardWrapper::Private(register, _) => Ok(register.clone()),
+        }
+    }
+
+    fn set_contents(&mut self, contents: String) -> Result<(), Box<dyn Error + Send + Sync>> {
+        match self {
+            ClipboardWrapper::Real(clipboard, _) => clipboard.set_contents(contents),
+            ClipboardWrapper::Internal(register) => {
+                *register = contents;
+                Ok(())
+            }
+            ClipboardWrapper::Private(..) => {
+                self.leave_private();
+                self.set_contents(contents)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Mode::Normal => write!(f, "Normal"),
+            Mode::Insert => write!(f, "Insert"),
+            Mode::Visual => write!(f, "Visual"),
+            Mode::VisualBlock => write!(f, "VisualBlock"),
+            Mode::Command => write!(f, "Command"),
+            Mode::Search => write!(f, "Search"),
+            Mode::FileSelect => write!(f, "FileSelect"),
+            Mode::DirectoryNav => write!(f, "DirectoryNav"),
+            Mode::SidebarActive => write!(f, "SidebarActive"),
+            Mode::Picker => write!(f, "Picker"),
+            Mode::Outline => write!(f, "Outline"),
+            Mode::Terminal => write!(f, "Terminal"),
+            Mode::Debug => write!(f, "Debug"),
+            Mode::Pager => write!(f, "Pager"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mode {
+    Normal,
+    Insert,
+    Command,
+    Visual,
+    VisualBlock,
+    FileSelect,
+    DirectoryNav,
+    Search,
+    SidebarActive,
+    Picker,
+    Outline,
+    Terminal,
+    Debug,
+    Pager,
+}
+
+pub struct Editor {
+    pub(crate) content: Vec<String>,
+    pub(crate) cursor_position: (usize, usize),
+    pub(crate) mode: Mode,
+    pub(crate) debug_messages: Vec<LogEntry>,
+    pub(crate) logger: Option<FileLogger>,
+    pub(crate) debug_scroll: usize,
+    pub(crate) command_buffer: InputLine,
+    pub(crate) current_file: Option<String>,
+    pub(crate) ps: SyntaxSet,
+    pub(crate) ts: ThemeSet,
+    pub(crate) syntax: String,
+    pub(crate) cursor_style: Style,
+    pub(crate) clipboard_context: ClipboardWrapper,
+    pub(crate) visual_start: (usize, usize),
+    pub(crate) block_register: Option<Vec<String>>,
+    pub(crate) block_insert: Option<BlockInsert>,
+    pub(crate) file_selector: Option<FileSelector>,
+    pub(crate) show_debug: bool,
+    pub(crate) search_query: InputLine,
+    pub(crate) search_results: Vec<(usize, usize)>,
+    pub(crate) search_highlight: bool,
+    pub(crate) current_search_index: usize,
+    pub(crate) scroll_offset: usize,
+    pub(crate) horizontal_scroll: usize,
+    pub(crate) keybindings: Keybindings,
+    pub(crate) color_config: ColorConfig,
+    pub(crate) settings: Settings,
+    pub(crate) status_message: Option<StatusMessage>,
+    pub(crate) message_history: Vec<(MessageLevel, String)>,
+    pub(crate) show_sidebar: bool,
+    pub(crate) sidebar_width: u16,
+    pub(crate) sidebar_preview: Option<SidebarPreview>,
+    pub(crate) preview_target: Option<(PathBuf, Instant)>,
+    pub(crate) pending_key: Option<String>,
+    pub(crate) pending_key_since: Option<(Instant, KeyEvent)>,
+    pub(crate) tabs: Vec<Tab>,
+    pub(crate) active_tab: usize,
+    pub(crate) tab_history: Vec<usize>,
+    pub(crate) mouse_selection_start: Option<(usize, usize)>,
+    pub(crate) mouse_selection_end: Option<(usize, usize)>,
+    pub(crate) mouse_drag: Option<(u16, u16)>,
+    pub(crate) mouse_scroll_row: Option<u16>,
+    pub(crate) show_minimap: bool,
+    pub(crate) minimap_width: u16,
+    pub(crate) minimap_line_mapping: Vec<(usize, usize)>,
+    pub(crate) minimap_tokens: TokenCache,
+    pub(crate) minimap_focus: Option<MinimapFocus>,
+    pub(crate) output: Option<OutputPager>,
+    pub(crate) privileged_write: Option<PrivilegedWrite>,
+    pub(crate) private_filter: Option<PrivateFilter>,
+    pub(crate) rename: Option<RenameReview>,
+    pub(crate) editor_height: usize,
+    pub(crate) editor_width: usize,
+    pub(crate) layout: ScreenLayout,
+    pub(crate) positions: PositionStore,
+    pub(crate) recent_files: RecentFiles,
+    pub(crate) bookmark_store: LineStore<usize>,
+    pub(crate) fold_store: LineStore<(usize, usize)>,
+    pub(crate) picker: Option<Picker>,
+    pub(crate) todo_scope: Option<TodoScope>,
+    pub(crate) outline: Option<Outline>,
+    pub(crate) blame: Option<Blame>,
+    pub(crate) terminal: Option<TerminalPanel>,
+    pub(crate) pending_terminal_key: Option<KeyEvent>,
+    pub(crate) command_completions: Vec<String>,
+    pub(crate) command_completion_index: usize,
+    pub(crate) command_completion_start: usize,
+    pub(crate) completion: Option<Completion>,
+    pub(crate) prompt: Option<Prompt>,
+    pub(crate) visual_marks: Option<(usize, usize)>,
+    pub(crate) undo_group_depth: usize,
+    pub(crate) undo_group_tab: Option<usize>,
+    pub(crate) prompt_result: Option<PromptResult>,
+    pub(crate) project_root: Option<PathBuf>,
+    pub(crate) paths: ConfigPaths,
+    pub(crate) dry_run_writes: Option<DryRunWrites>,
+    pub(crate) plugins: Vec<Plugin>,
+    pub(crate) pager: bool,
+    pub(crate) substitute_confirm: Option<SubstituteConfirm>,
+    pub(crate) pager_input: Option<PagerInput>,
+    pub(crate) start_screen: Option<StartScreen>,
+    pub(crate) selection_expansion: Option<SelectionExpansion>,
+    pub(crate) literal_input: Option<LiteralInput>,
+    pub(crate) surround_input: Option<SurroundInput>,
+    pub(crate) closed_tabs: Vec<ClosedTab>,
+    pub(crate) tasks: Tasks,
+}
+
+impl Editor {
+    pub fn new(config_override: Option<PathBuf>) -> Self {
+        let paths = ConfigPaths::resolve(config_override);
+        let startup_warning = paths.startup_warning();
+        let mut editor = Self::with_paths(paths, ClipboardWrapper::new());
+        if let Some(warning) = startup_warning {
+            editor.warn(warning);
+        }
+        editor.start_plugins();
+        editor
+    }
+
+    pub fn headless() -> Self {
+        let paths = ConfigPaths { config_dir: None, config_writable: false, data_dir: None };
+        Self::with_paths(paths, ClipboardWrapper::Internal(String::new()))
+    }
+
+    fn with_paths(paths: ConfigPaths, clipboard_context: ClipboardWrapper) -> Self {
+        let config_dir = paths.config_dir.as_deref();
+        let exists = |name: &str| config_dir.is_some_and(|dir| dir.join(name).exists());
+        let mut config_errors = Vec::new();
+        let mut keybindings = Self::load_config(config_dir, paths.config_writable).unwrap_or_else(|e| {
+            config_errors.extend(exists("config.toml").then(|| e.to_string()));
+            Keybindings::default()
+        });
+        let mut settings = Self::load_settings(config_dir, paths.config_writable).unwrap_or_else(|e| {
+            config_errors.extend(exists("settings.toml").then(|| e.to_string()));
+            Settings::default()
+        });
+        config_errors.extend(clamp_settings(&mut settings));
+        let (default_preset, detected_mode) = Self::color_environment(&settings);
+        let fallback = || ColorConfig::preset(default_preset.unwrap_or("dark")).unwrap_or_else(ColorConfig::default);
+        let (mut color_config, color_errors) = match Self::load_color_config(config_dir, paths.config_writable, default_preset) {
+            Ok(mut color_config) => {
+                let errors = color_config.validate();
+                (color_config, errors)
+            }
+            Err(e) if exists("colors.json") => (fallback(), vec![e.to_string()]),
+            Err(_) => (fallback(), Vec::new()),
+        };
+        color_config.detected_mode = detected_mode;
+        let (ps, syntax_warnings) = Self::load_syntax_set(config_dir);
+        if settings.cua_bindings {
+            keybindings.apply_cua_bindings();
+        }
+        let mut editor = Editor {
+            content: vec![String::new()],
+            cursor_position: (0, 0),
+            mode: Mode::Normal,
+            debug_messages: Vec::new(),
+            logger: None,
+            debug_scroll: 0,
+            command_buffer: InputLine::default(),
+            current_file: None,
+            ps,
+            ts: ThemeSet::load_defaults(),
+            syntax: "Plain Text".to_string(),
+            cursor_style: Style::default().fg(Color::Yellow),
+            clipboard_context,
+            visual_start: (0, 0),
+            block_register: None,
+            block_insert: None,
+            file_selector: None,
+            show_debug: false,
+            search_query: InputLine::default(),
+            search_results: Vec::new(),
+            search_highlight: false,
+            current_search_index: 0,
+            scroll_offset: 0,
+            horizontal_scroll: 0,
+            keybindings,
+            color_config,
+            settings,
+            status_message: None,
+            message_history: Vec::new(),
+            show_sidebar: false,
+            sidebar_width: 30,
+            sidebar_preview: None,
+            preview_target: None,
+            pending_key: None,
+            pending_key_since: None,
+            tabs: vec![Tab::new()],
+            active_tab: 0,
+            tab_history: Vec::new(),
+            mouse_selection_start: None,
+            mouse_selection_end: None,
+            mouse_drag: None,
+            mouse_scroll_row: None,
+            show_minimap: false,
+            minimap_width: 30,
+            minimap_line_mapping: Vec::new(),
+            minimap_tokens: TokenCache::default(),
+            minimap_focus: None,
+            output: None,
+            privileged_write: None,
+            private_filter: None,
+            rename: None,
+            editor_height: 24,
+            editor_width: 80,
+            layout: ScreenLayout::default(),
+            positions: paths.data_dir.as_ref()
+                .map(|dir| PositionStore::load(&dir.join("positions.json")))
+                .unwrap_or_default(),
+            recent_files: paths.data_dir.as_ref()
+                .map(|dir| RecentFiles::load(&dir.join("recent.json")))
+                .unwrap_or_default(),
+            bookmark_store: paths.data_dir.as_ref()
+                .map(|dir| LineStore::load(&dir.join("bookmarks.json")))
+                .unwrap_or_default(),
+            fold_store: paths.data_dir.as_ref()
+                .map(|dir| LineStore::load(&dir.join("folds.json")))
+                .unwrap_or_default(),
+            picker: None,
+            todo_scope: None,
+            outline: None,
+            blame: None,
+            terminal: None,
+            pending_terminal_key: None,
+            command_completions: Vec::new(),
+            command_completion_index: 0,
+            command_completion_start: 0,
+            completion: None,
+            prompt: None,
+            visual_marks: None,
+            undo_group_depth: 0,
+            undo_group_tab: None,
+            prompt_result: None,
+            project_root: Self::project_root(),
+            paths,
+            dry_run_writes: None,
+            plugins: Vec::new(),
+            pager: false,
+            substitute_confirm: None,
+            pager_input: None,
+            start_screen: None,
+            selection_expansion: None,
+            literal_input: None,
+            surround_input: None,
+            closed_tabs: Vec::new(),
+            tasks: Tasks::new(),
+        };
+        if editor.settings.persist_ui_state {
+            editor.restore_ui_state();
+        }
+        let binding_warnings = editor.unknown_keybindings();
+        for warning in config_errors.into_iter().chain(color_errors).chain(syntax_warnings).chain(binding_warnings) {
+            editor.warn(warning);
+        }
+        editor
+    }
+
+    pub(crate) const STATUS_TIMEOUT: Duration = Duration::from_secs(4);
+    const TAB_FLASH: Duration = Duration::from_millis(1500);
+
+    pub(crate) const MAX_MESSAGE_HISTORY: usize = 200;
+
+    pub(crate) fn show_message(&mut self, level: MessageLevel, text: String) {
+        self.log(level.into(), text.clone());
+        self.message_history.push((level, text.clone()));
+        if self.message_history.len() > Self::MAX_MESSAGE_HISTORY {
+            self.message_history.remove(0);
+        }
+        self.status_message = Some(StatusMessage { text, level, shown_at: Instant::now() });
+    }
+
+    pub(crate) fn info(&mut self, text: impl Into<String>) {
+        self.show_message(MessageLevel::Info, text.into());
+    }
+
+    pub(crate) fn warn(&mut self, text: impl Into<String>) {
+        self.show_message(MessageLevel::Warn, text.into());
+    }
+
+    pub(crate) fn error(&mut self, text: impl Into<String>) {
+        self.show_message(MessageLevel::Error, text.into());
+    }
+
+    pub fn expire_status_message(&mut self) -> bool {
+        let expired = self.status_message.as_ref().is_some_and(|message| {
+            message.level != MessageLevel::Error && message.shown_at.elapsed() >= Self::STATUS_TIMEOUT
+        });
+        if expired {
+            self.status_message = None;
+        }
+        expired
+    }
+
+    pub(crate) fn show_messages(&mut self) {
+        if self.message_history.is_empty() {
+            self.info("No messages");
+            return;
+        }
+        let lines = self.message_history.iter()
+            .map(|(level, text)| format!("[{}] {}", level, text))
+            .collect();
+        self.show_output("Messages", lines);
+    }
+
+    pub(crate) fn handle_minimap_click(&mut self, _x: u16, y: u16) {
+        let total_lines = self.tabs[self.active_tab].content.len();
+    
+        let top = self.layout.minimap.map_or(0, |area| area.y);
+        let adjusted_y = y.saturating_sub(top + 1) as usize;
+    
+        if adjusted_y >= self.minimap_line_mapping.len() {
+            return;
+        }
+    
+        let (min_line, max_line) = self.minimap_line_mapping[adjusted_y];
+        let clicked_line = (min_line + max_line) / 2;
+    
+        let new_cursor_line = clicked_line.min(total_lines.saturating_sub(1));
+        let editor_height = self.get_editor_height();
+        let new_scroll_offset = new_cursor_line.saturating_sub(editor_height / 2);
+    
+        let tab = &mut self.tabs[self.active_tab];
+        tab.cursor_position.1 = new_cursor_line;
+        tab.scroll_offset = new_scroll_offset;
+    
+        self.ensure_cursor_visible();
+    }
+
+    pub(crate) fn scroll_margin(&self) -> usize {
+        self.option("scrolloff").number().min(self.get_editor_height().saturating_sub(1) / 2)
+    }
+
+    pub(crate) fn ensure_cursor_visible(&mut self) {
+        let editor_height = self.get_editor_height();
+        let margin = self.scroll_margin();
+        let tab = &mut self.tabs[self.active_tab];
+        let cursor_line = tab.cursor_position.1;
+        if tab.fold_at(cursor_line).is_some_and(|(start, _)| start < cursor_line) {
+            tab.open_folds_at(cursor_line);
+        }
+
+        let cursor = tab.line_to_row(cursor_line);
+        let mut scroll = tab.line_to_row(tab.scroll_offset);
+        if cursor < scroll + margin {
+            scroll = cursor.saturating_sub(margin);
+        } else if cursor + margin >= scroll + editor_height {
+            let max_scroll = tab.row_count().saturating_sub(editor_height);
+            scroll = (cursor + margin + 1 - editor_height).min(max_scroll);
+        }
+        tab.scroll_offset = tab.row_to_line(scroll);
+        self.fit_wrapped_cursor();
+    }
+
+    pub(crate) fn wrap_width(&self) -> Option<usize> {
+        self.option("wrap").bool().then(|| self.get_editor_width())
+    }
+
+    pub(crate) fn wrapped_cursor_row(&self) -> usize {
+        let tab = &self.tabs[self.active_tab];
+        let Some(width) = self.wrap_width().filter(|_| tab.fold_at(tab.cursor_position.1).is_none()) else {
+            return 0;
+        };
+        let tab_width = self.option("tabstop").number();
+        let (x, y) = tab.cursor_position;
+        (tab.visual_column(y, x, tab_width) / width.max(1)).min(tab.wrapped_rows(y, width, tab_width) - 1)
+    }
+
+    fn fit_wrapped_cursor(&mut self) {
+        let Some(width) = self.wrap_width() else {
+            return;
+        };
+        let editor_height = self.get_editor_height();
+        let tab_width = self.option("tabstop").number();
+        let cursor_row = self.wrapped_cursor_row();
+        let tab = &mut self.tabs[self.active_tab];
+        let cursor = tab.line_to_row(tab.cursor_position.1);
+        let mut scroll = tab.line_to_row(tab.scroll_offset);
+        let mut used: usize = (scroll..cursor).map(|row| tab.wrapped_rows(tab.row_to_line(row), width, tab_width)).sum::<usize>() + cursor_row + 1;
+        while used > editor_height && scroll < cursor {
+            used -= tab.wrapped_rows(tab.row_to_line(scroll), width, tab_width);
+            scroll += 1;
+        }
+        tab.scroll_offset = tab.row_to_line(scroll);
+    }
+
+    pub(crate) fn adjust_horizontal_scroll(&mut self) {
+        if self.wrap_width().is_some() {
+            self.tabs[self.active_tab].horizontal_scroll = 0;
+            return;
+        }
+        let editor_width = self.get_editor_width();
+        let sidescrolloff = self.option("sidescrolloff").number();
+        let tab_width = self.option("tabstop").number();
+        self.tabs[self.active_tab].adjust_horizontal_scroll(editor_width, sidescrolloff, tab_width);
+    }
+
+    pub(crate) fn scroll_cursor_to(&mut self, position: &str) {
+        let editor_height = self.get_editor_height();
+        let margin = self.scroll_margin();
+        let tab = &mut self.tabs[self.active_tab];
+        let cursor = tab.line_to_row(tab.cursor_position.1);
+        let scroll = match position {
+            "top" => cursor.saturating_sub(margin),
+            "bottom" => (cursor + margin + 1).saturating_sub(editor_height),
+            _ => cursor.saturating_sub(editor_height / 2),
+        };
+        tab.scroll_offset = tab.row_to_line(scroll);
+        self.ensure_cursor_visible();
+    }
+
+    pub(crate) fn toggle_minimap(&mut self) -> io::Result<bool> {
+        self.show_minimap = !self.show_minimap;
+        let status = if self.show_minimap { "shown" } else { "hidden" };
+        
+        self.log(LogLevel::Debug, format!("Minimap toggle attempted. New state: {}", status));
+        
+        if self.show_minimap {
+            if self.tabs[self.active_tab].content.iter().all(|line| line.is_empty()) {
+                self.show_minimap = false;
+                self.log(LogLevel::Debug, "Cannot show minimap: No content");
+            } else {
+                self.log(LogLevel::Debug, format!("Minimap {} (content available)", status));
+            }
+        } else {
+            self.unfocus_minimap();
+            self.log(LogLevel::Debug, format!("Minimap {}", status));
+        }
+        
+        if let Ok((width, height)) = crossterm::terminal::size() {
+            self.log(LogLevel::Debug, format!("Terminal size: {}x{}", width, height));
+        } else {
+            self.log(LogLevel::Debug, "Failed to get terminal size");
+        }
+        
+        Ok(false)
+    }
+
+    pub(crate) fn set_active_tab(&mut self, tab_index: usize) {
+        if tab_index != self.active_tab {
+            let previous = self.active_tab;
+            self.tab_history.retain(|&index| index != previous && index != tab_index);
+            self.tab_history.push(previous);
+            self.active_tab = tab_index;
+        }
+    }
+
+    pub(crate) fn alternate_tab(&self) -> Option<usize> {
+        self.tab_history.last().copied()
+    }
+
+    pub(crate) fn toggle_alternate_tab(&mut self) {
+        match self.alternate_tab().filter(|&index| index < self.tabs.len()) {
+            Some(index) => {
+                self.set_active_tab(index);
+                self.update_current_tab_info();
+            }
+            None => self.warn("No alternate tab"),
+        }
+    }
+
+    pub(crate) fn switch_to_tab(&mut self, tab_index: usize) {
+        if tab_index < self.tabs.len() {
+            self.set_active_tab(tab_index);
+            self.log(LogLevel::Debug, format!("Switched to tab {}", tab_index + 1));
+            self.update_current_tab_info();
+        } else {
+            self.warn(format!("Tab {} does not exist", tab_index + 1));
+        }
+    }
+
+    pub(crate) fn close_tab(&mut self) {
+        self.close_tab_at(self.active_tab);
+    }
+
+    pub(crate) fn close_tab_at(&mut self, tab_index: usize) {
+        self.remember_closed_tab(tab_index);
+        self.remove_tab(tab_index);
+    }
+
+    pub(crate) fn tab_command(&mut self, argument: &str) {
+        match argument.parse::<usize>() {
+            Ok(number) if number >= 1 => self.switch_to_tab(number - 1),
+            _ => self.error(format!("Invalid tab number: {}", argument)),
+        }
+    }
+
+    pub(crate) fn tab_new_command(&mut self, argument: &str, background: bool) -> io::Result<()> {
+        if argument.is_empty() {
+            if background {
+                self.error("Missing file name");
+            } else {
+                self.new_tab();
+                self.update_current_tab_info();
+            }
+            return Ok(());
+        }
+        match self.resolve_command_path(argument) {
+            Ok(path) if background => self.open_file_in_background(&path),
+            Ok(path) => self.open_file(&path),
+            Err(e) => {
+                self.error(e);
+                Ok(())
+            }
+        }
+    }
+
+    pub(crate) fn open_file_in_background(&mut self, path: &Path) -> io::Result<()> {
+        let file = Self::absolute_path(path).to_string_lossy().into_owned();
+        if let Some(open) = self.tabs.iter().position(|tab| tab.current_file.as_deref() == Some(file.as_str())) {
+            self.tabs[open].flashed = Some(Instant::now());
+            self.info(format!("{} is already open in tab {}", self.display_path(&file), open + 1));
+            return Ok(());
+        }
+        let (active, history, mode) = (self.active_tab, self.tab_history.clone(), self.mode);
+        self.open_file(path)?;
+        if self.active_tab == active {
+            return Ok(());
+        }
+        let opened = self.active_tab;
+        self.active_tab = active;
+        self.tab_history = history;
+        self.mode = mode;
+        self.update_current_tab_info();
+        self.tabs[opened].flashed = Some(Instant::now());
+        if self.prompt.is_none() {
+            self.info(format!("Opened {} in tab {}", self.display_path(&file), opened + 1));
+        }
+        Ok(())
+    }
+
+    pub fn expire_tab_flashes(&mut self, now: Instant) -> bool {
+        let mut expired = false;
+        for tab in &mut self.tabs {
+            if tab.flashed.is_some_and(|flashed| now.saturating_duration_since(flashed) >= Self::TAB_FLASH) {
+                tab.flashed = None;
+                expired = true;
+            }
+        }
+        expired
+    }
+
+    pub(crate) fn close_tab_command(&mut self, argument: &str) {
+        if argument.is_empty() {
+            self.close_tab();
+            return;
+        }
+        match argument.parse::<usize>() {
+            Ok(number) if number >= 1 && number <= self.tabs.len() => self.close_tab_at(number - 1),
+            Ok(number) => self.warn(format!("Tab {} does not exist", number)),
+            Err(_) => self.error(format!("Invalid tab number: {}", argument)),
+        }
+    }
+
+    pub(crate) fn close_other_tabs(&mut self, force: bool) {
+        if self.tabs.len() == 1 {
+            self.info("Already only one tab");
+            return;
+        }
+        let modified: Vec<String> = (0..self.tabs.len())
+            .filter(|&index| index != self.active_tab && self.tabs[index].is_modified())
+            .map(|index| Self::tab_title(&self.tabs[index], index))
+            .collect();
+        if modified.is_empty() || force {
+            self.remove_other_tabs(false);
+        } else {
+            self.confirm(PromptAction::CloseOtherTabs, &format!("Save changes to {}?", modified.join(", ")), "Save", "Discard");
+        }
+    }
+
+    pub(crate) fn remove_other_tabs(&mut self, save: bool) {
+        let (mut closed, mut kept) = (0, 0);
+        for index in (0..self.tabs.len()).rev() {
+            if index == self.active_tab {
+                continue;
+            }
+            let tab = &self.tabs[index];
+            if save && tab.is_modified() {
+                let Some(file) = tab.current_file.clone().filter(|_| !tab.read_only && !tab.private) else {
+                    kept += 1;
+                    continue;
+                };
+                self.start_save(index, PathBuf::from(file));
+                if let Err(e) = self.wait_for_save(index) {
+                    self.error(format!("Save failed: {}", e));
+                    kept += 1;
+                    continue;
+                }
+            }
+            self.close_tab_at(index);
+            closed += 1;
+        }
+        if kept > 0 {
+            self.warn(format!("Closed {} tab{}; kept {} with unsaved changes", closed, if closed == 1 { "" } else { "s" }, kept));
+        } else {
+            self.info(format!("Closed {} tab{}", closed, if closed == 1 { "" } else { "s" }));
+        }
+    }
+
+    pub(crate) fn remove_tab(&mut self, closed: usize) {
+        if let Err(e) = self.wait_for_save(closed) {
+            self.error(format!("Save failed: {}", e));
+        }
+        self.remember_position(closed);
+        let private = self.tabs[closed].private;
+        if private {
+            self.wipe_private_tab(closed);
+        }
+        if self.tabs.len() == 1 {
+            self.tabs[0] = Tab::new();
+            self.tab_history.clear();
+        } else {
+            self.tabs.remove(closed);
+            self.tab_history = self.tab_history.iter()
+                .filter(|&&index| index != closed)
+                .map(|&index| if index > closed { index - 1 } else { index })
+                .collect();
+            if self.active_tab == closed {
+                self.active_tab = self.tab_history.pop().unwrap_or(closed.saturating_sub(1));
+            } else if self.active_tab > closed {
+                self.active_tab -= 1;
+            }
+            if self.tab_history.is_empty() && self.tabs.len() > 1 {
+                self.tab_history.push(if self.active_tab > 0 { self.active_tab - 1 } else { 1 });
+            }
+        }
+        if private && !self.tabs.iter().any(|tab| tab.private) {
+            self.clipboard_context.leave_private();
+        }
+        self.update_current_tab_info();
+    }
+
+    pub(crate) fn ensure_cursor_in_bounds(&mut self) {
+        let tab = &mut self.tabs[self.active_tab];
+        if tab.content.is_empty() {
+            tab.content.push(String::new());
+        }
+        tab.cursor_position.1 = tab.cursor_position.1.min(tab.content.len() - 1);
+        let line_length = tab.content[tab.cursor_position.1].len();
+        tab.cursor_position.0 = tab.cursor_position.0.min(line_length);
+    }
+
+    pub(crate) fn next_tab(&mut self) {
+        if !self.tabs.is_empty() {
+            self.set_active_tab((self.active_tab + 1) % self.tabs.len());
+            self.update_current_tab_info();
+        }
+    }
+
+    pub(crate) fn new_tab(&mut self) {
+        if self.tabs.len() == 1 && self.tabs[0].content == vec![String::new()] && self.tabs[0].current_file.is_none() {
+            self.active_tab = 0;
+        } else {
+            self.tabs.push(Tab::new());
+            self.set_active_tab(self.tabs.len() - 1);
+        }
+    }
+
+    pub(crate) fn previous_tab(&mut self) {
+        if !self.tabs.is_empty() {
+            self.set_active_tab((self.active_tab + self.tabs.len() - 1) % self.tabs.len());
+            self.update_current_tab_info();
+        }
+    }
+
+    pub(crate) fn update_current_tab_info(&mut self) {
+        let tab = &self.tabs[self.active_tab];
+        self.content = tab.content.clone();
+        self.cursor_position = tab.cursor_position;
+        self.scroll_offset = tab.scroll_offset;
+        self.horizontal_scroll = tab.horizontal_scroll;
+        self.current_file = tab.current_file.clone();
+        self.syntax = tab.syntax.clone();
+    }
+
+    pub(crate) fn absolute_path(path: &Path) -> PathBuf {
+        if let Ok(canonical) = fs::canonicalize(path) {
+            return canonical;
+        }
+        let absolute = if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            env::current_dir().map(|dir| dir.join(path)).unwrap_or_else(|_| path.to_path_buf())
+        };
+        match (absolute.parent().and_then(|parent| fs::canonicalize(parent).ok()), absolute.file_name()) {
+            (Some(parent), Some(name)) => parent.join(name),
+            _ => absolute,
+        }
+    }
+
+    pub(crate) fn project_root() -> Option<PathBuf> {
+        let cwd = env::current_dir().ok()?;
+        let cwd = fs::canonicalize(&cwd).unwrap_or(cwd);
+        let root = cwd.ancestors().find(|dir| dir.join(".git").exists()).unwrap_or(&cwd);
+        Some(root.to_path_buf())
+    }
+
+    pub(crate) fn display_path(&self, path: &str) -> String {
+        let path = Path::new(path);
+        if let Some(relative) = self.project_root.as_ref().and_then(|root| path.strip_prefix(root).ok()) {
+            if !relative.as_os_str().is_empty() {
+                return relative.to_string_lossy().into_owned();
+            }
+        }
+        if let Some(relative) = dirs::home_dir().and_then(|home| path.strip_prefix(home).ok().map(Path::to_path_buf)) {
+            return Path::new("~").join(relative).to_string_lossy().into_owned();
+        }
+        path.to_string_lossy().into_owned()
+    }
+
+    pub(crate) fn canonical_path(path: &Path) -> String {
+        fs::canonicalize(path)
+            .unwrap_or_else(|_| path.to_path_buf())
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    pub(crate) fn glob_match(pattern: &str, text: &str) -> bool {
+        let pattern: Vec<char> = pattern.chars().collect();
+        let text: Vec<char> = text.chars().collect();
+        let (mut p, mut t) = (0, 0);
+        let mut backtrack: Option<(usize, usize)> = None;
+        while t < text.len() {
+            if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+                p += 1;
+                t += 1;
+            } else if p < pattern.len() && pattern[p] == '*' {
+                backtrack = Some((p, t));
+                p += 1;
+            } else if let Some((star_p, star_t)) = backtrack {
+                p = star_p + 1;
+                t = star_t + 1;
+                backtrack = Some((star_p, star_t + 1));
+            } else {
+                return false;
+            }
+        }
+        pattern[p..].iter().all(|&c| c == '*')
+    }
+
+    pub(crate) fn path_matches(patterns: &[String], path: &Path) -> bool {
+        let full = path.to_string_lossy();
+        let name = path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+        patterns.iter().any(|pattern| {
+            if pattern.contains('/') {
+                Self::glob_match(pattern, &full)
+            } else {
+                Self::glob_match(pattern, &name)
+            }
+        })
+    }
+
+    pub(crate) fn remember_position(&mut self, tab_index: usize) {
+        let tab = &self.tabs[tab_index];
+        if !self.settings.restore_position || tab.private {
+            return;
+        }
+        let Some(file) = &tab.current_file else {
+            return;
+        };
+        let path = Path::new(file);
+        if !path.exists() || Self::path_matches(&self.settings.restore_position_exclude, path) {
+            return;
+        }
+        self.positions.record(StoredPosition {
+            path: Self::canonical_path(path),
+            cursor_position: tab.cursor_position,
+            scroll_offset: tab.scroll_offset,
+            changes: tab.changes.clone(),
+        });
+        if let Some(data_dir) = &self.paths.data_dir {
+            if let Err(e) = self.positions.save(&data_dir.join("positions.json")) {
+                self.warn(format!("Failed to save cursor positions: {}", e));
+            }
+        }
+    }
+
+    pub(crate) fn store_bookmarks(&mut self, tab_index: usize) {
+        let tab = &self.tabs[tab_index];
+        let Some(file) = tab.current_file.as_ref().filter(|_| !tab.private) else {
+            return;
+        };
+        let path = Path::new(file);
+        if !path.exists() || !self.bookmark_store.set(Self::canonical_path(path), &self.tabs[tab_index].bookmarks) {
+            return;
+        }
+        if let Some(data_dir) = &self.paths.data_dir {
+            if let Err(e) = self.bookmark_store.save(&data_dir.join("bookmarks.json")) {
+                self.warn(format!("Failed to save bookmarks: {}", e));
+            }
+        }
+    }
+
+    pub(crate) fn toggle_bookmark(&mut self) {
+        let tab = &mut self.tabs[self.active_tab];
+        let line = tab.cursor_position.1;
+        if tab.toggle_bookmark(line) {
+            self.info(format!("Bookmarked line {}", line + 1));
+        } else {
+            self.info(format!("Removed bookmark on line {}", line + 1));
+        }
+        self.store_bookmarks(self.active_tab);
+    }
+
+    pub(crate) fn jump_to_bookmark(&mut self, forward: bool) {
+        let tab = &self.tabs[self.active_tab];
+        match tab.next_bookmark(tab.cursor_position.1, forward) {
+            Some(line) => self.goto_location(line + 1, None),
+            None => self.info("No bookmarks"),
+        }
+    }
+
+    pub(crate) fn jump_to_change(&mut self, older: bool) {
+        let tab = &mut self.tabs[self.active_tab];
+        if tab.changes.is_empty() {
+            self.info("Change list is empty");
+            return;
+        }
+        if older && tab.change_index == 0 {
+            self.info("At start of change list");
+            return;
+        }
+        if !older && tab.change_index + 1 >= tab.changes.len() {
+            self.info("At end of change list");
+            return;
+        }
+        tab.change_index = if older { tab.change_index - 1 } else { tab.change_index + 1 };
+        let position = tab.changes[tab.change_index];
+        self.goto_change(position);
+    }
+
+    pub(crate) fn jump_to_last_change(&mut self) {
+        match self.tabs[self.active_tab].changes.last() {
+            Some(&position) => self.goto_change(position),
+            None => self.info("Change list is empty"),
+        }
+    }
+
+    fn goto_change(&mut self, position: (usize, usize)) {
+        let tab = &mut self.tabs[self.active_tab];
+        tab.cursor_position = tab.clamp_position(position);
+        let line = tab.cursor_position.1;
+        tab.open_folds_at(line);
+        self.ensure_cursor_visible();
+        self.adjust_horizontal_scroll();
+    }
+
+    pub(crate) fn open_bookmarks_picker(&mut self) {
+        let mut items = Vec::new();
+        let mut locations = Vec::new();
+        for (tab_index, tab) in self.tabs.iter().enumerate() {
+            let title = Self::tab_title(tab, tab_index);
+            for &line in &tab.bookmarks {
+                let text = tab.content.get(line).map_or("", |text| text.trim());
+                items.push(format!("{}:{}: {}", title, line + 1, text));
+                locations.push(PickerLocation::Tab(tab_index, line));
+            }
+        }
+        if items.is_empty() {
+            self.info("No bookmarks");
+            return;
+        }
+        let mut picker = Picker::new(PickerKind::Bookmarks, "Bookmarks", items);
+        picker.locations = locations;
+        self.picker = Some(picker);
+        self.mode = Mode::Picker;
+    }
+
+    pub(crate) fn goto_picker_location(&mut self, location: PickerLocation) -> io::Result<()> {
+        let line = match location {
+            PickerLocation::Tab(tab_index, line) => {
+                if tab_index >= self.tabs.len() {
+                    return Ok(());
+                }
+                self.set_active_tab(tab_index);
+                line
+            }
+            PickerLocation::File(path, line) => {
+                let canonical = Self::canonical_path(&path);
+                let open = self.tabs.iter().position(|tab| {
+                    tab.current_file.as_ref().is_some_and(|file| Self::canonical_path(Path::new(file)) == canonical)
+                });
+                match open {
+                    Some(tab_index) => self.set_active_tab(tab_index),
+                    None => self.open_file(&path)?,
+                }
+                line
+            }
+        };
+        self.goto_location(line + 1, None);
+        Ok(())
+    }
+
+    pub(crate) fn begin_undo_group(&mut self) {
+        self.undo_group_depth += 1;
+        if self.undo_group_depth == 1 {
+            self.undo_group_tab = None;
+        }
+    }
+
+    pub(crate) fn end_undo_group(&mut self) {
+        self.undo_group_depth = self.undo_group_depth.saturating_sub(1);
+        if self.undo_group_depth == 0 {
+            self.undo_group_tab = None;
+        }
+    }
+
+    pub(crate) fn break_undo_group(&mut self) {
+        self.undo_group_tab = None;
+    }
+
+    pub(crate) fn save_state(&mut self) {
+        self.save_tab_state(self.active_tab);
+    }
+
+    pub(crate) fn save_tab_state(&mut self, tab_index: usize) {
+        self.end_mouse_selection();
+        let cursor = self.tabs[tab_index].cursor_position;
+        self.tabs[tab_index].record_change(cursor);
+        let cursor_line = cursor.1;
+        self.tabs[tab_index].open_folds_at(cursor_line);
+        if self.block_insert.is_some() || (self.undo_group_depth > 0 && self.undo_group_tab == Some(tab_index)) {
+            self.tabs[tab_index].mark_modified();
+            return;
+        }
+        if self.undo_group_depth > 0 {
+            self.undo_group_tab = Some(tab_index);
+        }
+        self.tabs[tab_index].push_undo();
+        self.trim_undo_history(tab_index);
+    }
+
+    pub(crate) fn refresh_modified(&mut self) {
+        if self.mode == Mode::Insert {
+            return;
+        }
+        for tab in &mut self.tabs {
+            tab.refresh_modified();
+        }
+    }
+
+    pub fn undo(&mut self) {
+        self.break_undo_group();
+        self.tabs[self.active_tab].undo();
+    }
+
+    pub fn redo(&mut self) {
+        self.break_undo_group();
+        self.tabs[self.active_tab].redo();
+    }
+
+    pub(crate) fn mouse_selection(&self) -> Option<((usize, usize), (usize, usize))> {
+        let tab = &self.tabs[self.active_tab];
+        let valid = |(x, y): (usize, usize)| tab.content.get(y).is_some_and(|line| line.is_char_boundary(x));
+        self.mouse_selection_start.zip(self.mouse_selection_end)
+            .filter(|&(start, end)| start != end && valid(start) && valid(end))
+    }
+
+    pub(crate) fn copy_selection_to_clipboard(&mut self) {
+        if let Some((start, end)) = self.mouse_selection() {
+            let selected_text = self.tabs[self.active_tab].selection_text(start, end);
+            if self.set_clipboard(selected_text) {
+                self.info("Text copied to clipboard");
+            }
+        }
+    }
+
+    pub(crate) fn set_clipboard(&mut self, text: String) -> bool {
+        if self.tabs[self.active_tab].private {
+            self.clipboard_context.set_private(text);
+            return true;
+        }
+        match self.clipboard_context.set_contents(text) {
+            Ok(()) => true,
+            Err(e) => {
+                self.report(PhantomError::Clipboard(e.to_string()));
+                false
+            }
+        }
+    }
+
+    pub(crate) fn start_mouse_selection(&mut self, x: u16, y: u16) {
+        self.mouse_drag = None;
+        let tab = &self.tabs[self.active_tab];
+        let position = self.screen_to_content_position(x, y).map(|position| tab.clamp_position(position));
+        self.mouse_selection_start = position;
+        self.mouse_selection_end = position;
+    }
+
+    pub(crate) fn extend_mouse_selection(&mut self, x: u16, y: u16) {
+        let Some(position) = self.screen_to_content_position(x, y) else {
+            return;
+        };
+        let tab = &self.tabs[self.active_tab];
+        self.mouse_drag = None;
+        self.mouse_selection_start = Some(tab.cursor_position);
+        self.mouse_selection_end = Some(tab.clamp_position(position));
+    }
+
+    pub(crate) fn update_mouse_selection(&mut self, x: u16, y: u16) {
+        if self.mouse_selection_start.is_none() {
+            return;
+        }
+        let (x, y) = self.clamp_to_text_area(x, y);
+        if let Some(position) = self.screen_to_content_position(x, y) {
+            self.mouse_selection_end = Some(self.tabs[self.active_tab].clamp_position(position));
+        }
+    }
+
+    pub(crate) fn finish_mouse_selection(&mut self) {
+        self.mouse_drag = None;
+        let Some((start, end)) = self.mouse_selection() else {
+            self.end_mouse_selection();
+            return;
+        };
+        if !self.settings.copy_on_select || self.tabs[self.active_tab].private {
+            return;
+        }
+        let selected_text = self.tabs[self.active_tab].selection_text(start, end);
+        if let Err(e) = self.clipboard_context.set_primary(selected_text) {
+            self.report(PhantomError::Clipboard(e.to_string()));
+        }
+    }
+
+    pub(crate) fn drag_mouse_selection(&mut self, x: u16, y: u16) {
+        if self.mouse_selection_start.is_none() {
+            return;
+        }
+        self.mouse_drag = Some((x, y));
+        self.auto_scroll_selection();
+        self.update_mouse_selection(x, y);
+    }
+
+    pub(crate) fn auto_scroll_selection(&mut self) -> bool {
+        let Some((x, y)) = self.mouse_drag else {
+            return false;
+        };
+        let area = self.layout.text_area();
+        let rows = if y < area.y {
+            -((area.y - y) as isize)
+        } else if y >= area.bottom() {
+            (y + 1 - area.bottom()) as isize
+        } else {
+            0
+        };
+        let columns = if x < area.x {
+            -((area.x - x) as isize)
+        } else if x >= area.right() {
+            (x + 1 - area.right()) as isize
+        } else {
+            0
+        };
+        if rows == 0 && columns == 0 {
+            return false;
+        }
+        let editor_height = self.get_editor_height();
+        let editor_width = self.get_editor_width();
+        let tab_width = self.option("tabstop").number();
+        let wrap = self.wrap_width().is_some();
+        let tab = &mut self.tabs[self.active_tab];
+        let (scroll_offset, horizontal_scroll) = (tab.scroll_offset, tab.horizontal_scroll);
+        if rows != 0 {
+            let max_scroll = tab.row_count().saturating_sub(editor_height);
+            let scroll = tab.line_to_row(tab.scroll_offset).saturating_add_signed(rows).min(max_scroll);
+            tab.scroll_offset = tab.row_to_line(scroll);
+        }
+        if columns != 0 && !wrap {
+            let end = (tab.scroll_offset + editor_height).min(tab.content.len());
+            let widest = tab.content[tab.scroll_offset.min(end)..end].iter().map(|line| line_width(line, tab_width)).max().unwrap_or(0);
+            let max_scroll = (widest + 1).saturating_sub(editor_width).max(tab.horizontal_scroll);
+            tab.horizontal_scroll = tab.horizontal_scroll.saturating_add_signed(columns).min(max_scroll);
+        }
+        (tab.scroll_offset, tab.horizontal_scroll) != (scroll_offset, horizontal_scroll)
+    }
+
+    pub fn poll_mouse_drag(&mut self) -> bool {
+        let Some((x, y)) = self.mouse_drag else {
+            return false;
+        };
+        if !self.auto_scroll_selection() {
+            return false;
+        }
+        self.update_mouse_selection(x, y);
+        true
+    }
+
+    pub fn auto_scrolling(&self) -> bool {
+        self.mouse_drag.is_some()
+    }
+
+    pub fn frame_interval(&self) -> Duration {
+        Duration::from_secs(1) / self.settings.max_fps.clamp(1, 1000) as u32
+    }
+
+    pub(crate) fn end_mouse_selection(&mut self) {
+        self.mouse_selection_start = None;
+        self.mouse_selection_end = None;
+        self.mouse_drag = None;
+        self.mouse_scroll_row = None;
+    }
+
+    pub(crate) fn record_recent_file(&mut self, path: &Path) {
+        let canonical = Self::canonical_path(path);
+        if !self.settings.recent_files || Self::path_matches(&self.settings.recent_files_exclude, Path::new(&canonical)) {
+            return;
+        }
+        self.recent_files.record(canonical);
+        self.save_recent_files();
+    }
+
+    pub(crate) fn save_recent_files(&mut self) {
+        if let Some(data_dir) = &self.paths.data_dir {
+            if let Err(e) = self.recent_files.save(&data_dir.join("recent.json")) {
+                self.warn(format!("Failed to save recent files: {}", e));
+            }
+        }
+    }
+
+    pub(crate) fn open_oldfiles_picker(&mut self) {
+        if self.recent_files.prune() {
+            self.save_recent_files();
+        }
+        if self.recent_files.entries.is_empty() {
+            self.info("No recent files");
+            return;
+        }
+        self.picker = Some(Picker::new(PickerKind::OldFiles, "Recent Files", self.recent_files.entries.clone()));
+        self.mode = Mode::Picker;
+    }
+
+    pub(crate) fn handle_picker_mode(&mut self, key: KeyEvent) -> io::Result<bool> {
+        let Some(picker) = &mut self.picker else {
+            self.mode = Mode::Normal;
+            return Ok(false);
+        };
+        match key.code {
+            KeyCode::Esc => {
+                self.picker = None;
+                self.mode = Mode::Normal;
+            }
+            KeyCode::Up => picker.up(),
+            KeyCode::Down => picker.down(),
+            KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::CONTROL) => picker.up(),
+            KeyCode::Char('n') if key.modifiers.contains(KeyModifiers::CONTROL) => picker.down(),
+            KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) && picker.kind == PickerKind::Todos => {
+                if let Some(scope) = self.todo_scope.clone() {
+                    self.open_todos(scope);
+                }
+            }
+            KeyCode::Backspace => {
+                picker.query.pop();
+                picker.filter();
+            }
+            KeyCode::Char(c) => {
+                picker.query.push(c);
+                picker.filter();
+            }
+            KeyCode::Enter => {
+                let kind = picker.kind;
+                let selected = picker.selected().map(String::from);
+                let location = picker.selected_location();
+                self.picker = None;
+                self.mode = Mode::Normal;
+                if let Some(selected) = selected {
+                    match kind {
+                        PickerKind::OldFiles => self.open_file(Path::new(&selected))?,
+                        PickerKind::ClosedTabs => self.reopen_picked_closed_tab(&selected),
+                        PickerKind::Digraphs => {}
+                        PickerKind::Options => self.edit_option(&selected),
+                        PickerKind::Actions => {
+                            let action = selected.split_whitespace().next().unwrap_or_default().to_string();
+                            return self.execute_action(&action);
+                        }
+                        PickerKind::Bookmarks | PickerKind::Todos | PickerKind::Matches => {
+                            if let Some(location) = location {
+                                self.goto_picker_location(location)?;
+                            }
+                        }
+                        PickerKind::Tasks => self.cancel_picked_task(&selected),
+                        PickerKind::Characters => self.goto_picked_character(&selected),
+                        PickerKind::TabSearch => {
+                            if let Some(location) = location {
+                                self.goto_tab_search_match(location)?;
+                            }
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    pub(crate) fn confirm(&mut self, action: PromptAction, message: &str, yes: &str, no: &str) {
+        self.prompt = Some(Prompt::Confirm(ConfirmPrompt::new(action, message, yes, no)));
+    }
+
+    pub(crate) fn input(&mut self, action: InputAction, label: &str, default: &str) {
+        let mut prompt = InputPrompt::new(action, label, default);
+        prompt.warning = self.validate_input(&prompt.action, &prompt.input.text);
+        self.prompt = Some(Prompt::Input(prompt));
+    }
+
+    pub(crate) fn answer_prompt(&mut self, answer: PromptAnswer) {
+        if let Some(Prompt::Confirm(prompt)) = self.prompt.take() {
+            self.prompt_result = Some(PromptResult::Confirm(prompt.action, answer));
+        }
+    }
+
+    pub(crate) fn handle_prompt_key(&mut self, key: KeyEvent) {
+        match &mut self.prompt {
+            Some(Prompt::Confirm(prompt)) => {
+                if let Some(answer) = prompt.handle_key(key) {
+                    self.answer_prompt(answer);
+                }
+            }
+            Some(Prompt::Input(prompt)) => {
+                match key.code {
+                    KeyCode::Esc => self.prompt = None,
+                    KeyCode::Enter => {
+                        if let Some(Prompt::Input(prompt)) = self.prompt.take() {
+                            self.prompt_result = Some(PromptResult::Input(prompt.action, prompt.input.text));
+                        }
+                    }
+                    KeyCode::Tab => prompt.complete(),
+                    _ => {
+                        prompt.completions.clear();
+                        prompt.input.handle_key(key, None, &mut self.clipboard_context);
+                    }
+                }
+                if let Some(Prompt::Input(prompt)) = &self.prompt {
+                    let warning = self.validate_input(&prompt.action, &prompt.input.text);
+                    if let Some(Prompt::Input(prompt)) = &mut self.prompt {
+                        prompt.warning = warning;
+                    }
+                }
+            }
+            None => {}
+        }
+    }
+
+    pub(crate) fn input_path(text: &str) -> PathBuf {
+        match text.strip_prefix("~/") {
+            Some(rest) => dirs::home_dir().map(|home| home.join(rest)).unwrap_or_else(|| PathBuf::from(text)),
+            None => PathBuf::from(text),
+        }
+    }
+
+    pub(crate) fn validate_input(&self, action: &InputAction, text: &str) -> Option<String> {
+        let text = text.trim();
+        if *action == InputAction::Expression {
+            return self.evaluate_expression(text).err().filter(|_| !text.is_empty());
+        }
+        if matches!(action, InputAction::SurroundTag(_) | InputAction::RenameWord) {
+            return None;
+        }
+        if text.is_empty() {
+            return Some("Enter a file name".to_string());
+        }
+        let path = Self::input_path(text);
+        match action {
+            InputAction::SaveAs { .. } if path.is_dir() => Some("Target is a directory".to_string()),
+            InputAction::SaveAs { .. } if path.exists() => Some("File exists and will be overwritten".to_string()),
+            InputAction::CreateFile if path.exists() => Some("Already exists".to_string()),
+            InputAction::Rename(from) if path.exists() && path != *from => Some("Target already exists".to_string()),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn resolve_prompt(&mut self) -> io::Result<bool> {
+        let Some(result) = self.prompt_result.take() else {
+            return Ok(false);
+        };
+        match result {
+            PromptResult::Confirm(PromptAction::Locked(file), answer) => {
+                let edit = match answer {
+                    PromptAnswer::Yes => Some(false),
+                    PromptAnswer::No => Some(true),
+                    PromptAnswer::Cancel => None,
+                };
+                self.resolve_lock_prompt(&file, edit);
+                Ok(false)
+            }
+            PromptResult::Confirm(_, PromptAnswer::Cancel) => Ok(false),
+            PromptResult::Confirm(PromptAction::Quit, answer) => {
+                if answer == PromptAnswer::Yes {
+                    match self.tabs[self.active_tab].current_file.clone() {
+                        Some(file) => return Ok(self.save_as_path(Path::new(&file), true)),
+                        None => {
+                            self.save_as(true);
+                            return Ok(false);
+                        }
+                    }
+                }
+                Ok(self.quit_tab())
+            }
+            PromptResult::Confirm(PromptAction::OpenDroppedFiles { paths, .. }, PromptAnswer::Yes) => {
+                self.open_dropped_files(&paths);
+                Ok(false)
+            }
+            PromptResult::Confirm(PromptAction::OpenDroppedFiles { text, .. }, PromptAnswer::No) => {
+                self.paste_text(&text);
+                Ok(false)
+            }
+            PromptResult::Confirm(PromptAction::CloseOtherTabs, answer) => {
+                self.remove_other_tabs(answer == PromptAnswer::Yes);
+                Ok(false)
+            }
+            PromptResult::Confirm(PromptAction::CreateDirectory { path, quit }, PromptAnswer::Yes) => {
+                if let Err(e) = self.create_parent_dirs(&path) {
+                    self.error(e);
+                    return Ok(false);
+                }
+                Ok(self.save_as_path(&path, quit))
+            }
+            PromptResult::Confirm(PromptAction::CreateDirectory { .. }, PromptAnswer::No) => {
+                self.warn("Not saved");
+                Ok(false)
+            }
+            PromptResult::Confirm(PromptAction::PermissionDenied { path, quit }, PromptAnswer::Yes) => {
+                self.privileged_write = Some(PrivilegedWrite { tab_index: self.active_tab, path, quit });
+                Ok(false)
+            }
+            PromptResult::Confirm(PromptAction::PermissionDenied { path, quit }, PromptAnswer::No) => {
+                self.input(InputAction::SaveAs { quit }, "Save as", &path.to_string_lossy());
+                Ok(false)
+            }
+            PromptResult::Confirm(PromptAction::ClearUndo(tab_index), PromptAnswer::Yes) => {
+                self.clear_undo(tab_index);
+                Ok(false)
+            }
+            PromptResult::Confirm(PromptAction::ClearUndo(_), PromptAnswer::No) => Ok(false),
+            PromptResult::Confirm(PromptAction::Overwrite { path, quit }, PromptAnswer::Yes) => Ok(self.save_as_path(&path, quit)),
+            PromptResult::Confirm(PromptAction::Overwrite { path, quit }, PromptAnswer::No) => {
+                self.input(InputAction::SaveAs { quit }, "Save as", &path.to_string_lossy());
+                Ok(false)
+            }
+            PromptResult::Input(action, text) => {
+                let text = text.trim();
+                if text.is_empty() {
+                    return Ok(false);
+                }
+                if action == InputAction::Expression {
+                    self.insert_expression_result(text);
+                    return Ok(false);
+                }
+                if let InputAction::SurroundTag(bounds) = action {
+                    self.surround_with_tag(bounds, text);
+                    return Ok(false);
+                }
+                if action == InputAction::RenameWord {
+                    self.apply_rename(text);
+                    return Ok(false);
+                }
+                let path = Self::input_path(text);
+                match action {
+                    InputAction::SaveAs { quit } => {
+                        if path.is_dir() {
+                            self.error(format!("{} is a directory", path.display()));
+                            self.input(InputAction::SaveAs { quit }, "Save as", text);
+                            return Ok(false);
+                        }
+                        if path.exists() {
+                            let name = self.display_path(&Self::absolute_path(&path).to_string_lossy());
+                            self.confirm(PromptAction::Overwrite { path, quit }, &format!("{} already exists. Overwrite it?", name), "Overwrite", "Choose another name");
+                            return Ok(false);
+                        }
+                        return Ok(self.save_as_path(&path, quit));
+                    }
+                    InputAction::CreateFile => {
+                        let result = if text.ends_with('/') {
+                            fs::create_dir_all(&path)
+                        } else {
+                            path.parent().map_or(Ok(()), fs::create_dir_all)
+                                .and_then(|_| fs::OpenOptions::new().write(true).create_new(true).open(&path).map(|_| ()))
+                        };
+                        match result {
+                            Ok(()) => self.info(format!("Created {}", self.display_path(&Self::absolute_path(&path).to_string_lossy()))),
+                            Err(e) => self.error(format!("Create failed: {}", e)),
+                        }
+                        self.refresh_file_selector();
+                    }
+                    InputAction::Expression | InputAction::SurroundTag(_) | InputAction::RenameWord => {}
+                    InputAction::Rename(from) => {
+                        if path.exists() && path != from {
+                            self.error(format!("{} already exists", path.display()));
+                            return Ok(false);
+                        }
+                        match fs::rename(&from, &path) {
+                            Ok(()) => {
+                                let from = Self::absolute_path(&from).to_string_lossy().into_owned();
+                                let to = Self::absolute_path(&path).to_string_lossy().into_owned();
+                                for tab in &mut self.tabs {
+                                    if tab.current_file.as_deref() == Some(from.as_str()) {
+                                        tab.current_file = Some(to.clone());
+                                    }
+                                }
+                                self.info(format!("Renamed to {}", self.display_path(&to)));
+                            }
+                            Err(e) => self.error(format!("Rename failed: {}", e)),
+                        }
+                        self.refresh_file_selector();
+                    }
+                }
+                Ok(false)
+            }
+        }
+    }
+
+    pub fn poll_file_selector(&mut self) -> bool {
+        let listing = self.file_selector.as_ref()
+            .filter(|file_selector| file_selector.reader.is_some() && file_selector.progress.is_none())
+            .map(|file_selector| format!("Listing {}", self.display_path(&file_selector.current_dir.to_string_lossy())));
+        if let Some(label) = listing {
+            let progress = self.start_task(label, true);
+            if let Some(file_selector) = &mut self.file_selector {
+                file_selector.progress = Some(progress);
+            }
+        }
+        self.file_selector.as_mut().is_some_and(FileSelector::poll)
+    }
+
+    pub fn file_selector_loading(&self) -> bool {
+        self.file_selector.as_ref().is_some_and(|file_selector| file_selector.reader.is_some())
+    }
+
+    pub fn watch_sidebar(&mut self, now: Instant) -> bool {
+        match &mut self.file_selector {
+            Some(file_selector) if self.show_sidebar || self.mode == Mode::FileSelect => file_selector.watch(now),
+            _ => false,
+        }
+    }
+
+    pub(crate) fn refresh_file_selector(&mut self) {
+        if let Some(file_selector) = &mut self.file_selector {
+            file_selector.reload(Instant::now());
+        }
+    }
+
+    pub(crate) fn missing_parent_dir(&self, path: &Path) -> Option<PathBuf> {
+        if self.dry_run_writes.is_some() || self.tabs[self.active_tab].read_only {
+            return None;
+        }
+        Self::absolute_path(path).parent().filter(|parent| !parent.exists()).map(Path::to_path_buf)
+    }
+
+    pub(crate) fn create_parent_dirs(&mut self, path: &Path) -> Result<(), String> {
+        match self.missing_parent_dir(path) {
+            Some(dir) => fs::create_dir_all(&dir)
+                .map_err(|e| format!("Could not create directory '{}': {}", self.display_path(&dir.to_string_lossy()), e)),
+            None => Ok(()),
+        }
+    }
+
+    pub(crate) fn save_as_path(&mut self, path: &Path, quit: bool) -> bool {
+        if let Some(dir) = self.missing_parent_dir(path) {
+            let message = format!("Directory '{}' does not exist. Create it?", self.display_path(&dir.to_string_lossy()));
+            self.confirm(PromptAction::CreateDirectory { path: path.to_path_buf(), quit }, &message, "Create", "Don't save");
+            return false;
+        }
+        if self.tabs[self.active_tab].private {
+            self.save_private(path, quit);
+            return false;
+        }
+        let result = if quit { self.save_and_wait(Some(path)) } else { self.save_file(Some(path)) };
+        if let Err(e) = result {
+            self.save_failed(self.active_tab, path, e, quit);
+            return false;
+        }
+        quit && self.quit_tab()
+    }
+
+    pub(crate) fn save_as(&mut self, quit: bool) {
+        let dir = env::current_dir().map(|dir| format!("{}/", dir.display())).unwrap_or_default();
+        self.input(InputAction::SaveAs { quit }, "Save as", &dir);
+    }
+
+    pub(crate) fn sidebar_file_action(&mut self, key: KeyEvent) -> bool {
+        let Some(file_selector) = &self.file_selector else {
+            return false;
+        };
+        match key.code {
+            KeyCode::Char('a') => {
+                let dir = format!("{}/", Self::absolute_path(&file_selector.current_dir).display());
+                self.input(InputAction::CreateFile, "New file (end with / for a directory)", &dir);
+                true
+            }
+            KeyCode::Char('r') => {
+                if file_selector.parent_dir_index == Some(file_selector.selected_index) {
+                    return true;
+                }
+                if let Some(path) = file_selector.entries.get(file_selector.selected_index) {
+                    let path = Self::absolute_path(path);
+                    let default = path.to_string_lossy().into_owned();
+                    self.input(InputAction::Rename(path), "Rename to", &default);
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub(crate) fn quit_tab(&mut self) -> bool {
+        if self.tabs.len() > 1 {
+            self.close_tab();
+            false
+        } else {
+            true
+        }
+    }
+
+    pub(crate) fn handle_key_event(&mut self, key: KeyEvent) -> io::Result<bool> {
+        if self.prompt.is_some() {
+            self.handle_prompt_key(key);
+            return Ok(false);
+        }
+        if self.substitute_confirm.is_some() {
+            self.handle_substitute_confirm_key(key);
+            return Ok(false);
+        }
+        if self.output.is_some() {
+            self.handle_output_key(key);
+            return Ok(false);
+        }
+        if self.rename.is_some() {
+            self.handle_rename_key(key);
+            return Ok(false);
+        }
+        if self.start_screen.is_some() {
+            if let Some(result) = self.handle_start_screen_key(key) {
+                return result;
+            }
+        }
+
+        let _key_str = Self::key_event_to_string(key);
+        let acknowledge = key.code == KeyCode::Esc;
+        if self.status_message.as_ref().is_some_and(|message| message.level == MessageLevel::Info || acknowledge) {
+            self.status_message = None;
+        }
+        
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('m') {
+            self.log(LogLevel::Debug, "Ctrl+M detected, toggling minimap");
+            return self.toggle_minimap();
+        }
+        if self.minimap_focus.is_some() {
+            self.handle_minimap_focus_key(key);
+            return Ok(false);
+        }
+
+        if key.code == KeyCode::Char('q') && key.modifiers == KeyModifiers::CONTROL {
+            return Ok(true);
+        }
+
+        match self.mode {
+            Mode::Normal => self.handle_normal_mode(key),
+            Mode::Insert => self.handle_insert_mode(key),
+            Mode::Command => self.handle_command_mode(key),
+            Mode::Visual | Mode::VisualBlock => self.handle_visual_mode(key),
+            Mode::FileSelect | Mode::DirectoryNav => self.handle_file_select_mode(key),
+            Mode::Search => self.handle_search_mode(key),
+            Mode::SidebarActive => self.handle_sidebar_active_mode(key),
+            Mode::Picker => self.handle_picker_mode(key),
+            Mode::Outline => {
+                self.handle_outline_mode(key);
+                Ok(false)
+            }
+            Mode::Terminal => {
+                self.handle_terminal_mode(key);
+                Ok(false)
+            }
+            Mode::Debug => {
+                self.handle_debug_mode(key);
+                Ok(false)
+            }
+            Mode::Pager => self.handle_pager_mode(key),
+        }
+    }
+
+    pub fn handle_event(&mut self, event: Event) -> io::Result<bool> {
+        match event {
+            Event::Mouse(_) if !self.mouse_enabled() => {}
+            Event::Mouse(mouse_event) => self.handle_mouse_event(mouse_event),
+            Event::Paste(text) => {
+                self.handle_paste(&text);
+                self.end_mouse_selection();
+            }
+            Event::Key(key) => {
+                if key.modifiers == KeyModifiers::CONTROL && key.code == KeyCode::Char('q') {
+                    return Ok(true);
+                }
+
+                if self.tabs[self.active_tab].private {
+                    self.log(LogLevel::Trace, "Key pressed in a private tab");
+                } else {
+                    self.log(LogLevel::Trace, format!("Key pressed: {:?}", key));
+                }
+                self.log(LogLevel::Trace, format!("Cursor: ({}, {})", self.cursor_position.0, self.cursor_position.1));
+                let result = self.dispatch_key_event(key);
+                self.end_mouse_selection();
+                if result? {
+                    return Ok(true);
+                }
+            }
+            _ => {}
+        }
+        self.resolve_prompt()
+    }
+
+    pub fn execute_command_line(&mut self, command: &str) -> io::Result<bool> {
+        self.command_buffer.clear();
+        self.command_buffer.insert_str(command.strip_prefix(':').unwrap_or(command));
+        let quit = self.execute_command()?;
+        Ok(quit || self.resolve_prompt()?)
+    }
+
+    pub fn search(&mut self, query: &str) -> usize {
+        self.search_query.clear();
+        self.search_query.insert_str(query);
+        self.perform_search();
+        self.search_results.len()
+    }
+
+    pub fn open_directory(&mut self, path: &Path) -> io::Result<()> {
+        self.file_selector = Some(self.new_file_selector(path));
+        self.mode = Mode::FileSelect;
+        Ok(())
+    }
+
+    pub fn shutdown(&mut self) {
+        let _ = self.wait_for_saves();
+        for tab_index in 0..self.tabs.len() {
+            self.remember_position(tab_index);
+            self.store_bookmarks(tab_index);
+            self.store_folds(tab_index);
+        }
+        if self.settings.persist_ui_state {
+            self.save_ui_state();
+        }
+        self.release_locks();
+        self.plugins.clear();
+        self.terminal = None;
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    pub fn tabs(&self) -> &[Tab] {
+        &self.tabs
+    }
+
+    pub(crate) fn tab_index(&self, id: usize) -> Option<usize> {
+        self.tabs.iter().position(|tab| tab.id == id)
+    }
+
+    pub fn active_tab(&self) -> &Tab {
+        &self.tabs[self.active_tab]
+    }
+
+    pub fn status_message(&self) -> Option<&str> {
+        self.status_message.as_ref().map(|message| message.text.as_str())
+    }
+
+    pub fn dispatch_key_event(&mut self, key: KeyEvent) -> io::Result<bool> {
+        let previous_mode = self.mode;
+        let undo_depth = self.tabs[self.active_tab].undo_stack.len();
+        let visual = matches!(previous_mode, Mode::Visual | Mode::VisualBlock);
+        if visual {
+            self.remember_visual_marks();
+        }
+        let read_only = (self.active_tab, self.tabs.len());
+        let snapshot = self.tabs[self.active_tab].read_only.then(|| {
+            let tab = &self.tabs[self.active_tab];
+            (tab.content.clone(), tab.undo_stack.front().cloned(), tab.redo_stack.clone(), tab.modified)
+        });
+        let result = self.handle_key_event(key);
+        if let Some((content, last_undo, redo_stack, modified)) = snapshot.filter(|_| read_only == (self.active_tab, self.tabs.len())) {
+            let tab = &mut self.tabs[self.active_tab];
+            let edited = tab.content != content;
+            if edited {
+                tab.content = content;
+                let added = tab.undo_stack.len().saturating_sub(undo_depth);
+                tab.undo_stack.drain(..added);
+                if let Some(last_undo) = last_undo.filter(|_| tab.undo_stack.len() < undo_depth) {
+                    tab.undo_stack.push_front(last_undo);
+                }
+                tab.redo_stack = redo_stack;
+                tab.modified = modified;
+                let (x, y) = tab.cursor_position;
+                tab.set_cursor(x, y);
+            }
+            if edited || self.mode == Mode::Insert {
+                self.mode = Mode::Normal;
+                self.block_insert = None;
+                self.error("Buffer is read-only");
+                return result;
+            }
+        }
+        if previous_mode != Mode::Insert && self.mode == Mode::Insert {
+            self.begin_undo_group();
+            if self.tabs[self.active_tab].undo_stack.len() > undo_depth {
+                self.undo_group_tab = Some(self.active_tab);
+            }
+        } else if previous_mode == Mode::Insert && self.mode != Mode::Insert {
+            self.end_undo_group();
+        }
+        if self.mode != Mode::Insert {
+            self.completion = None;
+        }
+        result
+    }
+
+    pub(crate) fn toggle_sidebar(&mut self) -> io::Result<bool> {
+        self.show_sidebar = !self.show_sidebar;
+        if self.show_sidebar {
+            let current_dir = if let Some(file) = &self.tabs[self.active_tab].current_file {
+                Path::new(file).parent().unwrap_or(Path::new(".")).to_path_buf()
+            } else {
+                env::current_dir()?
+            };
+            self.file_selector = Some(self.new_file_selector(&current_dir));
+            self.mode = Mode::SidebarActive;
+        } else {
+            self.close_sidebar_preview();
+            self.mode = Mode::Normal;
+        }
+        Ok(false)
+    }
+
+    pub(crate) fn reveal_in_sidebar(&mut self) -> io::Result<bool> {
+        let Some(file) = self.tabs[self.active_tab].current_file.clone() else {
+            self.info("No file to reveal");
+            return Ok(false);
+        };
+        let path = Self::absolute_path(Path::new(&file));
+        let dir = path.parent().unwrap_or(Path::new(".")).to_path_buf();
+        let mut file_selector = self.new_file_selector(&dir);
+        let mut found = file_selector.select(&path);
+        if !found && !file_selector.show_ignored {
+            file_selector.toggle_show_ignored();
+            found = file_selector.select(&path);
+        }
+        if !found {
+            self.info(format!("{} is not in {}", self.display_path(&file), dir.display()));
+        }
+        self.file_selector = Some(file_selector);
+        self.show_sidebar = true;
+        self.mode = Mode::SidebarActive;
+        Ok(false)
+    }
+
+    pub(crate) fn handle_normal_mode(&mut self, key: KeyEvent) -> io::Result<bool> {
+        if self.surround_input.is_some() {
+            self.handle_surround_input(key);
+            return Ok(false);
+        }
+        let key_str = Self::key_event_to_string(key);
+        match self.match_key_sequence(key, &key_str) {
+            KeyMatch::Action(action) => self.execute_action(&action),
+            KeyMatch::Pending => Ok(false),
+            KeyMatch::Abandoned(prefix_action) => self.redispatch_key(prefix_action, key),
+            KeyMatch::Unmatched => match Self::fallback_action(key.code) {
+                Some(action) => self.execute_action(action),
+                None => Ok(false),
+            },
+        }
+    }
+
+    pub fn execute_action(&mut self, action: &str) -> io::Result<bool> {
+        match action {
+            "enter_insert_mode" => {
+                self.mode = Mode::Insert;
+                Ok(false)
+            },
+            "append" => {
+                self.mode = Mode::Insert;
+                self.move_cursor_right();
+                Ok(false)
+            },
+            "open_line_below" => {
+                self.insert_line_below();
+                self.mode = Mode::Insert;
+                Ok(false)
+            },
+            "open_line_above" => {
+                self.insert_line_above();
+                self.mode = Mode::Insert;
+                Ok(false)
+            },
+            "delete_line" => {
+                self.delete_line();
+                Ok(false)
+            },
+            "yank_line" => {
+                self.yank_line();
+                Ok(false)
+            },
+            "paste_after" => {
+                self.paste_after();
+                Ok(false)
+            },
+            "enter_visual_mode" => {
+                self.mode = Mode::Visual;
+                self.visual_start = self.tabs[self.active_tab].cursor_position;
+                Ok(false)
+            },
+            "enter_visual_block_mode" => {
+                self.mode = Mode::VisualBlock;
+                self.visual_start = self.tabs[self.active_tab].cursor_position;
+                Ok(false)
+            },
+            "block_insert" => {
+                self.start_block_insert(false);
+                Ok(false)
+            },
+            "block_append" => {
+                self.start_block_insert(true);
+                Ok(false)
+            },
+            "enter_command_mode" => {
+                let from_visual = matches!(self.mode, Mode::Visual | Mode::VisualBlock);
+                self.mode = Mode::Command;
+                self.command_buffer.clear();
+                if from_visual {
+                    self.remember_visual_marks();
+                    self.command_buffer.insert_str("'<,'>");
+                }
+                Ok(false)
+            },
+            "move_left" => {
+                self.move_cursor_left();
+                Ok(false)
+            },
+            "move_down" => {
+                self.move_cursor_down();
+                Ok(false)
+            },
+            "move_up" => {
+                self.move_cursor_up();
+                Ok(false)
+            },
+            "move_right" => {
+                self.move_cursor_right();
+                Ok(false)
+            },
+            "goto_line_start" => {
+                self.move_cursor_start_of_line();
+                Ok(false)
+            },
+            "goto_line_end" => {
+                self.move_cursor_end_of_line();
+                Ok(false)
+            },
+            "page_up" => {
+                self.page_up();
+                Ok(false)
+            },
+            "page_down" => {
+                self.page_down();
+                Ok(false)
+            },
+            "execute_command" => self.execute_command(),
+            "execute_search" => {
+                self.perform_search();
+                self.mode = self.base_mode();
+                Ok(false)
+            },
+            "select_file" => self.select_file(),
+            "exit_file_select_mode" => {
+                self.mode = Mode::Normal;
+                self.file_selector = None;
+                Ok(false)
+            },
+            "close_terminal" => {
+                self.close_terminal();
+                Ok(false)
+            },
+            "scroll_debug_up" | "scroll_debug_down" => {
+                self.scroll_debug(action == "scroll_debug_up", 1);
+                Ok(false)
+            },
+            "toggle_bookmark" => {
+                self.toggle_bookmark();
+                Ok(false)
+            },
+            "next_bookmark" | "prev_bookmark" => {
+                self.jump_to_bookmark(action == "next_bookmark");
+                Ok(false)
+            },
+            "older_change" | "newer_change" => {
+                self.jump_to_change(action == "older_change");
+                Ok(false)
+            },
+            "last_change" => {
+                self.jump_to_last_change();
+                Ok(false)
+            },
+            "toggle_debug_menu" => {
+                self.toggle_debug_menu();
+                Ok(false)
+            },
+            "focus_debug" => {
+                self.focus_debug();
+                Ok(false)
+            },
+            "enter_directory_nav_mode" => self.enter_directory_nav_mode(),
+            "enter_search_mode" => {
+                self.enter_search_mode();
+                Ok(false)
+            },
+            "search_all_tabs" => {
+                self.mode = Mode::Command;
+                self.command_buffer.clear();
+                self.command_buffer.insert_str("bsearch ");
+                Ok(false)
+            },
+            "next_search_result" => {
+                self.next_search_result();
+                Ok(false)
+            },
+            "previous_search_result" => {
+                self.previous_search_result();
+                Ok(false)
+            },
+            "copy_selection" => {
+                self.copy_selection();
+                Ok(false)
+            },
+            "paste_clipboard" => {
+                self.paste_clipboard();
+                Ok(false)
+            },
+            "undo" => {
+                self.undo();
+                Ok(false)
+            },
+            "redo" => {
+                self.redo();
+                Ok(false)
+            },
+            "toggle_sidebar" => self.toggle_sidebar(),
+            "reveal_in_sidebar" => self.reveal_in_sidebar(),
+            "clear_search_highlight" => {
+                self.search_highlight = false;
+                Ok(false)
+            }
+            "refresh_sidebar" => {
+                self.refresh_file_selector();
+                Ok(false)
+            }
+            "next_tab" => {
+                self.next_tab();
+                self.update_current_tab_info();
+                Ok(false)
+            },
+            "previous_tab" => {
+                self.previous_tab();
+                self.update_current_tab_info();
+                Ok(false)
+            },
+            "toggle_alternate_tab" => {
+                self.toggle_alternate_tab();
+                Ok(false)
+            },
+            "switch_to_tab_1" => {
+                self.switch_to_tab(0);
+                self.update_current_tab_info();
+                Ok(false)
+            },
+            "switch_to_tab_2" => {
+                self.switch_to_tab(1);
+                self.update_current_tab_info();
+                Ok(false)
+            },
+            "switch_to_tab_3" => {
+                self.switch_to_tab(2);
+                self.update_current_tab_info();
+                Ok(false)
+            },
+            "switch_to_tab_4" => {
+                self.switch_to_tab(3);
+                self.update_current_tab_info();
+                Ok(false)
+            },
+            "switch_to_tab_5" => {
+                self.switch_to_tab(4);
+                self.update_current_tab_info();
+                Ok(false)
+            },
+            "switch_to_tab_6" => {
+                self.switch_to_tab(5);
+                self.update_current_tab_info();
+                Ok(false)
+            },
+            "switch_to_tab_7" => {
+                self.switch_to_tab(6);
+                self.update_current_tab_info();
+                Ok(false)
+            },
+            "switch_to_tab_8" => {
+                self.switch_to_tab(7);
+                self.update_current_tab_info();
+                Ok(false)
+            },
+            "switch_to_tab_9" => {
+                self.switch_to_tab(8);
+                self.update_current_tab_info();
+                Ok(false)
+            },
+            "new_tab" => {
+                self.new_tab();
+                self.update_current_tab_info();
+                Ok(false)
+            },
+            "close_tab" => {
+                self.close_tab();
+                self.update_current_tab_info();
+                Ok(false)
+            },
+            "reopen_closed_tab" => {
+                self.reopen_closed_tab();
+                Ok(false)
+            },
+            "rebase_cycle" => {
+                self.cycle_rebase_command();
+                Ok(false)
+            }
+            "toggle_minimap" => self.toggle_minimap(),
+            "focus_minimap" => self.focus_minimap(),
+            "toggle_fold" => {
+                self.toggle_fold();
+                Ok(false)
+            }
+            "open_all_folds" => {
+                self.open_all_folds();
+                Ok(false)
+            }
+            "close_all_folds" => {
+                self.close_all_folds();
+                Ok(false)
+            }
+            "toggle_outline" => {
+                self.toggle_outline();
+                Ok(false)
+            }
+            "yank_to_new_tab" => {
+                self.yank_to_new_tab();
+                Ok(false)
+            }
+            "toggle_terminal" => {
+                self.toggle_terminal();
+                Ok(false)
+            }
+            "char_info" => {
+                self.show_char_info();
+                Ok(false)
+            }
+            "rename_word" => {
+                self.rename_word();
+                Ok(false)
+            }
+            "toggle_blame" => {
+                self.toggle_blame();
+                Ok(false)
+            }
+            "show_commit" => {
+                self.show_commit();
+                Ok(false)
+            }
+            "scroll_half_page_down" => {
+                self.scroll_half_page(true);
+                Ok(false)
+            },
+            "scroll_half_page_up" => {
+                self.scroll_half_page(false);
+                Ok(false)
+            },
+            "scroll_line_down" => {
+                self.scroll_line(true);
+                Ok(false)
+            },
+            "scroll_line_up" => {
+                self.scroll_line(false);
+                Ok(false)
+            },
+            "scroll_cursor_center" => {
+                self.scroll_cursor_to("center");
+                Ok(false)
+            },
+            "scroll_cursor_top" => {
+                self.scroll_cursor_to("top");
+                Ok(false)
+            },
+            "scroll_cursor_bottom" => {
+                self.scroll_cursor_to("bottom");
+                Ok(false)
+            },
+            "buffer_stats" => {
+                self.buffer_stats();
+                Ok(false)
+            },
+            "yank_selection" => {
+                if self.mode == Mode::VisualBlock {
+                    self.yank_block();
+                } else {
+                    self.copy_selection();
+                }
+                self.mode = Mode::Normal;
+                Ok(false)
+            },
+            "delete_selection" => {
+                if self.mode == Mode::VisualBlock {
+                    self.delete_block();
+                } else {
+                    self.delete_selection();
+                }
+                self.mode = Mode::Normal;
+                Ok(false)
+            },
+            "exit_visual_mode" | "exit_command_mode" | "exit_search_mode" | "exit_terminal_mode" => {
+                self.mode = self.base_mode();
+                Ok(false)
+            },
+            "insert_expression" => {
+                if self.mode == Mode::Insert {
+                    self.input(InputAction::Expression, "Expression (=)", "");
+                }
+                Ok(false)
+            },
+            "surround_selection" => {
+                if self.mode == Mode::Visual {
+                    self.start_surround_input(SurroundInput::Add);
+                }
+                Ok(false)
+            },
+            "surround_delete" => {
+                self.start_surround_input(SurroundInput::Delete);
+                Ok(false)
+            },
+            "surround_change" => {
+                self.start_surround_input(SurroundInput::Change(None));
+                Ok(false)
+            },
+            "insert_digraph" => {
+                self.start_literal_input(LiteralInput::Digraph(String::new()));
+                Ok(false)
+            },
+            "insert_unicode" => {
+                self.start_literal_input(LiteralInput::Verbatim);
+                Ok(false)
+            },
+            "complete_path" => {
+                self.complete_path();
+                Ok(false)
+            },
+            "exit_insert_mode" => {
+                self.mode = Mode::Normal;
+                self.finish_block_insert();
+                Ok(false)
+            },
+            "save_file" if self.tabs[self.active_tab].current_file.is_none() => {
+                self.save_as(false);
+                Ok(false)
+            },
+            "save_file" => {
+                let file = self.tabs[self.active_tab].current_file.clone().unwrap_or_default();
+                self.save_as_path(Path::new(&file), false);
+                Ok(false)
+            },
+            "select_all" => {
+                self.select_all();
+                Ok(false)
+            },
+            "expand_selection" => {
+                self.expand_selection();
+                Ok(false)
+            },
+            "shrink_selection" => {
+                self.shrink_selection();
+                Ok(false)
+            },
+            "paste_over_selection" if self.mode == Mode::Visual => {
+                self.paste_over_selection();
+                self.mode = Mode::Normal;
+                Ok(false)
+            },
+            "change_selection" if self.mode == Mode::Visual => {
+                self.change_selection();
+                Ok(false)
+            },
+            "paste_over_selection" | "change_selection" => Ok(false),
+            "cut_selection" => {
+                self.copy_selection();
+                self.delete_selection();
+                self.mode = Mode::Normal;
+                Ok(false)
+            },
+            action => {
+                if !self.run_plugin_action(action) {
+                    self.warn(format!("Unknown action: {}", action));
+                }
+                Ok(false)
+            },
+        }
+    }
+
+    pub(crate) fn handle_sidebar_active_mode(&mut self, key: KeyEvent) -> io::Result<bool> {
+        let key_str = Self::key_event_to_string(key);
+        
+        if let Some(action) = self.keybindings.normal_mode.get(&key_str) {
+            if action == "toggle_sidebar" {
+                return self.toggle_sidebar();
+            }
+        }
+    
+        if self.sidebar_file_action(key) {
+            return Ok(false);
+        }
+        if let Some(file_selector) = &mut self.file_selector {
+            match key.code {
+                KeyCode::Up => file_selector.up(),
+                KeyCode::Down => file_selector.down(),
+                KeyCode::Char('I') => file_selector.toggle_show_ignored(),
+                KeyCode::Char('L') => file_selector.load_all(),
+                KeyCode::Char('R') => file_selector.reload(Instant::now()),
+                KeyCode::Char('p') => self.preview_sidebar_selection(),
+                KeyCode::Char('b') => {
+                    if let Some(path) = file_selector.entries.get(file_selector.selected_index).filter(|path| !path.is_dir()).cloned() {
+                        self.open_file_in_background(&path)?;
+                    }
+                }
+                KeyCode::Enter => {
+                    if let Some(path) = file_selector.enter() {
+                        self.open_file(&path)?;
+                        self.toggle_sidebar()?;
+                    }
+                }
+                KeyCode::Esc => {
+                    self.toggle_sidebar()?;
+                }
+                _ => {}
+            }
+        }
+        Ok(false)
+    }
+
+    pub(crate) fn handle_insert_mode(&mut self, key: KeyEvent) -> io::Result<bool> {
+        if self.literal_input.is_some() {
+            self.handle_literal_input(key);
+            return Ok(false);
+        }
+        let key_str = Self::key_event_to_string(key);
+        if let Some(pending) = self.clear_pending_key() {
+            let combined_key = format!("{}{}", pending, key_str);
+            if let Some(action) = self.keybindings.insert_mode.get(&combined_key).cloned() {
+                return self.execute_action(&action);
+            }
+            if combined_key == "Ctrl+xCtrl+f" {
+                return self.execute_action("complete_path");
+            }
+        }
+        if self.handle_completion_key(key) {
+            return Ok(false);
+        }
+        if let Some(action) = self.keybindings.insert_mode.get(&key_str).cloned() {
+            return self.execute_action(&action);
+        }
+        let modified = key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
+        if modified && (key_str == "Ctrl+x" || self.keybindings.insert_mode.keys().any(|k| k.len() > key_str.len() && k.starts_with(&key_str))) {
+            self.set_pending_key(key_str, key);
+            return Ok(false);
+        }
+
+        match key.code {
+            KeyCode::Esc => {
+                self.mode = Mode::Normal;
+                self.finish_block_insert();
+            },
+            KeyCode::Enter => self.insert_newline(),
+            KeyCode::Backspace => self.backspace(),
+            KeyCode::Delete => self.delete_char(),
+            KeyCode::Left | KeyCode::Down | KeyCode::Up | KeyCode::Right => {
+                if self.settings.insert_arrow_breaks_undo {
+                    self.break_undo_group();
+                }
+                match key.code {
+                    KeyCode::Left => self.move_cursor_left(),
+                    KeyCode::Down => self.move_cursor_down(),
+                    KeyCode::Up => self.move_cursor_up(),
+                    _ => self.move_cursor_right(),
+                }
+            }
+            KeyCode::Char(c) => self.insert_char(c),
+            KeyCode::Tab => self.insert_tab(),
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    pub(crate) fn handle_command_mode(&mut self, key: KeyEvent) -> io::Result<bool> {
+        if let Some(action) = self.keybindings.command_mode.get(&Self::key_event_to_string(key)).cloned() {
+            return self.execute_action(&action);
+        }
+        match key.code {
+            KeyCode::Enter => return self.execute_command(),
+            KeyCode::Esc => self.mode = Mode::Normal,
+            KeyCode::Tab => self.complete_command(),
+            _ => {
+                self.command_completions.clear();
+                let word = self.word_under_cursor();
+                self.command_buffer.handle_key(key, word, &mut self.clipboard_context);
+            }
+        }
+        Ok(false)
+    }
+
+    pub(crate) fn word_under_cursor(&self) -> Option<String> {
+        let tab = &self.tabs[self.active_tab];
+        let line = tab.content.get(tab.cursor_position.1)?;
+        let is_word = |c: char| c.is_alphanumeric() || c == '_';
+        let cursor = tab.cursor_position.0.min(line.len());
+        let start = line[..cursor].char_indices().rev().find(|&(_, c)| !is_word(c)).map(|(i, c)| i + c.len_utf8()).unwrap_or(0);
+        let end = line[cursor..].find(|c: char| !is_word(c)).map(|i| cursor + i).unwrap_or(line.len());
+        if start < end {
+            Some(line[start..end].to_string())
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn handle_visual_mode(&mut self, key: KeyEvent) -> io::Result<bool> {
+        if self.surround_input.is_some() {
+            self.handle_surround_input(key);
+            return Ok(false);
+        }
+        let key_str = Self::key_event_to_string(key);
+        match self.match_key_sequence(key, &key_str) {
+            KeyMatch::Action(action) => return self.execute_action(&action),
+            KeyMatch::Pending => return Ok(false),
+            KeyMatch::Abandoned(prefix_action) => return self.redispatch_key(prefix_action, key),
+            KeyMatch::Unmatched => {}
+        }
+
+        let fallback = match key.code {
+            KeyCode::Esc => Some("exit_visual_mode"),
+            KeyCode::Char('y') => Some("yank_selection"),
+            KeyCode::Char('d') => Some("delete_selection"),
+            KeyCode::Left | KeyCode::Down | KeyCode::Up | KeyCode::Right => Self::fallback_action(key.code),
+            _ => None,
+        };
+        match fallback {
+            Some(action) => self.execute_action(action),
+            None => Ok(false),
+        }
+    }
+
+    pub(crate) fn handle_file_select_mode(&mut self, key: KeyEvent) -> io::Result<bool> {
+        if self.sidebar_file_action(key) {
+            return Ok(false);
+        }
+        if let Some(action) = self.keybindings.file_select_mode.get(&Self::key_event_to_string(key)).cloned() {
+            return self.execute_action(&action);
+        }
+        match key.code {
+            KeyCode::Enter => return self.select_file(),
+            KeyCode::Esc => return self.execute_action("exit_file_select_mode"),
+            _ => {}
+        }
+        if let Some(file_selector) = &mut self.file_selector {
+            match key.code {
+                KeyCode::Up => file_selector.up(),
+                KeyCode::Down => file_selector.down(),
+                KeyCode::Char('I') => file_selector.toggle_show_ignored(),
+                KeyCode::Char('L') => file_selector.load_all(),
+                KeyCode::Char('R') => file_selector.reload(Instant::now()),
+                _ => {}
+            }
+        }
+        Ok(false)
+    }
+
+    pub(crate) fn select_file(&mut self) -> io::Result<bool> {
+        let Some(file_selector) = &mut self.file_selector else {
+            return Ok(false);
+        };
+        if let Some(path) = file_selector.enter() {
+            self.open_file(&path)?;
+            self.mode = Mode::Normal;
+            self.file_selector = None;
+        }
+        Ok(false)
+    }
+
+    pub(crate) fn move_cursor_up(&mut self) {
+        let tab = &mut self.tabs[self.active_tab];
+        let (x, y) = tab.cursor_position;
+        let row = tab.line_to_row(y);
+        if row > 0 {
+            tab.set_cursor(x, tab.row_to_line(row - 1));
+            self.ensure_cursor_visible();
+        }
+    }
+
+    pub(crate) fn move_cursor_down(&mut self) {
+        let tab = &mut self.tabs[self.active_tab];
+        let (x, y) = tab.cursor_position;
+        let row = tab.line_to_row(y);
+        if row + 1 < tab.row_count() {
+            tab.set_cursor(x, tab.row_to_line(row + 1));
+            self.ensure_cursor_visible();
+        }
+    }
+
+    pub(crate) fn move_cursor_left(&mut self) {
+        let tab = &mut self.tabs[self.active_tab];
+        let (x, y) = tab.cursor_position;
+        if x > 0 {
+            tab.cursor_position.0 = tab.content[y][..x].char_indices().next_back().map_or(0, |(index, _)| index);
+        } else if y > 0 {
+            tab.cursor_position.1 = tab.row_to_line(tab.line_to_row(y).saturating_sub(1));
+            tab.cursor_position.0 = tab.content[tab.cursor_position.1].len();
+            self.ensure_cursor_visible();
+        }
+        self.adjust_horizontal_scroll();
+    }
+
+    pub(crate) fn move_cursor_right(&mut self) {
+        let tab = &mut self.tabs[self.active_tab];
+        let (x, y) = tab.cursor_position;
+        if let Some(c) = tab.content[y][x..].chars().next() {
+            tab.cursor_position.0 += c.len_utf8();
+        } else if tab.line_to_row(y) + 1 < tab.row_count() {
+            tab.cursor_position.1 = tab.row_to_line(tab.line_to_row(y) + 1);
+            tab.cursor_position.0 = 0;
+            self.ensure_cursor_visible();
+        }
+        self.adjust_horizontal_scroll();
+    }
+
+    pub(crate) fn get_editor_height(&self) -> usize {
+        self.editor_height
+    }
+
+    pub(crate) fn move_cursor_start_of_line(&mut self) {
+        let tab = &mut self.tabs[self.active_tab];
+        tab.cursor_position.0 = 0;
+        self.adjust_horizontal_scroll();
+    }
+
+    pub(crate) fn move_cursor_end_of_line(&mut self) {
+        let tab = &mut self.tabs[self.active_tab];
+        tab.cursor_position.0 = tab.content[tab.cursor_position.1].len();
+        self.adjust_horizontal_scroll();
+    }
+
+    pub(crate) fn insert_char(&mut self, c: char) {
+        self.delete_mouse_selection();
+        self.save_state();
+        self.tabs[self.active_tab].insert_char(c);
+        self.adjust_horizontal_scroll();
+    }
+
+    pub(crate) fn insert_tab(&mut self) {
+        if !self.option("expandtab").bool() {
+            self.insert_char('\t');
+            return;
+        }
+        self.delete_mouse_selection();
+        let tab_width = self.option("tabstop").number();
+        let tab = &self.tabs[self.active_tab];
+        let column = tab.visual_column(tab.cursor_position.1, tab.cursor_position.0, tab_width);
+        self.save_state();
+        for _ in 0..tab_width - column % tab_width {
+            self.tabs[self.active_tab].insert_char(' ');
+        }
+        self.adjust_horizontal_scroll();
+    }
+
+    pub(crate) fn insert_newline(&mut self) {
+        self.delete_mouse_selection();
+        self.save_state();
+        self.tabs[self.active_tab].insert_newline();
+        self.ensure_cursor_visible();
+        self.adjust_horizontal_scroll();
+    }
+
+    pub(crate) fn page_up(&mut self) {
+        let visible_lines = self.get_editor_height();
+        let margin = self.scroll_margin();
+        let tab = &mut self.tabs[self.active_tab];
+        let scroll = tab.line_to_row(tab.scroll_offset).saturating_sub(visible_lines);
+        let cursor = if scroll == 0 { 0 } else { scroll + margin };
+        tab.scroll_offset = tab.row_to_line(scroll);
+        tab.cursor_position.1 = tab.row_to_line(cursor);
+        self.ensure_cursor_in_bounds();
+    }
+
+    pub(crate) fn page_down(&mut self) {
+        let visible_lines = self.get_editor_height();
+        let margin = self.scroll_margin();
+        let tab = &mut self.tabs[self.active_tab];
+        let rows = tab.row_count();
+        let max_scroll = rows.saturating_sub(visible_lines);
+        let scroll = (tab.line_to_row(tab.scroll_offset) + visible_lines).min(max_scroll);
+        let cursor = if scroll == max_scroll { rows - 1 } else { scroll + visible_lines - 1 - margin };
+        tab.scroll_offset = tab.row_to_line(scroll);
+        tab.cursor_position.1 = tab.row_to_line(cursor);
+        self.ensure_cursor_in_bounds();
+    }
+
+    pub(crate) fn scroll_half_page(&mut self, down: bool) {
+        let editor_height = self.get_editor_height();
+        let amount = (editor_height / 2).max(1);
+        let tab = &mut self.tabs[self.active_tab];
+        let rows = tab.row_count();
+        let max_scroll = rows.saturating_sub(editor_height);
+        let scroll = tab.line_to_row(tab.scroll_offset);
+        let cursor = tab.line_to_row(tab.cursor_position.1);
+        let (scroll, cursor) = if down {
+            ((scroll + amount).min(max_scroll), (cursor + amount).min(rows - 1))
+        } else {
+            (scroll.saturating_sub(amount), cursor.saturating_sub(amount))
+        };
+        tab.scroll_offset = tab.row_to_line(scroll);
+        tab.cursor_position.1 = tab.row_to_line(cursor);
+        self.ensure_cursor_in_bounds();
+        self.ensure_cursor_visible();
+    }
+
+    pub(crate) fn scroll_line(&mut self, down: bool) {
+        let editor_height = self.get_editor_height();
+        let margin = self.scroll_margin();
+        let tab = &mut self.tabs[self.active_tab];
+        let rows = tab.row_count();
+        let max_scroll = rows.saturating_sub(editor_height);
+        let previous_scroll = tab.line_to_row(tab.scroll_offset);
+        let cursor = tab.line_to_row(tab.cursor_position.1);
+        if down {
+            let scroll = (previous_scroll + 1).min(max_scroll);
+            tab.scroll_offset = tab.row_to_line(scroll);
+            if scroll != previous_scroll && cursor < scroll + margin {
+                tab.cursor_position.1 = tab.row_to_line((scroll + margin).min(rows - 1));
+            }
+        } else {
+            let scroll = previous_scroll.saturating_sub(1);
+            tab.scroll_offset = tab.row_to_line(scroll);
+            let bottom = (scroll + editor_height).saturating_sub(margin + 1);
+            if scroll != previous_scroll && cursor > bottom {
+                tab.cursor_position.1 = tab.row_to_line(bottom);
+            }
+        }
+        self.ensure_cursor_in_bounds();
+    }
+
+    pub(crate) fn backspace(&mut self) {
+        if self.delete_mouse_selection() {
+            return;
+        }
+        self.save_state();
+        self.tabs[self.active_tab].backspace();
+    }
+
+    pub(crate) fn delete_char(&mut self) {
+        if self.delete_mouse_selection() {
+            return;
+        }
+        self.save_state();
+        self.tabs[self.active_tab].delete_char();
+    }
+
+    pub(crate) fn delete_line(&mut self) {
+        let tab_index = self.active_tab;
+        
+        if self.tabs[tab_index].cursor_position.1 < self.tabs[tab_index].content.len() {
+            self.save_state();
+
+            let tab = &mut self.tabs[tab_index];
+            let cursor_y = tab.cursor_position.1;
+            
+            let line = tab.content.remove(cursor_y);
+            tab.shift_lines(cursor_y, 1, 0);
+            if tab.content.is_empty() {
+                tab.content.push(String::new());
+            }
+            
+            if cursor_y == tab.content.len() && cursor_y > 0 {
+                tab.cursor_position.1 -= 1;
+            }
+            
+            tab.cursor_position.0 = 0;
+            self.set_clipboard(line);
+            self.block_register = None;
+        }
+    }
+
+    pub(crate) fn insert_line_below(&mut self) {
+        self.save_state();
+        let tab = &mut self.tabs[self.active_tab];
+        tab.content.insert(tab.cursor_position.1 + 1, String::new());
+        tab.shift_lines(tab.cursor_position.1 + 1, 0, 1);
+        tab.cursor_position = (0, tab.cursor_position.1 + 1);
+    }
+
+    pub(crate) fn insert_line_above(&mut self) {
+        self.save_state();
+        let tab = &mut self.tabs[self.active_tab];
+        tab.content.insert(tab.cursor_position.1, String::new());
+        tab.shift_lines(tab.cursor_position.1, 0, 1);
+        tab.cursor_position = (0, tab.cursor_position.1);
+    }
+
+    pub(crate) fn yank_line(&mut self) {
+        let tab = &mut self.tabs[self.active_tab];
+        if tab.cursor_position.1 < tab.content.len() {
+            let line = tab.content[tab.cursor_position.1].clone();
+            self.set_clipboard(line);
+            self.block_register = None;
+        }
+    }
+
+    pub(crate) fn paste_after(&mut self) {
+        if let Ok(content) = self.clipboard_context.get_contents() {
+            if let Some(selection) = self.mouse_selection() {
+                self.replace_selection(selection, &content);
+                return;
+            }
+            if let Some(block) = self.block_register.clone() {
+                if content == block.join("\n") {
+                    self.paste_block(&block);
+                    return;
+                }
+            }
+            self.save_state();
+            
+            let tab = &mut self.tabs[self.active_tab];
+            let current_line = tab.cursor_position.1;
+            let current_column = tab.cursor_position.0;
+
+            if current_line >= tab.content.len() {
+                tab.content.push(String::new());
+            }
+
+            let line = tab.content[current_line].clone();
+            let (left, right) = line.split_at(current_column.min(line.len()));
+
+            let mut new_lines: Vec<String> = content.split('\n').map(String::from).collect();
+            
+            if new_lines.is_empty() {
+                new_lines.push(String::new());
+            }
+
+            let first_new_line = new_lines.remove(0);
+            let mut combined_lines = vec![format!("{}{}", left, first_new_line)];
+            combined_lines.extend(new_lines);
+            combined_lines.push(right.to_string());
+
+            let combined_lines_len = combined_lines.len();
+            tab.content.splice(current_line..=current_line, combined_lines);
+            tab.shift_lines(current_line, 1, combined_lines_len);
+
+            let last_inserted_line = current_line + combined_lines_len - 1;
+            tab.cursor_position = (tab.content[last_inserted_line].len() - right.len(), last_inserted_line);
+        }
+        self.ensure_cursor_in_bounds();
+    }
+
+    pub(crate) fn selected_text(&self) -> String {
+        if self.mode == Mode::VisualBlock {
+            return self.block_fragments().join("\n");
+        }
+        let tab = &self.tabs[self.active_tab];
+        tab.selection_text(self.visual_start, tab.cursor_position)
+    }
+
+    pub(crate) fn yank_to_new_tab(&mut self) {
+        let visual = matches!(self.mode, Mode::Visual | Mode::VisualBlock);
+        let source = &self.tabs[self.active_tab];
+        let lines: Vec<String> = if visual {
+            self.selected_text().split('\n').map(String::from).collect()
+        } else {
+            source.content.clone()
+        };
+        let mut tab = Tab::from_lines(lines);
+        tab.syntax = source.syntax.clone();
+        self.mode = Mode::Normal;
+        self.tabs.push(tab);
+        self.set_active_tab(self.tabs.len() - 1);
+        self.update_current_tab_info();
+    }
+
+    pub(crate) fn copy_selection(&mut self) {
+        let selected_text = self.selected_text();
+        self.block_register = None;
+
+        if self.set_clipboard(selected_text) {
+            self.info("Text copied to clipboard");
+        }
+    }
+
+    pub(crate) fn char_to_byte(line: &str, column: usize) -> usize {
+        line.char_indices().nth(column).map(|(i, _)| i).unwrap_or(line.len())
+    }
+
+    pub(crate) fn byte_to_char(line: &str, byte: usize) -> usize {
+        line.char_indices().take_while(|(i, _)| *i < byte).count()
+    }
+
+    pub(crate) fn block_bounds(&self) -> (usize, usize, usize, usize) {
+        let tab = &self.tabs[self.active_tab];
+        let tab_width = self.option("tabstop").number();
+        let columns = |(x, y): (usize, usize)| {
+            let line = tab.content.get(y).map_or("", String::as_str);
+            let x = x.min(line.len());
+            let start = line_width(line.get(..x).unwrap_or(line), tab_width);
+            let width = line.get(x..).and_then(|rest| rest.chars().next()).map_or(1, |c| char_width_at(c, start, tab_width).max(1));
+            (start, start + width - 1)
+        };
+        let (start_left, start_right) = columns(self.visual_start);
+        let (end_left, end_right) = columns(tab.cursor_position);
+        let (start, end) = (self.visual_start, tab.cursor_position);
+        (
+            start.1.min(end.1),
+            start.1.max(end.1).min(tab.content.len().saturating_sub(1)),
+            start_left.min(end_left),
+            start_right.max(end_right),
+        )
+    }
+
+    pub(crate) fn block_span(&self, y: usize) -> BlockSpan {
+        let (_, _, left, right) = self.block_bounds();
+        block_span(&self.tabs[self.active_tab].content[y], left, right, self.option("tabstop").number())
+    }
+
+    pub(crate) fn block_fragments(&self) -> Vec<String> {
+        let (top, bottom, _, _) = self.block_bounds();
+        (top..=bottom).map(|y| self.block_span(y).text).collect()
+    }
+
+    pub(crate) fn yank_block(&mut self) {
+        let fragments = self.block_fragments();
+        if self.set_clipboard(fragments.join("\n")) {
+            self.info("Block copied to clipboard");
+        }
+        self.block_register = Some(fragments);
+    }
+
+    pub(crate) fn delete_block(&mut self) {
+        let fragments = self.block_fragments();
+        self.set_clipboard(fragments.join("\n"));
+        self.block_register = Some(fragments);
+
+        let (top, bottom, left, _) = self.block_bounds();
+        let spans: Vec<_> = (top..=bottom).map(|y| self.block_span(y)).collect();
+        let tab_width = self.option("tabstop").number();
+        self.save_state();
+        let tab = &mut self.tabs[self.active_tab];
+        for (line, span) in tab.content[top..=bottom].iter_mut().zip(spans).filter(|(_, span)| span.start < span.end) {
+            line.replace_range(span.start..span.end, &" ".repeat(span.before + span.after));
+        }
+        tab.cursor_position = (column_to_byte(&tab.content[top], left, tab_width), top);
+    }
+
+    pub(crate) fn paste_block(&mut self, block: &[String]) {
+        let tab_width = self.option("tabstop").number();
+        self.save_state();
+        let tab = &mut self.tabs[self.active_tab];
+        let (cursor_x, cursor_y) = tab.cursor_position;
+        let line = &tab.content[cursor_y];
+        let column = line_width(line.get(..cursor_x).unwrap_or(line), tab_width);
+
+        for (i, fragment) in block.iter().enumerate() {
+            let y = cursor_y + i;
+            if y >= tab.content.len() {
+                tab.content.push(String::new());
+            }
+            insert_at_column(&mut tab.content[y], column, fragment, tab_width);
+        }
+        tab.cursor_position = (column_to_byte(&tab.content[cursor_y], column, tab_width), cursor_y);
+    }
+
+    pub(crate) fn start_block_insert(&mut self, append: bool) {
+        let (top, bottom, left, right) = self.block_bounds();
+        let column = if append { right + 1 } else { left };
+        let tab_width = self.option("tabstop").number();
+        self.save_state();
+
+        let tab = &mut self.tabs[self.active_tab];
+        let line = &mut tab.content[top];
+        insert_at_column(line, column, "", tab_width);
+        tab.cursor_position = (column_to_byte(line, column, tab_width), top);
+
+        self.block_insert = Some(BlockInsert {
+            top,
+            bottom,
+            column,
+            line_length: line.len(),
+            pad: append,
+        });
+        self.mode = Mode::Insert;
+    }
+
+    pub(crate) fn finish_block_insert(&mut self) {
+        let Some(block) = self.block_insert.take() else {
+            return;
+        };
+        let tab_width = self.option("tabstop").number();
+        let tab = &mut self.tabs[self.active_tab];
+        let top_line = &tab.content[block.top];
+        let inserted_bytes = top_line.len().saturating_sub(block.line_length);
+        if tab.cursor_position.1 != block.top || tab.content.len() <= block.bottom || inserted_bytes == 0 {
+            return;
+        }
+        let at = column_to_byte(top_line, block.column, tab_width);
+        let Some(inserted) = top_line.get(at..at + inserted_bytes).map(str::to_string) else {
+            return;
+        };
+
+        for line in &mut tab.content[block.top + 1..=block.bottom] {
+            if !block.pad && line_width(line, tab_width) <= block.column {
+                continue;
+            }
+            insert_at_column(line, block.column, &inserted, tab_width);
+        }
+    }
+
+    pub(crate) fn select_all(&mut self) {
+        let tab = &mut self.tabs[self.active_tab];
+        let last_line = tab.content.len() - 1;
+        self.visual_start = (0, 0);
+        tab.cursor_position = (tab.content[last_line].len(), last_line);
+        self.mode = Mode::Visual;
+        self.ensure_cursor_visible();
+    }
+
+    pub(crate) fn delete_selection(&mut self) {
+        self.save_state();
+        let tab = &mut self.tabs[self.active_tab];
+        let cursor = tab.cursor_position;
+        tab.selection_remove(self.visual_start, cursor);
+    }
+
+    pub(crate) fn delete_mouse_selection(&mut self) -> bool {
+        let Some((start, end)) = self.mouse_selection() else {
+            return false;
+        };
+        self.save_state();
+        self.tabs[self.active_tab].selection_remove(start, end);
+        true
+    }
+
+    pub(crate) fn replace_selection(&mut self, (start, end): ((usize, usize), (usize, usize)), content: &str) {
+        self.save_state();
+        let tab = &mut self.tabs[self.active_tab];
+        tab.selection_remove(start, end);
+        let position = tab.cursor_position;
+        tab.insert_text(content);
+        tab.cursor_position = position;
+        self.ensure_cursor_visible();
+    }
+
+    pub(crate) fn paste_over_selection(&mut self) {
+        let content = match self.clipboard_context.get_contents() {
+            Ok(content) => content,
+            Err(e) => {
+                self.report(PhantomError::Clipboard(e.to_string()));
+                return;
+            }
+        };
+        let replaced = self.selected_text();
+        let cursor = self.tabs[self.active_tab].cursor_position;
+        self.replace_selection((self.visual_start, cursor), &content);
+        self.block_register = None;
+        self.set_clipboard(replaced);
+    }
+
+    pub(crate) fn change_selection(&mut self) {
+        let replaced = self.selected_text();
+        self.block_register = None;
+        self.set_clipboard(replaced);
+        self.delete_selection();
+        self.mode = Mode::Insert;
+    }
+
+    pub(crate) fn paste_clipboard(&mut self) {
+        match self.clipboard_context.get_contents() {
+            Ok(content) => self.insert_pasted(&content),
+            Err(e) => {
+                self.report(PhantomError::Clipboard(e.to_string()));
+            }
+        }
+    }
+
+    pub(crate) fn insert_pasted(&mut self, content: &str) {
+        match self.mouse_selection() {
+            Some(selection) => self.replace_selection(selection, content),
+            None => {
+                self.save_state();
+                self.tabs[self.active_tab].insert_text(content);
+            }
+        }
+    }
+
+    pub(crate) fn save_file(&mut self, filename: Option<&Path>) -> io::Result<()> {
+        let tab = &mut self.tabs[self.active_tab];
+        if tab.read_only {
+            return Err(io::Error::other("Buffer is read-only"));
+        }
+        let filename = if let Some(name) = filename {
+            name.to_path_buf()
+        } else if let Some(ref name) = tab.current_file {
+            PathBuf::from(name)
+        } else {
+            return Err(io::Error::other("No filename specified. Use :w <filename> to save."));
+        };
+        if tab.private {
+            self.save_private(&filename, false);
+            return Ok(());
+        }
+    
+        if let Some(writes) = &mut self.dry_run_writes {
+            writes.push((Self::absolute_path(&filename), tab.content.clone()));
+            tab.mark_saved(&tab.content.clone());
+            self.file_saved(self.active_tab, &filename);
+            return Ok(());
+        }
+        let queued = tab.save.is_some();
+        self.start_save(self.active_tab, filename.clone());
+        let name = self.display_path(&Self::absolute_path(&filename).to_string_lossy());
+        if queued {
+            self.info(format!("Saving {}… (queued)", name));
+        } else {
+            self.info(format!("Saving {}…", name));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn save_and_wait(&mut self, filename: Option<&Path>) -> io::Result<()> {
+        self.save_file(filename)?;
+        self.wait_for_save(self.active_tab)
+    }
+
+    pub fn goto_location(&mut self, line: usize, column: Option<usize>) {
+        let tab = &mut self.tabs[self.active_tab];
+        let y = line.saturating_sub(1).min(tab.content.len() - 1);
+        let x = column.map_or(0, |column| Self::char_to_byte(&tab.content[y], column.saturating_sub(1)));
+        tab.cursor_position = (x, y);
+        self.ensure_cursor_visible();
+        self.adjust_horizontal_scroll();
+    }
+
+    pub fn open_file(&mut self, path: &Path) -> io::Result<()> {
+        if !path.exists() {
+            if let Some((file, line, column)) = Self::split_file_location(&path.to_string_lossy()) {
+                self.open_file(&file)?;
+                self.goto_location(line, column);
+                return Ok(());
+            }
+        }
+
+        let absolute = Self::absolute_path(path);
+        let path = absolute.as_path();
+        let new_tab = if path.exists() {
+            let positions = if self.settings.restore_position && !Self::path_matches(&self.settings.restore_position_exclude, path) {
+                Some(&self.positions)
+            } else {
+                None
+            };
+            let mut tab = Tab::from_file(path, &self.ps, positions)?;
+            if let Some(syntax) = self.configured_syntax(path) {
+                tab.syntax = syntax;
+            }
+            tab.bookmarks = self.bookmark_store.get(&Self::canonical_path(path)).iter()
+                .copied()
+                .filter(|&line| line < tab.content.len())
+                .collect();
+            tab.folds = self.fold_store.get(&Self::canonical_path(path)).iter()
+                .copied()
+                .filter(|&(start, end)| start < end && end < tab.content.len())
+                .collect();
+            tab
+        } else {
+            let mut tab = Tab::new();
+            tab.current_file = Some(path.to_string_lossy().into_owned());
+            tab
+        };
+    
+        if self.tabs.len() == 1 && self.tabs[0].content == vec![String::new()] && self.tabs[0].current_file.is_none() {
+            self.tabs[0] = new_tab;
+            self.active_tab = 0;
+        } else {
+            self.tabs.push(new_tab);
+            self.set_active_tab(self.tabs.len() - 1);
+        }
+        
+        
+        self.notify_plugins(EditorMessage::BufferOpened { path: path.to_string_lossy().into_owned() });
+        if path.exists() {
+            self.record_recent_file(path);
+            self.info(format!("Opened {}", self.display_path(&path.to_string_lossy())));
+            self.apply_modeline();
+            self.apply_detected_indent();
+        } else {
+            self.info(format!("New file: {} (not yet saved)", self.display_path(&path.to_string_lossy())));
+        }
+        self.lock_file(self.active_tab);
+        self.apply_git_defaults();
+
+        Ok(())
+    }
+
+    pub(crate) fn buffer_text(&self) -> String {
+        let mut text = String::new();
+        for line in &self.tabs[self.active_tab].content {
+            text.push_str(line);
+            text.push('\n');
+        }
+        text
+    }
+
+    pub(crate) fn buffer_stats(&mut self) {
+        let buffer = TextStats::from_text(&self.buffer_text());
+        let message = if self.mode == Mode::Visual {
+            let selection = TextStats::from_text(&self.selected_text());
+            format!("Selection: {} (buffer: {})", selection, buffer)
+        } else {
+            format!("Buffer: {}", buffer)
+        };
+        self.info(message);
+    }
+
+    pub(crate) fn live_word_count(&self) -> Option<usize> {
+        let syntax = &self.tabs[self.active_tab].syntax;
+        if self.settings.live_word_count.iter().any(|s| s.eq_ignore_ascii_case(syntax)) {
+            Some(self.tabs[self.active_tab].content.iter().map(|line| line.split_whitespace().count()).sum())
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn new_file_selector(&self, dir: &Path) -> FileSelector {
+        let global_ignore = self.paths.config_dir.as_ref().map(|dir| dir.join("ignore"));
+        FileSelector::new(dir, self.settings.show_ignored, global_ignore)
+    }
+
+    pub(crate) fn enter_directory_nav_mode(&mut self) -> io::Result<bool> {
+        let current_dir = if let Some(file) = &self.tabs[self.active_tab].current_file {
+            Path::new(file).parent().unwrap_or(Path::new(".")).to_path_buf()
+        } else {
+            env::current_dir()?
+        };
+        self.file_selector = Some(self.new_file_selector(&current_dir));
+        self.mode = Mode::DirectoryNav;
+        Ok(false)
+    }
+
+    pub(crate) fn enter_search_mode(&mut self) {
+        self.mode = Mode::Search;
+        self.search_query.clear();
+        self.search_results.clear();
+        self.current_search_index = 0;
+    }
+
+    pub(crate) fn perform_search(&mut self) {
+        self.search_results = self.tabs[self.active_tab].find(&self.search_query.text);
+        self.search_highlight = true;
+        self.current_search_index = 0;
+        if !self.search_results.is_empty() {
+            let (line, col) = self.search_results[0];
+            let tab = &mut self.tabs[self.active_tab];
+            tab.cursor_position = (col, line);
+            self.ensure_cursor_visible();
+            self.adjust_horizontal_scroll();
+        }
+    }
+
+    pub(crate) fn next_search_result(&mut self) {
+        self.jump_to_search_result(true);
+    }
+
+    pub(crate) fn previous_search_result(&mut self) {
+        self.jump_to_search_result(false);
+    }
+
+    fn jump_to_search_result(&mut self, forward: bool) {
+        if self.search_query.text.is_empty() {
+            return;
+        }
+        self.search_highlight = true;
+        self.search_results = self.tabs[self.active_tab].find(&self.search_query.text);
+        if self.search_results.is_empty() {
+            self.warn(format!("Pattern not found: {}", self.search_query.text));
+            return;
+        }
+        let (x, y) = self.tabs[self.active_tab].cursor_position;
+        let found = if forward {
+            self.search_results.iter().position(|&position| position > (y, x))
+        } else {
+            self.search_results.iter().rposition(|&position| position < (y, x))
+        };
+        self.current_search_index = match found {
+            Some(index) => index,
+            None if forward => {
+                self.info("Search hit BOTTOM, continuing at TOP");
+                0
+            }
+            None => {
+                self.info("Search hit TOP, continuing at BOTTOM");
+                self.search_results.len() - 1
+            }
+        };
+        let (line, col) = self.search_results[self.current_search_index];
+        self.tabs[self.active_tab].set_cursor(col, line);
+        self.ensure_cursor_visible();
+        self.adjust_horizontal_scroll();
+    }
+
+    pub(crate) fn handle_search_mode(&mut self, key: KeyEvent) -> io::Result<bool> {
+        if let Some(action) = self.keybindings.search_mode.get(&Self::key_event_to_string(key)).cloned() {
+            return self.execute_action(&action);
+        }
+        match key.code {
+            KeyCode::Esc => {
+                self.mode = self.base_mode();
+            }
+            KeyCode::Enter => {
+                self.perform_search();
+                self.mode = self.base_mode();
+            }
+            _ => {
+                let word = self.word_under_cursor();
+                self.search_query.handle_key(key, word, &mut self.clipboard_context);
+            }
+        }
+        Ok(false)
+    }
+
+    pub(crate) fn get_editor_width(&self) -> usize {
+        self.editor_width
+    }
+}
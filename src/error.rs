@@ -0,0 +1,84 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::editor::Editor;
+
+#[derive(Debug)]
+pub enum PhantomError {
+    Io { path: PathBuf, source: io::Error },
+    Config { file: String, field: Option<String>, message: String },
+    Clipboard(String),
+    Syntax { file: String, message: String },
+    Command { input: String, message: String },
+    NoConfigDir,
+    ReadOnly(PathBuf),
+}
+
+impl PhantomError {
+    pub(crate) fn io(path: &Path, source: io::Error) -> Self {
+        PhantomError::Io { path: path.to_path_buf(), source }
+    }
+
+    pub(crate) fn config(file: &str, message: impl fmt::Display) -> Self {
+        PhantomError::Config { file: file.to_string(), field: None, message: message.to_string() }
+    }
+
+    pub(crate) fn toml(file: &str, text: &str, error: &toml::de::Error) -> Self {
+        let field = error.span().and_then(|span| toml_field(text, span.start));
+        let line = error.span().map(|span| text[..span.start.min(text.len())].matches('\n').count() + 1);
+        let message = match line {
+            Some(line) => format!("{} (line {})", error.message().trim_end(), line),
+            None => error.message().trim_end().to_string(),
+        };
+        PhantomError::Config { file: file.to_string(), field, message }
+    }
+}
+
+fn toml_field(text: &str, offset: usize) -> Option<String> {
+    let before = text.get(..offset)?;
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    let line = &text[line_start..];
+    let line = line.split('\n').next().unwrap_or(line);
+    let equals = line.find('=').filter(|&equals| line_start + equals < offset)?;
+    let key = line[..equals].trim().trim_matches('"');
+    let section = before[..line_start].lines().rev()
+        .map(str::trim)
+        .find(|line| line.starts_with('[') && !line.starts_with("[["))
+        .map(|line| line.trim_matches(|c| c == '[' || c == ']').trim());
+    Some(match section {
+        Some(section) => format!("{}.{}", section, key),
+        None => key.to_string(),
+    })
+}
+
+impl fmt::Display for PhantomError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PhantomError::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+            PhantomError::Config { file, field: Some(field), message } => write!(f, "{}: {}: {}", file, field, message),
+            PhantomError::Config { file, field: None, message } => write!(f, "{}: {}", file, message),
+            PhantomError::Clipboard(message) => write!(f, "Clipboard error: {}", message),
+            PhantomError::Syntax { file, message } => write!(f, "{}: {}", file, message),
+            PhantomError::Command { input, message } => write!(f, "{}: {}", message, input),
+            PhantomError::NoConfigDir => write!(f, "Could not find config directory"),
+            PhantomError::ReadOnly(path) => write!(f, "{} is read-only", path.display()),
+        }
+    }
+}
+
+impl Error for PhantomError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PhantomError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl Editor {
+    pub(crate) fn report(&mut self, error: PhantomError) {
+        self.error(error.to_string());
+    }
+}
@@ -0,0 +1,143 @@
+use std::path::{Path, PathBuf};
+use std::slice;
+
+use syntect::parsing::{SyntaxReference, SyntaxSet};
+
+use crate::complete::{nearest, prefix_completions};
+use crate::editor::Editor;
+
+const WELL_KNOWN_FILES: &[(&str, &str)] = &[
+    ("APKBUILD", "Bourne Again Shell (bash)"),
+    ("CMakeLists.txt", "CMake"),
+    ("COMMIT_EDITMSG", "Git Commit"),
+    ("Cargo.lock", "TOML"),
+    ("Containerfile", "Dockerfile"),
+    ("Dockerfile", "Dockerfile"),
+    ("Jenkinsfile", "Groovy"),
+    ("MERGE_MSG", "Git Commit"),
+    ("PKGBUILD", "Bourne Again Shell (bash)"),
+    ("Pipfile", "TOML"),
+    ("TAG_EDITMSG", "Git Commit"),
+    ("git-rebase-todo", "Git Rebase Todo"),
+    (".gitconfig", "Git Config"),
+    (".zprofile", "Bourne Again Shell (bash)"),
+    (".zshrc", "Bourne Again Shell (bash)"),
+];
+
+const MODELINE_LINES: usize = 5;
+
+fn file_name_syntax<'a>(ps: &'a SyntaxSet, name: &str) -> Option<&'a SyntaxReference> {
+    ps.find_syntax_by_extension(name).or_else(|| {
+        let (_, syntax) = WELL_KNOWN_FILES.iter().find(|(file, _)| *file == name)?;
+        ps.find_syntax_by_name(syntax)
+    })
+}
+
+fn interpreter_syntax<'a>(ps: &'a SyntaxSet, line: &str) -> Option<&'a SyntaxReference> {
+    let mut words = line.strip_prefix("#!")?.split_whitespace();
+    let mut interpreter = words.next()?.rsplit('/').next()?;
+    if interpreter == "env" {
+        interpreter = words.find(|word| !word.starts_with('-') && !word.contains('='))?;
+    }
+    match interpreter.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.') {
+        "node" | "nodejs" | "deno" | "bun" => ps.find_syntax_by_extension("js"),
+        name => ps.find_syntax_by_token(name),
+    }
+}
+
+pub(crate) fn detect_syntax<'a>(ps: &'a SyntaxSet, path: &Path, first_line: &str) -> Option<&'a SyntaxReference> {
+    // File names win over extensions (CMakeLists.txt is not plain text), and the first line
+    // is only consulted for files neither of them identifies. Editor::open_file then applies
+    // [filetype_detect] and, when enabled, modelines on top of this.
+    let name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+    let extension = path.extension().and_then(|extension| extension.to_str());
+    file_name_syntax(ps, name)
+        .or_else(|| extension.and_then(|extension| ps.find_syntax_by_extension(extension)))
+        .or_else(|| ps.find_syntax_by_first_line(first_line))
+        .or_else(|| interpreter_syntax(ps, first_line))
+}
+
+pub(crate) fn parse_modeline(line: &str) -> Option<Vec<String>> {
+    let start = ["vim:", "Vim:", "vi:", "ex:"].iter()
+        .filter_map(|marker| {
+            line.match_indices(marker)
+                .find(|&(index, _)| index == 0 || line[..index].ends_with(char::is_whitespace))
+                .map(|(index, _)| index + marker.len())
+        })
+        .min()?;
+    let rest = line[start..].trim_start();
+    let options: Vec<String> = match rest.strip_prefix("set ").or_else(|| rest.strip_prefix("se ")) {
+        Some(rest) => rest.split_once(':')?.0.split_whitespace().map(String::from).collect(),
+        None => rest.split(|c: char| c == ':' || c.is_whitespace())
+            .filter(|option| !option.is_empty())
+            .map(String::from)
+            .collect(),
+    };
+    Some(options).filter(|options| !options.is_empty())
+}
+
+impl Editor {
+    pub(crate) fn syntax_names(&self) -> impl Iterator<Item = &str> {
+        self.ps.syntaxes().iter().map(|syntax| syntax.name.as_str())
+    }
+
+    pub(crate) fn unknown_syntax(&self, name: &str) -> String {
+        match nearest(name, self.syntax_names()) {
+            Some(syntax) => format!("Unknown syntax: {} (did you mean {}?)", name, syntax),
+            None => format!("Unknown syntax: {}", name),
+        }
+    }
+
+    pub(crate) fn syntax_completions(&self, arguments: &str) -> (usize, Vec<String>) {
+        (0, prefix_completions(arguments, self.syntax_names()))
+    }
+
+    pub(crate) fn find_syntax(&self, name: &str) -> Option<&SyntaxReference> {
+        self.ps.syntaxes().iter()
+            .find(|syntax| syntax.name.eq_ignore_ascii_case(name))
+            .or_else(|| self.ps.find_syntax_by_extension(name))
+    }
+
+    pub(crate) fn configured_syntax(&mut self, path: &Path) -> Option<String> {
+        let extension = path.extension().map(|extension| extension.to_string_lossy()).unwrap_or_default();
+        let (pattern, name) = self.settings.filetype_detect.iter()
+            .find(|(pattern, _)| **pattern == extension || Self::path_matches(slice::from_ref(pattern), path))?;
+        match self.find_syntax(name) {
+            Some(syntax) => Some(syntax.name.clone()),
+            None => {
+                let warning = format!("filetype_detect: unknown syntax {} for {}", name, pattern);
+                self.warn(warning);
+                None
+            }
+        }
+    }
+
+    pub(crate) fn detect_file_syntax(&mut self, tab_index: usize) -> Option<String> {
+        let path = PathBuf::from(self.tabs[tab_index].current_file.as_ref()?);
+        let first_line = self.tabs[tab_index].content[0].clone();
+        self.configured_syntax(&path)
+            .or_else(|| detect_syntax(&self.ps, &path, &first_line).map(|syntax| syntax.name.clone()))
+    }
+
+    pub(crate) fn apply_modeline(&mut self) {
+        if !self.option("modeline").bool() {
+            return;
+        }
+        let content = &self.tabs[self.active_tab].content;
+        let tail = content.len().saturating_sub(MODELINE_LINES).max(MODELINE_LINES.min(content.len()));
+        let options: Vec<String> = content[..MODELINE_LINES.min(content.len())].iter()
+            .chain(&content[tail..])
+            .filter_map(|line| parse_modeline(line))
+            .flatten()
+            .collect();
+        for option in options {
+            let argument = match option.split_once('=') {
+                Some(("ft" | "filetype", value)) => format!("syntax={}", value),
+                _ => option,
+            };
+            if let Err(e) = self.set_option(&argument, true) {
+                self.warn(format!("Modeline: {}", e));
+            }
+        }
+    }
+}
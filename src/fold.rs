@@ -0,0 +1,70 @@
+use std::path::Path;
+
+use crate::config::FoldMethod;
+use crate::editor::Editor;
+
+impl Editor {
+    pub(crate) fn fold_method(&self) -> FoldMethod {
+        if self.option("foldmethod").text() == "brace" {
+            FoldMethod::Brace
+        } else {
+            FoldMethod::Indent
+        }
+    }
+
+    pub(crate) fn store_folds(&mut self, tab_index: usize) {
+        let tab = &self.tabs[tab_index];
+        let Some(file) = tab.current_file.as_ref().filter(|_| !tab.private) else {
+            return;
+        };
+        let path = Path::new(file);
+        if !path.exists() || !self.fold_store.set(Self::canonical_path(path), &self.tabs[tab_index].folds) {
+            return;
+        }
+        if let Some(data_dir) = &self.paths.data_dir {
+            if let Err(e) = self.fold_store.save(&data_dir.join("folds.json")) {
+                self.warn(format!("Failed to save folds: {}", e));
+            }
+        }
+    }
+
+    pub(crate) fn toggle_fold(&mut self) {
+        let method = self.fold_method();
+        let tab = &mut self.tabs[self.active_tab];
+        let (x, y) = tab.cursor_position;
+        if let Some(fold) = tab.fold_at(y) {
+            tab.folds.retain(|&closed| closed != fold);
+        } else {
+            let region = tab.fold_regions(method).into_iter()
+                .filter(|&(start, end)| start <= y && y <= end)
+                .min_by_key(|&(start, end)| end - start);
+            let Some(region) = region else {
+                self.info("No fold found");
+                return;
+            };
+            tab.folds.push(region);
+            tab.folds.sort_unstable();
+            tab.set_cursor(x, region.0);
+        }
+        self.ensure_cursor_visible();
+        self.store_folds(self.active_tab);
+    }
+
+    pub(crate) fn open_all_folds(&mut self) {
+        self.tabs[self.active_tab].folds.clear();
+        self.ensure_cursor_visible();
+        self.store_folds(self.active_tab);
+    }
+
+    pub(crate) fn close_all_folds(&mut self) {
+        let method = self.fold_method();
+        let tab = &mut self.tabs[self.active_tab];
+        tab.folds = tab.fold_regions(method);
+        if let Some((start, _)) = tab.fold_at(tab.cursor_position.1) {
+            let x = tab.cursor_position.0;
+            tab.set_cursor(x, start);
+        }
+        self.ensure_cursor_visible();
+        self.store_folds(self.active_tab);
+    }
+}
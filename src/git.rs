@@ -0,0 +1,149 @@
+use std::path::Path;
+
+use tui::style::{Modifier, Style};
+
+use crate::buffer::Tab;
+use crate::editor::{Editor, Mode};
+use crate::options::OptionValue;
+
+const MESSAGE_FILES: [&str; 5] = ["COMMIT_EDITMSG", "MERGE_MSG", "TAG_EDITMSG", "SQUASH_MSG", "EDIT_DESCRIPTION"];
+const REBASE_TODO: &str = "git-rebase-todo";
+
+const REBASE_COMMANDS: [(&str, &str); 12] = [
+    ("p", "pick"),
+    ("r", "reword"),
+    ("e", "edit"),
+    ("s", "squash"),
+    ("f", "fixup"),
+    ("x", "exec"),
+    ("b", "break"),
+    ("d", "drop"),
+    ("l", "label"),
+    ("t", "reset"),
+    ("m", "merge"),
+    ("u", "update-ref"),
+];
+const REBASE_CYCLE: [&str; 4] = ["pick", "squash", "fixup", "edit"];
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub(crate) enum GitFile {
+    Message,
+    RebaseTodo,
+}
+
+pub(crate) fn git_file(path: &Path) -> Option<GitFile> {
+    let name = path.file_name()?.to_str()?;
+    if MESSAGE_FILES.contains(&name) {
+        Some(GitFile::Message)
+    } else if name == REBASE_TODO {
+        Some(GitFile::RebaseTodo)
+    } else {
+        None
+    }
+}
+
+fn rebase_command(word: &str) -> Option<&'static str> {
+    REBASE_COMMANDS.iter()
+        .find(|(short, long)| *short == word || *long == word)
+        .map(|(_, long)| *long)
+}
+
+fn command_word(line: &str) -> (usize, usize) {
+    let start = line.len() - line.trim_start().len();
+    let end = line[start..].find(char::is_whitespace).map_or(line.len(), |end| start + end);
+    (start, end)
+}
+
+impl Tab {
+    pub(crate) fn git_file(&self) -> Option<GitFile> {
+        self.current_file.as_deref().and_then(|file| git_file(Path::new(file)))
+    }
+}
+
+impl Editor {
+    pub(crate) fn apply_git_defaults(&mut self) {
+        let Some(kind) = self.tabs[self.active_tab].git_file() else {
+            return;
+        };
+        let color_column = self.settings.git.color_column;
+        let insert = self.settings.git.insert_mode;
+        let wrap = self.settings.git.wrap;
+        let tab = &mut self.tabs[self.active_tab];
+        tab.cursor_position = (0, 0);
+        tab.scroll_offset = 0;
+        if kind != GitFile::Message {
+            return;
+        }
+        if color_column > 0 {
+            tab.options.entry("colorcolumn").or_insert(OptionValue::Number(color_column));
+        }
+        if wrap {
+            tab.options.entry("wrap").or_insert(OptionValue::Bool(true));
+        }
+        if insert && !tab.read_only && tab.content[0].is_empty() {
+            self.mode = Mode::Insert;
+        }
+    }
+
+    pub(crate) fn git_highlight<'a>(&self, kind: GitFile, index: usize, line: &'a str) -> Vec<(Style, &'a str)> {
+        let plain = self.color_config.fg(&self.color_config.foreground);
+        if line.starts_with('#') {
+            return vec![(self.color_config.fg(&self.color_config.comment), line)];
+        }
+        match kind {
+            GitFile::Message if index == 0 => vec![(self.color_config.fg(&self.color_config.keyword).add_modifier(Modifier::BOLD), line)],
+            GitFile::Message => vec![(plain, line)],
+            GitFile::RebaseTodo => {
+                let (start, end) = command_word(line);
+                if rebase_command(&line[start..end]).is_none() {
+                    return vec![(plain, line)];
+                }
+                let rest = &line[end..];
+                let hash_start = end + rest.len() - rest.trim_start().len();
+                let hash_end = line[hash_start..].find(char::is_whitespace).map_or(line.len(), |end| hash_start + end);
+                let hash = &line[hash_start..hash_end];
+                let mut ranges = vec![(plain, &line[..start]), (self.color_config.fg(&self.color_config.keyword), &line[start..end])];
+                if hash.len() >= 4 && hash.bytes().all(|b| b.is_ascii_hexdigit()) {
+                    ranges.push((plain, &line[end..hash_start]));
+                    ranges.push((self.color_config.fg(&self.color_config.number), hash));
+                    ranges.push((plain, &line[hash_end..]));
+                } else {
+                    ranges.push((plain, &line[end..]));
+                }
+                ranges
+            }
+        }
+    }
+
+    pub(crate) fn cycle_rebase_command(&mut self) {
+        let tab = &self.tabs[self.active_tab];
+        if tab.git_file() != Some(GitFile::RebaseTodo) {
+            self.info("Not a git rebase todo list");
+            return;
+        }
+        if tab.read_only {
+            self.error("Buffer is read-only");
+            return;
+        }
+        let line = &tab.content[tab.cursor_position.1];
+        let (start, end) = command_word(line);
+        let next = match rebase_command(&line[start..end]) {
+            Some(command) if matches!(command, "pick" | "reword" | "edit" | "squash" | "fixup" | "drop") => REBASE_CYCLE.iter()
+                .position(|cycle| *cycle == command)
+                .map_or("pick", |index| REBASE_CYCLE[(index + 1) % REBASE_CYCLE.len()]),
+            Some(command) => {
+                self.warn(format!("{} doesn't take a commit", command));
+                return;
+            }
+            None => {
+                self.warn("No rebase command on this line");
+                return;
+            }
+        };
+        self.save_state();
+        let tab = &mut self.tabs[self.active_tab];
+        let y = tab.cursor_position.1;
+        tab.content[y].replace_range(start..end, next);
+        tab.cursor_position.0 = start;
+    }
+}
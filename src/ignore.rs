@@ -0,0 +1,207 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::natural::natural_path_cmp;
+
+pub(crate) struct IgnoreRule {
+    pub(crate) base: PathBuf,
+    pub(crate) pattern: Vec<char>,
+    pub(crate) negated: bool,
+    pub(crate) dir_only: bool,
+    pub(crate) anchored: bool,
+}
+
+pub(crate) struct IgnoreRules {
+    pub(crate) root: PathBuf,
+    pub(crate) rules: Vec<IgnoreRule>,
+}
+
+impl IgnoreRules {
+    pub(crate) fn for_dir(dir: &Path, global_ignore: Option<&Path>) -> Self {
+        let dir = fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf());
+        let root = dir.ancestors()
+            .find(|ancestor| ancestor.join(".git").exists())
+            .unwrap_or(&dir)
+            .to_path_buf();
+
+        let mut ignore_rules = IgnoreRules { root: root.clone(), rules: Vec::new() };
+        if let Some(global_ignore) = global_ignore {
+            ignore_rules.add_file(global_ignore, &root);
+        }
+        let mut chain: Vec<&Path> = dir.ancestors().take_while(|ancestor| ancestor.starts_with(&root)).collect();
+        chain.reverse();
+        for ancestor in chain {
+            ignore_rules.add_file(&ancestor.join(".gitignore"), ancestor);
+        }
+        ignore_rules
+    }
+
+    pub(crate) fn add_file(&mut self, file: &Path, base: &Path) {
+        let Ok(contents) = fs::read_to_string(file) else {
+            return;
+        };
+        for line in contents.lines() {
+            let line = line.trim_end();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (negated, line) = match line.strip_prefix('!') {
+                Some(rest) => (true, rest),
+                None => (false, line.strip_prefix('\\').unwrap_or(line)),
+            };
+            let (dir_only, line) = match line.strip_suffix('/') {
+                Some(rest) => (true, rest),
+                None => (false, line),
+            };
+            let anchored = line.contains('/');
+            self.rules.push(IgnoreRule {
+                base: base.to_path_buf(),
+                pattern: line.trim_start_matches('/').chars().collect(),
+                negated,
+                dir_only,
+                anchored,
+            });
+        }
+    }
+
+    pub(crate) fn glob(pattern: &[char], text: &[char]) -> bool {
+        match pattern.first() {
+            None => text.is_empty(),
+            Some('*') if pattern.get(1) == Some(&'*') => {
+                let mut rest = &pattern[2..];
+                if rest.first() == Some(&'/') {
+                    rest = &rest[1..];
+                }
+                (0..=text.len()).any(|i| (i == 0 || text[i - 1] == '/') && Self::glob(rest, &text[i..]))
+                    || rest.is_empty()
+            }
+            Some('*') => {
+                for i in 0..=text.len() {
+                    if Self::glob(&pattern[1..], &text[i..]) {
+                        return true;
+                    }
+                    if i < text.len() && text[i] == '/' {
+                        break;
+                    }
+                }
+                false
+            }
+            Some('?') => !text.is_empty() && text[0] != '/' && Self::glob(&pattern[1..], &text[1..]),
+            Some('[') => {
+                let Some(close) = pattern.iter().skip(2).position(|&c| c == ']').map(|i| i + 2) else {
+                    return text.first() == Some(&'[') && Self::glob(&pattern[1..], &text[1..]);
+                };
+                let Some(&c) = text.first() else {
+                    return false;
+                };
+                let mut class = &pattern[1..close];
+                let negated = matches!(class.first(), Some('!') | Some('^'));
+                if negated {
+                    class = &class[1..];
+                }
+                let mut matched = false;
+                let mut i = 0;
+                while i < class.len() {
+                    if i + 2 < class.len() && class[i + 1] == '-' {
+                        matched |= class[i] <= c && c <= class[i + 2];
+                        i += 3;
+                    } else {
+                        matched |= class[i] == c;
+                        i += 1;
+                    }
+                }
+                matched != negated && Self::glob(&pattern[close + 1..], &text[1..])
+            }
+            Some(&p) => text.first() == Some(&p) && Self::glob(&pattern[1..], &text[1..]),
+        }
+    }
+
+    pub(crate) fn matches(&self, path: &Path, is_dir: bool) -> bool {
+        if path.file_name().is_some_and(|name| name == ".git") {
+            return true;
+        }
+        let mut ignored = false;
+        for rule in &self.rules {
+            if rule.dir_only && !is_dir {
+                continue;
+            }
+            let Ok(relative) = path.strip_prefix(&rule.base) else {
+                continue;
+            };
+            let text: Vec<char> = if rule.anchored {
+                relative.to_string_lossy().replace('\\', "/").chars().collect()
+            } else {
+                match path.file_name() {
+                    Some(name) => name.to_string_lossy().chars().collect(),
+                    None => continue,
+                }
+            };
+            if Self::glob(&rule.pattern, &text) {
+                ignored = !rule.negated;
+            }
+        }
+        ignored
+    }
+
+    pub(crate) fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let Ok(relative) = path.strip_prefix(&self.root) else {
+            return false;
+        };
+        let mut current = self.root.clone();
+        let components: Vec<_> = relative.components().collect();
+        for (i, component) in components.iter().enumerate() {
+            current.push(component);
+            let last = i + 1 == components.len();
+            if self.matches(&current, if last { is_dir } else { true }) {
+                return true;
+            }
+        }
+        false
+    }
+
+    pub(crate) fn list_dir(&self, dir: &Path, show_ignored: bool) -> io::Result<Vec<(PathBuf, bool)>> {
+        let dir = fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf());
+        Ok(fs::read_dir(&dir)?
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| self.entry(entry.path(), show_ignored))
+            .collect())
+    }
+
+    pub(crate) fn entries(self, dir: &Path, show_ignored: bool) -> io::Result<impl Iterator<Item = (PathBuf, bool)> + Send> {
+        let dir = fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf());
+        Ok(fs::read_dir(&dir)?
+            .filter_map(|entry| entry.ok())
+            .filter_map(move |entry| self.entry(entry.path(), show_ignored)))
+    }
+
+    fn entry(&self, path: PathBuf, show_ignored: bool) -> Option<(PathBuf, bool)> {
+        let ignored = self.is_ignored(&path, path.is_dir());
+        (show_ignored || !ignored).then_some((path, ignored))
+    }
+
+    pub(crate) fn walk(mut self, dir: &Path) -> Vec<PathBuf> {
+        let mut files = Vec::new();
+        let start = fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf());
+        let mut pending = vec![start.clone()];
+        while let Some(dir) = pending.pop() {
+            if dir != start {
+                self.add_file(&dir.join(".gitignore"), &dir);
+            }
+            let Ok(listing) = self.list_dir(&dir, false) else {
+                continue;
+            };
+            let mut listing: Vec<PathBuf> = listing.into_iter().map(|(path, _)| path).collect();
+            listing.sort_by(|a, b| natural_path_cmp(a, b));
+            for path in listing.into_iter().rev() {
+                if path.is_dir() {
+                    pending.push(path);
+                } else {
+                    files.push(path);
+                }
+            }
+        }
+        files.sort_by(|a, b| natural_path_cmp(a, b));
+        files
+    }
+}
@@ -0,0 +1,229 @@
+use syntect::parsing::{ParseState, Scope, ScopeStack, SyntaxReference, SyntaxSet};
+
+use crate::buffer::line_width;
+use crate::commands::LineRange;
+use crate::editor::Editor;
+use crate::options::OptionValue;
+
+const SAMPLE_LINES: usize = 1000;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub(crate) enum IndentStyle {
+    Tabs,
+    Spaces(usize),
+}
+
+pub(crate) fn leading_whitespace(line: &str) -> &str {
+    &line[..line.len() - line.trim_start_matches([' ', '\t']).len()]
+}
+
+pub(crate) fn detect_indent(lines: &[String]) -> Option<(IndentStyle, bool)> {
+    let (mut tabs, mut spaces, mut spaces_before_tabs) = (0, 0, 0);
+    let mut steps = [0; 9];
+    let mut previous = 0;
+    for line in lines.iter().take(SAMPLE_LINES) {
+        let indent = leading_whitespace(line);
+        if indent.len() == line.len() {
+            continue;
+        }
+        if indent.starts_with('\t') {
+            tabs += 1;
+            previous = 0;
+        } else if indent.contains('\t') {
+            spaces_before_tabs += 1;
+            previous = 0;
+        } else if indent.len() >= 2 {
+            spaces += 1;
+            if let Some(step) = steps.get_mut(indent.len().saturating_sub(previous)) {
+                *step += 1;
+            }
+            previous = indent.len();
+        } else {
+            previous = indent.len();
+        }
+    }
+    if tabs == 0 && spaces == 0 {
+        return None;
+    }
+    let mixed = (tabs > 0 && spaces > 0) || spaces_before_tabs > 0;
+    if tabs >= spaces {
+        return Some((IndentStyle::Tabs, mixed));
+    }
+    let width = (2..steps.len()).rev().max_by_key(|&width| steps[width]).filter(|&width| steps[width] > 0).unwrap_or(4);
+    Some((IndentStyle::Spaces(width), mixed))
+}
+
+pub(crate) fn indent_disagrees(style: IndentStyle, indent: &str) -> bool {
+    match style {
+        IndentStyle::Tabs => indent.starts_with("  ") || indent.contains(" \t"),
+        IndentStyle::Spaces(_) => indent.contains('\t'),
+    }
+}
+
+pub(crate) fn indent_columns(line: &str, tab_width: usize) -> Option<usize> {
+    (!line.trim().is_empty()).then(|| line_width(leading_whitespace(line), tab_width))
+}
+
+pub(crate) struct GuideLevels<'a> {
+    content: &'a [String],
+    tab_width: usize,
+    line: usize,
+    previous: usize,
+    next: Option<(usize, usize)>,
+}
+
+impl<'a> GuideLevels<'a> {
+    pub(crate) fn new(content: &'a [String], tab_width: usize) -> Self {
+        GuideLevels { content, tab_width, line: usize::MAX, previous: 0, next: None }
+    }
+
+    pub(crate) fn level(&mut self, y: usize) -> usize {
+        if self.line.wrapping_add(1) != y {
+            self.previous = self.content[..y].iter().rev().find_map(|line| indent_columns(line, self.tab_width)).unwrap_or(0);
+            self.next = None;
+        }
+        self.line = y;
+        if let Some(width) = indent_columns(&self.content[y], self.tab_width) {
+            self.previous = width;
+            return width;
+        }
+        let next = match self.next.filter(|&(line, _)| line > y) {
+            Some((_, width)) => width,
+            None => {
+                let next = self.content[y + 1..].iter()
+                    .enumerate()
+                    .find_map(|(offset, line)| indent_columns(line, self.tab_width).map(|width| (y + 1 + offset, width)))
+                    .unwrap_or((usize::MAX, 0));
+                self.next = Some(next);
+                next.1
+            }
+        };
+        self.previous.min(next)
+    }
+
+    pub(crate) fn columns(&self, level: usize) -> impl Iterator<Item = usize> {
+        (0..level).step_by(self.tab_width.max(1))
+    }
+
+    pub(crate) fn active_block(&mut self, cursor: usize, limit: usize) -> Option<(usize, usize, usize)> {
+        let level = self.level(cursor);
+        let column = self.columns(level).last()?;
+        let inside = |y: &usize| indent_columns(&self.content[*y], self.tab_width).map(|width| width > column);
+        let mut first = cursor;
+        for y in (cursor.saturating_sub(limit)..cursor).rev() {
+            match inside(&y) {
+                Some(true) => first = y,
+                Some(false) => break,
+                None => {}
+            }
+        }
+        let mut last = cursor;
+        for y in cursor + 1..(cursor + limit).min(self.content.len()) {
+            match inside(&y) {
+                Some(true) => last = y,
+                Some(false) => break,
+                None => {}
+            }
+        }
+        Some((column, first, last))
+    }
+}
+
+fn string_lines(lines: &[String], syntax: &SyntaxReference, ps: &SyntaxSet) -> Vec<bool> {
+    let string = Scope::new("string").expect("the string scope name is valid");
+    let mut state = ParseState::new(syntax);
+    let mut stack = ScopeStack::new();
+    lines.iter()
+        .map(|line| {
+            let in_string = stack.as_slice().iter().any(|&scope| string.is_prefix_of(scope));
+            for (_, op) in state.parse_line(&format!("{}\n", line), ps).unwrap_or_default() {
+                let _ = stack.apply(&op);
+            }
+            in_string
+        })
+        .collect()
+}
+
+impl Editor {
+    pub(crate) fn apply_detected_indent(&mut self) {
+        let tab = &mut self.tabs[self.active_tab];
+        let detected = detect_indent(&tab.content);
+        tab.indent_style = detected.map(|(style, _)| style);
+        let Some((style, mixed)) = detected else {
+            return;
+        };
+        if self.settings.detect_indent {
+            let (expand_tab, tab_width) = match style {
+                IndentStyle::Tabs => (false, None),
+                IndentStyle::Spaces(width) => (true, Some(width)),
+            };
+            tab.options.entry("expandtab").or_insert(OptionValue::Bool(expand_tab));
+            if let Some(width) = tab_width {
+                tab.options.entry("tabstop").or_insert(OptionValue::Number(width));
+            }
+        }
+        if mixed {
+            let file = self.tabs[self.active_tab].current_file.clone().unwrap_or_default();
+            let name = self.display_path(&file);
+            self.warn(format!("{}: mixed indentation (tabs+spaces)", name));
+        }
+    }
+
+    pub(crate) fn retab(&mut self, range: Option<LineRange>, args: &str) -> Result<(), String> {
+        let (to_tabs, width) = match args.strip_prefix('!') {
+            Some(width) => (true, width.trim()),
+            None => (false, args.trim()),
+        };
+        let width = match width {
+            "" => self.option("tabstop").number(),
+            width => width.parse::<usize>().ok().filter(|width| (1..=32).contains(width)).ok_or_else(|| format!("Invalid tab width: {}", width))?,
+        };
+        let tab = &self.tabs[self.active_tab];
+        if tab.read_only {
+            return Err("Buffer is read-only".to_string());
+        }
+        let (start, end) = range.unwrap_or((1, tab.content.len()));
+        if start == 0 {
+            return Err("Invalid range".to_string());
+        }
+        let syntax = self.ps.find_syntax_by_name(&tab.syntax).filter(|syntax| syntax.name != "Plain Text");
+        let in_string = syntax.map(|syntax| string_lines(&tab.content[..end], syntax, &self.ps)).unwrap_or_default();
+        let changes: Vec<(usize, String)> = (start - 1..end)
+            .filter(|&y| !in_string.get(y).copied().unwrap_or(false))
+            .filter_map(|y| {
+                let indent = leading_whitespace(&tab.content[y]);
+                let columns = line_width(indent, width);
+                let replaced = if to_tabs {
+                    format!("{}{}", "\t".repeat(columns / width), " ".repeat(columns % width))
+                } else {
+                    " ".repeat(columns)
+                };
+                (replaced != indent).then_some((y, replaced))
+            })
+            .collect();
+
+        if !changes.is_empty() {
+            self.save_state();
+        }
+        let tab = &mut self.tabs[self.active_tab];
+        for (y, replaced) in &changes {
+            let old = leading_whitespace(&tab.content[*y]).len();
+            tab.content[*y].replace_range(..old, replaced);
+            if tab.cursor_position.1 == *y {
+                let x = tab.cursor_position.0;
+                tab.cursor_position.0 = if x < old { x.min(replaced.len()) } else { x + replaced.len() - old };
+            }
+        }
+        tab.options.insert("expandtab", OptionValue::Bool(!to_tabs));
+        tab.options.insert("tabstop", OptionValue::Number(width));
+        if range.is_none() {
+            tab.indent_style = Some(if to_tabs { IndentStyle::Tabs } else { IndentStyle::Spaces(width) });
+        }
+        self.info(match changes.len() {
+            0 => "Indentation already matches".to_string(),
+            1 => "Retabbed 1 line".to_string(),
+            count => format!("Retabbed {} lines", count),
+        });
+        Ok(())
+    }
+}
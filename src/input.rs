@@ -0,0 +1,135 @@
+use copypasta::ClipboardProvider;
+use crossterm::event::{self, KeyCode, KeyEvent, KeyModifiers};
+
+use crate::editor::{ClipboardWrapper, Editor};
+
+#[derive(Default)]
+pub(crate) struct InputLine {
+    pub(crate) text: String,
+    pub(crate) cursor: usize,
+    pub(crate) pending_register: bool,
+}
+
+impl InputLine {
+    pub(crate) fn byte_index(&self, cursor: usize) -> usize {
+        self.text.char_indices().nth(cursor).map(|(i, _)| i).unwrap_or(self.text.len())
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.text.chars().count()
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.text.clear();
+        self.cursor = 0;
+        self.pending_register = false;
+    }
+
+    pub(crate) fn insert_str(&mut self, text: &str) {
+        let text: String = text.chars().filter(|c| !c.is_control()).collect();
+        let index = self.byte_index(self.cursor);
+        self.text.insert_str(index, &text);
+        self.cursor += text.chars().count();
+    }
+
+    pub(crate) fn delete_range(&mut self, start: usize, end: usize) {
+        let (start_byte, end_byte) = (self.byte_index(start), self.byte_index(end));
+        self.text.replace_range(start_byte..end_byte, "");
+        self.cursor = start;
+    }
+
+    pub(crate) fn word_start_before_cursor(&self) -> usize {
+        let chars: Vec<char> = self.text.chars().collect();
+        let mut start = self.cursor;
+        while start > 0 && chars[start - 1].is_whitespace() {
+            start -= 1;
+        }
+        let word = start > 0 && (chars[start - 1].is_alphanumeric() || chars[start - 1] == '_');
+        while start > 0 && !chars[start - 1].is_whitespace()
+            && (chars[start - 1].is_alphanumeric() || chars[start - 1] == '_') == word {
+            start -= 1;
+        }
+        start
+    }
+
+    pub(crate) fn handle_key(&mut self, key: KeyEvent, word: Option<String>, clipboard: &mut ClipboardWrapper) -> bool {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        if self.pending_register {
+            self.pending_register = false;
+            match key.code {
+                KeyCode::Char('w') if ctrl => self.insert_str(&word.unwrap_or_default()),
+                KeyCode::Char('+') | KeyCode::Char('*') | KeyCode::Char('"') => {
+                    let contents = clipboard.get_contents().unwrap_or_default();
+                    self.insert_str(contents.lines().next().unwrap_or_default());
+                }
+                _ => {}
+            }
+            return true;
+        }
+        match key.code {
+            KeyCode::Char('r') if ctrl => self.pending_register = true,
+            KeyCode::Char('v') | KeyCode::Char('p') if ctrl => {
+                let contents = clipboard.get_contents().unwrap_or_default();
+                self.insert_str(contents.lines().next().unwrap_or_default());
+            }
+            KeyCode::Char('w') if ctrl => self.delete_range(self.word_start_before_cursor(), self.cursor),
+            KeyCode::Char('u') if ctrl => self.delete_range(0, self.cursor),
+            KeyCode::Char('a') if ctrl => self.cursor = 0,
+            KeyCode::Char('e') if ctrl => self.cursor = self.len(),
+            KeyCode::Char(_) if ctrl => {}
+            KeyCode::Char(c) => self.insert_str(&c.to_string()),
+            KeyCode::Backspace if self.cursor > 0 => self.delete_range(self.cursor - 1, self.cursor),
+            KeyCode::Delete if self.cursor < self.len() => self.delete_range(self.cursor, self.cursor + 1),
+            KeyCode::Left => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Right => self.cursor = (self.cursor + 1).min(self.len()),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = self.len(),
+            KeyCode::Backspace | KeyCode::Delete => {}
+            _ => return false,
+        }
+        true
+    }
+}
+
+impl Editor {
+    pub(crate) fn key_event_to_string(key: event::KeyEvent) -> String {
+        let mut key_string = String::new();
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            key_string.push_str("Ctrl+");
+        }
+        if key.modifiers.contains(KeyModifiers::ALT) {
+            key_string.push_str("Alt+");
+        }
+        let plain_char = matches!(key.code, KeyCode::Char(_)) && !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
+        if key.modifiers.contains(KeyModifiers::SHIFT) && !plain_char {
+            key_string.push_str("Shift+");
+        }
+        match key.code {
+            KeyCode::Char(c) => {
+                if key.modifiers.contains(KeyModifiers::CONTROL) {
+                    key_string.push(c.to_ascii_lowercase());
+                } else {
+                    key_string.push(c);
+                }
+            },
+            KeyCode::F(n) => key_string.push_str(&format!("F{}", n)),
+            KeyCode::Enter => key_string.push_str("Enter"),
+            KeyCode::Left => key_string.push_str("Left"),
+            KeyCode::Right => key_string.push_str("Right"),
+            KeyCode::Up => key_string.push_str("Up"),
+            KeyCode::Down => key_string.push_str("Down"),
+            KeyCode::Backspace => key_string.push_str("Backspace"),
+            KeyCode::Delete => key_string.push_str("Delete"),
+            KeyCode::Home => key_string.push_str("Home"),
+            KeyCode::End => key_string.push_str("End"),
+            KeyCode::PageUp => key_string.push_str("PageUp"),
+            KeyCode::PageDown => key_string.push_str("PageDown"),
+            KeyCode::Tab => key_string.push_str("Tab"),
+            KeyCode::BackTab => key_string.push_str("BackTab"),
+            KeyCode::Insert => key_string.push_str("Insert"),
+            KeyCode::Esc => key_string.push_str("Esc"),
+            _ => key_string.push_str(&format!("{:?}", key.code)),
+        }
+        key_string
+    }
+}
@@ -0,0 +1,58 @@
+#![deny(clippy::unwrap_used)]
+mod actions;
+mod batch;
+mod blame;
+mod buffer;
+mod calc;
+mod charinfo;
+mod closed;
+mod color;
+mod commands;
+mod complete;
+mod config;
+mod diff;
+mod digraph;
+mod editor;
+mod error;
+mod filetype;
+mod fold;
+mod git;
+mod ignore;
+mod indent;
+mod input;
+mod lock;
+mod log;
+mod minimap;
+mod mouse;
+mod natural;
+mod options;
+mod outline;
+mod output;
+mod pager;
+mod pending;
+mod paste;
+mod plugin;
+mod preview;
+mod private;
+mod rename;
+mod save;
+mod search;
+mod start;
+mod surround;
+mod tasks;
+mod terminal;
+mod textobject;
+mod todos;
+mod ui;
+mod undo;
+
+pub use batch::Batch;
+pub use buffer::{Tab, TextStats};
+pub use color::{parse_color, parse_style, ColorMode, StyleSpec};
+pub use diff::unified_diff;
+pub use editor::{frame_wait, Editor, Mode};
+pub use error::PhantomError;
+pub use natural::natural_cmp;
+pub use plugin::plugin_protocol_docs;
+pub use search::{CaseMode, Matches, Replaced, SearchOptions, SearchPattern};
+pub use ui::EditorView;
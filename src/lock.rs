@@ -0,0 +1,158 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::editor::Editor;
+use crate::ui::PromptAction;
+
+const LOCK_TIMEOUT: Duration = Duration::from_millis(500);
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub(crate) struct LockOwner {
+    pub(crate) pid: u32,
+    pub(crate) host: String,
+    pub(crate) path: String,
+}
+
+pub(crate) struct FileLock {
+    pub(crate) path: PathBuf,
+}
+
+enum LockResult {
+    Acquired(FileLock),
+    Held(LockOwner),
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+fn hostname() -> String {
+    #[cfg(unix)]
+    {
+        let mut name = [0u8; 256];
+        // SAFETY: the pointer and length describe `name`, which outlives the call.
+        if unsafe { libc::gethostname(name.as_mut_ptr().cast(), name.len()) } == 0 {
+            if let Ok(name) = std::ffi::CStr::from_bytes_until_nul(&name) {
+                return name.to_string_lossy().into_owned();
+            }
+        }
+    }
+    std::env::var("COMPUTERNAME").or_else(|_| std::env::var("HOSTNAME")).unwrap_or_default()
+}
+
+fn process_alive(pid: u32) -> bool {
+    #[cfg(unix)]
+    {
+        let Ok(pid) = libc::pid_t::try_from(pid) else {
+            return false;
+        };
+        // SAFETY: signal 0 only checks that the process exists and sends nothing.
+        let signalled = unsafe { libc::kill(pid, 0) } == 0;
+        signalled || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = pid;
+        true
+    }
+}
+
+fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+}
+
+fn lock_path(data_dir: &Path, file: &Path) -> PathBuf {
+    let file = Editor::canonical_path(file);
+    let name: String = Path::new(&file).file_name().unwrap_or_default().to_string_lossy().chars().take(64).collect();
+    data_dir.join("locks").join(format!("{}-{:016x}.lock", name, fnv1a(&file)))
+}
+
+fn acquire(lock: &Path, owner: &LockOwner) -> io::Result<LockResult> {
+    if let Some(parent) = lock.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let name = lock.file_name().map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+    let temp = lock.with_file_name(format!(".{}.{}", name, std::process::id()));
+    fs::write(&temp, serde_json::to_string(owner).map_err(io::Error::other)?)?;
+    let result = claim(lock, &temp, owner);
+    let _ = fs::remove_file(&temp);
+    result
+}
+
+fn claim(lock: &Path, temp: &Path, owner: &LockOwner) -> io::Result<LockResult> {
+    match fs::hard_link(temp, lock) {
+        Ok(()) => return Ok(LockResult::Acquired(FileLock { path: lock.to_path_buf() })),
+        Err(e) if e.kind() != io::ErrorKind::AlreadyExists => return Err(e),
+        Err(_) => {}
+    }
+    if let Ok(existing) = fs::read_to_string(lock) {
+        if let Ok(holder) = serde_json::from_str::<LockOwner>(&existing) {
+            let stale = holder.host == owner.host && (holder.pid == owner.pid || !process_alive(holder.pid));
+            if !stale {
+                return Ok(LockResult::Held(holder));
+            }
+        }
+    }
+    fs::rename(temp, lock)?;
+    Ok(LockResult::Acquired(FileLock { path: lock.to_path_buf() }))
+}
+
+impl Editor {
+    pub(crate) fn lock_file(&mut self, tab_index: usize) {
+        let Some(data_dir) = self.paths.data_dir.clone().filter(|_| self.settings.file_locks) else {
+            return;
+        };
+        let Some(file) = self.tabs[tab_index].current_file.clone() else {
+            return;
+        };
+        let shared = self.tabs.iter()
+            .find(|tab| tab.current_file.as_deref() == Some(file.as_str()) && tab.lock.is_some())
+            .and_then(|tab| tab.lock.clone());
+        if let Some(shared) = shared {
+            self.tabs[tab_index].lock = Some(shared);
+            return;
+        }
+
+        let owner = LockOwner { pid: std::process::id(), host: hostname(), path: file.clone() };
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let lock = lock_path(&data_dir, Path::new(&owner.path));
+            let _ = sender.send(acquire(&lock, &owner));
+        });
+        let name = self.display_path(&file);
+        match receiver.recv_timeout(LOCK_TIMEOUT) {
+            Ok(Ok(LockResult::Acquired(lock))) => self.tabs[tab_index].lock = Some(Arc::new(lock)),
+            Ok(Ok(LockResult::Held(holder))) => {
+                self.tabs[tab_index].read_only = true;
+                let message = format!("{} is already open in phantom (PID {} on {}). Open it read-only?", name, holder.pid, holder.host);
+                self.confirm(PromptAction::Locked(file), &message, "Open read-only", "Edit anyway");
+            }
+            Ok(Err(e)) => self.warn(format!("Could not lock {}: {}", name, e)),
+            Err(_) => self.warn(format!("Checking the lock for {} timed out; opened without a lock", name)),
+        }
+    }
+
+    pub(crate) fn resolve_lock_prompt(&mut self, file: &str, edit: Option<bool>) {
+        let Some(tab_index) = self.tabs.iter().rposition(|tab| tab.current_file.as_deref() == Some(file)) else {
+            return;
+        };
+        match edit {
+            Some(edit) => self.tabs[tab_index].read_only = !edit,
+            None => self.remove_tab(tab_index),
+        }
+    }
+
+    pub(crate) fn release_locks(&mut self) {
+        for tab in &mut self.tabs {
+            tab.lock = None;
+        }
+    }
+}
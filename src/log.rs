@@ -0,0 +1,185 @@
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crossterm::event::{KeyCode, KeyEvent};
+use serde::{Deserialize, Serialize};
+
+use crate::editor::{Editor, MessageLevel, Mode};
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl LogLevel {
+    pub(crate) const ALL: [LogLevel; 5] = [LogLevel::Trace, LogLevel::Debug, LogLevel::Info, LogLevel::Warn, LogLevel::Error];
+
+    pub(crate) const NAMES: &'static [&'static str] = &["trace", "debug", "info", "warn", "error"];
+
+    pub(crate) fn parse(name: &str) -> Option<Self> {
+        Self::NAMES.iter().position(|level| level.eq_ignore_ascii_case(name)).map(|index| Self::ALL[index])
+    }
+
+    pub(crate) fn next(self) -> Self {
+        Self::ALL[(self as usize + 1) % Self::ALL.len()]
+    }
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(Self::NAMES[*self as usize])
+    }
+}
+
+impl From<MessageLevel> for LogLevel {
+    fn from(level: MessageLevel) -> Self {
+        match level {
+            MessageLevel::Info => LogLevel::Info,
+            MessageLevel::Warn => LogLevel::Warn,
+            MessageLevel::Error => LogLevel::Error,
+        }
+    }
+}
+
+pub(crate) struct LogEntry {
+    pub(crate) level: LogLevel,
+    pub(crate) text: String,
+}
+
+pub(crate) struct FileLogger {
+    path: PathBuf,
+    level: LogLevel,
+    file: File,
+    size: u64,
+}
+
+impl FileLogger {
+    pub(crate) const MAX_SIZE: u64 = 1024 * 1024;
+
+    pub(crate) const KEEP: usize = 3;
+
+    pub(crate) fn open(path: PathBuf, level: LogLevel) -> io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(FileLogger { path, level, file, size })
+    }
+
+    fn rotated(&self, index: usize) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{}", index));
+        PathBuf::from(name)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        for index in (1..Self::KEEP).rev() {
+            let from = self.rotated(index);
+            if from.exists() {
+                fs::rename(&from, self.rotated(index + 1))?;
+            }
+        }
+        fs::rename(&self.path, self.rotated(1))?;
+        self.file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+
+    pub(crate) fn write(&mut self, entry: &LogEntry) -> io::Result<()> {
+        if entry.level < self.level {
+            return Ok(());
+        }
+        if self.size >= Self::MAX_SIZE {
+            self.rotate()?;
+        }
+        let time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        let line = format!("{}.{:03} {:<5} {}\n", time.as_secs(), time.subsec_millis(), entry.level.to_string().to_uppercase(), entry.text);
+        self.file.write_all(line.as_bytes())?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+}
+
+impl Editor {
+    pub(crate) const MAX_DEBUG_MESSAGES: usize = 1000;
+
+    pub(crate) fn log(&mut self, level: LogLevel, text: impl Into<String>) {
+        let entry = LogEntry { level, text: text.into() };
+        if let Some(logger) = &mut self.logger {
+            if logger.write(&entry).is_err() {
+                self.logger = None;
+            }
+        }
+        self.debug_messages.push(entry);
+        if self.debug_messages.len() > Self::MAX_DEBUG_MESSAGES {
+            let excess = self.debug_messages.len() - Self::MAX_DEBUG_MESSAGES;
+            self.debug_messages.drain(..excess);
+        }
+    }
+
+    pub fn enable_log(&mut self, level: &str) -> Result<PathBuf, String> {
+        let level = LogLevel::parse(level)
+            .ok_or_else(|| format!("invalid log level {:?} (expected {})", level, LogLevel::NAMES.join(", ")))?;
+        let data_dir = self.paths.data_dir.as_ref().ok_or("no writable data directory for phantom.log")?;
+        let path = data_dir.join("phantom.log");
+        let mut logger = FileLogger::open(path.clone(), level).map_err(|e| format!("{}: {}", path.display(), e))?;
+        for entry in &self.debug_messages {
+            logger.write(entry).map_err(|e| format!("{}: {}", path.display(), e))?;
+        }
+        self.logger = Some(logger);
+        Ok(path)
+    }
+
+    pub(crate) fn debug_entries(&self) -> Vec<&LogEntry> {
+        self.debug_messages.iter().filter(|entry| entry.level >= self.settings.debug_level).collect()
+    }
+
+    pub(crate) fn toggle_debug_menu(&mut self) {
+        self.show_debug = !self.show_debug;
+        if !self.show_debug && self.mode == Mode::Debug {
+            self.mode = self.base_mode();
+        }
+        self.log(LogLevel::Debug, if self.show_debug { "Debug menu shown" } else { "Debug menu hidden" });
+    }
+
+    pub(crate) fn focus_debug(&mut self) {
+        self.show_debug = true;
+        self.mode = Mode::Debug;
+    }
+
+    pub(crate) fn scroll_debug(&mut self, up: bool, lines: usize) {
+        let max_scroll = self.debug_entries().len().saturating_sub(1);
+        self.debug_scroll = if up {
+            self.debug_scroll.saturating_add(lines).min(max_scroll)
+        } else {
+            self.debug_scroll.saturating_sub(lines)
+        };
+    }
+
+    pub(crate) fn handle_debug_mode(&mut self, key: KeyEvent) {
+        let page = self.layout.debug.map_or(1, |area| area.height.saturating_sub(2).max(1) as usize);
+        match key.code {
+            KeyCode::Char('k') | KeyCode::Up => self.scroll_debug(true, 1),
+            KeyCode::Char('j') | KeyCode::Down => self.scroll_debug(false, 1),
+            KeyCode::PageUp => self.scroll_debug(true, page),
+            KeyCode::PageDown => self.scroll_debug(false, page),
+            KeyCode::Char('g') | KeyCode::Home => self.scroll_debug(true, usize::MAX),
+            KeyCode::Char('G') | KeyCode::End => self.debug_scroll = 0,
+            KeyCode::Char('f') => {
+                self.settings.debug_level = self.settings.debug_level.next();
+                self.debug_scroll = 0;
+            }
+            KeyCode::Char('q') | KeyCode::Esc => self.mode = self.base_mode(),
+            _ => {}
+        }
+    }
+}
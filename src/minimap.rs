@@ -0,0 +1,247 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::iter;
+
+use crossterm::event::{KeyCode, KeyEvent};
+use syntect::parsing::{ParseState, Scope, ScopeStack, SyntaxReference, SyntaxSet};
+
+use crate::buffer::char_width_at;
+use crate::editor::Editor;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum TokenKind {
+    Text,
+    Comment,
+    String,
+    Keyword,
+    Function,
+}
+
+impl TokenKind {
+    pub(crate) const ALL: [TokenKind; 5] = [TokenKind::Text, TokenKind::Comment, TokenKind::String, TokenKind::Keyword, TokenKind::Function];
+}
+
+struct TokenScopes {
+    comment: Scope,
+    string: Scope,
+    keyword: Scope,
+    storage: Scope,
+    function: Scope,
+    function_call: Scope,
+    support_function: Scope,
+}
+
+impl TokenScopes {
+    fn new() -> Self {
+        let scope = |name: &str| Scope::new(name).expect("token scope names are valid");
+        TokenScopes {
+            comment: scope("comment"),
+            string: scope("string"),
+            keyword: scope("keyword"),
+            storage: scope("storage"),
+            function: scope("entity.name.function"),
+            function_call: scope("variable.function"),
+            support_function: scope("support.function"),
+        }
+    }
+
+    fn kind(&self, stack: &ScopeStack) -> TokenKind {
+        for &scope in stack.as_slice().iter().rev() {
+            if self.comment.is_prefix_of(scope) {
+                return TokenKind::Comment;
+            }
+            if self.string.is_prefix_of(scope) {
+                return TokenKind::String;
+            }
+            if [self.function, self.function_call, self.support_function].iter().any(|prefix| prefix.is_prefix_of(scope)) {
+                return TokenKind::Function;
+            }
+            if self.keyword.is_prefix_of(scope) || self.storage.is_prefix_of(scope) {
+                return TokenKind::Keyword;
+            }
+        }
+        TokenKind::Text
+    }
+}
+
+const LONG_LINE_HASH: u64 = u64::MAX;
+
+fn line_hash(line: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    line.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn classify_line(state: &mut ParseState, stack: &mut ScopeStack, line: &str, ps: &SyntaxSet, scopes: &TokenScopes, tab_width: usize) -> Vec<Option<TokenKind>> {
+    let ops = state.parse_line(&format!("{}\n", line), ps).unwrap_or_default();
+    let mut cells = Vec::new();
+    let mut push = |text: &str, kind: TokenKind| {
+        for c in text.chars() {
+            let width = char_width_at(c, cells.len(), tab_width);
+            let cell = if c.is_whitespace() { None } else { Some(kind) };
+            cells.extend(iter::repeat_n(cell, width));
+        }
+    };
+    let mut position = 0;
+    for (offset, op) in ops {
+        let offset = offset.min(line.len());
+        if offset > position {
+            push(&line[position..offset], scopes.kind(stack));
+            position = offset;
+        }
+        let _ = stack.apply(&op);
+    }
+    push(&line[position..], scopes.kind(stack));
+    cells
+}
+
+#[derive(Default)]
+pub(crate) struct TokenCache {
+    syntax: String,
+    tab_width: usize,
+    long_line_threshold: usize,
+    hashes: Vec<u64>,
+    checkpoints: Vec<(ParseState, ScopeStack)>,
+    pub(crate) lines: Vec<Vec<Option<TokenKind>>>,
+}
+
+impl TokenCache {
+    pub(crate) const CHECKPOINT_LINES: usize = 128;
+
+    pub(crate) fn update(&mut self, content: &[String], syntax: &SyntaxReference, ps: &SyntaxSet, tab_width: usize, long_line_threshold: usize) {
+        let hashes: Vec<u64> = content.iter()
+            .map(|line| if line.len() > long_line_threshold { LONG_LINE_HASH } else { line_hash(line) })
+            .collect();
+        let first_changed = if self.syntax != syntax.name || self.tab_width != tab_width || self.long_line_threshold != long_line_threshold {
+            0
+        } else {
+            hashes.iter().zip(&self.hashes)
+                .position(|(new, old)| new != old)
+                .unwrap_or(hashes.len().min(self.hashes.len()))
+        };
+        if first_changed == hashes.len() && hashes.len() == self.hashes.len() {
+            return;
+        }
+        if first_changed == 0 {
+            self.checkpoints = vec![(ParseState::new(syntax), ScopeStack::new())];
+        }
+        let checkpoint = (first_changed / Self::CHECKPOINT_LINES).min(self.checkpoints.len() - 1);
+        self.checkpoints.truncate(checkpoint + 1);
+        let start = checkpoint * Self::CHECKPOINT_LINES;
+        self.lines.truncate(start);
+        let (mut state, mut stack) = self.checkpoints[checkpoint].clone();
+        let scopes = TokenScopes::new();
+        for (index, line) in content.iter().enumerate().skip(start) {
+            if index > start && index % Self::CHECKPOINT_LINES == 0 {
+                self.checkpoints.push((state.clone(), stack.clone()));
+            }
+            if line.len() > long_line_threshold {
+                self.lines.push(Vec::new());
+            } else {
+                self.lines.push(classify_line(&mut state, &mut stack, line, ps, &scopes, tab_width));
+            }
+        }
+        self.syntax = syntax.name.clone();
+        self.tab_width = tab_width;
+        self.long_line_threshold = long_line_threshold;
+        self.hashes = hashes;
+    }
+
+    pub(crate) fn cell(&self, lines: &[usize], columns: (usize, usize)) -> (u32, Option<TokenKind>) {
+        let mut dots = 0;
+        let mut counts = [0usize; TokenKind::ALL.len()];
+        for (dy, &line) in lines.iter().enumerate() {
+            let Some(cells) = self.lines.get(line) else {
+                continue;
+            };
+            for dx in 0..2 {
+                let from = (columns.0 + dx * columns.1).min(cells.len());
+                let to = (from + columns.1).min(cells.len());
+                let mut filled = false;
+                for kind in cells[from..to].iter().flatten() {
+                    counts[*kind as usize] += 1;
+                    filled = true;
+                }
+                if filled {
+                    dots |= 1 << if dy == 3 { 6 + dx } else { dy + 3 * dx };
+                }
+            }
+        }
+        let dominant = TokenKind::ALL.iter()
+            .copied()
+            .filter(|kind| counts[*kind as usize] > 0)
+            .max_by_key(|kind| (counts[*kind as usize], *kind != TokenKind::Text));
+        (dots, dominant)
+    }
+}
+
+pub(crate) struct MinimapFocus {
+    scroll_offset: usize,
+}
+
+impl Editor {
+    pub(crate) fn focus_minimap(&mut self) -> io::Result<bool> {
+        if !self.show_minimap {
+            self.toggle_minimap()?;
+        }
+        if !self.show_minimap {
+            self.info("The minimap has no content to show");
+            return Ok(false);
+        }
+        self.minimap_focus = Some(MinimapFocus { scroll_offset: self.tabs[self.active_tab].scroll_offset });
+        Ok(false)
+    }
+
+    pub(crate) fn minimap_band(&self) -> (usize, usize) {
+        let tab = &self.tabs[self.active_tab];
+        let end = tab.row_to_line(tab.line_to_row(tab.scroll_offset) + self.get_editor_height().max(1) - 1);
+        (tab.scroll_offset, end.min(tab.content.len().saturating_sub(1)))
+    }
+
+    fn minimap_row(&self, line: usize) -> usize {
+        self.minimap_line_mapping.iter()
+            .position(|&(_, max_line)| line <= max_line)
+            .unwrap_or(self.minimap_line_mapping.len().saturating_sub(1))
+    }
+
+    fn scroll_minimap_band(&mut self, rows: isize) {
+        let tab = &self.tabs[self.active_tab];
+        let max_scroll = tab.row_to_line(tab.row_count().saturating_sub(self.get_editor_height()));
+        let target = if self.minimap_line_mapping.is_empty() {
+            tab.scroll_offset.saturating_add_signed(rows)
+        } else {
+            let row = self.minimap_row(tab.scroll_offset).saturating_add_signed(rows);
+            self.minimap_line_mapping[row.min(self.minimap_line_mapping.len() - 1)].0
+        };
+        self.tabs[self.active_tab].scroll_offset = target.min(max_scroll);
+    }
+
+    pub(crate) fn handle_minimap_focus_key(&mut self, key: KeyEvent) {
+        let (top, bottom) = self.minimap_band();
+        let page = (self.minimap_row(bottom) + 1).saturating_sub(self.minimap_row(top)).max(1) as isize;
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down => self.scroll_minimap_band(1),
+            KeyCode::Char('k') | KeyCode::Up => self.scroll_minimap_band(-1),
+            KeyCode::PageDown => self.scroll_minimap_band(page),
+            KeyCode::PageUp => self.scroll_minimap_band(-page),
+            KeyCode::Char('g') | KeyCode::Home => self.scroll_minimap_band(isize::MIN),
+            KeyCode::Char('G') | KeyCode::End => self.scroll_minimap_band(isize::MAX),
+            KeyCode::Enter => {
+                self.minimap_focus = None;
+                let tab = &mut self.tabs[self.active_tab];
+                let line = tab.row_to_line((tab.line_to_row(top) + tab.line_to_row(bottom)) / 2);
+                tab.set_cursor(tab.cursor_position.0, line);
+                self.ensure_cursor_visible();
+            }
+            KeyCode::Esc => self.unfocus_minimap(),
+            _ => {}
+        }
+    }
+
+    pub(crate) fn unfocus_minimap(&mut self) {
+        if let Some(focus) = self.minimap_focus.take() {
+            self.tabs[self.active_tab].scroll_offset = focus.scroll_offset;
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use crossterm::event::{KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+
+use crate::config::{DragAction, MouseAction};
+use crate::editor::Editor;
+use crate::ui::{contains, Prompt};
+
+impl Editor {
+    pub fn mouse_enabled(&self) -> bool {
+        self.settings.mouse.enabled
+    }
+
+    fn click_action(&self, button: MouseButton) -> MouseAction {
+        match button {
+            MouseButton::Left => self.settings.mouse.left_click,
+            MouseButton::Right => self.settings.mouse.right_click,
+            MouseButton::Middle => self.settings.mouse.middle_click,
+        }
+    }
+
+    pub(crate) fn handle_mouse_event(&mut self, event: MouseEvent) {
+        let (x, y) = (event.column, event.row);
+        if self.prompt.is_some() {
+            let answer = match &self.prompt {
+                Some(Prompt::Confirm(prompt)) if event.kind == MouseEventKind::Down(MouseButton::Left) => prompt.handle_click(x, y),
+                _ => None,
+            };
+            if let Some(answer) = answer {
+                self.answer_prompt(answer);
+            }
+            return;
+        }
+        match event.kind {
+            MouseEventKind::Down(MouseButton::Left) if self.is_minimap_area(x, y) => self.handle_minimap_click(x, y),
+            MouseEventKind::Down(MouseButton::Left) if self.layout.outline.is_some_and(|area| contains(area, x, y)) => {
+                self.handle_outline_click(y);
+            }
+            MouseEventKind::Down(button) => match self.click_action(button) {
+                MouseAction::Select if event.modifiers.contains(KeyModifiers::SHIFT) => self.extend_mouse_selection(x, y),
+                MouseAction::Select => {
+                    self.start_mouse_selection(x, y);
+                    if self.settings.mouse.drag == DragAction::Scroll {
+                        self.mouse_scroll_row = Some(y);
+                    }
+                }
+                MouseAction::Paste => self.paste_at_mouse(x, y),
+                MouseAction::Copy | MouseAction::None => {}
+            },
+            MouseEventKind::Drag(button) if self.click_action(button) == MouseAction::Select => match self.settings.mouse.drag {
+                DragAction::Select => self.drag_mouse_selection(x, y),
+                DragAction::Scroll => self.drag_scroll(y),
+                DragAction::None => {}
+            },
+            MouseEventKind::Up(button) => match self.click_action(button) {
+                MouseAction::Select => self.finish_mouse_selection(),
+                MouseAction::Copy => {
+                    self.copy_selection_to_clipboard();
+                    self.end_mouse_selection();
+                }
+                MouseAction::Paste | MouseAction::None => {}
+            },
+            MouseEventKind::ScrollUp | MouseEventKind::ScrollDown => {
+                self.scroll_wheel(x, y, event.kind == MouseEventKind::ScrollDown);
+            }
+            _ => {}
+        }
+    }
+
+    fn paste_at_mouse(&mut self, x: u16, y: u16) {
+        if self.tabs[self.active_tab].read_only {
+            self.error("Buffer is read-only");
+            return;
+        }
+        let Some(position) = self.screen_to_content_position(x, y) else {
+            return;
+        };
+        if self.mouse_selection().is_none() {
+            let tab = &mut self.tabs[self.active_tab];
+            tab.cursor_position = tab.clamp_position(position);
+        }
+        self.paste_clipboard();
+        self.end_mouse_selection();
+    }
+
+    fn drag_scroll(&mut self, y: u16) {
+        let Some(anchor) = self.mouse_scroll_row else {
+            return;
+        };
+        self.mouse_scroll_row = Some(y);
+        for _ in 0..anchor.abs_diff(y) {
+            self.scroll_line(y < anchor);
+        }
+    }
+
+    fn scroll_wheel(&mut self, x: u16, y: u16, down: bool) {
+        let lines = self.settings.mouse.wheel_lines;
+        if self.layout.debug.is_some_and(|area| contains(area, x, y)) {
+            self.scroll_debug(!down, lines);
+        } else if self.layout.terminal.is_some_and(|area| contains(area, x, y)) {
+            self.scroll_terminal(!down, lines);
+        } else if contains(self.layout.text_area(), x, y) {
+            for _ in 0..lines {
+                self.scroll_line(down);
+            }
+        }
+    }
+}
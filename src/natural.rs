@@ -0,0 +1,39 @@
+use std::cmp::Ordering;
+use std::path::Path;
+
+fn natural_key(name: &str) -> impl Iterator<Item = (char, usize, &str)> {
+    let mut rest = name;
+    let mut lowered: Option<std::char::ToLowercase> = None;
+    std::iter::from_fn(move || {
+        if let Some(c) = lowered.as_mut().and_then(Iterator::next) {
+            return Some((c, 0, ""));
+        }
+        let c = rest.chars().next()?;
+        if c.is_ascii_digit() {
+            let end = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+            let digits = rest[..end].trim_start_matches('0');
+            rest = &rest[end..];
+            return Some(('0', digits.len(), digits));
+        }
+        rest = &rest[c.len_utf8()..];
+        let mut lower = c.to_lowercase();
+        let first = lower.next().unwrap_or(c);
+        lowered = Some(lower);
+        Some((first, 0, ""))
+    })
+}
+
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    natural_key(a).cmp(natural_key(b)).then_with(|| a.cmp(b))
+}
+
+pub(crate) fn natural_path_cmp(a: &Path, b: &Path) -> Ordering {
+    let components = |path: &Path| path.components().map(|component| component.as_os_str().to_string_lossy().into_owned()).collect::<Vec<_>>();
+    let (a_parts, b_parts) = (components(a), components(b));
+    a_parts.iter()
+        .zip(&b_parts)
+        .map(|(a, b)| natural_cmp(a, b))
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or_else(|| a_parts.len().cmp(&b_parts.len()))
+        .then_with(|| a.cmp(b))
+}
@@ -0,0 +1,729 @@
+use crate::buffer::Tab;
+use crate::complete::{nearest, prefix_completions};
+use crate::config::{BlameDisplay, FoldMethod, Settings};
+use crate::editor::{Editor, Mode};
+use crate::log::LogLevel;
+use crate::ui::{Picker, PickerKind};
+
+#[derive(Clone, PartialEq)]
+pub(crate) enum OptionValue {
+    Bool(bool),
+    Number(usize),
+    Text(String),
+}
+
+impl OptionValue {
+    pub(crate) fn bool(&self) -> bool {
+        matches!(self, OptionValue::Bool(true))
+    }
+
+    pub(crate) fn number(&self) -> usize {
+        match self {
+            OptionValue::Number(number) => *number,
+            _ => 0,
+        }
+    }
+
+    pub(crate) fn text(&self) -> &str {
+        match self {
+            OptionValue::Text(text) => text,
+            _ => "",
+        }
+    }
+
+    pub(crate) fn describe(&self, name: &str) -> String {
+        match self {
+            OptionValue::Bool(true) => name.to_string(),
+            OptionValue::Bool(false) => format!("no{}", name),
+            OptionValue::Number(number) => format!("{}={}", name, number),
+            OptionValue::Text(text) => format!("{}={}", name, text),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum OptionScope {
+    Global,
+    Local,
+    Buffer,
+}
+
+pub(crate) enum OptionKind {
+    Bool,
+    Number { min: usize, max: usize },
+    Choice(&'static [&'static str]),
+    Text,
+    Syntax,
+}
+
+type OptionHook = fn(&mut Editor) -> Result<(), String>;
+
+pub(crate) struct OptionSpec {
+    pub(crate) name: &'static str,
+    pub(crate) short: Option<&'static str>,
+    pub(crate) kind: OptionKind,
+    pub(crate) scope: OptionScope,
+    pub(crate) get: fn(&Settings, &Tab) -> OptionValue,
+    pub(crate) set: fn(&mut Settings, &mut Tab, OptionValue),
+    pub(crate) changed: Option<OptionHook>,
+}
+
+const ANY_NUMBER: OptionKind = OptionKind::Number { min: 0, max: usize::MAX };
+const PANEL_HEIGHT: OptionKind = OptionKind::Number { min: 3, max: u16::MAX as usize };
+
+pub(crate) fn clamp_settings(settings: &mut Settings) -> Vec<String> {
+    let mut tab = Tab::default();
+    let mut warnings = Vec::new();
+    for spec in OPTIONS.iter().filter(|spec| spec.scope != OptionScope::Buffer) {
+        let OptionKind::Number { min, max } = spec.kind else {
+            continue;
+        };
+        let value = (spec.get)(settings, &tab).number();
+        let clamped = value.clamp(min, max);
+        if clamped != value {
+            (spec.set)(settings, &mut tab, OptionValue::Number(clamped));
+            warnings.push(format!("settings.toml: {}={} is out of range; using {}", spec.name, value, clamped));
+        }
+    }
+    warnings
+}
+
+pub(crate) static OPTIONS: &[OptionSpec] = &[
+    OptionSpec {
+        name: "activetabtitleformat",
+        short: None,
+        kind: OptionKind::Text,
+        scope: OptionScope::Global,
+        get: |settings, _| OptionValue::Text(settings.active_tab_title_format.clone()),
+        set: |settings, _, value| settings.active_tab_title_format = value.text().to_string(),
+        changed: None,
+    },
+    OptionSpec {
+        name: "blame",
+        short: None,
+        kind: OptionKind::Choice(&["column", "status"]),
+        scope: OptionScope::Global,
+        get: |settings, _| OptionValue::Text(match settings.blame_display {
+            BlameDisplay::Column => "column".to_string(),
+            BlameDisplay::Status => "status".to_string(),
+        }),
+        set: |settings, _, value| {
+            settings.blame_display = if value.text() == "status" { BlameDisplay::Status } else { BlameDisplay::Column };
+        },
+        changed: None,
+    },
+    OptionSpec {
+        name: "bomb",
+        short: None,
+        kind: OptionKind::Bool,
+        scope: OptionScope::Buffer,
+        get: |_, tab| OptionValue::Bool(tab.bom),
+        set: |_, tab, value| {
+            if tab.bom != value.bool() {
+                tab.bom = value.bool();
+                tab.mark_modified();
+            }
+        },
+        changed: None,
+    },
+    OptionSpec {
+        name: "colorcolumn",
+        short: Some("cc"),
+        kind: ANY_NUMBER,
+        scope: OptionScope::Local,
+        get: |settings, _| OptionValue::Number(settings.color_column),
+        set: |settings, _, value| settings.color_column = value.number(),
+        changed: None,
+    },
+    OptionSpec {
+        name: "copyonselect",
+        short: None,
+        kind: OptionKind::Bool,
+        scope: OptionScope::Global,
+        get: |settings, _| OptionValue::Bool(settings.copy_on_select),
+        set: |settings, _, value| settings.copy_on_select = value.bool(),
+        changed: None,
+    },
+    OptionSpec {
+        name: "cursorline",
+        short: Some("cul"),
+        kind: OptionKind::Bool,
+        scope: OptionScope::Local,
+        get: |settings, _| OptionValue::Bool(settings.cursor_line),
+        set: |settings, _, value| settings.cursor_line = value.bool(),
+        changed: None,
+    },
+    OptionSpec {
+        name: "debugheight",
+        short: None,
+        kind: PANEL_HEIGHT,
+        scope: OptionScope::Global,
+        get: |settings, _| OptionValue::Number(settings.debug_height as usize),
+        set: |settings, _, value| settings.debug_height = value.number() as u16,
+        changed: None,
+    },
+    OptionSpec {
+        name: "debuglevel",
+        short: None,
+        kind: OptionKind::Choice(LogLevel::NAMES),
+        scope: OptionScope::Global,
+        get: |settings, _| OptionValue::Text(settings.debug_level.to_string()),
+        set: |settings, _, value| settings.debug_level = LogLevel::parse(value.text()).unwrap_or(LogLevel::Debug),
+        changed: Some(|editor| {
+            editor.debug_scroll = 0;
+            Ok(())
+        }),
+    },
+    OptionSpec {
+        name: "detectindent",
+        short: None,
+        kind: OptionKind::Bool,
+        scope: OptionScope::Global,
+        get: |settings, _| OptionValue::Bool(settings.detect_indent),
+        set: |settings, _, value| settings.detect_indent = value.bool(),
+        changed: None,
+    },
+    OptionSpec {
+        name: "endofline",
+        short: Some("eol"),
+        kind: OptionKind::Bool,
+        scope: OptionScope::Buffer,
+        get: |_, tab| OptionValue::Bool(tab.eol),
+        set: |_, tab, value| {
+            if tab.eol != value.bool() {
+                tab.eol = value.bool();
+                tab.mark_modified();
+            }
+        },
+        changed: None,
+    },
+    OptionSpec {
+        name: "expandtab",
+        short: Some("et"),
+        kind: OptionKind::Bool,
+        scope: OptionScope::Local,
+        get: |settings, _| OptionValue::Bool(settings.expand_tab),
+        set: |settings, _, value| settings.expand_tab = value.bool(),
+        changed: None,
+    },
+    OptionSpec {
+        name: "fileformat",
+        short: Some("ff"),
+        kind: OptionKind::Choice(&["unix", "dos"]),
+        scope: OptionScope::Buffer,
+        get: |_, tab| OptionValue::Text(if tab.crlf { "dos" } else { "unix" }.to_string()),
+        set: |_, tab, value| {
+            let crlf = value.text() == "dos";
+            if tab.crlf != crlf {
+                tab.crlf = crlf;
+                tab.mark_modified();
+            }
+        },
+        changed: None,
+    },
+    OptionSpec {
+        name: "foldmethod",
+        short: Some("fdm"),
+        kind: OptionKind::Choice(&["indent", "brace"]),
+        scope: OptionScope::Local,
+        get: |settings, tab| OptionValue::Text(match settings.fold_methods.get(&tab.syntax) {
+            Some(FoldMethod::Brace) => "brace".to_string(),
+            _ => "indent".to_string(),
+        }),
+        set: |settings, tab, value| {
+            let method = if value.text() == "brace" { FoldMethod::Brace } else { FoldMethod::Indent };
+            settings.fold_methods.insert(tab.syntax.clone(), method);
+        },
+        changed: None,
+    },
+    OptionSpec {
+        name: "forcecolor",
+        short: None,
+        kind: OptionKind::Bool,
+        scope: OptionScope::Global,
+        get: |settings, _| OptionValue::Bool(settings.force_color),
+        set: |settings, _, value| settings.force_color = value.bool(),
+        changed: Some(|editor| {
+            editor.color_config.detected_mode = Editor::color_environment(&editor.settings).1;
+            Ok(())
+        }),
+    },
+    OptionSpec {
+        name: "hlsearch",
+        short: Some("hls"),
+        kind: OptionKind::Bool,
+        scope: OptionScope::Global,
+        get: |settings, _| OptionValue::Bool(settings.hlsearch),
+        set: |settings, _, value| settings.hlsearch = value.bool(),
+        changed: None,
+    },
+    OptionSpec {
+        name: "indentguides",
+        short: None,
+        kind: OptionKind::Bool,
+        scope: OptionScope::Local,
+        get: |settings, _| OptionValue::Bool(settings.indent_guides),
+        set: |settings, _, value| settings.indent_guides = value.bool(),
+        changed: None,
+    },
+    OptionSpec {
+        name: "list",
+        short: None,
+        kind: OptionKind::Bool,
+        scope: OptionScope::Local,
+        get: |settings, _| OptionValue::Bool(settings.list),
+        set: |settings, _, value| settings.list = value.bool(),
+        changed: None,
+    },
+    OptionSpec {
+        name: "longlinethreshold",
+        short: None,
+        kind: OptionKind::Number { min: 80, max: usize::MAX },
+        scope: OptionScope::Global,
+        get: |settings, _| OptionValue::Number(settings.long_line_threshold),
+        set: |settings, _, value| settings.long_line_threshold = value.number(),
+        changed: None,
+    },
+    OptionSpec {
+        name: "maxfps",
+        short: None,
+        kind: OptionKind::Number { min: 1, max: 1000 },
+        scope: OptionScope::Global,
+        get: |settings, _| OptionValue::Number(settings.max_fps),
+        set: |settings, _, value| settings.max_fps = value.number(),
+        changed: None,
+    },
+    OptionSpec {
+        name: "minimapscale",
+        short: None,
+        kind: OptionKind::Number { min: 1, max: 16 },
+        scope: OptionScope::Global,
+        get: |settings, _| OptionValue::Number(settings.minimap_scale),
+        set: |settings, _, value| settings.minimap_scale = value.number(),
+        changed: None,
+    },
+    OptionSpec {
+        name: "modeline",
+        short: Some("ml"),
+        kind: OptionKind::Bool,
+        scope: OptionScope::Global,
+        get: |settings, _| OptionValue::Bool(settings.modeline),
+        set: |settings, _, value| settings.modeline = value.bool(),
+        changed: None,
+    },
+    OptionSpec {
+        name: "mouse",
+        short: None,
+        kind: OptionKind::Choice(&["on", "off"]),
+        scope: OptionScope::Global,
+        get: |settings, _| OptionValue::Text(if settings.mouse.enabled { "on" } else { "off" }.to_string()),
+        set: |settings, _, value| settings.mouse.enabled = value.text() == "on",
+        changed: None,
+    },
+    OptionSpec {
+        name: "number",
+        short: Some("nu"),
+        kind: OptionKind::Bool,
+        scope: OptionScope::Local,
+        get: |settings, _| OptionValue::Bool(settings.number),
+        set: |settings, _, value| settings.number = value.bool(),
+        changed: None,
+    },
+    OptionSpec {
+        name: "opendroppedfiles",
+        short: None,
+        kind: OptionKind::Bool,
+        scope: OptionScope::Global,
+        get: |settings, _| OptionValue::Bool(settings.open_dropped_files),
+        set: |settings, _, value| settings.open_dropped_files = value.bool(),
+        changed: None,
+    },
+    OptionSpec {
+        name: "persistuistate",
+        short: None,
+        kind: OptionKind::Bool,
+        scope: OptionScope::Global,
+        get: |settings, _| OptionValue::Bool(settings.persist_ui_state),
+        set: |settings, _, value| settings.persist_ui_state = value.bool(),
+        changed: None,
+    },
+    OptionSpec {
+        name: "readonly",
+        short: Some("ro"),
+        kind: OptionKind::Bool,
+        scope: OptionScope::Buffer,
+        get: |_, tab| OptionValue::Bool(tab.read_only),
+        set: |_, tab, value| tab.read_only = value.bool(),
+        changed: None,
+    },
+    OptionSpec {
+        name: "recentfiles",
+        short: None,
+        kind: OptionKind::Bool,
+        scope: OptionScope::Global,
+        get: |settings, _| OptionValue::Bool(settings.recent_files),
+        set: |settings, _, value| settings.recent_files = value.bool(),
+        changed: None,
+    },
+    OptionSpec {
+        name: "restoreposition",
+        short: None,
+        kind: OptionKind::Bool,
+        scope: OptionScope::Global,
+        get: |settings, _| OptionValue::Bool(settings.restore_position),
+        set: |settings, _, value| settings.restore_position = value.bool(),
+        changed: None,
+    },
+    OptionSpec {
+        name: "scrolloff",
+        short: Some("so"),
+        kind: ANY_NUMBER,
+        scope: OptionScope::Local,
+        get: |settings, _| OptionValue::Number(settings.scrolloff),
+        set: |settings, _, value| settings.scrolloff = value.number(),
+        changed: None,
+    },
+    OptionSpec {
+        name: "showignored",
+        short: None,
+        kind: OptionKind::Bool,
+        scope: OptionScope::Global,
+        get: |settings, _| OptionValue::Bool(settings.show_ignored),
+        set: |settings, _, value| settings.show_ignored = value.bool(),
+        changed: Some(|editor| {
+            if let Some(file_selector) = &mut editor.file_selector {
+                file_selector.show_ignored = editor.settings.show_ignored;
+                file_selector.load_entries();
+            }
+            Ok(())
+        }),
+    },
+    OptionSpec {
+        name: "sidescrolloff",
+        short: Some("siso"),
+        kind: ANY_NUMBER,
+        scope: OptionScope::Local,
+        get: |settings, _| OptionValue::Number(settings.sidescrolloff),
+        set: |settings, _, value| settings.sidescrolloff = value.number(),
+        changed: None,
+    },
+    OptionSpec {
+        name: "syntax",
+        short: Some("syn"),
+        kind: OptionKind::Syntax,
+        scope: OptionScope::Buffer,
+        get: |_, tab| OptionValue::Text(tab.syntax.clone()),
+        set: |_, tab, value| tab.syntax = value.text().to_string(),
+        changed: None,
+    },
+    OptionSpec {
+        name: "tabsearchlimit",
+        short: None,
+        kind: OptionKind::Number { min: 1, max: usize::MAX },
+        scope: OptionScope::Global,
+        get: |settings, _| OptionValue::Number(settings.tab_search_limit),
+        set: |settings, _, value| settings.tab_search_limit = value.number(),
+        changed: None,
+    },
+    OptionSpec {
+        name: "tabstop",
+        short: Some("ts"),
+        kind: OptionKind::Number { min: 1, max: 32 },
+        scope: OptionScope::Local,
+        get: |settings, _| OptionValue::Number(settings.tab_width),
+        set: |settings, _, value| settings.tab_width = value.number(),
+        changed: None,
+    },
+    OptionSpec {
+        name: "tabtitleformat",
+        short: None,
+        kind: OptionKind::Text,
+        scope: OptionScope::Global,
+        get: |settings, _| OptionValue::Text(settings.tab_title_format.clone()),
+        set: |settings, _, value| settings.tab_title_format = value.text().to_string(),
+        changed: None,
+    },
+    OptionSpec {
+        name: "terminalheight",
+        short: None,
+        kind: PANEL_HEIGHT,
+        scope: OptionScope::Global,
+        get: |settings, _| OptionValue::Number(settings.terminal_height as usize),
+        set: |settings, _, value| settings.terminal_height = value.number() as u16,
+        changed: None,
+    },
+    OptionSpec {
+        name: "timeoutlen",
+        short: Some("tm"),
+        kind: OptionKind::Number { min: 1, max: 60_000 },
+        scope: OptionScope::Global,
+        get: |settings, _| OptionValue::Number(settings.key_timeout_ms as usize),
+        set: |settings, _, value| settings.key_timeout_ms = value.number() as u64,
+        changed: None,
+    },
+    OptionSpec {
+        name: "todomarkers",
+        short: None,
+        kind: OptionKind::Text,
+        scope: OptionScope::Global,
+        get: |settings, _| OptionValue::Text(settings.todo_markers.join(",")),
+        set: |settings, _, value| {
+            settings.todo_markers = value.text().split(',').map(str::trim).filter(|marker| !marker.is_empty()).map(String::from).collect();
+        },
+        changed: None,
+    },
+    OptionSpec {
+        name: "undolevels",
+        short: Some("ul"),
+        kind: OptionKind::Number { min: 1, max: 10_000 },
+        scope: OptionScope::Global,
+        get: |settings, _| OptionValue::Number(settings.undo_levels),
+        set: |settings, _, value| settings.undo_levels = value.number(),
+        changed: Some(|editor| {
+            editor.trim_all_undo_history();
+            Ok(())
+        }),
+    },
+    OptionSpec {
+        name: "undomemorylimit",
+        short: None,
+        kind: ANY_NUMBER,
+        scope: OptionScope::Global,
+        get: |settings, _| OptionValue::Number(settings.undo_memory_limit),
+        set: |settings, _, value| settings.undo_memory_limit = value.number(),
+        changed: Some(|editor| {
+            editor.trim_all_undo_history();
+            Ok(())
+        }),
+    },
+    OptionSpec {
+        name: "wrap",
+        short: None,
+        kind: OptionKind::Bool,
+        scope: OptionScope::Local,
+        get: |settings, _| OptionValue::Bool(settings.wrap),
+        set: |settings, _, value| settings.wrap = value.bool(),
+        changed: None,
+    },
+];
+
+fn split_arguments(arguments: &str) -> Vec<String> {
+    let mut split = Vec::new();
+    let mut argument = String::new();
+    let mut chars = arguments.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => argument.extend(chars.next()),
+            c if c.is_whitespace() => {
+                if !argument.is_empty() {
+                    split.push(std::mem::take(&mut argument));
+                }
+            }
+            c => argument.push(c),
+        }
+    }
+    if !argument.is_empty() {
+        split.push(argument);
+    }
+    split
+}
+
+pub(crate) fn find_option(name: &str) -> Option<&'static OptionSpec> {
+    OPTIONS.iter().find(|spec| spec.name == name || spec.short == Some(name) || (name.contains('_') && spec.name == name.replace('_', "")))
+}
+
+fn unknown_option(name: &str) -> String {
+    let name = name.trim_end_matches('!');
+    let bool_names = OPTIONS.iter().filter(|spec| matches!(spec.kind, OptionKind::Bool)).map(|spec| spec.name);
+    let suggestion = match name.strip_prefix("no").and_then(|rest| nearest(rest, bool_names)) {
+        Some(spec) => Some(format!("no{}", spec)),
+        None => nearest(name, OPTIONS.iter().map(|spec| spec.name)).map(String::from),
+    };
+    match suggestion {
+        Some(suggestion) => format!("Unknown option: {} (did you mean {}?)", name, suggestion),
+        None => format!("Unknown option: {}", name),
+    }
+}
+
+impl Editor {
+    pub(crate) fn option_value(&self, spec: &OptionSpec) -> OptionValue {
+        let tab = &self.tabs[self.active_tab];
+        tab.options.get(spec.name).cloned().unwrap_or_else(|| (spec.get)(&self.settings, tab))
+    }
+
+    pub(crate) fn option_default(&self, spec: &OptionSpec) -> OptionValue {
+        match spec.scope {
+            OptionScope::Buffer => (spec.get)(&Settings::default(), &Tab::new()),
+            _ => (spec.get)(&Settings::default(), &self.tabs[self.active_tab]),
+        }
+    }
+
+    pub(crate) fn option(&self, name: &str) -> OptionValue {
+        find_option(name).map_or(OptionValue::Bool(false), |spec| self.option_value(spec))
+    }
+
+    fn parse_option_value(&self, spec: &OptionSpec, value: &str) -> Result<OptionValue, String> {
+        match spec.kind {
+            OptionKind::Bool => Err(format!("Option {} takes no value", spec.name)),
+            OptionKind::Number { min, max } => value.parse::<usize>()
+                .map(|number| OptionValue::Number(number.clamp(min, max)))
+                .map_err(|_| format!("Invalid number: {}", value)),
+            OptionKind::Choice(choices) => choices.iter()
+                .find(|choice| **choice == value)
+                .map(|choice| OptionValue::Text(choice.to_string()))
+                .ok_or_else(|| match nearest(value, choices.iter().copied()) {
+                    Some(choice) => format!("Invalid value for {}: {} (did you mean {}?)", spec.name, value, choice),
+                    None => format!("Invalid value for {}: {} (expected {})", spec.name, value, choices.join(", ")),
+                }),
+            OptionKind::Text => Ok(OptionValue::Text(value.to_string())),
+            OptionKind::Syntax => self.find_syntax(value)
+                .map(|syntax| OptionValue::Text(syntax.name.clone()))
+                .ok_or_else(|| self.unknown_syntax(value)),
+        }
+    }
+
+    pub(crate) fn set_option(&mut self, argument: &str, local: bool) -> Result<bool, String> {
+        if let Some(name) = argument.strip_suffix('?') {
+            let spec = find_option(name).ok_or_else(|| unknown_option(name))?;
+            self.info(self.option_value(spec).describe(spec.name));
+            return Ok(false);
+        }
+        let is_bool = |spec: &&OptionSpec| matches!(spec.kind, OptionKind::Bool);
+        let (spec, value) = match argument.split_once('=') {
+            Some((name, value)) => {
+                let spec = find_option(name).ok_or_else(|| unknown_option(name))?;
+                (spec, self.parse_option_value(spec, value)?)
+            }
+            None => {
+                if let Some(spec) = find_option(argument) {
+                    if !is_bool(&spec) {
+                        self.info(self.option_value(spec).describe(spec.name));
+                        return Ok(false);
+                    }
+                    (spec, OptionValue::Bool(true))
+                } else if let Some(spec) = argument.strip_prefix("no").and_then(find_option).filter(is_bool) {
+                    (spec, OptionValue::Bool(false))
+                } else if let Some(spec) = argument.strip_suffix('!').and_then(find_option).filter(is_bool) {
+                    (spec, OptionValue::Bool(!self.option_value(spec).bool()))
+                } else {
+                    return Err(unknown_option(argument));
+                }
+            }
+        };
+        self.apply_option(spec, value, local)
+    }
+
+    fn apply_option(&mut self, spec: &OptionSpec, value: OptionValue, local: bool) -> Result<bool, String> {
+        let tab = &mut self.tabs[self.active_tab];
+        let persist = match (spec.scope, local) {
+            (OptionScope::Global, true) => return Err(format!("{} is a global option", spec.name)),
+            (OptionScope::Local, true) => {
+                tab.options.insert(spec.name, value);
+                false
+            }
+            (OptionScope::Local, false) => {
+                tab.options.remove(spec.name);
+                (spec.set)(&mut self.settings, tab, value);
+                true
+            }
+            (OptionScope::Buffer, _) => {
+                (spec.set)(&mut self.settings, tab, value);
+                false
+            }
+            (OptionScope::Global, false) => {
+                (spec.set)(&mut self.settings, tab, value);
+                true
+            }
+        };
+        if let Some(changed) = spec.changed {
+            changed(self)?;
+        }
+        self.ensure_cursor_visible();
+        self.adjust_horizontal_scroll();
+        Ok(persist)
+    }
+
+    pub(crate) fn set_command(&mut self, arguments: &str, local: bool) {
+        if arguments.is_empty() {
+            self.show_options(local);
+            return;
+        }
+        let mut persist = false;
+        let mut result = Ok(());
+        for argument in split_arguments(arguments) {
+            match self.set_option(&argument, local) {
+                Ok(changed) => persist |= changed,
+                Err(e) => {
+                    result = Err(e);
+                    break;
+                }
+            }
+        }
+        if persist {
+            if let Err(e) = self.save_settings() {
+                self.error(format!("Failed to save settings: {}", e));
+            }
+        }
+        if let Err(e) = result {
+            self.error(e);
+        }
+    }
+
+    pub(crate) fn show_options(&mut self, local: bool) {
+        let tab = &self.tabs[self.active_tab];
+        let items: Vec<String> = OPTIONS.iter()
+            .filter(|spec| if local { tab.options.contains_key(spec.name) } else { self.option_value(spec) != self.option_default(spec) })
+            .map(|spec| {
+                let marker = if tab.options.contains_key(spec.name) { " (local)" } else { "" };
+                format!("{}{}", self.option_value(spec).describe(spec.name), marker)
+            })
+            .collect();
+        if items.is_empty() {
+            self.info(if local { "No local options set" } else { "All options have their default values" });
+            return;
+        }
+        self.picker = Some(Picker::new(PickerKind::Options, if local { "Local options" } else { "Options" }, items));
+        self.mode = Mode::Picker;
+    }
+
+    pub(crate) fn edit_option(&mut self, item: &str) {
+        self.command_buffer.clear();
+        let item = item.trim_end_matches(" (local)");
+        let item = match item.split_once('=') {
+            Some((name, value)) => format!("{}={}", name, value.replace('\\', "\\\\").replace(' ', "\\ ")),
+            None => item.to_string(),
+        };
+        self.command_buffer.insert_str(&format!("set {}", item));
+        self.mode = Mode::Command;
+    }
+
+    pub(crate) fn option_completions(&self, arguments: &str) -> (usize, Vec<String>) {
+        let start = arguments.char_indices()
+            .rfind(|&(index, c)| c == ' ' && !arguments[..index].ends_with('\\'))
+            .map_or(0, |(index, _)| index + 1);
+        let word = &arguments[start..];
+        let completions = match word.split_once('=') {
+            Some((name, value)) => {
+                let values = match find_option(name).map(|spec| &spec.kind) {
+                    Some(OptionKind::Choice(choices)) => prefix_completions(value, choices.iter().copied()),
+                    Some(OptionKind::Syntax) => prefix_completions(&value.replace("\\ ", " "), self.syntax_names()).iter()
+                        .map(|syntax| syntax.replace(' ', "\\ "))
+                        .collect(),
+                    _ => Vec::new(),
+                };
+                values.iter().map(|value| format!("{}={}", name, value)).collect()
+            }
+            None => {
+                let mut completions = prefix_completions(word, OPTIONS.iter().map(|spec| spec.name));
+                if let Some(rest) = word.strip_prefix("no") {
+                    completions.extend(OPTIONS.iter()
+                        .filter(|spec| matches!(spec.kind, OptionKind::Bool) && spec.name.starts_with(rest))
+                        .map(|spec| format!("no{}", spec.name)));
+                    completions.sort();
+                }
+                completions
+            }
+        };
+        (start, completions)
+    }
+}
@@ -0,0 +1,282 @@
+use std::time::{Duration, Instant};
+
+use crossterm::event::{KeyCode, KeyEvent};
+use serde::{Deserialize, Serialize};
+use syntect::parsing::{Regex, Region};
+
+use crate::buffer::display_width;
+use crate::editor::{Editor, Mode};
+
+#[derive(Deserialize, Serialize, Clone)]
+pub(crate) struct OutlineRule {
+    pub(crate) kind: String,
+    pub(crate) pattern: String,
+    #[serde(default = "OutlineRule::default_name_group")]
+    pub(crate) name_group: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) level_group: Option<usize>,
+}
+
+impl OutlineRule {
+    fn default_name_group() -> usize {
+        1
+    }
+
+    fn new(kind: &str, pattern: &str) -> Self {
+        OutlineRule { kind: kind.to_string(), pattern: pattern.to_string(), name_group: 1, level_group: None }
+    }
+
+    pub(crate) fn defaults(syntax: &str) -> Vec<OutlineRule> {
+        let rust_item = |keyword: &str| format!(r"^\s*(?:pub(?:\([^)]*\))?\s+)?{}\s+([A-Za-z_][A-Za-z0-9_]*)", keyword);
+        match syntax {
+            "Rust" => vec![
+                OutlineRule::new("fn", r#"^\s*(?:pub(?:\([^)]*\))?\s+)?(?:const\s+)?(?:async\s+)?(?:unsafe\s+)?(?:extern\s+"[^"]*"\s+)?fn\s+([A-Za-z_][A-Za-z0-9_]*)"#),
+                OutlineRule::new("struct", &rust_item("struct")),
+                OutlineRule::new("enum", &rust_item("enum")),
+                OutlineRule::new("trait", &rust_item("(?:unsafe\\s+)?trait")),
+                OutlineRule::new("impl", r"^\s*(?:unsafe\s+)?impl(?:<[^{]*?>)?\s+([^{]+?)\s*(?:\{.*|where\b.*)?$"),
+            ],
+            "Python" => vec![
+                OutlineRule::new("def", r"^\s*(?:async\s+)?def\s+([A-Za-z_]\w*)"),
+                OutlineRule::new("class", r"^\s*class\s+([A-Za-z_]\w*)"),
+            ],
+            "Markdown" => vec![OutlineRule {
+                kind: String::new(),
+                pattern: r"^(#{1,6})\s+(.+?)(?:\s+#+)?\s*$".to_string(),
+                name_group: 2,
+                level_group: Some(1),
+            }],
+            _ => Vec::new(),
+        }
+    }
+}
+
+pub(crate) struct LineSymbol {
+    pub(crate) kind: String,
+    pub(crate) name: String,
+    pub(crate) level: Option<usize>,
+}
+
+pub(crate) struct OutlineSymbol {
+    pub(crate) line: usize,
+    pub(crate) end: usize,
+    pub(crate) depth: usize,
+    pub(crate) label: String,
+}
+
+pub(crate) struct Outline {
+    pub(crate) tab: usize,
+    pub(crate) syntax: String,
+    pub(crate) rules: Vec<(OutlineRule, Regex)>,
+    pub(crate) source: Vec<String>,
+    pub(crate) matches: Vec<Option<LineSymbol>>,
+    pub(crate) symbols: Vec<OutlineSymbol>,
+    pub(crate) selected: usize,
+    pub(crate) changed_at: Option<Instant>,
+}
+
+impl Outline {
+    pub(crate) const DEBOUNCE: Duration = Duration::from_millis(300);
+
+    pub(crate) fn new(tab: usize, syntax: &str, rules: Vec<OutlineRule>, content: &[String]) -> Self {
+        let rules = rules.into_iter()
+            .filter(|rule| Regex::try_compile(&rule.pattern).is_none())
+            .map(|rule| {
+                let regex = Regex::new(rule.pattern.clone());
+                (rule, regex)
+            })
+            .collect();
+        let mut outline = Outline {
+            tab,
+            syntax: syntax.to_string(),
+            rules,
+            source: Vec::new(),
+            matches: Vec::new(),
+            symbols: Vec::new(),
+            selected: 0,
+            changed_at: None,
+        };
+        outline.update(content);
+        outline
+    }
+
+    fn scan_line(&self, line: &str) -> Option<LineSymbol> {
+        let mut region = Region::new();
+        self.rules.iter().find_map(|(rule, regex)| {
+            if !regex.search(line, 0, line.len(), Some(&mut region)) {
+                return None;
+            }
+            let (start, end) = region.pos(rule.name_group)?;
+            let level = rule.level_group.map(|group| region.pos(group).map_or(1, |(start, end)| line[start..end].chars().count()));
+            Some(LineSymbol { kind: rule.kind.clone(), name: line[start..end].trim().to_string(), level })
+        })
+    }
+
+    pub(crate) fn update(&mut self, content: &[String]) {
+        let prefix = self.source.iter().zip(content).take_while(|(old, new)| old == new).count();
+        let max_suffix = self.source.len().min(content.len()) - prefix;
+        let suffix = self.source.iter().rev().zip(content.iter().rev())
+            .take(max_suffix)
+            .take_while(|(old, new)| old == new)
+            .count();
+        let rescanned: Vec<Option<LineSymbol>> = content[prefix..content.len() - suffix].iter()
+            .map(|line| self.scan_line(line))
+            .collect();
+        self.matches.splice(prefix..self.source.len() - suffix, rescanned);
+        self.source.splice(prefix..self.source.len() - suffix, content[prefix..content.len() - suffix].iter().cloned());
+        self.changed_at = None;
+        self.rebuild_symbols();
+    }
+
+    fn rebuild_symbols(&mut self) {
+        let indent = |line: &str| display_width(&line[..line.len() - line.trim_start().len()]);
+        let found: Vec<(usize, &LineSymbol)> = self.matches.iter()
+            .enumerate()
+            .filter_map(|(line, symbol)| symbol.as_ref().map(|symbol| (line, symbol)))
+            .collect();
+        let mut symbols: Vec<OutlineSymbol> = Vec::new();
+        let mut open: Vec<usize> = Vec::new();
+        for (index, &(line, symbol)) in found.iter().enumerate() {
+            let end = match symbol.level {
+                Some(level) => found[index + 1..].iter()
+                    .find(|(_, next)| next.level.is_some_and(|next| next <= level))
+                    .map_or(self.source.len(), |&(next, _)| next),
+                None => {
+                    let own = indent(&self.source[line]);
+                    (line + 1..self.source.len())
+                        .find(|&next| {
+                            let text = &self.source[next];
+                            let trimmed = text.trim_start();
+                            let next_indent = indent(text);
+                            !trimmed.is_empty()
+                                && (next_indent < own || (next_indent == own && !trimmed.starts_with(['}', ')', ']'])))
+                        })
+                        .unwrap_or(self.source.len())
+                }
+            };
+            while open.last().is_some_and(|&parent| symbols[parent].end <= line) {
+                open.pop();
+            }
+            let label = if symbol.kind.is_empty() { symbol.name.clone() } else { format!("{} {}", symbol.kind, symbol.name) };
+            symbols.push(OutlineSymbol { line, end, depth: open.len(), label });
+            open.push(symbols.len() - 1);
+        }
+        self.symbols = symbols;
+        self.selected = self.selected.min(self.symbols.len().saturating_sub(1));
+    }
+
+    pub(crate) fn enclosing(&self, line: usize) -> Option<usize> {
+        self.symbols.iter().rposition(|symbol| symbol.line <= line && line < symbol.end)
+    }
+}
+
+impl Editor {
+    pub(crate) fn outline_rules(&self, syntax: &str) -> Vec<OutlineRule> {
+        self.settings.outline_rules.get(syntax).cloned().unwrap_or_else(|| OutlineRule::defaults(syntax))
+    }
+
+    pub(crate) fn toggle_outline(&mut self) {
+        if self.outline.take().is_some() {
+            if self.mode == Mode::Outline {
+                self.mode = Mode::Normal;
+            }
+            return;
+        }
+        let syntax = self.tabs[self.active_tab].syntax.clone();
+        let rules = self.outline_rules(&syntax);
+        if rules.is_empty() {
+            self.info(format!("No outline rules for {}", syntax));
+        }
+        for rule in &rules {
+            if let Some(e) = Regex::try_compile(&rule.pattern) {
+                self.error(format!("Invalid outline pattern for {} {}: {}", syntax, rule.kind, e));
+            }
+        }
+        let tab = &self.tabs[self.active_tab];
+        let mut outline = Outline::new(self.active_tab, &tab.syntax, rules, &tab.content);
+        outline.selected = outline.enclosing(tab.cursor_position.1).unwrap_or(0);
+        self.outline = Some(outline);
+        self.mode = Mode::Outline;
+    }
+
+    pub fn refresh_outline(&mut self) -> bool {
+        let Some(outline) = &mut self.outline else {
+            return false;
+        };
+        let tab = &self.tabs[self.active_tab];
+        if outline.tab != self.active_tab || outline.syntax != tab.syntax {
+            let rules = self.outline_rules(&tab.syntax);
+            self.outline = Some(Outline::new(self.active_tab, &tab.syntax, rules, &tab.content));
+            return true;
+        }
+        if outline.source == tab.content {
+            outline.changed_at = None;
+            return false;
+        }
+        match outline.changed_at {
+            Some(changed_at) if changed_at.elapsed() >= Outline::DEBOUNCE => {
+                outline.update(&tab.content);
+                true
+            }
+            Some(_) => false,
+            None => {
+                outline.changed_at = Some(Instant::now());
+                false
+            }
+        }
+    }
+
+    pub(crate) fn outline_jump(&mut self, index: usize) {
+        let Some(line) = self.outline.as_ref().and_then(|outline| outline.symbols.get(index)).map(|symbol| symbol.line) else {
+            return;
+        };
+        if let Some(outline) = &mut self.outline {
+            outline.selected = index;
+        }
+        self.goto_location(line + 1, None);
+        let tab = &mut self.tabs[self.active_tab];
+        let x = tab.content[line].len() - tab.content[line].trim_start().len();
+        tab.cursor_position.0 = x;
+        self.adjust_horizontal_scroll();
+    }
+
+    pub(crate) fn handle_outline_mode(&mut self, key: KeyEvent) {
+        let Some(outline) = &mut self.outline else {
+            self.mode = Mode::Normal;
+            return;
+        };
+        match key.code {
+            KeyCode::Up => outline.selected = outline.selected.saturating_sub(1),
+            KeyCode::Down if outline.selected + 1 < outline.symbols.len() => outline.selected += 1,
+            KeyCode::Enter => {
+                let selected = outline.selected;
+                self.mode = Mode::Normal;
+                self.outline_jump(selected);
+            }
+            KeyCode::Esc => self.mode = Mode::Normal,
+            _ => {}
+        }
+    }
+
+    pub(crate) fn handle_outline_click(&mut self, y: u16) {
+        let Some(area) = self.layout.outline else {
+            return;
+        };
+        let Some(outline) = &self.outline else {
+            return;
+        };
+        let rows = area.height.saturating_sub(2) as usize;
+        let focus = if self.mode == Mode::Outline { Some(outline.selected) } else { outline.enclosing(self.tabs[self.active_tab].cursor_position.1) };
+        let offset = Self::outline_offset(focus, rows);
+        let Some(row) = (y as usize).checked_sub(area.y as usize + 1) else {
+            return;
+        };
+        if row < rows && offset + row < outline.symbols.len() {
+            self.outline_jump(offset + row);
+        }
+    }
+
+    pub(crate) fn outline_offset(focus: Option<usize>, rows: usize) -> usize {
+        focus.map_or(0, |focus| (focus + 1).saturating_sub(rows.max(1)))
+    }
+}
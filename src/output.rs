@@ -0,0 +1,290 @@
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::thread;
+
+use crossterm::event::{KeyCode, KeyEvent};
+use tui::backend::Backend;
+use tui::layout::Rect;
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, Borders, Clear, Paragraph};
+use tui::Frame;
+
+use crate::config::ColorConfig;
+use crate::editor::Editor;
+use crate::input::InputLine;
+use crate::tasks::Progress;
+use crate::ui::centered_rect;
+
+pub(crate) const OUTPUT_SCROLLBACK: usize = 10_000;
+const DROPPED_MARKER: &str = "... older output dropped";
+
+pub(crate) struct OutputPager {
+    title: String,
+    lines: VecDeque<String>,
+    dropped: bool,
+    receiver: Option<Receiver<Vec<String>>>,
+    current: usize,
+    scroll: usize,
+    height: usize,
+    search: Option<InputLine>,
+    query: String,
+    pending_yank: bool,
+}
+
+impl OutputPager {
+    fn new(title: &str, lines: Vec<String>, receiver: Option<Receiver<Vec<String>>>) -> Self {
+        let mut pager = OutputPager {
+            title: title.to_string(),
+            lines: VecDeque::new(),
+            dropped: false,
+            receiver,
+            current: 0,
+            scroll: 0,
+            height: 1,
+            search: None,
+            query: String::new(),
+            pending_yank: false,
+        };
+        pager.push(lines);
+        pager
+    }
+
+    fn len(&self) -> usize {
+        self.lines.len() + usize::from(self.dropped)
+    }
+
+    fn line(&self, index: usize) -> &str {
+        match index.checked_sub(usize::from(self.dropped)) {
+            Some(index) => &self.lines[index],
+            None => DROPPED_MARKER,
+        }
+    }
+
+    fn push(&mut self, lines: Vec<String>) {
+        let following = self.current + 1 >= self.len();
+        self.lines.extend(lines);
+        let excess = self.lines.len().saturating_sub(OUTPUT_SCROLLBACK);
+        if excess > 0 {
+            self.lines.drain(..excess);
+            self.current = self.current.saturating_sub(excess);
+            self.scroll = self.scroll.saturating_sub(excess);
+            self.dropped = true;
+        }
+        if following {
+            self.select(usize::MAX);
+        }
+    }
+
+    fn select(&mut self, index: usize) {
+        self.current = index.min(self.len().saturating_sub(1));
+        if self.current < self.scroll {
+            self.scroll = self.current;
+        } else if self.current >= self.scroll + self.height {
+            self.scroll = self.current + 1 - self.height;
+        }
+    }
+
+    fn matches(&self, index: usize) -> bool {
+        let line = self.line(index);
+        if self.query.chars().any(char::is_uppercase) {
+            line.contains(&self.query)
+        } else {
+            line.to_lowercase().contains(&self.query)
+        }
+    }
+
+    fn find(&mut self, forward: bool) -> bool {
+        let len = self.len();
+        if self.query.is_empty() || len == 0 {
+            return false;
+        }
+        let found = (1..=len)
+            .map(|offset| if forward { (self.current + offset) % len } else { (self.current + len * 2 - offset) % len })
+            .find(|&index| self.matches(index));
+        if let Some(index) = found {
+            self.select(index);
+        }
+        found.is_some()
+    }
+
+    pub(crate) fn render<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect, color_config: &ColorConfig) {
+        let area = centered_rect(area, (area.width * 3 / 4).max(20), (area.height * 3 / 4).max(5));
+        f.render_widget(Clear, area);
+        let prompt = self.search.as_ref().map(|search| format!("/{}", search.text));
+        self.height = (area.height.saturating_sub(2) as usize).saturating_sub(usize::from(prompt.is_some())).max(1);
+        self.select(self.current);
+
+        let style = color_config.bg(&color_config.pager_background).patch(color_config.fg(&color_config.foreground));
+        let current_style = color_config.highlight(&color_config.cursor_line);
+        let match_style = color_config.highlight(&color_config.selection);
+        let dropped_style = color_config.fg(&color_config.comment);
+        let mut text: Vec<Spans> = (self.scroll..(self.scroll + self.height).min(self.len()))
+            .map(|index| {
+                let line = self.line(index).replace('\t', "    ");
+                let style = match index {
+                    0 if self.dropped => dropped_style,
+                    _ if !self.query.is_empty() && self.matches(index) => match_style,
+                    _ => Default::default(),
+                };
+                let style = if index == self.current { style.patch(current_style) } else { style };
+                let width = area.width.saturating_sub(2) as usize;
+                Spans::from(Span::styled(format!("{:<width$}", line, width = width), style))
+            })
+            .collect();
+        if let Some(prompt) = prompt {
+            text.resize(self.height, Spans::default());
+            text.push(Spans::from(prompt));
+        }
+        let streaming = if self.receiver.is_some() { " …" } else { "" };
+        let title = format!("{} ({}/{}){}", self.title, self.current + 1, self.len(), streaming);
+        let paragraph = Paragraph::new(text)
+            .block(Block::default().title(title).borders(Borders::ALL).border_style(color_config.fg(&color_config.pager_border)))
+            .style(style);
+        f.render_widget(paragraph, area);
+        if let Some(search) = &self.search {
+            f.set_cursor(area.x + 2 + search.cursor as u16, area.y + area.height.saturating_sub(2));
+        }
+    }
+}
+
+fn forward_lines(reader: impl Read, sender: &Sender<Vec<String>>, cancelled: impl Fn() -> bool) -> bool {
+    for line in BufReader::new(reader).lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if cancelled() || sender.send(vec![line]).is_err() {
+            return false;
+        }
+    }
+    true
+}
+
+fn stream_command(mut child: Child, command: String, sender: Sender<Vec<String>>, progress: Progress) {
+    let stderr = child.stderr.take().map(|stderr| {
+        let sender = sender.clone();
+        thread::spawn(move || forward_lines(stderr, &sender, || false))
+    });
+    let finished = child.stdout.take().is_none_or(|stdout| forward_lines(stdout, &sender, || progress.cancelled()));
+    if !finished {
+        let _ = child.kill();
+    }
+    if let Some(stderr) = stderr {
+        let _ = stderr.join();
+    }
+    match child.wait() {
+        Ok(status) if finished && !status.success() => {
+            let _ = sender.send(vec![String::new(), format!("{} failed ({})", command, status)]);
+        }
+        Ok(_) => {}
+        Err(e) => progress.fail(e),
+    }
+}
+
+impl Editor {
+    pub(crate) fn show_output(&mut self, title: &str, lines: Vec<String>) {
+        self.output = Some(OutputPager::new(title, lines, None));
+    }
+
+    pub(crate) fn stream_output(&mut self, title: &str, receiver: Receiver<Vec<String>>) {
+        self.output = Some(OutputPager::new(title, Vec::new(), Some(receiver)));
+    }
+
+    pub fn output_lines(&self) -> Option<Vec<String>> {
+        self.output.as_ref().map(|output| (0..output.len()).map(|index| output.line(index).to_string()).collect())
+    }
+
+    pub fn poll_output(&mut self) -> bool {
+        let Some(output) = &mut self.output else {
+            return false;
+        };
+        let Some(receiver) = &output.receiver else {
+            return false;
+        };
+        let mut received = Vec::new();
+        let finished = loop {
+            match receiver.try_recv() {
+                Ok(chunk) => received.extend(chunk),
+                Err(TryRecvError::Empty) => break false,
+                Err(TryRecvError::Disconnected) => break true,
+            }
+        };
+        if finished {
+            output.receiver = None;
+        }
+        let changed = finished || !received.is_empty();
+        output.push(received);
+        changed
+    }
+
+    pub(crate) fn run_shell_output(&mut self, command: &str) -> Result<(), String> {
+        let command = command.trim();
+        if command.is_empty() {
+            return Err("Missing shell command".to_string());
+        }
+        let mut shell = if cfg!(windows) { Command::new("cmd") } else { Command::new("sh") };
+        shell.arg(if cfg!(windows) { "/C" } else { "-c" }).arg(command);
+        if let Some(dir) = self.tabs[self.active_tab].current_file.as_ref().and_then(|file| Path::new(file).parent()) {
+            shell.current_dir(dir);
+        }
+        let child = shell.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()
+            .map_err(|e| format!("Failed to run {}: {}", command, e))?;
+        let (sender, receiver) = mpsc::channel();
+        let progress = self.start_task(format!("Running {}", command), true);
+        let label = command.to_string();
+        thread::spawn(move || stream_command(child, label, sender, progress));
+        self.stream_output(&format!("!{}", command), receiver);
+        Ok(())
+    }
+
+    pub(crate) fn handle_output_key(&mut self, key: KeyEvent) {
+        let Some(output) = &mut self.output else {
+            return;
+        };
+        if let Some(search) = &mut output.search {
+            match key.code {
+                KeyCode::Enter => {
+                    output.query = search.text.clone();
+                    output.search = None;
+                    if !output.find(true) && !output.query.is_empty() {
+                        let query = output.query.clone();
+                        self.warn(format!("Pattern not found: {}", query));
+                    }
+                }
+                KeyCode::Esc => output.search = None,
+                _ => {
+                    search.handle_key(key, None, &mut self.clipboard_context);
+                }
+            }
+            return;
+        }
+        let yank = std::mem::take(&mut output.pending_yank);
+        let page = output.height;
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => self.output = None,
+            KeyCode::Char('j') | KeyCode::Down | KeyCode::Enter => output.select(output.current + 1),
+            KeyCode::Char('k') | KeyCode::Up => output.select(output.current.saturating_sub(1)),
+            KeyCode::Char(' ') | KeyCode::PageDown => output.select(output.current + page),
+            KeyCode::Char('b') | KeyCode::PageUp => output.select(output.current.saturating_sub(page)),
+            KeyCode::Char('g') | KeyCode::Home => output.select(0),
+            KeyCode::Char('G') | KeyCode::End => output.select(usize::MAX),
+            KeyCode::Char('/') => output.search = Some(InputLine::default()),
+            KeyCode::Char('n') => {
+                output.find(true);
+            }
+            KeyCode::Char('N') => {
+                output.find(false);
+            }
+            KeyCode::Char('y') if yank => {
+                let line = output.line(output.current).to_string();
+                if self.set_clipboard(line) {
+                    self.info("Line copied to clipboard");
+                }
+            }
+            KeyCode::Char('y') => output.pending_yank = true,
+            _ => {}
+        }
+    }
+}
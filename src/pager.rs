@@ -0,0 +1,159 @@
+use std::io::{self, BufRead, BufReader, Read};
+use std::mem;
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+use crossterm::event::{KeyCode, KeyEvent};
+
+use crate::editor::{Editor, Mode};
+use crate::filetype::detect_syntax;
+use crate::options::OptionValue;
+use crate::tasks::Progress;
+
+pub(crate) const PAGER_CHUNK_LINES: usize = 4096;
+
+pub(crate) struct PagerInput {
+    pub(crate) receiver: Receiver<Vec<String>>,
+    pub(crate) tab: usize,
+    pub(crate) lines: usize,
+}
+
+fn read_chunks(mut reader: BufReader<impl Read>, sender: mpsc::Sender<Vec<String>>, progress: Progress) {
+    let mut chunk = Vec::new();
+    let mut buffer = Vec::new();
+    let mut lines = 0;
+    loop {
+        if progress.cancelled() {
+            return;
+        }
+        buffer.clear();
+        match reader.read_until(b'\n', &mut buffer) {
+            Ok(0) => break,
+            Ok(_) => {}
+            Err(e) => {
+                if !chunk.is_empty() {
+                    let _ = sender.send(chunk);
+                }
+                progress.fail(e);
+                return;
+            }
+        }
+        if buffer.ends_with(b"\n") {
+            buffer.pop();
+            if buffer.ends_with(b"\r") {
+                buffer.pop();
+            }
+        }
+        chunk.push(String::from_utf8_lossy(&buffer).into_owned());
+        lines += 1;
+        let flush = chunk.len() >= PAGER_CHUNK_LINES || reader.buffer().is_empty();
+        if flush {
+            progress.report(lines, None);
+        }
+        if flush && sender.send(mem::take(&mut chunk)).is_err() {
+            return;
+        }
+    }
+    if !chunk.is_empty() {
+        let _ = sender.send(chunk);
+    }
+}
+
+impl Editor {
+    pub fn start_pager(&mut self) {
+        self.pager = true;
+        self.mode = Mode::Pager;
+        for tab in &mut self.tabs {
+            tab.read_only = true;
+            tab.options.entry("wrap").or_insert(OptionValue::Bool(true));
+        }
+    }
+
+    pub fn page_reader(&mut self, reader: impl Read + Send + 'static) {
+        let (sender, receiver) = mpsc::channel();
+        let progress = self.start_task("Reading stdin", true);
+        thread::spawn(move || read_chunks(BufReader::new(reader), sender, progress));
+        self.tabs[self.active_tab].title = Some("stdin".to_string());
+        self.pager_input = Some(PagerInput { receiver, tab: self.active_tab, lines: 0 });
+        self.start_pager();
+    }
+
+    pub fn page_stdin(&mut self) {
+        self.page_reader(io::stdin());
+    }
+
+    pub fn poll_pager(&mut self) -> bool {
+        let Some(input) = &mut self.pager_input else {
+            return false;
+        };
+        let mut received = Vec::new();
+        let finished = loop {
+            match input.receiver.try_recv() {
+                Ok(chunk) => received.extend(chunk),
+                Err(TryRecvError::Empty) => break false,
+                Err(TryRecvError::Disconnected) => break true,
+            }
+        };
+        let (tab_index, first) = (input.tab, input.lines == 0);
+        input.lines += received.len();
+        if finished {
+            self.pager_input = None;
+        }
+        let Some(tab) = self.tabs.get_mut(tab_index) else {
+            self.pager_input = None;
+            return false;
+        };
+        if received.is_empty() {
+            return finished;
+        }
+        if first {
+            tab.content.clear();
+            if tab.syntax == "Plain Text" {
+                if let Some(syntax) = detect_syntax(&self.ps, Path::new(""), &received[0]) {
+                    tab.syntax = syntax.name.clone();
+                }
+            }
+        }
+        tab.content.extend(received);
+        if tab_index == self.active_tab {
+            self.syntax = self.tabs[tab_index].syntax.clone();
+        }
+        true
+    }
+
+    pub(crate) fn base_mode(&self) -> Mode {
+        if self.pager { Mode::Pager } else { Mode::Normal }
+    }
+
+    pub(crate) fn handle_pager_mode(&mut self, key: KeyEvent) -> io::Result<bool> {
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Char('Q') => return Ok(true),
+            KeyCode::Char(' ') | KeyCode::Char('f') | KeyCode::PageDown => self.page_down(),
+            KeyCode::Char('b') | KeyCode::PageUp => self.page_up(),
+            KeyCode::Char('j') | KeyCode::Down | KeyCode::Enter => self.scroll_line(true),
+            KeyCode::Char('k') | KeyCode::Up => self.scroll_line(false),
+            KeyCode::Char('g') | KeyCode::Home => {
+                self.tabs[self.active_tab].cursor_position = (0, 0);
+                self.ensure_cursor_visible();
+            }
+            KeyCode::Char('G') | KeyCode::End => {
+                let tab = &mut self.tabs[self.active_tab];
+                tab.cursor_position = (0, tab.content.len() - 1);
+                self.ensure_cursor_visible();
+            }
+            KeyCode::Char('/') => self.enter_search_mode(),
+            KeyCode::Char('n') => self.next_search_result(),
+            KeyCode::Char('N') => self.previous_search_result(),
+            KeyCode::Char('w') => {
+                let wrap = !self.option("wrap").bool();
+                self.tabs[self.active_tab].options.insert("wrap", OptionValue::Bool(wrap));
+                self.adjust_horizontal_scroll();
+                self.ensure_cursor_visible();
+            }
+            KeyCode::Esc => {}
+            _ => self.info("Read-only pager: Space/b page, / searches, n/N jump, w toggles wrapping, q quits"),
+        }
+        Ok(false)
+    }
+}
@@ -0,0 +1,152 @@
+use std::path::{Path, PathBuf};
+
+use crate::editor::{Editor, Mode};
+use crate::ui::{Prompt, PromptAction};
+
+fn percent_decode(text: &str) -> Option<String> {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = text.get(i + 1..i + 3)?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+fn split_dropped(text: &str) -> Option<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut quote = None;
+    let mut started = false;
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"'), '\\') => word.push(chars.next()?),
+            (Some(_), c) => word.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                started = true;
+            }
+            (None, '\\') => {
+                word.push(chars.next()?);
+                started = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if started {
+                    words.push(std::mem::take(&mut word));
+                    started = false;
+                }
+            }
+            (None, c) => {
+                word.push(c);
+                started = true;
+            }
+        }
+    }
+    if quote.is_some() {
+        return None;
+    }
+    if started {
+        words.push(word);
+    }
+    Some(words)
+}
+
+pub(crate) fn dropped_paths(text: &str) -> Option<Vec<PathBuf>> {
+    let text = text.trim();
+    let words = if text.contains('\n') {
+        text.lines().map(str::trim).filter(|line| !line.is_empty()).map(String::from).collect()
+    } else {
+        split_dropped(text)?
+    };
+    if words.is_empty() {
+        return None;
+    }
+    words.iter()
+        .map(|word| {
+            let path = match word.strip_prefix("file://") {
+                Some(uri) => PathBuf::from(percent_decode(uri)?),
+                None => PathBuf::from(word),
+            };
+            (path.is_absolute() && path.is_file()).then_some(path)
+        })
+        .collect()
+}
+
+impl Editor {
+    pub fn handle_paste(&mut self, text: &str) {
+        if let Some(Prompt::Input(prompt)) = &mut self.prompt {
+            prompt.completions.clear();
+            prompt.input.insert_str(text.lines().next().unwrap_or_default());
+            return;
+        }
+        if self.prompt.is_some() {
+            return;
+        }
+        let dropped = Some(self.mode)
+            .filter(|mode| self.settings.open_dropped_files && matches!(mode, Mode::Normal | Mode::Insert | Mode::FileSelect | Mode::DirectoryNav))
+            .and_then(|_| dropped_paths(text));
+        match dropped {
+            Some(paths) if matches!(self.mode, Mode::FileSelect | Mode::DirectoryNav) || self.start_screen_active() => {
+                self.open_dropped_files(&paths);
+            }
+            Some(paths) => {
+                let message = match paths.as_slice() {
+                    [path] => format!("Open dropped file {}?", self.display_path(&path.to_string_lossy())),
+                    _ => format!("Open {} dropped files as tabs?", paths.len()),
+                };
+                self.confirm(PromptAction::OpenDroppedFiles { paths, text: text.to_string() }, &message, "Open", "Paste as text");
+            }
+            None => self.paste_text(text),
+        }
+    }
+
+    pub(crate) fn open_dropped_files(&mut self, paths: &[PathBuf]) {
+        self.file_selector = None;
+        self.mode = Mode::Normal;
+        let mut first = None;
+        for path in paths {
+            match self.open_file(Path::new(path)) {
+                Ok(()) => {
+                    first.get_or_insert(self.active_tab);
+                }
+                Err(e) => self.error(format!("Failed to open {}: {}", self.display_path(&path.to_string_lossy()), e)),
+            }
+        }
+        if let Some(first) = first {
+            self.set_active_tab(first);
+            self.update_current_tab_info();
+            if paths.len() > 1 {
+                self.info(format!("Opened {} dropped files", paths.len()));
+            }
+        }
+    }
+
+    pub(crate) fn paste_text(&mut self, text: &str) {
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+        match self.mode {
+            Mode::Normal | Mode::Insert if self.tabs[self.active_tab].read_only => self.error("Buffer is read-only"),
+            Mode::Normal | Mode::Insert => {
+                self.insert_pasted(&text);
+                self.ensure_cursor_visible();
+                self.adjust_horizontal_scroll();
+            }
+            Mode::Command => self.command_buffer.insert_str(text.lines().next().unwrap_or_default()),
+            Mode::Search => self.search_query.insert_str(text.lines().next().unwrap_or_default()),
+            Mode::Terminal => {
+                if let Some(terminal) = &mut self.terminal {
+                    terminal.send(text.as_bytes());
+                }
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+use std::io;
+use std::time::{Duration, Instant};
+
+use crossterm::event::KeyEvent;
+
+use crate::editor::{Editor, Mode};
+use crate::git::GitFile;
+use crate::log::LogLevel;
+
+pub(crate) enum KeyMatch {
+    Action(String),
+    Pending,
+    Abandoned(Option<String>),
+    Unmatched,
+}
+
+impl Editor {
+    fn mode_bindings(&self) -> Option<&HashMap<String, String>> {
+        match self.mode {
+            Mode::Normal => Some(&self.keybindings.normal_mode),
+            Mode::Insert => Some(&self.keybindings.insert_mode),
+            Mode::Visual => Some(&self.keybindings.visual_mode),
+            Mode::VisualBlock => Some(&self.keybindings.visual_block_mode),
+            _ => None,
+        }
+    }
+
+    fn filetype_bindings(&self) -> Option<&HashMap<String, String>> {
+        let rebase_todo = self.mode == Mode::Normal && self.tabs[self.active_tab].git_file() == Some(GitFile::RebaseTodo);
+        rebase_todo.then_some(&self.keybindings.git_rebase_todo)
+    }
+
+    fn bound_action(&self, sequence: &str) -> Option<String> {
+        self.filetype_bindings().and_then(|bindings| bindings.get(sequence))
+            .or_else(|| self.mode_bindings().and_then(|bindings| bindings.get(sequence)))
+            .cloned()
+    }
+
+    pub(crate) fn set_pending_key(&mut self, sequence: String, key: KeyEvent) {
+        if self.pending_key.is_none() {
+            self.pending_key_since = Some((Instant::now(), key));
+        }
+        self.pending_key = Some(sequence);
+    }
+
+    pub(crate) fn clear_pending_key(&mut self) -> Option<String> {
+        self.pending_key_since = None;
+        self.pending_key.take()
+    }
+
+    pub(crate) fn match_key_sequence(&mut self, key: KeyEvent, key_str: &str) -> KeyMatch {
+        let Some(bindings) = self.mode_bindings() else {
+            return KeyMatch::Unmatched;
+        };
+        let prefix = self.pending_key.clone();
+        let sequence = format!("{}{}", prefix.as_deref().unwrap_or_default(), key_str);
+        let action = self.bound_action(&sequence);
+        let extends = bindings.keys().chain(self.filetype_bindings().into_iter().flat_map(HashMap::keys))
+            .any(|k| k.len() > sequence.len() && k.starts_with(&sequence));
+        if extends && (action.is_none() || key_str.chars().count() == 1) {
+            self.set_pending_key(sequence, key);
+            return KeyMatch::Pending;
+        }
+        let prefix_action = prefix.as_ref().and_then(|prefix| self.bound_action(prefix));
+        self.clear_pending_key();
+        match (action, prefix) {
+            (Some(action), _) => KeyMatch::Action(action),
+            (None, Some(_)) => KeyMatch::Abandoned(prefix_action),
+            (None, None) => KeyMatch::Unmatched,
+        }
+    }
+
+    pub(crate) fn redispatch_key(&mut self, prefix_action: Option<String>, key: KeyEvent) -> io::Result<bool> {
+        if let Some(action) = prefix_action {
+            if self.execute_action(&action)? {
+                return Ok(true);
+            }
+        }
+        self.handle_key_event(key)
+    }
+
+    pub fn pending_keys(&self) -> Option<&str> {
+        self.pending_key.as_deref()
+    }
+
+    pub fn expire_pending_keys(&mut self, now: Instant) -> io::Result<bool> {
+        let timeout = Duration::from_millis(self.settings.key_timeout_ms);
+        let Some((since, key)) = self.pending_key_since.filter(|(since, _)| now.saturating_duration_since(*since) >= timeout) else {
+            return Ok(false);
+        };
+        let Some(sequence) = self.clear_pending_key() else {
+            return Ok(false);
+        };
+        let single = sequence == Self::key_event_to_string(key);
+        let action = self.bound_action(&sequence)
+            .or_else(|| Self::fallback_action(key.code).filter(|_| single).map(String::from));
+        self.log(LogLevel::Debug, format!("Key sequence {} timed out after {:?}", sequence, now.saturating_duration_since(since)));
+        if let Some(action) = action {
+            self.execute_action(&action)?;
+        }
+        Ok(true)
+    }
+}
@@ -0,0 +1,375 @@
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::editor::{Editor, MessageLevel};
+use crate::tasks::Progress;
+
+#[derive(Deserialize, Serialize, Clone)]
+pub(crate) struct PluginConfig {
+    pub(crate) name: String,
+    pub(crate) command: Vec<String>,
+    #[serde(default)]
+    pub(crate) actions: Vec<String>,
+    #[serde(default = "PluginConfig::default_timeout_ms")]
+    pub(crate) timeout_ms: u64,
+}
+
+impl PluginConfig {
+    fn default_timeout_ms() -> u64 {
+        2000
+    }
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EditorMessage {
+    BufferOpened { path: String },
+    BufferSaved { path: String },
+    Action { name: String, path: Option<String>, cursor: (usize, usize), lines: Vec<String> },
+}
+
+impl EditorMessage {
+    fn description(&self) -> &'static str {
+        match self {
+            EditorMessage::BufferOpened { .. } => "Sent to every plugin after a file is opened.",
+            EditorMessage::BufferSaved { .. } => "Sent to every plugin after a file is written.",
+            EditorMessage::Action { .. } => {
+                "Sent to the plugin that declared the action when it is invoked from a key binding. \
+                 `cursor` is `[byte column, line]`, both zero-based. The plugin must answer with any number \
+                 of messages followed by `done` before its `timeout_ms` runs out. The editor keeps running while it waits, \
+                 and the edits are applied to the buffer the action came from as a single undo step."
+            }
+        }
+    }
+
+    fn examples() -> Vec<Self> {
+        vec![
+            EditorMessage::BufferOpened { path: "/home/user/notes.md".to_string() },
+            EditorMessage::BufferSaved { path: "/home/user/notes.md".to_string() },
+            EditorMessage::Action {
+                name: "word_frequency".to_string(),
+                path: Some("/home/user/notes.md".to_string()),
+                cursor: (0, 1),
+                lines: vec!["# Notes".to_string(), "some notes".to_string()],
+            },
+        ]
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum StatusLevel {
+    #[default]
+    Info,
+    Warn,
+    Error,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PluginMessage {
+    ReplaceLines { start: usize, end: usize, lines: Vec<String> },
+    Status { text: String, #[serde(default)] level: StatusLevel },
+    Done,
+}
+
+impl PluginMessage {
+    fn description(&self) -> &'static str {
+        match self {
+            PluginMessage::ReplaceLines { .. } => {
+                "Replaces lines `start..end` (zero-based, end exclusive) of the buffer the action came from with `lines`. \
+                 `start == end` inserts without removing anything. Only accepted in the reply to an `action`, and refused \
+                 when that buffer has been closed or is read-only or private."
+            }
+            PluginMessage::Status { .. } => "Shows a message on the status line. `level` is `info` (default), `warn` or `error`.",
+            PluginMessage::Done => "Ends the reply to an `action` message.",
+        }
+    }
+
+    fn examples() -> Vec<Self> {
+        vec![
+            PluginMessage::ReplaceLines { start: 0, end: 1, lines: vec!["# Meeting notes".to_string()] },
+            PluginMessage::Status { text: "notes: 3, some: 1".to_string(), level: StatusLevel::Info },
+            PluginMessage::Done,
+        ]
+    }
+}
+
+fn document<T: Serialize>(output: &mut String, message: &T, description: &str) {
+    let json = serde_json::to_value(message).unwrap_or_default();
+    let name = json["type"].as_str().unwrap_or_default();
+    let example = serde_json::to_string(message).unwrap_or_default();
+    output.push_str(&format!("### `{}`\n\n{}\n\n```json\n{}\n```\n\n", name, description, example));
+}
+
+pub fn plugin_protocol_docs() -> String {
+    let mut output = String::from(
+        "# Plugin protocol\n\n\
+         Plugins are external programs listed under `[[plugins]]` in `settings.toml`. phantom starts them with \
+         the editor and talks to them over stdin/stdout, one JSON object per line. Every message has a `type` field. \
+         Plugins that exit, stop responding or print invalid JSON are reported on the status line and never block the editor.\n\n\
+         This file is generated by `phantom --plugin-protocol`.\n\n\
+         ## Editor to plugin\n\n",
+    );
+    for message in EditorMessage::examples() {
+        document(&mut output, &message, message.description());
+    }
+    output.push_str("## Plugin to editor\n\n");
+    for message in PluginMessage::examples() {
+        document(&mut output, &message, message.description());
+    }
+    output.truncate(output.trim_end().len());
+    output.push('\n');
+    output
+}
+
+pub(crate) struct PluginRequest {
+    action: String,
+    tab: usize,
+    deadline: Instant,
+    replies: Vec<Result<PluginMessage, String>>,
+    _progress: Progress,
+}
+
+pub(crate) struct Plugin {
+    pub(crate) config: PluginConfig,
+    child: Child,
+    outgoing: Sender<String>,
+    incoming: Receiver<Result<PluginMessage, String>>,
+    pub(crate) alive: bool,
+    pub(crate) stale_replies: usize,
+    pub(crate) pending: Option<PluginRequest>,
+}
+
+impl Plugin {
+    pub(crate) fn spawn(config: PluginConfig) -> Result<Self, String> {
+        let (program, args) = config.command.split_first().ok_or("No command given")?;
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| e.to_string())?;
+        let mut stdin = child.stdin.take().ok_or("No stdin")?;
+        let stdout = child.stdout.take().ok_or("No stdout")?;
+
+        let (outgoing, outgoing_rx) = mpsc::channel::<String>();
+        thread::spawn(move || {
+            for line in outgoing_rx {
+                if writeln!(stdin, "{}", line).and_then(|_| stdin.flush()).is_err() {
+                    break;
+                }
+            }
+        });
+        let (incoming_tx, incoming) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let message = serde_json::from_str(&line).map_err(|e| format!("invalid message: {}", e));
+                if incoming_tx.send(message).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Plugin { config, child, outgoing, incoming, alive: true, stale_replies: 0, pending: None })
+    }
+
+    pub(crate) fn send(&mut self, message: &EditorMessage) -> bool {
+        let Ok(line) = serde_json::to_string(message) else {
+            return self.alive;
+        };
+        if self.outgoing.send(line).is_err() {
+            self.alive = false;
+        }
+        self.alive
+    }
+
+    pub(crate) fn try_recv(&mut self) -> Option<Result<PluginMessage, String>> {
+        match self.incoming.try_recv() {
+            Ok(message) => Some(message),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => {
+                self.alive = false;
+                None
+            }
+        }
+    }
+}
+
+impl Drop for Plugin {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+impl Editor {
+    pub(crate) fn start_plugins(&mut self) {
+        for config in self.settings.plugins.clone() {
+            let name = config.name.clone();
+            match Plugin::spawn(config) {
+                Ok(plugin) => self.plugins.push(plugin),
+                Err(e) => self.warn(format!("Plugin {} failed to start: {}", name, e)),
+            }
+        }
+    }
+
+    pub(crate) fn notify_plugins(&mut self, message: EditorMessage) {
+        for plugin in &mut self.plugins {
+            if plugin.alive {
+                plugin.send(&message);
+            }
+        }
+    }
+
+    pub(crate) fn report_dead_plugins(&mut self) {
+        let mut dead = Vec::new();
+        self.plugins.retain(|plugin| {
+            if !plugin.alive {
+                dead.push(plugin.config.name.clone());
+            }
+            plugin.alive
+        });
+        for name in dead {
+            self.warn(format!("Plugin {} exited", name));
+        }
+    }
+
+    pub fn plugins_busy(&self) -> bool {
+        self.plugins.iter().any(|plugin| plugin.pending.is_some())
+    }
+
+    pub fn poll_plugins(&mut self) -> bool {
+        let now = Instant::now();
+        let (mut received, mut answered, mut expired) = (Vec::new(), Vec::new(), Vec::new());
+        for (index, plugin) in self.plugins.iter_mut().enumerate() {
+            while let Some(message) = plugin.try_recv() {
+                match (message, plugin.pending.as_mut()) {
+                    (Ok(PluginMessage::Done), _) if plugin.stale_replies > 0 => plugin.stale_replies -= 1,
+                    (Ok(PluginMessage::ReplaceLines { .. }), _) if plugin.stale_replies > 0 => {}
+                    (Ok(PluginMessage::Done), Some(_)) => answered.extend(plugin.pending.take().map(|request| (index, request))),
+                    (message, Some(request)) => request.replies.push(message),
+                    (message, None) => received.push((index, message)),
+                }
+            }
+            if plugin.pending.as_ref().is_some_and(|request| !plugin.alive || request.deadline <= now) {
+                expired.extend(plugin.pending.take().filter(|_| plugin.alive).map(|request| (index, request.action)));
+                plugin.stale_replies += usize::from(plugin.alive);
+            }
+        }
+        let changed = !received.is_empty() || !answered.is_empty() || !expired.is_empty() || self.plugins.iter().any(|plugin| !plugin.alive);
+        for (index, message) in received {
+            self.apply_plugin_message(index, None, message);
+        }
+        for (index, request) in answered {
+            self.begin_undo_group();
+            for reply in request.replies {
+                self.apply_plugin_message(index, Some(request.tab), reply);
+            }
+            self.end_undo_group();
+        }
+        for (index, action) in expired {
+            let name = self.plugins[index].config.name.clone();
+            self.error(format!("Plugin {} did not answer {} in time", name, action));
+        }
+        self.report_dead_plugins();
+        changed
+    }
+
+    pub(crate) fn apply_plugin_message(&mut self, index: usize, tab: Option<usize>, message: Result<PluginMessage, String>) {
+        let name = self.plugins[index].config.name.clone();
+        match message {
+            Ok(PluginMessage::ReplaceLines { start, end, lines }) => {
+                let Some(tab) = tab else {
+                    self.error(format!("Plugin {} sent an edit outside the reply to an action", name));
+                    return;
+                };
+                let Some(tab_index) = self.tab_index(tab) else {
+                    self.error(format!("Plugin {} answered for a tab that has been closed", name));
+                    return;
+                };
+                let tab = &self.tabs[tab_index];
+                if tab.read_only || tab.private {
+                    let kind = if tab.private { "private" } else { "read-only" };
+                    self.error(format!("Plugin {} can't edit a {} buffer", name, kind));
+                    return;
+                }
+                if start > end || end > tab.content.len() {
+                    self.error(format!("Plugin {} sent an invalid range {}..{}", name, start, end));
+                    return;
+                }
+                self.save_tab_state(tab_index);
+                let tab = &mut self.tabs[tab_index];
+                let inserted = lines.len();
+                tab.content.splice(start..end, lines);
+                tab.shift_lines(start, end - start, inserted);
+                if tab.content.is_empty() {
+                    tab.content.push(String::new());
+                }
+                let (x, y) = tab.cursor_position;
+                tab.set_cursor(x, y);
+                if tab_index == self.active_tab {
+                    self.ensure_cursor_visible();
+                }
+            }
+            Ok(PluginMessage::Status { text, level }) => {
+                let level = match level {
+                    StatusLevel::Info => MessageLevel::Info,
+                    StatusLevel::Warn => MessageLevel::Warn,
+                    StatusLevel::Error => MessageLevel::Error,
+                };
+                self.show_message(level, format!("{}: {}", name, text));
+            }
+            Ok(PluginMessage::Done) => {}
+            Err(e) => self.error(format!("Plugin {}: {}", name, e)),
+        }
+    }
+
+    pub(crate) fn run_plugin_action(&mut self, action: &str) -> bool {
+        let Some(index) = self.plugins.iter().position(|plugin| plugin.config.actions.iter().any(|a| a == action)) else {
+            return false;
+        };
+        let tab = &self.tabs[self.active_tab];
+        if tab.private {
+            self.error(format!("{} is not sent to plugins from a private tab", action));
+            return true;
+        }
+        if let Some(request) = &self.plugins[index].pending {
+            let message = format!("Plugin {} is still answering {}", self.plugins[index].config.name, request.action);
+            self.warn(message);
+            return true;
+        }
+        let message = EditorMessage::Action {
+            name: action.to_string(),
+            path: tab.current_file.clone(),
+            cursor: tab.cursor_position,
+            lines: tab.content.clone(),
+        };
+        let tab = tab.id;
+        let progress = self.start_task(format!("Running {}", action), false);
+        let plugin = &mut self.plugins[index];
+        if plugin.send(&message) {
+            plugin.pending = Some(PluginRequest {
+                action: action.to_string(),
+                tab,
+                deadline: Instant::now() + Duration::from_millis(plugin.config.timeout_ms),
+                replies: Vec::new(),
+                _progress: progress,
+            });
+        }
+        self.report_dead_plugins();
+        true
+    }
+}
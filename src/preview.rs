@@ -0,0 +1,154 @@
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use syntect::easy::HighlightLines;
+use syntect::parsing::SyntaxSet;
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    text::{Span, Spans},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+use crate::editor::Editor;
+use crate::filetype::detect_syntax;
+use crate::ui::{render_line, WhitespaceMarks};
+
+pub(crate) const PREVIEW_LINES: usize = 40;
+
+pub(crate) const PREVIEW_MAX_BYTES: u64 = 64 * 1024;
+
+pub(crate) const PREVIEW_DELAY: Duration = Duration::from_millis(300);
+
+pub(crate) enum PreviewContent {
+    File { lines: Vec<String>, syntax: String },
+    Binary(u64),
+    Directory { entries: usize, size: u64 },
+    Error(String),
+}
+
+pub(crate) struct SidebarPreview {
+    pub(crate) path: PathBuf,
+    pub(crate) content: PreviewContent,
+}
+
+pub(crate) fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} bytes", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+fn read_preview(path: &Path, ps: &SyntaxSet) -> PreviewContent {
+    if path.is_dir() {
+        return match fs::read_dir(path) {
+            Ok(entries) => {
+                let (mut count, mut size) = (0, 0);
+                for entry in entries.filter_map(|entry| entry.ok()) {
+                    count += 1;
+                    size += entry.metadata().ok().filter(|metadata| metadata.is_file()).map_or(0, |metadata| metadata.len());
+                }
+                PreviewContent::Directory { entries: count, size }
+            }
+            Err(e) => PreviewContent::Error(e.to_string()),
+        };
+    }
+    let mut bytes = Vec::new();
+    let read = File::open(path).and_then(|file| file.take(PREVIEW_MAX_BYTES).read_to_end(&mut bytes));
+    if let Err(e) = read {
+        return PreviewContent::Error(e.to_string());
+    }
+    if bytes.contains(&0) {
+        return PreviewContent::Binary(fs::metadata(path).map_or(bytes.len() as u64, |metadata| metadata.len()));
+    }
+    let text = String::from_utf8_lossy(&bytes);
+    let lines: Vec<String> = text.lines().take(PREVIEW_LINES).map(str::to_string).collect();
+    let first_line = lines.first().map_or("", String::as_str);
+    let syntax = detect_syntax(ps, path, first_line).unwrap_or_else(|| ps.find_syntax_plain_text());
+    PreviewContent::File { lines, syntax: syntax.name.clone() }
+}
+
+impl Editor {
+    fn sidebar_selection(&self) -> Option<PathBuf> {
+        let file_selector = self.file_selector.as_ref().filter(|_| self.show_sidebar)?;
+        file_selector.entries.get(file_selector.selected_index).cloned()
+    }
+
+    pub(crate) fn preview_sidebar_selection(&mut self) {
+        let Some(path) = self.sidebar_selection() else {
+            return;
+        };
+        let content = read_preview(&path, &self.ps);
+        self.sidebar_preview = Some(SidebarPreview { path: path.clone(), content });
+        self.preview_target = Some((path, Instant::now()));
+    }
+
+    pub fn poll_sidebar_preview(&mut self) -> bool {
+        let Some(selected) = self.sidebar_selection() else {
+            self.preview_target = None;
+            return self.sidebar_preview.take().is_some();
+        };
+        match &self.preview_target {
+            Some((target, since)) if *target == selected => {
+                if since.elapsed() < PREVIEW_DELAY || self.sidebar_preview.as_ref().is_some_and(|preview| preview.path == selected) {
+                    return false;
+                }
+                self.preview_sidebar_selection();
+                true
+            }
+            _ => {
+                self.preview_target = Some((selected, Instant::now()));
+                false
+            }
+        }
+    }
+
+    pub(crate) fn close_sidebar_preview(&mut self) {
+        self.sidebar_preview = None;
+        self.preview_target = None;
+    }
+
+    pub(crate) fn render_sidebar_preview<B: Backend>(&self, f: &mut Frame<B>, area: Rect) {
+        let Some(preview) = &self.sidebar_preview else {
+            return;
+        };
+        let name = preview.path.file_name().map_or_else(|| preview.path.to_string_lossy(), |name| name.to_string_lossy());
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(format!("Preview: {}", name))
+            .border_style(self.color_config.fg(&self.color_config.file_selector_border));
+        let width = area.width.saturating_sub(2) as usize;
+        let info = self.color_config.fg(&self.color_config.comment);
+        let text: Vec<Spans> = match &preview.content {
+            PreviewContent::File { lines, syntax } => {
+                let syntax = self.ps.find_syntax_by_name(syntax).unwrap_or_else(|| self.ps.find_syntax_plain_text());
+                let mut h = HighlightLines::new(syntax, self.syntax_theme());
+                let tab_width = self.option("tabstop").number();
+                lines.iter()
+                    .take(area.height.saturating_sub(2) as usize)
+                    .map(|line| Spans::from(render_line(&self.highlight_ranges(&mut h, line), 0, width, &[], None, tab_width, &WhitespaceMarks::default())))
+                    .collect()
+            }
+            PreviewContent::Binary(size) => vec![Spans::from(Span::styled(format!("Binary file, {}", format_size(*size)), info))],
+            PreviewContent::Directory { entries, size } => vec![
+                Spans::from(Span::styled(format!("{} {}", entries, if *entries == 1 { "entry" } else { "entries" }), info)),
+                Spans::from(Span::styled(format!("{} in files", format_size(*size)), info)),
+            ],
+            PreviewContent::Error(e) => vec![Spans::from(Span::styled(e.clone(), self.color_config.fg(&self.color_config.message_error)))],
+        };
+        let paragraph = Paragraph::new(text)
+            .block(block)
+            .style(self.color_config.bg(&self.color_config.background));
+        f.render_widget(paragraph, area);
+    }
+}
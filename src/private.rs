@@ -0,0 +1,166 @@
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::ptr;
+use std::sync::atomic::{compiler_fence, Ordering};
+
+use crate::editor::Editor;
+use crate::filetype::detect_syntax;
+use crate::save::{shell_quote, write_through_command};
+
+pub(crate) enum PrivateFilter {
+    Decrypt(PathBuf),
+    Encrypt { tab: usize, path: PathBuf, quit: bool },
+}
+
+pub(crate) fn wipe(text: &mut String) {
+    // SAFETY: the bytes are cleared before anything is written, so the string is never left holding invalid UTF-8.
+    let bytes = unsafe { text.as_mut_vec() };
+    bytes.clear();
+    for byte in bytes.spare_capacity_mut() {
+        // SAFETY: `byte` is inside the vector's own allocation, which stays alive and unaliased for the loop.
+        unsafe { ptr::write_volatile(byte.as_mut_ptr(), 0) };
+    }
+    compiler_fence(Ordering::SeqCst);
+}
+
+fn read_from_command(command: &str) -> Result<String, String> {
+    let mut shell = if cfg!(windows) { Command::new("cmd") } else { Command::new("sh") };
+    let output = shell.arg(if cfg!(windows) { "/C" } else { "-c" }).arg(command)
+        .stdin(Stdio::inherit()).stdout(Stdio::piped()).stderr(Stdio::piped())
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", command, e))?;
+    if output.status.success() {
+        return String::from_utf8(output.stdout).map_err(|_| "the decrypted file is not UTF-8 text".to_string());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    Err(match stderr.lines().rev().map(str::trim).find(|line| !line.is_empty()) {
+        Some(reason) => reason.to_string(),
+        None => format!("{} failed ({})", command, output.status),
+    })
+}
+
+fn syntax_path(path: &Path) -> &Path {
+    match path.file_stem().map(Path::new) {
+        Some(stem) if stem.extension().is_some() => stem,
+        _ => path,
+    }
+}
+
+impl Editor {
+    pub(crate) fn private_command(&mut self, argument: &str) {
+        if argument.is_empty() {
+            self.new_tab();
+            self.tabs[self.active_tab].private = true;
+            self.update_current_tab_info();
+            self.info("Private tab: its text stays in memory and yanks stay inside phantom");
+            return;
+        }
+        let path = match self.resolve_command_path(argument) {
+            Ok(path) => Self::absolute_path(&path),
+            Err(e) => {
+                self.error(e);
+                return;
+            }
+        };
+        let file = path.to_string_lossy().into_owned();
+        if let Some(open) = self.tabs.iter().position(|tab| tab.current_file.as_deref() == Some(file.as_str())) {
+            self.set_active_tab(open);
+            self.update_current_tab_info();
+            if self.tabs[open].private {
+                self.info(format!("{} is already open", self.display_path(&file)));
+            } else {
+                self.warn(format!("{} is already open in a tab that is not private", self.display_path(&file)));
+            }
+            return;
+        }
+        if !path.exists() {
+            self.open_private(&path, String::new());
+            self.info(format!("New private file {}", self.display_path(&file)));
+        } else if self.settings.private_decrypt_command.trim().is_empty() {
+            self.error("Set private_decrypt_command to open files in a private tab");
+        } else {
+            self.private_filter = Some(PrivateFilter::Decrypt(path));
+        }
+    }
+
+    fn open_private(&mut self, path: &Path, mut text: String) {
+        self.new_tab();
+        let tab = &mut self.tabs[self.active_tab];
+        tab.content = text.lines().map(String::from).collect();
+        if tab.content.is_empty() {
+            tab.content.push(String::new());
+        }
+        tab.crlf = text.contains("\r\n");
+        tab.eol = text.is_empty() || text.ends_with('\n');
+        wipe(&mut text);
+        tab.syntax = detect_syntax(&self.ps, syntax_path(path), &tab.content[0]).map_or_else(|| "Plain Text".to_string(), |syntax| syntax.name.clone());
+        tab.current_file = Some(path.to_string_lossy().into_owned());
+        tab.saved_hash = tab.content_hash(&tab.content);
+        tab.private = true;
+        self.update_current_tab_info();
+    }
+
+    pub(crate) fn save_private(&mut self, path: &Path, quit: bool) {
+        if self.settings.private_encrypt_command.trim().is_empty() {
+            self.error("Set private_encrypt_command to save private tabs");
+            return;
+        }
+        self.private_filter = Some(PrivateFilter::Encrypt { tab: self.tabs[self.active_tab].id, path: Self::absolute_path(path), quit });
+    }
+
+    pub fn private_filter_target(&self) -> Option<String> {
+        self.private_filter.as_ref().map(|filter| match filter {
+            PrivateFilter::Decrypt(path) => format!("decrypting {}", self.display_path(&path.to_string_lossy())),
+            PrivateFilter::Encrypt { path, .. } => format!("encrypting {}", self.display_path(&path.to_string_lossy())),
+        })
+    }
+
+    pub fn run_private_filter(&mut self) -> bool {
+        match self.private_filter.take() {
+            Some(PrivateFilter::Decrypt(path)) => {
+                let command = self.settings.private_decrypt_command.trim().replace('%', &shell_quote(&path));
+                match read_from_command(&command) {
+                    Ok(text) => {
+                        self.open_private(&path, text);
+                        self.info(format!("Decrypted {}", self.display_path(&path.to_string_lossy())));
+                    }
+                    Err(e) => self.error(format!("Decrypt failed: {}", e)),
+                }
+                false
+            }
+            Some(PrivateFilter::Encrypt { tab, path, quit }) => {
+                let Some(tab_index) = self.tab_index(tab) else {
+                    self.error(format!("Save failed: the tab for {} has been closed", self.display_path(&path.to_string_lossy())));
+                    return false;
+                };
+                let tab = &mut self.tabs[tab_index];
+                let command = self.settings.private_encrypt_command.trim().replace('%', &shell_quote(&path));
+                if let Err(e) = write_through_command(&command, &tab.content, tab.file_format()) {
+                    self.error(format!("Save failed: {}", e));
+                    return false;
+                }
+                tab.saved_hash = tab.content_hash(&tab.content);
+                tab.modified = false;
+                tab.modified_checked = true;
+                tab.current_file = Some(path.to_string_lossy().into_owned());
+                if tab_index == self.active_tab {
+                    self.current_file = tab.current_file.clone();
+                }
+                self.info(format!("Encrypted {}", self.display_path(&path.to_string_lossy())));
+                quit && tab_index == self.active_tab && self.quit_tab()
+            }
+            None => false,
+        }
+    }
+
+    pub(crate) fn wipe_private_tab(&mut self, tab_index: usize) {
+        let tab = &mut self.tabs[tab_index];
+        let history = tab.undo_stack.iter_mut().chain(tab.redo_stack.iter_mut()).flat_map(|operation| operation.content.iter_mut());
+        for line in tab.content.iter_mut().chain(history) {
+            wipe(line);
+        }
+        if tab_index == self.active_tab {
+            self.content.iter_mut().for_each(wipe);
+        }
+    }
+}
@@ -0,0 +1,437 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+use crossterm::event::{KeyCode, KeyEvent};
+use tui::backend::Backend;
+use tui::layout::Rect;
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, Borders, Clear, Paragraph};
+use tui::Frame;
+
+use crate::config::ColorConfig;
+use crate::editor::Editor;
+use crate::ignore::IgnoreRules;
+use crate::save::{write_atomic, FileFormat};
+use crate::search::SearchPattern;
+use crate::tasks::Progress;
+use crate::ui::{centered_rect, InputAction};
+
+const BINARY_SNIFF: usize = 8192;
+const PREVIEW_WIDTH: usize = 200;
+
+pub(crate) struct RenameMatch {
+    line: usize,
+    column: usize,
+    preview: String,
+    checked: bool,
+}
+
+pub(crate) struct RenameFile {
+    path: PathBuf,
+    label: String,
+    matches: Vec<RenameMatch>,
+}
+
+impl RenameFile {
+    fn checked(&self) -> usize {
+        self.matches.iter().filter(|found| found.checked).count()
+    }
+}
+
+struct RenameSearch {
+    files: Vec<RenameFile>,
+    binary: usize,
+    long_lines: usize,
+}
+
+pub(crate) struct RenameReview {
+    word: String,
+    root: PathBuf,
+    files: Vec<RenameFile>,
+    binary: usize,
+    long_lines: usize,
+    receiver: Option<Receiver<RenameSearch>>,
+    current: usize,
+    scroll: usize,
+    height: usize,
+    write_files: bool,
+}
+
+fn replace_matches(line: &mut String, columns: &[usize], word: &SearchPattern, replacement: &str) -> (usize, usize) {
+    let found: Vec<Range<usize>> = word.matches(line).collect();
+    let (mut replaced, mut stale) = (0, 0);
+    for &column in columns.iter().rev() {
+        if let Some(span) = found.iter().find(|span| span.start == column) {
+            line.replace_range(span.clone(), replacement);
+            replaced += 1;
+        } else {
+            stale += 1;
+        }
+    }
+    (replaced, stale)
+}
+
+fn preview(line: &str) -> String {
+    line.trim().chars().take(PREVIEW_WIDTH).collect()
+}
+
+fn search_files(word: &SearchPattern, root: &Path, rules: IgnoreRules, open: &HashMap<PathBuf, Vec<String>>, long_line: usize, progress: &Progress) -> Option<RenameSearch> {
+    let mut search = RenameSearch { files: Vec::new(), binary: 0, long_lines: 0 };
+    let paths = rules.walk(root);
+    for (index, path) in paths.iter().enumerate() {
+        if progress.cancelled() {
+            return None;
+        }
+        progress.report(index as u64, Some(paths.len() as u64));
+        let read;
+        let lines: Vec<&str> = match open.get(path) {
+            Some(lines) => lines.iter().map(String::as_str).collect(),
+            None => {
+                let Ok(bytes) = fs::read(path) else {
+                    continue;
+                };
+                if bytes[..bytes.len().min(BINARY_SNIFF)].contains(&0) {
+                    search.binary += 1;
+                    continue;
+                }
+                let Ok(text) = String::from_utf8(bytes) else {
+                    search.binary += 1;
+                    continue;
+                };
+                read = text;
+                read.lines().collect()
+            }
+        };
+        let mut matches = Vec::new();
+        for (line_number, line) in lines.iter().enumerate() {
+            if line.len() > long_line {
+                search.long_lines += usize::from(word.find_at(line, 0).is_some());
+                continue;
+            }
+            matches.extend(word.matches(line).map(|found| RenameMatch { line: line_number, column: found.start, preview: preview(line), checked: true }));
+        }
+        if !matches.is_empty() {
+            search.files.push(RenameFile { path: path.clone(), label: String::new(), matches });
+        }
+    }
+    Some(search)
+}
+
+impl RenameReview {
+    fn rows(&self) -> Vec<(usize, Option<usize>)> {
+        self.files.iter()
+            .enumerate()
+            .flat_map(|(file, found)| std::iter::once((file, None)).chain((0..found.matches.len()).map(move |index| (file, Some(index)))))
+            .collect()
+    }
+
+    fn select(&mut self, index: usize) {
+        self.current = index.min(self.rows().len().saturating_sub(1));
+        if self.current < self.scroll {
+            self.scroll = self.current;
+        } else if self.current >= self.scroll + self.height {
+            self.scroll = self.current + 1 - self.height;
+        }
+    }
+
+    fn toggle(&mut self) {
+        let Some(&(file, index)) = self.rows().get(self.current) else {
+            return;
+        };
+        let file = &mut self.files[file];
+        match index {
+            Some(index) => file.matches[index].checked = !file.matches[index].checked,
+            None => {
+                let checked = file.checked() < file.matches.len();
+                for found in &mut file.matches {
+                    found.checked = checked;
+                }
+            }
+        }
+    }
+
+    fn counts(&self) -> (usize, usize, usize) {
+        let total = self.files.iter().map(|file| file.matches.len()).sum();
+        let checked = self.files.iter().map(RenameFile::checked).sum();
+        let files = self.files.iter().filter(|file| file.checked() > 0).count();
+        (checked, total, files)
+    }
+
+    fn skipped(&self) -> String {
+        let mut skipped = Vec::new();
+        if self.binary > 0 {
+            skipped.push(format!("{} binary file{}", self.binary, if self.binary == 1 { "" } else { "s" }));
+        }
+        if self.long_lines > 0 {
+            skipped.push(format!("{} long line{}", self.long_lines, if self.long_lines == 1 { "" } else { "s" }));
+        }
+        if skipped.is_empty() {
+            String::new()
+        } else {
+            format!(", skipped {}", skipped.join(" and "))
+        }
+    }
+
+    pub(crate) fn render<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect, color_config: &ColorConfig) {
+        let area = centered_rect(area, (area.width * 3 / 4).max(20), (area.height * 3 / 4).max(5));
+        f.render_widget(Clear, area);
+        self.height = (area.height.saturating_sub(3) as usize).max(1);
+        self.select(self.current);
+
+        let style = color_config.bg(&color_config.pager_background).patch(color_config.fg(&color_config.foreground));
+        let current_style = color_config.highlight(&color_config.cursor_line);
+        let file_style = color_config.fg(&color_config.keyword);
+        let hint_style = color_config.fg(&color_config.comment);
+        let width = area.width.saturating_sub(2) as usize;
+        let rows = self.rows();
+        let mut text: Vec<Spans> = rows.iter()
+            .enumerate()
+            .skip(self.scroll)
+            .take(self.height)
+            .map(|(row, &(file, index))| {
+                let file = &self.files[file];
+                let (line, style) = match index {
+                    Some(index) => {
+                        let found = &file.matches[index];
+                        let mark = if found.checked { "x" } else { " " };
+                        (format!("    [{}] {}:{}  {}", mark, found.line + 1, found.column + 1, found.preview), Default::default())
+                    }
+                    None => {
+                        let mark = match file.checked() {
+                            0 => " ",
+                            checked if checked == file.matches.len() => "x",
+                            _ => "-",
+                        };
+                        (format!("[{}] {} ({})", mark, file.label, file.matches.len()), file_style)
+                    }
+                };
+                let style = if row == self.current { style.patch(current_style) } else { style };
+                Spans::from(Span::styled(format!("{:<width$}", line, width = width), style))
+            })
+            .collect();
+        text.resize(self.height, Spans::default());
+        let unopened = if self.write_files { "write to disk" } else { "open in tabs" };
+        text.push(Spans::from(Span::styled(format!("Space toggle  w unopened files: {}  Enter rename  Esc cancel", unopened), hint_style)));
+
+        let title = match &self.receiver {
+            Some(_) => format!("Rename {} (searching…)", self.word),
+            None => {
+                let (checked, total, files) = self.counts();
+                format!("Rename {}: {}/{} matches in {} file{}{}", self.word, checked, total, files, if files == 1 { "" } else { "s" }, self.skipped())
+            }
+        };
+        let paragraph = Paragraph::new(text)
+            .block(Block::default().title(title).borders(Borders::ALL).border_style(color_config.fg(&color_config.pager_border)))
+            .style(style);
+        f.render_widget(paragraph, area);
+    }
+}
+
+impl Editor {
+    pub(crate) fn rename_word(&mut self) {
+        let Some(word) = self.word_under_cursor() else {
+            self.warn("No word under cursor");
+            return;
+        };
+        let dir = self.tabs[self.active_tab].current_file.as_ref()
+            .and_then(|file| Path::new(file).parent().map(Path::to_path_buf))
+            .or_else(|| env::current_dir().ok())
+            .unwrap_or_else(|| PathBuf::from("."));
+        let global_ignore = self.paths.config_dir.as_ref().map(|dir| dir.join("ignore"));
+        let rules = IgnoreRules::for_dir(&dir, global_ignore.as_deref());
+        let root = rules.root.clone();
+        let search_root = root.clone();
+        let open: HashMap<PathBuf, Vec<String>> = self.tabs.iter()
+            .filter_map(|tab| tab.current_file.as_ref().map(|file| (PathBuf::from(Self::canonical_path(Path::new(file))), tab.content.clone())))
+            .collect();
+        let long_line = self.settings.long_line_threshold;
+        let (sender, receiver) = mpsc::channel();
+        let progress = self.start_task(format!("Searching for {}", word), true);
+        let search_word = SearchPattern::word(&word);
+        thread::spawn(move || {
+            if let Some(search) = search_files(&search_word, &search_root, rules, &open, long_line, &progress) {
+                let _ = sender.send(search);
+            }
+        });
+        self.rename = Some(RenameReview {
+            word,
+            root,
+            files: Vec::new(),
+            binary: 0,
+            long_lines: 0,
+            receiver: Some(receiver),
+            current: 0,
+            scroll: 0,
+            height: 1,
+            write_files: false,
+        });
+    }
+
+    pub fn poll_rename(&mut self) -> bool {
+        let Some(receiver) = self.rename.as_ref().and_then(|rename| rename.receiver.as_ref()) else {
+            return false;
+        };
+        let search = match receiver.try_recv() {
+            Ok(search) => search,
+            Err(TryRecvError::Empty) => return false,
+            Err(TryRecvError::Disconnected) => {
+                self.rename = None;
+                return true;
+            }
+        };
+        let Some(rename) = &mut self.rename else {
+            return false;
+        };
+        rename.receiver = None;
+        rename.files = search.files;
+        for file in &mut rename.files {
+            file.label = file.path.strip_prefix(&rename.root).unwrap_or(&file.path).to_string_lossy().into_owned();
+        }
+        rename.binary = search.binary;
+        rename.long_lines = search.long_lines;
+        if rename.files.is_empty() {
+            let message = format!("No matches for {}{}", rename.word, rename.skipped());
+            self.rename = None;
+            self.info(message);
+        }
+        true
+    }
+
+    pub(crate) fn handle_rename_key(&mut self, key: KeyEvent) {
+        let Some(rename) = &mut self.rename else {
+            return;
+        };
+        if key.code == KeyCode::Esc || key.code == KeyCode::Char('q') {
+            self.rename = None;
+            self.info("Rename cancelled");
+            return;
+        }
+        if rename.receiver.is_some() {
+            return;
+        }
+        let page = rename.height;
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down => rename.select(rename.current + 1),
+            KeyCode::Char('k') | KeyCode::Up => rename.select(rename.current.saturating_sub(1)),
+            KeyCode::PageDown => rename.select(rename.current + page),
+            KeyCode::PageUp => rename.select(rename.current.saturating_sub(page)),
+            KeyCode::Char('g') | KeyCode::Home => rename.select(0),
+            KeyCode::Char('G') | KeyCode::End => rename.select(usize::MAX),
+            KeyCode::Char(' ') => rename.toggle(),
+            KeyCode::Char('w') => rename.write_files = !rename.write_files,
+            KeyCode::Enter if rename.counts().0 == 0 => self.warn("No matches selected"),
+            KeyCode::Enter => {
+                let word = rename.word.clone();
+                self.input(InputAction::RenameWord, &format!("Rename {} to", word), &word);
+            }
+            _ => {}
+        }
+    }
+
+    pub(crate) fn apply_rename(&mut self, replacement: &str) {
+        let Some(rename) = self.rename.take() else {
+            return;
+        };
+        let active = self.active_tab;
+        let word = SearchPattern::word(&rename.word);
+        let (mut replaced, mut files, mut written, mut stale) = (0, 0, 0, 0);
+        let mut failed = Vec::new();
+        for file in rename.files.iter().filter(|file| file.checked() > 0) {
+            let mut lines: Vec<(usize, Vec<usize>)> = Vec::new();
+            for found in file.matches.iter().filter(|found| found.checked) {
+                match lines.last_mut() {
+                    Some((line, columns)) if *line == found.line => columns.push(found.column),
+                    _ => lines.push((found.line, vec![found.column])),
+                }
+            }
+            let path = file.path.to_string_lossy();
+            let open = self.tabs.iter().position(|tab| tab.current_file.as_deref().is_some_and(|current| Self::canonical_path(Path::new(current)) == path));
+            let (count, skipped) = match open {
+                None if rename.write_files => match Self::rename_in_file(&file.path, &lines, &word, replacement, &self.start_task(format!("Writing {}", file.label), false)) {
+                    Ok(counts) => {
+                        written += 1;
+                        counts
+                    }
+                    Err(e) => {
+                        failed.push(format!("{}: {}", file.label, e));
+                        continue;
+                    }
+                },
+                _ => {
+                    let tab_index = match open {
+                        Some(tab_index) => tab_index,
+                        None => {
+                            if let Err(e) = self.open_file(&file.path) {
+                                failed.push(format!("{}: {}", file.label, e));
+                                continue;
+                            }
+                            self.active_tab
+                        }
+                    };
+                    let tab = &mut self.tabs[tab_index];
+                    if tab.read_only {
+                        failed.push(format!("{}: buffer is read-only", file.label));
+                        continue;
+                    }
+                    tab.push_undo();
+                    let (mut count, mut skipped) = (0, 0);
+                    for (line, columns) in &lines {
+                        let Some(text) = tab.content.get_mut(*line) else {
+                            skipped += columns.len();
+                            continue;
+                        };
+                        let (done, missed) = replace_matches(text, columns, &word, replacement);
+                        count += done;
+                        skipped += missed;
+                    }
+                    tab.cursor_position = tab.clamp_position(tab.cursor_position);
+                    (count, skipped)
+                }
+            };
+            replaced += count;
+            stale += skipped;
+            files += usize::from(count > 0);
+        }
+        self.set_active_tab(active.min(self.tabs.len() - 1));
+        self.trim_all_undo_history();
+
+        let mut message = format!("Renamed {} match{} in {} file{}", replaced, if replaced == 1 { "" } else { "es" }, files, if files == 1 { "" } else { "s" });
+        if written > 0 {
+            message.push_str(&format!(", wrote {} to disk", written));
+        }
+        if stale > 0 {
+            message.push_str(&format!(", skipped {} that changed since the search", stale));
+        }
+        message.push_str(&rename.skipped());
+        if failed.is_empty() {
+            self.info(message);
+        } else {
+            self.error(format!("{}; failed: {}", message, failed.join(", ")));
+        }
+    }
+
+    fn rename_in_file(path: &Path, lines: &[(usize, Vec<usize>)], word: &SearchPattern, replacement: &str, progress: &Progress) -> Result<(usize, usize), String> {
+        let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let mut content: Vec<String> = text.split_inclusive('\n').map(str::to_string).collect();
+        let (mut count, mut skipped) = (0, 0);
+        for (line, columns) in lines {
+            let Some(text) = content.get_mut(*line) else {
+                skipped += columns.len();
+                continue;
+            };
+            let (done, missed) = replace_matches(text, columns, word, replacement);
+            count += done;
+            skipped += missed;
+        }
+        if count > 0 {
+            let format = FileFormat { line_ending: "", bom: false, eol: false };
+            write_atomic(path, &[content.concat()], format, progress).map_err(|e| e.to_string())?;
+        }
+        Ok((count, skipped))
+    }
+}
@@ -0,0 +1,266 @@
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+
+use crate::buffer::Tab;
+use crate::editor::Editor;
+use crate::plugin::EditorMessage;
+use crate::tasks::Progress;
+use crate::ui::{InputAction, PromptAction};
+
+const PROGRESS_LINES: usize = 16384;
+
+pub(crate) struct PendingSave {
+    pub(crate) path: PathBuf,
+    pub(crate) content: Arc<Vec<String>>,
+    pub(crate) receiver: Receiver<io::Result<()>>,
+    pub(crate) queued: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct FileFormat {
+    pub(crate) line_ending: &'static str,
+    pub(crate) bom: bool,
+    pub(crate) eol: bool,
+}
+
+pub(crate) struct PrivilegedWrite {
+    pub(crate) tab_index: usize,
+    pub(crate) path: PathBuf,
+    pub(crate) quit: bool,
+}
+
+fn write_lines(writer: &mut impl Write, lines: &[String], format: FileFormat, progress: Option<&Progress>) -> io::Result<()> {
+    if format.bom {
+        writer.write_all("\u{feff}".as_bytes())?;
+    }
+    for (index, line) in lines.iter().enumerate() {
+        if let Some(progress) = progress.filter(|_| index % PROGRESS_LINES == 0) {
+            progress.report(index as u64, Some(lines.len() as u64));
+        }
+        writer.write_all(line.as_bytes())?;
+        if format.eol || index + 1 < lines.len() {
+            writer.write_all(format.line_ending.as_bytes())?;
+        }
+    }
+    Ok(())
+}
+
+fn write_file(file: fs::File, lines: &[String], format: FileFormat, progress: &Progress) -> io::Result<()> {
+    let mut writer = BufWriter::new(file);
+    write_lines(&mut writer, lines, format, Some(progress))?;
+    writer.into_inner().map_err(|e| e.into_error())?.sync_all()
+}
+
+pub(crate) fn shell_quote(path: &Path) -> String {
+    format!("'{}'", path.to_string_lossy().replace('\'', "'\\''"))
+}
+
+fn rename_loses_identity(metadata: &fs::Metadata) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        // SAFETY: geteuid takes no arguments and cannot fail.
+        metadata.nlink() > 1 || metadata.uid() != unsafe { libc::geteuid() }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = metadata;
+        false
+    }
+}
+
+// Renaming a temporary file over the target keeps only its permission bits.
+// Hard links, the owner and group, ACLs and extended attributes belong to the
+// old inode, so files that would lose a link or an owner are written in place.
+pub(crate) fn write_atomic(path: &Path, lines: &[String], format: FileFormat, progress: &Progress) -> io::Result<()> {
+    let target = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let metadata = fs::metadata(&target).ok();
+    if metadata.as_ref().is_some_and(rename_loses_identity) {
+        return write_file(fs::File::create(&target)?, lines, format, progress);
+    }
+    let name = target.file_name().map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+    let temp = target.with_file_name(format!(".{}.phantom-save-{}", name, process::id()));
+    let Ok(file) = fs::File::create(&temp) else {
+        return write_file(fs::File::create(&target)?, lines, format, progress);
+    };
+    let result = write_file(file, lines, format, progress)
+        .and_then(|_| match metadata {
+            Some(metadata) => fs::set_permissions(&temp, metadata.permissions()),
+            None => Ok(()),
+        })
+        .and_then(|_| fs::rename(&temp, &target));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+pub(crate) fn write_through_command(command: &str, lines: &[String], format: FileFormat) -> Result<(), String> {
+    let mut shell = if cfg!(windows) { Command::new("cmd") } else { Command::new("sh") };
+    let mut child = shell.arg(if cfg!(windows) { "/C" } else { "-c" }).arg(command)
+        .stdin(Stdio::piped()).stdout(Stdio::null()).stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run {}: {}", command, e))?;
+    let written = child.stdin.take().map_or(Ok(()), |stdin| {
+        let mut writer = BufWriter::new(stdin);
+        write_lines(&mut writer, lines, format, None).and_then(|_| writer.flush())
+    });
+    let output = child.wait_with_output().map_err(|e| format!("{}: {}", command, e))?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let reason = stderr.lines().rev().map(str::trim).find(|line| !line.is_empty());
+    match (output.status.success(), written, reason) {
+        (true, Ok(()), _) => Ok(()),
+        (_, _, Some(reason)) => Err(reason.to_string()),
+        (true, Err(e), None) => Err(e.to_string()),
+        (false, _, None) => Err(format!("{} failed ({})", command, output.status)),
+    }
+}
+
+impl Tab {
+    pub(crate) fn file_format(&self) -> FileFormat {
+        FileFormat {
+            line_ending: if self.crlf { "\r\n" } else { "\n" },
+            bom: self.bom,
+            eol: self.eol,
+        }
+    }
+}
+
+impl Editor {
+    pub(crate) fn start_save(&mut self, tab_index: usize, path: PathBuf) {
+        let tab = &mut self.tabs[tab_index];
+        if let Some(save) = &mut tab.save {
+            save.queued = Some(path);
+            return;
+        }
+        let content = Arc::new(tab.content.clone());
+        let lines = Arc::clone(&content);
+        let format = tab.file_format();
+        let target = path.clone();
+        let (sender, receiver) = mpsc::channel();
+        tab.save = Some(PendingSave { path, content, receiver, queued: None });
+        let progress = self.start_task(format!("Saving {}", self.display_path(&target.to_string_lossy())), false);
+        thread::spawn(move || {
+            let _ = sender.send(write_atomic(&target, &lines, format, &progress));
+        });
+    }
+
+    pub(crate) fn file_saved(&mut self, tab_index: usize, path: &Path) {
+        let saved = Self::absolute_path(path).to_string_lossy().into_owned();
+        let renamed = self.tabs[tab_index].current_file.as_deref() != Some(saved.as_str());
+        self.tabs[tab_index].current_file = Some(saved.clone());
+        if renamed {
+            let syntax = self.detect_file_syntax(tab_index).unwrap_or_else(|| "Plain Text".to_string());
+            self.tabs[tab_index].syntax = syntax;
+            if tab_index == self.active_tab {
+                self.syntax = self.tabs[tab_index].syntax.clone();
+            }
+        }
+        self.remember_position(tab_index);
+        self.info(format!("Saved {}", self.display_path(&saved)));
+        self.store_bookmarks(tab_index);
+        self.store_folds(tab_index);
+        self.notify_plugins(EditorMessage::BufferSaved { path: saved });
+    }
+
+    fn finish_save(&mut self, tab_index: usize, result: io::Result<()>) -> io::Result<()> {
+        let Some(save) = self.tabs[tab_index].save.take() else {
+            return Ok(());
+        };
+        if result.is_ok() {
+            self.tabs[tab_index].mark_saved(&save.content);
+            self.file_saved(tab_index, &save.path);
+        }
+        if let Some(path) = save.queued {
+            self.start_save(tab_index, path);
+        }
+        result
+    }
+
+    pub fn saving(&self) -> bool {
+        self.tabs.iter().any(|tab| tab.save.is_some())
+    }
+
+    pub fn poll_saves(&mut self) -> bool {
+        let mut finished = false;
+        for tab_index in 0..self.tabs.len() {
+            let Some(save) = &self.tabs[tab_index].save else {
+                continue;
+            };
+            let result = match save.receiver.try_recv() {
+                Ok(result) => result,
+                Err(TryRecvError::Empty) => continue,
+                Err(TryRecvError::Disconnected) => Err(io::Error::other("save thread exited")),
+            };
+            let path = save.path.clone();
+            if let Err(e) = self.finish_save(tab_index, result) {
+                self.save_failed(tab_index, &path, e, false);
+            }
+            finished = true;
+        }
+        finished
+    }
+
+    pub(crate) fn save_failed(&mut self, tab_index: usize, path: &Path, error: io::Error, quit: bool) {
+        self.error(format!("Save failed: {}", error));
+        if error.kind() != io::ErrorKind::PermissionDenied || tab_index != self.active_tab || self.prompt.is_some() {
+            return;
+        }
+        let path = Self::absolute_path(path);
+        let message = format!("Permission denied writing {}", self.display_path(&path.to_string_lossy()));
+        match self.settings.privileged_write_command.split_whitespace().next().map(str::to_string) {
+            Some(program) => self.confirm(PromptAction::PermissionDenied { path, quit }, &message, &format!("Write with {}", program), "Save elsewhere"),
+            None => self.input(InputAction::SaveAs { quit }, &format!("{}. Save as", message), &path.to_string_lossy()),
+        }
+    }
+
+    pub fn privileged_write_target(&self) -> Option<String> {
+        self.privileged_write.as_ref().map(|write| self.display_path(&write.path.to_string_lossy()))
+    }
+
+    pub fn run_privileged_write(&mut self) -> bool {
+        let Some(write) = self.privileged_write.take() else {
+            return false;
+        };
+        let Some(tab) = self.tabs.get(write.tab_index) else {
+            return false;
+        };
+        let command = self.settings.privileged_write_command.trim().replace('%', &shell_quote(&write.path));
+        let content = tab.content.clone();
+        match write_through_command(&command, &content, tab.file_format()) {
+            Ok(()) => {
+                self.tabs[write.tab_index].mark_saved(&content);
+                self.file_saved(write.tab_index, &write.path);
+                write.quit && self.quit_tab()
+            }
+            Err(e) => {
+                self.error(format!("Save failed: {}", e));
+                false
+            }
+        }
+    }
+
+    pub(crate) fn wait_for_save(&mut self, tab_index: usize) -> io::Result<()> {
+        let mut finished = Ok(());
+        while let Some(save) = &self.tabs[tab_index].save {
+            let result = save.receiver.recv().unwrap_or_else(|_| Err(io::Error::other("save thread exited")));
+            finished = self.finish_save(tab_index, result);
+        }
+        finished
+    }
+
+    pub fn wait_for_saves(&mut self) -> io::Result<()> {
+        let mut result = Ok(());
+        for tab_index in 0..self.tabs.len() {
+            if let Err(e) = self.wait_for_save(tab_index) {
+                result = result.and(Err(e));
+            }
+        }
+        result
+    }
+}
@@ -0,0 +1,230 @@
+use std::ops::Range;
+
+use syntect::parsing::{Regex, Region};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum CaseMode {
+    #[default]
+    Sensitive,
+    Ignore,
+    Smart,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct SearchOptions {
+    pub regex: bool,
+    pub case: CaseMode,
+    pub whole_word: bool,
+}
+
+#[derive(Clone, Debug)]
+enum Matcher {
+    Literal { text: String, ignore_case: bool },
+    Regex(Regex),
+}
+
+#[derive(Clone, Debug)]
+pub struct SearchPattern {
+    matcher: Matcher,
+    whole_word: bool,
+}
+
+#[derive(Debug)]
+pub struct Replaced {
+    pub lines: Vec<String>,
+    pub counts: Vec<usize>,
+}
+
+impl Replaced {
+    pub fn total(&self) -> usize {
+        self.counts.iter().sum()
+    }
+
+    pub fn changed_lines(&self) -> usize {
+        self.counts.iter().filter(|&&count| count > 0).count()
+    }
+}
+
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn literal_match_at(line: &str, start: usize, pattern: &str, ignore_case: bool) -> Option<usize> {
+    let mut chars = line[start..].chars();
+    let mut end = start;
+    for p in pattern.chars() {
+        let c = chars.next()?;
+        let same = if ignore_case { c.to_lowercase().eq(p.to_lowercase()) } else { c == p };
+        if !same {
+            return None;
+        }
+        end += c.len_utf8();
+    }
+    Some(end)
+}
+
+fn next_boundary(line: &str, position: usize) -> Option<usize> {
+    line[position..].chars().next().map(|c| position + c.len_utf8())
+}
+
+impl SearchPattern {
+    pub fn new(pattern: &str, options: SearchOptions) -> Result<Self, String> {
+        let ignore_case = match options.case {
+            CaseMode::Sensitive => false,
+            CaseMode::Ignore => true,
+            CaseMode::Smart => !pattern.chars().any(char::is_uppercase),
+        };
+        let matcher = if options.regex && !pattern.is_empty() {
+            let source = if ignore_case { format!("(?i){}", pattern) } else { pattern.to_string() };
+            if let Some(e) = Regex::try_compile(&source) {
+                return Err(format!("Invalid pattern {}: {}", pattern, e));
+            }
+            Matcher::Regex(Regex::new(source))
+        } else {
+            Matcher::Literal { text: pattern.to_string(), ignore_case }
+        };
+        Ok(SearchPattern { matcher, whole_word: options.whole_word })
+    }
+
+    pub fn literal(pattern: &str, ignore_case: bool) -> Self {
+        SearchPattern { matcher: Matcher::Literal { text: pattern.to_string(), ignore_case }, whole_word: false }
+    }
+
+    pub fn word(word: &str) -> Self {
+        SearchPattern { whole_word: true, ..Self::literal(word, false) }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        matches!(&self.matcher, Matcher::Literal { text, .. } if text.is_empty())
+    }
+
+    fn search(&self, line: &str, from: usize, region: &mut Region) -> Option<Range<usize>> {
+        let mut from = from;
+        loop {
+            let found = match &self.matcher {
+                Matcher::Literal { text, .. } if text.is_empty() => return None,
+                Matcher::Literal { text, ignore_case } => line.get(from..)?
+                    .char_indices()
+                    .map(|(i, _)| from + i)
+                    .find_map(|start| literal_match_at(line, start, text, *ignore_case).map(|end| start..end))?,
+                Matcher::Regex(regex) => {
+                    if from > line.len() || !regex.search(line, from, line.len(), Some(region)) {
+                        return None;
+                    }
+                    let (start, end) = region.pos(0)?;
+                    start..end
+                }
+            };
+            let before = line[..found.start].chars().next_back().is_some_and(is_word);
+            let after = line[found.end..].chars().next().is_some_and(is_word);
+            if !self.whole_word || (!before && !after) {
+                return Some(found);
+            }
+            from = next_boundary(line, found.start)?;
+        }
+    }
+
+    pub fn find_at(&self, line: &str, from: usize) -> Option<Range<usize>> {
+        self.search(line, from, &mut Region::new())
+    }
+
+    pub fn matches<'a>(&'a self, line: &'a str) -> Matches<'a> {
+        Matches { pattern: self, line, from: Some(0), last_end: None, region: Region::new() }
+    }
+
+    pub fn find_in(&self, lines: &[String], limit: usize) -> Vec<(usize, usize)> {
+        let mut results = Vec::new();
+        for (line_number, line) in lines.iter().enumerate() {
+            for found in self.matches(line) {
+                if results.len() == limit {
+                    return results;
+                }
+                results.push((line_number, found.start));
+            }
+        }
+        results
+    }
+
+    fn expand(&self, replacement: &str, line: &str, region: &Region, output: &mut String) {
+        if let Matcher::Literal { .. } = self.matcher {
+            output.push_str(replacement);
+            return;
+        }
+        let mut chars = replacement.chars().peekable();
+        while let Some(c) = chars.next() {
+            match (c, chars.peek().copied()) {
+                ('$', Some('$')) => {
+                    chars.next();
+                    output.push('$');
+                }
+                ('$', Some(digit @ '0'..='9')) => {
+                    chars.next();
+                    let group = digit as usize - '0' as usize;
+                    if let Some((start, end)) = region.pos(group) {
+                        output.push_str(&line[start..end]);
+                    }
+                }
+                _ => output.push(c),
+            }
+        }
+    }
+
+    pub fn replace_line(&self, line: &str, replacement: &str, global: bool) -> Option<(String, usize)> {
+        let mut matches = self.matches(line);
+        let mut result = String::new();
+        let (mut position, mut count) = (0, 0);
+        while let Some(found) = matches.next() {
+            result.push_str(&line[position..found.start]);
+            self.expand(replacement, line, &matches.region, &mut result);
+            position = found.end;
+            count += 1;
+            if !global {
+                break;
+            }
+        }
+        if count == 0 {
+            return None;
+        }
+        result.push_str(&line[position..]);
+        Some((result, count))
+    }
+
+    pub fn replace(&self, lines: &[String], range: Range<usize>, replacement: &str, global: bool) -> Replaced {
+        let (lines, counts) = lines[range].iter()
+            .map(|line| match self.replace_line(line, replacement, global) {
+                Some((replaced, count)) => (replaced, count),
+                None => (line.clone(), 0),
+            })
+            .unzip();
+        Replaced { lines, counts }
+    }
+}
+
+pub struct Matches<'a> {
+    pattern: &'a SearchPattern,
+    line: &'a str,
+    from: Option<usize>,
+    last_end: Option<usize>,
+    region: Region,
+}
+
+impl Iterator for Matches<'_> {
+    type Item = Range<usize>;
+
+    fn next(&mut self) -> Option<Range<usize>> {
+        loop {
+            let found = self.pattern.search(self.line, self.from?, &mut self.region);
+            self.from = match &found {
+                Some(found) if found.is_empty() => next_boundary(self.line, found.end),
+                Some(found) => Some(found.end),
+                None => None,
+            };
+            let found = found?;
+            if found.is_empty() && self.last_end == Some(found.start) {
+                continue;
+            }
+            self.last_end = Some(found.end);
+            return Some(found);
+        }
+    }
+}
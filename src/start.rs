@@ -0,0 +1,194 @@
+use std::env;
+use std::io;
+use std::path::Path;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Rect},
+    style::Modifier,
+    text::{Span, Spans},
+    widgets::{Clear, Paragraph},
+    Frame,
+};
+
+use crate::editor::{Editor, Mode};
+
+pub(crate) const START_RECENT_FILES: usize = 10;
+
+const LOGO: [&str; 5] = [
+    r"       _                 _                  ",
+    r" _ __ | |__   __ _ _ __ | |_ ___  _ __ ___  ",
+    r"| '_ \| '_ \ / _` | '_ \| __/ _ \| '_ ` _ \ ",
+    r"| .__/|_| |_|\__,_|_| |_|\__\___/|_| |_| |_|",
+    r"|_|                                         ",
+];
+
+const CHEATSHEET: [(&str, &str); 6] = [
+    ("enter_insert_mode", "insert text"),
+    ("enter_command_mode", "run a command"),
+    ("save_file", "save"),
+    ("toggle_sidebar", "file sidebar"),
+    ("toggle_terminal", "terminal"),
+    ("close_tab", "close tab"),
+];
+
+#[derive(Clone, PartialEq, Debug)]
+pub(crate) enum StartEntry {
+    Recent(String),
+    Browse,
+    New,
+    Help,
+}
+
+impl StartEntry {
+    fn label(&self) -> String {
+        match self {
+            StartEntry::Recent(path) => match dirs::home_dir().and_then(|home| Path::new(path).strip_prefix(home).ok().map(Path::to_path_buf)) {
+                Some(relative) => format!("~/{}", relative.display()),
+                None => path.clone(),
+            },
+            StartEntry::Browse => "Open file browser".to_string(),
+            StartEntry::New => "New file".to_string(),
+            StartEntry::Help => "Help: actions and key bindings".to_string(),
+        }
+    }
+}
+
+pub(crate) struct StartScreen {
+    pub(crate) entries: Vec<StartEntry>,
+    pub(crate) selected: usize,
+}
+
+impl Editor {
+    pub fn show_start_screen(&mut self) {
+        if !self.settings.show_start_screen || !self.pristine() {
+            return;
+        }
+        if self.recent_files.prune() {
+            self.save_recent_files();
+        }
+        let mut entries: Vec<StartEntry> = self.recent_files.entries.iter()
+            .take(START_RECENT_FILES)
+            .map(|path| StartEntry::Recent(path.clone()))
+            .collect();
+        entries.extend([StartEntry::Browse, StartEntry::New, StartEntry::Help]);
+        self.start_screen = Some(StartScreen { entries, selected: 0 });
+    }
+
+    fn pristine(&self) -> bool {
+        let tab = &self.tabs[self.active_tab];
+        self.tabs.len() == 1 && !self.pager && tab.current_file.is_none() && !tab.is_modified() && tab.content == [""]
+    }
+
+    pub(crate) fn start_screen_active(&self) -> bool {
+        self.start_screen.is_some() && self.mode == Mode::Normal && self.pristine()
+    }
+
+    pub(crate) fn handle_start_screen_key(&mut self, key: KeyEvent) -> Option<io::Result<bool>> {
+        if !self.start_screen_active() {
+            self.start_screen = None;
+            return None;
+        }
+        let start_screen = self.start_screen.as_mut()?;
+        let last = start_screen.entries.len() - 1;
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down if key.modifiers == KeyModifiers::NONE => {
+                start_screen.selected = (start_screen.selected + 1).min(last);
+            }
+            KeyCode::Char('k') | KeyCode::Up if key.modifiers == KeyModifiers::NONE => {
+                start_screen.selected = start_screen.selected.saturating_sub(1);
+            }
+            KeyCode::Enter => {
+                let entry = start_screen.entries[start_screen.selected].clone();
+                self.start_screen = None;
+                return Some(self.open_start_entry(entry));
+            }
+            _ => {
+                self.start_screen = None;
+                return None;
+            }
+        }
+        Some(Ok(false))
+    }
+
+    fn open_start_entry(&mut self, entry: StartEntry) -> io::Result<bool> {
+        match entry {
+            StartEntry::Recent(path) => {
+                if let Err(e) = self.open_file(Path::new(&path)) {
+                    self.error(format!("{}: {}", path, e));
+                }
+            }
+            StartEntry::Browse => {
+                if let Err(e) = self.open_directory(&env::current_dir()?) {
+                    self.error(format!("Failed to open file browser: {}", e));
+                }
+            }
+            StartEntry::New => self.mode = Mode::Insert,
+            StartEntry::Help => self.show_actions(),
+        }
+        Ok(false)
+    }
+
+    fn start_screen_binding(&self, action: &str) -> Option<String> {
+        let mut keys: Vec<&String> = self.keybindings.normal_mode.iter()
+            .filter(|(_, bound)| *bound == action)
+            .map(|(key, _)| key)
+            .collect();
+        keys.sort_by_key(|key| (key.len(), key.to_string()));
+        keys.first().map(|key| key.to_string())
+    }
+
+    pub(crate) fn render_start_screen<B: Backend>(&self, f: &mut Frame<B>, area: Rect) {
+        let Some(start_screen) = &self.start_screen else {
+            return;
+        };
+        let title = self.color_config.fg(&self.color_config.start_screen_title);
+        let text = self.color_config.fg(&self.color_config.foreground);
+        let info = self.color_config.fg(&self.color_config.comment);
+        let selected = text.patch(self.color_config.highlight(&self.color_config.start_screen_selection));
+        let heading = |name: &str| Spans::from(Span::styled(name.to_string(), title.add_modifier(Modifier::BOLD)));
+
+        let mut lines: Vec<Spans> = LOGO.iter().map(|line| Spans::from(Span::styled(*line, title))).collect();
+        lines.push(Spans::from(Span::styled(format!("phantom {}", env!("CARGO_PKG_VERSION")), info)));
+        lines.push(Spans::default());
+        if start_screen.entries.len() > 3 {
+            lines.push(heading("Recent files"));
+        }
+        for (index, entry) in start_screen.entries.iter().enumerate() {
+            if *entry == StartEntry::Browse {
+                lines.push(Spans::default());
+            }
+            let style = if index == start_screen.selected { selected } else { text };
+            lines.push(Spans::from(Span::styled(format!(" {:<40} ", entry.label()), style)));
+        }
+        let cheatsheet: Vec<(String, &str)> = CHEATSHEET.iter()
+            .filter_map(|(action, label)| self.start_screen_binding(action).map(|key| (key, *label)))
+            .collect();
+        if !cheatsheet.is_empty() {
+            lines.push(Spans::default());
+            lines.push(heading("Keys"));
+            for (key, label) in cheatsheet {
+                lines.push(Spans::from(vec![Span::styled(format!("{:>8}  ", key), title), Span::styled(format!("{:<32}", label), text)]));
+            }
+        }
+        lines.push(Spans::default());
+        match &self.paths.config_dir {
+            Some(dir) => {
+                lines.push(heading("Config files"));
+                for name in ["settings.toml", "config.toml", "colors.json"] {
+                    lines.push(Spans::from(Span::styled(dir.join(name).display().to_string(), info)));
+                }
+            }
+            None => lines.push(Spans::from(Span::styled("No config directory; using built-in defaults", info))),
+        }
+
+        let top = area.height.saturating_sub(lines.len() as u16) / 2;
+        lines.splice(0..0, (0..top).map(|_| Spans::default()));
+        f.render_widget(Clear, area);
+        let paragraph = Paragraph::new(lines)
+            .alignment(Alignment::Center)
+            .style(self.color_config.bg(&self.color_config.background));
+        f.render_widget(paragraph, area);
+    }
+}
@@ -0,0 +1,119 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::editor::{Editor, Mode};
+use crate::textobject::TextRange;
+use crate::ui::InputAction;
+
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum SurroundInput {
+    Add,
+    Delete,
+    Change(Option<char>),
+}
+
+fn surround_pair(c: char) -> Option<(String, String)> {
+    let (open, close) = match c {
+        '(' => ("( ", " )"),
+        ')' | 'b' => ("(", ")"),
+        '[' => ("[ ", " ]"),
+        ']' | 'r' => ("[", "]"),
+        '{' => ("{ ", " }"),
+        '}' | 'B' => ("{", "}"),
+        '>' | 'a' => ("<", ">"),
+        '"' | '\'' | '`' | '*' | '_' | '~' | '|' | '/' | '=' => return Some((c.to_string(), c.to_string())),
+        _ => return None,
+    };
+    Some((open.to_string(), close.to_string()))
+}
+
+fn tag_pair(text: &str) -> Option<(String, String)> {
+    let tag = text.trim().trim_start_matches('<').trim_end_matches('>').trim();
+    let name = tag.split_whitespace().next()?;
+    Some((format!("<{}>", tag), format!("</{}>", name)))
+}
+
+impl Editor {
+    pub(crate) fn start_surround_input(&mut self, input: SurroundInput) {
+        if self.tabs[self.active_tab].read_only {
+            self.error("Buffer is read-only");
+            return;
+        }
+        self.surround_input = Some(input);
+    }
+
+    pub(crate) fn handle_surround_input(&mut self, key: KeyEvent) {
+        let Some(input) = self.surround_input.take() else {
+            return;
+        };
+        let typed = match key.code {
+            KeyCode::Char(c) if !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => c,
+            _ => return,
+        };
+        match input {
+            SurroundInput::Add if typed == 't' => {
+                let bounds = self.surround_bounds();
+                self.input(InputAction::SurroundTag(bounds), "Surround with tag", "");
+            }
+            SurroundInput::Add => match surround_pair(typed) {
+                Some((open, close)) => {
+                    let bounds = self.surround_bounds();
+                    self.surround_range(bounds, &open, &close);
+                }
+                None => self.warn(format!("Unknown surround delimiter: {}", typed)),
+            },
+            SurroundInput::Delete => self.replace_surrounding(typed, None),
+            SurroundInput::Change(None) => self.surround_input = Some(SurroundInput::Change(Some(typed))),
+            SurroundInput::Change(Some(target)) => match surround_pair(typed) {
+                Some(pair) => self.replace_surrounding(target, Some(pair)),
+                None => self.warn(format!("Unknown surround delimiter: {}", typed)),
+            },
+        }
+    }
+
+    fn surround_bounds(&self) -> TextRange {
+        let tab = &self.tabs[self.active_tab];
+        tab.selection_bounds(self.visual_start, tab.cursor_position)
+    }
+
+    pub(crate) fn surround_with_tag(&mut self, bounds: TextRange, text: &str) {
+        match tag_pair(text) {
+            Some((open, close)) => self.surround_range(bounds, &open, &close),
+            None => self.mode = Mode::Normal,
+        }
+    }
+
+    fn surround_range(&mut self, (start, end): TextRange, open: &str, close: &str) {
+        self.save_state();
+        let tab = &mut self.tabs[self.active_tab];
+        tab.content[end.1].insert_str(end.0, close);
+        tab.content[start.1].insert_str(start.0, open);
+        tab.cursor_position = start;
+        self.mode = Mode::Normal;
+        self.ensure_cursor_visible();
+    }
+
+    fn replace_surrounding(&mut self, target: char, replacement: Option<(String, String)>) {
+        let tab = &self.tabs[self.active_tab];
+        let cursor = tab.clamp_position(tab.cursor_position);
+        let Some((open, close)) = tab.surrounding_pair(cursor, target) else {
+            self.warn(format!("No surrounding {} found", target));
+            return;
+        };
+        let open_char = tab.content[open.1][open.0..].chars().next().map_or(1, char::len_utf8);
+        let close_char = tab.content[close.1][close.0..].chars().next().map_or(1, char::len_utf8);
+        let mut open_range = (open.0, open.0 + open_char);
+        let mut close_range = (close.0, close.0 + close_char);
+        let padded = tab.content[open.1][open_range.1..].starts_with(' ') && tab.content[close.1][..close.0].ends_with(' ');
+        if matches!(target, '(' | '[' | '{') && padded && (open.1 != close.1 || open_range.1 < close.0 - 1) {
+            open_range.1 += 1;
+            close_range.0 -= 1;
+        }
+        let (new_open, new_close) = replacement.unwrap_or_default();
+        self.save_state();
+        let tab = &mut self.tabs[self.active_tab];
+        tab.content[close.1].replace_range(close_range.0..close_range.1, &new_close);
+        tab.content[open.1].replace_range(open_range.0..open_range.1, &new_open);
+        tab.cursor_position = open;
+        self.ensure_cursor_visible();
+    }
+}
@@ -0,0 +1,178 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::editor::{Editor, Mode};
+use crate::ui::{Picker, PickerKind};
+
+const SPINNER: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+pub(crate) enum TaskEvent {
+    Progress { id: usize, current: u64, total: Option<u64> },
+    Finished { id: usize, error: Option<String> },
+}
+
+pub(crate) struct Progress {
+    id: usize,
+    sender: Sender<TaskEvent>,
+    cancel: Arc<AtomicBool>,
+    finished: bool,
+}
+
+impl Progress {
+    pub(crate) fn report(&self, current: u64, total: Option<u64>) {
+        let _ = self.sender.send(TaskEvent::Progress { id: self.id, current, total });
+    }
+
+    pub(crate) fn cancelled(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn fail(mut self, error: impl ToString) {
+        self.finished = true;
+        let _ = self.sender.send(TaskEvent::Finished { id: self.id, error: Some(error.to_string()) });
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        if !self.finished {
+            let _ = self.sender.send(TaskEvent::Finished { id: self.id, error: None });
+        }
+    }
+}
+
+pub(crate) struct Task {
+    id: usize,
+    label: String,
+    current: u64,
+    total: Option<u64>,
+    started: Instant,
+    cancel: Option<Arc<AtomicBool>>,
+}
+
+impl Task {
+    fn progress(&self) -> Option<String> {
+        match self.total {
+            Some(total) if total > 0 => Some(format!("{}%", self.current.min(total) * 100 / total)),
+            _ if self.current > 0 => Some(self.current.to_string()),
+            _ => None,
+        }
+    }
+
+    fn cancelling(&self) -> bool {
+        self.cancel.as_ref().is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+    }
+}
+
+pub(crate) struct Tasks {
+    sender: Sender<TaskEvent>,
+    receiver: Receiver<TaskEvent>,
+    active: Vec<Task>,
+    next_id: usize,
+}
+
+impl Tasks {
+    pub(crate) fn new() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Tasks { sender, receiver, active: Vec::new(), next_id: 1 }
+    }
+}
+
+impl Editor {
+    pub(crate) fn start_task(&mut self, label: impl Into<String>, cancellable: bool) -> Progress {
+        let tasks = &mut self.tasks;
+        let id = tasks.next_id;
+        tasks.next_id += 1;
+        let cancel = Arc::new(AtomicBool::new(false));
+        tasks.active.push(Task {
+            id,
+            label: label.into(),
+            current: 0,
+            total: None,
+            started: Instant::now(),
+            cancel: cancellable.then(|| Arc::clone(&cancel)),
+        });
+        Progress { id, sender: tasks.sender.clone(), cancel, finished: false }
+    }
+
+    pub fn running_tasks(&self) -> usize {
+        self.tasks.active.len()
+    }
+
+    pub fn poll_tasks(&mut self) -> bool {
+        let mut changed = !self.tasks.active.is_empty();
+        while let Ok(event) = self.tasks.receiver.try_recv() {
+            changed = true;
+            match event {
+                TaskEvent::Progress { id, current, total } => {
+                    if let Some(task) = self.tasks.active.iter_mut().find(|task| task.id == id) {
+                        task.current = current;
+                        task.total = total;
+                    }
+                }
+                TaskEvent::Finished { id, error } => {
+                    let Some(index) = self.tasks.active.iter().position(|task| task.id == id) else {
+                        continue;
+                    };
+                    let task = self.tasks.active.remove(index);
+                    match error {
+                        Some(error) => self.error(format!("{} failed: {}", task.label, error)),
+                        None if task.cancelling() => self.info(format!("Cancelled {}", task.label)),
+                        None => {}
+                    }
+                }
+            }
+        }
+        changed
+    }
+
+    pub(crate) fn task_indicator(&self) -> Option<String> {
+        let latest = self.tasks.active.last()?;
+        let frame = SPINNER[(latest.started.elapsed().as_millis() / 100) as usize % SPINNER.len()];
+        if self.tasks.active.len() > 1 {
+            return Some(format!("{} {} tasks…", frame, self.tasks.active.len()));
+        }
+        Some(match latest.progress() {
+            Some(progress) => format!("{} {} {}", frame, latest.label, progress),
+            None => format!("{} {}", frame, latest.label),
+        })
+    }
+
+    pub(crate) fn open_tasks(&mut self) {
+        if self.tasks.active.is_empty() {
+            self.info("No running tasks");
+            return;
+        }
+        let items = self.tasks.active.iter()
+            .map(|task| {
+                let state = match (&task.cancel, task.cancelling()) {
+                    (_, true) => "  cancelling",
+                    (Some(_), false) => "  Enter cancels",
+                    (None, false) => "",
+                };
+                let progress = task.progress().map(|progress| format!("  {}", progress)).unwrap_or_default();
+                format!("{}  {}{}  {:.1}s{}", task.id, task.label, progress, task.started.elapsed().as_secs_f32(), state)
+            })
+            .collect();
+        self.picker = Some(Picker::new(PickerKind::Tasks, "Tasks", items));
+        self.mode = Mode::Picker;
+    }
+
+    pub(crate) fn cancel_picked_task(&mut self, selected: &str) {
+        let id = selected.split_whitespace().next().and_then(|id| id.parse::<usize>().ok());
+        let Some(task) = self.tasks.active.iter().find(|task| Some(task.id) == id) else {
+            self.info("Task already finished");
+            return;
+        };
+        let label = task.label.clone();
+        match task.cancel.clone() {
+            Some(cancel) => {
+                cancel.store(true, Ordering::Relaxed);
+                self.info(format!("Cancelling {}", label));
+            }
+            None => self.warn(format!("{} can't be cancelled", label)),
+        }
+    }
+}
@@ -0,0 +1,414 @@
+use std::env;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::process::{Child, Command};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::editor::{Editor, Mode};
+use crate::tasks::Progress;
+
+pub(crate) struct TerminalPanel {
+    pub(crate) lines: Vec<String>,
+    pub(crate) column: usize,
+    pub(crate) scroll: usize,
+    pub(crate) alive: bool,
+    pub(crate) size: (u16, u16),
+    pending: Vec<u8>,
+    receiver: Receiver<Vec<u8>>,
+    starting: Option<Progress>,
+    writer: File,
+    child: Child,
+}
+
+#[cfg(unix)]
+fn open_pty() -> io::Result<(File, File)> {
+    use std::ffi::CStr;
+    use std::os::unix::io::FromRawFd;
+
+    // SAFETY: every descriptor is checked before it is used and handed to exactly one
+    // File, which closes it. `name` is zeroed and at most 127 bytes are copied into it,
+    // so it stays NUL-terminated for CStr::from_ptr.
+    unsafe {
+        let master = libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY);
+        if master < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let master_file = File::from_raw_fd(master);
+        libc::fcntl(master, libc::F_SETFD, libc::FD_CLOEXEC);
+        if libc::grantpt(master) != 0 || libc::unlockpt(master) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let mut name = [0 as libc::c_char; 128];
+        #[cfg(target_os = "linux")]
+        let named = libc::ptsname_r(master, name.as_mut_ptr(), name.len()) == 0;
+        #[cfg(not(target_os = "linux"))]
+        let named = {
+            let pointer = libc::ptsname(master);
+            !pointer.is_null() && {
+                let bytes = CStr::from_ptr(pointer).to_bytes_with_nul();
+                let len = bytes.len().min(name.len() - 1);
+                std::ptr::copy_nonoverlapping(bytes.as_ptr() as *const libc::c_char, name.as_mut_ptr(), len);
+                true
+            }
+        };
+        if !named {
+            return Err(io::Error::last_os_error());
+        }
+        let slave = libc::open(CStr::from_ptr(name.as_ptr()).as_ptr(), libc::O_RDWR | libc::O_NOCTTY | libc::O_CLOEXEC);
+        if slave < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok((master_file, File::from_raw_fd(slave)))
+    }
+}
+
+fn escape_length(data: &[u8]) -> Option<usize> {
+    match data.get(1)? {
+        b'[' => data[2..].iter().position(|b| (0x40..=0x7e).contains(b)).map(|end| end + 3),
+        b']' => (2..data.len()).find_map(|end| match data[end] {
+            0x07 => Some(end + 1),
+            0x1b if data.get(end + 1) == Some(&b'\\') => Some(end + 2),
+            _ => None,
+        }),
+        b'(' | b')' => (data.len() > 2).then_some(3),
+        _ => Some(2),
+    }
+}
+
+impl TerminalPanel {
+    pub(crate) const MAX_LINES: usize = 2000;
+
+    #[cfg(unix)]
+    pub(crate) fn spawn(size: (u16, u16)) -> io::Result<Self> {
+        use std::os::unix::process::CommandExt;
+
+        let (master, slave) = open_pty()?;
+        let shell = env::var("SHELL").ok().filter(|shell| !shell.is_empty()).unwrap_or_else(|| "/bin/sh".to_string());
+        let mut command = Command::new(shell);
+        command.env("TERM", "dumb")
+            .stdin(slave.try_clone()?)
+            .stdout(slave.try_clone()?)
+            .stderr(slave);
+        // SAFETY: the hook runs in the forked child before exec and only calls setsid and
+        // ioctl, which are async-signal-safe, without allocating or taking locks.
+        unsafe {
+            command.pre_exec(|| {
+                if libc::setsid() < 0 || libc::ioctl(0, libc::TIOCSCTTY as _, 0) < 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+        let child = command.spawn()?;
+        drop(command);
+        let mut reader = master.try_clone()?;
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let mut buffer = [0u8; 4096];
+            loop {
+                match reader.read(&mut buffer) {
+                    Ok(0) | Err(_) => break,
+                    Ok(count) => {
+                        if sender.send(buffer[..count].to_vec()).is_err() {
+                            break;
+                        }
+                    }
+                }
+            }
+        });
+        let mut panel = TerminalPanel {
+            lines: vec![String::new()],
+            column: 0,
+            scroll: 0,
+            alive: true,
+            size: (0, 0),
+            pending: Vec::new(),
+            receiver,
+            starting: None,
+            writer: master,
+            child,
+        };
+        panel.resize(size);
+        Ok(panel)
+    }
+
+    #[cfg(not(unix))]
+    pub(crate) fn spawn(_size: (u16, u16)) -> io::Result<Self> {
+        Err(io::Error::other("The terminal panel needs a Unix system"))
+    }
+
+    pub(crate) fn resize(&mut self, size: (u16, u16)) {
+        if size == self.size {
+            return;
+        }
+        self.size = size;
+        #[cfg(unix)]
+        {
+            use std::os::unix::io::AsRawFd;
+
+            let winsize = libc::winsize { ws_row: size.1.max(1), ws_col: size.0.max(1), ws_xpixel: 0, ws_ypixel: 0 };
+            // SAFETY: the descriptor belongs to `self.writer`, which is open, and TIOCSWINSZ
+            // only reads the winsize struct, which lives for the whole call.
+            unsafe {
+                libc::ioctl(self.writer.as_raw_fd(), libc::TIOCSWINSZ, &winsize);
+            }
+        }
+    }
+
+    pub(crate) fn send(&mut self, bytes: &[u8]) {
+        if self.alive && self.writer.write_all(bytes).is_err() {
+            self.alive = false;
+        }
+        self.scroll = 0;
+    }
+
+    fn put(&mut self, c: char) {
+        let Some(line) = self.lines.last_mut() else {
+            return;
+        };
+        let count = line.chars().count();
+        if self.column < count {
+            let start = line.char_indices().nth(self.column).map_or(line.len(), |(index, _)| index);
+            let end = line[start..].chars().next().map_or(start, |old| start + old.len_utf8());
+            line.replace_range(start..end, c.encode_utf8(&mut [0; 4]));
+        } else {
+            line.extend(std::iter::repeat_n(' ', self.column - count));
+            line.push(c);
+        }
+        self.column += 1;
+    }
+
+    fn erase_line(&mut self) {
+        if let Some((index, line)) = self.lines.last_mut().and_then(|line| Some((line.char_indices().nth(self.column)?.0, line))) {
+            line.truncate(index);
+        }
+    }
+
+    pub(crate) fn feed(&mut self, bytes: &[u8]) {
+        let mut data = std::mem::take(&mut self.pending);
+        data.extend_from_slice(bytes);
+        let mut i = 0;
+        while i < data.len() {
+            match data[i] {
+                0x1b => {
+                    let Some(length) = escape_length(&data[i..]) else {
+                        if data.len() - i < 4096 {
+                            self.pending = data[i..].to_vec();
+                        }
+                        break;
+                    };
+                    if data[i + 1] == b'[' && data[i + length - 1] == b'K' {
+                        self.erase_line();
+                    }
+                    i += length;
+                }
+                b'\n' => {
+                    self.lines.push(String::new());
+                    self.column = 0;
+                    i += 1;
+                }
+                b'\r' => {
+                    self.column = 0;
+                    i += 1;
+                }
+                0x08 => {
+                    self.column = self.column.saturating_sub(1);
+                    i += 1;
+                }
+                b'\t' => {
+                    for _ in 0..8 - self.column % 8 {
+                        self.put(' ');
+                    }
+                    i += 1;
+                }
+                byte if byte < 0x20 || byte == 0x7f => i += 1,
+                byte => {
+                    let length = match byte {
+                        0xc0..=0xdf => 2,
+                        0xe0..=0xef => 3,
+                        0xf0..=0xf7 => 4,
+                        _ => 1,
+                    };
+                    if i + length > data.len() {
+                        self.pending = data[i..].to_vec();
+                        break;
+                    }
+                    match std::str::from_utf8(&data[i..i + length]) {
+                        Ok(text) => {
+                            text.chars().for_each(|c| self.put(c));
+                            i += length;
+                        }
+                        Err(_) => {
+                            self.put(char::REPLACEMENT_CHARACTER);
+                            i += 1;
+                        }
+                    }
+                }
+            }
+        }
+        if self.lines.len() > Self::MAX_LINES {
+            let excess = self.lines.len() - Self::MAX_LINES;
+            self.lines.drain(..excess);
+        }
+    }
+
+    pub(crate) fn poll(&mut self) -> bool {
+        let mut changed = false;
+        loop {
+            match self.receiver.try_recv() {
+                Ok(bytes) => {
+                    self.starting = None;
+                    self.feed(&bytes);
+                    changed = true;
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.starting = None;
+                    if self.alive {
+                        self.alive = false;
+                        let _ = self.child.try_wait();
+                        changed = true;
+                    }
+                    break;
+                }
+            }
+        }
+        changed
+    }
+
+    pub(crate) fn key_bytes(key: KeyEvent) -> Vec<u8> {
+        let bytes: &[u8] = match key.code {
+            KeyCode::Char(c) if key.modifiers.contains(KeyModifiers::CONTROL) && c.is_ascii() => {
+                return vec![(c.to_ascii_lowercase() as u8) & 0x1f];
+            }
+            KeyCode::Char(c) => {
+                let mut bytes = if key.modifiers.contains(KeyModifiers::ALT) { vec![0x1b] } else { Vec::new() };
+                bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                return bytes;
+            }
+            KeyCode::Enter => b"\r",
+            KeyCode::Backspace => b"\x7f",
+            KeyCode::Tab => b"\t",
+            KeyCode::BackTab => b"\x1b[Z",
+            KeyCode::Esc => b"\x1b",
+            KeyCode::Up => b"\x1b[A",
+            KeyCode::Down => b"\x1b[B",
+            KeyCode::Right => b"\x1b[C",
+            KeyCode::Left => b"\x1b[D",
+            KeyCode::Home => b"\x1b[H",
+            KeyCode::End => b"\x1b[F",
+            KeyCode::Delete => b"\x1b[3~",
+            KeyCode::Insert => b"\x1b[2~",
+            KeyCode::PageUp => b"\x1b[5~",
+            KeyCode::PageDown => b"\x1b[6~",
+            _ => b"",
+        };
+        bytes.to_vec()
+    }
+}
+
+impl Drop for TerminalPanel {
+    fn drop(&mut self) {
+        // SAFETY: kill takes no pointers. The child called setsid, so its pid is also its
+        // process group id, and it is not reaped until the wait below, so the id can't
+        // have been reused by another process.
+        #[cfg(unix)]
+        unsafe {
+            libc::kill(-(self.child.id() as libc::pid_t), libc::SIGHUP);
+            libc::kill(-(self.child.id() as libc::pid_t), libc::SIGKILL);
+        }
+        #[cfg(not(unix))]
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+impl Editor {
+    pub(crate) fn open_terminal(&mut self) {
+        if self.terminal.is_none() {
+            let size = (self.editor_width.max(1) as u16, self.settings.terminal_height.saturating_sub(2).max(1));
+            match TerminalPanel::spawn(size) {
+                Ok(mut terminal) => {
+                    terminal.starting = Some(self.start_task("Starting shell", false));
+                    self.terminal = Some(terminal);
+                }
+                Err(e) => {
+                    self.error(format!("Failed to start terminal: {}", e));
+                    return;
+                }
+            }
+        }
+        self.mode = Mode::Terminal;
+    }
+
+    pub(crate) fn close_terminal(&mut self) {
+        self.terminal = None;
+        if self.mode == Mode::Terminal {
+            self.mode = Mode::Normal;
+        }
+    }
+
+    pub(crate) fn toggle_terminal(&mut self) {
+        if self.terminal.is_some() {
+            self.close_terminal();
+        } else {
+            self.open_terminal();
+        }
+    }
+
+    pub fn poll_terminal(&mut self) -> bool {
+        self.terminal.as_mut().is_some_and(TerminalPanel::poll)
+    }
+
+    pub(crate) fn handle_terminal_mode(&mut self, key: KeyEvent) {
+        let key_str = Self::key_event_to_string(key);
+        let Some(terminal) = &mut self.terminal else {
+            self.mode = Mode::Normal;
+            return;
+        };
+        if !terminal.alive {
+            self.close_terminal();
+            return;
+        }
+        let mut bytes = Vec::new();
+        if let Some(pending) = self.pending_terminal_key.take() {
+            let combined = format!("{}{}", Self::key_event_to_string(pending), key_str);
+            if let Some(action) = self.keybindings.terminal_mode.get(&combined).cloned() {
+                self.terminal_action(&action);
+                return;
+            }
+            bytes.extend(TerminalPanel::key_bytes(pending));
+        }
+        if let Some(action) = self.keybindings.terminal_mode.get(&key_str).cloned() {
+            self.terminal_action(&action);
+            return;
+        }
+        if self.keybindings.terminal_mode.keys().any(|binding| binding.starts_with(&key_str)) {
+            self.pending_terminal_key = Some(key);
+        } else {
+            bytes.extend(TerminalPanel::key_bytes(key));
+        }
+        if let Some(terminal) = &mut self.terminal {
+            terminal.send(&bytes);
+        }
+    }
+
+    pub(crate) fn terminal_action(&mut self, action: &str) {
+        self.pending_terminal_key = None;
+        self.mode = Mode::Normal;
+        if let Err(e) = self.execute_action(action) {
+            self.error(e.to_string());
+        }
+    }
+
+    pub(crate) fn scroll_terminal(&mut self, up: bool, amount: usize) {
+        if let Some(terminal) = &mut self.terminal {
+            let rows = terminal.size.1 as usize;
+            let max_scroll = terminal.lines.len().saturating_sub(rows);
+            terminal.scroll = if up { (terminal.scroll + amount).min(max_scroll) } else { terminal.scroll.saturating_sub(amount) };
+        }
+    }
+}
@@ -0,0 +1,284 @@
+use std::mem;
+
+use crate::buffer::Tab;
+use crate::editor::{Editor, Mode};
+
+pub(crate) type Position = (usize, usize);
+
+pub(crate) type TextRange = (Position, Position);
+
+const BRACKETS: [(char, char); 3] = [('(', ')'), ('[', ']'), ('{', '}')];
+
+const QUOTES: [char; 3] = ['"', '\'', '`'];
+
+pub(crate) struct SelectionExpansion {
+    pub(crate) history: Vec<(Mode, Position, Position)>,
+    pub(crate) range: TextRange,
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn before(a: Position, b: Position) -> bool {
+    (a.1, a.0) < (b.1, b.0)
+}
+
+fn contains(outer: TextRange, inner: TextRange) -> bool {
+    !before(inner.0, outer.0) && !before(outer.1, inner.1)
+}
+
+impl Tab {
+    fn next_position(&self, (x, y): Position) -> Option<Position> {
+        match self.content[y][x..].chars().next() {
+            Some(c) => Some((x + c.len_utf8(), y)),
+            None if y + 1 < self.content.len() => Some((0, y + 1)),
+            None => None,
+        }
+    }
+
+    fn previous_position(&self, (x, y): Position) -> Option<Position> {
+        match self.content[y][..x].chars().next_back() {
+            Some(c) => Some((x - c.len_utf8(), y)),
+            None if y > 0 => Some((self.content[y - 1].len(), y - 1)),
+            None => None,
+        }
+    }
+
+    fn char_at(&self, (x, y): Position) -> Option<char> {
+        self.content[y][x..].chars().next()
+    }
+
+    fn range_size(&self, (start, end): TextRange) -> usize {
+        if start.1 == end.1 {
+            return end.0 - start.0;
+        }
+        let middle: usize = self.content[start.1 + 1..end.1].iter().map(|line| line.len() + 1).sum();
+        self.content[start.1].len() + 1 - start.0 + middle + end.0
+    }
+
+    pub(crate) fn word_object(&self, (x, y): Position) -> Option<TextRange> {
+        let line = &self.content[y];
+        let at_word = line[x..].chars().next().is_some_and(is_word_char);
+        let after_word = line[..x].chars().next_back().is_some_and(is_word_char);
+        if !at_word && !after_word {
+            return None;
+        }
+        let start = line[..x].char_indices().rev().take_while(|(_, c)| is_word_char(*c)).last().map_or(x, |(i, _)| i);
+        let end = x + line[x..].chars().take_while(|c| is_word_char(*c)).map(char::len_utf8).sum::<usize>();
+        Some(((start, y), (end, y)))
+    }
+
+    pub(crate) fn quote_objects(&self, range: TextRange) -> Vec<(TextRange, TextRange)> {
+        QUOTES.into_iter().flat_map(|quote| self.delimiter_objects(range, quote)).collect()
+    }
+
+    fn delimiter_objects(&self, (start, end): TextRange, quote: char) -> Vec<(TextRange, TextRange)> {
+        if start.1 != end.1 {
+            return Vec::new();
+        }
+        let y = start.1;
+        let line = &self.content[y];
+        let mut objects = Vec::new();
+        let mut open = None;
+        let mut escaped = false;
+        for (i, c) in line.char_indices() {
+            if escaped {
+                escaped = false;
+                continue;
+            }
+            if c == '\\' {
+                escaped = true;
+                continue;
+            }
+            if c != quote {
+                continue;
+            }
+            match open.take() {
+                None => open = Some(i),
+                Some(from) => {
+                    let (inner, around) = (((from + quote.len_utf8(), y), (i, y)), ((from, y), (i + quote.len_utf8(), y)));
+                    if contains(around, (start, end)) {
+                        objects.push((inner, around));
+                    }
+                }
+            }
+        }
+        objects
+    }
+
+    fn matching_close(&self, open: Position, pair: (char, char)) -> Option<Position> {
+        let mut depth = 0;
+        let mut position = self.next_position(open)?;
+        loop {
+            match self.char_at(position) {
+                Some(c) if c == pair.0 => depth += 1,
+                Some(c) if c == pair.1 && depth == 0 => return Some(position),
+                Some(c) if c == pair.1 => depth -= 1,
+                _ => {}
+            }
+            position = self.next_position(position)?;
+        }
+    }
+
+    fn enclosing_open(&self, from: Position, pair: (char, char)) -> Option<Position> {
+        let mut depth = 0;
+        let mut position = from;
+        loop {
+            position = self.previous_position(position)?;
+            match self.char_at(position) {
+                Some(c) if c == pair.1 => depth += 1,
+                Some(c) if c == pair.0 && depth == 0 => return Some(position),
+                Some(c) if c == pair.0 => depth -= 1,
+                _ => {}
+            }
+        }
+    }
+
+    pub(crate) fn bracket_objects(&self, range: TextRange) -> Vec<(TextRange, TextRange)> {
+        BRACKETS.into_iter().flat_map(|pair| self.pair_objects(range, pair)).collect()
+    }
+
+    fn pair_objects(&self, range: TextRange, pair: (char, char)) -> Vec<(TextRange, TextRange)> {
+        let mut objects = Vec::new();
+        let mut from = range.0;
+        if self.char_at(from) == Some(pair.0) {
+            from = self.next_position(from).unwrap_or(from);
+        }
+        while let Some(open) = self.enclosing_open(from, pair) {
+            let Some(close) = self.matching_close(open, pair) else {
+                break;
+            };
+            let mut inner = (self.next_position(open).unwrap_or(open), close);
+            let open_ends_line = inner.0.0 == self.content[open.1].len();
+            let close_starts_line = self.content[close.1][..close.0].trim().is_empty();
+            if open_ends_line && close_starts_line && close.1 > open.1 + 1 {
+                inner = self.line_object(((0, open.1 + 1), (0, close.1 - 1)));
+            }
+            let around = (open, self.next_position(close).unwrap_or(close));
+            if contains(around, range) {
+                objects.push((inner, around));
+            }
+            from = open;
+        }
+        objects
+    }
+
+    pub(crate) fn surrounding_pair(&self, position: Position, target: char) -> Option<(Position, Position)> {
+        let range = (position, position);
+        let pair = match target {
+            '(' | ')' | 'b' => Some(('(', ')')),
+            '[' | ']' | 'r' => Some(('[', ']')),
+            '{' | '}' | 'B' => Some(('{', '}')),
+            '<' | '>' | 'a' => Some(('<', '>')),
+            _ => None,
+        };
+        let (_, around) = match pair {
+            Some(pair) => self.pair_objects(range, pair).into_iter().next()?,
+            None if target.is_ascii_punctuation() => self.delimiter_objects(range, target).into_iter().next()?,
+            None => return None,
+        };
+        Some((around.0, self.previous_position(around.1)?))
+    }
+
+    pub(crate) fn line_object(&self, (start, end): TextRange) -> TextRange {
+        ((0, start.1), (self.content[end.1].len(), end.1))
+    }
+
+    pub(crate) fn paragraph_object(&self, (start, end): TextRange) -> TextRange {
+        let blank = |y: usize| self.content[y].trim().is_empty();
+        let mut first = start.1;
+        while first > 0 && !blank(first - 1) {
+            first -= 1;
+        }
+        let mut last = end.1;
+        while last + 1 < self.content.len() && !blank(last + 1) {
+            last += 1;
+        }
+        self.line_object(((0, first), (0, last)))
+    }
+
+    pub(crate) fn expand_range(&self, range: TextRange) -> Option<TextRange> {
+        let mut candidates: Vec<TextRange> = self.word_object(range.0).into_iter().collect();
+        for (inner, around) in self.quote_objects(range).into_iter().chain(self.bracket_objects(range)) {
+            candidates.extend([inner, around]);
+        }
+        candidates.push(self.line_object(range));
+        candidates.push(self.paragraph_object(range));
+        let last = self.content.len() - 1;
+        candidates.push(((0, 0), (self.content[last].len(), last)));
+        candidates.into_iter()
+            .filter(|&candidate| candidate != range && contains(candidate, range))
+            .min_by_key(|&candidate| self.range_size(candidate))
+    }
+}
+
+impl Editor {
+    fn current_selection_range(&self) -> TextRange {
+        let tab = &self.tabs[self.active_tab];
+        let cursor = tab.clamp_position(tab.cursor_position);
+        match self.mode {
+            Mode::Visual => tab.selection_bounds(self.visual_start, cursor),
+            _ => (cursor, cursor),
+        }
+    }
+
+    fn valid_selection_expansion(&mut self) -> Option<&mut SelectionExpansion> {
+        let current = self.current_selection_range();
+        let valid = self.mode == Mode::Visual && self.selection_expansion.as_ref().is_some_and(|expansion| contains(expansion.range, current));
+        if !valid {
+            self.selection_expansion = None;
+        }
+        self.selection_expansion.as_mut()
+    }
+
+    pub(crate) fn expand_selection(&mut self) {
+        if !matches!(self.mode, Mode::Normal | Mode::Visual) {
+            return;
+        }
+        let range = self.current_selection_range();
+        let Some(expanded) = self.tabs[self.active_tab].expand_range(range) else {
+            return;
+        };
+        let snapshot = (self.mode, self.visual_start, self.tabs[self.active_tab].cursor_position);
+        let mut history = self.valid_selection_expansion().map(|expansion| mem::take(&mut expansion.history)).unwrap_or_default();
+        history.push(snapshot);
+        self.select_range(expanded);
+        self.selection_expansion = Some(SelectionExpansion { history, range: expanded });
+    }
+
+    pub(crate) fn shrink_selection(&mut self) {
+        let Some(expansion) = self.valid_selection_expansion() else {
+            return;
+        };
+        let Some((mode, visual_start, cursor)) = expansion.history.pop() else {
+            return;
+        };
+        let tab = &mut self.tabs[self.active_tab];
+        self.visual_start = tab.clamp_position(visual_start);
+        tab.cursor_position = tab.clamp_position(cursor);
+        self.mode = mode;
+        match self.selection_expansion.as_ref().filter(|expansion| !expansion.history.is_empty()) {
+            Some(_) => {
+                let range = self.current_selection_range();
+                if let Some(expansion) = &mut self.selection_expansion {
+                    expansion.range = range;
+                }
+            }
+            None => self.selection_expansion = None,
+        }
+        self.ensure_cursor_visible();
+    }
+
+    fn select_range(&mut self, (start, end): TextRange) {
+        let tab = &mut self.tabs[self.active_tab];
+        self.visual_start = start;
+        tab.cursor_position = if end.0 == 0 && end.1 > start.1 {
+            (tab.content[end.1 - 1].len(), end.1 - 1)
+        } else {
+            tab.previous_position(end).filter(|&last| !before(last, start)).unwrap_or(start)
+        };
+        self.mode = Mode::Visual;
+        self.ensure_cursor_visible();
+    }
+}
@@ -0,0 +1,154 @@
+use std::fs;
+use std::path::PathBuf;
+
+use syntect::parsing::{ParseState, Scope, ScopeStack, SyntaxReference, SyntaxSet};
+
+use crate::editor::{Editor, Mode};
+use crate::ignore::IgnoreRules;
+use crate::ui::{Picker, PickerKind, PickerLocation};
+
+#[derive(Clone)]
+pub(crate) enum TodoScope {
+    Buffer(usize),
+    Tabs,
+    Tree(PathBuf),
+}
+
+pub(crate) struct TodoItem {
+    pub(crate) line: usize,
+    pub(crate) text: String,
+}
+
+fn comment_segments(state: &mut ParseState, stack: &mut ScopeStack, line: &str, ps: &SyntaxSet, comment: Scope) -> Vec<(usize, usize)> {
+    let in_comment = |stack: &ScopeStack| stack.as_slice().iter().any(|&scope| comment.is_prefix_of(scope));
+    let ops = state.parse_line(&format!("{}\n", line), ps).unwrap_or_default();
+    let mut segments = Vec::new();
+    let mut position = 0;
+    for (offset, op) in ops {
+        let offset = offset.min(line.len());
+        if offset > position && in_comment(stack) {
+            segments.push((position, offset));
+        }
+        position = position.max(offset);
+        let _ = stack.apply(&op);
+    }
+    if position < line.len() && in_comment(stack) {
+        segments.push((position, line.len()));
+    }
+    segments
+}
+
+fn find_marker(line: &str, markers: &[String], allowed: impl Fn(usize) -> bool) -> Option<usize> {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    markers.iter()
+        .filter(|marker| !marker.is_empty())
+        .flat_map(|marker| line.match_indices(marker.as_str()).map(move |(start, _)| (start, start + marker.len())))
+        .filter(|&(start, end)| {
+            !line[..start].chars().next_back().is_some_and(is_word) && !line[end..].chars().next().is_some_and(is_word)
+        })
+        .map(|(start, _)| start)
+        .filter(|&start| allowed(start))
+        .min()
+}
+
+fn todo_text(text: &str) -> String {
+    let text = text.trim_end();
+    let text = ["*/", "-->", "#}", "%}"].iter().fold(text, |text, suffix| text.strip_suffix(suffix).unwrap_or(text));
+    text.trim().to_string()
+}
+
+pub(crate) fn scan_todos<'a>(lines: impl IntoIterator<Item = &'a str>, syntax: &SyntaxReference, ps: &SyntaxSet, markers: &[String]) -> Vec<TodoItem> {
+    let plain = syntax.name == "Plain Text";
+    let comment = Scope::new("comment").expect("the comment scope name is valid");
+    let mut state = ParseState::new(syntax);
+    let mut stack = ScopeStack::new();
+    let mut items = Vec::new();
+    for (line_number, line) in lines.into_iter().enumerate() {
+        let segments = if plain { Vec::new() } else { comment_segments(&mut state, &mut stack, line, ps, comment) };
+        let allowed = |start: usize| plain || segments.iter().any(|&(from, to)| from <= start && start < to);
+        if let Some(start) = find_marker(line, markers, allowed) {
+            items.push(TodoItem { line: line_number, text: todo_text(&line[start..]) });
+        }
+    }
+    items
+}
+
+impl Editor {
+    pub(crate) fn open_todos(&mut self, scope: TodoScope) {
+        let markers = self.settings.todo_markers.clone();
+        let mut found: Vec<(String, PickerLocation, TodoItem)> = Vec::new();
+        match &scope {
+            TodoScope::Buffer(_) | TodoScope::Tabs => {
+                let tab_indexes: Vec<usize> = match scope {
+                    TodoScope::Buffer(index) => vec![index.min(self.tabs.len() - 1)],
+                    _ => (0..self.tabs.len()).collect(),
+                };
+                for tab_index in tab_indexes {
+                    let tab = &self.tabs[tab_index];
+                    let syntax = self.ps.find_syntax_by_name(&tab.syntax).unwrap_or_else(|| self.ps.find_syntax_plain_text());
+                    let label = tab.current_file.as_ref()
+                        .map(|file| self.display_path(file))
+                        .unwrap_or_else(|| Self::tab_title(tab, tab_index));
+                    for item in scan_todos(tab.content.iter().map(String::as_str), syntax, &self.ps, &markers) {
+                        found.push((label.clone(), PickerLocation::Tab(tab_index, item.line), item));
+                    }
+                }
+            }
+            TodoScope::Tree(dir) => {
+                let global_ignore = self.paths.config_dir.as_ref().map(|dir| dir.join("ignore"));
+                for path in IgnoreRules::for_dir(dir, global_ignore.as_deref()).walk(dir) {
+                    let Ok(content) = fs::read_to_string(&path) else {
+                        continue;
+                    };
+                    let syntax = self.ps.find_syntax_for_file(&path).ok().flatten().unwrap_or_else(|| self.ps.find_syntax_plain_text());
+                    let label = self.display_path(&path.to_string_lossy());
+                    for item in scan_todos(content.lines(), syntax, &self.ps, &markers) {
+                        found.push((label.clone(), PickerLocation::File(path.clone(), item.line), item));
+                    }
+                }
+            }
+        }
+        if found.is_empty() {
+            self.picker = None;
+            self.mode = Mode::Normal;
+            self.info("No TODOs found");
+            return;
+        }
+        found.sort_by(|a, b| (&a.0, a.2.line).cmp(&(&b.0, b.2.line)));
+        let files = found.iter().map(|(label, _, _)| label).collect::<std::collections::BTreeSet<_>>().len();
+        let items = found.iter().map(|(label, _, item)| format!("{}:{}: {}", label, item.line + 1, item.text)).collect();
+        let title = format!("TODOs in {} file{}", files, if files == 1 { "" } else { "s" });
+        let query = self.picker.as_ref().filter(|picker| picker.kind == PickerKind::Todos).map(|picker| picker.query.clone());
+        let mut picker = Picker::new(PickerKind::Todos, &title, items);
+        picker.locations = found.into_iter().map(|(_, location, _)| location).collect();
+        if let Some(query) = query {
+            picker.query = query;
+            picker.filter();
+        }
+        self.picker = Some(picker);
+        self.todo_scope = Some(scope);
+        self.mode = Mode::Picker;
+    }
+
+    pub(crate) fn todos_command(&mut self, bang: bool, argument: &str) {
+        let scope = if !argument.is_empty() {
+            match self.resolve_command_path(argument) {
+                Ok(path) if path.is_dir() => TodoScope::Tree(path),
+                Ok(path) => {
+                    self.error(format!("Not a directory: {}", path.display()));
+                    return;
+                }
+                Err(e) => {
+                    self.error(e);
+                    return;
+                }
+            }
+        } else if bang {
+            TodoScope::Tabs
+        } else {
+            TodoScope::Buffer(self.active_tab)
+        };
+        self.picker = None;
+        self.open_todos(scope);
+    }
+}
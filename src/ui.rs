@@ -0,0 +1,1744 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use crossterm::event::{KeyCode, KeyEvent};
+use syntect::easy::HighlightLines;
+use syntect::highlighting::Theme;
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Tabs, Wrap},
+    Frame,
+};
+
+use crate::buffer::{char_width, char_width_at, column_to_byte, display_width, line_width, Tab};
+use crate::complete::{common_prefix, path_completions};
+use crate::config::{BlameDisplay, ColorConfig};
+use crate::editor::{Editor, MessageLevel, Mode};
+use crate::ignore::IgnoreRules;
+use crate::indent::{indent_disagrees, leading_whitespace, GuideLevels};
+use crate::input::InputLine;
+use crate::log::LogLevel;
+use crate::minimap::TokenKind;
+use crate::natural::{natural_cmp, natural_path_cmp};
+use crate::search::SearchPattern;
+use crate::tasks::Progress;
+use crate::textobject::TextRange;
+
+const LONG_LINE_MARGIN: usize = 64;
+
+pub(crate) const DIRECTORY_PAGE: usize = 500;
+
+const DIRECTORY_PAGE_MARGIN: usize = 20;
+const SIDEBAR_WATCH_INTERVAL: Duration = Duration::from_secs(1);
+const SIDEBAR_FLASH: Duration = Duration::from_millis(1500);
+
+const MIN_TAB_NAME_WIDTH: usize = 5;
+
+pub(crate) struct FileSelector {
+    pub(crate) current_dir: PathBuf,
+    pub(crate) entries: Vec<PathBuf>,
+    pub(crate) ignored: Vec<bool>,
+    pub(crate) directories: Vec<bool>,
+    pub(crate) selected_index: usize,
+    pub(crate) parent_dir_index: Option<usize>,
+    pub(crate) show_ignored: bool,
+    pub(crate) global_ignore: Option<PathBuf>,
+    pub(crate) listing: Vec<(PathBuf, bool)>,
+    pub(crate) limit: usize,
+    pub(crate) reader: Option<Receiver<Vec<(PathBuf, bool)>>>,
+    pub(crate) progress: Option<Progress>,
+    pub(crate) error: Option<String>,
+    modified: Option<SystemTime>,
+    checked: Option<Instant>,
+    appeared: Vec<(PathBuf, Instant)>,
+}
+
+fn group_digits(n: usize) -> String {
+    let digits = n.to_string();
+    let mut grouped = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+    grouped
+}
+
+pub(crate) fn file_icon(path: &Path, directory: bool) -> &'static str {
+    if directory {
+        return "📁";
+    }
+    match path.extension().and_then(|s| s.to_str()) {
+        Some("rs") => "🦀",
+        Some("js") => "🟨",
+        Some("py") => "🐍",
+        Some("html") => "🌐",
+        Some("css") => "🎨",
+        Some("json") => "📊",
+        Some("md") => "📝",
+        Some("txt") => "📄",
+        Some("pdf") => "📕",
+        Some("jpg") | Some("jpeg") | Some("png") | Some("gif") => "🖼️",
+        Some("mp3") | Some("wav") | Some("ogg") => "🎵",
+        Some("mp4") | Some("avi") | Some("mov") => "🎬",
+        Some("zip") | Some("tar") | Some("gz") => "🗜️",
+        Some("exe") | Some("msi") => "⚙️",
+        _ => "📄",
+    }
+}
+
+pub(crate) fn elide_middle(text: &str, width: usize) -> String {
+    if display_width(text) <= width {
+        return text.to_string();
+    }
+    if width == 0 {
+        return String::new();
+    }
+    let budget = width - 1;
+    let mut head_width = 0;
+    let head: String = text.chars()
+        .take_while(|&c| {
+            head_width += char_width(c);
+            head_width <= budget.div_ceil(2)
+        })
+        .collect();
+    let mut tail_width = display_width(&head);
+    let mut tail: Vec<char> = text.chars().rev()
+        .take_while(|&c| {
+            tail_width += char_width(c);
+            tail_width <= budget
+        })
+        .collect();
+    tail.reverse();
+    format!("{}…{}", head, tail.into_iter().collect::<String>())
+}
+
+fn abbreviate_dir(dir: &str) -> String {
+    if dir.is_empty() || dir == "." {
+        return String::new();
+    }
+    let components: Vec<&str> = dir.split('/').collect();
+    let last = components.len() - 1;
+    let abbreviated: Vec<String> = components.iter().enumerate()
+        .map(|(i, component)| match component.chars().next() {
+            Some('.') if i < last => component.chars().take(2).collect(),
+            Some(c) if i < last => c.to_string(),
+            _ => component.to_string(),
+        })
+        .collect();
+    format!("{}/", abbreviated.join("/"))
+}
+
+fn expand_placeholders(format: &str, value: impl Fn(&str) -> Option<String>) -> String {
+    let mut expanded = String::new();
+    let mut rest = format;
+    while let Some(open) = rest.find('{') {
+        expanded.push_str(&rest[..open]);
+        let placeholder = rest[open + 1..].find('}').and_then(|close| value(&rest[open + 1..open + 1 + close]).map(|text| (close, text)));
+        match placeholder {
+            Some((close, text)) => {
+                expanded.push_str(&text);
+                rest = &rest[open + close + 2..];
+            }
+            None => {
+                expanded.push('{');
+                rest = &rest[open + 1..];
+            }
+        }
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+impl FileSelector {
+    pub(crate) fn new(path: &Path, show_ignored: bool, global_ignore: Option<PathBuf>) -> Self {
+        let mut file_selector = FileSelector {
+            current_dir: path.to_path_buf(),
+            entries: Vec::new(),
+            ignored: Vec::new(),
+            directories: Vec::new(),
+            selected_index: 0,
+            parent_dir_index: Some(0),
+            show_ignored,
+            global_ignore,
+            listing: Vec::new(),
+            limit: DIRECTORY_PAGE,
+            reader: None,
+            progress: None,
+            error: None,
+            modified: None,
+            checked: None,
+            appeared: Vec::new(),
+        };
+        file_selector.load_entries();
+        file_selector
+    }
+
+    pub(crate) fn load_entries(&mut self) {
+        let rules = IgnoreRules::for_dir(&self.current_dir, self.global_ignore.as_deref());
+        self.listing.clear();
+        self.reader = None;
+        self.progress = None;
+        self.error = None;
+        self.modified = fs::metadata(&self.current_dir)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .filter(|modified| SystemTime::now().duration_since(*modified).is_ok_and(|age| age >= SIDEBAR_WATCH_INTERVAL));
+        match rules.entries(&self.current_dir, self.show_ignored) {
+            Ok(mut entries) => {
+                self.listing.extend(entries.by_ref().take(DIRECTORY_PAGE + 1));
+                if self.listing.len() > DIRECTORY_PAGE {
+                    let (sender, receiver) = mpsc::channel();
+                    thread::spawn(move || {
+                        let mut batch = Vec::new();
+                        for entry in entries {
+                            batch.push(entry);
+                            if batch.len() == DIRECTORY_PAGE && sender.send(std::mem::take(&mut batch)).is_err() {
+                                return;
+                            }
+                        }
+                        let _ = sender.send(batch);
+                    });
+                    self.reader = Some(receiver);
+                }
+            }
+            Err(e) => self.error = Some(e.to_string()),
+        }
+        self.show_entries();
+    }
+
+    fn show_entries(&mut self) {
+        let selected = self.entries.get(self.selected_index).cloned();
+        let mut shown: Vec<&(PathBuf, bool)> = self.listing.iter().take(self.limit).collect();
+        shown.sort_by(|a, b| natural_path_cmp(&a.0, &b.0));
+        self.entries = vec![self.current_dir.join("..")];
+        self.ignored = vec![false];
+        for (path, ignored) in shown {
+            self.entries.push(path.clone());
+            self.ignored.push(*ignored);
+        }
+        self.directories = self.entries.iter().map(|path| path.is_dir()).collect();
+        self.selected_index = selected
+            .and_then(|selected| self.entries.iter().position(|path| *path == selected))
+            .unwrap_or(self.selected_index)
+            .min(self.entries.len() - 1);
+        self.parent_dir_index = Some(0);
+    }
+
+    pub(crate) fn poll(&mut self) -> bool {
+        let Some(reader) = &self.reader else {
+            return false;
+        };
+        let mut batches = Vec::new();
+        let finished = loop {
+            match reader.try_recv() {
+                Ok(batch) => batches.push(batch),
+                Err(TryRecvError::Empty) => break false,
+                Err(TryRecvError::Disconnected) => break true,
+            }
+        };
+        let cancelled = self.progress.as_ref().is_some_and(Progress::cancelled);
+        if batches.is_empty() && !finished && !cancelled {
+            return false;
+        }
+        self.listing.extend(batches.into_iter().flatten());
+        if finished || cancelled {
+            self.reader = None;
+            self.progress = None;
+        } else if let Some(progress) = &self.progress {
+            progress.report(self.listing.len() as u64, None);
+        }
+        if self.entries.len() - 1 < self.limit {
+            self.show_entries();
+        }
+        true
+    }
+
+    pub(crate) fn reload(&mut self, now: Instant) {
+        let before: Vec<PathBuf> = self.listing.iter().map(|(path, _)| path.clone()).collect();
+        self.load_entries();
+        self.appeared.retain(|(path, _)| self.listing.iter().any(|(entry, _)| entry == path));
+        let appeared: Vec<PathBuf> = self.listing.iter()
+            .filter(|(path, _)| !before.contains(path))
+            .map(|(path, _)| path.clone())
+            .collect();
+        self.appeared.extend(appeared.into_iter().map(|path| (path, now)));
+    }
+
+    pub(crate) fn watch(&mut self, now: Instant) -> bool {
+        let flashes = self.appeared.len();
+        self.appeared.retain(|(_, since)| now.saturating_duration_since(*since) < SIDEBAR_FLASH);
+        let expired = self.appeared.len() != flashes;
+        if self.reader.is_some() || self.checked.is_some_and(|checked| now.saturating_duration_since(checked) < SIDEBAR_WATCH_INTERVAL) {
+            return expired;
+        }
+        self.checked = Some(now);
+        if fs::metadata(&self.current_dir).and_then(|metadata| metadata.modified()).ok() == self.modified {
+            return expired;
+        }
+        self.reload(now);
+        true
+    }
+
+    fn partial(&self) -> bool {
+        self.reader.is_some() || self.listing.len() > self.entries.len() - 1
+    }
+
+    fn show_more(&mut self, count: usize) {
+        if self.limit < self.listing.len() || self.reader.is_some() {
+            self.limit = self.limit.saturating_add(count);
+            self.show_entries();
+        }
+    }
+
+    pub(crate) fn select(&mut self, path: &Path) -> bool {
+        if !self.listing.iter().any(|(entry, _)| entry == path) {
+            if let Some(reader) = self.reader.take() {
+                self.listing.extend(reader.iter().flatten());
+            }
+        }
+        let Some(position) = self.listing.iter().position(|(entry, _)| entry == path) else {
+            return false;
+        };
+        self.limit = self.limit.max(position + 1);
+        self.show_entries();
+        self.selected_index = self.entries.iter().position(|entry| entry == path).unwrap_or(0);
+        true
+    }
+
+    pub(crate) fn load_all(&mut self) {
+        self.show_more(usize::MAX);
+    }
+
+    pub(crate) fn toggle_show_ignored(&mut self) {
+        self.show_ignored = !self.show_ignored;
+        self.load_entries();
+    }
+
+    pub(crate) fn up(&mut self) {
+        if self.selected_index > 0 {
+            self.selected_index -= 1;
+        }
+    }
+
+    pub(crate) fn down(&mut self) {
+        if self.selected_index < self.entries.len() - 1 {
+            self.selected_index += 1;
+        }
+        if self.selected_index + DIRECTORY_PAGE_MARGIN >= self.entries.len() {
+            self.show_more(DIRECTORY_PAGE);
+        }
+    }
+
+    pub(crate) fn enter(&mut self) -> Option<PathBuf> {
+        let selected = self.entries.get(self.selected_index)?.clone();
+        if !selected.is_dir() {
+            return Some(selected);
+        }
+        self.current_dir = selected;
+        self.entries.clear();
+        self.appeared.clear();
+        self.selected_index = 0;
+        self.limit = DIRECTORY_PAGE;
+        self.load_entries();
+        None
+    }
+
+    pub(crate) fn render<B: Backend>(&self, f: &mut Frame<B>, area: Rect, color_config: &ColorConfig) {
+        let mut items: Vec<ListItem> = self.entries
+            .iter()
+            .enumerate()
+            .map(|(index, path)| {
+                let name = if Some(index) == self.parent_dir_index {
+                    ".. (Parent Directory)".to_string()
+                } else {
+                    path.file_name().unwrap_or_default().to_string_lossy().into_owned()
+                };
+                
+                let icon = file_icon(path, self.directories.get(index).copied().unwrap_or(false));
+                
+                let item = ListItem::new(format!("{} {}", icon, name));
+                if self.appeared.iter().any(|(appeared, _)| appeared == path) {
+                    item.style(color_config.fg(&color_config.file_selector_new))
+                } else if self.ignored.get(index).copied().unwrap_or(false) {
+                    item.style(Style::default().add_modifier(Modifier::DIM))
+                } else {
+                    item
+                }
+            })
+            .collect();
+        if let Some(error) = &self.error {
+            items.push(ListItem::new(format!("⚠ {}", error)).style(color_config.fg(&color_config.message_error)));
+        }
+        let title = if self.partial() {
+            let hidden = self.listing.len().saturating_sub(self.entries.len() - 1);
+            let more = if hidden == 0 {
+                "… reading directory".to_string()
+            } else {
+                format!("… {}{} more (press L to load)", group_digits(hidden), if self.reader.is_some() { "+" } else { "" })
+            };
+            items.push(ListItem::new(more).style(Style::default().add_modifier(Modifier::DIM)));
+            format!("File Selector (first {})", group_digits(self.entries.len() - 1))
+        } else {
+            "File Selector".to_string()
+        };
+
+        let list = List::new(items)
+            .block(Block::default().title(title).borders(Borders::ALL)
+                .border_style(color_config.fg(&color_config.file_selector_border)))
+            .style(color_config.bg(&color_config.file_selector_background).patch(color_config.fg(&color_config.file_selector_foreground)))
+            .highlight_style(
+                color_config.highlight(&color_config.file_selector_highlight)
+                    .add_modifier(Modifier::BOLD),
+            );
+
+        let mut state = ListState::default();
+        state.select(Some(self.selected_index));
+        f.render_stateful_widget(list, area, &mut state);
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum PickerKind {
+    OldFiles,
+    Bookmarks,
+    Todos,
+    Options,
+    Actions,
+    Digraphs,
+    ClosedTabs,
+    Matches,
+    TabSearch,
+    Tasks,
+    Characters,
+}
+
+#[derive(Clone, PartialEq)]
+pub(crate) enum PickerLocation {
+    Tab(usize, usize),
+    File(PathBuf, usize),
+}
+
+pub(crate) struct Picker {
+    pub(crate) kind: PickerKind,
+    pub(crate) title: String,
+    pub(crate) items: Vec<String>,
+    pub(crate) query: String,
+    pub(crate) filtered: Vec<usize>,
+    pub(crate) selected_index: usize,
+    pub(crate) locations: Vec<PickerLocation>,
+}
+
+impl Picker {
+    pub(crate) fn new(kind: PickerKind, title: &str, items: Vec<String>) -> Self {
+        let mut picker = Picker {
+            kind,
+            title: title.to_string(),
+            items,
+            query: String::new(),
+            filtered: Vec::new(),
+            selected_index: 0,
+            locations: Vec::new(),
+        };
+        picker.filter();
+        picker
+    }
+
+    pub(crate) fn fuzzy_score(query: &str, item: &str) -> Option<usize> {
+        let item: Vec<char> = item.to_lowercase().chars().collect();
+        let mut score = 0;
+        let mut position = 0;
+        let mut previous: Option<usize> = None;
+        for c in query.to_lowercase().chars() {
+            let found = item[position..].iter().position(|&ic| ic == c)? + position;
+            score += match previous {
+                Some(prev) if found == prev + 1 => 0,
+                _ => found - position + 1,
+            };
+            if found == 0 || matches!(item[found - 1], '/' | '\\' | '_' | '-' | '.' | ' ') {
+                score = score.saturating_sub(1);
+            }
+            previous = Some(found);
+            position = found + 1;
+        }
+        Some(score)
+    }
+
+    pub(crate) fn filter(&mut self) {
+        let mut scored: Vec<(usize, usize)> = self.items.iter()
+            .enumerate()
+            .filter_map(|(index, item)| Self::fuzzy_score(&self.query, item).map(|score| (score, index)))
+            .collect();
+        if !self.query.is_empty() {
+            scored.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| natural_cmp(&self.items[a.1], &self.items[b.1])));
+        }
+        self.filtered = scored.into_iter().map(|(_, index)| index).collect();
+        self.selected_index = 0;
+    }
+
+    pub(crate) fn up(&mut self) {
+        self.selected_index = self.selected_index.saturating_sub(1);
+    }
+
+    pub(crate) fn down(&mut self) {
+        if self.selected_index + 1 < self.filtered.len() {
+            self.selected_index += 1;
+        }
+    }
+
+    pub(crate) fn selected(&self) -> Option<&str> {
+        self.filtered.get(self.selected_index).map(|&index| self.items[index].as_str())
+    }
+
+    pub(crate) fn selected_location(&self) -> Option<PickerLocation> {
+        self.filtered.get(self.selected_index).and_then(|&index| self.locations.get(index).cloned())
+    }
+
+    pub(crate) fn render<B: Backend>(&self, f: &mut Frame<B>, area: Rect, color_config: &ColorConfig) {
+        let area = centered_rect(area, (area.width * 3 / 4).max(20), (area.height * 3 / 4).max(5));
+        f.render_widget(Clear, area);
+
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Min(1)])
+            .split(area);
+        let style = color_config.bg(&color_config.file_selector_background).patch(color_config.fg(&color_config.file_selector_foreground));
+        let border_style = color_config.fg(&color_config.file_selector_border);
+
+        let query = Paragraph::new(format!("> {}", self.query))
+            .block(Block::default().title(format!("{} ({}/{})", self.title, self.filtered.len(), self.items.len()))
+                .borders(Borders::ALL)
+                .border_style(border_style))
+            .style(style);
+        f.render_widget(query, layout[0]);
+
+        let items: Vec<ListItem> = self.filtered.iter()
+            .map(|&index| ListItem::new(self.items[index].clone()))
+            .collect();
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).border_style(border_style))
+            .style(style)
+            .highlight_style(
+                color_config.highlight(&color_config.file_selector_highlight)
+                    .add_modifier(Modifier::BOLD),
+            );
+        let mut state = ListState::default();
+        if !self.filtered.is_empty() {
+            state.select(Some(self.selected_index));
+        }
+        f.render_stateful_widget(list, layout[1], &mut state);
+        f.set_cursor(layout[0].x + 3 + self.query.chars().count() as u16, layout[0].y + 1);
+    }
+}
+
+pub(crate) fn centered_rect(area: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+#[derive(Clone, PartialEq)]
+pub(crate) enum PromptAction {
+    Quit,
+    Overwrite { path: PathBuf, quit: bool },
+    Locked(String),
+    CreateDirectory { path: PathBuf, quit: bool },
+    CloseOtherTabs,
+    OpenDroppedFiles { paths: Vec<PathBuf>, text: String },
+    PermissionDenied { path: PathBuf, quit: bool },
+    ClearUndo(usize),
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum PromptAnswer {
+    Yes,
+    No,
+    Cancel,
+}
+
+pub(crate) struct ConfirmPrompt {
+    pub(crate) action: PromptAction,
+    pub(crate) message: String,
+    pub(crate) options: Vec<(PromptAnswer, String)>,
+    pub(crate) selected_index: usize,
+    pub(crate) button_areas: Vec<Rect>,
+}
+
+impl ConfirmPrompt {
+    pub(crate) fn new(action: PromptAction, message: &str, yes: &str, no: &str) -> Self {
+        ConfirmPrompt {
+            action,
+            message: message.to_string(),
+            options: vec![
+                (PromptAnswer::Yes, yes.to_string()),
+                (PromptAnswer::No, no.to_string()),
+                (PromptAnswer::Cancel, "Cancel".to_string()),
+            ],
+            selected_index: 0,
+            button_areas: Vec::new(),
+        }
+    }
+
+    pub(crate) fn handle_key(&mut self, key: KeyEvent) -> Option<PromptAnswer> {
+        match key.code {
+            KeyCode::Char('y') | KeyCode::Char('Y') => Some(PromptAnswer::Yes),
+            KeyCode::Char('n') | KeyCode::Char('N') => Some(PromptAnswer::No),
+            KeyCode::Char('c') | KeyCode::Char('C') | KeyCode::Esc => Some(PromptAnswer::Cancel),
+            KeyCode::Enter => Some(self.options[self.selected_index].0),
+            KeyCode::Left | KeyCode::BackTab => {
+                self.selected_index = (self.selected_index + self.options.len() - 1) % self.options.len();
+                None
+            }
+            KeyCode::Right | KeyCode::Tab => {
+                self.selected_index = (self.selected_index + 1) % self.options.len();
+                None
+            }
+            _ => None,
+        }
+    }
+
+    pub(crate) fn handle_click(&self, x: u16, y: u16) -> Option<PromptAnswer> {
+        self.button_areas.iter()
+            .position(|area| x >= area.x && x < area.right() && y >= area.y && y < area.bottom())
+            .map(|index| self.options[index].0)
+    }
+
+    pub(crate) fn render<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect, color_config: &ColorConfig) {
+        let labels: Vec<String> = self.options.iter().map(|(_, label)| format!("[ {} ]", label)).collect();
+        let buttons_width = labels.iter().map(|label| label.chars().count() as u16 + 2).sum::<u16>();
+        let width = (self.message.chars().count() as u16 + 4).max(buttons_width + 2).max(30);
+        let area = centered_rect(area, width, 6);
+        f.render_widget(Clear, area);
+
+        let style = color_config.bg(&color_config.file_selector_background).patch(color_config.fg(&color_config.file_selector_foreground));
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(color_config.fg(&color_config.file_selector_border))
+            .title("Confirm");
+        let message = Paragraph::new(vec![Spans::from(self.message.clone())])
+            .block(block)
+            .style(style)
+            .alignment(Alignment::Center);
+        f.render_widget(message, area);
+
+        let mut x = area.x + area.width.saturating_sub(buttons_width) / 2;
+        let y = area.y + area.height.saturating_sub(2);
+        self.button_areas.clear();
+        for (index, label) in labels.iter().enumerate() {
+            let width = (label.chars().count() as u16).min(area.right().saturating_sub(x));
+            let button_area = Rect::new(x, y, width, 1);
+            let button_style = if index == self.selected_index {
+                style.patch(color_config.highlight(&color_config.file_selector_highlight)).add_modifier(Modifier::BOLD)
+            } else {
+                style
+            };
+            f.render_widget(Paragraph::new(label.clone()).style(button_style), button_area);
+            self.button_areas.push(button_area);
+            x += width + 2;
+        }
+    }
+}
+
+#[derive(Clone, PartialEq)]
+pub(crate) enum InputAction {
+    SaveAs { quit: bool },
+    CreateFile,
+    Rename(PathBuf),
+    Expression,
+    SurroundTag(TextRange),
+    RenameWord,
+}
+
+pub(crate) struct InputPrompt {
+    pub(crate) action: InputAction,
+    pub(crate) label: String,
+    pub(crate) input: InputLine,
+    pub(crate) warning: Option<String>,
+    pub(crate) completions: Vec<String>,
+    pub(crate) completion_index: usize,
+}
+
+impl InputPrompt {
+    pub(crate) fn new(action: InputAction, label: &str, default: &str) -> Self {
+        let mut input = InputLine::default();
+        input.insert_str(default);
+        InputPrompt {
+            action,
+            label: label.to_string(),
+            input,
+            warning: None,
+            completions: Vec::new(),
+            completion_index: 0,
+        }
+    }
+
+    pub(crate) fn complete(&mut self) {
+        if !self.completions.is_empty() {
+            self.completion_index = (self.completion_index + 1) % self.completions.len();
+            self.input.clear();
+            self.input.insert_str(&self.completions[self.completion_index]);
+            return;
+        }
+        if matches!(self.action, InputAction::Expression | InputAction::SurroundTag(_) | InputAction::RenameWord) {
+            return;
+        }
+        let completions = path_completions(&self.input.text, Path::new("."));
+        if completions.is_empty() {
+            return;
+        }
+        let common = common_prefix(&completions);
+        if completions.len() > 1 && common.chars().count() <= self.input.len() {
+            self.completions = completions;
+            self.completion_index = 0;
+            self.input.clear();
+            self.input.insert_str(&self.completions[0]);
+        } else {
+            self.input.clear();
+            self.input.insert_str(&common);
+        }
+    }
+
+    pub(crate) fn render<B: Backend>(&self, f: &mut Frame<B>, area: Rect, color_config: &ColorConfig) {
+        let width = (area.width * 2 / 3).max(40);
+        let area = centered_rect(area, width, 5);
+        f.render_widget(Clear, area);
+
+        let style = color_config.bg(&color_config.file_selector_background).patch(color_config.fg(&color_config.file_selector_foreground));
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(color_config.fg(&color_config.file_selector_border))
+            .title(self.label.clone());
+        let visible_width = area.width.saturating_sub(4) as usize;
+        let skip = self.input.cursor.saturating_sub(visible_width);
+        let text: String = self.input.text.chars().skip(skip).collect();
+        let mut lines = vec![Spans::from(format!("> {}", text))];
+        if let Some(warning) = &self.warning {
+            lines.push(Spans::from(Span::styled(
+                warning.clone(),
+                color_config.fg(&color_config.message_warn),
+            )));
+        } else if self.completions.len() > 1 {
+            lines.push(Spans::from(format!("{} of {} matches", self.completion_index + 1, self.completions.len())));
+        }
+        f.render_widget(Paragraph::new(lines).block(block).style(style), area);
+        f.set_cursor(area.x + 3 + (self.input.cursor - skip) as u16, area.y + 1);
+    }
+}
+
+pub(crate) enum Prompt {
+    Confirm(ConfirmPrompt),
+    Input(InputPrompt),
+}
+
+pub(crate) enum PromptResult {
+    Confirm(PromptAction, PromptAnswer),
+    Input(InputAction, String),
+}
+
+#[derive(Clone, Copy, Default, PartialEq)]
+pub(crate) struct ScreenLayout {
+    pub(crate) sidebar: Option<Rect>,
+    pub(crate) preview: Option<Rect>,
+    pub(crate) tab_bar: Rect,
+    pub(crate) debug: Option<Rect>,
+    pub(crate) editor: Rect,
+    pub(crate) status: Rect,
+    pub(crate) minimap: Option<Rect>,
+    pub(crate) outline: Option<Rect>,
+    pub(crate) terminal: Option<Rect>,
+    pub(crate) gutter: u16,
+}
+
+impl ScreenLayout {
+    pub(crate) fn text_area(&self) -> Rect {
+        Rect {
+            x: self.editor.x + 1 + self.gutter,
+            y: self.editor.y + 1,
+            width: self.editor.width.saturating_sub(2 + self.gutter),
+            height: self.editor.height.saturating_sub(2),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Layer {
+    CursorLine,
+    ColorColumn,
+    Diagnostic,
+    SearchMatch,
+    Selection,
+    CurrentMatch,
+}
+
+#[derive(Default)]
+pub(crate) struct WhitespaceMarks {
+    pub(crate) list: Option<Style>,
+    pub(crate) guides: Vec<(usize, Style)>,
+}
+
+pub(crate) fn render_line(
+    ranges: &[(Style, &str)],
+    horizontal_scroll: usize,
+    width: usize,
+    overlays: &[(usize, usize, Style)],
+    cursor: Option<(usize, Style)>,
+    tab_width: usize,
+    marks: &WhitespaceMarks,
+) -> Vec<Span<'static>> {
+    let list = marks.list;
+    let mut spans = Vec::new();
+    let mut pending = String::new();
+    let mut pending_style = Style::default();
+    let mut cursor = cursor;
+    let visible_end = horizontal_scroll + width;
+    let mut column = 0;
+    'ranges: for &(base, content) in ranges {
+        for c in content.chars() {
+            let start = column;
+            column += char_width_at(c, start, tab_width);
+            if start >= visible_end {
+                break 'ranges;
+            }
+            if column <= horizontal_scroll && start < horizontal_scroll {
+                continue;
+            }
+            let base = match list {
+                Some(list) if c == '\t' => base.patch(list),
+                _ => base,
+            };
+            let style = overlays.iter()
+                .filter(|(from, to, _)| *from <= start && start < *to)
+                .fold(base, |style, (_, _, overlay)| style.patch(*overlay));
+            if style != pending_style || cursor.is_some_and(|(at, _)| at <= start) {
+                if !pending.is_empty() {
+                    spans.push(Span::styled(std::mem::take(&mut pending), pending_style));
+                }
+                pending_style = style;
+            }
+            if let Some((_, cursor_style)) = cursor.filter(|(at, _)| *at <= start) {
+                spans.push(Span::styled(String::new(), cursor_style));
+                cursor = None;
+            }
+            let from = start.max(horizontal_scroll);
+            let guide = marks.guides.iter()
+                .filter(|_| c == ' ' || c == '\t')
+                .find(|(at, _)| from <= *at && *at < column.min(visible_end) && !(c == '\t' && list.is_some() && *at == start));
+            if let Some(&(at, guide_style)) = guide {
+                if c == '\t' && list.is_some() && from == start {
+                    pending.push('→');
+                    pending.push_str(&" ".repeat(at - from - 1));
+                } else {
+                    pending.push_str(&" ".repeat(at - from));
+                }
+                if !pending.is_empty() {
+                    spans.push(Span::styled(std::mem::take(&mut pending), pending_style));
+                }
+                spans.push(Span::styled("│", style.patch(guide_style)));
+                pending.push_str(&" ".repeat(column.min(visible_end) - at - 1));
+            } else if start < horizontal_scroll || column > visible_end {
+                pending.push_str(&" ".repeat(column.min(visible_end) - from));
+            } else if c == '\t' {
+                pending.push(if list.is_some() { '→' } else { ' ' });
+                pending.push_str(&" ".repeat(column - start - 1));
+            } else {
+                pending.push(c);
+            }
+        }
+    }
+    if !pending.is_empty() {
+        spans.push(Span::styled(pending, pending_style));
+    }
+    if let Some((_, cursor_style)) = cursor {
+        spans.push(Span::styled(String::new(), cursor_style));
+    }
+    spans
+}
+
+fn long_line_window(line: &str, horizontal_scroll: usize, width: usize, tab_width: usize) -> (usize, usize) {
+    let first = column_to_byte(line, horizontal_scroll.saturating_sub(LONG_LINE_MARGIN), tab_width);
+    let last = column_to_byte(line, horizontal_scroll + width + LONG_LINE_MARGIN, tab_width);
+    (first, last)
+}
+
+pub(crate) fn contains(area: Rect, x: u16, y: u16) -> bool {
+    x >= area.x && x < area.right() && y >= area.y && y < area.bottom()
+}
+
+pub struct EditorView {
+    pub mode: Mode,
+    pub tabs: Vec<String>,
+    pub active_tab: usize,
+    pub lines: Vec<String>,
+    pub first_line: usize,
+    pub cursor: (usize, usize),
+    pub modified: bool,
+    pub status: Option<String>,
+}
+
+impl Editor {
+    pub(crate) fn tab_label(&self, index: usize, name_width: Option<usize>) -> String {
+        let tab = &self.tabs[index];
+        let format = match &self.settings.active_tab_title_format {
+            format if index == self.active_tab && !format.is_empty() => format,
+            _ => &self.settings.tab_title_format,
+        };
+        let name = Self::tab_title(tab, index);
+        let name = match name_width {
+            Some(width) => elide_middle(&name, width),
+            None => name,
+        };
+        let path = tab.current_file.as_deref().map(Path::new);
+        let dir = path.and_then(Path::parent)
+            .map(|dir| abbreviate_dir(&self.display_path(&dir.to_string_lossy())))
+            .unwrap_or_default();
+        let modified = match (tab.save.is_some(), tab.modified) {
+            (true, _) => " (saving…)",
+            (false, true) => " +",
+            (false, false) => "",
+        };
+        let marker = if Some(index) == self.alternate_tab() { "#" } else { " " };
+        let label = expand_placeholders(format, |placeholder| match placeholder {
+            "index" => Some((index + 1).to_string()),
+            "name" => Some(name.clone()),
+            "dir" => Some(dir.clone()),
+            "modified" => Some(modified.to_string()),
+            "readonly" => Some(if tab.read_only { " [RO]" } else { "" }.to_string()),
+            "icon" => Some(path.map_or("📄", |path| file_icon(path, false)).to_string()),
+            _ => None,
+        });
+        let private = if tab.private { " [private]" } else { "" };
+        format!("{}{}{}", marker, label, private)
+    }
+
+    pub(crate) fn tab_labels(&self, width: usize) -> Vec<String> {
+        let labels: Vec<String> = (0..self.tabs.len()).map(|index| self.tab_label(index, None)).collect();
+        let separators = 3 * labels.len() - 1;
+        if labels.iter().map(|label| display_width(label) + 1).sum::<usize>() + separators <= width {
+            return labels;
+        }
+        let names: Vec<usize> = self.tabs.iter().enumerate().map(|(index, tab)| display_width(&Self::tab_title(tab, index))).collect();
+        let fixed: usize = (0..self.tabs.len()).map(|index| display_width(&self.tab_label(index, Some(0))) + 1).sum();
+        let available = width.saturating_sub(fixed + separators);
+        let longest = names.iter().copied().max().unwrap_or(0);
+        let cap = (MIN_TAB_NAME_WIDTH..longest).rev()
+            .find(|&cap| names.iter().map(|&name| name.min(cap)).sum::<usize>() <= available)
+            .unwrap_or(MIN_TAB_NAME_WIDTH);
+        (0..self.tabs.len()).map(|index| self.tab_label(index, Some(cap))).collect()
+    }
+
+    pub(crate) fn tab_title(tab: &Tab, index: usize) -> String {
+        tab.current_file.as_ref()
+            .and_then(|f| Path::new(f).file_name())
+            .and_then(|f| f.to_str())
+            .map(|s| s.to_string())
+            .or_else(|| tab.title.clone())
+            .unwrap_or_else(|| format!("Untitled-{}", index + 1))
+    }
+
+    pub(crate) fn syntax_theme(&self) -> &Theme {
+        self.color_config.syntax_theme.as_ref()
+            .and_then(|name| self.ts.themes.get(name))
+            .unwrap_or_else(|| &self.ts.themes[self.color_config.preset_theme()])
+    }
+
+    pub(crate) fn highlight_ranges<'a>(&self, h: &mut HighlightLines, line: &'a str) -> Vec<(Style, &'a str)> {
+        let Ok(ranges) = h.highlight_line(line, &self.ps) else {
+            return vec![(self.color_config.fg(&self.color_config.foreground), line)];
+        };
+        ranges.into_iter()
+            .map(|(style, content)| {
+                let color = style.foreground;
+                (Style::default().fg(self.color_config.color_mode().convert(Color::Rgb(color.r, color.g, color.b))), content)
+            })
+            .collect()
+    }
+
+    pub(crate) fn is_minimap_area(&self, x: u16, y: u16) -> bool {
+        let Some(area) = self.layout.minimap else {
+            return false;
+        };
+        let rows = Rect { y: area.y + 1, height: (self.minimap_line_mapping.len() as u16).min(area.height.saturating_sub(1)), ..area };
+        contains(rows, x, y)
+    }
+
+    pub fn screen_to_content_position(&self, x: u16, y: u16) -> Option<(usize, usize)> {
+        let area = self.layout.text_area();
+        if !contains(area, x, y) {
+            return None;
+        }
+        let tab = &self.tabs[self.active_tab];
+        let tab_width = self.option("tabstop").number();
+        let (mut row, mut column) = ((y - area.y) as usize + tab.line_to_row(tab.scroll_offset), (x - area.x) as usize + tab.horizontal_scroll);
+        if let Some(width) = self.wrap_width() {
+            let (mut screen_row, mut line_row) = ((y - area.y) as usize, tab.line_to_row(tab.scroll_offset));
+            while line_row < tab.row_count() {
+                let rows = tab.wrapped_rows(tab.row_to_line(line_row), width, tab_width);
+                if screen_row < rows {
+                    break;
+                }
+                screen_row -= rows;
+                line_row += 1;
+            }
+            if line_row < tab.row_count() {
+                column = (x - area.x) as usize + screen_row * width;
+                screen_row = 0;
+            }
+            row = line_row + screen_row;
+        }
+        let line = if row < tab.row_count() { tab.row_to_line(row) } else { tab.content.len() + row - tab.row_count() };
+        if tab.fold_at(line).is_some() {
+            return Some((0, line));
+        }
+        Some((tab.content.get(line).map_or(0, |text| column_to_byte(text, column, tab_width)), line))
+    }
+
+    pub(crate) fn clamp_to_text_area(&self, x: u16, y: u16) -> (u16, u16) {
+        let area = self.layout.text_area();
+        (
+            x.clamp(area.x, area.right().saturating_sub(1).max(area.x)),
+            y.clamp(area.y, area.bottom().saturating_sub(1).max(area.y)),
+        )
+    }
+
+    pub fn view(&self) -> EditorView {
+        let tab = &self.tabs[self.active_tab];
+        let height = self.get_editor_height();
+        let end = (tab.scroll_offset + height).min(tab.content.len());
+        EditorView {
+            mode: self.mode,
+            tabs: self.tabs.iter().enumerate().map(|(i, tab)| Self::tab_title(tab, i)).collect(),
+            active_tab: self.active_tab,
+            lines: tab.content[tab.scroll_offset.min(end)..end].to_vec(),
+            first_line: tab.scroll_offset,
+            cursor: tab.cursor_position,
+            modified: tab.is_modified(),
+            status: self.status_message.as_ref().map(|message| message.text.clone()),
+        }
+    }
+
+    pub(crate) fn render_minimap<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect) {
+        let tab = &self.tabs[self.active_tab];
+        let content = &tab.content;
+    
+        if content.is_empty() || (content.len() == 1 && content[0].is_empty()) {
+            self.minimap_line_mapping.clear();
+            let empty_minimap = Paragraph::new("No content")
+                .block(Block::default().borders(Borders::ALL).title("Minimap"))
+                .style(self.color_config.bg(&self.color_config.minimap_background).patch(self.color_config.fg(&self.color_config.minimap_content)));
+            f.render_widget(empty_minimap, area);
+            return;
+        }
+    
+        let total_lines = content.len();
+        if area.width < 3 || area.height < 3 {
+            self.minimap_line_mapping.clear();
+            return;
+        }
+        let minimap_height = area.height as usize - 2;
+        let minimap_width = area.width as usize - 2;
+        let scale_y = (total_lines as f32 / minimap_height as f32).max(1.0);
+        let scale_x = self.settings.minimap_scale.max(1);
+
+        let syntax = self.ps.find_syntax_by_name(&tab.syntax).unwrap_or_else(|| self.ps.find_syntax_plain_text());
+        self.minimap_tokens.update(content, syntax, &self.ps, self.option("tabstop").number(), self.settings.long_line_threshold);
+
+        let minimap_highlight = self.color_config.highlight(&self.color_config.minimap_highlight);
+        let token_style = |kind: TokenKind| match kind {
+            TokenKind::Text => self.color_config.fg(&self.color_config.minimap_content),
+            TokenKind::Comment => self.color_config.fg(&self.color_config.comment),
+            TokenKind::String => self.color_config.fg(&self.color_config.string),
+            TokenKind::Keyword => self.color_config.fg(&self.color_config.keyword),
+            TokenKind::Function => self.color_config.fg(&self.color_config.function),
+        };
+
+        let current_line = tab.cursor_position.1;
+        let (band_top, band_bottom) = self.minimap_band();
+        let band_style = self.color_config.bg(&self.color_config.cursor_line);
+        let mut minimap_content = Vec::new();
+        let mut line_mapping = Vec::new();
+
+        for y in 0..minimap_height {
+            let min_line = (y as f32 * scale_y) as usize;
+            if min_line >= total_lines {
+                break;
+            }
+            let max_line = (((y + 1) as f32 * scale_y) as usize).saturating_sub(1).min(total_lines - 1).max(min_line);
+            let span = max_line - min_line + 1;
+            let sampled: Vec<usize> = (0..4.min(span)).map(|dy| min_line + dy * span / 4.min(span)).collect();
+            let current = current_line >= min_line && current_line <= max_line;
+            let in_band = min_line <= band_bottom && band_top <= max_line;
+
+            let mut line_spans = Vec::new();
+            for x in 0..minimap_width {
+                let (dots, kind) = self.minimap_tokens.cell(&sampled, (x * 2 * scale_x, scale_x));
+                let style = kind.map_or_else(Style::default, token_style);
+                let style = if in_band { band_style.patch(style) } else { style };
+                let style = if current { style.patch(minimap_highlight) } else { style };
+                line_spans.push(Span::styled(char::from_u32(0x2800 + dots).unwrap_or(' ').to_string(), style));
+            }
+            minimap_content.push(Spans::from(line_spans));
+            line_mapping.push((min_line, max_line));
+        }
+
+        let border = if self.minimap_focus.is_some() { &self.color_config.tab_active } else { &self.color_config.minimap_border };
+        let minimap = Paragraph::new(minimap_content)
+            .block(Block::default()
+                .borders(Borders::ALL)
+                .title("Minimap")
+                .border_style(self.color_config.fg(border)))
+            .style(self.color_config.bg(&self.color_config.minimap_background));
+    
+        f.render_widget(minimap, area);
+    
+        self.minimap_line_mapping = line_mapping;
+    }
+
+    pub(crate) fn render_terminal<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect) -> Option<(u16, u16)> {
+        let terminal = self.terminal.as_mut()?;
+        let rows = area.height.saturating_sub(2) as usize;
+        terminal.resize((area.width.saturating_sub(2), rows as u16));
+        let end = terminal.lines.len() - terminal.scroll.min(terminal.lines.len().saturating_sub(1));
+        let start = end.saturating_sub(rows);
+        let text: Vec<Spans> = terminal.lines[start..end].iter().map(|line| Spans::from(line.clone())).collect();
+        let mut title = "Terminal".to_string();
+        if terminal.scroll > 0 {
+            title.push_str(&format!(" (+{})", terminal.scroll));
+        }
+        if !terminal.alive {
+            title.push_str(" [exited]");
+        }
+        let mut title_style = self.color_config.fg(&self.color_config.foreground);
+        if self.mode == Mode::Terminal {
+            title_style = title_style.add_modifier(Modifier::BOLD);
+        }
+        let paragraph = Paragraph::new(text)
+            .block(Block::default().borders(Borders::ALL).title(Span::styled(title, title_style)))
+            .style(self.color_config.bg(&self.color_config.background));
+        f.render_widget(paragraph, area);
+        let last = terminal.lines.last().map_or("", String::as_str);
+        let column = display_width(&last.chars().take(terminal.column).collect::<String>()) + terminal.column.saturating_sub(last.chars().count());
+        (terminal.scroll == 0).then(|| (
+            (area.x + 1 + column.min(u16::MAX as usize) as u16).min(area.right().saturating_sub(2)),
+            area.y + 1 + (end - start).saturating_sub(1) as u16,
+        ))
+    }
+
+    pub(crate) fn render_outline<B: Backend>(&self, f: &mut Frame<B>, area: Rect) {
+        let Some(outline) = &self.outline else {
+            return;
+        };
+        let focused = self.mode == Mode::Outline;
+        let focus = if focused {
+            Some(outline.selected)
+        } else {
+            outline.enclosing(self.tabs[self.active_tab].cursor_position.1)
+        };
+        let rows = area.height.saturating_sub(2) as usize;
+        let offset = Self::outline_offset(focus, rows);
+        let highlight = self.color_config.highlight(&self.color_config.file_selector_highlight)
+            .add_modifier(Modifier::BOLD);
+        let items: Vec<ListItem> = outline.symbols.iter()
+            .enumerate()
+            .skip(offset)
+            .take(rows)
+            .map(|(index, symbol)| {
+                let item = ListItem::new(format!("{}{}", "  ".repeat(symbol.depth), symbol.label));
+                if Some(index) == focus { item.style(highlight) } else { item }
+            })
+            .collect();
+        let title = if outline.symbols.is_empty() { "Outline (empty)" } else { "Outline" };
+        let border = if focused { self.color_config.tab_active.as_str() } else { self.color_config.file_selector_border.as_str() };
+        let list = List::new(items)
+            .block(Block::default().title(title).borders(Borders::ALL)
+                .border_style(self.color_config.fg(border)))
+            .style(self.color_config.bg(&self.color_config.file_selector_background).patch(self.color_config.fg(&self.color_config.file_selector_foreground)));
+        f.render_widget(list, area);
+    }
+
+    pub(crate) const MAX_COMPLETION_ROWS: usize = 10;
+
+    pub(crate) fn render_completion<B: Backend>(&self, f: &mut Frame<B>, area: Rect, (cursor_x, cursor_y): (u16, u16)) {
+        let Some(completion) = &self.completion else {
+            return;
+        };
+        let tab = &self.tabs[self.active_tab];
+        let line = &tab.content[completion.line];
+        let typed = display_width(&line[completion.start..tab.cursor_position.0.min(line.len())]) as u16;
+        let rows = completion.items.len().min(Self::MAX_COMPLETION_ROWS);
+        let offset = (completion.selected + 1).saturating_sub(rows);
+        let width = completion.items.iter().map(|item| display_width(item)).max().unwrap_or(0) as u16 + 2;
+        let width = width.min(area.width);
+        let height = (rows as u16 + 2).min(area.height);
+        let x = cursor_x.saturating_sub(typed).max(area.x).min(area.right().saturating_sub(width));
+        let y = if cursor_y + 1 + height <= area.bottom() { cursor_y + 1 } else { cursor_y.saturating_sub(height).max(area.y) };
+        let popup = Rect::new(x, y, width, height);
+        let highlight = self.color_config.highlight(&self.color_config.file_selector_highlight)
+            .add_modifier(Modifier::BOLD);
+        let items: Vec<ListItem> = completion.items.iter()
+            .enumerate()
+            .skip(offset)
+            .take(rows)
+            .map(|(index, item)| {
+                let item = ListItem::new(item.clone());
+                if index == completion.selected { item.style(highlight) } else { item }
+            })
+            .collect();
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL)
+                .border_style(self.color_config.fg(&self.color_config.file_selector_border)))
+            .style(self.color_config.bg(&self.color_config.file_selector_background).patch(self.color_config.fg(&self.color_config.file_selector_foreground)));
+        f.render_widget(Clear, popup);
+        f.render_widget(list, popup);
+    }
+
+    pub(crate) const MIN_EDITOR_WIDTH: u16 = 20;
+
+    pub(crate) const MIN_HEIGHT: u16 = 7;
+
+    pub(crate) const GUTTER_WIDTH: u16 = 2;
+
+    pub(crate) const OUTLINE_WIDTH: u16 = 30;
+
+    pub(crate) const PREVIEW_WIDTH: u16 = 40;
+
+    pub fn ui<B: Backend>(&mut self, f: &mut Frame<B>) {
+        let size = f.size();
+        if size.width < Self::MIN_EDITOR_WIDTH || size.height < Self::MIN_HEIGHT {
+            self.layout = ScreenLayout::default();
+            let message = format!("window too small (need {}x{})", Self::MIN_EDITOR_WIDTH, Self::MIN_HEIGHT);
+            f.render_widget(Paragraph::new(message).wrap(Wrap { trim: true }), size);
+            return;
+        }
+
+        self.refresh_outline();
+        self.refresh_blame();
+        self.refresh_modified();
+        let total_width = size.width;
+        let mut sidebar_width = if self.show_sidebar { self.sidebar_width } else { 0 };
+        let mut minimap_width = if self.show_minimap && !self.tabs[self.active_tab].content.is_empty() { self.minimap_width } else { 0 };
+        let mut outline_width = if self.outline.is_some() { Self::OUTLINE_WIDTH } else { 0 };
+        let mut preview_width = if self.show_sidebar && self.sidebar_preview.is_some() { Self::PREVIEW_WIDTH } else { 0 };
+        if sidebar_width + preview_width + outline_width + minimap_width + Self::MIN_EDITOR_WIDTH > total_width {
+            preview_width = 0;
+        }
+        if sidebar_width + outline_width + minimap_width + Self::MIN_EDITOR_WIDTH > total_width {
+            minimap_width = 0;
+        }
+        if sidebar_width + outline_width + Self::MIN_EDITOR_WIDTH > total_width {
+            outline_width = 0;
+        }
+        if sidebar_width + Self::MIN_EDITOR_WIDTH > total_width {
+            sidebar_width = 0;
+            preview_width = 0;
+        }
+        let debug_height = self.settings.debug_height.max(3);
+        let show_debug = self.show_debug && size.height >= Self::MIN_HEIGHT.saturating_add(debug_height);
+        let terminal_height = self.settings.terminal_height.max(3);
+        let show_terminal = self.terminal.is_some()
+            && size.height >= Self::MIN_HEIGHT.saturating_add(terminal_height).saturating_add(if show_debug { debug_height } else { 0 });
+        let editor_width = total_width.saturating_sub(sidebar_width + preview_width + outline_width + minimap_width);
+        
+        let mut constraints = vec![];
+        if sidebar_width > 0 {
+            constraints.push(Constraint::Length(sidebar_width));
+        }
+        if preview_width > 0 {
+            constraints.push(Constraint::Length(preview_width));
+        }
+        constraints.push(Constraint::Length(editor_width));
+        if outline_width > 0 {
+            constraints.push(Constraint::Length(outline_width));
+        }
+        if minimap_width > 0 {
+            constraints.push(Constraint::Length(minimap_width));
+        }    
+
+        let main_layout = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(constraints)
+        .split(size);
+    
+        let mut current_layout_index = 0;
+                    
+        if sidebar_width > 0 {
+            if let Some(file_selector) = &self.file_selector {
+                file_selector.render(f, main_layout[current_layout_index], &self.color_config);
+            }
+            current_layout_index += 1;
+        }
+        let preview_area = (preview_width > 0).then(|| main_layout[current_layout_index]);
+        if let Some(area) = preview_area {
+            self.render_sidebar_preview(f, area);
+            current_layout_index += 1;
+        }
+
+        let editor_area = main_layout[current_layout_index];
+        current_layout_index += 1;    
+        let outline_area = (outline_width > 0).then(|| main_layout[current_layout_index]);
+        if outline_area.is_some() {
+            current_layout_index += 1;
+        }
+                            
+        let tab_bar_height = 3;
+        let mut vertical_constraints = vec![Constraint::Length(tab_bar_height)];
+        if show_debug {
+            vertical_constraints.push(Constraint::Length(debug_height));
+        }
+        vertical_constraints.push(Constraint::Min(1));
+        if show_terminal {
+            vertical_constraints.push(Constraint::Length(terminal_height));
+        }
+        vertical_constraints.push(Constraint::Length(1));
+        let editor_layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vertical_constraints)
+            .split(editor_area);
+        let editor_chunk_index = if show_debug { 2 } else { 1 };
+        let text_width = editor_layout[editor_chunk_index].width.saturating_sub(2);
+        let marker_width = if self.tabs[self.active_tab].bookmarks.is_empty() || text_width <= Self::GUTTER_WIDTH {
+            0
+        } else {
+            Self::GUTTER_WIDTH
+        };
+        let digits = self.tabs[self.active_tab].content.len().to_string().len().max(3) as u16 + 1;
+        let number_width = if self.option("number").bool() && text_width > marker_width + digits + Self::GUTTER_WIDTH {
+            digits
+        } else {
+            0
+        };
+        let show_blame = self.blame.as_ref().is_some_and(|blame| blame.loaded())
+            && self.settings.blame_display == BlameDisplay::Column
+            && text_width > marker_width + number_width + Self::BLAME_WIDTH + Self::GUTTER_WIDTH;
+        let gutter = marker_width + number_width + if show_blame { Self::BLAME_WIDTH } else { 0 };
+        self.layout = ScreenLayout {
+            sidebar: (sidebar_width > 0).then(|| main_layout[0]),
+            preview: preview_area,
+            tab_bar: editor_layout[0],
+            debug: show_debug.then(|| editor_layout[1]),
+            editor: editor_layout[editor_chunk_index],
+            status: editor_layout[editor_layout.len() - 1],
+            minimap: (minimap_width > 0).then(|| main_layout[current_layout_index]),
+            outline: outline_area,
+            terminal: show_terminal.then(|| editor_layout[editor_chunk_index + 1]),
+            gutter,
+        };
+        let editor_height = editor_layout[editor_chunk_index].height.saturating_sub(2).max(1) as usize;
+        let editor_width = text_width.saturating_sub(gutter).max(1) as usize;
+        if (editor_height, editor_width) != (self.editor_height, self.editor_width) {
+            self.editor_height = editor_height;
+            self.editor_width = editor_width;
+            self.ensure_cursor_visible();
+            self.adjust_horizontal_scroll();
+        }
+        
+            let tab_titles: Vec<Spans> = self.tab_labels(editor_layout[0].width.saturating_sub(2) as usize).into_iter().enumerate().map(|(i, label)| {
+                let style = if i == self.active_tab {
+                    self.color_config.fg(&self.color_config.tab_active)
+                } else {
+                    self.color_config.fg(&self.color_config.tab_inactive)
+                };
+                let style = if self.tabs[i].flashed.is_some() { style.add_modifier(Modifier::REVERSED) } else { style };
+                Spans::from(vec![
+                    Span::styled(label, style),
+                    Span::raw(" "),
+                ])
+            }).collect();
+        
+        let tab_bar = Tabs::new(tab_titles)
+            .block(Block::default().borders(Borders::ALL).title("Tabs"))
+            .select(self.active_tab)
+            .style(self.color_config.bg(&self.color_config.tab_background))
+            .highlight_style(self.color_config.fg(&self.color_config.tab_active));
+    
+        f.render_widget(tab_bar, editor_layout[0]);
+
+        let mode_indicator = match self.mode {
+            Mode::Normal => "NORMAL",
+            Mode::Insert => "INSERT",
+            Mode::Command => "COMMAND",
+            Mode::Visual => "VISUAL",
+            Mode::VisualBlock => "VISUAL BLOCK",
+            Mode::FileSelect => "FILE SELECT",
+            Mode::DirectoryNav => "DIRECTORY NAV",
+            Mode::Search => "SEARCH",
+            Mode::SidebarActive => "SIDEBAR",
+            Mode::Picker => "PICKER",
+            Mode::Outline => "OUTLINE",
+            Mode::Terminal => "TERMINAL",
+            Mode::Debug => "DEBUG",
+            Mode::Pager => "PAGER",
+        };
+    
+        let title = match &self.tabs[self.active_tab].current_file {
+            Some(file) => format!("Phantom - {} - {}", mode_indicator, self.display_path(file)),
+            None => format!("Phantom - {}", mode_indicator),
+        };
+        let title = if self.tabs[self.active_tab].private { format!("{} [private]", title) } else { title };
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(Span::styled(
+                title,
+                self.color_config.fg(&self.color_config.foreground)
+                    .add_modifier(Modifier::BOLD),
+            ));
+    
+        let syntax = self.ps.find_syntax_by_extension("rs")
+            .or_else(|| self.ps.find_syntax_by_name(&self.syntax))
+            .unwrap_or_else(|| self.ps.find_syntax_plain_text());
+    
+        let theme = self.syntax_theme();
+        let _background_color = self.color_config.color(&self.color_config.background);
+        let _foreground_color = self.color_config.color(&self.color_config.foreground);
+    
+        let mut h = HighlightLines::new(syntax, theme);
+    
+    
+        let active_tab = &self.tabs[self.active_tab];
+        let content = &active_tab.content;
+        let cursor_position = active_tab.cursor_position;
+        let scroll_offset = active_tab.scroll_offset;
+        let wrap = self.wrap_width().is_some();
+        let horizontal_scroll = if wrap { 0 } else { active_tab.horizontal_scroll };
+    
+        let fold_style = self.color_config.bg(&self.color_config.fold).patch(self.color_config.fg(&self.color_config.comment));
+        let marker_style = self.color_config.fg(&self.color_config.bookmark);
+        let blame_style = self.color_config.fg(&self.color_config.comment)
+            .add_modifier(Modifier::DIM);
+        let blame_column = self.blame.as_ref().filter(|_| show_blame);
+        let now = Self::unix_now();
+        let number_style = self.color_config.fg(&self.color_config.comment);
+        let current_number_style = self.color_config.fg(&self.color_config.foreground);
+        let line_number = |line: usize| {
+            let style = if line == cursor_position.1 { current_number_style } else { number_style };
+            Span::styled(format!("{:>width$} ", line + 1, width = number_width as usize - 1), style)
+        };
+        let tab_width = self.option("tabstop").number();
+        let cursor_line = self.option("cursorline").bool();
+        let color_column = self.option("colorcolumn").number();
+        let long_line_threshold = self.settings.long_line_threshold;
+        let plain_style = self.color_config.fg(&self.color_config.foreground);
+        let whitespace_style = self.option("list").bool()
+            .then(|| self.color_config.fg(&self.color_config.whitespace));
+        let line_style = self.color_config.bg(&self.color_config.cursor_line);
+        let indent_style = self.color_config.bg(&self.color_config.whitespace);
+        let git_file = active_tab.git_file().filter(|_| active_tab.syntax == "Plain Text");
+        let search = Some(self.search_query.text.as_str())
+            .filter(|query| self.search_highlight && self.settings.hlsearch && self.mode != Mode::Search && !query.is_empty())
+            .map(|query| SearchPattern::literal(query, true));
+        let search_style = self.color_config.highlight(&self.color_config.search_match);
+        let indent_guides = self.option("indentguides").bool();
+        let guide_style = self.color_config.fg(&self.color_config.indent_guide);
+        let active_guide_style = self.color_config.fg(&self.color_config.indent_guide_active);
+        let mut guide_levels = GuideLevels::new(content, tab_width);
+        let active_guide = indent_guides.then(|| GuideLevels::new(content, tab_width).active_block(cursor_position.1, editor_height)).flatten();
+        let mut text = Vec::new();
+        let mut wrapped_cursor = None;
+        let mut y = active_tab.fold_at(scroll_offset).map_or(scroll_offset, |(start, _)| start);
+        while text.len() < editor_height && y < content.len() {
+            let line = &content[y];
+            if let Some((start, end)) = active_tab.fold_at(y) {
+                for hidden in &content[start..=end] {
+                    let _ = h.highlight_line(hidden, &self.ps);
+                }
+                let label = format!("+-- {} lines: {} ", end - start + 1, line.trim());
+                let label = format!("{:<width$}", label, width = editor_width);
+                let cursor = (start <= cursor_position.1 && cursor_position.1 <= end).then_some((0, self.cursor_style));
+                if cursor.is_some() {
+                    wrapped_cursor = Some((text.len(), 0));
+                }
+                let mut spans = render_line(&[(fold_style, label.as_str())], 0, editor_width, &[], cursor, tab_width, &WhitespaceMarks::default());
+                if number_width > 0 {
+                    spans.insert(0, line_number(start));
+                }
+                if marker_width > 0 {
+                    let marked = active_tab.bookmarks.iter().any(|&bookmark| start <= bookmark && bookmark <= end);
+                    spans.insert(0, Span::styled(if marked { "● " } else { "  " }, marker_style));
+                }
+                if let Some(blame) = blame_column {
+                    spans.insert(0, Span::styled(blame.label(start, now), blame_style));
+                }
+                text.push(Spans::from(spans));
+                y = end + 1;
+                continue;
+            }
+            let long_line = line.len() > long_line_threshold;
+            let rows = if wrap { active_tab.wrapped_rows(y, editor_width, tab_width).min(editor_height - text.len()) } else { 1 };
+            let window_width = editor_width * rows;
+            let (first, last) = if long_line {
+                long_line_window(line, horizontal_scroll, window_width, tab_width)
+            } else {
+                (0, line.len())
+            };
+            let visible = &line[first..last];
+            let offset = line_width(&line[..first], tab_width);
+            let base = offset - offset % tab_width;
+            let lead = " ".repeat(offset - base);
+            let mut ranges = if long_line {
+                vec![(Style::default(), lead.as_str()), (plain_style, visible)]
+            } else if let Some(kind) = git_file {
+                self.git_highlight(kind, y, line)
+            } else {
+                self.highlight_ranges(&mut h, line)
+            };
+            let trailing = whitespace_style.filter(|_| last == line.len()).map_or(0, |_| visible.len() - visible.trim_end_matches(' ').len());
+            let dots = "·".repeat(trailing);
+            if let Some(style) = whitespace_style.filter(|_| trailing > 0) {
+                let mut kept = lead.len() + visible.len() - trailing;
+                ranges.retain_mut(|(_, content)| {
+                    *content = &content[..kept.min(content.len())];
+                    kept -= content.len();
+                    !content.is_empty()
+                });
+                ranges.push((style, &dots));
+            }
+            let column = |byte: usize| line_width(line.get(..byte).unwrap_or(line), tab_width);
+            let highlight_line = cursor_line && y == cursor_position.1;
+            let level = if indent_guides && !long_line { guide_levels.level(y) } else { 0 };
+            let end = offset + line_width(visible, tab_width);
+            let padding = if highlight_line || color_column > 0 {
+                " ".repeat((horizontal_scroll + window_width).saturating_sub(end))
+            } else {
+                " ".repeat(level.saturating_sub(end))
+            };
+            let marks = WhitespaceMarks {
+                list: whitespace_style,
+                guides: guide_levels.columns(level)
+                    .map(|column| match active_guide {
+                        Some((active, first, last)) if active == column && first <= y && y <= last => (column, active_guide_style),
+                        _ => (column, guide_style),
+                    })
+                    .collect(),
+            };
+            ranges.push((Style::default(), &padding));
+
+            let mut overlays = Vec::new();
+            if highlight_line {
+                overlays.push((Layer::CursorLine, 0, usize::MAX, line_style));
+            }
+            if color_column > 0 {
+                overlays.push((Layer::ColorColumn, color_column - 1, color_column, line_style));
+            }
+            let indent = leading_whitespace(line);
+            if active_tab.indent_style.is_some_and(|style| indent_disagrees(style, indent)) {
+                overlays.push((Layer::Diagnostic, 0, column(indent.len()), indent_style));
+            }
+            if let Some(search) = search.as_ref().filter(|_| !long_line) {
+                for found in search.matches(line) {
+                    overlays.push((Layer::SearchMatch, column(found.start), column(found.end), search_style));
+                }
+            }
+            if let Some((start, end)) = self.mouse_selection().map(|(start, end)| active_tab.selection_bounds(start, end))
+            {
+                if y >= start.1 && y <= end.1 {
+                    let from = if y == start.1 { column(start.0) } else { 0 };
+                    let to = if y == end.1 { column(end.0) } else { usize::MAX };
+                    overlays.push((Layer::Selection, from, to, self.color_config.highlight("fg=black bg=white")));
+                }
+            }
+            if let Some((_, start, end)) = self.substitute_confirm.as_ref().map(|confirm| confirm.current).filter(|current| current.0 == y) {
+                overlays.push((Layer::CurrentMatch, column(start), column(end), self.color_config.highlight(&self.color_config.selection)));
+            }
+            if self.mode == Mode::VisualBlock {
+                let (top, bottom, left, right) = self.block_bounds();
+                if y >= top && y <= bottom {
+                    let width = line_width(line, tab_width);
+                    overlays.push((
+                        Layer::Selection,
+                        left.min(width),
+                        (right + 1).min(width),
+                        self.color_config.highlight(&self.color_config.selection),
+                    ));
+                }
+            }
+            overlays.sort_by_key(|&(layer, ..)| layer);
+            let overlays: Vec<(usize, usize, Style)> = overlays.into_iter()
+                .map(|(_, from, to, style)| (from.saturating_sub(base), to.saturating_sub(base), style))
+                .collect();
+            let cursor_row = (y == cursor_position.1).then(|| self.wrapped_cursor_row());
+            for row in 0..rows {
+                let scroll = horizontal_scroll + row * editor_width;
+                let cursor = cursor_row.filter(|&at| at == row).map(|_| (column(cursor_position.0).saturating_sub(base), self.cursor_style));
+                if cursor.is_some() {
+                    wrapped_cursor = Some((text.len(), column(cursor_position.0).saturating_sub(scroll)));
+                }
+                let mut spans = render_line(&ranges, scroll - base, editor_width, &overlays, cursor, tab_width, &marks);
+                if row > 0 {
+                    spans.insert(0, Span::raw(" ".repeat(gutter as usize)));
+                    text.push(Spans::from(spans));
+                    continue;
+                }
+                if number_width > 0 {
+                    spans.insert(0, line_number(y));
+                }
+                if marker_width > 0 {
+                    let marker = if active_tab.bookmarks.binary_search(&y).is_ok() { "● " } else { "  " };
+                    spans.insert(0, Span::styled(marker, marker_style));
+                }
+                if let Some(blame) = blame_column {
+                    spans.insert(0, Span::styled(blame.label(y, now), blame_style));
+                }
+                text.push(Spans::from(spans));
+            }
+            y += 1;
+        }
+            
+        let paragraph = Paragraph::new(text)
+            .block(block)
+            .style(self.color_config.bg(&self.color_config.background));
+        f.render_widget(paragraph, editor_layout[editor_chunk_index]);
+        let start_screen = self.start_screen_active();
+        if start_screen {
+            self.render_start_screen(f, self.layout.text_area());
+        }
+    
+        if let Some(debug_area) = self.layout.debug {
+            let rows = debug_area.height.saturating_sub(2) as usize;
+            let entries = self.debug_entries();
+            let max_scroll = entries.len().saturating_sub(rows);
+            let scroll = self.debug_scroll.min(max_scroll);
+            let end = entries.len() - scroll;
+            let debug_messages: Vec<Spans> = entries[end.saturating_sub(rows)..end].iter()
+                .map(|entry| {
+                    let level_style = match entry.level {
+                        LogLevel::Error => self.color_config.fg(&self.color_config.message_error),
+                        LogLevel::Warn => self.color_config.fg(&self.color_config.message_warn),
+                        LogLevel::Info => self.color_config.fg(&self.color_config.message_info),
+                        _ => self.color_config.fg(&self.color_config.comment),
+                    };
+                    Spans::from(vec![
+                        Span::styled(format!("{:<5} ", entry.level), level_style),
+                        Span::raw(entry.text.clone()),
+                    ])
+                })
+                .collect();
+            let mut title = format!("Debug Output [{}+]", self.settings.debug_level);
+            if scroll > 0 {
+                title.push_str(&format!(" (+{})", scroll));
+            }
+            let mut title_style = self.color_config.fg(&self.color_config.foreground);
+            if self.mode == Mode::Debug {
+                title_style = title_style.add_modifier(Modifier::BOLD);
+            }
+            self.debug_scroll = scroll;
+            let debug_paragraph = Paragraph::new(debug_messages)
+                .block(Block::default().borders(Borders::ALL).title(Span::styled(title, title_style)));
+            f.render_widget(debug_paragraph, debug_area);
+        }
+    
+        let mut input_cursor = None;
+        if self.mode == Mode::Command {
+            let command_text = Spans::from(format!(":{}", self.command_buffer.text));
+            let command_paragraph = Paragraph::new(vec![command_text]);
+            let area = editor_layout[editor_layout.len() - 1];
+            f.render_widget(command_paragraph, area);
+            input_cursor = Some((area, 1 + self.command_buffer.cursor as u16));
+        } else if self.mode == Mode::Search {
+            let search_text = Spans::from(format!("Search: {}", self.search_query.text));
+            let search_paragraph = Paragraph::new(vec![search_text]);
+            let area = editor_layout[editor_layout.len() - 1];
+            f.render_widget(search_paragraph, area);
+            input_cursor = Some((area, 8 + self.search_query.cursor as u16));
+        } else if let Some(confirm) = &self.substitute_confirm {
+            let question = format!("replace with {}? (y/n/a/q/l)", confirm.replacement);
+            let width = display_width(&question) as u16;
+            let style = self.color_config.fg(&self.color_config.message_info);
+            let area = editor_layout[editor_layout.len() - 1];
+            f.render_widget(Paragraph::new(vec![Spans::from(Span::styled(question, style))]), area);
+            input_cursor = Some((area, width));
+        } else {
+            if let Some(message) = &self.status_message {
+                let color = match message.level {
+                    MessageLevel::Info => &self.color_config.message_info,
+                    MessageLevel::Warn => &self.color_config.message_warn,
+                    MessageLevel::Error => &self.color_config.message_error,
+                };
+                let style = self.color_config.fg(color);
+                let status_paragraph = Paragraph::new(vec![Spans::from(Span::styled(message.text.clone(), style))]);
+                f.render_widget(status_paragraph, editor_layout[editor_layout.len() - 1]);
+            } else if let Some(blame) = self.blame_status().filter(|_| self.settings.blame_display == BlameDisplay::Status) {
+                let style = self.color_config.fg(&self.color_config.comment);
+                let blame_paragraph = Paragraph::new(vec![Spans::from(Span::styled(blame, style))]);
+                f.render_widget(blame_paragraph, editor_layout[editor_layout.len() - 1]);
+            }
+            let mut indicators = Vec::new();
+            indicators.extend(self.task_indicator());
+            if let Some(keys) = &self.pending_key {
+                indicators.push(keys.clone());
+            }
+            if active_tab.bom {
+                indicators.push("[BOM]".to_string());
+            }
+            if !active_tab.eol {
+                indicators.push("[noeol]".to_string());
+            }
+            if let Some(words) = self.live_word_count() {
+                indicators.push(format!("{} words", words));
+            }
+            if !indicators.is_empty() {
+                let right = Paragraph::new(vec![Spans::from(format!("{} ", indicators.join(" ")))])
+                    .alignment(Alignment::Right);
+                f.render_widget(right, editor_layout[editor_layout.len() - 1]);
+            }
+        }
+    
+        let text_area = editor_layout[editor_chunk_index];
+        let cursor_column = if active_tab.fold_at(cursor_position.1).is_some() {
+            horizontal_scroll
+        } else {
+            active_tab.visual_column(cursor_position.1, cursor_position.0, tab_width)
+        };
+        let cursor_row = active_tab.line_to_row(cursor_position.1).saturating_sub(active_tab.line_to_row(scroll_offset));
+        let (cursor_column, cursor_row) = wrapped_cursor.filter(|_| wrap).map_or((cursor_column, cursor_row), |(row, column)| (column, row));
+        let cursor_x = text_area.x + 1 + gutter + cursor_column.saturating_sub(horizontal_scroll).min(u16::MAX as usize) as u16;
+        let cursor_y = text_area.y + 1 + cursor_row.min(u16::MAX as usize) as u16;
+    
+        let terminal_cursor = self.layout.terminal.and_then(|area| self.render_terminal(f, area));
+        if let Some((area, offset)) = input_cursor {
+            f.set_cursor((area.x + offset).min(area.right().saturating_sub(1)), area.y);
+        } else if let Some((x, y)) = terminal_cursor.filter(|_| self.mode == Mode::Terminal) {
+            f.set_cursor(x, y);
+        } else if !start_screen {
+            f.set_cursor(
+                cursor_x.min(text_area.right().saturating_sub(2)),
+                cursor_y.min(text_area.bottom().saturating_sub(2))
+            );
+        }
+
+        if let Some(outline_area) = self.layout.outline {
+            self.render_outline(f, outline_area);
+        }
+
+        if let Some(minimap_area) = self.layout.minimap {
+            self.render_minimap(f, minimap_area);
+        }
+
+        self.render_completion(f, text_area, (cursor_x, cursor_y));
+
+        if let Some(picker) = &self.picker {
+            picker.render(f, size, &self.color_config);
+        }
+        if let Some(output) = &mut self.output {
+            output.render(f, size, &self.color_config);
+        }
+        if let Some(rename) = &mut self.rename {
+            rename.render(f, size, &self.color_config);
+        }
+        match &mut self.prompt {
+            Some(Prompt::Confirm(prompt)) => prompt.render(f, size, &self.color_config),
+            Some(Prompt::Input(prompt)) => prompt.render(f, size, &self.color_config),
+            None => {}
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use std::mem::{size_of, size_of_val};
+
+use crate::buffer::{EditOperation, Tab};
+use crate::editor::Editor;
+use crate::preview::format_size;
+use crate::ui::PromptAction;
+
+const MEGABYTE: usize = 1024 * 1024;
+
+pub(crate) fn snapshot_size(content: &[String], bookmarks: &[usize], folds: &[(usize, usize)]) -> usize {
+    size_of::<EditOperation>()
+        + content.iter().map(|line| line.len() + size_of::<String>()).sum::<usize>()
+        + size_of_val(bookmarks)
+        + size_of_val(folds)
+}
+
+impl Tab {
+    pub(crate) fn undo_memory(&self) -> usize {
+        self.undo_stack.iter().chain(&self.redo_stack).map(|operation| operation.size).sum()
+    }
+
+    fn trim_undo(&mut self, levels: usize, memory_limit: usize) -> bool {
+        self.undo_stack.truncate(levels);
+        if memory_limit == 0 {
+            return false;
+        }
+        let mut memory = self.undo_memory();
+        let mut trimmed = false;
+        while memory > memory_limit && self.undo_stack.len() > 1 {
+            memory -= self.undo_stack.pop_back().map_or(0, |operation| operation.size);
+            trimmed = true;
+        }
+        trimmed
+    }
+}
+
+impl Editor {
+    pub(crate) fn trim_undo_history(&mut self, tab_index: usize) {
+        let memory_limit = self.settings.undo_memory_limit.saturating_mul(MEGABYTE);
+        let tab = &mut self.tabs[tab_index];
+        if !tab.trim_undo(self.settings.undo_levels, memory_limit) || std::mem::replace(&mut tab.undo_trimmed, true) {
+            return;
+        }
+        let message = format!("Undo history of {} trimmed to {} changes to stay under undo_memory_limit", Self::tab_title(tab, tab_index), tab.undo_stack.len());
+        self.warn(message);
+    }
+
+    pub(crate) fn trim_all_undo_history(&mut self) {
+        for tab_index in 0..self.tabs.len() {
+            self.trim_undo_history(tab_index);
+        }
+    }
+
+    pub(crate) fn show_undo_list(&mut self) {
+        let tab = &self.tabs[self.active_tab];
+        if tab.undo_stack.is_empty() && tab.redo_stack.is_empty() {
+            self.info("No undo history");
+            return;
+        }
+        let limit = match self.settings.undo_memory_limit {
+            0 => String::new(),
+            limit => format!(" of {}", format_size((limit * MEGABYTE) as u64)),
+        };
+        let title = format!("Undo history: {} changes, {} redo, {}{}", tab.undo_stack.len(), tab.redo_stack.len(), format_size(tab.undo_memory() as u64), limit);
+        let lines = tab.undo_stack.iter()
+            .enumerate()
+            .map(|(index, operation)| {
+                let (x, y) = operation.cursor_position;
+                format!("{:>4}  {:>10}  {} lines, cursor {}:{}", index + 1, format_size(operation.size as u64), operation.content.len(), y + 1, x + 1)
+            })
+            .collect();
+        self.show_output(&title, lines);
+    }
+
+    pub(crate) fn clear_undo_command(&mut self, force: bool) {
+        let tab = &self.tabs[self.active_tab];
+        let changes = tab.undo_stack.len() + tab.redo_stack.len();
+        if changes == 0 {
+            self.info("No undo history");
+        } else if force {
+            self.clear_undo(self.active_tab);
+        } else {
+            let message = format!("Clear the undo history of {} ({} changes, {})?", Self::tab_title(tab, self.active_tab), changes, format_size(tab.undo_memory() as u64));
+            self.confirm(PromptAction::ClearUndo(self.active_tab), &message, "Clear", "Keep");
+        }
+    }
+
+    pub(crate) fn clear_undo(&mut self, tab_index: usize) {
+        let Some(tab) = self.tabs.get_mut(tab_index) else {
+            return;
+        };
+        let memory = tab.undo_memory();
+        tab.undo_stack.clear();
+        tab.redo_stack.clear();
+        tab.undo_trimmed = false;
+        self.info(format!("Cleared undo history ({})", format_size(memory as u64)));
+    }
+}
@@ -0,0 +1,58 @@
+use std::fs;
+use std::path::PathBuf;
+
+use phantom::{unified_diff, Batch};
+
+fn temp_file(name: &str, content: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("phantom-batch-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    fs::write(&path, content).unwrap();
+    path
+}
+
+fn batch(commands: &[&str], dry_run: bool) -> Batch {
+    Batch { commands: commands.iter().map(|c| c.to_string()).collect(), dry_run }
+}
+
+fn lines(text: &str) -> Vec<String> {
+    text.lines().map(String::from).collect()
+}
+
+#[test]
+fn substitute_honours_range_and_flags() {
+    let path = temp_file("substitute.txt", "foo Foo foo\nfoo\nbar\n");
+    batch(&[":1s/foo/x/", ":2,$s/FOO/y/gi", ":1s#Foo#a/b#", ":wq"], false).run_file(&path).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "x a/b foo\ny\nbar\n");
+}
+
+#[test]
+fn failing_commands_stop_the_batch() {
+    let path = temp_file("failing.txt", "alpha\n");
+    let error = batch(&[":%s/beta/gamma/", ":wq"], false).run_file(&path).unwrap_err();
+    assert_eq!(error, ":%s/beta/gamma/: Pattern not found: beta");
+    assert!(batch(&[":%s/alpha/beta/", ":q"], false).run_file(&path).is_err());
+    assert!(batch(&[":oldfiles"], false).run_file(&path).is_err());
+    assert!(batch(&[":%s/alpha/beta/c", ":wq"], false).run_file(&path).is_err());
+    assert_eq!(fs::read_to_string(&path).unwrap(), "alpha\n");
+}
+
+#[test]
+fn dry_run_prints_a_diff_without_writing() {
+    let path = temp_file("dry.txt", "one\ntwo\nthree\n");
+    let diff = batch(&[":2s/two/2/", ":$t0", ":wq"], true).run_file(&path).unwrap();
+    let name = path.to_string_lossy();
+    assert_eq!(diff, format!("--- {name}\n+++ {name}\n@@ -1,3 +1,4 @@\n+three\n one\n-two\n+2\n three\n"));
+    assert_eq!(fs::read_to_string(&path).unwrap(), "one\ntwo\nthree\n");
+}
+
+#[test]
+fn unified_diff_splits_distant_changes_into_hunks() {
+    let old = lines("1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12");
+    let new = lines("one\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11");
+    assert_eq!(
+        unified_diff("a", "b", &old, &new),
+        "--- a\n+++ b\n@@ -1,4 +1,4 @@\n-1\n+one\n 2\n 3\n 4\n@@ -9,4 +9,3 @@\n 9\n 10\n 11\n-12\n"
+    );
+    assert_eq!(unified_diff("a", "b", &old, &old), "");
+}
@@ -0,0 +1,160 @@
+use phantom::{parse_color, parse_style, ColorMode, StyleSpec};
+use tui::style::{Color, Modifier, Style};
+
+#[test]
+fn parses_hex_colors() {
+    assert_eq!(parse_color("#1e90ff"), Ok(Color::Rgb(0x1e, 0x90, 0xff)));
+    assert_eq!(parse_color("#1E90FF"), Ok(Color::Rgb(0x1e, 0x90, 0xff)));
+    assert_eq!(parse_color("#f80"), Ok(Color::Rgb(0xff, 0x88, 0x00)));
+}
+
+#[test]
+fn parses_named_colors() {
+    assert_eq!(parse_color("red"), Ok(Color::Red));
+    assert_eq!(parse_color("white"), Ok(Color::Gray));
+    assert_eq!(parse_color("bright_blue"), Ok(Color::LightBlue));
+    assert_eq!(parse_color("Bright-White"), Ok(Color::White));
+    assert_eq!(parse_color("bright_black"), Ok(Color::DarkGray));
+    assert_eq!(parse_color("default"), Ok(Color::Reset));
+}
+
+#[test]
+fn parses_palette_indexes() {
+    assert_eq!(parse_color("0"), Ok(Color::Indexed(0)));
+    assert_eq!(parse_color("214"), Ok(Color::Indexed(214)));
+    assert_eq!(parse_color("255"), Ok(Color::Indexed(255)));
+}
+
+#[test]
+fn rejects_malformed_colors_without_panicking() {
+    for input in ["", "#", "#12", "#1234", "#12345g", "#ééé", "é", "256", "-1", "reddish", "rgb(1,2,3)", "#ffffff00"] {
+        let error = parse_color(input).unwrap_err();
+        assert!(error.contains(&format!("{:?}", input.trim())), "{}", error);
+    }
+}
+
+#[test]
+fn downconverts_to_smaller_palettes() {
+    assert_eq!(ColorMode::TrueColor.convert(Color::Rgb(1, 2, 3)), Color::Rgb(1, 2, 3));
+    assert_eq!(ColorMode::Palette256.convert(Color::Rgb(255, 135, 0)), Color::Indexed(208));
+    assert_eq!(ColorMode::Palette256.convert(Color::Rgb(40, 40, 40)), Color::Indexed(235));
+    assert_eq!(ColorMode::Palette256.convert(Color::Red), Color::Red);
+    assert_eq!(ColorMode::Palette16.convert(Color::Rgb(250, 10, 10)), Color::LightRed);
+    assert_eq!(ColorMode::Palette16.convert(Color::Rgb(20, 20, 20)), Color::Black);
+    assert_eq!(ColorMode::Palette16.convert(Color::Indexed(21)), Color::Blue);
+    assert_eq!(ColorMode::Palette16.convert(Color::Indexed(9)), Color::LightRed);
+    assert_eq!(ColorMode::NoColor.convert(Color::Rgb(250, 10, 10)), Color::Reset);
+    assert_eq!(ColorMode::NoColor.convert(Color::Indexed(9)), Color::Reset);
+}
+
+#[test]
+fn invalid_config_fields_are_reported_by_name() {
+    let dir = std::env::temp_dir().join(format!("phantom-colors-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    phantom::Editor::new(Some(dir.clone()));
+    let path = dir.join("colors.json");
+    let mut colors: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    colors["tab_active"] = "#12".into();
+    colors["tab_inactive"] = "bright_cyan".into();
+    colors["color_mode"] = "256".into();
+    std::fs::write(&path, colors.to_string()).unwrap();
+
+    let editor = phantom::Editor::new(Some(dir));
+    let message = editor.status_message().unwrap_or_default();
+    assert!(message.starts_with("colors.json: tab_active: invalid color \"#12\""), "{}", message);
+}
+
+#[test]
+fn colorscheme_switches_presets_and_keeps_overrides() {
+    let dir = std::env::temp_dir().join(format!("phantom-presets-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("colors.json"), r##"{"preset": "dark", "tab_active": "red"}"##).unwrap();
+    let mut editor = phantom::Editor::new(Some(dir.clone()));
+    let mut terminal = tui::Terminal::new(tui::backend::TestBackend::new(80, 24)).unwrap();
+
+    for preset in ["dark", "light", "solarized-dark", "solarized-light", "gruvbox"] {
+        editor.execute_command_line(&format!("colorscheme {}", preset)).unwrap();
+        assert_eq!(editor.status_message(), Some(format!("Color scheme: {}", preset).as_str()));
+        terminal.draw(|f| editor.ui(f)).unwrap();
+    }
+    editor.execute_command_line("colorscheme nope").unwrap();
+    assert!(editor.status_message().unwrap().starts_with("Unknown color preset: nope"));
+
+    editor.execute_command_line("colorscheme! light").unwrap();
+    let saved: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(dir.join("colors.json")).unwrap()).unwrap();
+    assert_eq!(saved["preset"], "light");
+    assert_eq!(saved["tab_active"], "red");
+    assert_eq!(saved["background"], "#FAFAFA");
+}
+
+#[test]
+fn parses_style_specs_and_legacy_colors() {
+    assert_eq!(parse_style("#1E1E1E"), Ok(StyleSpec { color: Some(Color::Rgb(0x1e, 0x1e, 0x1e)), ..StyleSpec::default() }));
+    assert_eq!(parse_style("bright blue"), Ok(StyleSpec { color: Some(Color::LightBlue), ..StyleSpec::default() }));
+    assert_eq!(
+        parse_style("fg=#61AFEF bg=235 bold Italic"),
+        Ok(StyleSpec {
+            color: None,
+            fg: Some(Color::Rgb(0x61, 0xaf, 0xef)),
+            bg: Some(Color::Indexed(235)),
+            modifiers: Modifier::BOLD | Modifier::ITALIC,
+        })
+    );
+    assert_eq!(parse_style("red underline").unwrap().fg(), Style::default().fg(Color::Red).add_modifier(Modifier::UNDERLINED));
+    assert_eq!(parse_style("red underline").unwrap().bg(), Style::default().bg(Color::Red).add_modifier(Modifier::UNDERLINED));
+    assert_eq!(parse_style("fg=red bg=blue").unwrap().bg(), Style::default().fg(Color::Red).bg(Color::Blue));
+
+    assert!(parse_style("fg=#12 bold").unwrap_err().contains("\"#12\""));
+    assert!(parse_style("red boldd").unwrap_err().starts_with("invalid style \"boldd\""));
+    assert!(parse_style("red blue").unwrap_err().starts_with("more than one color"));
+    assert!(parse_style("").is_err());
+}
+
+#[test]
+fn style_specs_render_and_survive_saving() {
+    let dir = std::env::temp_dir().join(format!("phantom-styles-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("colors.json"), r##"{
+        "preset": "dark",
+        "tab_active": "fg=#FF0000 bold",
+        "comment": {"fg": "#888888", "modifiers": ["italic"]},
+        "tab_inactive": "#7F848E"
+    }"##).unwrap();
+    let mut editor = phantom::Editor::new(Some(dir.clone()));
+    assert_eq!(editor.status_message(), None);
+    let mut terminal = tui::Terminal::new(tui::backend::TestBackend::new(40, 10)).unwrap();
+    terminal.draw(|f| editor.ui(f)).unwrap();
+    let buffer = terminal.backend().buffer();
+    let active = (0..40).map(|x| buffer.get(x, 1)).find(|cell| cell.symbol == "1").unwrap();
+    assert_eq!(active.fg, Color::Rgb(255, 0, 0));
+    assert!(active.modifier.contains(Modifier::BOLD));
+
+    editor.execute_command_line("colorscheme! light").unwrap();
+    let saved: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(dir.join("colors.json")).unwrap()).unwrap();
+    assert_eq!(saved["tab_active"], "fg=#FF0000 bold");
+    assert_eq!(saved["comment"], "fg=#888888 italic");
+    assert_eq!(saved["tab_inactive"], "#A0A1A7");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn transparent_preset_leaves_the_terminal_background_alone() {
+    let dir = std::env::temp_dir().join(format!("phantom-transparent-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("colors.json"), r##"{"preset": "transparent", "selection": "default", "tab_active": "fg=red bg=none"}"##).unwrap();
+    let mut editor = phantom::Editor::new(Some(dir.clone()));
+    let message = editor.status_message().unwrap_or_default().to_string();
+    assert!(message.starts_with("colors.json: selection: \"default\" would hide the highlight"), "{}", message);
+    assert_eq!(parse_style("default"), Ok(StyleSpec::default()));
+    assert_eq!(parse_style("fg=red bg=none").unwrap().bg(), Style::default().fg(Color::Red));
+
+    let mut terminal = tui::Terminal::new(tui::backend::TestBackend::new(40, 10)).unwrap();
+    terminal.draw(|f| editor.ui(f)).unwrap();
+    let buffer = terminal.backend().buffer();
+    for y in 3..9 {
+        assert!((0..40).all(|x| buffer.get(x, y).bg == Color::Reset), "background painted on row {}", y);
+    }
+    let active = (0..40).map(|x| buffer.get(x, 1)).find(|cell| cell.symbol == "1").unwrap();
+    assert_eq!((active.fg, active.bg), (Color::Red, Color::Reset));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,181 @@
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use phantom::Editor;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use tui::backend::TestBackend;
+use tui::layout::Rect;
+use tui::Terminal;
+use unicode_width::UnicodeWidthStr;
+
+pub fn keys(notation: &str) -> Vec<KeyEvent> {
+    let mut events = Vec::new();
+    let mut chars = notation.chars();
+    while let Some(c) = chars.next() {
+        if c != '<' {
+            let modifiers = if c.is_ascii_uppercase() { KeyModifiers::SHIFT } else { KeyModifiers::NONE };
+            events.push(KeyEvent::new(KeyCode::Char(c), modifiers));
+            continue;
+        }
+        let name: String = chars.by_ref().take_while(|&c| c != '>').collect();
+        events.push(special_key(&name));
+    }
+    events
+}
+
+fn special_key(name: &str) -> KeyEvent {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut rest = name;
+    while let Some((prefix, tail)) = rest.split_once('-').filter(|(_, tail)| !tail.is_empty()) {
+        modifiers |= match prefix {
+            "C" => KeyModifiers::CONTROL,
+            "S" => KeyModifiers::SHIFT,
+            "A" | "M" => KeyModifiers::ALT,
+            _ => panic!("unknown modifier in <{}>", name),
+        };
+        rest = tail;
+    }
+    let code = match rest {
+        "Esc" => KeyCode::Esc,
+        "CR" | "Enter" => KeyCode::Enter,
+        "Tab" if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::BackTab,
+        "Tab" => KeyCode::Tab,
+        "BS" => KeyCode::Backspace,
+        "Del" => KeyCode::Delete,
+        "Up" => KeyCode::Up,
+        "Down" => KeyCode::Down,
+        "Left" => KeyCode::Left,
+        "Right" => KeyCode::Right,
+        "Home" => KeyCode::Home,
+        "End" => KeyCode::End,
+        "PageUp" => KeyCode::PageUp,
+        "PageDown" => KeyCode::PageDown,
+        "Insert" => KeyCode::Insert,
+        "Space" => KeyCode::Char(' '),
+        "lt" => KeyCode::Char('<'),
+        _ if rest.starts_with('F') && rest.len() > 1 => KeyCode::F(rest[1..].parse().unwrap_or_else(|_| panic!("unknown key <{}>", name))),
+        _ if rest.chars().count() == 1 => KeyCode::Char(rest.chars().next().unwrap()),
+        _ => panic!("unknown key <{}>", name),
+    };
+    KeyEvent::new(code, modifiers)
+}
+
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("phantom-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        TempDir { path }
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
+
+pub struct Harness {
+    pub editor: Editor,
+    pub terminal: Terminal<TestBackend>,
+}
+
+impl Harness {
+    pub fn new(width: u16, height: u16) -> Self {
+        let mut harness = Harness {
+            editor: Editor::headless(),
+            terminal: Terminal::new(TestBackend::new(width, height)).unwrap(),
+        };
+        harness.draw();
+        harness
+    }
+
+    pub fn draw(&mut self) {
+        let editor = &mut self.editor;
+        self.terminal.draw(|f| editor.ui(f)).unwrap();
+    }
+
+    pub fn send(&mut self, event: Event) -> bool {
+        let quit = self.editor.handle_event(event).unwrap();
+        self.draw();
+        quit
+    }
+
+    pub fn type_str(&mut self, notation: &str) -> bool {
+        keys(notation).into_iter().any(|key| self.send(Event::Key(key)))
+    }
+
+    pub fn click(&mut self, column: u16, row: u16) {
+        self.mouse(MouseEventKind::Down(MouseButton::Left), column, row);
+    }
+
+    pub fn shift_click(&mut self, column: u16, row: u16) {
+        self.send(Event::Mouse(MouseEvent { kind: MouseEventKind::Down(MouseButton::Left), column, row, modifiers: KeyModifiers::SHIFT }));
+    }
+
+    pub fn drag(&mut self, column: u16, row: u16) {
+        self.mouse(MouseEventKind::Drag(MouseButton::Left), column, row);
+    }
+
+    pub fn release(&mut self, column: u16, row: u16) {
+        self.mouse(MouseEventKind::Up(MouseButton::Left), column, row);
+    }
+
+    pub fn scroll(&mut self, down: bool, column: u16, row: u16) {
+        self.mouse(if down { MouseEventKind::ScrollDown } else { MouseEventKind::ScrollUp }, column, row);
+    }
+
+    fn mouse(&mut self, kind: MouseEventKind, column: u16, row: u16) {
+        self.send(Event::Mouse(MouseEvent { kind, column, row, modifiers: KeyModifiers::NONE }));
+    }
+
+    pub fn resize(&mut self, width: u16, height: u16) {
+        self.terminal.backend_mut().resize(width, height);
+        self.terminal.resize(Rect::new(0, 0, width, height)).unwrap();
+        self.send(Event::Resize(width, height));
+    }
+
+    pub fn lines(&self) -> Vec<&str> {
+        self.editor.active_tab().lines().iter().map(String::as_str).collect()
+    }
+
+    pub fn rows(&self) -> Vec<String> {
+        let buffer = self.terminal.backend().buffer();
+        (0..buffer.area.height).map(|y| {
+            let mut row = String::new();
+            let mut x = 0;
+            while x < buffer.area.width {
+                let symbol = &buffer.get(x, y).symbol;
+                row.push_str(symbol);
+                x += symbol.width().max(1) as u16;
+            }
+            row
+        }).collect()
+    }
+
+    pub fn screen(&self) -> String {
+        self.rows().join("\n")
+    }
+
+    pub fn cursor_cell(&mut self) -> String {
+        let (x, y) = self.terminal.get_cursor().unwrap();
+        self.terminal.backend().buffer().get(x, y).symbol.clone()
+    }
+}
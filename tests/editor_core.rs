@@ -0,0 +1,575 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use phantom::{frame_wait, Editor, Mode, Tab, TextStats};
+
+fn press(editor: &mut Editor, code: KeyCode) {
+    editor.dispatch_key_event(KeyEvent::new(code, KeyModifiers::NONE)).unwrap();
+}
+
+fn type_text(editor: &mut Editor, text: &str) {
+    for c in text.chars() {
+        match c {
+            '\n' => press(editor, KeyCode::Enter),
+            _ => press(editor, KeyCode::Char(c)),
+        }
+    }
+}
+
+fn lines(tab: &Tab) -> Vec<&str> {
+    tab.lines().iter().map(String::as_str).collect()
+}
+
+#[test]
+fn insert_mode_typing_edits_the_buffer() {
+    let mut editor = Editor::headless();
+    press(&mut editor, KeyCode::Char('i'));
+    assert_eq!(editor.mode(), Mode::Insert);
+    type_text(&mut editor, "hello\nworld");
+    press(&mut editor, KeyCode::Esc);
+
+    assert_eq!(editor.mode(), Mode::Normal);
+    assert_eq!(lines(editor.active_tab()), ["hello", "world"]);
+    assert!(editor.active_tab().is_modified());
+}
+
+#[test]
+fn undo_reverts_a_whole_insert_session() {
+    let mut editor = Editor::headless();
+    press(&mut editor, KeyCode::Char('i'));
+    type_text(&mut editor, "one two");
+    press(&mut editor, KeyCode::Esc);
+    press(&mut editor, KeyCode::Char('u'));
+    assert_eq!(lines(editor.active_tab()), [""]);
+
+    editor.execute_action("redo").unwrap();
+    assert_eq!(lines(editor.active_tab()), ["one two"]);
+}
+
+#[test]
+fn insert_sessions_undo_as_one_step_at_the_buffer_edges() {
+    let mut editor = Editor::headless();
+    press(&mut editor, KeyCode::Char('u'));
+    editor.execute_action("redo").unwrap();
+    assert_eq!(lines(editor.active_tab()), [""]);
+    assert!(!editor.active_tab().is_modified());
+    press(&mut editor, KeyCode::Char('i'));
+    press(&mut editor, KeyCode::Esc);
+    press(&mut editor, KeyCode::Char('i'));
+    type_text(&mut editor, "one");
+    press(&mut editor, KeyCode::Esc);
+    press(&mut editor, KeyCode::Char('i'));
+    press(&mut editor, KeyCode::Esc);
+    press(&mut editor, KeyCode::Char('u'));
+    assert_eq!(lines(editor.active_tab()), [""]);
+    editor.execute_action("redo").unwrap();
+    assert_eq!(lines(editor.active_tab()), ["one"]);
+
+    press(&mut editor, KeyCode::Char('o'));
+    type_text(&mut editor, "two\nthree");
+    press(&mut editor, KeyCode::Esc);
+    editor.goto_location(1, None);
+    press(&mut editor, KeyCode::Char('O'));
+    type_text(&mut editor, "zero");
+    press(&mut editor, KeyCode::Esc);
+    assert_eq!(lines(editor.active_tab()), ["zero", "one", "two", "three"]);
+    press(&mut editor, KeyCode::Char('u'));
+    assert_eq!(lines(editor.active_tab()), ["one", "two", "three"]);
+    press(&mut editor, KeyCode::Char('u'));
+    assert_eq!(lines(editor.active_tab()), ["one"]);
+    editor.execute_action("redo").unwrap();
+    assert_eq!(lines(editor.active_tab()), ["one", "two", "three"]);
+
+    editor.goto_location(1, None);
+    press(&mut editor, KeyCode::Char('i'));
+    press(&mut editor, KeyCode::Backspace);
+    for _ in 0.."one\ntwo\nthree".len() {
+        press(&mut editor, KeyCode::Delete);
+    }
+    press(&mut editor, KeyCode::Esc);
+    assert_eq!(lines(editor.active_tab()), [""]);
+    press(&mut editor, KeyCode::Char('u'));
+    assert_eq!(lines(editor.active_tab()), ["one", "two", "three"]);
+    editor.execute_action("redo").unwrap();
+    assert_eq!(lines(editor.active_tab()), [""]);
+    press(&mut editor, KeyCode::Char('u'));
+
+    editor.goto_location(3, Some(6));
+    press(&mut editor, KeyCode::Char('i'));
+    type_text(&mut editor, "!");
+    press(&mut editor, KeyCode::Left);
+    type_text(&mut editor, "?");
+    press(&mut editor, KeyCode::Esc);
+    assert_eq!(lines(editor.active_tab()), ["one", "two", "three?!"]);
+    press(&mut editor, KeyCode::Char('u'));
+    assert_eq!(lines(editor.active_tab()), ["one", "two", "three!"]);
+    press(&mut editor, KeyCode::Char('u'));
+    assert_eq!(lines(editor.active_tab()), ["one", "two", "three"]);
+}
+
+#[test]
+fn copy_and_move_reach_the_first_and_last_lines_and_work_on_empty_buffers() {
+    let mut editor = Editor::headless();
+    editor.execute_command_line("t.").unwrap();
+    assert_eq!(lines(editor.active_tab()), ["", ""]);
+    editor.execute_command_line("1m$").unwrap();
+    assert_eq!(lines(editor.active_tab()), ["", ""]);
+    press(&mut editor, KeyCode::Char('u'));
+    press(&mut editor, KeyCode::Char('u'));
+    assert_eq!(lines(editor.active_tab()), [""]);
+    editor.execute_command_line("m0").unwrap();
+    assert_eq!(lines(editor.active_tab()), [""]);
+
+    press(&mut editor, KeyCode::Char('i'));
+    type_text(&mut editor, "one\ntwo\nthree");
+    press(&mut editor, KeyCode::Esc);
+    editor.execute_command_line("1t0").unwrap();
+    assert_eq!(lines(editor.active_tab()), ["one", "one", "two", "three"]);
+    assert_eq!(editor.active_tab().cursor(), (0, 0));
+    press(&mut editor, KeyCode::Char('u'));
+    editor.execute_command_line("$t$").unwrap();
+    assert_eq!(lines(editor.active_tab()), ["one", "two", "three", "three"]);
+    assert_eq!(editor.active_tab().cursor(), (0, 3));
+    press(&mut editor, KeyCode::Char('u'));
+
+    editor.execute_command_line("$m0").unwrap();
+    assert_eq!(lines(editor.active_tab()), ["three", "one", "two"]);
+    editor.execute_command_line("1m$").unwrap();
+    assert_eq!(lines(editor.active_tab()), ["one", "two", "three"]);
+    assert_eq!(editor.active_tab().cursor(), (0, 2));
+    editor.execute_command_line("1,$m0").unwrap();
+    assert_eq!(lines(editor.active_tab()), ["one", "two", "three"]);
+    editor.execute_command_line("1,$m$").unwrap();
+    assert_eq!(lines(editor.active_tab()), ["one", "two", "three"]);
+    editor.execute_command_line("1,$t$").unwrap();
+    assert_eq!(lines(editor.active_tab()), ["one", "two", "three", "one", "two", "three"]);
+    press(&mut editor, KeyCode::Char('u'));
+    assert_eq!(lines(editor.active_tab()), ["one", "two", "three"]);
+
+    editor.execute_command_line("2,3m1").unwrap();
+    editor.execute_command_line("2,3m3").unwrap();
+    editor.execute_command_line("1,2m0").unwrap();
+    assert_eq!(editor.status_message(), None);
+    assert_eq!(lines(editor.active_tab()), ["one", "two", "three"]);
+    assert_eq!(editor.active_tab().cursor(), (0, 0));
+    press(&mut editor, KeyCode::Char('u'));
+    assert_eq!(lines(editor.active_tab()), ["three", "one", "two"]);
+    editor.execute_command_line("1m$").unwrap();
+    editor.execute_command_line("1,2m1").unwrap();
+    assert_eq!(editor.status_message(), Some("Cannot move a range of lines into itself"));
+    editor.execute_command_line("1,3m2").unwrap();
+    assert_eq!(editor.status_message(), Some("Cannot move a range of lines into itself"));
+    editor.execute_command_line("1t4").unwrap();
+    assert_eq!(editor.status_message(), Some("Line 4 is past the end of the buffer"));
+    editor.execute_command_line("0m1").unwrap();
+    assert_eq!(editor.status_message(), Some("Invalid range"));
+    assert_eq!(lines(editor.active_tab()), ["one", "two", "three"]);
+}
+
+#[test]
+fn delete_line_and_character() {
+    let mut editor = Editor::headless();
+    press(&mut editor, KeyCode::Char('i'));
+    type_text(&mut editor, "first\nsecond\nthird");
+    press(&mut editor, KeyCode::Esc);
+
+    editor.execute_action("delete_line").unwrap();
+    assert_eq!(lines(editor.active_tab()), ["first", "second"]);
+
+    press(&mut editor, KeyCode::Char('i'));
+    press(&mut editor, KeyCode::Home);
+    press(&mut editor, KeyCode::Delete);
+    press(&mut editor, KeyCode::Esc);
+    assert_eq!(lines(editor.active_tab()), ["first", "econd"]);
+}
+
+#[test]
+fn search_moves_the_cursor_to_the_first_match() {
+    let mut editor = Editor::headless();
+    press(&mut editor, KeyCode::Char('i'));
+    type_text(&mut editor, "alpha\nbeta\nGamma beta");
+    press(&mut editor, KeyCode::Esc);
+
+    assert_eq!(editor.search("BETA"), 2);
+    assert_eq!(editor.active_tab().cursor(), (0, 1));
+    editor.execute_action("next_search_result").unwrap();
+    assert_eq!(editor.active_tab().cursor(), (6, 2));
+    assert_eq!(editor.search("delta"), 0);
+}
+
+#[test]
+fn view_reports_the_render_model() {
+    let mut editor = Editor::headless();
+    press(&mut editor, KeyCode::Char('i'));
+    type_text(&mut editor, "abc");
+
+    let view = editor.view();
+    assert_eq!(view.mode, Mode::Insert);
+    assert_eq!(view.tabs, ["Untitled-1"]);
+    assert_eq!(view.lines, ["abc"]);
+    assert_eq!(view.cursor, (3, 0));
+    assert!(view.modified);
+}
+
+#[test]
+fn tab_edits_handle_multibyte_characters() {
+    let mut tab = Tab::new();
+    tab.push_undo();
+    for c in "héllo".chars() {
+        tab.insert_char(c);
+    }
+    assert_eq!(tab.cursor(), (6, 0));
+    tab.backspace();
+    tab.backspace();
+    assert_eq!(lines(&tab), ["hél"]);
+    tab.insert_newline();
+    tab.insert_char('x');
+    assert_eq!(lines(&tab), ["hél", "x"]);
+
+    tab.set_cursor(usize::MAX, 0);
+    tab.delete_char();
+    assert_eq!(lines(&tab), ["hélx"]);
+
+    assert!(tab.undo());
+    assert_eq!(lines(&tab), [""]);
+    assert!(!tab.undo());
+    assert!(tab.redo());
+    assert_eq!(lines(&tab), ["hélx"]);
+}
+
+#[test]
+fn text_stats_count_chars_words_and_bytes_separately_for_multibyte_text() {
+    let cases = [
+        ("日本語のテキスト\u{3000}です", 1, 2, 11, 33),
+        ("cafe\u{301} nai\u{308}ve", 1, 2, 12, 14),
+        ("\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467} \u{1F44D}\u{1F3FD}", 1, 2, 8, 27),
+        ("漢字だけ", 1, 1, 4, 12),
+        ("", 0, 0, 0, 0),
+    ];
+    for (text, lines, words, chars, bytes) in cases {
+        let stats = TextStats::from_text(text);
+        assert_eq!((stats.lines, stats.words, stats.chars, stats.bytes), (lines, words, chars, bytes), "{:?}", text);
+    }
+
+    let mut editor = Editor::headless();
+    press(&mut editor, KeyCode::Char('i'));
+    type_text(&mut editor, &cases[..3].iter().map(|case| case.0).collect::<Vec<_>>().join("\n"));
+    press(&mut editor, KeyCode::Esc);
+    editor.execute_action("buffer_stats").unwrap();
+    assert_eq!(editor.status_message(), Some("Buffer: 34 chars, 6 words, 3 lines, 77 bytes"));
+}
+
+#[test]
+fn tab_find_is_case_insensitive() {
+    let tab = Tab::from_lines(vec!["Foo bar".into(), "baz".into(), "a FOO".into()]);
+    assert_eq!(tab.find("foo"), [(0, 0), (2, 2)]);
+}
+
+#[test]
+fn search_patterns_match_literals_words_and_regexes() {
+    use phantom::{CaseMode, SearchOptions, SearchPattern};
+
+    let spans = |pattern: &SearchPattern, line: &str| pattern.matches(line).map(|span| (span.start, span.end)).collect::<Vec<_>>();
+    let smart = SearchOptions { case: CaseMode::Smart, ..SearchOptions::default() };
+    assert_eq!(spans(&SearchPattern::new("foo", smart).unwrap(), "Foo foo"), [(0, 3), (4, 7)]);
+    assert_eq!(spans(&SearchPattern::new("Foo", smart).unwrap(), "Foo foo"), [(0, 3)]);
+    assert_eq!(spans(&SearchPattern::literal("é", true), "ÉtÉ"), [(0, 2), (3, 5)]);
+    assert_eq!(spans(&SearchPattern::word("id"), "id idx _id id"), [(0, 2), (11, 13)]);
+    assert_eq!(spans(&SearchPattern::literal("", false), "abc"), []);
+
+    let regex = SearchOptions { regex: true, ..SearchOptions::default() };
+    assert_eq!(spans(&SearchPattern::new(r"\d+", regex).unwrap(), "a1 22 ü333"), [(1, 2), (3, 5), (8, 11)]);
+    assert_eq!(spans(&SearchPattern::new("x*", regex).unwrap(), "äx"), [(0, 0), (2, 3)]);
+    assert!(SearchPattern::new("(", regex).unwrap_err().starts_with("Invalid pattern ("));
+
+    let lines: Vec<String> = ["let a = b;", "a + a", "a"].iter().map(|line| line.to_string()).collect();
+    let replaced = SearchPattern::word("a").replace(&lines, 0..2, "x", true);
+    assert_eq!(replaced.lines, ["let x = b;", "x + x"]);
+    assert_eq!(replaced.counts, [1, 2]);
+    assert_eq!((replaced.total(), replaced.changed_lines()), (3, 2));
+    let replaced = SearchPattern::literal("a", false).replace(&lines, 1..3, "b", false);
+    assert_eq!(replaced.lines, ["b + a", "b"]);
+    let swap = SearchPattern::new(r"(\w+) = (\w+)", regex).unwrap();
+    assert_eq!(swap.replace_line("let a = b;", "$2 = $1 $$", true), Some(("let b = a $;".to_string(), 1)));
+}
+
+#[test]
+fn search_spans_stay_on_char_boundaries_and_replacing_is_idempotent() {
+    use phantom::{CaseMode, SearchOptions, SearchPattern};
+
+    let alphabet: Vec<char> = "aAbé É_ßİ1\u{1F600}.".chars().collect();
+    let mut state: u64 = 0x9e37_79b9_7f4a_7c15;
+    let mut next = || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+    let regexes = ["a+", "[éÉ]", r"\w*", "ß|İ", r"\b.", "x?", "$", r"\p{So}+"];
+    for round in 0..400 {
+        let length = (next() % 12) as usize;
+        let line: String = (0..length).map(|_| alphabet[(next() % alphabet.len() as u64) as usize]).collect();
+        let chars: Vec<char> = line.chars().collect();
+        let start = (next() % (chars.len() as u64 + 1)) as usize;
+        let end = (start + (next() % 3) as usize).min(chars.len());
+        let literal: String = chars[start..end].iter().collect();
+        let case = [CaseMode::Sensitive, CaseMode::Ignore, CaseMode::Smart][round % 3];
+        let whole_word = next() % 4 == 0;
+        let regex = round % 2 == 1;
+        let source = if regex { regexes[(next() % regexes.len() as u64) as usize] } else { literal.as_str() };
+        let pattern = SearchPattern::new(source, SearchOptions { regex, case, whole_word }).unwrap();
+
+        let spans: Vec<_> = pattern.matches(&line).collect();
+        let mut previous = 0;
+        for span in &spans {
+            assert!(previous <= span.start && span.start <= span.end && span.end <= line.len(), "{:?} {:?} {:?}", source, line, spans);
+            assert!(line.is_char_boundary(span.start) && line.is_char_boundary(span.end), "{:?} {:?} {:?}", source, line, spans);
+            previous = span.end;
+        }
+        if !regex && case == CaseMode::Sensitive {
+            assert!(spans.iter().all(|span| line[span.clone()] == literal));
+            if !whole_word {
+                assert_eq!(spans.is_empty(), literal.is_empty() || !line.contains(&literal));
+            }
+        }
+
+        let lines = vec![line.clone()];
+        let identity = if regex { "$0" } else { literal.as_str() };
+        if regex || case == CaseMode::Sensitive {
+            let replaced = pattern.replace(&lines, 0..1, identity, true);
+            assert_eq!(replaced.lines, lines, "{:?}", source);
+            assert_eq!(replaced.counts, [spans.len()]);
+        }
+        let removed = SearchPattern::new("[aAé]+", SearchOptions { regex: true, ..SearchOptions::default() }).unwrap();
+        let once = removed.replace(&lines, 0..1, "-", true);
+        let twice = removed.replace(&once.lines, 0..1, "-", true);
+        assert_eq!(twice.lines, once.lines);
+        assert_eq!(twice.counts, [0]);
+    }
+}
+
+#[test]
+fn filetype_detection_uses_names_extensions_shebangs_and_modelines() {
+    let dir = std::env::temp_dir().join(format!("phantom-filetypes-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let mut editor = Editor::headless();
+    let syntax_of = |editor: &mut Editor, name: &str, content: &str| {
+        std::fs::write(dir.join(name), content).unwrap();
+        editor.open_file(&dir.join(name)).unwrap();
+        editor.execute_command_line("set syntax?").unwrap();
+        editor.status_message().unwrap_or_default().trim_start_matches("syntax=").to_string()
+    };
+    let files = [
+        ("deploy", "#!/usr/bin/env python3\nprint(1)\n", "Python"),
+        ("run", "#!/bin/sh\necho hi\n", "Bourne Again Shell (bash)"),
+        ("serve", "#!/usr/bin/env -S node --harmony\n", "JavaScript"),
+        ("tool", "#!/usr/bin/perl -w\n", "Perl"),
+        ("Makefile", "all:\n", "Makefile"),
+        ("Gemfile", "source 'https://rubygems.org'\n", "Ruby"),
+        ("Jenkinsfile", "pipeline {}\n", "Groovy"),
+        ("CMakeLists.txt", "project(demo)\n", "Plain Text"),
+        ("main.rs", "#!/usr/bin/env python3\n", "Rust"),
+        ("notes", "hello\n", "Plain Text"),
+        ("settings", "# vim: ft=yaml ts=2\nkey: value\n", "Plain Text"),
+    ];
+    for (name, content, expected) in files {
+        assert_eq!(syntax_of(&mut editor, name, content), expected, "{}", name);
+    }
+
+    editor.execute_command_line("set modeline").unwrap();
+    assert_eq!(syntax_of(&mut editor, "values", "key: value\n# vim: set ft=yaml ts=2 et: trailing\n"), "YAML");
+    editor.execute_command_line("set tabstop?").unwrap();
+    assert_eq!(editor.status_message(), Some("tabstop=2"));
+    press(&mut editor, KeyCode::Char('i'));
+    press(&mut editor, KeyCode::Tab);
+    press(&mut editor, KeyCode::Esc);
+    assert_eq!(lines(editor.active_tab())[0], "  key: value");
+
+    std::fs::write(dir.join("global"), "# vim: noshowignored\n").unwrap();
+    editor.open_file(&dir.join("global")).unwrap();
+    assert_eq!(editor.status_message(), Some("Modeline: showignored is a global option"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn selection_yank_delete_paste_round_trips() {
+    let mut seed = 0x2545_f491_4f6c_dd1d_u64;
+    let mut random = |bound: usize| {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        (seed % bound as u64) as usize
+    };
+    let alphabet = ['a', 'b', ' ', '\t', 'é', '日', '😀', 'ñ'];
+    for _ in 0..500 {
+        let lines: Vec<String> = (0..1 + random(5))
+            .map(|_| (0..random(8)).map(|_| alphabet[random(alphabet.len())]).collect())
+            .collect();
+        let mut tab = Tab::from_lines(lines.clone());
+        let a = (random(40), random(7));
+        let b = (random(40), random(7));
+
+        let text = tab.selection_text(a, b);
+        assert_eq!(text, tab.selection_text(b, a));
+        tab.selection_remove(a, b);
+        let removed = lines.join("\n").len() - tab.text().len();
+        assert_eq!(removed, text.len(), "{:?} {:?} {:?}", lines, a, b);
+        tab.insert_text(&text);
+        assert_eq!(tab.lines(), lines, "{:?} {:?}", a, b);
+    }
+}
+
+#[test]
+fn visual_yank_and_delete_cover_the_same_characters() {
+    let mut editor = Editor::headless();
+    press(&mut editor, KeyCode::Char('i'));
+    type_text(&mut editor, "héllo wörld\n日本語 text");
+    press(&mut editor, KeyCode::Esc);
+    editor.goto_location(2, Some(2));
+    press(&mut editor, KeyCode::Char('v'));
+    press(&mut editor, KeyCode::Up);
+    editor.execute_action("yank_to_new_tab").unwrap();
+    assert_eq!(lines(editor.active_tab()), ["llo wörld", "日本"]);
+
+    editor.execute_action("previous_tab").unwrap();
+    editor.goto_location(2, Some(2));
+    press(&mut editor, KeyCode::Char('v'));
+    press(&mut editor, KeyCode::Up);
+    press(&mut editor, KeyCode::Char('d'));
+    assert_eq!(lines(editor.active_tab()), ["hé語 text"]);
+}
+
+#[test]
+fn calc_evaluates_expressions_without_touching_the_buffer() {
+    let mut editor = Editor::headless();
+    editor.open_file(std::path::Path::new("Cargo.toml")).unwrap();
+    editor.goto_location(3, Some(5));
+    let cases = [
+        ("1 + 2 * 3", "1 + 2 * 3 = 7"),
+        ("(1 + 2) * 3", "(1 + 2) * 3 = 9"),
+        ("-2 * -3 - -1", "-2 * -3 - -1 = 7"),
+        ("7 / 2", "7 / 2 = 3.5"),
+        ("17 % 5 + 0x1F", "17 % 5 + 0x1F = 33"),
+        ("max(1, line, 2) + min(col, 9) + abs(-1.5)", "max(1, line, 2) + min(col, 9) + abs(-1.5) = 9.5"),
+        ("2.5e2 + round(0.4)", "2.5e2 + round(0.4) = 250"),
+    ];
+    for (expression, expected) in cases {
+        editor.execute_command_line(&format!("calc {}", expression)).unwrap();
+        assert_eq!(editor.status_message(), Some(expected));
+    }
+    let lines = editor.active_tab().lines().len();
+    editor.execute_command_line("calc lines").unwrap();
+    assert_eq!(editor.status_message(), Some(format!("lines = {}", lines).as_str()));
+
+    let errors = [
+        ("1 +", "calc: Unexpected end of expression"),
+        ("1 / (2 - 2)", "calc: Division by zero"),
+        ("2 * ) 3", "calc: Unexpected ')' at column 5"),
+        ("foo + 1", "calc: Unknown variable foo"),
+        ("pow(2, 3)", "calc: Unknown function pow"),
+        ("abs(1, 2)", "calc: abs() takes 1 argument"),
+        ("max()", "calc: max() takes at least 1 argument"),
+        ("1 $ 2", "calc: Unexpected '$' at column 3"),
+        ("(1", "calc: Unexpected end of expression"),
+        ("1 2", "calc: Unexpected '2' at column 3"),
+        ("0x", "calc: Invalid hex literal at column 1"),
+        ("", "calc: Empty expression"),
+    ];
+    for (expression, expected) in errors {
+        editor.execute_command_line(&format!("calc {}", expression)).unwrap();
+        assert_eq!(editor.status_message(), Some(expected), "{}", expression);
+    }
+    editor.execute_command_line(&format!("calc {}1", "(".repeat(10_000))).unwrap();
+    assert_eq!(editor.status_message(), Some("calc: Expression is nested too deeply"));
+    assert!(!editor.active_tab().is_modified());
+}
+
+#[test]
+fn natural_order_compares_digit_runs_as_numbers_and_is_a_total_order() {
+    use std::cmp::Ordering;
+    use phantom::natural_cmp;
+
+    let mut names = vec!["file10.rs", "file2.rs", "File1.rs", "file.rs", "file02.rs", "file1.10.rs", "file1.9.rs", "Ärger", "apple", "zebra", "10", "9", ""];
+    names.sort_by(|a, b| natural_cmp(a, b));
+    assert_eq!(names, ["", "9", "10", "apple", "file.rs", "file1.9.rs", "file1.10.rs", "File1.rs", "file02.rs", "file2.rs", "file10.rs", "zebra", "Ärger"]);
+    assert_eq!(natural_cmp("README", "readme"), Ordering::Less);
+    assert_eq!(natural_cmp("a99999999999999999999999", "a100000000000000000000000"), Ordering::Less);
+
+    let alphabet: Vec<char> = "aAb0019.-_/éÉßİ ".chars().collect();
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+    let samples: Vec<String> = (0..120)
+        .map(|_| {
+            let len = (next() % 7) as usize;
+            (0..len).map(|_| alphabet[(next() % alphabet.len() as u64) as usize]).collect()
+        })
+        .collect();
+    for a in &samples {
+        assert_eq!(natural_cmp(a, a), Ordering::Equal);
+        for b in &samples {
+            let ab = natural_cmp(a, b);
+            assert_eq!(ab, natural_cmp(b, a).reverse(), "{:?} {:?}", a, b);
+            assert_eq!(ab == Ordering::Equal, a == b, "{:?} {:?}", a, b);
+            for c in &samples {
+                if ab != Ordering::Greater && natural_cmp(b, c) != Ordering::Greater {
+                    assert_ne!(natural_cmp(a, c), Ordering::Greater, "{:?} {:?} {:?}", a, b, c);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn file_locations_prefer_a_file_named_with_the_colon() {
+    let dir = std::env::temp_dir().join(format!("phantom-locations-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let numbered: String = (1..=12).map(|n| format!("line {}\n", n)).collect();
+    std::fs::write(dir.join("foo.rs"), &numbered).unwrap();
+    std::fs::write(dir.join("foo.rs:10"), "named with a colon\n").unwrap();
+    let mut editor = Editor::headless();
+
+    editor.open_file(&dir.join("foo.rs:10")).unwrap();
+    assert!(editor.active_tab().file().unwrap().ends_with("foo.rs:10"));
+    assert_eq!(lines(editor.active_tab()), ["named with a colon"]);
+    assert_eq!(editor.active_tab().cursor(), (0, 0));
+
+    editor.open_file(&dir.join("foo.rs:10:3")).unwrap();
+    assert!(editor.active_tab().file().unwrap().ends_with("foo.rs"));
+    assert_eq!(editor.active_tab().cursor(), (2, 9));
+
+    editor.open_file(&dir.join("foo.rs:4")).unwrap();
+    assert!(editor.active_tab().file().unwrap().ends_with("foo.rs"));
+    assert_eq!(editor.active_tab().cursor(), (0, 3));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn held_keys_redraw_once_every_frame_at_max_fps() {
+    use std::time::Duration;
+
+    let mut editor = Editor::headless();
+    assert_eq!(editor.frame_interval(), Duration::from_secs(1) / 60);
+    editor.execute_command_line("set maxfps=40").unwrap();
+    let frame = editor.frame_interval();
+    assert_eq!(frame, Duration::from_millis(25));
+
+    assert_eq!(frame_wait(Duration::ZERO, frame), Some(frame));
+    assert_eq!(frame_wait(Duration::from_millis(10), frame), Some(Duration::from_millis(15)));
+    assert_eq!(frame_wait(frame, frame), None);
+    assert_eq!(frame_wait(frame * 2, frame), None);
+
+    let key_every = Duration::from_millis(5);
+    let (mut since_draw, mut draws) = (Duration::ZERO, 0);
+    for _ in 0..200 {
+        since_draw += key_every;
+        if frame_wait(since_draw, frame).is_none() {
+            draws += 1;
+            since_draw = Duration::ZERO;
+        }
+    }
+    assert_eq!(draws, 40);
+}
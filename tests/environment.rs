@@ -0,0 +1,320 @@
+use std::sync::Mutex;
+
+use tui::backend::TestBackend;
+use tui::style::Color;
+use tui::Terminal;
+
+static DATA_DIR: Mutex<()> = Mutex::new(());
+
+fn colors(editor: &mut phantom::Editor) -> Vec<Color> {
+    let mut terminal = Terminal::new(TestBackend::new(60, 12)).unwrap();
+    terminal.draw(|f| editor.ui(f)).unwrap();
+    terminal.backend().buffer().content().iter().flat_map(|cell| [cell.fg, cell.bg]).collect()
+}
+
+fn preset(editor: &mut phantom::Editor) -> String {
+    editor.execute_command_line("colorscheme").unwrap();
+    editor.status_message().unwrap_or_default().rsplit("current: ").next().unwrap().trim_end_matches(')').to_string()
+}
+
+#[test]
+fn no_color_and_light_backgrounds_pick_defaults_unless_configured() {
+    let dir = std::env::temp_dir().join(format!("phantom-environment-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::env::set_var("NO_COLOR", "1");
+    std::env::set_var("COLORFGBG", "0;15");
+
+    let mut editor = phantom::Editor::new(Some(dir.clone()));
+    assert_eq!(preset(&mut editor), "light");
+    assert!(colors(&mut editor).iter().all(|color| *color == Color::Reset));
+    let saved: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(dir.join("colors.json")).unwrap()).unwrap();
+    assert!(saved.get("preset").is_none() && saved.get("color_mode").is_none());
+
+    editor.execute_command_line("set forcecolor").unwrap();
+    assert!(colors(&mut editor).iter().any(|color| matches!(color, Color::Rgb(..))));
+    editor.execute_command_line("set noforcecolor").unwrap();
+    assert!(colors(&mut editor).iter().all(|color| *color == Color::Reset));
+
+    std::fs::write(dir.join("colors.json"), r#"{"preset": "gruvbox", "color_mode": "256"}"#).unwrap();
+    editor.execute_command_line("config reload").unwrap();
+    assert_eq!(preset(&mut editor), "gruvbox");
+    assert!(colors(&mut editor).iter().any(|color| matches!(color, Color::Indexed(_))));
+
+    std::env::remove_var("NO_COLOR");
+    std::env::set_var("COLORFGBG", "15;default;0");
+    std::fs::remove_file(dir.join("colors.json")).unwrap();
+    let mut editor = phantom::Editor::new(Some(dir.clone()));
+    assert_eq!(preset(&mut editor), "dark");
+    assert!(colors(&mut editor).iter().any(|color| matches!(color, Color::Rgb(..))));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn log_level_writes_messages_to_the_data_dir() {
+    let _data_dir = DATA_DIR.lock().unwrap();
+    let dir = std::env::temp_dir().join(format!("phantom-log-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("config")).unwrap();
+    std::env::set_var("PHANTOM_DATA_DIR", dir.join("data"));
+
+    let mut editor = phantom::Editor::new(Some(dir.join("config")));
+    assert!(editor.enable_log("loud").unwrap_err().starts_with("invalid log level \"loud\""));
+    let path = editor.enable_log("WARN").unwrap();
+    assert_eq!(path, dir.join("data/phantom.log"));
+    editor.execute_action("no_such_action").unwrap();
+    editor.execute_command_line("set number").unwrap();
+    editor.handle_event(crossterm::event::Event::Key(crossterm::event::KeyCode::Char('j').into())).unwrap();
+
+    let log = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(log.lines().count(), 1, "{}", log);
+    assert!(log.trim_end().ends_with(" WARN  Unknown action: no_such_action"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn mkconfig_keeps_comments_and_ui_state_survives_restarts() {
+    let _data_dir = DATA_DIR.lock().unwrap();
+    let dir = std::env::temp_dir().join(format!("phantom-state-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("config")).unwrap();
+    std::fs::create_dir_all(dir.join("data")).unwrap();
+    std::env::set_var("PHANTOM_DATA_DIR", dir.join("data"));
+    std::fs::write(dir.join("config/settings.toml"), "persist_ui_state = true\ndebug_height = 9\n").unwrap();
+    std::fs::write(dir.join("data/state.json"), r#"{"sidebar_width": 40, "show_debug": true, "debug_height": 12, "terminal_height": 20}"#).unwrap();
+
+    let mut editor = phantom::Editor::new(Some(dir.join("config")));
+    editor.execute_command_line("set debugheight? terminalheight?").unwrap();
+    assert_eq!(editor.status_message(), Some("terminalheight=20"));
+    editor.shutdown();
+    let state: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(dir.join("data/state.json")).unwrap()).unwrap();
+    assert_eq!(state["debug_height"], 9);
+    assert_eq!(state["terminal_height"], 20);
+    assert_eq!(state["sidebar_width"], 40);
+    assert_eq!(state["show_debug"], true);
+
+    let mut editor = phantom::Editor::new(Some(dir.join("config")));
+    editor.execute_action("toggle_debug_menu").unwrap();
+    editor.shutdown();
+    let state: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(dir.join("data/state.json")).unwrap()).unwrap();
+    assert_eq!(state["show_debug"], false);
+
+    let config = std::fs::read_to_string(dir.join("config/config.toml")).unwrap()
+        .replace("[normal_mode]\n", "# my bindings\n[normal_mode]\n")
+        .replace("dd = \"delete_line\"", "dd = \"delete_line\" # keep me");
+    std::fs::write(dir.join("config/config.toml"), &config).unwrap();
+    let mut editor = phantom::Editor::new(Some(dir.join("config")));
+    editor.execute_command_line("colorscheme gruvbox").unwrap();
+    editor.execute_command_line("mkconfig").unwrap();
+    assert!(editor.status_message().unwrap().starts_with("Wrote settings.toml, config.toml and colors.json"));
+    let written = std::fs::read_to_string(dir.join("config/config.toml")).unwrap();
+    assert!(written.contains("# my bindings\n[normal_mode]"));
+    assert!(written.contains("dd = \"delete_line\" # keep me"));
+    assert!(written.contains("[terminal_mode]"));
+    let colors: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(dir.join("config/colors.json")).unwrap()).unwrap();
+    assert_eq!(colors["preset"], "gruvbox");
+    std::env::remove_var("PHANTOM_DATA_DIR");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn config_writes_keep_unknown_keys_and_comments() {
+    let _data_dir = DATA_DIR.lock().unwrap();
+    let dir = std::env::temp_dir().join(format!("phantom-roundtrip-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("config")).unwrap();
+    std::env::set_var("PHANTOM_DATA_DIR", dir.join("data"));
+    let settings = "# my settings\nshow_start_screen = false # quiet\nfuture_setting = \"kept\"\n\n[mouse]\n# wheel tuning\nwheel_lines = 5\nfuture_mouse = true\n";
+    std::fs::write(dir.join("config/settings.toml"), settings).unwrap();
+    let colors = r##"{"keyword": {"fg": "#ff0000", "modifiers": ["bold"]}, "future_color": "#123456", "preset": "dark"}"##;
+    std::fs::write(dir.join("config/colors.json"), colors).unwrap();
+
+    let mut editor = phantom::Editor::new(Some(dir.join("config")));
+    editor.execute_command_line("set mouse=off").unwrap();
+    editor.execute_command_line("colorscheme! light").unwrap();
+    let written = std::fs::read_to_string(dir.join("config/settings.toml")).unwrap();
+    assert!(written.starts_with("# my settings\nshow_start_screen = false # quiet\nfuture_setting = \"kept\"\n"), "{}", written);
+    assert!(written.contains("[mouse]\n# wheel tuning\nwheel_lines = 5\nfuture_mouse = true\n"), "{}", written);
+    assert!(written.contains("enabled = false"), "{}", written);
+    let saved: toml::Table = toml::from_str(&written).unwrap();
+    assert_eq!(saved["tab_width"], toml::Value::Integer(4));
+
+    let saved = std::fs::read_to_string(dir.join("config/colors.json")).unwrap();
+    let saved: serde_json::Value = serde_json::from_str(&saved).unwrap();
+    assert_eq!(saved["future_color"], "#123456");
+    assert_eq!(saved["keyword"], "fg=#ff0000 bold");
+    assert_eq!(saved["preset"], "light");
+    let keys: Vec<&String> = saved.as_object().unwrap().keys().take(3).collect();
+    assert_eq!(keys, ["keyword", "future_color", "preset"]);
+
+    editor.execute_command_line("mkconfig").unwrap();
+    let written = std::fs::read_to_string(dir.join("config/settings.toml")).unwrap();
+    assert!(written.contains("future_setting = \"kept\"") && written.contains("# wheel tuning"));
+    let saved: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(dir.join("config/colors.json")).unwrap()).unwrap();
+    assert_eq!(saved["future_color"], "#123456");
+    std::env::remove_var("PHANTOM_DATA_DIR");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn start_screen_lists_recent_files_until_a_key_dismisses_it() {
+    let _data_dir = DATA_DIR.lock().unwrap();
+    let dir = std::env::temp_dir().join(format!("phantom-start-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("config")).unwrap();
+    std::fs::create_dir_all(dir.join("data")).unwrap();
+    std::env::set_var("PHANTOM_DATA_DIR", dir.join("data"));
+    std::fs::write(dir.join("notes.txt"), "notes\n").unwrap();
+    std::fs::write(dir.join("todo.txt"), "todo\n").unwrap();
+    let recent: Vec<String> = ["todo.txt", "notes.txt", "gone.txt"].iter()
+        .map(|name| dir.join(name).to_string_lossy().into_owned())
+        .collect();
+    std::fs::write(dir.join("data/recent.json"), serde_json::json!({ "entries": recent }).to_string()).unwrap();
+    let key = |code| crossterm::event::Event::Key(crossterm::event::KeyEvent::from(code));
+    let screen = |editor: &mut phantom::Editor| {
+        let mut terminal = Terminal::new(TestBackend::new(100, 40)).unwrap();
+        terminal.draw(|f| editor.ui(f)).unwrap();
+        terminal.backend().buffer().content().iter().map(|cell| cell.symbol.as_str()).collect::<String>()
+    };
+
+    let mut editor = phantom::Editor::new(Some(dir.join("config")));
+    editor.show_start_screen();
+    let shown = screen(&mut editor);
+    assert!(shown.contains("todo.txt") && shown.contains("notes.txt"));
+    assert!(!shown.contains("gone.txt"));
+    assert!(shown.contains("Open file browser") && shown.contains("New file"));
+    assert!(shown.contains(&dir.join("config").join("settings.toml").display().to_string()));
+    editor.handle_event(key(crossterm::event::KeyCode::Char('j'))).unwrap();
+    editor.handle_event(key(crossterm::event::KeyCode::Enter)).unwrap();
+    assert_eq!(editor.active_tab().lines(), ["notes"]);
+    assert!(!screen(&mut editor).contains("Open file browser"));
+
+    let mut editor = phantom::Editor::new(Some(dir.join("config")));
+    editor.show_start_screen();
+    editor.handle_event(key(crossterm::event::KeyCode::Char('i'))).unwrap();
+    editor.handle_event(key(crossterm::event::KeyCode::Char('x'))).unwrap();
+    assert_eq!(editor.active_tab().lines(), ["x"]);
+    assert!(!screen(&mut editor).contains("Open file browser"));
+
+    std::fs::write(dir.join("config/settings.toml"), "show_start_screen = false\n").unwrap();
+    let mut editor = phantom::Editor::new(Some(dir.join("config")));
+    editor.show_start_screen();
+    assert!(!screen(&mut editor).contains("Open file browser"));
+    std::env::remove_var("PHANTOM_DATA_DIR");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn open_files_are_locked_against_other_instances() {
+    let _data_dir = DATA_DIR.lock().unwrap();
+    let dir = std::env::temp_dir().join(format!("phantom-locks-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("config")).unwrap();
+    std::env::set_var("PHANTOM_DATA_DIR", dir.join("data"));
+    let file = dir.join("notes.txt");
+    std::fs::write(&file, "hello\n").unwrap();
+    let lock = || std::fs::read_dir(dir.join("data/locks")).unwrap().next().map(|entry| entry.unwrap().path());
+
+    let mut editor = phantom::Editor::new(Some(dir.join("config")));
+    editor.open_file(&file).unwrap();
+    let path = lock().unwrap();
+    let owner: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(owner["pid"], std::process::id());
+    editor.shutdown();
+    assert!(lock().is_none());
+
+    let mut other = std::process::Command::new("sleep").arg("30").spawn().unwrap();
+    let mut foreign = owner.clone();
+    foreign["pid"] = other.id().into();
+    let foreign = foreign.to_string();
+    std::fs::write(&path, &foreign).unwrap();
+
+    let mut editor = phantom::Editor::new(Some(dir.join("config")));
+    editor.open_file(&file).unwrap();
+    let mut terminal = Terminal::new(TestBackend::new(100, 16)).unwrap();
+    terminal.draw(|f| editor.ui(f)).unwrap();
+    let screen: String = terminal.backend().buffer().content().iter().map(|cell| cell.symbol.as_str()).collect();
+    assert!(screen.contains(&format!("already open in phantom (PID {}", other.id())), "{}", screen);
+    assert!(screen.contains("[ Open read-only ]") && screen.contains("[ Edit anyway ]"));
+    editor.handle_event(crossterm::event::Event::Key(crossterm::event::KeyCode::Char('y').into())).unwrap();
+    for _ in 0..2 {
+        editor.dispatch_key_event(crossterm::event::KeyCode::Char('d').into()).unwrap();
+    }
+    assert_eq!(editor.active_tab().lines(), ["hello"]);
+    editor.shutdown();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), foreign);
+
+    let mut editor = phantom::Editor::new(Some(dir.join("config")));
+    editor.open_file(&file).unwrap();
+    editor.handle_event(crossterm::event::Event::Key(crossterm::event::KeyCode::Esc.into())).unwrap();
+    assert_eq!(editor.active_tab().file(), None);
+
+    other.kill().unwrap();
+    other.wait().unwrap();
+    editor.open_file(&file).unwrap();
+    assert_eq!(editor.mode(), phantom::Mode::Normal);
+    let reclaimed: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(reclaimed["pid"], std::process::id());
+    assert_eq!(std::fs::read_dir(dir.join("data/locks")).unwrap().count(), 1);
+    for _ in 0..2 {
+        editor.dispatch_key_event(crossterm::event::KeyCode::Char('d').into()).unwrap();
+    }
+    assert_eq!(editor.active_tab().lines(), [""]);
+    std::env::remove_var("PHANTOM_DATA_DIR");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn mouse_section_remaps_drags_and_wheel_or_disables_capture() {
+    let _data_dir = DATA_DIR.lock().unwrap();
+    let dir = std::env::temp_dir().join(format!("phantom-mouse-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("config")).unwrap();
+    std::env::set_var("PHANTOM_DATA_DIR", dir.join("data"));
+    std::fs::write(dir.join("config/settings.toml"), "[mouse]\ndrag = \"scroll\"\nwheel_lines = 1\n").unwrap();
+    let file = dir.join("lines.txt");
+    std::fs::write(&file, (1..=40).map(|n| format!("line {}\n", n)).collect::<String>()).unwrap();
+    let mouse = |kind, row| crossterm::event::Event::Mouse(crossterm::event::MouseEvent {
+        kind,
+        column: 10,
+        row,
+        modifiers: crossterm::event::KeyModifiers::NONE,
+    });
+    let mut terminal = Terminal::new(TestBackend::new(60, 16)).unwrap();
+    let mut first_line = |editor: &mut phantom::Editor| {
+        terminal.draw(|f| editor.ui(f)).unwrap();
+        let buffer = terminal.backend().buffer();
+        (0..60).map(|x| buffer.get(x, 4).symbol.as_str()).collect::<String>().trim_matches(|c| c == '│' || c == ' ').to_string()
+    };
+
+    let mut editor = phantom::Editor::new(Some(dir.join("config")));
+    editor.open_file(&file).unwrap();
+    assert!(editor.mouse_enabled());
+    assert_eq!(first_line(&mut editor), "line 1");
+    editor.handle_event(mouse(crossterm::event::MouseEventKind::ScrollDown, 8)).unwrap();
+    assert_eq!(first_line(&mut editor), "line 2");
+    editor.handle_event(mouse(crossterm::event::MouseEventKind::Down(crossterm::event::MouseButton::Left), 10)).unwrap();
+    editor.handle_event(mouse(crossterm::event::MouseEventKind::Drag(crossterm::event::MouseButton::Left), 7)).unwrap();
+    editor.handle_event(mouse(crossterm::event::MouseEventKind::Up(crossterm::event::MouseButton::Left), 7)).unwrap();
+    assert_eq!(first_line(&mut editor), "line 5");
+
+    std::fs::write(dir.join("config/settings.toml"), "[mouse]\nenabled = false\n").unwrap();
+    editor.execute_command_line("config reload").unwrap();
+    assert!(!editor.mouse_enabled());
+    editor.handle_event(mouse(crossterm::event::MouseEventKind::ScrollDown, 8)).unwrap();
+    assert_eq!(first_line(&mut editor), "line 5");
+    std::env::remove_var("PHANTOM_DATA_DIR");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn malformed_settings_warn_with_the_field_and_line_instead_of_panicking() {
+    let _data_dir = DATA_DIR.lock().unwrap();
+    let dir = std::env::temp_dir().join(format!("phantom-errors-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("config")).unwrap();
+    std::env::set_var("PHANTOM_DATA_DIR", dir.join("data"));
+    std::fs::write(dir.join("config/settings.toml"), "show_start_screen = false\n\n[mouse]\nwheel_lines = \"many\"\n").unwrap();
+
+    let mut editor = phantom::Editor::new(Some(dir.join("config")));
+    let message = editor.status_message().unwrap().to_string();
+    assert!(message.starts_with("settings.toml: mouse.wheel_lines: "), "{}", message);
+    assert!(message.ends_with("(line 4)"), "{}", message);
+
+    editor.execute_command_line("definitely-not-a-command").unwrap();
+    assert_eq!(editor.status_message(), Some("Unknown command: definitely-not-a-command"));
+    std::env::remove_var("PHANTOM_DATA_DIR");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,2661 @@
+mod common;
+
+use common::{keys, Harness, TempDir};
+use crossterm::event::{KeyCode, KeyModifiers};
+use phantom::Mode;
+use tui::style::{Color, Modifier};
+use unicode_width::UnicodeWidthStr;
+
+#[test]
+fn key_notation_parses_specials_and_modifiers() {
+    let events = keys("iA<Esc><C-r><lt><S-Tab><F2><C-Up>");
+    let codes: Vec<(KeyCode, KeyModifiers)> = events.iter().map(|key| (key.code, key.modifiers)).collect();
+    assert_eq!(codes, [
+        (KeyCode::Char('i'), KeyModifiers::NONE),
+        (KeyCode::Char('A'), KeyModifiers::SHIFT),
+        (KeyCode::Esc, KeyModifiers::NONE),
+        (KeyCode::Char('r'), KeyModifiers::CONTROL),
+        (KeyCode::Char('<'), KeyModifiers::NONE),
+        (KeyCode::BackTab, KeyModifiers::SHIFT),
+        (KeyCode::F(2), KeyModifiers::NONE),
+        (KeyCode::Up, KeyModifiers::CONTROL),
+    ]);
+}
+
+#[test]
+fn opening_a_file_shows_it_in_the_tab_bar_and_text_area() {
+    let dir = TempDir::new("interaction");
+    let path = dir.join("notes.txt");
+    std::fs::write(&path, "first line\nsecond line\n").unwrap();
+
+    let mut harness = Harness::new(60, 12);
+    harness.editor.open_file(&path).unwrap();
+    harness.draw();
+    let rows = harness.rows();
+    assert!(rows[1].contains("notes.txt"));
+    assert!(rows[4].contains("first line"));
+    assert!(rows[5].contains("second line"));
+    assert_eq!(harness.cursor_cell(), "f");
+}
+
+#[test]
+fn typing_in_insert_mode_updates_buffer_and_screen() {
+    let mut harness = Harness::new(60, 12);
+    harness.type_str("ihello<CR>world<Esc>");
+    assert_eq!(harness.editor.mode(), Mode::Normal);
+    assert_eq!(harness.lines(), ["hello", "world"]);
+    assert!(harness.rows()[4].contains("hello"));
+    assert!(harness.rows()[5].contains("world"));
+    assert!(harness.screen().contains("Phantom - NORMAL"));
+}
+
+#[test]
+fn undo_and_redo_walk_insert_sessions() {
+    let mut harness = Harness::new(60, 12);
+    harness.type_str("ione<Esc>otwo<Esc>");
+    assert_eq!(harness.lines(), ["one", "two"]);
+    harness.type_str("u");
+    assert_eq!(harness.lines(), ["one"]);
+    harness.type_str("u");
+    assert_eq!(harness.lines(), [""]);
+    harness.type_str("<C-r><C-r>");
+    assert_eq!(harness.lines(), ["one", "two"]);
+    assert!(harness.rows()[5].contains("two"));
+}
+
+#[test]
+fn search_jumps_between_matches() {
+    let mut harness = Harness::new(60, 12);
+    harness.type_str("ialpha<CR>needle one<CR>beta<CR>needle two<Esc>");
+    harness.type_str("<C-Home>/needle<CR>");
+    assert_eq!(harness.editor.mode(), Mode::Normal);
+    let first = harness.editor.active_tab().cursor();
+    harness.type_str("n");
+    let second = harness.editor.active_tab().cursor();
+    assert_ne!(first.1, second.1);
+    assert!([first.1, second.1].contains(&1) && [first.1, second.1].contains(&3));
+    assert_eq!(harness.cursor_cell(), "n");
+}
+
+#[test]
+fn tabs_switch_with_keys_and_render_their_own_content() {
+    let mut harness = Harness::new(60, 12);
+    harness.type_str("ifirst tab<Esc><C-t>isecond tab<Esc>");
+    assert_eq!(harness.editor.tabs().len(), 2);
+    assert!(harness.rows()[4].contains("second tab"));
+    harness.type_str("<Tab>");
+    assert_eq!(harness.lines(), ["first tab"]);
+    assert!(harness.rows()[4].contains("first tab"));
+    harness.type_str("<F2>");
+    assert_eq!(harness.lines(), ["second tab"]);
+}
+
+#[test]
+fn known_panic_sequences_are_harmless() {
+    let mut harness = Harness::new(60, 12);
+    harness.type_str("uu<C-r>dddd<BS><Del>");
+    harness.type_str("iñé日本<BS><Left><Del><Right><BS><Esc>");
+    assert_eq!(harness.lines(), ["ñ"]);
+    harness.type_str("<C-w><C-w><End><PageDown><PageUp>");
+    harness.type_str("vd<Esc><C-v>jjd<Esc>");
+    harness.type_str("/<CR>n<S-n>");
+    harness.type_str(":s/x/y/<CR>:<Esc>");
+
+    for (width, height) in [(1, 1), (0, 0), (19, 6), (200, 3), (60, 12)] {
+        harness.resize(width, height);
+        harness.click(width / 2, height / 2);
+        harness.drag(width, height);
+    }
+    harness.click(59, 11);
+    harness.drag(0, 0);
+    harness.type_str("iafter<Esc>");
+    assert!(harness.screen().contains("after"));
+}
+
+fn selection_snapshot(harness: &Harness, row: u16) -> String {
+    let buffer = harness.terminal.backend().buffer();
+    let mut snapshot = String::new();
+    let mut selected = false;
+    let mut x = 1;
+    while x + 1 < buffer.area.width {
+        let cell = buffer.get(x, row);
+        let highlighted = cell.bg == Color::Gray;
+        if highlighted != selected {
+            snapshot.push(if highlighted { '[' } else { ']' });
+            selected = highlighted;
+        }
+        snapshot.push_str(&cell.symbol);
+        x += cell.symbol.width().max(1) as u16;
+    }
+    if selected {
+        snapshot.push(']');
+    }
+    snapshot.trim_end().to_string()
+}
+
+#[test]
+fn mouse_selection_highlights_display_columns_across_spans() {
+    let mut harness = Harness::new(40, 10);
+    harness.type_str("ilet s = \"é日本x\"; // ü<CR>fn f() { \"ñ\" }<Esc>");
+    harness.click(3, 4);
+    harness.drag(15, 4);
+    assert_eq!(selection_snapshot(&harness, 4), "le[t s = \"é日本x]\"; // ü");
+    assert_eq!(selection_snapshot(&harness, 5), "fn f() { \"ñ\" }");
+
+    harness.drag(11, 5);
+    assert_eq!(selection_snapshot(&harness, 4), "le[t s = \"é日本x\"; // ü]");
+    assert_eq!(selection_snapshot(&harness, 5), "[fn f() { \"ñ]\" }");
+}
+
+#[test]
+fn mouse_selection_follows_horizontal_scroll() {
+    let mut harness = Harness::new(40, 10);
+    harness.type_str(":set sidescrolloff=0<CR>");
+    harness.type_str("ilet s = \"日本語テキスト\"; // naïve café<CR><Esc>");
+    harness.type_str(&format!("i{}<Esc>", "x".repeat(80)));
+    harness.editor.goto_location(1, None);
+    harness.draw();
+    harness.click(10, 4);
+    harness.drag(36, 4);
+    assert_eq!(selection_snapshot(&harness, 4), "let s = \"[日本語テキスト\"; // naïve c]af");
+
+    let snapshots: Vec<String> = [45, 48, 52, 60].iter().map(|&column| {
+        harness.editor.goto_location(2, Some(column));
+        harness.draw();
+        selection_snapshot(&harness, 4)
+    }).collect();
+    assert_eq!(snapshots, [
+        " \"[日本語テキスト\"; // naïve c]afé",
+        "[ 本語テキスト\"; // naïve c]afé",
+        "[ テキスト\"; // naïve c]afé",
+        "[ \"; // naïve c]afé",
+    ]);
+}
+
+#[test]
+fn visual_block_highlight_uses_the_same_columns() {
+    let mut harness = Harness::new(40, 10);
+    harness.type_str("iaé日b<CR>ñxyz<Esc>");
+    harness.editor.goto_location(1, Some(2));
+    harness.type_str("<C-v><Down><Right><Right>");
+    let buffer = harness.terminal.backend().buffer();
+    let selection = buffer.get(3, 4).bg;
+    assert_ne!(selection, buffer.get(1, 4).bg);
+    assert_eq!(buffer.get(2, 4).bg, selection);
+    assert_eq!(buffer.get(3, 4).bg, selection);
+    assert_ne!(buffer.get(5, 4).bg, selection);
+    assert_eq!(buffer.get(2, 5).bg, selection);
+    assert_eq!(buffer.get(3, 5).bg, selection);
+    assert_ne!(buffer.get(4, 5).bg, selection);
+}
+
+#[test]
+fn visual_block_edits_follow_display_columns_across_tabs() {
+    let mut harness = Harness::new(40, 10);
+    harness.type_str("iabcdefghij<CR><Tab>xyz<Esc>");
+    harness.editor.goto_location(2, Some(2));
+    harness.type_str("<C-v><Up>d");
+    assert_eq!(harness.lines(), ["afghij", " yz"]);
+    assert_eq!(harness.editor.active_tab().cursor(), (1, 0));
+
+    let mut harness = Harness::new(40, 10);
+    harness.type_str("iPQ<CR>RS<CR>ab<Tab>def<CR>abcdefghij<Esc>");
+    harness.editor.goto_location(1, Some(1));
+    harness.type_str("<C-v><Down><Right>y");
+    harness.editor.goto_location(3, Some(4));
+    harness.type_str("p");
+    assert_eq!(harness.lines(), ["PQ", "RS", "ab\tPQdef", "abcdRSefghij"]);
+}
+
+#[test]
+fn visual_block_insert_skips_short_lines_and_append_pads_them() {
+    let mut harness = Harness::new(40, 10);
+    harness.type_str("iabcdef<CR>ab<CR>abcdef<Esc>");
+    harness.editor.goto_location(1, Some(3));
+    harness.type_str("<C-v><Down><Down><Right>IX<Esc>");
+    assert_eq!(harness.lines(), ["abXcdef", "ab", "abXcdef"]);
+
+    harness.type_str("u");
+    harness.editor.goto_location(1, Some(3));
+    harness.type_str("<C-v><Down><Down><Right>AY<Esc>");
+    assert_eq!(harness.lines(), ["abcdYef", "ab  Y", "abcdYef"]);
+}
+
+#[test]
+fn search_matches_stay_highlighted_until_nohlsearch() {
+    let mut harness = Harness::new(40, 10);
+    harness.type_str("ifoo bar foo<CR>bar<Esc>/foo<CR>");
+    let bg = |harness: &Harness, x: u16, y: u16| harness.terminal.backend().buffer().get(x, y).bg;
+    let plain = bg(&harness, 6, 4);
+    let matched = bg(&harness, 2, 4);
+    assert_ne!(matched, plain);
+    assert_eq!(bg(&harness, 10, 4), matched);
+    assert_eq!(bg(&harness, 4, 4), plain);
+
+    harness.type_str(":noh<CR>");
+    assert_eq!(bg(&harness, 10, 4), plain);
+    harness.type_str("n");
+    assert_eq!(bg(&harness, 2, 4), matched);
+    harness.type_str("<Down><End>i foo<Esc>");
+    assert_eq!(bg(&harness, 6, 5), matched);
+    assert_eq!(bg(&harness, 2, 5), plain);
+
+    harness.click(1, 4);
+    harness.drag(6, 4);
+    assert_ne!(bg(&harness, 3, 4), matched);
+    assert_ne!(bg(&harness, 3, 4), plain);
+    assert_eq!(bg(&harness, 10, 4), matched);
+    harness.type_str(":set nohlsearch<CR>");
+    assert_eq!(bg(&harness, 10, 4), plain);
+    harness.type_str(":set hlsearch<CR>");
+    assert_eq!(bg(&harness, 10, 4), matched);
+    harness.editor.execute_action("clear_search_highlight").unwrap();
+    harness.draw();
+    assert_eq!(bg(&harness, 10, 4), plain);
+}
+
+#[test]
+fn cursor_lands_on_wide_characters_at_every_scroll_offset() {
+    let line = "ab日本語cd😀e漢字fgh中文ijklmnopq한국어rs";
+    for sidescrolloff in [0, 2, 5] {
+        let mut harness = Harness::new(20, 8);
+        harness.type_str(&format!(":set sidescrolloff={}<CR>", sidescrolloff));
+        harness.type_str(&format!("i{}<Esc>", line));
+        harness.type_str("<Home>");
+        for (byte, c) in line.char_indices() {
+            let tab = harness.editor.active_tab();
+            assert_eq!(tab.cursor(), (byte, 0), "sidescrolloff={}", sidescrolloff);
+            assert_eq!(harness.cursor_cell(), c.to_string(), "sidescrolloff={} at {}", sidescrolloff, byte);
+            let (x, y) = harness.terminal.get_cursor().unwrap();
+            assert_eq!(harness.editor.screen_to_content_position(x, y), Some((byte, 0)));
+            assert_eq!(harness.terminal.backend().buffer().get(19, 4).symbol, "│");
+            let row = &harness.rows()[4];
+            assert!(row.starts_with('│') && row.ends_with('│'));
+            harness.type_str("<Right>");
+        }
+    }
+}
+
+#[test]
+fn clipped_wide_characters_are_padded_with_spaces() {
+    let mut harness = Harness::new(20, 8);
+    harness.type_str(":set sidescrolloff=0<CR>");
+    harness.type_str("i日本語日本語日本語日本語<CR>abcdefghijklmnopqrstu<Esc>");
+    harness.editor.goto_location(2, Some(1));
+    harness.editor.goto_location(2, Some(20));
+    harness.draw();
+    assert_eq!(harness.rows()[4], "│本語日本語日本語日│");
+    harness.editor.goto_location(2, Some(21));
+    harness.draw();
+    assert_eq!(harness.rows()[4], "│ 語日本語日本語日 │");
+}
+
+fn bookmark_lines(harness: &Harness) -> Vec<usize> {
+    harness.rows().iter().skip(4).take_while(|row| !row.starts_with('└'))
+        .enumerate()
+        .filter(|(_, row)| row.starts_with("│●"))
+        .map(|(index, _)| index + 1)
+        .collect()
+}
+
+#[test]
+fn bookmarks_show_in_the_gutter_and_follow_edits() {
+    let mut harness = Harness::new(40, 14);
+    harness.type_str("ione<CR>two<CR>three<CR>four<CR>five<Esc>");
+    assert!(harness.rows()[4].starts_with("│one"));
+    harness.editor.goto_location(2, None);
+    harness.type_str("mm");
+    harness.editor.goto_location(4, None);
+    harness.type_str("mm");
+    assert_eq!(bookmark_lines(&harness), [2, 4]);
+    assert!(harness.rows()[4].starts_with("│  one"));
+    assert_eq!(harness.cursor_cell(), "f");
+
+    harness.editor.goto_location(1, None);
+    harness.type_str("Ozero<Esc>");
+    assert_eq!(bookmark_lines(&harness), [3, 5]);
+    harness.type_str("]b");
+    assert_eq!(harness.editor.active_tab().cursor(), (0, 2));
+    harness.type_str("]b]b");
+    assert_eq!(harness.editor.active_tab().cursor(), (0, 2));
+    harness.type_str("[b");
+    assert_eq!(harness.editor.active_tab().cursor(), (0, 4));
+
+    harness.type_str("dd");
+    assert_eq!(bookmark_lines(&harness), [3]);
+    harness.type_str("u");
+    assert_eq!(bookmark_lines(&harness), [3, 5]);
+    harness.editor.goto_location(2, Some(99));
+    harness.type_str("i<CR>inserted<Esc>");
+    assert_eq!(bookmark_lines(&harness), [4, 6]);
+
+    harness.editor.goto_location(4, None);
+    harness.type_str("mm");
+    harness.editor.goto_location(6, None);
+    harness.type_str("mm");
+    assert_eq!(bookmark_lines(&harness), Vec::<usize>::new());
+    assert!(harness.rows()[4].starts_with("│zero"));
+}
+
+#[test]
+fn change_list_jumps_between_edits_and_follows_inserted_lines() {
+    let mut harness = Harness::new(40, 14);
+    harness.type_str("g;");
+    assert!(harness.screen().contains("Change list is empty"));
+    harness.type_str("ione<CR>two<CR>three<CR>four<CR>five<Esc>");
+    harness.editor.goto_location(1, Some(2));
+    harness.type_str("iA<Esc>");
+    harness.editor.goto_location(3, Some(1));
+    harness.type_str("iB<Esc>");
+    harness.editor.goto_location(4, Some(1));
+    harness.type_str("iC<Esc>");
+    assert_eq!(harness.lines(), ["oAne", "two", "Bthree", "Cfour", "five"]);
+
+    harness.editor.goto_location(2, None);
+    harness.type_str("g;");
+    assert_eq!(harness.editor.active_tab().cursor(), (0, 3));
+    harness.type_str("g;");
+    assert_eq!(harness.editor.active_tab().cursor(), (1, 0));
+    harness.type_str("g;");
+    assert_eq!(harness.editor.active_tab().cursor(), (3, 4));
+    harness.type_str("g;");
+    assert_eq!(harness.editor.active_tab().cursor(), (3, 4));
+    assert!(harness.screen().contains("At start of change list"));
+    harness.type_str("g,");
+    assert_eq!(harness.editor.active_tab().cursor(), (1, 0));
+    harness.type_str("`.");
+    assert_eq!(harness.editor.active_tab().cursor(), (0, 3));
+
+    harness.editor.goto_location(1, None);
+    harness.type_str("Ozero<Esc>g;g;");
+    assert_eq!(harness.lines()[4], "Cfour");
+    assert_eq!(harness.editor.active_tab().cursor(), (0, 4));
+}
+
+#[test]
+fn bookmarks_picker_lists_every_tab_and_jumps() {
+    let mut harness = Harness::new(60, 16);
+    harness.type_str("ialpha<CR>beta<Esc>mm<C-t>igamma<CR>delta<CR>epsilon<Esc>mm");
+    harness.type_str(":bookmarks<CR>");
+    assert_eq!(harness.editor.mode(), Mode::Picker);
+    let screen = harness.screen();
+    assert!(screen.contains("Untitled-1:2: beta"));
+    assert!(screen.contains("Untitled-2:3: epsilon"));
+
+    harness.type_str("bet<CR>");
+    assert_eq!(harness.editor.mode(), Mode::Normal);
+    assert_eq!(harness.lines(), ["alpha", "beta"]);
+    assert_eq!(harness.editor.active_tab().cursor(), (0, 1));
+}
+
+#[test]
+fn bookmarks_and_folds_persist_per_file() {
+    let dir = TempDir::new("bookmarks");
+    std::fs::create_dir_all(dir.join("config")).unwrap();
+    std::env::set_var("PHANTOM_DATA_DIR", dir.join("data"));
+    let path = dir.join("notes.txt");
+    std::fs::write(&path, "one\ntwo\nthree\nfour\n  five\n  six\n").unwrap();
+
+    let mut harness = Harness::new(40, 12);
+    harness.editor = phantom::Editor::new(Some(dir.join("config")));
+    harness.editor.open_file(&path).unwrap();
+    harness.editor.goto_location(3, None);
+    harness.type_str("mm");
+    harness.editor.goto_location(2, Some(2));
+    harness.type_str("iX<Esc>");
+    harness.editor.goto_location(5, None);
+    harness.type_str("za");
+    harness.editor.shutdown();
+    assert!(std::fs::read_to_string(dir.join("data/bookmarks.json")).unwrap().contains("notes.txt"));
+    assert!(std::fs::read_to_string(dir.join("data/folds.json")).unwrap().contains("notes.txt"));
+
+    harness.editor = phantom::Editor::new(Some(dir.join("config")));
+    harness.editor.open_file(&path).unwrap();
+    harness.editor.goto_location(1, None);
+    harness.draw();
+    assert_eq!(bookmark_lines(&harness), [3]);
+    assert_eq!(text_rows(&harness)[3].trim_start_matches([' ', '●']), "+-- 3 lines: four");
+    harness.type_str("`.");
+    assert_eq!(harness.editor.active_tab().cursor(), (1, 1));
+}
+
+#[test]
+fn todos_panel_scans_comments_tabs_and_trees() {
+    let dir = TempDir::new("todos");
+    std::fs::create_dir_all(dir.join("src")).unwrap();
+    std::fs::create_dir_all(dir.join("target")).unwrap();
+    std::fs::write(dir.join(".gitignore"), "target/\n").unwrap();
+    std::fs::write(dir.join("src/main.rs"), "// TODO: split this up\nfn main() {\n    let s = \"TODO in a string\";\n    /* FIXME later */\n    let todos = 1; // XXXL is not a marker\n}\n").unwrap();
+    std::fs::write(dir.join("notes.txt"), "plain\nHACK everywhere\n").unwrap();
+    std::fs::write(dir.join("target/build.rs"), "// TODO ignored\n").unwrap();
+
+    let mut harness = Harness::new(140, 20);
+    harness.editor.open_file(&dir.join("src/main.rs")).unwrap();
+    harness.type_str(":todos<CR>");
+    assert_eq!(harness.editor.mode(), Mode::Picker);
+    let screen = harness.screen();
+    assert!(screen.contains("main.rs:1: TODO: split this up"));
+    assert!(screen.contains("main.rs:4: FIXME later"));
+    assert!(!screen.contains("TODO in a string"));
+    assert!(!screen.contains("XXXL"));
+    harness.type_str("fix<CR>");
+    assert_eq!(harness.editor.active_tab().cursor(), (0, 3));
+
+    harness.type_str(":todos ..<CR>");
+    let screen = harness.screen();
+    assert!(screen.contains("TODOs in 2 files"));
+    assert!(screen.contains("notes.txt:2: HACK everywhere"));
+    assert!(!screen.contains("ignored"));
+
+    std::fs::write(dir.join("src/lib.rs"), "// HACK added later\n").unwrap();
+    harness.type_str("HACK<C-r>");
+    let screen = harness.screen();
+    assert!(screen.contains("TODOs in 3 files"));
+    assert!(screen.contains("lib.rs:1: HACK added later"));
+    assert!(!screen.contains("split this up"));
+    harness.type_str("<BS><BS><BS><BS>everywhere<CR>");
+    assert_eq!(harness.lines(), ["plain", "HACK everywhere"]);
+    assert_eq!(harness.editor.active_tab().cursor(), (0, 1));
+
+    harness.type_str(":todos!<CR>");
+    assert!(harness.screen().contains("TODOs in 2 files"));
+    harness.type_str("<Esc>");
+    harness.editor.goto_location(1, None);
+    harness.type_str("OXXX fresh<Esc>:todos!<CR>");
+    assert!(harness.screen().contains("notes.txt:1: XXX fresh"));
+}
+
+#[test]
+fn todo_markers_are_configurable() {
+    let mut harness = Harness::new(60, 16);
+    harness.type_str("iTODO one<CR>NOTE two<Esc>:todos<CR>");
+    assert!(harness.screen().contains("Untitled-1:1: TODO one"));
+    harness.type_str("<Esc>:set todomarkers=NOTE<CR>:todos<CR>");
+    let screen = harness.screen();
+    assert!(screen.contains("Untitled-1:2: NOTE two"));
+    assert!(!screen.contains("TODO one"));
+}
+
+fn outline_entries(harness: &Harness) -> (Vec<String>, Option<String>) {
+    let rows = harness.rows();
+    let Some((top, left)) = rows.iter().enumerate().find_map(|(y, row)| row.find("┌Outline").map(|x| (y, row[..x].chars().count()))) else {
+        return (Vec::new(), None);
+    };
+    let buffer = harness.terminal.backend().buffer();
+    let mut entries = Vec::new();
+    let mut highlighted = None;
+    for (y, row) in rows.iter().enumerate().skip(top + 1) {
+        let text: String = row.chars().skip(left + 1).take(28).collect();
+        if text.starts_with('─') {
+            break;
+        }
+        let text = text.trim_end().to_string();
+        if text.is_empty() {
+            continue;
+        }
+        if buffer.get(left as u16 + 1, y as u16).modifier.contains(Modifier::BOLD) {
+            highlighted = Some(text.trim().to_string());
+        }
+        entries.push(text);
+    }
+    (entries, highlighted)
+}
+
+#[test]
+fn outline_lists_nested_symbols_and_follows_the_cursor() {
+    let dir = TempDir::new("outline");
+    let path = dir.join("point.rs");
+    std::fs::write(&path, "struct Point {\n    x: i32,\n}\n\nimpl Point {\n    pub fn new() -> Self {\n        Point { x: 0 }\n    }\n\n    fn len(&self) -> i32 {\n        self.x\n    }\n}\n\nfn main() {\n}\n").unwrap();
+
+    let mut harness = Harness::new(100, 24);
+    harness.editor.open_file(&path).unwrap();
+    harness.type_str(":outline<CR>");
+    assert_eq!(harness.editor.mode(), Mode::Outline);
+    let (entries, highlighted) = outline_entries(&harness);
+    assert_eq!(entries, ["struct Point", "impl Point", "  fn new", "  fn len", "fn main"]);
+    assert_eq!(highlighted.as_deref(), Some("struct Point"));
+
+    harness.type_str("<Down><Down><Down><CR>");
+    assert_eq!(harness.editor.mode(), Mode::Normal);
+    assert_eq!(harness.editor.active_tab().cursor(), (4, 9));
+    harness.editor.goto_location(13, None);
+    harness.draw();
+    assert_eq!(outline_entries(&harness).1.as_deref(), Some("impl Point"));
+    harness.editor.goto_location(2, None);
+    harness.draw();
+    assert_eq!(outline_entries(&harness).1.as_deref(), Some("struct Point"));
+
+    let rows = harness.rows();
+    let (y, x) = rows.iter().enumerate().find_map(|(y, row)| row.find("fn main").map(|x| (y, row[..x].chars().count()))).unwrap();
+    harness.click(x as u16, y as u16);
+    assert_eq!(harness.editor.active_tab().cursor(), (0, 14));
+
+    harness.editor.goto_location(1, None);
+    harness.type_str("Oenum Kind {}<Esc>");
+    assert_eq!(outline_entries(&harness).0[0], "struct Point");
+    std::thread::sleep(std::time::Duration::from_millis(350));
+    assert!(harness.editor.refresh_outline());
+    harness.draw();
+    assert_eq!(outline_entries(&harness).0[..2], ["enum Kind", "struct Point"]);
+
+    harness.type_str(":outline<CR>");
+    assert_eq!(outline_entries(&harness).0, Vec::<String>::new());
+}
+
+#[test]
+fn outline_nests_markdown_headings_by_level() {
+    let dir = TempDir::new("outline-md");
+    let path = dir.join("notes.md");
+    std::fs::write(&path, "# Title\n\nintro\n\n## Setup\n\n### Linux\n\n## Usage ##\n\n# Appendix\n").unwrap();
+
+    let mut harness = Harness::new(100, 24);
+    harness.editor.open_file(&path).unwrap();
+    harness.type_str(":outline<CR><Esc>");
+    let (entries, highlighted) = outline_entries(&harness);
+    assert_eq!(entries, ["Title", "  Setup", "    Linux", "  Usage", "Appendix"]);
+    assert_eq!(highlighted.as_deref(), Some("Title"));
+    harness.editor.goto_location(8, None);
+    harness.draw();
+    assert_eq!(outline_entries(&harness).1.as_deref(), Some("Linux"));
+}
+
+fn text_rows(harness: &Harness) -> Vec<String> {
+    harness.rows().iter().skip(4).take_while(|row| !row.starts_with('└'))
+        .map(|row| row.trim_start_matches('│').trim_end_matches('│').trim_end().to_string())
+        .collect()
+}
+
+#[test]
+fn folds_collapse_regions_and_reopen_on_search_and_edits() {
+    let dir = TempDir::new("folds");
+    let path = dir.join("main.rs");
+    std::fs::write(&path, "fn main() {\n    let a = 1;\n    if a > 0 {\n        println!(\"{}\", a);\n    }\n}\n\nfn other() {\n    let needle = \"}\";\n}\n").unwrap();
+
+    let mut harness = Harness::new(50, 16);
+    harness.editor.open_file(&path).unwrap();
+    harness.editor.goto_location(4, None);
+    harness.type_str("za");
+    assert_eq!(text_rows(&harness)[..4], ["fn main() {", "    let a = 1;", "+-- 3 lines: if a > 0 {", "}"]);
+    assert_eq!(harness.editor.active_tab().cursor().1, 2);
+    harness.type_str("<Down>");
+    assert_eq!(harness.editor.active_tab().cursor().1, 5);
+    harness.type_str("<Up><Up>");
+    assert_eq!(harness.editor.active_tab().cursor().1, 1);
+
+    harness.type_str("zM");
+    assert_eq!(text_rows(&harness)[..4], ["+-- 6 lines: fn main() {", "", "+-- 3 lines: fn other() {", ""]);
+    assert_eq!(harness.editor.active_tab().cursor().1, 0);
+    harness.type_str("<Down><Down><Down>");
+    assert_eq!(harness.editor.active_tab().cursor().1, 7);
+
+    harness.type_str("/needle<CR>");
+    assert_eq!(harness.editor.active_tab().cursor(), (8, 8));
+    assert_eq!(text_rows(&harness)[2..5], ["fn other() {", "    let needle = \"}\";", "}"]);
+
+    harness.editor.goto_location(1, None);
+    harness.type_str("dd");
+    assert_eq!(text_rows(&harness)[..3], ["    let a = 1;", "+-- 3 lines: if a > 0 {", "}"]);
+    harness.type_str("zR");
+    assert_eq!(text_rows(&harness)[1], "    if a > 0 {");
+}
+
+#[test]
+fn indent_folds_and_fold_method_setting() {
+    let mut harness = Harness::new(50, 16);
+    harness.type_str("idef f():<CR>    if x:<CR>        pass<CR>    return {<CR>}<Esc>");
+    assert_eq!(harness.lines(), ["def f():", "    if x:", "        pass", "    return {", "}"]);
+    harness.editor.goto_location(3, None);
+    harness.type_str("za");
+    assert_eq!(text_rows(&harness)[..4], ["def f():", "+-- 2 lines: if x:", "    return {", "}"]);
+    harness.type_str("za");
+    harness.editor.goto_location(4, None);
+    harness.type_str(":set foldmethod=brace<CR>za");
+    assert_eq!(text_rows(&harness)[..4], ["def f():", "    if x:", "        pass", "+-- 2 lines: return {"]);
+}
+
+fn wait_for_blame(harness: &mut Harness) {
+    harness.draw();
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+    while harness.editor.blame_loading() && std::time::Instant::now() < deadline {
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        harness.editor.refresh_blame();
+    }
+    harness.draw();
+}
+
+#[test]
+fn blame_annotates_lines_and_shows_commits() {
+    let git = |dir: &std::path::Path, args: &[&str]| {
+        std::process::Command::new("git").current_dir(dir)
+            .args(["-c", "user.name=Ada Lovelace", "-c", "user.email=ada@example.com"])
+            .args(args)
+            .output()
+            .unwrap()
+    };
+    if std::process::Command::new("git").arg("--version").output().is_err() {
+        return;
+    }
+    let dir = TempDir::new("blame");
+    git(&dir, &["init", "-q"]);
+    std::fs::write(dir.join("notes.txt"), "first\nsecond\nthird\n").unwrap();
+    git(&dir, &["add", "notes.txt"]);
+    git(&dir, &["commit", "-q", "-m", "Add notes"]);
+    let hash = String::from_utf8(git(&dir, &["rev-parse", "--short=7", "HEAD"]).stdout).unwrap().trim().to_string();
+    std::fs::write(dir.join("scratch.txt"), "untracked\n").unwrap();
+
+    let mut harness = Harness::new(100, 16);
+    harness.editor.open_file(&dir.join("notes.txt")).unwrap();
+    harness.type_str(":blame<CR>");
+    assert_eq!(harness.editor.running_tasks(), 1);
+    wait_for_blame(&mut harness);
+    harness.editor.poll_tasks();
+    assert_eq!(harness.editor.running_tasks(), 0);
+    let rows = text_rows(&harness);
+    assert!(rows[0].starts_with(&format!("{} Ada Lovelace just now", hash)), "{:?}", rows);
+    assert!(rows[0].ends_with("first"));
+
+    harness.type_str("<Down>o");
+    harness.type_str("inserted<Esc>");
+    let rows = text_rows(&harness);
+    assert!(rows[2].starts_with("not committed"));
+    assert!(rows[2].ends_with("inserted"));
+    assert!(rows[3].starts_with(&hash) && rows[3].ends_with("third"));
+
+    harness.type_str(":set blame=status<CR><Esc><Up>");
+    assert_eq!(text_rows(&harness)[1], "second");
+    assert!(harness.rows()[15].starts_with(&format!("{} Ada Lovelace, just now - Add notes", hash)), "{:?}", harness.rows());
+
+    harness.editor.execute_action("show_commit").unwrap();
+    harness.draw();
+    assert_eq!(harness.editor.active_tab().lines()[0].get(..14), Some(&*format!("commit {}", hash)));
+    assert!(harness.screen().contains(&format!("git show {}", hash)));
+    harness.type_str("dd");
+    assert!(harness.editor.active_tab().lines()[0].starts_with("commit"));
+    assert_eq!(harness.editor.status_message(), Some("Buffer is read-only"));
+    harness.type_str("i");
+    assert_eq!(harness.editor.mode(), Mode::Normal);
+
+    harness.editor.open_file(&dir.join("scratch.txt")).unwrap();
+    wait_for_blame(&mut harness);
+    assert_eq!(text_rows(&harness)[0], "untracked");
+    assert!(harness.editor.status_message().unwrap().starts_with("Opened"));
+}
+
+fn wait_for_screen(harness: &mut Harness, text: &str) -> bool {
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+    while std::time::Instant::now() < deadline {
+        harness.editor.poll_terminal();
+        harness.draw();
+        if harness.screen().contains(text) {
+            return true;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(20));
+    }
+    false
+}
+
+#[test]
+fn terminal_panel_runs_shell_commands() {
+    let mut harness = Harness::new(80, 30);
+    harness.type_str(":terminal<CR>");
+    assert_eq!(harness.editor.mode(), Mode::Terminal);
+    assert!(harness.screen().contains("Terminal"));
+    assert!(harness.rows()[29].contains("Starting shell"), "{}", harness.rows()[29]);
+
+    harness.type_str("echo $((6 * 7))<CR>");
+    assert!(wait_for_screen(&mut harness, "\n│42 "), "{}", harness.screen());
+    harness.editor.poll_tasks();
+    assert_eq!(harness.editor.running_tasks(), 0);
+    harness.type_str("printf '\\033[31mred\\033[0m plain\\n'<CR>");
+    assert!(wait_for_screen(&mut harness, "│red plain"), "{}", harness.screen());
+
+    harness.type_str("<C-\\><C-n>");
+    assert_eq!(harness.editor.mode(), Mode::Normal);
+    harness.type_str("ihello<Esc>");
+    assert_eq!(harness.lines(), ["hello"]);
+    assert!(harness.screen().contains("red plain"));
+
+    harness.type_str(":terminal<CR>");
+    assert_eq!(harness.editor.mode(), Mode::Terminal);
+    harness.type_str("exit<CR>");
+    assert!(wait_for_screen(&mut harness, "Terminal [exited]"), "{}", harness.screen());
+    harness.type_str("x");
+    assert_eq!(harness.editor.mode(), Mode::Normal);
+    assert!(!harness.screen().contains("Terminal"));
+}
+
+#[test]
+fn read_inserts_files_and_command_output_below_the_cursor() {
+    let dir = TempDir::new("read");
+    std::fs::write(dir.join("part.txt"), "inserted one\ninserted two\n").unwrap();
+    std::fs::write(dir.join("main.txt"), "first\nsecond\n").unwrap();
+
+    let mut harness = Harness::new(80, 16);
+    harness.editor.open_file(&dir.join("main.txt")).unwrap();
+    harness.type_str(":r part.txt<CR>");
+    assert_eq!(harness.lines(), ["first", "inserted one", "inserted two", "second"]);
+    assert_eq!(harness.editor.active_tab().cursor(), (0, 1));
+    harness.type_str("u");
+    assert_eq!(harness.lines(), ["first", "second"]);
+
+    harness.type_str(":0r part.txt<CR>");
+    assert_eq!(harness.lines(), ["inserted one", "inserted two", "first", "second"]);
+    assert_eq!(harness.editor.active_tab().cursor(), (0, 0));
+
+    harness.type_str(":$r !printf 'out\\nput\\n'<CR>");
+    assert_eq!(harness.lines()[4..], ["out", "put"]);
+    assert_eq!(harness.editor.active_tab().cursor(), (0, 4));
+
+    harness.type_str(":r missing.txt<CR>");
+    assert!(harness.editor.status_message().unwrap().starts_with("Can't read"));
+    assert_eq!(harness.lines().len(), 6);
+}
+
+#[test]
+fn messages_and_shell_output_open_in_a_scrollable_pager() {
+    let mut harness = Harness::new(80, 16);
+    harness.type_str(":first<CR>:second<CR>:messages<CR>");
+    let lines = harness.editor.output_lines().unwrap();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].contains("first") && lines[1].contains("second"));
+    let screen = harness.screen();
+    assert!(screen.contains("Messages (2/2)"), "{}", screen);
+    harness.type_str("/first<CR>");
+    assert!(harness.screen().contains("Messages (1/2)"));
+    harness.type_str("yyq");
+    assert!(harness.editor.output_lines().is_none());
+    harness.type_str("p");
+    assert_eq!(harness.lines()[0], lines[0]);
+
+    harness.type_str(":!seq 1 10002; exit 3<CR>");
+    let started = std::time::Instant::now();
+    while harness.editor.output_lines().unwrap().last().is_none_or(|line| !line.contains("failed")) {
+        assert!(started.elapsed() < std::time::Duration::from_secs(10));
+        harness.editor.poll_output();
+    }
+    let lines = harness.editor.output_lines().unwrap();
+    assert_eq!(lines[0], "... older output dropped");
+    assert_eq!(lines.len(), 10_001);
+    assert_eq!(lines[lines.len() - 3..], ["10002", "", "seq 1 10002; exit 3 failed (exit status: 3)"]);
+    assert_eq!(harness.editor.mode(), Mode::Normal);
+    harness.type_str("gj");
+    assert!(harness.screen().contains("(2/10001)"));
+    harness.type_str("<Esc>");
+    assert!(harness.editor.output_lines().is_none());
+}
+
+#[test]
+fn yank_to_new_tab_copies_the_selection_or_buffer() {
+    let mut harness = Harness::new(80, 16);
+    harness.type_str("ifn main() {}<CR>fn other() {}<Esc>");
+    harness.editor.execute_action("yank_to_new_tab").unwrap();
+    assert_eq!(harness.editor.tabs().len(), 2);
+    assert_eq!(harness.lines(), ["fn main() {}", "fn other() {}"]);
+
+    harness.type_str("<F1>");
+    harness.editor.goto_location(1, Some(4));
+    harness.type_str("v<Down>");
+    harness.editor.execute_action("yank_to_new_tab").unwrap();
+    assert_eq!(harness.editor.mode(), Mode::Normal);
+    assert_eq!(harness.editor.tabs().len(), 3);
+    assert_eq!(harness.lines(), ["main() {}", "fn o"]);
+}
+
+#[test]
+fn set_options_render_immediately_and_support_local_overrides() {
+    let dir = TempDir::new("options");
+    let path = dir.join("dos.txt");
+    std::fs::write(&path, "\tx\r\nsecond  \r\n").unwrap();
+
+    let mut harness = Harness::new(60, 14);
+    harness.editor.open_file(&path).unwrap();
+    harness.type_str(":set ff?<CR>");
+    assert_eq!(harness.editor.status_message(), Some("fileformat=dos"));
+    assert_eq!(text_rows(&harness)[..2], ["    x", "second"]);
+
+    harness.type_str(":set ts=2 list<CR><Esc>");
+    assert_eq!(text_rows(&harness)[..2], ["→ x", "second··"]);
+    harness.type_str(":set nu<CR><Esc>");
+    assert_eq!(text_rows(&harness)[..2], ["  1 → x", "  2 second··"]);
+    harness.type_str(":set nonumber nolist<CR><Esc>");
+    assert_eq!(text_rows(&harness)[0], "  x");
+
+    harness.type_str(":setlocal ts=8<CR>");
+    assert_eq!(text_rows(&harness)[0], "        x");
+    harness.editor.execute_action("new_tab").unwrap();
+    harness.type_str(":set ts?<CR>");
+    assert_eq!(harness.editor.status_message(), Some("tabstop=2"));
+    harness.type_str(":setlocal ts?<CR>");
+    assert_eq!(harness.editor.status_message(), Some("tabstop=2"));
+    harness.type_str(":set so=x<CR>");
+    assert_eq!(harness.editor.status_message(), Some("Invalid number: x"));
+    harness.type_str(":set bogus<CR>");
+    assert_eq!(harness.editor.status_message(), Some("Unknown option: bogus"));
+    harness.type_str(":setlocal recentfiles<CR>");
+    assert_eq!(harness.editor.status_message(), Some("recentfiles is a global option"));
+
+    harness.type_str(":set cursorl<Tab>");
+    assert!(harness.screen().contains(":set cursorline"));
+    harness.type_str("<Esc>:set s<Tab>");
+    assert!(harness.screen().contains(":set scrolloff"));
+    harness.type_str("<Tab>");
+    assert!(harness.screen().contains(":set showignored"));
+    harness.type_str("<Esc><F1>:set<CR>");
+    assert_eq!(harness.editor.mode(), Mode::Picker);
+    let screen = harness.screen();
+    assert!(screen.contains("tabstop=8 (local)") && screen.contains("fileformat=dos") && !screen.contains("number"));
+    harness.type_str("<Esc>:set cul cc=3<CR><Esc>");
+    assert_eq!(text_rows(&harness)[..2], ["        x", "second"]);
+
+    harness.type_str(":set ff=unix<CR>:w<CR>");
+    harness.editor.wait_for_saves().unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "\tx\nsecond  \n");
+}
+
+#[test]
+fn wrap_splits_long_lines_across_rows_and_keeps_the_cursor_on_screen() {
+    let mut harness = Harness::new(30, 12);
+    let long: String = ('a'..='z').cycle().take(60).collect();
+    harness.type_str(&format!("i{}<CR>short<Esc>", long));
+    assert_eq!(text_rows(&harness)[..2], [&long[..28], "short"]);
+
+    harness.type_str(":set wrap<CR>");
+    assert_eq!(text_rows(&harness)[..4], [&long[..28], &long[28..56], &long[56..], "short"]);
+    assert_eq!(harness.terminal.get_cursor().unwrap(), (1 + 5, 4 + 3));
+    harness.type_str("<Up><End><Left>");
+    assert_eq!(harness.terminal.get_cursor().unwrap(), (1 + 3, 4 + 2));
+    assert_eq!(harness.cursor_cell(), "h");
+    harness.type_str(":set nu<CR>");
+    assert_eq!(text_rows(&harness)[..4], [format!("  1 {}", &long[..24]), format!("    {}", &long[24..48]), format!("    {}", &long[48..]), "  2 short".to_string()]);
+    harness.click(1 + 4 + 2, 4 + 1);
+    harness.drag(1 + 4 + 2, 4 + 3);
+    assert!(selection_snapshot(&harness, 4 + 1).starts_with(&format!("    {}[{}", &long[24..26], &long[26..48])));
+    assert_eq!(selection_snapshot(&harness, 4 + 3), "  2 [sho]rt");
+    harness.release(1 + 4 + 2, 4 + 3);
+
+    harness.type_str(&format!(":1<CR>{}", format!("o{}<Esc>", long).repeat(6)));
+    assert_eq!(harness.editor.active_tab().cursor().1, 6);
+    let (_, row) = harness.terminal.get_cursor().unwrap();
+    assert!((4..4 + 6).contains(&row), "{}", row);
+    assert_eq!(harness.cursor_cell(), " ");
+    assert_eq!(text_rows(&harness).last().unwrap(), &format!("    {}", &long[48..]));
+
+    harness.type_str(":set nowrap nonu<CR><Home>");
+    let rows = text_rows(&harness);
+    assert!(rows.iter().all(|row| row == &long[..28] || row == "short"), "{:?}", rows);
+}
+
+fn wait_for_saves(harness: &mut Harness) {
+    for _ in 0..200 {
+        harness.editor.poll_saves();
+        if !harness.editor.saving() {
+            return;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+    panic!("save did not finish");
+}
+
+#[test]
+fn saves_run_in_the_background_and_queue_behind_each_other() {
+    let dir = TempDir::new("save");
+    let path = dir.join("notes.txt");
+    std::fs::write(&path, "one\n").unwrap();
+
+    let mut harness = Harness::new(60, 12);
+    harness.editor.open_file(&path).unwrap();
+    harness.type_str("otwo<Esc>:w<CR>");
+    assert!(harness.rows()[1].contains("notes.txt (saving…)"));
+    harness.type_str("othree<Esc>");
+    wait_for_saves(&mut harness);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "one\ntwo\n");
+    assert!(harness.editor.active_tab().is_modified());
+    harness.draw();
+    assert!(harness.rows()[1].contains("notes.txt +"));
+
+    harness.type_str(":w<CR>ofour<Esc>:w<CR>");
+    assert!(harness.editor.status_message().unwrap().ends_with("notes.txt… (queued)"));
+    wait_for_saves(&mut harness);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "one\ntwo\nthree\nfour\n");
+    assert!(!harness.editor.active_tab().is_modified());
+    assert!(harness.editor.status_message().unwrap().starts_with("Saved "));
+    assert!(std::fs::read_dir(&dir).unwrap().count() == 1);
+
+    harness.type_str(":w notes.txt/inner.txt<CR>");
+    wait_for_saves(&mut harness);
+    assert!(harness.editor.status_message().unwrap().starts_with("Save failed"));
+    assert_eq!(harness.editor.active_tab().file(), Some(path.to_str().unwrap()));
+
+    harness.type_str("ofive<Esc>:w notes.txt/inner.txt<CR>:w<CR>");
+    assert!(harness.editor.status_message().unwrap().ends_with("notes.txt… (queued)"));
+    wait_for_saves(&mut harness);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "one\ntwo\nthree\nfour\nfive\n");
+    assert!(!harness.editor.active_tab().is_modified());
+
+    harness.type_str("osix<Esc>:w notes.txt/inner.txt<CR>:w<CR>");
+    harness.editor.wait_for_saves().unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "one\ntwo\nthree\nfour\nfive\nsix\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn hard_linked_files_are_saved_in_place() {
+    use std::os::unix::fs::MetadataExt;
+    let dir = TempDir::new("save-linked");
+    let path = dir.join("notes.txt");
+    let link = dir.join("link.txt");
+    std::fs::write(&path, "one\n").unwrap();
+    std::fs::hard_link(&path, &link).unwrap();
+    let inode = std::fs::metadata(&path).unwrap().ino();
+
+    let mut harness = Harness::new(60, 12);
+    harness.editor.open_file(&path).unwrap();
+    harness.type_str("otwo<Esc>:w<CR>");
+    wait_for_saves(&mut harness);
+    assert_eq!(std::fs::read_to_string(&link).unwrap(), "one\ntwo\n");
+    assert_eq!(std::fs::metadata(&path).unwrap().ino(), inode);
+    assert_eq!(std::fs::metadata(&path).unwrap().nlink(), 2);
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 2);
+}
+
+#[cfg(unix)]
+#[test]
+fn permission_denied_saves_offer_another_name_or_the_privileged_write_command() {
+    use std::os::unix::fs::PermissionsExt;
+    let dir = TempDir::new("denied");
+    let locked = dir.join("locked");
+    std::fs::create_dir_all(&locked).unwrap();
+    std::fs::write(dir.join("settings.toml"), "show_start_screen = false\nprivileged_write_command = \"chmod u+w % && cat > %\"\n").unwrap();
+    let path = locked.join("hosts");
+    let lock = |path: &std::path::Path, mode: u32| std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode)).unwrap();
+    std::fs::write(&path, "old\n").unwrap();
+    lock(&path, 0o444);
+    lock(&locked, 0o555);
+    if std::fs::OpenOptions::new().write(true).open(&path).is_ok() {
+        lock(&locked, 0o755);
+        return;
+    }
+
+    let mut harness = Harness::new(80, 16);
+    harness.editor = phantom::Editor::new(Some(dir.to_path_buf()));
+    harness.editor.open_file(&path).unwrap();
+    harness.type_str("onew<Esc>:w<CR>");
+    wait_for_saves(&mut harness);
+    assert!(harness.editor.status_message().unwrap().starts_with("Save failed: Permission denied"));
+    harness.draw();
+    let screen = harness.screen();
+    assert!(screen.contains("[ Write with chmod ]") && screen.contains("[ Save elsewhere ]"), "{}", screen);
+    harness.type_str("n");
+    assert!(harness.screen().contains("Save as"));
+    harness.type_str("<Esc>");
+
+    lock(&locked, 0o755);
+    std::fs::remove_file(&path).unwrap();
+    lock(&locked, 0o555);
+    harness.type_str(":w<CR>");
+    wait_for_saves(&mut harness);
+    harness.type_str("y");
+    assert!(harness.editor.privileged_write_target().unwrap().ends_with("hosts"));
+    assert!(!harness.editor.run_privileged_write());
+    assert!(harness.editor.privileged_write_target().is_none());
+    let message = harness.editor.status_message().unwrap().to_string();
+    assert!(message.starts_with("Save failed: chmod: ") && message.contains("hosts"), "{}", message);
+    assert!(harness.editor.active_tab().is_modified());
+
+    lock(&locked, 0o755);
+    std::fs::write(&path, "old\n").unwrap();
+    lock(&path, 0o444);
+    lock(&locked, 0o555);
+    harness.type_str(":w<CR>");
+    wait_for_saves(&mut harness);
+    harness.type_str("y");
+    assert!(!harness.editor.run_privileged_write());
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "old\nnew\n");
+    assert!(!harness.editor.active_tab().is_modified());
+    assert!(harness.editor.status_message().unwrap().starts_with("Saved "));
+    lock(&locked, 0o755);
+}
+
+#[test]
+fn blocked_edits_on_read_only_buffers_keep_the_undo_history() {
+    let mut harness = Harness::new(60, 12);
+    harness.type_str("ione<Esc>otwo<Esc>othree<Esc>u");
+    assert_eq!(harness.lines(), ["one", "two"]);
+    harness.type_str(":set readonly<CR><Esc>");
+    harness.type_str("dd");
+    assert_eq!(harness.editor.status_message(), Some("Buffer is read-only"));
+    harness.type_str("u");
+    assert_eq!(harness.lines(), ["one", "two"]);
+
+    harness.type_str(":set noro<CR><Esc>");
+    harness.type_str("<C-r>");
+    assert_eq!(harness.lines(), ["one", "two", "three"]);
+    harness.type_str("uu");
+    assert_eq!(harness.lines(), ["one"]);
+    harness.type_str("<C-r>");
+    assert_eq!(harness.lines(), ["one", "two"]);
+}
+
+#[test]
+fn modified_flag_follows_the_content_through_undo_redo_and_saves() {
+    let dir = TempDir::new("modified");
+    let path = dir.join("notes.txt");
+    std::fs::write(&path, "one\n").unwrap();
+
+    let mut harness = Harness::new(60, 12);
+    harness.editor.open_file(&path).unwrap();
+    let modified = |harness: &mut Harness| {
+        harness.draw();
+        let marked = harness.rows()[1].contains("notes.txt +");
+        assert_eq!(marked, harness.editor.active_tab().is_modified());
+        marked
+    };
+    assert!(!modified(&mut harness));
+    harness.type_str("otwo<Esc>");
+    assert!(modified(&mut harness));
+    harness.type_str("u");
+    assert!(!modified(&mut harness));
+    harness.editor.execute_action("redo").unwrap();
+    assert!(modified(&mut harness));
+
+    harness.type_str(":w<CR>");
+    wait_for_saves(&mut harness);
+    assert!(!modified(&mut harness));
+    harness.type_str("u");
+    assert!(modified(&mut harness));
+    harness.editor.execute_action("redo").unwrap();
+    assert!(!modified(&mut harness));
+
+    harness.type_str("ix");
+    assert!(harness.editor.active_tab().is_modified());
+    harness.type_str("<BS><Esc>");
+    assert!(!modified(&mut harness));
+    harness.type_str("ddu");
+    assert!(!modified(&mut harness));
+    harness.type_str(":set ff=dos<CR>");
+    assert!(modified(&mut harness));
+    harness.type_str(":set ff=unix<CR>");
+    assert!(!modified(&mut harness));
+}
+
+#[test]
+fn custom_syntaxes_and_filetype_detection_load_from_the_config_dir() {
+    let dir = TempDir::new("syntaxes");
+    let config = dir.join("config");
+    std::fs::create_dir_all(config.join("syntaxes")).unwrap();
+    let syntax = |name: &str, extension: &str| format!(
+        "%YAML 1.2\n---\nname: {}\nfile_extensions: [{}]\nscope: source.{}\ncontexts:\n  main:\n    - match: '#.*'\n      scope: comment.line\n",
+        name, extension, extension
+    );
+    std::fs::write(config.join("syntaxes/Recipe.sublime-syntax"), syntax("Recipe", "recipe")).unwrap();
+    std::fs::write(config.join("syntaxes/broken.sublime-syntax"), "name: [unclosed").unwrap();
+    std::fs::write(config.join("settings.toml"), "[filetype_detect]\n\"*.justfile\" = \"recipe\"\n\"Dockerfile*\" = \"sh\"\n").unwrap();
+    for name in ["dinner.recipe", "build.justfile", "Dockerfile.dev", "notes.later"] {
+        std::fs::write(dir.join(name), "# hello\n").unwrap();
+    }
+
+    let mut editor = phantom::Editor::new(Some(config.clone()));
+    let message = editor.status_message().unwrap_or_default().to_string();
+    assert!(message.starts_with("Skipped syntaxes/broken.sublime-syntax:"), "{}", message);
+    let syntax_of = |editor: &mut phantom::Editor, name: &str| {
+        editor.open_file(&dir.join(name)).unwrap();
+        editor.execute_command_line("set syntax?").unwrap();
+        editor.status_message().unwrap_or_default().to_string()
+    };
+    assert_eq!(syntax_of(&mut editor, "dinner.recipe"), "syntax=Recipe");
+    assert_eq!(syntax_of(&mut editor, "build.justfile"), "syntax=Recipe");
+    assert_eq!(syntax_of(&mut editor, "Dockerfile.dev"), "syntax=Bourne Again Shell (bash)");
+    assert_eq!(syntax_of(&mut editor, "notes.later"), "syntax=Plain Text");
+
+    std::fs::write(config.join("syntaxes/Later.sublime-syntax"), syntax("Later", "later")).unwrap();
+    editor.execute_command_line("config reload").unwrap();
+    assert!(editor.status_message().unwrap_or_default().starts_with("Skipped syntaxes/broken.sublime-syntax:"));
+    editor.execute_command_line("set syntax?").unwrap();
+    assert_eq!(editor.status_message(), Some("syntax=Later"));
+}
+
+#[test]
+fn default_keybindings_only_name_real_actions() {
+    assert_eq!(phantom::Editor::headless().unknown_keybindings(), Vec::<String>::new());
+
+    let dir = TempDir::new("actions");
+    std::fs::write(dir.join("settings.toml"), "cua_bindings = true\n").unwrap();
+    let mut editor = phantom::Editor::new(Some(dir.to_path_buf()));
+    assert_eq!(editor.unknown_keybindings(), Vec::<String>::new());
+
+    let config = std::fs::read_to_string(dir.join("config.toml")).unwrap();
+    std::fs::write(dir.join("config.toml"), config.replace("\"move_left\"", "\"move_lft\"")).unwrap();
+    editor.execute_command_line("config reload").unwrap();
+    assert!(editor.status_message().unwrap_or_default().ends_with("is bound to unknown action \"move_lft\""));
+    assert!(editor.unknown_keybindings().contains(&"config.toml: normal_mode \"Left\" is bound to unknown action \"move_lft\"".to_string()));
+}
+
+#[test]
+fn partial_config_keeps_the_default_bindings_it_does_not_mention() {
+    let dir = TempDir::new("partial-config");
+    std::fs::write(dir.join("settings.toml"), "show_start_screen = false\n").unwrap();
+    std::fs::write(dir.join("config.toml"), "# mine\n[normal_mode]\nq = \"buffer_stats\"\n\"Ctrl+b\" = \"\"\n").unwrap();
+    let mut harness = Harness::new(100, 20);
+    harness.editor = phantom::Editor::new(Some(dir.to_path_buf()));
+    assert_eq!(harness.editor.unknown_keybindings(), Vec::<String>::new());
+
+    harness.type_str("ione two<Esc>g<C-g>");
+    assert!(harness.editor.status_message().unwrap_or_default().starts_with("Buffer: "));
+    harness.editor.execute_command_line("set number?").unwrap();
+    harness.type_str("q");
+    assert!(harness.editor.status_message().unwrap_or_default().starts_with("Buffer: "));
+    harness.type_str("<C-b>");
+    assert!(!harness.screen().contains("Debug Output"));
+
+    harness.editor.execute_command_line("mkconfig").unwrap();
+    let written = std::fs::read_to_string(dir.join("config.toml")).unwrap();
+    assert!(written.starts_with("# mine\n[normal_mode]\nq = \"buffer_stats\"\n\"Ctrl+b\" = \"\"\n"), "{}", written);
+    assert!(written.contains("\"gCtrl+g\" = \"buffer_stats\""));
+    harness.editor.execute_command_line("config reload").unwrap();
+    harness.type_str("<C-b>");
+    assert!(!harness.screen().contains("Debug Output"));
+}
+
+#[test]
+fn out_of_range_settings_are_clamped_to_the_option_bounds() {
+    let dir = TempDir::new("clamped-settings");
+    std::fs::write(dir.join("settings.toml"), "show_start_screen = false\ntab_width = 0\n").unwrap();
+    let mut harness = Harness::new(60, 12);
+    harness.editor = phantom::Editor::new(Some(dir.to_path_buf()));
+    assert_eq!(harness.editor.status_message(), Some("settings.toml: tabstop=0 is out of range; using 1"));
+
+    harness.type_str("i\tx<Esc>");
+    assert!(harness.screen().contains(" x"));
+    harness.editor.execute_command_line("set tabstop?").unwrap();
+    assert_eq!(harness.editor.status_message(), Some("tabstop=1"));
+
+    harness.editor.execute_command_line("config reload").unwrap();
+    assert_eq!(harness.editor.status_message(), Some("settings.toml: tabstop=0 is out of range; using 1"));
+    harness.editor.execute_command_line("set tabstop?").unwrap();
+    assert_eq!(harness.editor.status_message(), Some("tabstop=1"));
+}
+
+#[test]
+fn navigation_keys_run_named_actions_listed_by_actions_command() {
+    let mut harness = Harness::new(120, 20);
+    harness.type_str("ione two<Esc><Home>");
+    assert_eq!(harness.editor.active_tab().cursor(), (0, 0));
+    harness.type_str("<End><Left>");
+    assert_eq!(harness.editor.active_tab().cursor(), (6, 0));
+    harness.type_str("<C-t><S-Tab>");
+    assert_eq!(harness.lines(), ["one two"]);
+
+    harness.type_str(":actions<CR>");
+    assert_eq!(harness.editor.mode(), Mode::Picker);
+    assert!(harness.screen().contains("close_terminal            unbound"));
+    harness.type_str("move_left");
+    let screen = harness.screen();
+    assert!(screen.contains("move_left                 normal Left, visual Left, visual_block Left"), "{}", screen);
+    harness.type_str("<BS><BS><BS><BS><BS><BS><BS><BS><BS>goto_line_st<CR>");
+    assert_eq!(harness.editor.mode(), Mode::Normal);
+    assert_eq!(harness.editor.active_tab().cursor(), (0, 0));
+
+    harness.editor.execute_action("no_such_action").unwrap();
+    assert_eq!(harness.editor.status_message(), Some("Unknown action: no_such_action"));
+}
+
+#[test]
+fn ctrl_x_ctrl_f_completes_paths_relative_to_the_file() {
+    let dir = TempDir::new("complete");
+    std::fs::create_dir_all(dir.join("src")).unwrap();
+    std::fs::write(dir.join("src/main.rs"), "").unwrap();
+    std::fs::write(dir.join("src/lib.rs"), "").unwrap();
+    std::fs::write(dir.join("notes.txt"), "").unwrap();
+    std::fs::write(dir.join(".hidden"), "").unwrap();
+
+    let mut harness = Harness::new(80, 16);
+    harness.editor.open_file(&dir.join("notes.txt")).unwrap();
+    harness.type_str("iuse(./s<C-x><C-f>");
+    assert!(harness.screen().contains("./src/"), "{}", harness.screen());
+    harness.type_str("<CR>");
+    assert_eq!(harness.lines(), ["use(./src/"]);
+    let screen = harness.screen();
+    assert!(screen.contains("./src/lib.rs") && screen.contains("./src/main.rs"), "{}", screen);
+    harness.type_str("<Down><CR>");
+    assert_eq!(harness.lines(), ["use(./src/main.rs"]);
+    assert!(!harness.screen().contains("./src/lib.rs"));
+    assert_eq!(harness.editor.mode(), Mode::Insert);
+
+    harness.type_str(" <C-x><C-f>");
+    let screen = harness.screen();
+    assert!(screen.contains("notes.txt") && !screen.contains(".hidden"), "{}", screen);
+    harness.type_str("<Esc>");
+    assert_eq!(harness.editor.mode(), Mode::Insert);
+    harness.type_str(".<C-x><C-f><CR>");
+    assert_eq!(harness.lines(), ["use(./src/main.rs .hidden"]);
+
+    harness.type_str(&format!(" {}/no<C-x><C-f><CR><Esc>", dir.display()));
+    assert_eq!(harness.lines(), [format!("use(./src/main.rs .hidden {}/notes.txt", dir.display())]);
+    assert_eq!(harness.editor.mode(), Mode::Normal);
+}
+
+#[test]
+fn saving_an_untitled_buffer_prompts_for_a_path() {
+    let dir = TempDir::new("save-as");
+    std::fs::write(dir.join("taken.txt"), "old\n").unwrap();
+
+    let mut harness = Harness::new(80, 16);
+    harness.type_str("ifn main() {}<Esc>:w<CR>");
+    assert!(harness.screen().contains("Save as"), "{}", harness.screen());
+    harness.type_str(&format!("<C-u>{}/taken.txt<CR>", dir.display()));
+    assert!(harness.screen().contains("Overwrite it?"), "{}", harness.screen());
+    harness.type_str("n");
+    assert!(harness.screen().contains("Save as"));
+    harness.type_str(&format!("<C-u>{}/main.rs<CR>", dir.display()));
+    wait_for_saves(&mut harness);
+    assert_eq!(std::fs::read_to_string(dir.join("main.rs")).unwrap(), "fn main() {}\n");
+    assert_eq!(std::fs::read_to_string(dir.join("taken.txt")).unwrap(), "old\n");
+    assert!(!harness.editor.active_tab().is_modified());
+    harness.draw();
+    assert!(harness.rows()[1].contains("main.rs"));
+    harness.type_str(":set syntax?<CR>");
+    assert_eq!(harness.editor.status_message(), Some("syntax=Rust"));
+
+    harness.type_str("<C-t>iscratch<Esc>:wq<CR><Esc>");
+    assert_eq!(harness.editor.tabs().len(), 2);
+    assert!(harness.editor.active_tab().file().is_none());
+    assert!(harness.editor.active_tab().is_modified());
+    harness.type_str(&format!(":wq<CR><C-u>{}/taken.txt<CR>y", dir.display()));
+    assert_eq!(std::fs::read_to_string(dir.join("taken.txt")).unwrap(), "scratch\n");
+}
+
+#[test]
+fn pager_streams_input_and_only_scrolls_searches_and_quits() {
+    let text: String = (1..=100).map(|n| format!("line {}\n", n)).collect();
+    let mut harness = Harness::new(60, 12);
+    harness.editor.page_reader(std::io::Cursor::new(text.into_bytes()));
+    for _ in 0..200 {
+        if !harness.editor.poll_pager() && harness.lines().len() == 100 {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(5));
+    }
+    assert_eq!(harness.lines().len(), 100);
+    assert_eq!(harness.lines()[99], "line 100");
+    harness.draw();
+    assert_eq!(harness.editor.mode(), Mode::Pager);
+    assert!(harness.screen().contains("PAGER"), "{}", harness.screen());
+    assert!(harness.rows()[1].contains("stdin"));
+
+    harness.type_str("xidd");
+    assert_eq!(harness.lines().len(), 100);
+    assert_eq!(harness.editor.mode(), Mode::Pager);
+    assert!(harness.editor.status_message().unwrap().starts_with("Read-only pager"));
+
+    harness.type_str(" ");
+    assert!(!text_rows(&harness).iter().any(|row| row.ends_with("line 1")));
+    harness.type_str("b");
+    assert!(text_rows(&harness).iter().any(|row| row.ends_with("line 1")));
+    harness.type_str("/line 50<CR>");
+    assert_eq!(harness.editor.mode(), Mode::Pager);
+    assert_eq!(harness.editor.active_tab().cursor(), (0, 49));
+    harness.type_str("G");
+    assert_eq!(harness.editor.active_tab().cursor().1, 99);
+    assert!(harness.type_str("q"));
+}
+
+#[test]
+fn pager_wraps_long_lines_until_w_turns_it_off() {
+    let long: String = ('a'..='z').cycle().take(100).collect();
+    let mut harness = Harness::new(60, 12);
+    harness.editor.page_reader(std::io::Cursor::new(format!("{}\nshort\n", long).into_bytes()));
+    for _ in 0..200 {
+        if !harness.editor.poll_pager() && harness.lines().len() == 2 {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(5));
+    }
+    harness.draw();
+    assert_eq!(text_rows(&harness)[..3], [&long[..58], &long[58..], "short"]);
+
+    harness.type_str("w");
+    assert_eq!(text_rows(&harness)[..2], [&long[..58], "short"]);
+    assert_eq!(harness.editor.mode(), Mode::Pager);
+    harness.type_str("w");
+    assert_eq!(text_rows(&harness)[..3], [&long[..58], &long[58..], "short"]);
+}
+
+struct ChannelReader(std::sync::mpsc::Receiver<Vec<u8>>, Vec<u8>);
+
+impl std::io::Read for ChannelReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.1.is_empty() {
+            match self.0.recv() {
+                Ok(data) => self.1 = data,
+                Err(_) => return Ok(0),
+            }
+        }
+        let n = buf.len().min(self.1.len());
+        buf[..n].copy_from_slice(&self.1[..n]);
+        self.1.drain(..n);
+        Ok(n)
+    }
+}
+
+#[test]
+fn background_tasks_report_progress_and_can_be_cancelled() {
+    let dir = TempDir::new("tasks");
+    let mut harness = Harness::new(80, 12);
+    harness.type_str(&format!("ihello<Esc>:w {}/saved.txt<CR>", dir.display()));
+    harness.draw();
+    assert!(harness.rows()[11].contains("Saving"), "{}", harness.rows()[11]);
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    harness.editor.execute_action("new_tab").unwrap();
+    harness.editor.page_reader(ChannelReader(receiver, Vec::new()));
+    harness.draw();
+    assert!(harness.rows()[11].contains("2 tasks…"), "{}", harness.rows()[11]);
+
+    sender.send(b"one\ntwo\n".to_vec()).unwrap();
+    for _ in 0..200 {
+        harness.editor.poll_pager();
+        harness.editor.poll_saves();
+        harness.editor.poll_tasks();
+        if harness.editor.running_tasks() == 1 && harness.lines().len() == 2 {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(5));
+    }
+    harness.draw();
+    assert!(harness.rows()[11].ends_with("Reading stdin 2 "), "{}", harness.rows()[11]);
+
+    harness.editor.execute_command_line("tasks").unwrap();
+    harness.draw();
+    assert!(harness.screen().contains("Reading stdin  2"), "{}", harness.screen());
+    harness.type_str("<CR>");
+    assert_eq!(harness.editor.status_message(), Some("Cancelling Reading stdin"));
+
+    sender.send(b"three\n".to_vec()).unwrap();
+    for _ in 0..200 {
+        if !harness.editor.poll_tasks() {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(5));
+    }
+    assert_eq!(harness.editor.running_tasks(), 0);
+    assert_eq!(harness.editor.status_message(), Some("Cancelled Reading stdin"));
+    harness.editor.execute_command_line("tasks").unwrap();
+    assert_eq!(harness.editor.status_message(), Some("No running tasks"));
+}
+
+#[test]
+fn git_commit_and_rebase_files_get_their_own_defaults() {
+    let dir = TempDir::new("git");
+    let message = "\n# Please enter the commit message for your changes.\n";
+    std::fs::write(dir.join("COMMIT_EDITMSG"), message).unwrap();
+    let mut harness = Harness::new(80, 12);
+    harness.editor.open_file(&dir.join("COMMIT_EDITMSG")).unwrap();
+    assert_eq!(harness.editor.mode(), Mode::Insert);
+    assert_eq!(harness.editor.active_tab().cursor(), (0, 0));
+    harness.type_str("Fix the parser<Esc>");
+    harness.draw();
+    let buffer = harness.terminal.backend().buffer().clone();
+    assert_eq!(buffer.get(1, 5).fg, Color::Rgb(0x7f, 0x84, 0x8e));
+    assert_eq!(buffer.get(72, 5).bg, Color::Rgb(0x2a, 0x2d, 0x2e));
+    let body: String = ('a'..='z').cycle().take(100).collect();
+    harness.type_str(&format!("o{}<Esc>", body));
+    assert_eq!(text_rows(&harness)[1..3], [&body[..78], &body[78..]]);
+    assert!(!harness.type_str(":q<CR>"));
+    assert!(harness.type_str("n"));
+    assert_eq!(std::fs::read_to_string(dir.join("COMMIT_EDITMSG")).unwrap(), message);
+
+    std::fs::write(dir.join("git-rebase-todo"), "pick 1234abc First\ns 5678def Second\nexec make\n").unwrap();
+    let mut harness = Harness::new(80, 12);
+    harness.editor.open_file(&dir.join("git-rebase-todo")).unwrap();
+    assert_eq!(harness.editor.mode(), Mode::Normal);
+    harness.draw();
+    let buffer = harness.terminal.backend().buffer().clone();
+    assert_eq!(buffer.get(1, 4).fg, Color::Rgb(0x61, 0xaf, 0xef));
+    assert_eq!(buffer.get(6, 4).fg, Color::Rgb(0xd1, 0x9a, 0x66));
+    for expected in ["squash", "fixup", "edit", "pick"] {
+        harness.type_str("<C-a>");
+        assert_eq!(harness.lines()[0], format!("{} 1234abc First", expected));
+    }
+    harness.type_str("<Down><C-a>");
+    assert_eq!(harness.lines()[1], "fixup 5678def Second");
+    harness.type_str("u");
+    assert_eq!(harness.lines()[1], "s 5678def Second");
+    harness.type_str("<Down><C-a>");
+    assert_eq!(harness.lines()[2], "exec make");
+    assert_eq!(harness.editor.status_message(), Some("exec doesn't take a commit"));
+}
+
+#[test]
+fn rebase_cycle_is_only_bound_in_rebase_todo_files() {
+    let dir = TempDir::new("rebase-keys");
+    std::fs::write(dir.join("settings.toml"), "cua_bindings = true\nshow_start_screen = false\n").unwrap();
+    std::fs::write(dir.join("notes.txt"), "one\ntwo\n").unwrap();
+    std::fs::write(dir.join("git-rebase-todo"), "pick 1234abc First\n").unwrap();
+    let mut harness = Harness::new(80, 12);
+    harness.editor = phantom::Editor::new(Some(dir.to_path_buf()));
+    harness.editor.open_file(&dir.join("notes.txt")).unwrap();
+    harness.type_str("<C-a>");
+    assert_eq!(harness.editor.mode(), Mode::Visual);
+    assert_ne!(harness.editor.status_message(), Some("Not a git rebase todo list"));
+    harness.type_str("<Esc>");
+
+    harness.editor.open_file(&dir.join("git-rebase-todo")).unwrap();
+    harness.type_str("<C-a>");
+    assert_eq!(harness.editor.mode(), Mode::Normal);
+    assert_eq!(harness.lines()[0], "squash 1234abc First");
+
+    std::fs::write(dir.join("config.toml"), "[git_rebase_todo]\n\"Ctrl+a\" = \"\"\nq = \"rebase_cycle\"\n").unwrap();
+    harness.editor.execute_command_line("config reload").unwrap();
+    harness.type_str("q");
+    assert_eq!(harness.lines()[0], "fixup 1234abc First");
+    harness.type_str("<C-a>");
+    assert_eq!(harness.editor.mode(), Mode::Visual);
+}
+
+#[test]
+fn char_info_reports_grapheme_clusters_and_ascii_flags_invisible_characters() {
+    let mut harness = Harness::new(120, 16);
+    harness.type_str("ie\u{301}x\u{200b}y\u{202e}z<CR><Esc>");
+    harness.type_str("ga");
+    assert_eq!(harness.editor.status_message(), Some("Empty line"));
+
+    harness.type_str("<Up><Home>ga");
+    assert_eq!(
+        harness.editor.status_message(),
+        Some("\"e\u{301}\" width 1: U+0065 (101) 65 LATIN SMALL LETTER E, U+0301 (769) CC 81 COMBINING ACUTE ACCENT")
+    );
+    harness.type_str("<Right>ga");
+    assert!(harness.editor.status_message().unwrap().starts_with("\"e\u{301}\" width 1"));
+    harness.type_str("<Right>ga");
+    assert_eq!(harness.editor.status_message(), Some("\"x\" width 1: U+0078 (120) 78 LATIN SMALL LETTER X"));
+    harness.type_str("<Right>ga");
+    assert_eq!(
+        harness.editor.status_message(),
+        Some("\"\\u{200b}\" width 0: U+200B (8203) E2 80 8B ZERO WIDTH SPACE [zero-width]")
+    );
+    harness.type_str("<End>ga");
+    assert_eq!(harness.editor.status_message(), Some("End of line"));
+    harness.type_str("<Left>ga");
+    assert!(harness.editor.status_message().unwrap().starts_with("\"z\" width 1"));
+
+    harness.type_str(":ascii<CR>");
+    assert_eq!(harness.editor.mode(), Mode::Picker);
+    let screen = harness.screen();
+    assert!(screen.contains("Line 1: 6 characters, 2 flagged"), "{}", screen);
+    assert!(screen.contains("   6  \"\\u{202e}\" width 0: U+202E (8238) E2 80 AE RIGHT-TO-LEFT OVERRIDE [bidi control]"), "{}", screen);
+    harness.type_str("bidi control<CR>");
+    assert_eq!(harness.editor.mode(), Mode::Normal);
+    assert_eq!(harness.editor.active_tab().cursor(), (8, 0));
+}
+
+#[test]
+fn mixed_indentation_is_reported_highlighted_and_fixed_by_retab() {
+    let dir = TempDir::new("retab");
+    let path = dir.join("mixed.py");
+    std::fs::write(&path, "def f():\n    x = 1\n\ty = 2\n    s = \"\"\"\n\tkept\n\"\"\"\n    return s\n").unwrap();
+    let mut harness = Harness::new(60, 14);
+    harness.editor.open_file(&path).unwrap();
+    assert!(harness.editor.status_message().unwrap().ends_with("mixed.py: mixed indentation (tabs+spaces)"));
+    harness.type_str(":set expandtab?<CR>");
+    assert_eq!(harness.editor.status_message(), Some("expandtab"));
+    harness.draw();
+    let buffer = harness.terminal.backend().buffer().clone();
+    assert_eq!(buffer.get(1, 6).bg, Color::Rgb(0x40, 0x40, 0x40));
+    assert_eq!(buffer.get(1, 5).bg, Color::Rgb(0x1e, 0x1e, 0x1e));
+
+    harness.type_str(":retab<CR>");
+    assert_eq!(harness.editor.status_message(), Some("Retabbed 1 line"));
+    assert_eq!(harness.lines()[2], "    y = 2");
+    assert_eq!(harness.lines()[4], "\tkept");
+    harness.type_str(":retab! 2<CR>");
+    assert_eq!(harness.lines()[1], "\t\tx = 1");
+    harness.type_str(":set tabstop?<CR>");
+    assert_eq!(harness.editor.status_message(), Some("tabstop=2"));
+    harness.type_str(":2,3retab 4<CR>");
+    assert_eq!(harness.lines()[1..3], ["        x = 1", "        y = 2"]);
+    assert_eq!(harness.lines()[6], "\t\treturn s");
+    harness.type_str("u");
+    assert_eq!(harness.lines()[1..3], ["\t\tx = 1", "\t\ty = 2"]);
+    harness.type_str("u");
+    assert_eq!(harness.lines()[1..3], ["    x = 1", "    y = 2"]);
+    harness.type_str("u");
+    assert_eq!(harness.lines()[2], "\ty = 2");
+}
+
+#[test]
+fn indent_guides_mark_each_level_and_brighten_the_cursor_block() {
+    let dir = TempDir::new("guides");
+    let path = dir.join("guides.txt");
+    std::fs::write(&path, "fn a() {\n    if x {\n        y();\n\n\t\tz();\n    }\n    w();\n}\n").unwrap();
+    let mut harness = Harness::new(60, 14);
+    harness.editor.open_file(&path).unwrap();
+    let guide_rows = |harness: &Harness| -> Vec<String> {
+        harness.rows()[4..12].iter().map(|row| row.strip_prefix('│').unwrap().trim_end_matches('│').trim_end().to_string()).collect()
+    };
+    harness.type_str(":set indent_guides<CR>");
+    harness.draw();
+    assert_eq!(guide_rows(&harness), ["fn a() {", "│   if x {", "│   │   y();", "│   │", "│   │   z();", "│   }", "│   w();", "}"]);
+    let buffer = harness.terminal.backend().buffer().clone();
+    assert_eq!(buffer.get(5, 6).fg, Color::Rgb(0x40, 0x40, 0x40));
+
+    harness.type_str("<Down><Down>");
+    harness.draw();
+    let buffer = harness.terminal.backend().buffer().clone();
+    for y in 6..9 {
+        assert_eq!(buffer.get(5, y).fg, Color::Rgb(0x7f, 0x84, 0x8e));
+        assert_eq!(buffer.get(1, y).fg, Color::Rgb(0x40, 0x40, 0x40));
+    }
+    assert_eq!(buffer.get(1, 9).fg, Color::Rgb(0x40, 0x40, 0x40));
+    assert_eq!(buffer.get(5, 9).symbol, "}");
+
+    harness.type_str(":set tabstop=2<CR>");
+    assert_eq!(guide_rows(&harness)[2], "│ │ │ │ y();");
+    assert_eq!(guide_rows(&harness)[4], "│ │ z();");
+    harness.type_str(":set noindentguides<CR>");
+    assert_eq!(guide_rows(&harness)[2], "        y();");
+}
+
+fn wait_for_rename(harness: &mut Harness) {
+    for _ in 0..200 {
+        harness.editor.poll_rename();
+        harness.draw();
+        if !harness.screen().contains("(searching…)") {
+            return;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+    panic!("rename search did not finish");
+}
+
+#[test]
+fn rename_word_reviews_project_matches_before_replacing_them() {
+    let dir = TempDir::new("rename");
+    std::fs::create_dir_all(dir.join(".git")).unwrap();
+    std::fs::write(dir.join(".gitignore"), "ignored.rs\n").unwrap();
+    std::fs::write(dir.join("a.rs"), "foo = foo_bar + foo;\n").unwrap();
+    std::fs::write(dir.join("b.rs"), "foo();\n").unwrap();
+    std::fs::write(dir.join("c.rs"), "x(foo, bar)\n").unwrap();
+    std::fs::write(dir.join("ignored.rs"), "foo\n").unwrap();
+    std::fs::write(dir.join("blob.bin"), b"foo\0\n").unwrap();
+    std::fs::write(dir.join("long.js"), format!("{} foo\n", "x".repeat(20_000))).unwrap();
+    let mut harness = Harness::new(120, 20);
+    harness.editor.open_file(&dir.join("a.rs")).unwrap();
+
+    harness.type_str("grn");
+    wait_for_rename(&mut harness);
+    let screen = harness.screen();
+    assert!(screen.contains("Rename foo: 4/4 matches in 3 files, skipped 1 binary file and 1 long line"), "{}", screen);
+    assert!(screen.contains("[x] a.rs (2)") && screen.contains("[x] 1:17  foo = foo_bar + foo;") && !screen.contains("ignored.rs"));
+    harness.type_str("<Down><Down><Space><Down><Down><Down><Space>");
+    let screen = harness.screen();
+    assert!(screen.contains("[-] a.rs (2)") && screen.contains("[ ] c.rs (1)"), "{}", screen);
+    harness.type_str("<CR>");
+    assert!(harness.screen().contains("Rename foo to"));
+    harness.type_str("<BS><BS><BS>bar<CR>");
+    assert_eq!(harness.editor.status_message(), Some("Renamed 2 matches in 2 files, skipped 1 binary file and 1 long line"));
+    assert_eq!(harness.lines(), ["bar = foo_bar + foo;"]);
+    assert!(harness.editor.active_tab().is_modified());
+    assert_eq!(harness.editor.tabs().len(), 2);
+    assert_eq!(harness.editor.tabs()[1].lines()[0], "bar();");
+    assert!(harness.editor.tabs()[1].is_modified());
+    assert_eq!(std::fs::read_to_string(dir.join("b.rs")).unwrap(), "foo();\n");
+    assert_eq!(std::fs::read_to_string(dir.join("c.rs")).unwrap(), "x(foo, bar)\n");
+
+    harness.type_str("grn");
+    wait_for_rename(&mut harness);
+    assert!(harness.screen().contains("Rename bar: 3/3 matches in 3 files"));
+    harness.type_str("w<CR><BS><BS><BS>baz<CR>");
+    assert!(harness.editor.status_message().unwrap().starts_with("Renamed 3 matches in 3 files, wrote 1 to disk"));
+    assert_eq!(std::fs::read_to_string(dir.join("c.rs")).unwrap(), "x(foo, baz)\n");
+    assert_eq!(harness.editor.tabs().len(), 2);
+
+    harness.type_str("grn");
+    wait_for_rename(&mut harness);
+    harness.type_str("<Esc>");
+    assert_eq!(harness.editor.status_message(), Some("Rename cancelled"));
+    assert_eq!(harness.lines()[0], "baz = foo_bar + foo;");
+    harness.type_str("u");
+    assert_eq!(harness.lines()[0], "bar = foo_bar + foo;");
+    harness.type_str("u");
+    assert_eq!(harness.lines()[0], "foo = foo_bar + foo;");
+}
+
+#[test]
+fn rename_keeps_cursors_in_other_tabs_on_a_character_boundary() {
+    let dir = TempDir::new("rename-boundary");
+    std::fs::create_dir_all(dir.join(".git")).unwrap();
+    std::fs::write(dir.join("a.rs"), "foo\n").unwrap();
+    std::fs::write(dir.join("b.rs"), "foo é\n").unwrap();
+    let mut harness = Harness::new(120, 20);
+    harness.editor.open_file(&dir.join("b.rs")).unwrap();
+    harness.type_str("<End><Left>");
+    assert_eq!(harness.editor.active_tab().cursor(), (4, 0));
+    harness.editor.open_file(&dir.join("a.rs")).unwrap();
+
+    harness.type_str("grn");
+    wait_for_rename(&mut harness);
+    harness.type_str("<CR><BS><BS><BS>ü<CR>");
+    assert_eq!(harness.editor.tabs()[0].lines(), ["ü é"]);
+    harness.editor.execute_action("previous_tab").unwrap();
+    assert_eq!(harness.editor.active_tab().cursor(), (3, 0));
+    harness.type_str("ix<Esc>");
+    assert_eq!(harness.lines(), ["ü xé"]);
+}
+
+#[test]
+fn substitute_with_confirmation_steps_through_matches() {
+    let mut harness = Harness::new(60, 12);
+    harness.type_str("ifoo foo<CR>bar<CR>foo<CR>foo foo<Esc>");
+    let original = ["foo foo", "bar", "foo", "foo foo"];
+
+    harness.type_str(":%s/foo/x/gc<CR>");
+    assert!(harness.screen().contains("replace with x? (y/n/a/q/l)"), "{}", harness.screen());
+    assert_eq!(harness.editor.active_tab().cursor(), (0, 0));
+    harness.type_str("yn");
+    assert_eq!(harness.editor.active_tab().cursor(), (0, 2));
+    harness.type_str("j");
+    assert_eq!(harness.editor.active_tab().cursor(), (0, 2));
+    harness.type_str("q");
+    assert_eq!(harness.lines(), ["x foo", "bar", "foo", "foo foo"]);
+    assert_eq!(harness.editor.status_message(), Some("1 substitution on 1 line"));
+    harness.type_str("u");
+    assert_eq!(harness.lines(), original);
+
+    harness.type_str(":%s/foo/x/gc<CR>ya");
+    assert_eq!(harness.lines(), ["x x", "bar", "x", "x x"]);
+    assert_eq!(harness.editor.status_message(), Some("5 substitutions on 3 lines"));
+    harness.type_str("u");
+    assert_eq!(harness.lines(), original);
+
+    harness.type_str(":%s/foo/z/c<CR>nl");
+    assert_eq!(harness.lines(), ["foo foo", "bar", "z", "foo foo"]);
+    harness.type_str(":%s/foo/y/<CR>");
+    assert_eq!(harness.editor.status_message(), Some("2 substitutions on 2 lines"));
+}
+
+#[test]
+fn byte_order_marks_and_missing_final_newlines_survive_saves() {
+    let dir = TempDir::new("bom");
+    let path = dir.join("windows.txt");
+    std::fs::write(&path, "\u{feff}first\nlast").unwrap();
+
+    let mut harness = Harness::new(60, 12);
+    harness.editor.open_file(&path).unwrap();
+    assert_eq!(harness.lines(), ["first", "last"]);
+    harness.draw();
+    assert!(harness.rows()[11].ends_with("[BOM] [noeol] "));
+    assert_eq!(text_rows(&harness)[0], "first");
+
+    harness.type_str("i> <Esc>:w<CR>");
+    wait_for_saves(&mut harness);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "\u{feff}> first\nlast");
+
+    harness.type_str(":set nobomb eol<CR>");
+    assert!(harness.editor.active_tab().is_modified());
+    harness.type_str(":w<CR>");
+    wait_for_saves(&mut harness);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "> first\nlast\n");
+    harness.type_str("<Esc>");
+    assert!(!harness.rows()[11].contains('['));
+}
+
+#[test]
+fn dragging_past_the_edges_scrolls_until_the_button_is_released() {
+    let mut harness = Harness::new(60, 12);
+    let long = "x".repeat(100);
+    let text: String = (0..40).map(|line| format!("line {:02} {}\n", line, if line == 1 { long.as_str() } else { "" })).collect();
+    harness.type_str(&format!("i{}<Esc>", text.trim_end().replace('\n', "<CR>")));
+    harness.editor.goto_location(1, None);
+    assert_eq!(harness.editor.view().first_line, 0);
+
+    harness.click(3, 4);
+    harness.drag(10, 11);
+    assert_eq!(harness.editor.view().first_line, 2);
+    assert!(harness.editor.poll_mouse_drag());
+    assert_eq!(harness.editor.view().first_line, 4);
+    harness.draw();
+    assert!(text_rows(&harness)[5].starts_with("line 09"));
+    let buffer = harness.terminal.backend().buffer();
+    assert_eq!(buffer.get(3, 9).bg, buffer.get(3, 5).bg);
+    assert_ne!(buffer.get(3, 9).bg, buffer.get(20, 9).bg);
+
+    harness.drag(10, 1);
+    assert_eq!(harness.editor.view().first_line, 1);
+    harness.drag(59, 4);
+    assert!(harness.editor.poll_mouse_drag());
+    harness.draw();
+    assert!(text_rows(&harness)[0].starts_with("ne 01"));
+
+    harness.release(59, 4);
+    assert!(!harness.editor.poll_mouse_drag());
+    assert!(text_rows(&harness)[0].starts_with("ne 01"));
+}
+
+#[test]
+fn released_mouse_selections_stay_until_the_next_key_or_click() {
+    let mut harness = Harness::new(60, 12);
+    harness.type_str("ifirst line<CR>second line<CR>third line<CR>fourth line<Esc>");
+    harness.editor.goto_location(1, None);
+
+    harness.click(4, 4);
+    harness.drag(9, 5);
+    harness.release(9, 5);
+    harness.draw();
+    let selected = |harness: &Harness, x: u16, y: u16| {
+        let buffer = harness.terminal.backend().buffer();
+        buffer.get(x, y).bg != buffer.get(30, y).bg
+    };
+    assert!(selected(&harness, 6, 4));
+    assert!(selected(&harness, 4, 5));
+    assert!(!selected(&harness, 10, 5));
+
+    harness.type_str("<Down>");
+    harness.draw();
+    assert!(!selected(&harness, 6, 4));
+
+    harness.click(6, 6);
+    harness.release(6, 6);
+    harness.draw();
+    assert!(!selected(&harness, 6, 6));
+
+    harness.shift_click(10, 7);
+    harness.draw();
+    assert!(selected(&harness, 4, 5));
+    assert!(selected(&harness, 6, 7));
+    assert!(!selected(&harness, 6, 4));
+
+    harness.release(10, 7);
+    harness.type_str("<Down>dddd");
+    harness.draw();
+    assert!(!selected(&harness, 4, 5));
+    assert!(text_rows(&harness)[1].starts_with("second line"));
+}
+
+#[test]
+fn mouse_off_ignores_every_mouse_event() {
+    let mut harness = Harness::new(60, 12);
+    let lines: Vec<String> = (1..=30).map(|n| format!("line {}", n)).collect();
+    harness.type_str(&format!("i{}<Esc>", lines.join("<CR>")));
+    harness.editor.goto_location(1, None);
+    harness.draw();
+    let selected = |harness: &Harness, x: u16, y: u16| {
+        let buffer = harness.terminal.backend().buffer();
+        buffer.get(x, y).bg != buffer.get(30, y).bg
+    };
+
+    harness.scroll(true, 10, 6);
+    assert_eq!(text_rows(&harness)[0], "line 4");
+    harness.scroll(false, 10, 6);
+    assert_eq!(text_rows(&harness)[0], "line 1");
+
+    harness.type_str(":set mouse=off<CR>:set mouse?<CR>");
+    assert_eq!(harness.editor.status_message(), Some("mouse=off"));
+    assert!(!harness.editor.mouse_enabled());
+    harness.click(4, 4);
+    harness.drag(9, 5);
+    harness.release(9, 5);
+    harness.scroll(true, 10, 6);
+    assert!(!selected(&harness, 6, 4));
+    assert_eq!(text_rows(&harness)[0], "line 1");
+
+    harness.type_str("<Down>dd");
+    assert_eq!(text_rows(&harness)[1], "line 3");
+
+    harness.type_str(":set mouse=on<CR>");
+    harness.click(4, 4);
+    harness.drag(9, 5);
+    assert!(selected(&harness, 6, 4));
+}
+
+#[test]
+fn expand_selection_grows_through_text_objects_and_shrinks_back() {
+    let mut harness = Harness::new(80, 16);
+    harness.type_str("ifn f() {<CR>    let v = call(first, \"a b\", [x, y]);<CR>}<CR><CR>other<Esc>");
+    let mut expanded = |presses: &str| {
+        harness.editor.goto_location(2, Some(26));
+        harness.type_str(presses);
+        harness.editor.execute_action("yank_to_new_tab").unwrap();
+        let lines: Vec<String> = harness.lines().iter().map(|line| line.to_string()).collect();
+        harness.editor.execute_action("close_tab").unwrap();
+        lines
+    };
+    assert_eq!(expanded("<A-Up>"), ["a"]);
+    assert_eq!(expanded("<A-Up><A-Up>"), ["a b"]);
+    assert_eq!(expanded("<A-Up><A-Up><A-Up>"), ["\"a b\""]);
+    assert_eq!(expanded(&"<A-Up>".repeat(4)), ["first, \"a b\", [x, y]"]);
+    assert_eq!(expanded(&"<A-Up>".repeat(5)), ["(first, \"a b\", [x, y])"]);
+    assert_eq!(expanded(&"<A-Up>".repeat(6)), ["    let v = call(first, \"a b\", [x, y]);"]);
+    assert_eq!(expanded(&"<A-Up>".repeat(7)), ["{", "    let v = call(first, \"a b\", [x, y]);", "}"]);
+    assert_eq!(expanded(&"<A-Up>".repeat(8)), ["fn f() {", "    let v = call(first, \"a b\", [x, y]);", "}"]);
+    assert_eq!(expanded(&"<A-Up>".repeat(9)).len(), 5);
+    assert_eq!(expanded("<A-Up><A-Up><A-Up><A-Down>"), ["a b"]);
+    assert_eq!(expanded(&("<A-Up>".repeat(6) + &"<A-Down>".repeat(4))), ["a b"]);
+
+    harness.editor.goto_location(2, Some(26));
+    harness.type_str("<A-Up><A-Down>");
+    assert_eq!(harness.editor.mode(), Mode::Normal);
+    assert_eq!(harness.editor.view().cursor, (25, 1));
+
+    harness.type_str("<A-Up><A-Up><Right><Right><A-Down>");
+    assert_eq!(harness.editor.mode(), Mode::Visual);
+}
+
+#[test]
+fn file_paths_copy_to_the_clipboard_and_reveal_in_the_sidebar() {
+    let mut harness = Harness::new(80, 16);
+    harness.type_str(":copy_path<CR>");
+    assert_eq!(harness.editor.status_message(), Some("No file name"));
+
+    harness.editor.open_file(std::path::Path::new("src/lib.rs")).unwrap();
+    harness.editor.goto_location(2, Some(3));
+    harness.type_str(":copy_location<CR>");
+    assert_eq!(harness.editor.status_message(), Some("Copied src/lib.rs:2:3"));
+    harness.type_str(":copy_relative_path<CR>");
+    assert_eq!(harness.editor.status_message(), Some("Copied src/lib.rs"));
+    harness.type_str(":copy_path<CR>");
+    let absolute = std::fs::canonicalize("src/lib.rs").unwrap();
+    assert_eq!(harness.editor.status_message().unwrap(), format!("Copied {}", absolute.display()));
+    harness.type_str("<C-t><C-p>");
+    assert_eq!(harness.lines(), [absolute.to_str().unwrap()]);
+    harness.type_str(":q!<CR>");
+
+    harness.editor.execute_action("reveal_in_sidebar").unwrap();
+    harness.draw();
+    assert_eq!(harness.editor.mode(), Mode::SidebarActive);
+    harness.type_str("<CR>");
+    assert_eq!(harness.editor.mode(), Mode::Normal);
+    assert_eq!(harness.editor.active_tab().file(), Some(absolute.to_str().unwrap()));
+}
+
+#[test]
+fn huge_directories_are_listed_a_page_at_a_time() {
+    let dir = TempDir::new("huge-dir");
+    for index in 0..1200 {
+        std::fs::write(dir.join(format!("file{:04}.txt", index)), "").unwrap();
+    }
+
+    let mut harness = Harness::new(80, 20);
+    harness.editor.open_file(&dir.join("file0000.txt")).unwrap();
+    harness.editor.execute_action("reveal_in_sidebar").unwrap();
+    harness.editor.open_directory(&dir).unwrap();
+    harness.editor.poll_file_selector();
+    assert_eq!(harness.editor.running_tasks(), 1);
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+    while harness.editor.poll_file_selector() || harness.editor.file_selector_loading() {
+        assert!(std::time::Instant::now() < deadline);
+    }
+    harness.editor.poll_tasks();
+    assert_eq!(harness.editor.running_tasks(), 0);
+    harness.draw();
+    assert!(harness.screen().contains("File Selector (first 500)"), "{}", harness.screen());
+    assert!(harness.screen().contains("file0000.txt"));
+    assert!(!harness.screen().contains("file0020.txt"));
+
+    harness.type_str(&"<Down>".repeat(480));
+    harness.draw();
+    assert!(harness.screen().contains("File Selector (first 500)"));
+    harness.type_str("<Down>");
+    harness.draw();
+    assert!(harness.screen().contains("File Selector (first 1,000)"));
+    harness.type_str("L");
+    harness.draw();
+    assert!(!harness.screen().contains("(first"));
+    assert!(harness.screen().contains("File Selector"));
+
+    harness.editor.open_directory(&dir).unwrap();
+    harness.editor.poll_file_selector();
+    harness.editor.execute_command_line("tasks").unwrap();
+    harness.type_str("<CR>");
+    assert!(harness.editor.status_message().unwrap_or_default().starts_with("Cancelling Listing "));
+    harness.editor.poll_file_selector();
+    assert!(!harness.editor.file_selector_loading());
+    harness.editor.poll_tasks();
+    assert!(harness.editor.status_message().unwrap_or_default().starts_with("Cancelled Listing "));
+
+    harness.editor.open_directory(&dir.join("missing")).unwrap();
+    harness.draw();
+    assert!(harness.screen().contains("⚠ No such file or directory"));
+    assert!(harness.screen().contains(".. (Parent Directory)"));
+}
+
+#[test]
+fn the_sidebar_lists_numbered_files_in_natural_order() {
+    let dir = TempDir::new("natural");
+    for name in ["file10.rs", "File2.rs", "file1.rs", "file.rs"] {
+        std::fs::write(dir.join(name), "").unwrap();
+    }
+
+    let mut harness = Harness::new(80, 20);
+    harness.editor.open_file(&dir.join("file1.rs")).unwrap();
+    harness.editor.execute_action("reveal_in_sidebar").unwrap();
+    while harness.editor.poll_file_selector() || harness.editor.file_selector_loading() {}
+    harness.draw();
+    let listed: Vec<String> = harness.rows()[2..6].iter().map(|row| row.split('│').nth(1).unwrap().trim().to_string()).collect();
+    assert_eq!(listed, ["🦀 file.rs", "🦀 file1.rs", "🦀 File2.rs", "🦀 file10.rs"], "{}", harness.screen());
+}
+
+#[test]
+fn the_sidebar_picks_up_files_changed_by_other_programs() {
+    let dir = TempDir::new("watch");
+    for name in ["a.txt", "b.txt", "c.txt"] {
+        std::fs::write(dir.join(name), name).unwrap();
+    }
+
+    let mut harness = Harness::new(80, 20);
+    harness.editor.open_file(&dir.join("a.txt")).unwrap();
+    harness.editor.execute_action("reveal_in_sidebar").unwrap();
+    harness.type_str("<Down>");
+    let now = std::time::Instant::now();
+    harness.editor.watch_sidebar(now);
+    std::fs::write(dir.join("new.txt"), "").unwrap();
+    assert!(!harness.editor.watch_sidebar(now + std::time::Duration::from_millis(500)));
+    assert!(harness.editor.watch_sidebar(now + std::time::Duration::from_secs(2)));
+    harness.draw();
+    let listed: Vec<String> = harness.rows()[2..6].iter().map(|row| row.split('│').nth(1).unwrap().trim().to_string()).collect();
+    assert_eq!(listed, ["📄 a.txt", "📄 b.txt", "📄 c.txt", "📄 new.txt"], "{}", harness.screen());
+    let fg = |harness: &Harness, row: u16| harness.terminal.backend().buffer().get(4, row).fg;
+    assert_ne!(fg(&harness, 5), fg(&harness, 4));
+
+    std::fs::remove_file(dir.join("b.txt")).unwrap();
+    assert!(harness.editor.watch_sidebar(now + std::time::Duration::from_secs(4)));
+    harness.draw();
+    assert!(!harness.screen().contains("b.txt"));
+    assert_eq!(fg(&harness, 4), fg(&harness, 2));
+    harness.type_str("<CR>");
+    assert_eq!(harness.lines(), ["c.txt"]);
+}
+
+#[test]
+fn command_line_completes_and_checks_option_values_colorschemes_and_syntaxes() {
+    let mut harness = Harness::new(60, 10);
+    let command_line = |harness: &Harness| harness.rows()[9].trim_end().to_string();
+    harness.type_str(":set hlsea<Tab>");
+    assert_eq!(command_line(&harness), ":set hlsearch");
+    harness.type_str("<Esc>:set nu foldmethod=<Tab>");
+    assert_eq!(command_line(&harness), ":set nu foldmethod=brace");
+    harness.type_str("<Tab>");
+    assert_eq!(command_line(&harness), ":set nu foldmethod=indent");
+    harness.type_str("<Esc>:set syntax=plain<Tab>");
+    assert_eq!(command_line(&harness), ":set syntax=Plain\\ Text");
+    harness.type_str("<Esc>:colorscheme so<Tab>");
+    assert_eq!(command_line(&harness), ":colorscheme solarized-");
+    harness.type_str("<Tab>");
+    assert_eq!(command_line(&harness), ":colorscheme solarized-dark");
+    harness.type_str("<Esc>:syntax rus<Tab>");
+    assert_eq!(command_line(&harness), ":syntax Rust");
+    harness.type_str("<CR>:syntax<CR>");
+    assert_eq!(harness.editor.status_message(), Some("Syntax: Rust"));
+
+    for (command, message) in [
+        (":set numbr<CR>", "Unknown option: numbr (did you mean number?)"),
+        (":set nonumbr<CR>", "Unknown option: nonumbr (did you mean nonumber?)"),
+        (":set foldmethod=indnt<CR>", "Invalid value for foldmethod: indnt (did you mean indent?)"),
+        (":set foldmethod=x<CR>", "Invalid value for foldmethod: x (expected indent, brace)"),
+        (":syntax pyhton<CR>", "Unknown syntax: pyhton (did you mean Python?)"),
+        (":colorscheme ligth<CR>", "Unknown color preset: ligth (did you mean light?)"),
+    ] {
+        harness.type_str(command);
+        assert_eq!(harness.editor.status_message(), Some(message));
+    }
+}
+
+#[test]
+fn private_tabs_keep_yanks_in_memory_and_are_not_remembered() {
+    let mut harness = Harness::new(60, 10);
+    harness.type_str("ipublic<Esc>yy:private<CR>isecret<Esc>yyp");
+    let rows = harness.rows();
+    assert!(rows[1].contains("2 Untitled-2 + [private]"), "{}", rows[1]);
+    assert!(rows[3].contains("Phantom - NORMAL [private]"), "{}", rows[3]);
+    assert_eq!(harness.editor.tabs()[1].lines().concat().matches("secret").count(), 2);
+
+    harness.type_str(":q!<CR>p");
+    assert_eq!(harness.editor.tabs().len(), 1);
+    assert_eq!(harness.editor.tabs()[0].lines().concat().matches("public").count(), 2);
+    harness.editor.execute_action("reopen_closed_tab").unwrap();
+    assert_eq!(harness.editor.status_message(), Some("No closed tabs to reopen"));
+}
+
+#[test]
+fn keys_typed_in_private_tabs_stay_out_of_the_log() {
+    let mut harness = Harness::new(100, 60);
+    harness.type_str(":set debuglevel=trace debugheight=40<CR>ivisible<Esc>:private<CR>isecret<Esc>");
+    harness.type_str(":messages<CR>");
+    let screen = harness.screen();
+    assert!(!screen.contains("Char('s')") && !screen.contains("Char('c')"), "{}", screen);
+    harness.type_str("q<C-b>");
+    let screen = harness.screen();
+    assert!(screen.contains("Key pressed in a private tab"), "{}", screen);
+    assert!(!screen.contains("Char('s')") && !screen.contains("Char('c')"), "{}", screen);
+}
+
+#[cfg(unix)]
+#[test]
+fn private_files_round_trip_through_the_encrypt_and_decrypt_commands() {
+    let dir = TempDir::new("private");
+    std::fs::write(
+        dir.join("settings.toml"),
+        "show_start_screen = false\nprivate_decrypt_command = \"tr a-z n-za-m < %\"\nprivate_encrypt_command = \"tr a-z n-za-m > %\"\n",
+    ).unwrap();
+    let path = dir.join("notes.txt.gpg");
+    let mut harness = Harness::new(80, 10);
+    harness.editor = phantom::Editor::new(Some(dir.to_path_buf()));
+    harness.type_str(&format!(":private {}<CR>", path.display()));
+    assert!(harness.editor.status_message().unwrap().starts_with("New private file"));
+    harness.type_str("ihello<Esc>:w<CR>");
+    assert!(harness.editor.private_filter_target().unwrap().starts_with("encrypting "));
+    assert!(!harness.editor.run_private_filter());
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "uryyb\n");
+    assert!(!harness.editor.active_tab().is_modified());
+
+    harness.type_str(":tabnew<CR>:tab 1<CR>:q<CR>");
+    assert_eq!(harness.editor.tabs().len(), 1);
+    harness.type_str(&format!(":private {}<CR>", path.display()));
+    assert!(harness.editor.private_filter_target().unwrap().starts_with("decrypting "));
+    assert!(!harness.editor.run_private_filter());
+    assert_eq!(harness.editor.active_tab().lines(), ["hello"]);
+    assert!(harness.editor.status_message().unwrap().starts_with("Decrypted "));
+    harness.draw();
+    assert!(harness.rows()[1].contains("notes.txt.gpg [private]"), "{}", harness.rows()[1]);
+
+    harness.type_str(":tabnew<CR>:tab 1<CR>:q<CR>ix<Esc>");
+    harness.type_str(&format!(":private {}<CR>", path.display()));
+    assert!(!harness.editor.run_private_filter());
+    harness.type_str("<End>i world<Esc>:w<CR>");
+    harness.editor.execute_action("previous_tab").unwrap();
+    harness.type_str(":q!<CR>");
+    assert_eq!(harness.editor.tabs().len(), 1);
+    assert!(!harness.editor.run_private_filter());
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "uryyb jbeyq\n");
+    assert!(harness.editor.status_message().unwrap().starts_with("Encrypted "));
+    assert!(!harness.editor.active_tab().is_modified());
+
+    harness.type_str("<End>i!<Esc>:w<CR>");
+    harness.editor.execute_action("close_tab").unwrap();
+    assert!(!harness.editor.run_private_filter());
+    assert!(harness.editor.status_message().unwrap().ends_with("notes.txt.gpg has been closed"), "{:?}", harness.editor.status_message());
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "uryyb jbeyq\n");
+}
+
+#[test]
+fn files_open_in_new_tabs_in_the_foreground_or_the_background() {
+    let dir = TempDir::new("tabnew");
+    for name in ["a.txt", "b.txt", "c.txt", "d.txt"] {
+        std::fs::write(dir.join(name), format!("{}1\n{}2\n{}3\n", name, name, name)).unwrap();
+    }
+
+    let mut harness = Harness::new(80, 20);
+    harness.editor.open_file(&dir.join("a.txt")).unwrap();
+    harness.type_str("<Down><Down><Right><Right>:badd b.txt<CR>");
+    assert_eq!(harness.editor.tabs().len(), 2);
+    assert_eq!(harness.lines()[0], "a.txt1");
+    assert_eq!(harness.editor.active_tab().cursor(), (2, 2));
+    assert!(harness.editor.status_message().unwrap().ends_with("b.txt in tab 2"));
+    let flashed = |harness: &Harness| {
+        let buffer = harness.terminal.backend().buffer();
+        (0..buffer.area.width).any(|x| buffer.get(x, 1).modifier.contains(Modifier::REVERSED))
+    };
+    assert!(flashed(&harness));
+    assert!(harness.editor.expire_tab_flashes(std::time::Instant::now() + std::time::Duration::from_secs(2)));
+    harness.draw();
+    assert!(!flashed(&harness));
+
+    harness.type_str(":badd b.txt<CR>");
+    assert_eq!(harness.editor.tabs().len(), 2);
+    assert!(harness.editor.status_message().unwrap().ends_with("b.txt is already open in tab 2"));
+    harness.type_str(":badd<CR>");
+    assert_eq!(harness.editor.status_message(), Some("Missing file name"));
+
+    harness.type_str(":tabe c.txt<CR>");
+    assert_eq!(harness.lines()[0], "c.txt1");
+    harness.type_str(":tabnew<CR>");
+    assert_eq!(harness.editor.tabs().len(), 4);
+    assert_eq!(harness.lines(), [""]);
+
+    harness.type_str(":tab 1<CR>");
+    harness.editor.execute_action("reveal_in_sidebar").unwrap();
+    harness.type_str("<Down><Down><Down>b");
+    assert_eq!(harness.editor.tabs().len(), 5);
+    assert_eq!(harness.editor.tabs()[4].lines()[0], "d.txt1");
+    assert_eq!(harness.editor.mode(), Mode::SidebarActive);
+    assert_eq!(harness.lines()[0], "a.txt1");
+}
+
+#[test]
+fn undo_history_is_trimmed_to_its_memory_limit_and_can_be_cleared() {
+    let dir = TempDir::new("undo-memory");
+    std::fs::write(dir.join("big.txt"), format!("{}\n", "x".repeat(40)).repeat(10_000)).unwrap();
+
+    let mut harness = Harness::new(100, 20);
+    harness.editor.open_file(&dir.join("big.txt")).unwrap();
+    harness.type_str(":set undomemorylimit=2<CR>");
+    for digit in 0..5 {
+        harness.type_str(&format!(":%s/x/{}/<CR>", digit));
+    }
+    assert!(harness.lines()[0].starts_with("01234x"));
+    harness.type_str(":messages<CR>");
+    let trimmed = harness.editor.output_lines().unwrap().iter().filter(|line| line.contains("trimmed to 3 changes")).count();
+    assert_eq!(trimmed, 1);
+    harness.type_str("q:undolist<CR>");
+    let entries = harness.editor.output_lines().unwrap();
+    assert_eq!(entries.len(), 3);
+    assert!(entries.iter().all(|entry| entry.contains(" KB  10000 lines")), "{:?}", entries);
+
+    harness.type_str("q:set undolevels=2<CR>uuu");
+    assert!(harness.lines()[0].starts_with("012x"));
+    harness.type_str("<C-r><C-r>");
+    assert!(harness.lines()[0].starts_with("01234x"));
+
+    harness.type_str(":clearundo<CR>");
+    assert!(harness.screen().contains("Clear the undo history of"));
+    harness.type_str("n");
+    harness.type_str("u");
+    assert!(harness.lines()[0].starts_with("0123x"));
+    harness.type_str(":clearundo<CR>y");
+    assert!(harness.editor.status_message().unwrap().starts_with("Cleared undo history"));
+    harness.type_str("u");
+    assert!(harness.lines()[0].starts_with("0123x"));
+}
+
+#[test]
+fn expression_register_inserts_the_result_at_the_cursor() {
+    let mut harness = Harness::new(80, 20);
+    harness.type_str("ioffset <C-r>=");
+    harness.draw();
+    assert!(harness.screen().contains("Expression (=)"));
+    harness.type_str("col * 2 +");
+    harness.draw();
+    assert!(harness.screen().contains("Unexpected end of expression"));
+    harness.type_str(" 1<CR> done<Esc>");
+    assert_eq!(harness.editor.active_tab().lines(), ["offset 17 done"]);
+
+    harness.type_str("o<C-r>=1 / 0<CR>");
+    assert_eq!(harness.editor.status_message(), Some("Expression: Division by zero"));
+    harness.type_str("x<Esc>");
+    assert_eq!(harness.editor.active_tab().lines(), ["offset 17 done", "x"]);
+    harness.type_str("$a<C-r>=line<Esc>!<Esc>");
+    assert_eq!(harness.editor.active_tab().lines(), ["offset 17 done", "x!"]);
+}
+
+#[test]
+fn digraphs_and_code_points_insert_unicode_characters() {
+    let mut harness = Harness::new(80, 20);
+    harness.type_str("i<C-k>a:<C-k>-><C-k>Eu<C-k>:o <C-v>u00e9<C-v>U01F600<C-v><Tab>x<Esc>");
+    assert_eq!(harness.editor.active_tab().lines(), ["ä→€ö é😀\tx"]);
+    assert_eq!(harness.editor.mode(), Mode::Normal);
+
+    harness.type_str("o<C-k>zq");
+    assert_eq!(harness.editor.status_message(), Some("Unknown digraph: Ctrl+K zq"));
+    harness.type_str("<C-v>u12g");
+    assert_eq!(harness.editor.status_message(), Some("Invalid sequence: Ctrl+V u12g"));
+    harness.type_str("<C-v>ud800");
+    assert_eq!(harness.editor.status_message(), Some("Invalid code point: Ctrl+V ud800"));
+    harness.type_str("<C-k>a<Esc>!<Esc>");
+    assert_eq!(harness.editor.active_tab().lines(), ["ä→€ö é😀\tx", "!"]);
+
+    harness.editor.execute_command_line("digraphs").unwrap();
+    harness.type_str("Eu");
+    harness.draw();
+    assert!(harness.screen().contains("Eu  €  U+20AC"));
+    harness.type_str("<Esc>");
+    assert_eq!(harness.editor.mode(), Mode::Normal);
+}
+
+#[test]
+fn pasting_or_changing_over_a_selection_replaces_it_in_one_undo_step() {
+    let mut harness = Harness::new(60, 12);
+    harness.type_str("ione two three<Esc>");
+    harness.editor.goto_location(1, Some(5));
+    harness.type_str("v<Right><Right>y");
+    harness.editor.goto_location(1, Some(13));
+    harness.type_str("v<Left><Left><Left><Left>p");
+    assert_eq!(harness.lines(), ["one two two"]);
+    assert_eq!(harness.editor.active_tab().cursor(), (8, 0));
+    assert_eq!(harness.editor.mode(), Mode::Normal);
+    harness.type_str("<End><C-p>");
+    assert_eq!(harness.lines(), ["one two twothree"]);
+    harness.type_str("uu");
+    assert_eq!(harness.lines(), ["one two three"]);
+
+    harness.type_str("oabc<CR>déf<Esc>");
+    harness.editor.goto_location(3, Some(2));
+    harness.type_str("v<Up><Left>cX<Esc>");
+    assert_eq!(harness.lines(), ["one two three", "Xf"]);
+    harness.type_str("u");
+    assert_eq!(harness.lines(), ["one two three", "abc", "déf"]);
+    harness.type_str("<End><C-p>");
+    assert_eq!(harness.lines(), ["one two three", "abcabc", "dé", "déf"]);
+    harness.type_str("u");
+
+    harness.draw();
+    harness.type_str("i");
+    harness.click(5, 4);
+    harness.drag(7, 4);
+    harness.release(7, 4);
+    harness.type_str("Z<Esc>");
+    assert_eq!(harness.lines(), ["one Z three", "abc", "déf"]);
+    harness.type_str("u");
+    assert_eq!(harness.lines(), ["one two three", "abc", "déf"]);
+
+    harness.editor.goto_location(1, Some(1));
+    harness.type_str("v<Right><Right>y:set nocopyonselect<CR><Esc>");
+    harness.draw();
+    harness.click(1, 5);
+    harness.drag(2, 6);
+    harness.release(2, 6);
+    harness.type_str("p");
+    assert_eq!(harness.lines(), ["one two three", "onef"]);
+    assert_eq!(harness.editor.active_tab().cursor(), (0, 1));
+    harness.type_str("u");
+    assert_eq!(harness.lines(), ["one two three", "abc", "déf"]);
+}
+
+#[test]
+fn closed_tabs_reopen_with_their_position_and_unsaved_changes() {
+    let dir = TempDir::new("closed");
+    std::fs::write(dir.join("a.txt"), "a1\na2\n").unwrap();
+    std::fs::write(dir.join("b.txt"), "b1\nb2\nb3\n").unwrap();
+
+    let mut harness = Harness::new(100, 16);
+    harness.type_str("<C-S-T>");
+    assert_eq!(harness.editor.status_message(), Some("No closed tabs to reopen"));
+    harness.editor.open_file(&dir.join("a.txt")).unwrap();
+    harness.editor.open_file(&dir.join("b.txt")).unwrap();
+    harness.type_str("<Down><Down><C-w>");
+    assert_eq!(harness.lines(), ["a1", "a2"]);
+    harness.type_str("<C-S-T>");
+    assert_eq!(harness.editor.tabs().len(), 2);
+    assert_eq!(harness.lines(), ["b1", "b2", "b3"]);
+    assert_eq!(harness.editor.active_tab().cursor(), (0, 2));
+    assert!(!harness.editor.active_tab().is_modified());
+
+    harness.type_str("ichanged <Esc>:q!<CR>");
+    harness.editor.execute_action("reopen_closed_tab").unwrap();
+    assert_eq!(harness.lines(), ["b1", "b2", "changed b3"]);
+    assert!(harness.editor.active_tab().is_modified());
+    assert_eq!(harness.editor.status_message(), Some("Reopened closed tab with its unsaved changes"));
+
+    harness.type_str(":q!<CR><C-t>iscratch<Esc>:q!<CR>:closedtabs<CR>");
+    assert_eq!(harness.editor.mode(), Mode::Picker);
+    let screen = harness.screen();
+    assert!(screen.contains("2  [untitled] [+]  line 1"), "{}", screen);
+    assert!(screen.contains("b.txt [+]  line 3"), "{}", screen);
+    harness.type_str("untitled<CR>");
+    assert_eq!(harness.lines(), ["scratch"]);
+    assert_eq!(harness.editor.tabs().len(), 2);
+
+    harness.editor.open_file(&dir.join("b.txt")).unwrap();
+    let tabs = harness.editor.tabs().len();
+    harness.type_str("<F1><C-S-T>");
+    assert_eq!(harness.editor.tabs().len(), tabs);
+    assert_eq!(harness.lines(), ["b1", "b2", "b3"]);
+    assert!(harness.editor.status_message().unwrap().ends_with("b.txt is already open"));
+}
+
+#[test]
+fn closing_tabs_returns_to_the_last_used_one_and_tabonly_asks_once() {
+    let dir = TempDir::new("tabonly");
+    for name in ["a", "b", "c", "d"] {
+        std::fs::write(dir.join(format!("{}.txt", name)), format!("{}1\n", name)).unwrap();
+    }
+
+    let mut harness = Harness::new(100, 16);
+    for name in ["a", "b", "c", "d"] {
+        harness.editor.open_file(&dir.join(format!("{}.txt", name))).unwrap();
+    }
+    harness.type_str("<F1><F3><C-w>");
+    assert_eq!(harness.lines(), ["a1"]);
+    assert_eq!(harness.editor.tabs().len(), 3);
+
+    harness.type_str(":tabclose 3<CR>");
+    assert_eq!(harness.lines(), ["a1"]);
+    assert_eq!(harness.editor.tabs().iter().map(|tab| tab.lines()[0].as_str()).collect::<Vec<_>>(), ["a1", "b1"]);
+    harness.type_str(":tabclose 5<CR>");
+    assert_eq!(harness.editor.status_message(), Some("Tab 5 does not exist"));
+
+    harness.type_str("<F2>ichanged <Esc><F1>:tabonly<CR>");
+    assert!(harness.screen().contains("Save changes to b.txt?"));
+    harness.type_str("n");
+    assert_eq!(harness.editor.tabs().len(), 1);
+    assert_eq!(std::fs::read_to_string(dir.join("b.txt")).unwrap(), "b1\n");
+
+    harness.type_str("<C-S-T>");
+    assert_eq!(harness.lines(), ["changed b1"]);
+    harness.type_str("<F1>:tabonly<CR>y");
+    assert_eq!(harness.editor.tabs().len(), 1);
+    assert_eq!(harness.editor.status_message(), Some("Closed 1 tab"));
+    assert_eq!(std::fs::read_to_string(dir.join("b.txt")).unwrap(), "changed b1\n");
+
+    harness.type_str("<C-w>");
+    assert_eq!(harness.editor.tabs().len(), 1);
+    assert_eq!(harness.editor.active_tab().file(), None);
+    assert_eq!(harness.lines(), [""]);
+    harness.type_str("<C-S-T>");
+    assert_eq!(harness.lines(), ["a1"]);
+}
+
+#[test]
+fn function_keys_follow_the_keybindings_and_tab_switches_by_number() {
+    let dir = TempDir::new("fkeys");
+    std::fs::write(dir.join("settings.toml"), "show_start_screen = false\n").unwrap();
+    drop(phantom::Editor::new(Some(dir.to_path_buf())));
+    let config = std::fs::read_to_string(dir.join("config.toml")).unwrap();
+    std::fs::write(dir.join("config.toml"), config.replace("F1 = \"switch_to_tab_1\"", "F1 = \"goto_line_end\"")).unwrap();
+
+    let mut harness = Harness::new(80, 12);
+    harness.editor = phantom::Editor::new(Some(dir.to_path_buf()));
+    harness.type_str("ione<Esc><C-t>itwo<Esc><C-t>ithree<Esc><Home><F1>");
+    assert_eq!(harness.lines(), ["three"]);
+    assert_eq!(harness.editor.tabs()[2].cursor(), (5, 0));
+
+    harness.type_str("i<F5><F2><Esc>");
+    assert_eq!(harness.lines(), ["three"]);
+    harness.type_str("<F2>");
+    assert_eq!(harness.lines(), ["two"]);
+    harness.type_str("<F5>");
+    assert_eq!(harness.lines(), ["two"]);
+    assert_eq!(harness.editor.status_message(), Some("Tab 5 does not exist"));
+
+    harness.type_str(":tab 1<CR>");
+    assert_eq!(harness.lines(), ["one"]);
+    harness.type_str(":tablast<CR>");
+    assert_eq!(harness.lines(), ["three"]);
+    harness.type_str(":tab 9<CR>");
+    assert_eq!(harness.lines(), ["three"]);
+    assert_eq!(harness.editor.status_message(), Some("Tab 9 does not exist"));
+    harness.type_str(":tab x<CR>");
+    assert_eq!(harness.editor.status_message(), Some("Invalid tab number: x"));
+}
+
+#[test]
+fn tab_titles_follow_the_configured_format_and_elide_long_names() {
+    let dir = TempDir::new("tab-titles");
+    let long = dir.join("an_extremely_long_module_name_used_for_tab_tests.rs");
+    std::fs::write(&long, "fn main() {}\n").unwrap();
+    std::fs::write(dir.join("notes.txt"), "notes\n").unwrap();
+
+    let mut harness = Harness::new(60, 12);
+    harness.editor.open_file(&long).unwrap();
+    harness.editor.open_file(&dir.join("notes.txt")).unwrap();
+    harness.type_str("ix<Esc>");
+    let tabs = &harness.rows()[1];
+    assert!(tabs.contains("#1 an_extremely_long…for_tab_tests.rs  │  2 notes.txt +"), "{}", tabs);
+
+    harness.resize(120, 12);
+    harness.type_str(r":set tabtitleformat={icon}\ {name} activetabtitleformat=[{index}]\ {dir}{name}{modified}<CR>");
+    let tabs = &harness.rows()[1];
+    assert!(tabs.contains("#🦀 an_extremely_long_module_name_used_for_tab_tests.rs"), "{}", tabs);
+    assert!(tabs.contains(&format!("/{}/notes.txt +", dir.file_name().unwrap().to_string_lossy())), "{}", tabs);
+    assert!(tabs.contains(" [2] /"));
+    harness.type_str(":set tabtitleformat?<CR>");
+    assert_eq!(harness.editor.status_message(), Some("tabtitleformat={icon} {name}"));
+}
+
+#[test]
+fn saving_into_a_missing_directory_asks_before_creating_it() {
+    let dir = TempDir::new("save-dirs");
+    let mut harness = Harness::new(100, 12);
+    harness.type_str("ihello<Esc>");
+
+    harness.type_str(&format!(":w {}/existing.txt<CR>", dir.display()));
+    assert!(harness.editor.mode() == Mode::Normal && harness.screen().contains("Saving"));
+    wait_for_saves(&mut harness);
+    assert_eq!(std::fs::read_to_string(dir.join("existing.txt")).unwrap(), "hello\n");
+
+    harness.type_str(&format!(":w {}/srd/main.rs<CR>", dir.display()));
+    assert!(harness.screen().contains("srd' does not exist. Create it?"), "{}", harness.screen());
+    harness.type_str("n");
+    assert!(!dir.join("srd").exists());
+    assert_eq!(harness.editor.status_message(), Some("Not saved"));
+
+    harness.type_str(&format!(":w {}/srd/main.rs<CR>y", dir.display()));
+    wait_for_saves(&mut harness);
+    assert_eq!(std::fs::read_to_string(dir.join("srd/main.rs")).unwrap(), "hello\n");
+
+    harness.type_str(&format!(":w {}/a/b/c/deep.txt<CR>", dir.display()));
+    assert!(harness.screen().contains("a/b/c' does not exist"), "{}", harness.screen());
+    harness.type_str("<Esc>");
+    assert!(!dir.join("a").exists());
+    harness.type_str(&format!(":w! {}/a/b/c/deep.txt<CR>", dir.display()));
+    wait_for_saves(&mut harness);
+    assert_eq!(std::fs::read_to_string(dir.join("a/b/c/deep.txt")).unwrap(), "hello\n");
+
+    harness.type_str(&format!(":w! {}/existing.txt/sub/file.txt<CR>", dir.display()));
+    let error = harness.editor.status_message().unwrap();
+    assert!(error.starts_with("Could not create directory '") && error.contains("existing.txt/sub': "), "{}", error);
+}
+
+#[test]
+fn substitutions_run_across_every_tab_with_one_undo_step_each() {
+    let dir = TempDir::new("replaceall");
+    std::fs::write(dir.join("b.txt"), "foo\nbar foo foo\n").unwrap();
+    std::fs::write(dir.join("c.txt"), "nothing here\n").unwrap();
+
+    let mut harness = Harness::new(80, 16);
+    harness.type_str("ifoo one foo<Esc>");
+    harness.editor.start_pager();
+    harness.editor.open_file(&dir.join("b.txt")).unwrap();
+    harness.editor.open_file(&dir.join("c.txt")).unwrap();
+
+    harness.editor.execute_command_line("replaceall? /foo/bar/g").unwrap();
+    assert_eq!(harness.editor.mode(), Mode::Picker);
+    harness.draw();
+    let screen = harness.screen();
+    assert!(screen.contains("Replace preview: 5 matches in 2 tabs"), "{}", screen);
+    assert!(screen.contains("Untitled-1:1 (read-only): foo one foo"));
+    assert!(screen.contains("b.txt:2: bar foo foo"));
+    harness.type_str("bar<CR>");
+    assert_eq!(harness.lines(), ["foo", "bar foo foo"]);
+    assert_eq!(harness.editor.active_tab().cursor().1, 1);
+
+    harness.editor.execute_command_line("bufdo %s/foo/baz/g").unwrap();
+    assert_eq!(harness.editor.status_message(), Some("3 substitutions in 1 tab (b.txt: 3); skipped 1 read-only tab"));
+    let tabs = harness.editor.tabs();
+    assert_eq!(tabs[0].lines(), ["foo one foo"]);
+    assert_eq!(tabs[1].lines(), ["baz", "bar baz baz"]);
+    assert!(tabs[1].is_modified() && !tabs[2].is_modified());
+
+    harness.editor.execute_command_line("replaceall /baz/qux/").unwrap();
+    assert_eq!(harness.editor.status_message(), Some("2 substitutions in 1 tab (b.txt: 2)"));
+    harness.editor.undo();
+    assert_eq!(harness.lines(), ["baz", "bar baz baz"]);
+    harness.editor.undo();
+    assert_eq!(harness.lines(), ["foo", "bar foo foo"]);
+
+    harness.editor.execute_command_line("replaceall /missing/x/").unwrap();
+    assert_eq!(harness.editor.status_message(), Some("Pattern not found: missing"));
+    harness.editor.execute_command_line("bufdo %s/foo/x/c").unwrap();
+    assert_eq!(harness.editor.status_message(), Some("The c flag is not supported across tabs"));
+}
+
+#[test]
+fn bsearch_lists_matches_in_every_tab_and_n_continues_in_the_chosen_one() {
+    let mut harness = Harness::new(80, 16);
+    harness.type_str("inothing here<Esc>");
+    harness.editor.execute_action("new_tab").unwrap();
+    harness.type_str("ifirst needle<CR>x needle needle<CR>needle<Esc>");
+    harness.editor.execute_action("new_tab").unwrap();
+    harness.type_str("ineedle a needle b needle<Esc>");
+    harness.type_str("<F1>");
+
+    harness.editor.execute_command_line("bsearch needle").unwrap();
+    assert_eq!(harness.editor.mode(), Mode::Picker);
+    harness.draw();
+    let screen = harness.screen();
+    assert!(screen.contains("Tab search: 7 matches in 2 tabs"), "{}", screen);
+    assert!(screen.contains("Untitled-2:2: x needle needle"), "{}", screen);
+    assert!(screen.contains("Untitled-3:1: needle a needle b needle"), "{}", screen);
+    assert!(!screen.contains("nothing here"));
+    harness.type_str("x needle<CR>");
+    assert_eq!(harness.editor.mode(), Mode::Normal);
+    assert_eq!(harness.lines()[0], "first needle");
+    assert_eq!(harness.editor.active_tab().cursor(), (2, 1));
+    harness.type_str("n");
+    assert_eq!(harness.editor.active_tab().cursor(), (9, 1));
+    harness.type_str("n");
+    assert_eq!(harness.editor.active_tab().cursor(), (0, 2));
+
+    harness.editor.execute_command_line("set tabsearchlimit=2").unwrap();
+    harness.type_str("<Esc>");
+    harness.editor.execute_command_line("bsearch").unwrap();
+    harness.draw();
+    let screen = harness.screen();
+    assert!(screen.contains("Tab search: 4 matches in 2 tabs (truncated)"), "{}", screen);
+    assert_eq!(harness.editor.status_message(), Some("Stopped after 2 matches in Untitled-2, Untitled-3"));
+    harness.type_str("<Esc>");
+
+    harness.editor.execute_command_line("bs missing").unwrap();
+    assert_eq!(harness.editor.status_message(), Some("Pattern not found: missing"));
+}
+
+#[test]
+fn search_results_are_found_relative_to_the_cursor() {
+    let mut harness = Harness::new(60, 12);
+    harness.type_str("ifoo x foo<CR>bar<CR>foo<Esc>/foo<CR>");
+    assert_eq!(harness.editor.active_tab().cursor(), (0, 0));
+    harness.type_str("n");
+    assert_eq!(harness.editor.active_tab().cursor(), (6, 0));
+    harness.type_str("n");
+    assert_eq!(harness.editor.active_tab().cursor(), (0, 2));
+    harness.type_str("n");
+    assert_eq!(harness.editor.active_tab().cursor(), (0, 0));
+    assert_eq!(harness.editor.status_message(), Some("Search hit BOTTOM, continuing at TOP"));
+    harness.type_str("N");
+    assert_eq!(harness.editor.active_tab().cursor(), (0, 2));
+    assert_eq!(harness.editor.status_message(), Some("Search hit TOP, continuing at BOTTOM"));
+
+    harness.click(2, 5);
+    harness.type_str("N");
+    assert_eq!(harness.editor.active_tab().cursor(), (6, 0));
+    harness.click(2, 5);
+    harness.type_str("n");
+    assert_eq!(harness.editor.active_tab().cursor(), (0, 2));
+
+    harness.type_str("<Up><Up>dd");
+    assert_eq!(harness.lines(), ["bar", "foo"]);
+    harness.type_str("n");
+    assert_eq!(harness.editor.active_tab().cursor(), (0, 1));
+    harness.type_str("dd");
+    harness.type_str("n");
+    assert_eq!(harness.editor.status_message(), Some("Pattern not found: foo"));
+    assert_eq!(harness.editor.active_tab().cursor(), (0, 0));
+}
+
+#[test]
+fn pasted_file_paths_offer_to_open_them_as_tabs() {
+    let dir = TempDir::new("dropped");
+    std::fs::write(dir.join("my notes.txt"), "notes\n").unwrap();
+    std::fs::write(dir.join("b.txt"), "b\n").unwrap();
+    let notes = dir.join("my notes.txt").to_string_lossy().into_owned();
+    let b = dir.join("b.txt").to_string_lossy().into_owned();
+    let paste = |harness: &mut Harness, text: &str| harness.send(crossterm::event::Event::Paste(text.to_string()));
+
+    let mut harness = Harness::new(80, 14);
+    harness.editor.show_start_screen();
+    paste(&mut harness, &format!("file://{}", notes.replace(' ', "%20")));
+    assert_eq!(harness.lines(), ["notes"]);
+    assert_eq!(harness.editor.tabs().len(), 1);
+
+    let mut harness = Harness::new(80, 14);
+    paste(&mut harness, &format!("'{}' {}", notes, b));
+    assert!(harness.screen().contains("Open 2 dropped files as tabs?"));
+    harness.type_str("y");
+    assert_eq!(harness.editor.tabs().len(), 2);
+    assert_eq!(harness.lines(), ["notes"]);
+
+    harness.type_str("<C-t>i");
+    paste(&mut harness, &format!("{}\n{}\n", b, dir.join("missing.txt").display()));
+    paste(&mut harness, "\nfn main() {}");
+    assert_eq!(harness.editor.mode(), Mode::Insert);
+    assert_eq!(harness.lines(), [b.as_str(), &dir.join("missing.txt").to_string_lossy(), "", "fn main() {}"]);
+
+    harness.type_str("<Esc>:q!<CR><C-t>");
+    paste(&mut harness, &b.replace(' ', "\\ "));
+    assert!(harness.screen().contains("Open dropped file"));
+    harness.type_str("n");
+    assert_eq!(harness.lines(), [b.as_str()]);
+
+    harness.type_str("u:set noopendroppedfiles<CR>");
+    paste(&mut harness, &b);
+    assert_eq!(harness.lines(), [b.as_str()]);
+    assert_eq!(harness.editor.tabs().len(), 3);
+}
+
+#[test]
+fn surround_adds_changes_and_deletes_delimiters_in_one_undo_step_each() {
+    let mut harness = Harness::new(80, 16);
+    harness.type_str("isay hello world<Esc><Home><Right><Right><Right><Right>v<Right><Right><Right><Right>S\"");
+    assert_eq!(harness.lines(), ["say \"hello\" world"]);
+    assert_eq!(harness.editor.mode(), Mode::Normal);
+    harness.type_str("<Right><Right>cs\"'");
+    assert_eq!(harness.lines(), ["say 'hello' world"]);
+    harness.type_str("<Right>ds'");
+    assert_eq!(harness.lines(), ["say hello world"]);
+    harness.type_str("u");
+    assert_eq!(harness.lines(), ["say 'hello' world"]);
+    harness.type_str("u");
+    assert_eq!(harness.lines(), ["say \"hello\" world"]);
+    harness.type_str("u");
+    assert_eq!(harness.lines(), ["say hello world"]);
+
+    harness.type_str("<Home><Right><Right><Right><Right>v<Right><Right><Right><Right>S(");
+    assert_eq!(harness.lines(), ["say ( hello ) world"]);
+    harness.type_str("<Right><Right><Right>cs(]");
+    assert_eq!(harness.lines(), ["say [hello] world"]);
+    harness.type_str("<Right>ds]");
+    assert_eq!(harness.lines(), ["say hello world"]);
+
+    harness.type_str("v<Right><Right><Right><Right>St");
+    harness.type_str("em class=\"x\"<CR>");
+    assert_eq!(harness.lines(), ["say <em class=\"x\">hello</em> world"]);
+    harness.type_str("u<Home>v<Right><Right>Sq");
+    assert_eq!(harness.lines(), ["say hello world"]);
+    assert_eq!(harness.editor.status_message(), Some("Unknown surround delimiter: q"));
+    harness.type_str("<Esc>ds(");
+    assert_eq!(harness.editor.status_message(), Some("No surrounding ( found"));
+
+    harness.type_str("osecond line<Esc><Up><Home>v<Down><Right><Right>S*");
+    assert_eq!(harness.lines(), ["*say hello world", "sec*ond line"]);
+    harness.type_str("u");
+    assert_eq!(harness.lines(), ["say hello world", "second line"]);
+}
+
+#[test]
+fn pending_key_sequences_time_out_and_unmatched_keys_are_replayed() {
+    let dir = TempDir::new("timeout");
+    std::fs::write(dir.join("settings.toml"), "key_timeout_ms = 500\nshow_start_screen = false\n").unwrap();
+    drop(phantom::Editor::new(Some(dir.to_path_buf())));
+    let config = std::fs::read_to_string(dir.join("config.toml")).unwrap();
+    std::fs::write(dir.join("config.toml"), config.replace("[normal_mode]\n", "[normal_mode]\nz = \"goto_line_end\"\n")).unwrap();
+
+    let mut harness = Harness::new(60, 12);
+    harness.editor = phantom::Editor::new(Some(dir.to_path_buf()));
+    harness.type_str("ione two<CR>three<Esc><Up><Home>");
+    let status = |harness: &mut Harness| {
+        harness.draw();
+        harness.rows()[11].trim_end().to_string()
+    };
+
+    harness.type_str("d");
+    assert_eq!(harness.editor.pending_keys(), Some("d"));
+    assert!(status(&mut harness).ends_with("d"));
+    let start = std::time::Instant::now();
+    assert!(!harness.editor.expire_pending_keys(start).unwrap());
+    assert!(harness.editor.expire_pending_keys(start + std::time::Duration::from_millis(600)).unwrap());
+    assert_eq!(harness.editor.pending_keys(), None);
+    assert!(!status(&mut harness).ends_with("d"));
+    assert_eq!(harness.lines(), ["one two", "three"]);
+
+    harness.type_str("z");
+    assert_eq!(harness.editor.pending_keys(), Some("z"));
+    assert_eq!(harness.editor.active_tab().cursor(), (0, 0));
+    harness.editor.expire_pending_keys(std::time::Instant::now() + std::time::Duration::from_secs(1)).unwrap();
+    assert_eq!(harness.editor.active_tab().cursor(), (7, 0));
+
+    harness.type_str("<Home>d<Down>");
+    assert_eq!(harness.editor.pending_keys(), None);
+    assert_eq!(harness.editor.active_tab().cursor(), (0, 1));
+    harness.type_str("z<Up>");
+    assert_eq!(harness.editor.active_tab().cursor(), (5, 0));
+    harness.type_str("dd");
+    assert_eq!(harness.lines(), ["three"]);
+}
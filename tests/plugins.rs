@@ -0,0 +1,85 @@
+use std::fs;
+
+use phantom::plugin_protocol_docs;
+
+#[test]
+fn protocol_docs_are_up_to_date() {
+    let docs = fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/docs/plugin-protocol.md")).unwrap();
+    assert_eq!(docs, plugin_protocol_docs(), "regenerate with `phantom --plugin-protocol > docs/plugin-protocol.md`");
+}
+
+#[cfg(unix)]
+#[test]
+fn plugin_actions_edit_the_buffer_and_never_hang() {
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use phantom::Editor;
+
+    let dir = std::env::temp_dir().join(format!("phantom-plugins-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    std::env::set_var("PHANTOM_DATA_DIR", &dir);
+    let reply = r#"case "$line" in *'"name":"shout"'*) echo '{"type":"replace_lines","start":0,"end":1,"lines":["HELLO","WORLD"]}'; echo '{"type":"status","text":"shouted"}'; echo '{"type":"done"}';; esac"#;
+    fs::write(dir.join("settings.toml"), format!(r#"
+[[plugins]]
+name = "shout"
+command = ["sh", "-c", "while read -r line; do {reply}; done"]
+actions = ["shout"]
+
+[[plugins]]
+name = "silent"
+command = ["sh", "-c", "cat > /dev/null"]
+actions = ["wait_forever"]
+timeout_ms = 100
+
+[[plugins]]
+name = "crash"
+command = ["sh", "-c", "exit 1"]
+"#, reply = reply.replace('\\', "\\\\").replace('"', "\\\"")))
+    .unwrap();
+
+    let wait = |editor: &mut Editor| {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while editor.plugins_busy() && Instant::now() < deadline {
+            editor.poll_plugins();
+            thread::sleep(Duration::from_millis(10));
+        }
+    };
+
+    let mut editor = Editor::new(Some(dir.clone()));
+    thread::sleep(Duration::from_millis(200));
+    editor.poll_plugins();
+    assert_eq!(editor.status_message(), Some("Plugin crash exited"));
+
+    editor.execute_action("shout").unwrap();
+    assert!(editor.plugins_busy());
+    assert_eq!(editor.active_tab().lines(), [""]);
+    wait(&mut editor);
+    assert_eq!(editor.active_tab().lines(), ["HELLO", "WORLD"]);
+    assert_eq!(editor.status_message(), Some("shout: shouted"));
+    editor.undo();
+    assert_eq!(editor.active_tab().lines(), [""]);
+
+    editor.execute_command_line("set readonly").unwrap();
+    editor.execute_action("shout").unwrap();
+    wait(&mut editor);
+    assert_eq!(editor.active_tab().lines(), [""]);
+    assert_eq!(editor.status_message(), Some("shout: shouted"));
+    editor.execute_command_line("set noreadonly").unwrap();
+
+    editor.execute_action("shout").unwrap();
+    editor.execute_action("new_tab").unwrap();
+    editor.execute_action("next_tab").unwrap();
+    editor.execute_action("close_tab").unwrap();
+    assert_eq!(editor.tabs().len(), 1);
+    wait(&mut editor);
+    assert_eq!(editor.active_tab().lines(), [""]);
+
+    let started = Instant::now();
+    editor.execute_action("wait_forever").unwrap();
+    assert!(started.elapsed() < Duration::from_millis(100));
+    wait(&mut editor);
+    assert!(started.elapsed() < Duration::from_secs(2));
+    assert_eq!(editor.status_message(), Some("Plugin silent did not answer wait_forever in time"));
+    editor.shutdown();
+}
@@ -0,0 +1,516 @@
+use crossterm::event::{Event, KeyCode, KeyEvent};
+use phantom::{Editor, Mode};
+use tui::backend::TestBackend;
+use tui::Terminal;
+
+fn screen(terminal: &Terminal<TestBackend>) -> String {
+    let buffer = terminal.backend().buffer();
+    buffer.content().iter().map(|cell| cell.symbol.as_str()).collect()
+}
+
+fn editor_with_panels() -> Editor {
+    let mut editor = Editor::headless();
+    editor.execute_action("enter_insert_mode").unwrap();
+    for _ in 0..50 {
+        for c in "a fairly long line of text to scroll sideways".chars() {
+            editor.dispatch_key_event(KeyEvent::from(KeyCode::Char(c))).unwrap();
+        }
+        editor.dispatch_key_event(KeyEvent::from(KeyCode::Enter)).unwrap();
+    }
+    editor.dispatch_key_event(KeyEvent::from(KeyCode::Esc)).unwrap();
+    editor.execute_action("toggle_minimap").unwrap();
+    editor.execute_action("toggle_debug_menu").unwrap();
+    editor.execute_action("toggle_sidebar").unwrap();
+    editor.dispatch_key_event(KeyEvent::from(KeyCode::Esc)).unwrap();
+    editor
+}
+
+#[test]
+fn renders_at_every_small_size_without_panicking() {
+    let mut editor = editor_with_panels();
+    for width in 0..=100 {
+        for height in [0, 1, 3, 6, 7, 8, 12, 13, 20, 40] {
+            let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+            terminal.draw(|f| editor.ui(f)).unwrap();
+        }
+    }
+}
+
+#[test]
+fn tiny_windows_show_a_placeholder() {
+    let mut editor = editor_with_panels();
+    let mut terminal = Terminal::new(TestBackend::new(40, 5)).unwrap();
+    terminal.draw(|f| editor.ui(f)).unwrap();
+    assert!(screen(&terminal).contains("window too small (need 20x7)"));
+}
+
+#[test]
+fn panels_collapse_and_return_with_the_window_size() {
+    let mut editor = editor_with_panels();
+    let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+    terminal.draw(|f| editor.ui(f)).unwrap();
+    assert!(screen(&terminal).contains("Minimap"));
+    assert!(screen(&terminal).contains("Debug Output"));
+
+    terminal.backend_mut().resize(45, 10);
+    terminal.resize(tui::layout::Rect::new(0, 0, 45, 10)).unwrap();
+    terminal.draw(|f| editor.ui(f)).unwrap();
+    assert!(!screen(&terminal).contains("Minimap"));
+    assert!(!screen(&terminal).contains("Debug Output"));
+
+    for (width, height) in [(30, 8), (120, 50), (21, 7), (80, 24), (3, 3), (100, 30)] {
+        editor.handle_event(Event::Resize(width, height)).unwrap();
+        terminal.backend_mut().resize(width, height);
+        terminal.resize(tui::layout::Rect::new(0, 0, width, height)).unwrap();
+        terminal.draw(|f| editor.ui(f)).unwrap();
+    }
+    assert!(screen(&terminal).contains("Minimap"));
+    assert!(screen(&terminal).contains("Debug Output"));
+    let view = editor.view();
+    assert!(view.cursor.1 >= view.first_line && view.cursor.1 < view.first_line + view.lines.len().max(1));
+}
+
+fn draw(editor: &mut Editor, width: u16, height: u16) {
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+    terminal.draw(|f| editor.ui(f)).unwrap();
+}
+
+#[test]
+fn screen_positions_follow_the_rendered_layout() {
+    for sidebar in [false, true] {
+        for minimap in [false, true] {
+            for debug in [false, true] {
+                let mut editor = editor_with_panels();
+                if sidebar {
+                    editor.execute_action("toggle_sidebar").unwrap();
+                }
+                if !minimap {
+                    editor.execute_action("toggle_minimap").unwrap();
+                }
+                if !debug {
+                    editor.execute_action("toggle_debug_menu").unwrap();
+                }
+                editor.goto_location(1, None);
+                draw(&mut editor, 100, 30);
+
+                let left = if sidebar { 31 } else { 1 };
+                let top = if debug { 10 } else { 4 };
+                let context = format!("sidebar={} minimap={} debug={}", sidebar, minimap, debug);
+                assert_eq!(editor.screen_to_content_position(left, top), Some((0, 0)), "{}", context);
+                assert_eq!(editor.screen_to_content_position(left + 5, top + 2), Some((5, 2)), "{}", context);
+                assert_eq!(editor.screen_to_content_position(left - 1, top), None, "{}", context);
+                assert_eq!(editor.screen_to_content_position(left, top - 1), None, "{}", context);
+                assert_eq!(editor.screen_to_content_position(left, 29), None, "{}", context);
+
+                let right = 100 - if minimap { 30 } else { 0 } - 2;
+                assert!(editor.screen_to_content_position(right, top).is_some(), "{}", context);
+                assert_eq!(editor.screen_to_content_position(right + 1, top), None, "{}", context);
+            }
+        }
+    }
+}
+
+#[test]
+fn minimap_clicks_move_the_cursor_and_editor_clicks_do_not() {
+    use crossterm::event::{KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+    let click = |column, row| Event::Mouse(MouseEvent {
+        kind: MouseEventKind::Down(MouseButton::Left),
+        column,
+        row,
+        modifiers: KeyModifiers::NONE,
+    });
+
+    let mut editor = editor_with_panels();
+    editor.execute_action("toggle_debug_menu").unwrap();
+    editor.execute_action("toggle_sidebar").unwrap();
+    editor.goto_location(1, None);
+    draw(&mut editor, 100, 30);
+    editor.handle_event(click(40, 20)).unwrap();
+    assert_eq!(editor.active_tab().cursor().1, 0);
+
+    editor.handle_event(click(85, 20)).unwrap();
+    assert!(editor.active_tab().cursor().1 > 0);
+}
+
+#[test]
+fn toggling_debug_keeps_the_cursor_on_its_character() {
+    let mut editor = Editor::headless();
+    editor.execute_action("enter_insert_mode").unwrap();
+    for line in 0..40 {
+        for c in format!("line {:02} abcdefghij", line).chars() {
+            editor.dispatch_key_event(KeyEvent::from(KeyCode::Char(c))).unwrap();
+        }
+        editor.dispatch_key_event(KeyEvent::from(KeyCode::Enter)).unwrap();
+    }
+    editor.dispatch_key_event(KeyEvent::from(KeyCode::Esc)).unwrap();
+    editor.goto_location(12, Some(9));
+
+    let mut terminal = Terminal::new(TestBackend::new(60, 24)).unwrap();
+    for _ in 0..4 {
+        editor.execute_action("toggle_debug_menu").unwrap();
+        terminal.draw(|f| editor.ui(f)).unwrap();
+        let (x, y) = terminal.get_cursor().unwrap();
+        let tab = editor.active_tab();
+        assert_eq!(editor.screen_to_content_position(x, y), Some(tab.cursor()));
+        assert_eq!(terminal.backend().buffer().get(x, y).symbol, "a");
+        assert_eq!(tab.lines()[tab.cursor().1], "line 11 abcdefghij");
+    }
+}
+
+#[test]
+fn debug_panel_height_is_configurable_and_scrolls() {
+    let mut editor = Editor::headless();
+    editor.execute_command_line("set debug_height=10 debuglevel=trace").unwrap();
+    editor.execute_action("toggle_debug_menu").unwrap();
+    for _ in 0..20 {
+        editor.handle_event(Event::Key(KeyEvent::from(KeyCode::Char('x')))).unwrap();
+        editor.handle_event(Event::Key(KeyEvent::from(KeyCode::Char('u')))).unwrap();
+    }
+    let mut terminal = Terminal::new(TestBackend::new(80, 30)).unwrap();
+    terminal.draw(|f| editor.ui(f)).unwrap();
+    let rows: Vec<String> = screen(&terminal).chars().collect::<Vec<_>>()
+        .chunks(80).map(|row| row.iter().collect()).collect();
+    assert!(rows[3].contains("Debug Output"));
+    assert!(rows[12].starts_with('└'));
+    assert_eq!(rows[4..12].iter().filter(|row| row.contains("Key pressed") || row.contains("Cursor")).count(), 8);
+    assert!(rows[13].starts_with('┌'));
+
+    for _ in 0..5 {
+        editor.execute_action("scroll_debug_up").unwrap();
+    }
+    terminal.draw(|f| editor.ui(f)).unwrap();
+    assert!(screen(&terminal).contains("Debug Output [trace+] (+5)"));
+    for _ in 0..10 {
+        editor.execute_action("scroll_debug_down").unwrap();
+    }
+    terminal.draw(|f| editor.ui(f)).unwrap();
+    assert!(!screen(&terminal).contains("(+"));
+}
+
+#[test]
+fn debug_panel_filters_by_level_and_takes_focus() {
+    let mut editor = Editor::headless();
+    editor.execute_command_line("set debugheight=8").unwrap();
+    editor.handle_event(Event::Key(KeyEvent::from(KeyCode::Esc))).unwrap();
+    for _ in 0..10 {
+        editor.handle_event(Event::Key(KeyEvent::from(KeyCode::Char('l')))).unwrap();
+    }
+    editor.execute_action("no_such_action").unwrap();
+    editor.execute_action("focus_debug").unwrap();
+    assert_eq!(editor.mode(), Mode::Debug);
+
+    let mut terminal = Terminal::new(TestBackend::new(80, 30)).unwrap();
+    terminal.draw(|f| editor.ui(f)).unwrap();
+    let text = screen(&terminal);
+    assert!(text.contains("Debug Output [debug+]"));
+    assert!(text.contains("warn  Unknown action: no_such_action"));
+    assert!(!text.contains("Key pressed"));
+
+    for _ in 0..2 {
+        editor.handle_event(Event::Key(KeyEvent::from(KeyCode::Char('f')))).unwrap();
+    }
+    terminal.draw(|f| editor.ui(f)).unwrap();
+    let text = screen(&terminal);
+    assert!(text.contains("Debug Output [warn+]"));
+    assert!(text.contains("error Failed to save settings") && !text.contains("info "));
+
+    editor.handle_event(Event::Key(KeyEvent::from(KeyCode::Char('f')))).unwrap();
+    editor.handle_event(Event::Key(KeyEvent::from(KeyCode::Char('f')))).unwrap();
+    editor.handle_event(Event::Key(KeyEvent::from(KeyCode::Char('k')))).unwrap();
+    editor.handle_event(Event::Key(KeyEvent::from(KeyCode::Char('k')))).unwrap();
+    terminal.draw(|f| editor.ui(f)).unwrap();
+    assert!(screen(&terminal).contains("Debug Output [trace+] (+2)"));
+    editor.handle_event(Event::Key(KeyEvent::from(KeyCode::Char('j')))).unwrap();
+    terminal.draw(|f| editor.ui(f)).unwrap();
+    assert!(screen(&terminal).contains("Debug Output [trace+] (+1)"));
+
+    editor.handle_event(Event::Key(KeyEvent::from(KeyCode::Char('q')))).unwrap();
+    assert_eq!(editor.mode(), Mode::Normal);
+    assert_eq!(editor.active_tab().cursor(), (0, 0));
+}
+
+#[test]
+fn minimap_keeps_indentation_empty_and_colors_by_token() {
+    let mut editor = Editor::headless();
+    editor.execute_action("enter_insert_mode").unwrap();
+    let lines = ["// a long comment block", "// that keeps going on", "fn main() {", "        let x = 1;", "}"];
+    for line in lines {
+        for c in line.chars() {
+            editor.dispatch_key_event(KeyEvent::from(KeyCode::Char(c))).unwrap();
+        }
+        editor.dispatch_key_event(KeyEvent::from(KeyCode::Enter)).unwrap();
+    }
+    editor.dispatch_key_event(KeyEvent::from(KeyCode::Esc)).unwrap();
+    editor.execute_command_line("setlocal syntax=Rust").unwrap();
+    editor.execute_command_line("set minimapscale=1").unwrap();
+    editor.execute_action("toggle_minimap").unwrap();
+
+    let mut terminal = Terminal::new(TestBackend::new(100, 20)).unwrap();
+    terminal.draw(|f| editor.ui(f)).unwrap();
+    let buffer = terminal.backend().buffer();
+    let symbols: Vec<&str> = buffer.content().iter().map(|cell| cell.symbol.as_str()).collect();
+    let title = symbols.windows(3).position(|cells| cells == ["┌", "M", "i"]).unwrap() as u16 + 1;
+    let (left, top) = (title % 100, title / 100 + 1);
+    let cell = |x: u16, line: u16| buffer.get(left + x, top + line);
+
+    assert_ne!(cell(0, 0).symbol, "\u{2800}");
+    assert_eq!(cell(0, 0).fg, cell(5, 1).fg);
+    assert_ne!(cell(0, 0).fg, cell(0, 2).fg);
+    assert_eq!(cell(0, 3).symbol, "\u{2800}");
+    assert_eq!(cell(3, 3).symbol, "\u{2800}");
+    assert_ne!(cell(4, 3).symbol, "\u{2800}");
+}
+
+#[test]
+fn minimap_focus_scrolls_the_viewport_band_from_the_keyboard() {
+    let mut editor = Editor::headless();
+    let text: String = (1..=200).map(|line| format!("line {:03}\n", line)).collect();
+    editor.execute_action("enter_insert_mode").unwrap();
+    for c in text.trim_end().chars() {
+        let code = if c == '\n' { KeyCode::Enter } else { KeyCode::Char(c) };
+        editor.dispatch_key_event(KeyEvent::from(code)).unwrap();
+    }
+    editor.dispatch_key_event(KeyEvent::from(KeyCode::Esc)).unwrap();
+    editor.goto_location(1, None);
+    let mut terminal = Terminal::new(TestBackend::new(100, 20)).unwrap();
+    let mut press = |editor: &mut Editor, code: KeyCode| {
+        editor.dispatch_key_event(KeyEvent::from(code)).unwrap();
+        terminal.draw(|f| editor.ui(f)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let symbols: Vec<&str> = buffer.content().iter().map(|cell| cell.symbol.as_str()).collect();
+        symbols.windows(3).position(|cells| cells == ["┌", "M", "i"]).map(|corner| buffer.content()[corner].fg)
+    };
+
+    let active = tui::style::Color::Rgb(0x61, 0xaf, 0xef);
+    assert_eq!(press(&mut editor, KeyCode::F(11)), Some(active));
+    assert_eq!(editor.view().first_line, 0);
+    press(&mut editor, KeyCode::Char('j'));
+    let step = editor.view().first_line;
+    assert!(step > 1, "{}", step);
+    press(&mut editor, KeyCode::PageDown);
+    assert!(editor.view().first_line > step * 2);
+    assert_eq!(editor.active_tab().cursor(), (0, 0));
+    assert_ne!(press(&mut editor, KeyCode::Esc), Some(active));
+    assert_eq!(editor.view().first_line, 0);
+    assert_eq!(editor.active_tab().cursor(), (0, 0));
+
+    press(&mut editor, KeyCode::F(11));
+    press(&mut editor, KeyCode::Char('G'));
+    let view = editor.view();
+    assert_eq!(view.lines.last().map(String::as_str), Some("line 200"));
+    assert_ne!(press(&mut editor, KeyCode::Enter), Some(active));
+    let view = editor.view();
+    assert_eq!(view.cursor.1, view.first_line + (view.lines.len() - 1) / 2);
+    press(&mut editor, KeyCode::F(11));
+    press(&mut editor, KeyCode::Char('g'));
+    press(&mut editor, KeyCode::Char('k'));
+    assert_eq!(editor.view().first_line, 0);
+}
+
+#[test]
+fn sidebar_previews_the_selected_file_without_touching_tabs() {
+    let dir = std::env::temp_dir().join(format!("phantom-preview-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("sub")).unwrap();
+    let source: String = (1..=60).map(|line| format!("fn line_{}() {{}}\n", line)).collect();
+    std::fs::write(dir.join("code.rs"), source).unwrap();
+    std::fs::write(dir.join("data.bin"), [0u8, 1, 2, 3]).unwrap();
+    std::fs::write(dir.join("sub/a.txt"), "12345").unwrap();
+    std::fs::write(dir.join("sub/b.txt"), "abc").unwrap();
+    std::fs::write(dir.join("notes.txt"), "notes\n").unwrap();
+
+    let mut editor = Editor::headless();
+    editor.open_file(&dir.join("notes.txt")).unwrap();
+    editor.execute_action("toggle_sidebar").unwrap();
+    let mut terminal = Terminal::new(TestBackend::new(120, 60)).unwrap();
+    let mut show = |editor: &mut Editor| {
+        terminal.draw(|f| editor.ui(f)).unwrap();
+        screen(&terminal)
+    };
+    let rows = show(&mut editor).chars().collect::<Vec<_>>().chunks(120).map(|row| row.iter().collect()).collect::<Vec<String>>();
+    let select = |editor: &mut Editor, name: &str| {
+        for _ in 0..rows.len() {
+            editor.handle_event(Event::Key(KeyEvent::from(KeyCode::Up))).unwrap();
+        }
+        for _ in 1..rows.iter().position(|row| row.contains(name)).unwrap() {
+            editor.handle_event(Event::Key(KeyEvent::from(KeyCode::Down))).unwrap();
+        }
+    };
+
+    select(&mut editor, "code.rs");
+    assert!(!editor.poll_sidebar_preview());
+    assert!(!show(&mut editor).contains("Preview:"));
+    std::thread::sleep(std::time::Duration::from_millis(350));
+    assert!(editor.poll_sidebar_preview());
+    let text = show(&mut editor);
+    assert!(text.contains("Preview: code.rs"));
+    assert!(text.contains("fn line_40() {}") && !text.contains("fn line_41() {}"));
+    assert_eq!(editor.tabs().len(), 1);
+    assert_eq!(editor.active_tab().lines(), ["notes"]);
+
+    select(&mut editor, "data.bin");
+    editor.handle_event(Event::Key(KeyEvent::from(KeyCode::Char('p')))).unwrap();
+    assert!(show(&mut editor).contains("Binary file, 4 bytes"));
+
+    select(&mut editor, "sub");
+    editor.handle_event(Event::Key(KeyEvent::from(KeyCode::Char('p')))).unwrap();
+    let text = show(&mut editor);
+    assert!(text.contains("Preview: sub") && text.contains("2 entries") && text.contains("8 bytes in files"));
+
+    editor.handle_event(Event::Key(KeyEvent::from(KeyCode::Esc))).unwrap();
+    assert!(!show(&mut editor).contains("Preview:"));
+    assert!(!editor.poll_sidebar_preview());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn multi_megabyte_single_lines_scroll_without_highlighting_them() {
+    use std::time::{Duration, Instant};
+
+    let dir = std::env::temp_dir().join(format!("phantom-long-line-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("bundle.min.json");
+    let mut json = String::from("[");
+    let mut index = 0;
+    while json.len() < 5_000_000 {
+        json.push_str(&format!("{{\"id\":{},\"name\":\"item {}\"}},", index, index));
+        index += 1;
+    }
+    json.push_str("{\"end\":true}]\nshort\n");
+    std::fs::write(&path, &json).unwrap();
+
+    let started = Instant::now();
+    let mut editor = Editor::headless();
+    editor.open_file(&path).unwrap();
+    editor.execute_action("toggle_minimap").unwrap();
+    let mut terminal = Terminal::new(TestBackend::new(100, 20)).unwrap();
+    terminal.draw(|f| editor.ui(f)).unwrap();
+    assert!(screen(&terminal).contains("[{\"id\":0,\"name\":\"item 0\"},{\"id\":1"));
+    for key in [KeyCode::End, KeyCode::Home, KeyCode::End] {
+        editor.dispatch_key_event(KeyEvent::from(key)).unwrap();
+        terminal.draw(|f| editor.ui(f)).unwrap();
+    }
+    assert!(screen(&terminal).contains("{\"end\":true}]"));
+    for key in [KeyCode::Left, KeyCode::Left, KeyCode::Right] {
+        editor.dispatch_key_event(KeyEvent::from(key)).unwrap();
+        terminal.draw(|f| editor.ui(f)).unwrap();
+    }
+    assert_eq!(editor.active_tab().cursor(), (json.find('\n').unwrap() - 1, 0));
+    for key in [KeyCode::Down, KeyCode::Home] {
+        editor.dispatch_key_event(KeyEvent::from(key)).unwrap();
+        terminal.draw(|f| editor.ui(f)).unwrap();
+    }
+    assert!(screen(&terminal).contains("short"));
+    assert!(started.elapsed() < Duration::from_secs(5));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+fn minimap_rows(terminal: &Terminal<TestBackend>) -> Vec<String> {
+    let buffer = terminal.backend().buffer();
+    let (width, height) = (buffer.area.width, buffer.area.height);
+    let left = width - 30;
+    let top = (0..height).find(|&y| buffer.get(left, y).symbol == "┌");
+    let bottom = (0..height).find(|&y| buffer.get(left, y).symbol == "└");
+    let (Some(top), Some(bottom)) = (top, bottom) else { return Vec::new() };
+    (top + 1..bottom).map(|y| (left + 1..width - 1).map(|x| buffer.get(x, y).symbol.as_str()).collect()).collect()
+}
+
+fn minimap_editor(dir: &std::path::Path) -> Editor {
+    std::fs::create_dir_all(dir).unwrap();
+    std::fs::write(dir.join("empty.txt"), "").unwrap();
+    let mut editor = Editor::headless();
+    editor.execute_action("enter_insert_mode").unwrap();
+    editor.dispatch_key_event(KeyEvent::from(KeyCode::Char('x'))).unwrap();
+    editor.dispatch_key_event(KeyEvent::from(KeyCode::Esc)).unwrap();
+    editor.execute_action("toggle_minimap").unwrap();
+    editor.open_file(&dir.join("empty.txt")).unwrap();
+    editor
+}
+
+fn draw_minimap(editor: &mut Editor) -> Terminal<TestBackend> {
+    for (width, height) in [(100, 12), (60, 8), (100, 3)] {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal.draw(|f| editor.ui(f)).unwrap();
+    }
+    let mut terminal = Terminal::new(TestBackend::new(100, 20)).unwrap();
+    terminal.draw(|f| editor.ui(f)).unwrap();
+    terminal
+}
+
+fn is_dotted(row: &str) -> bool {
+    row.chars().any(|c| ('\u{2801}'..='\u{28FF}').contains(&c))
+}
+
+#[test]
+fn minimap_renders_a_zero_line_file() {
+    let dir = std::env::temp_dir().join(format!("phantom-minimap-zero-{}", std::process::id()));
+    let mut editor = minimap_editor(&dir);
+    let terminal = draw_minimap(&mut editor);
+    assert!(screen(&terminal).contains("No content"));
+    assert!(!minimap_rows(&terminal).iter().any(|row| is_dotted(row)));
+    editor.execute_action("focus_minimap").unwrap();
+    editor.dispatch_key_event(KeyEvent::from(KeyCode::Down)).unwrap();
+    editor.dispatch_key_event(KeyEvent::from(KeyCode::Enter)).unwrap();
+    assert_eq!(editor.active_tab().cursor(), (0, 0));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn minimap_renders_one_empty_line() {
+    let dir = std::env::temp_dir().join(format!("phantom-minimap-empty-{}", std::process::id()));
+    let mut editor = minimap_editor(&dir);
+    editor.execute_action("enter_insert_mode").unwrap();
+    editor.dispatch_key_event(KeyEvent::from(KeyCode::Char('x'))).unwrap();
+    editor.dispatch_key_event(KeyEvent::from(KeyCode::Esc)).unwrap();
+    for _ in 0..2 {
+        editor.dispatch_key_event(KeyEvent::from(KeyCode::Char('d'))).unwrap();
+    }
+    assert_eq!(editor.active_tab().lines(), [""]);
+    let terminal = draw_minimap(&mut editor);
+    assert!(screen(&terminal).contains("No content"));
+    assert!(!minimap_rows(&terminal).iter().any(|row| is_dotted(row)));
+    assert_eq!(editor.active_tab().cursor(), (0, 0));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn minimap_renders_one_short_line() {
+    let dir = std::env::temp_dir().join(format!("phantom-minimap-short-{}", std::process::id()));
+    let mut editor = minimap_editor(&dir);
+    editor.execute_action("enter_insert_mode").unwrap();
+    for c in "hi".chars() {
+        editor.dispatch_key_event(KeyEvent::from(KeyCode::Char(c))).unwrap();
+    }
+    editor.dispatch_key_event(KeyEvent::from(KeyCode::Esc)).unwrap();
+    let terminal = draw_minimap(&mut editor);
+    assert!(!screen(&terminal).contains("No content"));
+    let rows = minimap_rows(&terminal);
+    assert!(is_dotted(&rows[0]));
+    assert!(!rows[1..].iter().any(|row| is_dotted(row)));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn minimap_renders_exactly_minimap_height_lines() {
+    let dir = std::env::temp_dir().join(format!("phantom-minimap-full-{}", std::process::id()));
+    let mut editor = minimap_editor(&dir);
+    editor.execute_action("enter_insert_mode").unwrap();
+    editor.dispatch_key_event(KeyEvent::from(KeyCode::Char('x'))).unwrap();
+    let height = minimap_rows(&draw_minimap(&mut editor)).len();
+    assert!(height > 0);
+    editor.dispatch_key_event(KeyEvent::from(KeyCode::Backspace)).unwrap();
+    for line in 0..height {
+        if line > 0 {
+            editor.dispatch_key_event(KeyEvent::from(KeyCode::Enter)).unwrap();
+        }
+        for c in format!("line {}", line).chars() {
+            editor.dispatch_key_event(KeyEvent::from(KeyCode::Char(c))).unwrap();
+        }
+    }
+    editor.dispatch_key_event(KeyEvent::from(KeyCode::Esc)).unwrap();
+    assert_eq!(editor.active_tab().lines().len(), height);
+    let rows = minimap_rows(&draw_minimap(&mut editor));
+    assert_eq!(rows.len(), height);
+    assert!(rows.iter().all(|row| is_dotted(row)));
+    std::fs::remove_dir_all(&dir).unwrap();
+}